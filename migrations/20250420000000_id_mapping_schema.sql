@@ -0,0 +1,15 @@
+-- Create the storage schema if it doesn't exist
+CREATE SCHEMA IF NOT EXISTS storage;
+
+-- Path <-> ID mappings for files and folders (replaces folder_ids.json / file_ids.json)
+CREATE TABLE IF NOT EXISTS storage.id_mappings (
+    id VARCHAR(36) PRIMARY KEY,
+    namespace VARCHAR(32) NOT NULL,
+    path TEXT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(namespace, path)
+);
+
+-- Prefix index so renames and moves can rewrite whole subtrees with LIKE 'prefix/%'
+CREATE INDEX IF NOT EXISTS idx_id_mappings_path_prefix
+    ON storage.id_mappings(path text_pattern_ops);
@@ -0,0 +1,12 @@
+-- Time zone of each event's local times (TZID of its DTSTART, or the owner's
+-- default zone for floating times); NULL for UTC and all-day events
+ALTER TABLE caldav.calendar_events ADD COLUMN IF NOT EXISTS timezone VARCHAR(255);
+
+-- Per-user calendar settings
+CREATE TABLE IF NOT EXISTS caldav.user_settings (
+    user_id VARCHAR(36) PRIMARY KEY REFERENCES auth.users(id) ON DELETE CASCADE,
+    default_timezone VARCHAR(255) NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+COMMENT ON TABLE caldav.user_settings IS 'Stores per-user calendar settings such as the default time zone';
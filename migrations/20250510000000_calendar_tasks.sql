@@ -0,0 +1,29 @@
+-- Tasks (VTODO) kept in calendars next to their events. The iCalendar data is
+-- the source of truth; the other columns are copies for querying
+CREATE TABLE IF NOT EXISTS caldav.calendar_tasks (
+    id UUID PRIMARY KEY,
+    calendar_id UUID NOT NULL REFERENCES caldav.calendars(id) ON DELETE CASCADE,
+    summary TEXT NOT NULL DEFAULT '',
+    description TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'NEEDS-ACTION', -- 'NEEDS-ACTION', 'IN-PROCESS', 'COMPLETED', 'CANCELLED'
+    priority SMALLINT, -- 1 (highest) to 9 (lowest)
+    start_time TIMESTAMP WITH TIME ZONE,
+    due_time TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    percent_complete SMALLINT,
+    parent_uid VARCHAR(255), -- UID of the parent task (RELATED-TO)
+    rrule TEXT, -- Recurrence rule
+    timezone VARCHAR(255),
+    ical_uid VARCHAR(255) NOT NULL, -- UID from iCalendar format
+    ical_data TEXT NOT NULL, -- Complete iCalendar data
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(calendar_id, ical_uid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_calendar_task_calendar ON caldav.calendar_tasks(calendar_id);
+CREATE INDEX IF NOT EXISTS idx_calendar_task_due ON caldav.calendar_tasks(calendar_id, due_time);
+CREATE INDEX IF NOT EXISTS idx_calendar_task_status ON caldav.calendar_tasks(calendar_id, status);
+CREATE INDEX IF NOT EXISTS idx_calendar_task_parent ON caldav.calendar_tasks(calendar_id, parent_uid);
+
+COMMENT ON TABLE caldav.calendar_tasks IS 'Stores calendar tasks (VTODO) with iCalendar data';
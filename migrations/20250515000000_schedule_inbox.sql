@@ -0,0 +1,14 @@
+-- Scheduling messages (iTIP) delivered to the inbox of each calendar user
+-- (RFC 6638, section 2.2)
+CREATE TABLE IF NOT EXISTS caldav.schedule_inbox (
+    id UUID PRIMARY KEY,
+    user_id VARCHAR(36) NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    method VARCHAR(16) NOT NULL, -- 'REQUEST', 'REPLY', 'CANCEL'
+    ical_uid VARCHAR(255) NOT NULL, -- UID of the scheduled object
+    ical_data TEXT NOT NULL, -- Complete iTIP message
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_schedule_inbox_user ON caldav.schedule_inbox(user_id, created_at);
+
+COMMENT ON TABLE caldav.schedule_inbox IS 'Stores the scheduling messages delivered to each user';
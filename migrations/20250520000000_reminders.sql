@@ -0,0 +1,40 @@
+-- Triggers of the alarms (VALARM) of events and tasks, pending or sent. The
+-- rows are derived from the iCalendar data and indexed again when it
+-- changes; a trigger is identified by its object, alarm and time so that it
+-- is sent only once
+CREATE TABLE IF NOT EXISTS caldav.reminders (
+    id UUID PRIMARY KEY,
+    user_id VARCHAR(36) NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    calendar_id UUID NOT NULL REFERENCES caldav.calendars(id) ON DELETE CASCADE,
+    object_id UUID NOT NULL, -- Event or task holding the alarm
+    component VARCHAR(10) NOT NULL, -- 'VEVENT', 'VTODO'
+    alarm_key TEXT NOT NULL, -- UID of the alarm, or its component and position
+    action VARCHAR(16) NOT NULL, -- 'DISPLAY', 'AUDIO', 'EMAIL'
+    trigger_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    occurrence_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    summary TEXT NOT NULL DEFAULT '',
+    description TEXT,
+    sent_at TIMESTAMP WITH TIME ZONE, -- NULL while pending
+    UNIQUE(object_id, alarm_key, trigger_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminders_due ON caldav.reminders(trigger_at) WHERE sent_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_reminders_object ON caldav.reminders(object_id);
+
+-- In-app notifications of the reminders sent, until the user acknowledges
+-- or snoozes them
+CREATE TABLE IF NOT EXISTS caldav.reminder_notifications (
+    id UUID PRIMARY KEY,
+    user_id VARCHAR(36) NOT NULL REFERENCES auth.users(id) ON DELETE CASCADE,
+    reminder_id UUID NOT NULL REFERENCES caldav.reminders(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminder_notifications_user ON caldav.reminder_notifications(user_id, created_at);
+
+-- Objects changed since the previous sync of the index
+CREATE INDEX IF NOT EXISTS idx_calendar_event_updated ON caldav.calendar_events(updated_at);
+CREATE INDEX IF NOT EXISTS idx_calendar_task_updated ON caldav.calendar_tasks(updated_at);
+
+COMMENT ON TABLE caldav.reminders IS 'Stores the upcoming and sent triggers of calendar alarms';
+COMMENT ON TABLE caldav.reminder_notifications IS 'Stores the in-app notifications of sent reminders';
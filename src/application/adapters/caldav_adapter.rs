@@ -1,8 +1,9 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use quick_xml::{
     events::{BytesEnd, BytesStart, BytesText, Event},
-    Reader, Writer,
+    NsReader, Reader, Writer,
 };
+use sha2::{Digest, Sha256};
 /**
  * CalDAV Adapter Module
  *
@@ -13,9 +14,39 @@ use std::io::{BufReader, Read, Write};
 use uuid::Uuid;
 
 use crate::application::adapters::webdav_adapter::{
-    PropFindRequest, PropFindType, QualifiedName, Result, WebDavAdapter, WebDavError,
+    PropFindRequest, PropFindType, QualifiedName, Result, WebDavAdapter, WebDavError, CALDAV_NS,
 };
-use crate::application::dtos::calendar_dto::{CalendarDto, CalendarEventDto};
+use crate::application::dtos::calendar_dto::{CalendarDto, CalendarObjectDto, ScheduleResponseDto};
+use crate::domain::entities::icalendar::{parse_date_time, ICalComponent, ICalProperty};
+use crate::domain::entities::recurrence;
+use crate::domain::entities::scheduling::ScheduleMessage;
+use crate::domain::entities::timezone::{CalendarTimeZone, TimeZones};
+use crate::domain::entities::webdav_acl::PrivilegeSet;
+
+/// Namespace of the CalendarServer extensions, such as `getctag`
+pub const CALENDARSERVER_NS: &str = "http://calendarserver.org/ns/";
+
+/// Namespace of the Apple iCal extensions, such as `calendar-color`
+pub const APPLE_ICAL_NS: &str = "http://apple.com/ns/ical/";
+
+/// Content type of calendar object resources
+pub const CALENDAR_CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+
+/// How the `calendar-data` of recurring events is returned
+/// (RFC 4791, sections 9.6.5 and 9.6.6)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecurrenceExpansion {
+    /// One component per instance in the range, without recurrence rules
+    Expand {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+    /// The master component and only the overrides in the range
+    LimitRecurrenceSet {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+}
 
 /// CalDAV report type
 #[derive(Debug, PartialEq)]
@@ -23,165 +54,229 @@ pub enum CalDavReportType {
     /// Calendar-query report
     CalendarQuery {
         time_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+        /// Component asked for by the `comp-filter` inside VCALENDAR, if any
+        component: Option<String>,
+        /// Conditions on the properties of that component
+        prop_filters: Vec<PropFilter>,
         props: Vec<QualifiedName>,
+        expansion: Option<RecurrenceExpansion>,
     },
     /// Calendar-multiget report
     CalendarMultiget {
         hrefs: Vec<String>,
         props: Vec<QualifiedName>,
+        expansion: Option<RecurrenceExpansion>,
     },
     /// Sync-collection report
     SyncCollection {
         sync_token: String,
         props: Vec<QualifiedName>,
     },
+    /// Free-busy-query report (RFC 4791, section 7.10)
+    FreeBusyQuery {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+}
+
+/// A `prop-filter` of a calendar-query (RFC 4791, section 9.7.2). Text is
+/// matched as a substring ignoring case; parameter filters are not supported.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropFilter {
+    /// Property name, in upper case
+    pub name: String,
+    /// Matches components without the property
+    pub is_not_defined: bool,
+    /// Text the property value must contain
+    pub text_match: Option<String>,
+    /// Matches values that do not contain the text instead
+    pub negate: bool,
+}
+
+impl PropFilter {
+    /// Indicates whether a component meets the filter
+    pub fn matches(&self, component: &ICalComponent) -> bool {
+        let mut values = component
+            .properties
+            .iter()
+            .filter(|property| property.name == self.name)
+            .map(ICalProperty::text)
+            .peekable();
+        if self.is_not_defined {
+            return values.peek().is_none();
+        }
+        match &self.text_match {
+            Some(text) => {
+                let text = text.to_lowercase();
+                values.any(|value| value.to_lowercase().contains(&text) != self.negate)
+            }
+            None => values.peek().is_some(),
+        }
+    }
+}
+
+/// Properties of a calendar created with MKCALENDAR
+#[derive(Debug, PartialEq)]
+pub struct MkCalendarRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    /// Components the calendar is restricted to; empty for the default set
+    pub components: Vec<String>,
+}
+
+/// A resource listed in a CalDAV PROPFIND response
+#[derive(Debug)]
+pub enum CalDavResource<'a> {
+    /// Collection holding the calendars of a user, or the CalDAV root,
+    /// which points to the home of the current user
+    Home {
+        href: String,
+        owner: String,
+        home: String,
+        /// Default time zone of the user, for floating times
+        timezone: Option<CalendarTimeZone>,
+    },
+    /// Calendar collection
+    Calendar {
+        calendar: &'a CalendarDto,
+        href: String,
+        owner: String,
+        /// Changes whenever an event of the calendar does
+        ctag: String,
+        privileges: PrivilegeSet,
+    },
+    /// Calendar object resource (`.ics`): an event or a task
+    Object {
+        object: &'a CalendarObjectDto,
+        href: String,
+        /// Asked for in the `calendar-data` of a REPORT
+        expansion: Option<RecurrenceExpansion>,
+    },
+    /// Scheduling inbox or outbox of a user (RFC 6638, section 2)
+    ScheduleBox {
+        href: String,
+        owner: String,
+        /// Whether it is the inbox rather than the outbox
+        inbox: bool,
+    },
+    /// Scheduling message delivered to an inbox
+    Message {
+        message: &'a ScheduleMessage,
+        href: String,
+    },
+}
+
+/// Element names found while reading a REPORT body
+#[derive(Default)]
+struct ReportState {
+    kind: Option<String>,
+    prop_depth: Option<usize>,
+    props: Vec<QualifiedName>,
+    hrefs: Vec<String>,
+    sync_token: String,
+    component: Option<String>,
+    prop_filters: Vec<PropFilter>,
+    prop_filter_depth: Option<usize>,
+    in_text_match: bool,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    has_time_range: bool,
+    expansion: Option<RecurrenceExpansion>,
+    in_href: bool,
+    in_sync_token: bool,
 }
 
 /// CalDAV adapter for converting between XML and domain objects
 pub struct CalDavAdapter;
 
 impl CalDavAdapter {
-    /// Parse a REPORT XML request for CalDAV
-    pub fn parse_report<R: Read>(reader: R) -> Result<CalDavReportType> {
-        let mut xml_reader = Reader::from_reader(BufReader::new(reader));
+    /// Live properties of the calendar home collection
+    const HOME_PROPERTIES: [(&'static str, &'static str); 7] = [
+        ("DAV:", "resourcetype"),
+        ("DAV:", "displayname"),
+        ("DAV:", "owner"),
+        ("DAV:", "current-user-principal"),
+        ("DAV:", "current-user-privilege-set"),
+        (CALDAV_NS, "calendar-home-set"),
+        (CALDAV_NS, "calendar-timezone"),
+    ];
+
+    /// Live properties of calendar collections
+    const CALENDAR_PROPERTIES: [(&'static str, &'static str); 11] = [
+        ("DAV:", "resourcetype"),
+        ("DAV:", "displayname"),
+        ("DAV:", "owner"),
+        ("DAV:", "current-user-principal"),
+        ("DAV:", "current-user-privilege-set"),
+        ("DAV:", "supported-report-set"),
+        (CALDAV_NS, "calendar-description"),
+        (CALDAV_NS, "supported-calendar-component-set"),
+        (CALDAV_NS, "supported-calendar-data"),
+        (CALENDARSERVER_NS, "getctag"),
+        (APPLE_ICAL_NS, "calendar-color"),
+    ];
+
+    /// Live properties of the scheduling inbox and outbox
+    const SCHEDULE_BOX_PROPERTIES: [(&'static str, &'static str); 5] = [
+        ("DAV:", "resourcetype"),
+        ("DAV:", "displayname"),
+        ("DAV:", "owner"),
+        ("DAV:", "current-user-principal"),
+        ("DAV:", "current-user-privilege-set"),
+    ];
+
+    /// Live properties of calendar object resources and scheduling messages
+    const OBJECT_PROPERTIES: [(&'static str, &'static str); 6] = [
+        ("DAV:", "resourcetype"),
+        ("DAV:", "getetag"),
+        ("DAV:", "getcontenttype"),
+        ("DAV:", "getcontentlength"),
+        ("DAV:", "getlastmodified"),
+        (CALDAV_NS, "calendar-data"),
+    ];
+
+    /// Parse a REPORT XML request for CalDAV; `None` if the report is not supported
+    pub fn parse_report<R: Read>(reader: R) -> Result<Option<CalDavReportType>> {
+        let mut xml_reader = NsReader::from_reader(BufReader::new(reader));
         xml_reader.config_mut().trim_text(true);
 
         let mut buffer = Vec::new();
-        let mut in_calendar_query = false;
-        let mut in_calendar_multiget = false;
-        let mut in_sync_collection = false;
-        let mut in_prop = false;
-        let mut in_filter = false;
-        let mut in_time_range = false;
-        let mut start_time: Option<DateTime<Utc>> = None;
-        let mut end_time: Option<DateTime<Utc>> = None;
-        let mut props = Vec::new();
-        let mut hrefs = Vec::new();
-        let mut sync_token = String::new();
+        let mut depth = 0;
+        let mut state = ReportState::default();
 
         loop {
             match xml_reader.read_event_into(&mut buffer) {
                 Ok(Event::Start(ref e)) => {
-                    let name = e.name();
-                    let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("");
-
-                    match name_str {
-                        s if s == "calendar-query" || s.ends_with(":calendar-query") => {
-                            in_calendar_query = true
-                        }
-                        s if s == "calendar-multiget" || s.ends_with(":calendar-multiget") => {
-                            in_calendar_multiget = true
-                        }
-                        s if s == "sync-collection" || s.ends_with(":sync-collection") => {
-                            in_sync_collection = true
-                        }
-                        s if s == "prop" || s.ends_with(":prop") => in_prop = true,
-                        s if s == "filter" || s.ends_with(":filter") => in_filter = true,
-                        s if s == "time-range" || s.ends_with(":time-range") => {
-                            in_time_range = true;
-
-                            // Parse time-range attributes
-                            for attr in e.attributes() {
-                                if let Ok(attr) = attr {
-                                    let attr_name =
-                                        std::str::from_utf8(attr.key.as_ref()).unwrap_or("");
-                                    let attr_value = attr.unescape_value().unwrap_or_default();
-
-                                    if attr_name == "start" {
-                                        // Parse ISO date format with Z for UTC
-                                        start_time = DateTime::parse_from_rfc3339(&attr_value)
-                                            .ok()
-                                            .map(|dt| dt.with_timezone(&Utc));
-                                    } else if attr_name == "end" {
-                                        end_time = DateTime::parse_from_rfc3339(&attr_value)
-                                            .ok()
-                                            .map(|dt| dt.with_timezone(&Utc));
-                                    }
-                                }
-                            }
-                        }
-                        s if s == "sync-token" || s.ends_with(":sync-token") => {
-                            // We'll capture the text in the Text event
-                        }
-                        s if s == "href" || s.ends_with(":href") => {
-                            // We'll capture the text in the Text event
-                        }
-                        _ if in_prop => {
-                            // Add property to request
-                            let namespace = WebDavAdapter::extract_namespace(name_str);
-                            let prop_name = WebDavAdapter::extract_local_name(name_str);
-
-                            props.push(QualifiedName::new(namespace, prop_name));
-                        }
-                        _ => { /* Ignore other elements */ }
-                    }
+                    depth += 1;
+                    Self::read_report_element(&xml_reader, e, depth, false, &mut state);
+                }
+                Ok(Event::Empty(ref e)) => {
+                    Self::read_report_element(&xml_reader, e, depth + 1, true, &mut state);
                 }
                 Ok(Event::Text(e)) => {
                     let text = e.unescape().unwrap_or_default();
-
-                    // Check if we're in sync-token element
-                    if in_sync_collection && !in_prop && !in_filter {
-                        sync_token = text.to_string();
-                    }
-
-                    // Check if we're in href element
-                    if (in_calendar_multiget || in_sync_collection) && !in_prop && !in_filter {
-                        hrefs.push(text.to_string());
-                    }
-                }
-                Ok(Event::End(ref e)) => {
-                    let name = e.name();
-                    let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("");
-
-                    match name_str {
-                        s if s == "calendar-query" || s.ends_with(":calendar-query") => {
-                            in_calendar_query = false
-                        }
-                        s if s == "calendar-multiget" || s.ends_with(":calendar-multiget") => {
-                            in_calendar_multiget = false
-                        }
-                        s if s == "sync-collection" || s.ends_with(":sync-collection") => {
-                            in_sync_collection = false
-                        }
-                        s if s == "prop" || s.ends_with(":prop") => in_prop = false,
-                        s if s == "filter" || s.ends_with(":filter") => in_filter = false,
-                        s if s == "time-range" || s.ends_with(":time-range") => {
-                            in_time_range = false
+                    if state.in_href {
+                        state.hrefs.push(text.trim().to_string());
+                    } else if state.in_text_match {
+                        if let Some(filter) = state.prop_filters.last_mut() {
+                            filter.text_match = Some(text.trim().to_string());
                         }
-                        _ => (),
+                    } else if state.in_sync_token {
+                        state.sync_token = text.trim().to_string();
                     }
                 }
-                Ok(Event::Empty(ref e)) => {
-                    let name = e.name();
-                    let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("");
-
-                    if in_prop {
-                        // Add empty property element to request
-                        let namespace = WebDavAdapter::extract_namespace(name_str);
-                        let prop_name = WebDavAdapter::extract_local_name(name_str);
-
-                        props.push(QualifiedName::new(namespace, prop_name));
-                    } else if name_str == "time-range" || name_str.ends_with(":time-range") {
-                        // Parse time-range attributes
-                        for attr in e.attributes() {
-                            if let Ok(attr) = attr {
-                                let attr_name =
-                                    std::str::from_utf8(attr.key.as_ref()).unwrap_or("");
-                                let attr_value = attr.unescape_value().unwrap_or_default();
-
-                                if attr_name == "start" {
-                                    // Parse ISO date format with Z for UTC
-                                    start_time = DateTime::parse_from_rfc3339(&attr_value)
-                                        .ok()
-                                        .map(|dt| dt.with_timezone(&Utc));
-                                } else if attr_name == "end" {
-                                    end_time = DateTime::parse_from_rfc3339(&attr_value)
-                                        .ok()
-                                        .map(|dt| dt.with_timezone(&Utc));
-                                }
-                            }
-                        }
+                Ok(Event::End(_)) => {
+                    if state.prop_depth == Some(depth) {
+                        state.prop_depth = None;
+                    }
+                    if state.prop_filter_depth == Some(depth) {
+                        state.prop_filter_depth = None;
                     }
+                    state.in_href = false;
+                    state.in_text_match = false;
+                    state.in_sync_token = false;
+                    depth = depth.saturating_sub(1);
                 }
                 Ok(Event::Eof) => break,
                 Err(e) => return Err(WebDavError::XmlError(e)),
@@ -191,625 +286,721 @@ impl CalDavAdapter {
             buffer.clear();
         }
 
-        // Create the appropriate report type based on what we parsed
-        let report_type = if in_calendar_query {
-            // If both start and end time are present, create a time range
-            let time_range = if let (Some(start), Some(end)) = (start_time, end_time) {
-                Some((start, end))
-            } else {
-                None
-            };
-
-            CalDavReportType::CalendarQuery { time_range, props }
-        } else if in_calendar_multiget {
-            CalDavReportType::CalendarMultiget { hrefs, props }
-        } else if in_sync_collection {
-            CalDavReportType::SyncCollection { sync_token, props }
-        } else {
-            // Default to empty calendar query
-            CalDavReportType::CalendarQuery {
-                time_range: None,
+        let props = state.props;
+        // An open-ended range runs to the beginning or the end of time
+        let time_range = state.has_time_range.then(|| {
+            (
+                state.start.unwrap_or(DateTime::<Utc>::UNIX_EPOCH),
+                state.end.unwrap_or_else(|| {
+                    NaiveDate::from_ymd_opt(9999, 12, 31)
+                        .and_then(|date| date.and_hms_opt(0, 0, 0))
+                        .map(|date| date.and_utc())
+                        .unwrap_or_else(Utc::now)
+                }),
+            )
+        });
+        Ok(match state.kind.as_deref() {
+            Some("calendar-query") => Some(CalDavReportType::CalendarQuery {
+                time_range,
+                component: state.component,
+                prop_filters: state.prop_filters,
                 props,
+                expansion: state.expansion,
+            }),
+            Some("calendar-multiget") => Some(CalDavReportType::CalendarMultiget {
+                hrefs: state.hrefs,
+                props,
+                expansion: state.expansion,
+            }),
+            Some("sync-collection") => Some(CalDavReportType::SyncCollection {
+                sync_token: state.sync_token,
+                props,
+            }),
+            // The time range is mandatory in a free-busy-query
+            Some("free-busy-query") => {
+                time_range.map(|(start, end)| CalDavReportType::FreeBusyQuery { start, end })
             }
-        };
-
-        Ok(report_type)
+            _ => None,
+        })
     }
 
-    /// Generate a PROPFIND response for calendars
-    pub fn generate_calendars_propfind_response<W: Write>(
-        writer: W,
-        calendars: &[CalendarDto],
-        request: &PropFindRequest,
-        base_href: &str,
-    ) -> Result<()> {
-        let mut xml_writer = Writer::new(writer);
-
-        // Start multistatus response
-        xml_writer.write_event(Event::Start(
-            BytesStart::new("D:multistatus").with_attributes([
-                ("xmlns:D", "DAV:"),
-                ("xmlns:C", "urn:ietf:params:xml:ns:caldav"),
-                ("xmlns:CS", "http://calendarserver.org/ns/"),
-            ]),
-        ))?;
-
-        // Add responses for calendars
-        for calendar in calendars {
-            Self::write_calendar_response(
-                &mut xml_writer,
-                calendar,
-                request,
-                &format!("{}{}/", base_href, calendar.id),
-            )?;
+    /// Handle an element of a REPORT body at the given depth (1 for the root)
+    fn read_report_element<R>(
+        xml_reader: &NsReader<R>,
+        e: &BytesStart,
+        depth: usize,
+        empty: bool,
+        state: &mut ReportState,
+    ) {
+        let name = WebDavAdapter::resolve_name(xml_reader, e.name());
+        if depth == 1 {
+            state.kind = Some(name.name);
+            return;
         }
-
-        // End multistatus
-        xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
-
-        Ok(())
-    }
-
-    /// Write calendar properties as a response
-    fn write_calendar_response<W: Write>(
-        xml_writer: &mut Writer<W>,
-        calendar: &CalendarDto,
-        request: &PropFindRequest,
-        href: &str,
-    ) -> Result<()> {
-        // Start response element
-        xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
-
-        // Write href
-        xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(href)))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
-
-        // Write propstat
-        xml_writer.write_event(Event::Start(BytesStart::new("D:propstat")))?;
-
-        // Start prop
-        xml_writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
-
-        // Write properties based on request type
-        match &request.prop_find_type {
-            PropFindType::AllProp => {
-                // Write all standard properties for a calendar
-                Self::write_calendar_standard_props(xml_writer, calendar)?;
+        if let Some(prop_depth) = state.prop_depth {
+            // Only the children of prop are properties; calendar-data may have its own
+            if depth == prop_depth + 1 {
+                state.props.push(name);
+                return;
             }
-            PropFindType::PropName => {
-                // Write only property names (empty elements)
-                Self::write_calendar_prop_names(xml_writer)?;
+            let start = Self::attribute(e, "start").and_then(|v| parse_date_time(&v));
+            let end = Self::attribute(e, "end").and_then(|v| parse_date_time(&v));
+            if let (Some(start), Some(end)) = (start, end) {
+                match name.name.as_str() {
+                    "expand" => state.expansion = Some(RecurrenceExpansion::Expand { start, end }),
+                    "limit-recurrence-set" => {
+                        state.expansion =
+                            Some(RecurrenceExpansion::LimitRecurrenceSet { start, end })
+                    }
+                    _ => (),
+                }
             }
-            PropFindType::Prop(props) => {
-                // Write requested properties
-                Self::write_calendar_requested_props(xml_writer, calendar, props)?;
+            return;
+        }
+        if let Some(filter_depth) = state.prop_filter_depth {
+            // Only the direct children of a prop-filter apply to it; time
+            // ranges on properties and param-filters are ignored
+            if let Some(filter) = state
+                .prop_filters
+                .last_mut()
+                .filter(|_| depth == filter_depth + 1)
+            {
+                match name.name.as_str() {
+                    "is-not-defined" => filter.is_not_defined = true,
+                    "text-match" => {
+                        filter.text_match = Some(String::new());
+                        filter.negate = Self::attribute(e, "negate-condition")
+                            .is_some_and(|negate| negate.eq_ignore_ascii_case("yes"));
+                        state.in_text_match = !empty;
+                    }
+                    _ => (),
+                }
             }
+            return;
         }
 
-        // End prop
-        xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
+        match name.name.as_str() {
+            "prop" if depth == 2 && !empty => state.prop_depth = Some(depth),
+            "href" => state.in_href = !empty,
+            "sync-token" => state.in_sync_token = !empty,
+            "comp-filter" => {
+                if let Some(component) = Self::attribute(e, "name") {
+                    if !component.eq_ignore_ascii_case("VCALENDAR") && state.component.is_none() {
+                        state.component = Some(component.to_ascii_uppercase());
+                    }
+                }
+            }
+            "prop-filter" => {
+                if let Some(property) = Self::attribute(e, "name") {
+                    state.prop_filters.push(PropFilter {
+                        name: property.to_ascii_uppercase(),
+                        ..Default::default()
+                    });
+                    if !empty {
+                        state.prop_filter_depth = Some(depth);
+                    }
+                }
+            }
+            "time-range" => {
+                state.has_time_range = true;
+                state.start = Self::attribute(e, "start").and_then(|v| parse_date_time(&v));
+                state.end = Self::attribute(e, "end").and_then(|v| parse_date_time(&v));
+            }
+            _ => (),
+        }
+    }
 
-        // Write status
-        xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
-        xml_writer.write_event(Event::Text(BytesText::new("HTTP/1.1 200 OK")))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
+    /// Value of an attribute of an element, by local name
+    fn attribute(e: &BytesStart, name: &str) -> Option<String> {
+        e.attributes()
+            .flatten()
+            .find(|attr| attr.key.local_name().as_ref() == name.as_bytes())
+            .and_then(|attr| attr.unescape_value().ok())
+            .map(|value| value.into_owned())
+    }
 
-        // End propstat
-        xml_writer.write_event(Event::End(BytesEnd::new("D:propstat")))?;
+    /// ETag of a calendar object, derived from its iCalendar data
+    pub fn object_etag(object: &CalendarObjectDto) -> String {
+        Self::data_etag(object.ical_data())
+    }
 
-        // End response
-        xml_writer.write_event(Event::End(BytesEnd::new("D:response")))?;
+    /// ETag of a scheduling message, derived from its iCalendar data
+    pub fn message_etag(message: &ScheduleMessage) -> String {
+        Self::data_etag(&message.ical_data)
+    }
 
-        Ok(())
+    fn data_etag(ical_data: &str) -> String {
+        format!(
+            "\"{}\"",
+            hex::encode(&Sha256::digest(ical_data.as_bytes())[..16])
+        )
     }
 
-    /// Write standard calendar properties
-    fn write_calendar_standard_props<W: Write>(
-        xml_writer: &mut Writer<W>,
-        calendar: &CalendarDto,
-    ) -> Result<()> {
-        // Common WebDAV properties
-
-        // Resource type (collection + calendar)
-        xml_writer.write_event(Event::Start(BytesStart::new("D:resourcetype")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("D:collection")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar")))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:resourcetype")))?;
-
-        // Display name
-        xml_writer.write_event(Event::Start(BytesStart::new("D:displayname")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(&calendar.name)))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:displayname")))?;
-
-        // Last modified
-        xml_writer.write_event(Event::Start(BytesStart::new("D:getlastmodified")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(
-            &calendar.updated_at.to_rfc2822(),
-        )))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:getlastmodified")))?;
-
-        // ETag
-        xml_writer.write_event(Event::Start(BytesStart::new("D:getetag")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(&format!("\"{}\"", calendar.id))))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:getetag")))?;
-
-        // Content type for calendar collection
-        xml_writer.write_event(Event::Start(BytesStart::new("D:getcontenttype")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(
-            "text/calendar; component=VCALENDAR",
-        )))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:getcontenttype")))?;
-
-        // CalDAV specific properties
-
-        // Supported calendar component set
-        xml_writer.write_event(Event::Start(BytesStart::new(
-            "C:supported-calendar-component-set",
-        )))?;
-        xml_writer.write_event(Event::Empty(
-            BytesStart::new("C:comp").with_attributes([("name", "VEVENT")]),
-        ))?;
-        xml_writer.write_event(Event::End(BytesEnd::new(
-            "C:supported-calendar-component-set",
-        )))?;
-
-        // Calendar timezone (empty for UTC)
-        xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar-timezone")))?;
-
-        // Calendar color
-        if let Some(color) = &calendar.color {
-            xml_writer.write_event(Event::Start(BytesStart::new("CS:calendar-color")))?;
-            xml_writer.write_event(Event::Text(BytesText::new(color)))?;
-            xml_writer.write_event(Event::End(BytesEnd::new("CS:calendar-color")))?;
+    /// CTag of a calendar: changes whenever one of its events or tasks is
+    /// added, changed or removed, or the calendar itself is modified
+    pub fn calendar_ctag(calendar: &CalendarDto, objects: &[CalendarObjectDto]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(calendar.updated_at.timestamp_micros().to_be_bytes());
+        let mut etags: Vec<(&str, String)> = objects
+            .iter()
+            .map(|object| (object.id(), Self::object_etag(object)))
+            .collect();
+        etags.sort();
+        for (id, etag) in etags {
+            hasher.update(id.as_bytes());
+            hasher.update(etag.as_bytes());
         }
+        hex::encode(&hasher.finalize()[..16])
+    }
 
-        // Support calendar-access (RFC4791)
-        xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar-access")))?;
-
-        // Current user privilege set
-        xml_writer.write_event(Event::Start(BytesStart::new(
-            "D:current-user-privilege-set",
-        )))?;
-        xml_writer.write_event(Event::Start(BytesStart::new("D:privilege")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("D:read")))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:privilege")))?;
-
-        // Only add write privilege if user owns the calendar or has write access
-        if calendar.owner_id == "current_user_id" {
-            // This should be replaced with actual user check
-            xml_writer.write_event(Event::Start(BytesStart::new("D:privilege")))?;
-            xml_writer.write_event(Event::Empty(BytesStart::new("D:write")))?;
-            xml_writer.write_event(Event::End(BytesEnd::new("D:privilege")))?;
+    /// Live properties of a resource
+    fn live_properties(resource: &CalDavResource) -> &'static [(&'static str, &'static str)] {
+        match resource {
+            CalDavResource::Home { .. } => &Self::HOME_PROPERTIES,
+            CalDavResource::Calendar { .. } => &Self::CALENDAR_PROPERTIES,
+            CalDavResource::ScheduleBox { .. } => &Self::SCHEDULE_BOX_PROPERTIES,
+            CalDavResource::Object { .. } | CalDavResource::Message { .. } => {
+                &Self::OBJECT_PROPERTIES
+            }
         }
+    }
 
-        xml_writer.write_event(Event::End(BytesEnd::new("D:current-user-privilege-set")))?;
-
-        // Calendar description if present
-        if let Some(desc) = &calendar.description {
-            xml_writer.write_event(Event::Start(BytesStart::new("C:calendar-description")))?;
-            xml_writer.write_event(Event::Text(BytesText::new(desc)))?;
-            xml_writer.write_event(Event::End(BytesEnd::new("C:calendar-description")))?;
+    /// Indicates whether a resource has a value for a property
+    fn has_property(resource: &CalDavResource, prop: &QualifiedName) -> bool {
+        let live = Self::live_properties(resource)
+            .iter()
+            .any(|(namespace, name)| prop.namespace == *namespace && prop.name == *name);
+        match resource {
+            CalDavResource::Calendar { calendar, .. } => match prop.name.as_str() {
+                "calendar-description" if live => calendar.description.is_some(),
+                "calendar-color" if live => calendar.color.is_some(),
+                _ => live || calendar.custom_properties.contains_key(&prop.to_string()),
+            },
+            CalDavResource::Home { timezone, .. } => match prop.name.as_str() {
+                "calendar-timezone" if live => timezone.is_some(),
+                _ => live,
+            },
+            _ => live,
         }
+    }
 
-        // Custom properties
-        for (name, value) in &calendar.custom_properties {
-            // Skip properties that start with _ - they're internal
-            if !name.starts_with('_') {
-                xml_writer.write_event(Event::Start(BytesStart::new(&format!("CS:{}", name))))?;
-                xml_writer.write_event(Event::Text(BytesText::new(value)))?;
-                xml_writer.write_event(Event::End(BytesEnd::new(&format!("CS:{}", name))))?;
-            }
+    /// Generate the multistatus of a PROPFIND on CalDAV resources: the
+    /// requested properties they have, and a 404 propstat for the rest
+    pub fn generate_propfind_response<W: Write>(
+        writer: W,
+        resources: &[CalDavResource],
+        request: &PropFindRequest,
+        current_principal: &str,
+    ) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+        Self::write_multistatus_start(&mut xml_writer)?;
+
+        for resource in resources {
+            let requested: Vec<QualifiedName> = match &request.prop_find_type {
+                // calendar-data is only returned when asked for by name
+                PropFindType::AllProp | PropFindType::PropName => {
+                    let mut props: Vec<QualifiedName> = Self::live_properties(resource)
+                        .iter()
+                        .filter(|(namespace, name)| {
+                            request.prop_find_type == PropFindType::PropName
+                                || !(*namespace == CALDAV_NS && *name == "calendar-data")
+                        })
+                        .map(|(namespace, name)| QualifiedName::new(*namespace, *name))
+                        .collect();
+                    if let CalDavResource::Calendar { calendar, .. } = resource {
+                        props.extend(
+                            calendar
+                                .custom_properties
+                                .keys()
+                                .filter_map(|key| Self::dead_property_name(key)),
+                        );
+                    }
+                    props
+                }
+                PropFindType::Prop(props) => props.clone(),
+            };
+            let (found, missing): (Vec<_>, Vec<_>) = requested
+                .into_iter()
+                .partition(|prop| Self::has_property(resource, prop));
+
+            let href = match resource {
+                CalDavResource::Home { href, .. }
+                | CalDavResource::Calendar { href, .. }
+                | CalDavResource::Object { href, .. }
+                | CalDavResource::ScheduleBox { href, .. }
+                | CalDavResource::Message { href, .. } => href,
+            };
+            xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
+            Self::write_href(&mut xml_writer, href)?;
+
+            Self::write_propstat(&mut xml_writer, &found, "HTTP/1.1 200 OK", |w, prop| {
+                if request.prop_find_type == PropFindType::PropName {
+                    WebDavAdapter::write_empty_prop(w, prop)
+                } else {
+                    Self::write_property(w, resource, prop, current_principal)
+                }
+            })?;
+            Self::write_propstat(
+                &mut xml_writer,
+                &missing,
+                "HTTP/1.1 404 Not Found",
+                |w, prop| WebDavAdapter::write_empty_prop(w, prop),
+            )?;
+
+            xml_writer.write_event(Event::End(BytesEnd::new("D:response")))?;
         }
 
+        xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
         Ok(())
     }
 
-    /// Write calendar property names
-    fn write_calendar_prop_names<W: Write>(xml_writer: &mut Writer<W>) -> Result<()> {
-        // Common WebDAV property names
-        xml_writer.write_event(Event::Empty(BytesStart::new("D:resourcetype")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("D:displayname")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("D:getlastmodified")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("D:getetag")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("D:getcontenttype")))?;
-
-        // CalDAV specific property names
-        xml_writer.write_event(Event::Empty(BytesStart::new(
-            "C:supported-calendar-component-set",
-        )))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar-timezone")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("CS:calendar-color")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar-access")))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new(
-            "D:current-user-privilege-set",
-        )))?;
-        xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar-description")))?;
-
-        Ok(())
+    /// Indicates whether one of the components of an object meets all the
+    /// `prop-filter`s of a query
+    pub fn matches_prop_filters(object: &CalendarObjectDto, filters: &[PropFilter]) -> bool {
+        if filters.is_empty() {
+            return true;
+        }
+        let Ok(calendar) = ICalComponent::parse(object.ical_data()) else {
+            return false;
+        };
+        let matches = calendar
+            .components_named(object.component())
+            .any(|component| filters.iter().all(|filter| filter.matches(component)));
+        matches
     }
 
-    /// Write requested calendar properties
-    fn write_calendar_requested_props<W: Write>(
-        xml_writer: &mut Writer<W>,
-        calendar: &CalendarDto,
+    /// Generate the multistatus of a calendar-query or calendar-multiget
+    /// REPORT: the objects found, and a 404 response for each missing href
+    pub fn generate_report_response<W: Write>(
+        writer: W,
+        objects: &[(String, &CalendarObjectDto)],
+        missing_hrefs: &[String],
         props: &[QualifiedName],
+        expansion: Option<RecurrenceExpansion>,
     ) -> Result<()> {
-        for prop in props {
-            match (prop.namespace.as_str(), prop.name.as_str()) {
-                // DAV namespace properties
-                ("DAV:", "resourcetype") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new("D:resourcetype")))?;
-                    xml_writer.write_event(Event::Empty(BytesStart::new("D:collection")))?;
-                    xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar")))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("D:resourcetype")))?;
-                }
-                ("DAV:", "displayname") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new("D:displayname")))?;
-                    xml_writer.write_event(Event::Text(BytesText::new(&calendar.name)))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("D:displayname")))?;
-                }
-                ("DAV:", "getlastmodified") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new("D:getlastmodified")))?;
-                    xml_writer.write_event(Event::Text(BytesText::new(
-                        &calendar.updated_at.to_rfc2822(),
-                    )))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("D:getlastmodified")))?;
-                }
-                ("DAV:", "getetag") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new("D:getetag")))?;
-                    xml_writer.write_event(Event::Text(BytesText::new(&format!(
-                        "\"{}\"",
-                        calendar.id
-                    ))))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("D:getetag")))?;
-                }
-                ("DAV:", "getcontenttype") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new("D:getcontenttype")))?;
-                    xml_writer.write_event(Event::Text(BytesText::new(
-                        "text/calendar; component=VCALENDAR",
-                    )))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("D:getcontenttype")))?;
-                }
-                ("DAV:", "current-user-privilege-set") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new(
-                        "D:current-user-privilege-set",
-                    )))?;
-                    xml_writer.write_event(Event::Start(BytesStart::new("D:privilege")))?;
-                    xml_writer.write_event(Event::Empty(BytesStart::new("D:read")))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("D:privilege")))?;
-
-                    // Only add write privilege if user owns the calendar or has write access
-                    if calendar.owner_id == "current_user_id" {
-                        // This should be replaced with actual user check
-                        xml_writer.write_event(Event::Start(BytesStart::new("D:privilege")))?;
-                        xml_writer.write_event(Event::Empty(BytesStart::new("D:write")))?;
-                        xml_writer.write_event(Event::End(BytesEnd::new("D:privilege")))?;
-                    }
-
-                    xml_writer
-                        .write_event(Event::End(BytesEnd::new("D:current-user-privilege-set")))?;
-                }
+        let mut xml_writer = Writer::new(writer);
+        Self::write_multistatus_start(&mut xml_writer)?;
 
-                // CalDAV namespace properties
-                ("urn:ietf:params:xml:ns:caldav", "supported-calendar-component-set") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new(
-                        "C:supported-calendar-component-set",
-                    )))?;
-                    xml_writer.write_event(Event::Empty(
-                        BytesStart::new("C:comp").with_attributes([("name", "VEVENT")]),
-                    ))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new(
-                        "C:supported-calendar-component-set",
-                    )))?;
-                }
-                ("urn:ietf:params:xml:ns:caldav", "calendar-timezone") => {
-                    xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar-timezone")))?;
-                }
-                ("urn:ietf:params:xml:ns:caldav", "calendar-access") => {
-                    xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar-access")))?;
-                }
-                ("urn:ietf:params:xml:ns:caldav", "calendar-description") => {
-                    if let Some(desc) = &calendar.description {
-                        xml_writer
-                            .write_event(Event::Start(BytesStart::new("C:calendar-description")))?;
-                        xml_writer.write_event(Event::Text(BytesText::new(desc)))?;
-                        xml_writer
-                            .write_event(Event::End(BytesEnd::new("C:calendar-description")))?;
-                    } else {
-                        xml_writer
-                            .write_event(Event::Empty(BytesStart::new("C:calendar-description")))?;
-                    }
-                }
+        let default_props = [QualifiedName::new("DAV:", "getetag")];
+        let props = if props.is_empty() {
+            &default_props[..]
+        } else {
+            props
+        };
 
-                // CalendarServer namespace properties
-                ("http://calendarserver.org/ns/", "calendar-color") => {
-                    if let Some(color) = &calendar.color {
-                        xml_writer
-                            .write_event(Event::Start(BytesStart::new("CS:calendar-color")))?;
-                        xml_writer.write_event(Event::Text(BytesText::new(color)))?;
-                        xml_writer.write_event(Event::End(BytesEnd::new("CS:calendar-color")))?;
-                    } else {
-                        xml_writer
-                            .write_event(Event::Empty(BytesStart::new("CS:calendar-color")))?;
-                    }
-                }
+        for (href, object) in objects {
+            let resource = CalDavResource::Object {
+                object,
+                href: href.clone(),
+                expansion,
+            };
+            let (found, missing): (Vec<_>, Vec<_>) = props
+                .iter()
+                .cloned()
+                .partition(|prop| Self::has_property(&resource, prop));
+
+            xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
+            Self::write_href(&mut xml_writer, href)?;
+            Self::write_propstat(&mut xml_writer, &found, "HTTP/1.1 200 OK", |w, prop| {
+                Self::write_property(w, &resource, prop, "")
+            })?;
+            Self::write_propstat(
+                &mut xml_writer,
+                &missing,
+                "HTTP/1.1 404 Not Found",
+                |w, prop| WebDavAdapter::write_empty_prop(w, prop),
+            )?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:response")))?;
+        }
 
-                // Custom properties from the calendar
-                _ => {
-                    // Check if it's a custom property
-                    if let Some(value) = calendar.custom_properties.get(&prop.name) {
-                        let prop_name = if prop.namespace == "http://calendarserver.org/ns/" {
-                            format!("CS:{}", prop.name)
-                        } else if prop.namespace == "urn:ietf:params:xml:ns:caldav" {
-                            format!("C:{}", prop.name)
-                        } else if prop.namespace == "DAV:" {
-                            format!("D:{}", prop.name)
-                        } else {
-                            format!("{}:{}", prop.namespace, prop.name)
-                        };
+        for href in missing_hrefs {
+            xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
+            Self::write_href(&mut xml_writer, href)?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
+            xml_writer.write_event(Event::Text(BytesText::new("HTTP/1.1 404 Not Found")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:response")))?;
+        }
 
-                        xml_writer.write_event(Event::Start(BytesStart::new(&prop_name)))?;
-                        xml_writer.write_event(Event::Text(BytesText::new(value)))?;
-                        xml_writer.write_event(Event::End(BytesEnd::new(&prop_name)))?;
-                    } else {
-                        // Property not found, write empty element
-                        let prop_name = if prop.namespace == "http://calendarserver.org/ns/" {
-                            format!("CS:{}", prop.name)
-                        } else if prop.namespace == "urn:ietf:params:xml:ns:caldav" {
-                            format!("C:{}", prop.name)
-                        } else if prop.namespace == "DAV:" {
-                            format!("D:{}", prop.name)
-                        } else {
-                            format!("{}:{}", prop.namespace, prop.name)
-                        };
+        xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
+        Ok(())
+    }
 
-                        xml_writer.write_event(Event::Empty(BytesStart::new(&prop_name)))?;
-                    }
-                }
+    /// iCalendar data of an object with its recurrences expanded or limited
+    /// to a range; data that can't be parsed is returned as stored
+    fn expanded_calendar_data(
+        object: &CalendarObjectDto,
+        expansion: &RecurrenceExpansion,
+    ) -> String {
+        let Ok(calendar) = ICalComponent::parse(object.ical_data()) else {
+            return object.ical_data().to_string();
+        };
+        let zones = TimeZones::for_calendar(&calendar, object.timezone());
+        match *expansion {
+            RecurrenceExpansion::Expand { start, end } => {
+                recurrence::expand_calendar(&calendar, &zones, start, end).to_ical()
+            }
+            RecurrenceExpansion::LimitRecurrenceSet { start, end } => {
+                recurrence::limit_recurrence_set(&calendar, &zones, start, end).to_ical()
             }
         }
-
-        Ok(())
     }
 
-    /// Generate a response for calendar events
-    pub fn generate_calendar_events_response<W: Write>(
+    /// Generate the `schedule-response` of a POST to a scheduling outbox
+    /// (RFC 6638, section 3.2.9): one response per recipient
+    pub fn generate_schedule_response<W: Write>(
         writer: W,
-        events: &[CalendarEventDto],
-        request: &CalDavReportType,
-        base_href: &str,
+        responses: &[ScheduleResponseDto],
     ) -> Result<()> {
         let mut xml_writer = Writer::new(writer);
-
-        // Start multistatus response
         xml_writer.write_event(Event::Start(
-            BytesStart::new("D:multistatus").with_attributes([
-                ("xmlns:D", "DAV:"),
-                ("xmlns:C", "urn:ietf:params:xml:ns:caldav"),
-                ("xmlns:CS", "http://calendarserver.org/ns/"),
-            ]),
+            BytesStart::new("C:schedule-response")
+                .with_attributes([("xmlns:D", "DAV:"), ("xmlns:C", CALDAV_NS)]),
         ))?;
+        for response in responses {
+            xml_writer.write_event(Event::Start(BytesStart::new("C:response")))?;
+            WebDavAdapter::write_href_prop(
+                &mut xml_writer,
+                "C:recipient",
+                Some(&response.recipient),
+            )?;
+            Self::write_text_prop(
+                &mut xml_writer,
+                "C:request-status",
+                &response.request_status,
+            )?;
+            if let Some(data) = &response.calendar_data {
+                Self::write_text_prop(&mut xml_writer, "C:calendar-data", data)?;
+            }
+            xml_writer.write_event(Event::End(BytesEnd::new("C:response")))?;
+        }
+        xml_writer.write_event(Event::End(BytesEnd::new("C:schedule-response")))?;
+        Ok(())
+    }
 
-        // Determine which properties to include based on request type
-        let props = match request {
-            CalDavReportType::CalendarQuery { props, .. } => props.clone(),
-            CalDavReportType::CalendarMultiget { props, .. } => props.clone(),
-            CalDavReportType::SyncCollection { props, .. } => props.clone(),
-        };
+    /// Generate a `DAV:error` body naming a CalDAV precondition that failed
+    pub fn generate_error_response<W: Write>(writer: W, condition: &str) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+        xml_writer.write_event(Event::Start(
+            BytesStart::new("D:error")
+                .with_attributes([("xmlns:D", "DAV:"), ("xmlns:C", CALDAV_NS)]),
+        ))?;
+        xml_writer.write_event(Event::Empty(BytesStart::new(format!("C:{}", condition))))?;
+        xml_writer.write_event(Event::End(BytesEnd::new("D:error")))?;
+        Ok(())
+    }
 
-        // Add responses for events
-        for event in events {
-            // Create the event href based on its UID
-            let href = format!("{}{}.ics", base_href, event.ical_uid);
+    /// Indicates whether a calendar property is computed by the server; the
+    /// display name, description and color can be changed with PROPPATCH
+    pub fn is_protected_calendar_property(prop: &QualifiedName) -> bool {
+        let editable = matches!(
+            (prop.namespace.as_str(), prop.name.as_str()),
+            ("DAV:", "displayname")
+                | (CALDAV_NS, "calendar-description")
+                | (APPLE_ICAL_NS, "calendar-color")
+        );
+        !editable
+            && (WebDavAdapter::is_protected_property(prop)
+                || Self::CALENDAR_PROPERTIES
+                    .iter()
+                    .any(|(namespace, name)| prop.namespace == *namespace && prop.name == *name))
+    }
 
-            // Write event response
-            Self::write_event_response(&mut xml_writer, event, &props, &href)?;
-        }
+    /// Key under which a property set with PROPPATCH is stored in the calendar
+    pub fn dead_property_key(prop: &QualifiedName) -> String {
+        prop.to_string()
+    }
 
-        // End multistatus
-        xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
+    /// Property stored under a key by `dead_property_key`; internal entries
+    /// (those not in `{namespace}name` form) are not properties
+    fn dead_property_name(key: &str) -> Option<QualifiedName> {
+        let (namespace, name) = key.strip_prefix('{')?.split_once('}')?;
+        Some(QualifiedName::new(namespace, name))
+    }
 
+    fn write_multistatus_start<W: Write>(xml_writer: &mut Writer<W>) -> Result<()> {
+        xml_writer.write_event(Event::Start(
+            BytesStart::new("D:multistatus").with_attributes([
+                ("xmlns:D", "DAV:"),
+                ("xmlns:C", CALDAV_NS),
+                ("xmlns:CS", CALENDARSERVER_NS),
+                ("xmlns:ICAL", APPLE_ICAL_NS),
+            ]),
+        ))?;
         Ok(())
     }
 
-    /// Write event properties as a response
-    fn write_event_response<W: Write>(
-        xml_writer: &mut Writer<W>,
-        event: &CalendarEventDto,
-        props: &[QualifiedName],
-        href: &str,
-    ) -> Result<()> {
-        // Start response element
-        xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
-
-        // Write href
+    fn write_href<W: Write>(xml_writer: &mut Writer<W>, href: &str) -> Result<()> {
         xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
         xml_writer.write_event(Event::Text(BytesText::new(href)))?;
         xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
+        Ok(())
+    }
 
-        // Write propstat
+    /// Write a propstat with the given status, unless there are no properties
+    fn write_propstat<W: Write>(
+        xml_writer: &mut Writer<W>,
+        props: &[QualifiedName],
+        status: &str,
+        mut write: impl FnMut(&mut Writer<W>, &QualifiedName) -> Result<()>,
+    ) -> Result<()> {
+        if props.is_empty() {
+            return Ok(());
+        }
         xml_writer.write_event(Event::Start(BytesStart::new("D:propstat")))?;
-
-        // Start prop
         xml_writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
-
-        // If no specific props requested, return all common ones
-        if props.is_empty() {
-            Self::write_event_standard_props(xml_writer, event)?;
-        } else {
-            // Write specifically requested properties
-            Self::write_event_requested_props(xml_writer, event, props)?;
+        for prop in props {
+            write(xml_writer, prop)?;
         }
-
-        // End prop
         xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
-
-        // Write status
         xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
-        xml_writer.write_event(Event::Text(BytesText::new("HTTP/1.1 200 OK")))?;
+        xml_writer.write_event(Event::Text(BytesText::new(status)))?;
         xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
-
-        // End propstat
         xml_writer.write_event(Event::End(BytesEnd::new("D:propstat")))?;
-
-        // End response
-        xml_writer.write_event(Event::End(BytesEnd::new("D:response")))?;
-
         Ok(())
     }
 
-    /// Write standard event properties
-    fn write_event_standard_props<W: Write>(
+    fn write_text_prop<W: Write>(
         xml_writer: &mut Writer<W>,
-        event: &CalendarEventDto,
+        element: &str,
+        text: &str,
     ) -> Result<()> {
-        // Common WebDAV properties
-
-        // Resource type (empty for non-collection)
-        xml_writer.write_event(Event::Empty(BytesStart::new("D:resourcetype")))?;
-
-        // ETag based on updated_at timestamp
-        xml_writer.write_event(Event::Start(BytesStart::new("D:getetag")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(&format!("\"{}\"", event.id))))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:getetag")))?;
-
-        // Content type
-        xml_writer.write_event(Event::Start(BytesStart::new("D:getcontenttype")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(
-            "text/calendar; component=VEVENT",
-        )))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:getcontenttype")))?;
-
-        // Last modified
-        xml_writer.write_event(Event::Start(BytesStart::new("D:getlastmodified")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(&event.updated_at.to_rfc2822())))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:getlastmodified")))?;
-
-        // CalDAV specific properties
-
-        // Calendar data (iCalendar format)
-        xml_writer.write_event(Event::Start(BytesStart::new("C:calendar-data")))?;
-        // In a full implementation, we would generate a complete iCalendar component here
-        // For now, we'll just provide a basic example
-        let ical_data = format!(
-            "BEGIN:VCALENDAR\r\n\
-            VERSION:2.0\r\n\
-            PRODID:-//OxiCloud//NONSGML Calendar//EN\r\n\
-            BEGIN:VEVENT\r\n\
-            UID:{}\r\n\
-            SUMMARY:{}\r\n\
-            DTSTART:{}\r\n\
-            DTEND:{}\r\n\
-            {}\
-            DTSTAMP:{}\r\n\
-            END:VEVENT\r\n\
-            END:VCALENDAR\r\n",
-            event.ical_uid,
-            event.summary.replace("\n", "\\n"),
-            event.start_time.format("%Y%m%dT%H%M%SZ"),
-            event.end_time.format("%Y%m%dT%H%M%SZ"),
-            event
-                .rrule
-                .as_ref()
-                .map_or("".to_string(), |r| format!("RRULE:{}\r\n", r)),
-            event.updated_at.format("%Y%m%dT%H%M%SZ"),
-        );
-        xml_writer.write_event(Event::Text(BytesText::new(&ical_data)))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("C:calendar-data")))?;
-
+        xml_writer.write_event(Event::Start(BytesStart::new(element)))?;
+        xml_writer.write_event(Event::Text(BytesText::new(text)))?;
+        xml_writer.write_event(Event::End(BytesEnd::new(element)))?;
         Ok(())
     }
 
-    /// Write requested event properties
-    fn write_event_requested_props<W: Write>(
+    /// Write a property the resource has
+    fn write_property<W: Write>(
         xml_writer: &mut Writer<W>,
-        event: &CalendarEventDto,
-        props: &[QualifiedName],
+        resource: &CalDavResource,
+        prop: &QualifiedName,
+        current_principal: &str,
     ) -> Result<()> {
-        for prop in props {
-            match (prop.namespace.as_str(), prop.name.as_str()) {
-                // DAV namespace properties
-                ("DAV:", "resourcetype") => {
-                    xml_writer.write_event(Event::Empty(BytesStart::new("D:resourcetype")))?;
-                }
-                ("DAV:", "getetag") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new("D:getetag")))?;
-                    xml_writer
-                        .write_event(Event::Text(BytesText::new(&format!("\"{}\"", event.id))))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("D:getetag")))?;
-                }
-                ("DAV:", "getcontenttype") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new("D:getcontenttype")))?;
-                    xml_writer.write_event(Event::Text(BytesText::new(
-                        "text/calendar; component=VEVENT",
-                    )))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("D:getcontenttype")))?;
+        match (prop.namespace.as_str(), prop.name.as_str(), resource) {
+            ("DAV:", "resourcetype", CalDavResource::Object { .. })
+            | ("DAV:", "resourcetype", CalDavResource::Message { .. }) => {
+                xml_writer.write_event(Event::Empty(BytesStart::new("D:resourcetype")))?;
+            }
+            ("DAV:", "resourcetype", _) => {
+                xml_writer.write_event(Event::Start(BytesStart::new("D:resourcetype")))?;
+                xml_writer.write_event(Event::Empty(BytesStart::new("D:collection")))?;
+                match resource {
+                    CalDavResource::Calendar { .. } => {
+                        xml_writer.write_event(Event::Empty(BytesStart::new("C:calendar")))?;
+                    }
+                    CalDavResource::ScheduleBox { inbox, .. } => {
+                        let kind = if *inbox {
+                            "C:schedule-inbox"
+                        } else {
+                            "C:schedule-outbox"
+                        };
+                        xml_writer.write_event(Event::Empty(BytesStart::new(kind)))?;
+                    }
+                    _ => (),
                 }
-                ("DAV:", "getlastmodified") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new("D:getlastmodified")))?;
-                    xml_writer
-                        .write_event(Event::Text(BytesText::new(&event.updated_at.to_rfc2822())))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("D:getlastmodified")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:resourcetype")))?;
+            }
+            ("DAV:", "displayname", CalDavResource::Home { .. }) => {
+                Self::write_text_prop(xml_writer, "D:displayname", "Calendars")?;
+            }
+            ("DAV:", "displayname", CalDavResource::ScheduleBox { inbox, .. }) => {
+                let name = if *inbox { "Inbox" } else { "Outbox" };
+                Self::write_text_prop(xml_writer, "D:displayname", name)?;
+            }
+            ("DAV:", "displayname", CalDavResource::Calendar { calendar, .. }) => {
+                Self::write_text_prop(xml_writer, "D:displayname", &calendar.name)?;
+            }
+            (CALDAV_NS, "calendar-home-set", CalDavResource::Home { home, .. }) => {
+                WebDavAdapter::write_href_prop(xml_writer, "C:calendar-home-set", Some(home))?;
+            }
+            (
+                CALDAV_NS,
+                "calendar-timezone",
+                CalDavResource::Home {
+                    timezone: Some(timezone),
+                    ..
+                },
+            ) => {
+                Self::write_text_prop(
+                    xml_writer,
+                    "C:calendar-timezone",
+                    &timezone.to_vcalendar().to_ical(),
+                )?;
+            }
+            ("DAV:", "owner", CalDavResource::Home { owner, .. })
+            | ("DAV:", "owner", CalDavResource::Calendar { owner, .. })
+            | ("DAV:", "owner", CalDavResource::ScheduleBox { owner, .. }) => {
+                WebDavAdapter::write_href_prop(xml_writer, "D:owner", Some(owner))?;
+            }
+            ("DAV:", "current-user-principal", _) => {
+                WebDavAdapter::write_href_prop(
+                    xml_writer,
+                    "D:current-user-principal",
+                    Some(current_principal),
+                )?;
+            }
+            ("DAV:", "current-user-privilege-set", CalDavResource::Calendar { privileges, .. }) => {
+                WebDavAdapter::write_privileges(
+                    xml_writer,
+                    "D:current-user-privilege-set",
+                    privileges,
+                )?;
+            }
+            ("DAV:", "current-user-privilege-set", _) => {
+                WebDavAdapter::write_privileges(
+                    xml_writer,
+                    "D:current-user-privilege-set",
+                    &PrivilegeSet::read_write(),
+                )?;
+            }
+            ("DAV:", "supported-report-set", _) => {
+                xml_writer.write_event(Event::Start(BytesStart::new("D:supported-report-set")))?;
+                for report in [
+                    "C:calendar-multiget",
+                    "C:calendar-query",
+                    "C:free-busy-query",
+                ] {
+                    xml_writer.write_event(Event::Start(BytesStart::new("D:supported-report")))?;
+                    xml_writer.write_event(Event::Start(BytesStart::new("D:report")))?;
+                    xml_writer.write_event(Event::Empty(BytesStart::new(report)))?;
+                    xml_writer.write_event(Event::End(BytesEnd::new("D:report")))?;
+                    xml_writer.write_event(Event::End(BytesEnd::new("D:supported-report")))?;
                 }
-
-                // CalDAV namespace properties
-                ("urn:ietf:params:xml:ns:caldav", "calendar-data") => {
-                    xml_writer.write_event(Event::Start(BytesStart::new("C:calendar-data")))?;
-                    // In a full implementation, we would generate a complete iCalendar component here
-                    // For now, we'll just provide a basic example
-                    let ical_data = format!(
-                        "BEGIN:VCALENDAR\r\n\
-                        VERSION:2.0\r\n\
-                        PRODID:-//OxiCloud//NONSGML Calendar//EN\r\n\
-                        BEGIN:VEVENT\r\n\
-                        UID:{}\r\n\
-                        SUMMARY:{}\r\n\
-                        DTSTART:{}\r\n\
-                        DTEND:{}\r\n\
-                        {}\
-                        DTSTAMP:{}\r\n\
-                        END:VEVENT\r\n\
-                        END:VCALENDAR\r\n",
-                        event.ical_uid,
-                        event.summary.replace("\n", "\\n"),
-                        event.start_time.format("%Y%m%dT%H%M%SZ"),
-                        event.end_time.format("%Y%m%dT%H%M%SZ"),
-                        event
-                            .rrule
-                            .as_ref()
-                            .map_or("".to_string(), |r| format!("RRULE:{}\r\n", r)),
-                        event.updated_at.format("%Y%m%dT%H%M%SZ"),
-                    );
-                    xml_writer.write_event(Event::Text(BytesText::new(&ical_data)))?;
-                    xml_writer.write_event(Event::End(BytesEnd::new("C:calendar-data")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:supported-report-set")))?;
+            }
+            (CALDAV_NS, "calendar-description", CalDavResource::Calendar { calendar, .. }) => {
+                let description = calendar.description.as_deref().unwrap_or_default();
+                Self::write_text_prop(xml_writer, "C:calendar-description", description)?;
+            }
+            (
+                CALDAV_NS,
+                "supported-calendar-component-set",
+                CalDavResource::Calendar { calendar, .. },
+            ) => {
+                xml_writer.write_event(Event::Start(BytesStart::new(
+                    "C:supported-calendar-component-set",
+                )))?;
+                for component in calendar.supported_components() {
+                    xml_writer.write_event(Event::Empty(
+                        BytesStart::new("C:comp").with_attributes([("name", component.as_str())]),
+                    ))?;
                 }
-
-                // Property not supported
-                _ => {
-                    // Write empty element
-                    let prop_name = if prop.namespace == "http://calendarserver.org/ns/" {
-                        format!("CS:{}", prop.name)
-                    } else if prop.namespace == "urn:ietf:params:xml:ns:caldav" {
-                        format!("C:{}", prop.name)
-                    } else if prop.namespace == "DAV:" {
-                        format!("D:{}", prop.name)
-                    } else {
-                        format!("{}:{}", prop.namespace, prop.name)
-                    };
-
-                    xml_writer.write_event(Event::Empty(BytesStart::new(&prop_name)))?;
+                xml_writer.write_event(Event::End(BytesEnd::new(
+                    "C:supported-calendar-component-set",
+                )))?;
+            }
+            (CALDAV_NS, "supported-calendar-data", _) => {
+                xml_writer
+                    .write_event(Event::Start(BytesStart::new("C:supported-calendar-data")))?;
+                xml_writer.write_event(Event::Empty(
+                    BytesStart::new("C:calendar-data")
+                        .with_attributes([("content-type", "text/calendar"), ("version", "2.0")]),
+                ))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("C:supported-calendar-data")))?;
+            }
+            (CALENDARSERVER_NS, "getctag", CalDavResource::Calendar { ctag, .. }) => {
+                Self::write_text_prop(xml_writer, "CS:getctag", ctag)?;
+            }
+            (APPLE_ICAL_NS, "calendar-color", CalDavResource::Calendar { calendar, .. }) => {
+                let color = calendar.color.as_deref().unwrap_or_default();
+                Self::write_text_prop(xml_writer, "ICAL:calendar-color", color)?;
+            }
+            ("DAV:", "getetag", CalDavResource::Object { object, .. }) => {
+                Self::write_text_prop(xml_writer, "D:getetag", &Self::object_etag(object))?;
+            }
+            ("DAV:", "getcontenttype", CalDavResource::Object { object, .. }) => {
+                Self::write_text_prop(
+                    xml_writer,
+                    "D:getcontenttype",
+                    &format!(
+                        "{}; component={}",
+                        CALENDAR_CONTENT_TYPE,
+                        object.component()
+                    ),
+                )?;
+            }
+            ("DAV:", "getcontentlength", CalDavResource::Object { object, .. }) => {
+                Self::write_text_prop(
+                    xml_writer,
+                    "D:getcontentlength",
+                    &object.ical_data().len().to_string(),
+                )?;
+            }
+            ("DAV:", "getlastmodified", CalDavResource::Object { object, .. }) => {
+                Self::write_text_prop(
+                    xml_writer,
+                    "D:getlastmodified",
+                    &object.updated_at().to_rfc2822(),
+                )?;
+            }
+            (
+                CALDAV_NS,
+                "calendar-data",
+                CalDavResource::Object {
+                    object, expansion, ..
+                },
+            ) => {
+                let data = match expansion {
+                    Some(expansion) => Self::expanded_calendar_data(object, expansion),
+                    None => object.ical_data().to_string(),
+                };
+                Self::write_text_prop(xml_writer, "C:calendar-data", &data)?;
+            }
+            ("DAV:", "getetag", CalDavResource::Message { message, .. }) => {
+                Self::write_text_prop(xml_writer, "D:getetag", &Self::message_etag(message))?;
+            }
+            ("DAV:", "getcontenttype", CalDavResource::Message { message, .. }) => {
+                Self::write_text_prop(
+                    xml_writer,
+                    "D:getcontenttype",
+                    &format!(
+                        "{}; method={}",
+                        CALENDAR_CONTENT_TYPE,
+                        message.method.as_str()
+                    ),
+                )?;
+            }
+            ("DAV:", "getcontentlength", CalDavResource::Message { message, .. }) => {
+                Self::write_text_prop(
+                    xml_writer,
+                    "D:getcontentlength",
+                    &message.ical_data.len().to_string(),
+                )?;
+            }
+            ("DAV:", "getlastmodified", CalDavResource::Message { message, .. }) => {
+                Self::write_text_prop(
+                    xml_writer,
+                    "D:getlastmodified",
+                    &message.created_at.to_rfc2822(),
+                )?;
+            }
+            (CALDAV_NS, "calendar-data", CalDavResource::Message { message, .. }) => {
+                Self::write_text_prop(xml_writer, "C:calendar-data", &message.ical_data)?;
+            }
+            // Properties set by clients with PROPPATCH
+            (_, _, CalDavResource::Calendar { calendar, .. }) => {
+                let (start, end) = WebDavAdapter::prop_element(prop);
+                match calendar
+                    .custom_properties
+                    .get(&Self::dead_property_key(prop))
+                {
+                    Some(value) if !value.is_empty() => {
+                        xml_writer.write_event(Event::Start(start))?;
+                        xml_writer.write_event(Event::Text(BytesText::new(value)))?;
+                        xml_writer.write_event(Event::End(BytesEnd::new(end)))?;
+                    }
+                    _ => xml_writer.write_event(Event::Empty(start))?,
                 }
             }
+            _ => WebDavAdapter::write_empty_prop(xml_writer, prop)?,
         }
-
         Ok(())
     }
 
     /// Parse a MKCALENDAR XML request
-    pub fn parse_mkcalendar<R: Read>(
-        reader: R,
-    ) -> Result<(String, Option<String>, Option<String>)> {
+    pub fn parse_mkcalendar<R: Read>(reader: R) -> Result<MkCalendarRequest> {
         let mut xml_reader = Reader::from_reader(BufReader::new(reader));
         xml_reader.config_mut().trim_text(true);
 
@@ -820,10 +1011,12 @@ impl CalDavAdapter {
         let mut in_displayname = false;
         let mut in_description = false;
         let mut in_calendar_color = false;
+        let mut in_component_set = false;
 
         let mut displayname = String::new();
         let mut description = None;
         let mut color = None;
+        let mut components = Vec::new();
 
         loop {
             match xml_reader.read_event_into(&mut buffer) {
@@ -851,9 +1044,24 @@ impl CalDavAdapter {
                         {
                             in_calendar_color = true
                         }
+                        s if in_prop
+                            && (s == "supported-calendar-component-set"
+                                || s.ends_with(":supported-calendar-component-set")) =>
+                        {
+                            in_component_set = true
+                        }
                         _ => (),
                     }
                 }
+                Ok(Event::Empty(ref e)) if in_component_set => {
+                    let name = e.name();
+                    let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("");
+                    if name_str == "comp" || name_str.ends_with(":comp") {
+                        if let Some(component) = Self::attribute(e, "name") {
+                            components.push(component.to_ascii_uppercase());
+                        }
+                    }
+                }
                 Ok(Event::Text(e)) => {
                     let text = e.unescape().unwrap_or_default();
 
@@ -886,6 +1094,11 @@ impl CalDavAdapter {
                         s if s == "calendar-color" || s.ends_with(":calendar-color") => {
                             in_calendar_color = false
                         }
+                        s if s == "supported-calendar-component-set"
+                            || s.ends_with(":supported-calendar-component-set") =>
+                        {
+                            in_component_set = false
+                        }
                         _ => (),
                     }
                 }
@@ -902,6 +1115,204 @@ impl CalDavAdapter {
             displayname = format!("Calendar {}", Uuid::new_v4());
         }
 
-        Ok((displayname, description, color))
+        Ok(MkCalendarRequest {
+            name: displayname,
+            description,
+            color,
+            components,
+        })
+    }
+
+    /// Zone named by a `calendar-timezone` value, a VCALENDAR object with a
+    /// single VTIMEZONE (RFC 4791, section 5.2.2); `None` unless the server
+    /// knows the zone
+    pub fn parse_calendar_timezone(value: &str) -> Option<CalendarTimeZone> {
+        let calendar = ICalComponent::parse(value).ok()?;
+        let tzid = calendar
+            .components_named("VTIMEZONE")
+            .next()?
+            .property("TZID")?;
+        CalendarTimeZone::bundled(&tzid.value)
+    }
+
+    /// Color in the `#RRGGBB` form calendars are stored with; clients such
+    /// as Apple Calendar send `#RRGGBBAA`
+    pub fn normalize_color(color: &str) -> Option<String> {
+        let color = color.trim();
+        let hex = color.strip_prefix('#')?;
+        (matches!(hex.len(), 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| format!("#{}", &hex[..6]).to_ascii_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_calendar_query_and_multiget() {
+        let query = r#"<?xml version="1.0" encoding="utf-8" ?>
+            <C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+              <D:prop>
+                <D:getetag/>
+                <C:calendar-data><C:comp name="VCALENDAR"/></C:calendar-data>
+              </D:prop>
+              <C:filter>
+                <C:comp-filter name="VCALENDAR">
+                  <C:comp-filter name="VEVENT">
+                    <C:time-range start="20240101T000000Z" end="20240201T000000Z"/>
+                  </C:comp-filter>
+                </C:comp-filter>
+              </C:filter>
+            </C:calendar-query>"#;
+        let Some(CalDavReportType::CalendarQuery {
+            time_range,
+            component,
+            prop_filters,
+            props,
+            expansion,
+        }) = CalDavAdapter::parse_report(query.as_bytes()).unwrap()
+        else {
+            panic!("expected a calendar-query");
+        };
+        assert!(prop_filters.is_empty());
+        let (start, end) = time_range.unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-02-01T00:00:00+00:00");
+        assert_eq!(component.as_deref(), Some("VEVENT"));
+        assert_eq!(expansion, None);
+        assert_eq!(
+            props,
+            vec![
+                QualifiedName::new("DAV:", "getetag"),
+                QualifiedName::new(CALDAV_NS, "calendar-data"),
+            ]
+        );
+
+        let multiget = r#"<c:calendar-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+              <d:prop>
+                <d:getetag/>
+                <c:calendar-data>
+                  <c:expand start="20240101T000000Z" end="20240108T000000Z"/>
+                </c:calendar-data>
+              </d:prop>
+              <d:href>/caldav/calendars/alice/work/a.ics</d:href>
+              <d:href>/caldav/calendars/alice/work/b.ics</d:href>
+            </c:calendar-multiget>"#;
+        let Some(CalDavReportType::CalendarMultiget {
+            hrefs,
+            props,
+            expansion,
+        }) = CalDavAdapter::parse_report(multiget.as_bytes()).unwrap()
+        else {
+            panic!("expected a calendar-multiget");
+        };
+        assert_eq!(hrefs.len(), 2);
+        assert_eq!(props.len(), 2);
+        assert!(matches!(
+            expansion,
+            Some(RecurrenceExpansion::Expand { start, end })
+                if end - start == chrono::Duration::days(7)
+        ));
+
+        let free_busy = r#"<C:free-busy-query xmlns:C="urn:ietf:params:xml:ns:caldav">
+              <C:time-range start="20250106T000000Z" end="20250113T000000Z"/>
+            </C:free-busy-query>"#;
+        assert!(matches!(
+            CalDavAdapter::parse_report(free_busy.as_bytes()).unwrap(),
+            Some(CalDavReportType::FreeBusyQuery { start, end })
+                if end - start == chrono::Duration::days(7)
+        ));
+
+        let unsupported = r#"<C:free-busy-query xmlns:C="urn:ietf:params:xml:ns:caldav"/>"#;
+        assert!(CalDavAdapter::parse_report(unsupported.as_bytes())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_task_query_with_prop_filters() {
+        let query = r#"<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+              <D:prop><D:getetag/></D:prop>
+              <C:filter>
+                <C:comp-filter name="VCALENDAR">
+                  <C:comp-filter name="VTODO">
+                    <C:prop-filter name="completed"><C:is-not-defined/></C:prop-filter>
+                    <C:prop-filter name="SUMMARY">
+                      <C:text-match negate-condition="yes">Groceries</C:text-match>
+                    </C:prop-filter>
+                    <C:prop-filter name="DUE">
+                      <C:time-range start="20240101T000000Z"/>
+                    </C:prop-filter>
+                  </C:comp-filter>
+                </C:comp-filter>
+              </C:filter>
+            </C:calendar-query>"#;
+        let Some(CalDavReportType::CalendarQuery {
+            time_range,
+            component,
+            prop_filters,
+            ..
+        }) = CalDavAdapter::parse_report(query.as_bytes()).unwrap()
+        else {
+            panic!("expected a calendar-query");
+        };
+        // The time range of a property is not the range of the query
+        assert!(time_range.is_none());
+        assert_eq!(component.as_deref(), Some("VTODO"));
+        assert_eq!(prop_filters.len(), 3);
+        assert!(prop_filters[0].is_not_defined);
+        assert_eq!(prop_filters[1].text_match.as_deref(), Some("Groceries"));
+        assert!(prop_filters[1].negate);
+
+        let calendar = ICalComponent::parse(
+            "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:t1\r\nSUMMARY:Buy groceries\r\n\
+             DUE:20240105T000000Z\r\nEND:VTODO\r\nEND:VCALENDAR\r\n",
+        )
+        .unwrap();
+        let task = calendar.components_named("VTODO").next().unwrap();
+        assert!(prop_filters[0].matches(task));
+        assert!(!prop_filters[1].matches(task));
+        assert!(prop_filters[2].matches(task));
+    }
+
+    #[test]
+    fn test_calendar_properties_and_colors() {
+        let mut calendar = CalendarDto {
+            name: "Work".to_string(),
+            ..Default::default()
+        };
+        calendar.custom_properties.insert(
+            CalDavAdapter::dead_property_key(&QualifiedName::new(APPLE_ICAL_NS, "calendar-order")),
+            "3".to_string(),
+        );
+        calendar
+            .custom_properties
+            .insert("_resource:abc".to_string(), "x.ics".to_string());
+        let resource = CalDavResource::Calendar {
+            calendar: &calendar,
+            href: "/caldav/calendars/alice/work/".to_string(),
+            owner: "/principals/users/alice/".to_string(),
+            ctag: "1".to_string(),
+            privileges: PrivilegeSet::full(),
+        };
+        let request = PropFindRequest {
+            prop_find_type: PropFindType::AllProp,
+        };
+        let mut body = Vec::new();
+        CalDavAdapter::generate_propfind_response(&mut body, &[resource], &request, "/p/").unwrap();
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains("<C:calendar/>"));
+        assert!(body.contains("<CS:getctag>1</CS:getctag>"));
+        assert!(body.contains(">3</x:calendar-order>"));
+        assert!(!body.contains("_resource"));
+        // Missing description and color are reported as not found
+        assert!(body.contains("404 Not Found"));
+
+        assert_eq!(
+            CalDavAdapter::normalize_color("#ff2968ff").as_deref(),
+            Some("#FF2968")
+        );
+        assert_eq!(CalDavAdapter::normalize_color("red"), None);
     }
 }
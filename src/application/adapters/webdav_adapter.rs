@@ -1,16 +1,32 @@
 use crate::application::dtos::file_dto::FileDto;
 use crate::application::dtos::folder_dto::FolderDto;
+use crate::application::dtos::search_dto::SearchCriteriaDto;
+use crate::application::ports::storage_ports::StorageQuota;
+use crate::application::ports::upload_ports::UploadedChunk;
+use crate::domain::entities::webdav_acl::{
+    resource_owner, AccessControlEntry, AclPrincipal, DavPrivilege, Principal, PrincipalMatch,
+    PrincipalProperty, PrivilegeSet, USER_PRINCIPALS_PATH,
+};
+use crate::domain::entities::webdav_lock::WebDavLock;
+use crate::domain::entities::webdav_property::DeadProperty;
+use crate::domain::entities::webdav_search::{
+    like_literal, Comparison, SearchCondition, SearchOrder, SearchProperty, SearchValue,
+    SearchedResource, COLLECTION_CONTENT_TYPE,
+};
 use chrono::Utc;
 use quick_xml::{
     events::{BytesEnd, BytesStart, BytesText, Event},
-    Reader, Writer,
+    name::{Namespace, QName, ResolveResult},
+    NsReader, Reader, Writer,
 };
+use sha2::{Digest, Sha256};
 /**
  * WebDAV Adapter Module
  *
  * This module provides conversion between WebDAV protocol XML structures and OxiCloud domain objects.
  * It handles parsing WebDAV request XML and generating WebDAV response XML according to RFC 4918.
  */
+use std::collections::HashMap;
 use std::io::{BufReader, Read, Write};
 
 /// Result type for WebDAV operations
@@ -46,6 +62,15 @@ impl std::fmt::Display for WebDavError {
     }
 }
 
+/// Namespace of the ownCloud properties read by Nextcloud clients
+pub const OWNCLOUD_NS: &str = "http://owncloud.org/ns";
+
+/// Namespace of the Nextcloud-specific properties
+pub const NEXTCLOUD_NS: &str = "http://nextcloud.org/ns";
+
+/// Namespace of the CalDAV properties (RFC 4791)
+pub const CALDAV_NS: &str = "urn:ietf:params:xml:ns:caldav";
+
 /// Qualified name with namespace and local name
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct QualifiedName {
@@ -87,6 +112,257 @@ pub struct PropFindRequest {
     pub prop_find_type: PropFindType,
 }
 
+impl PropFindRequest {
+    /// Indicates whether answering the request needs the dead properties
+    pub fn includes_dead_properties(&self) -> bool {
+        match &self.prop_find_type {
+            PropFindType::AllProp | PropFindType::PropName => true,
+            PropFindType::Prop(props) => props
+                .iter()
+                .any(|prop| !WebDavAdapter::is_protected_property(prop)),
+        }
+    }
+
+    /// Indicates whether the property was asked for by name
+    pub fn requests(&self, namespace: &str, name: &str) -> bool {
+        match &self.prop_find_type {
+            PropFindType::Prop(props) => props
+                .iter()
+                .any(|prop| prop.namespace == namespace && prop.name == name),
+            _ => false,
+        }
+    }
+}
+
+/// Server-side state reported next to the metadata of each resource
+#[derive(Debug, Default)]
+pub struct PropFindContext {
+    /// Active locks, matched to each resource by path
+    pub locks: Vec<WebDavLock>,
+    /// Dead properties by resource ID
+    pub dead_properties: HashMap<String, Vec<DeadProperty>>,
+    /// Sync tokens of collections by folder ID, only when requested
+    pub sync_tokens: HashMap<String, String>,
+    /// Storage quota of the requesting user, reported on collections
+    pub quota: Option<StorageQuota>,
+    /// Privileges of the requesting user by resource ID, only when requested
+    pub privileges: HashMap<String, PrivilegeSet>,
+    /// ACLs by resource ID, only when requested and readable by the user
+    pub acls: HashMap<String, Vec<AccessControlEntry>>,
+    /// Principal URL of the requesting user
+    pub principal: Option<String>,
+}
+
+/// Parsed ACL REPORT (RFC 3744, section 9)
+#[derive(Debug)]
+pub enum AclReport {
+    /// `DAV:acl-principal-prop-set`: properties of the principals in the ACL
+    PrincipalPropSet(PropFindRequest),
+    /// `DAV:principal-property-search`: principals whose properties match
+    PrincipalPropertySearch {
+        criteria: Vec<PrincipalMatch>,
+        /// Whether every criterion must match (`test="allof"`)
+        all_of: bool,
+        properties: PropFindRequest,
+    },
+}
+
+/// Resource under the principal tree
+#[derive(Debug, Clone)]
+pub enum PrincipalResource {
+    /// Collection of principals, such as `/principals/users/`
+    Collection { href: String, name: String },
+    /// Principal of a user
+    User(Principal),
+}
+
+/// Parsed `DAV:sync-collection` REPORT (RFC 6578, section 3.2)
+#[derive(Debug)]
+pub struct SyncCollectionRequest {
+    /// Token from a previous report; `None` asks for an initial sync
+    pub sync_token: Option<String>,
+    /// Whether members of child collections are reported too
+    pub infinite: bool,
+    /// Maximum number of member responses the client accepts
+    pub limit: Option<usize>,
+    /// Properties to return for changed members
+    pub properties: PropFindRequest,
+}
+
+/// Outcome of a sync-collection REPORT
+#[derive(Debug, Default)]
+pub struct SyncCollectionResult {
+    /// Members created or changed since the client's token
+    pub folders: Vec<FolderDto>,
+    pub files: Vec<FileDto>,
+    /// Hrefs of members that are gone from the collection
+    pub removed: Vec<String>,
+    /// Href of the collection when the results were cut at the client's limit
+    pub truncated: Option<String>,
+    /// Token that identifies the state the client has after this report
+    pub sync_token: String,
+}
+
+/// Parsed `DAV:basicsearch` query of a SEARCH request (RFC 5323, section 5)
+#[derive(Debug)]
+pub struct BasicSearch {
+    /// Properties to return for each result
+    pub select: PropFindRequest,
+    /// Href of the collection to search in
+    pub scope: String,
+    /// Whether the whole subtree is searched (`infinity`) or only the
+    /// members of the scope (`1`); depth `0` matches nothing
+    pub depth: String,
+    /// Condition results must meet; `None` matches every resource
+    pub condition: Option<SearchCondition>,
+    /// Ordering of the results, most significant first
+    pub order: Vec<SearchOrder>,
+    /// Maximum number of results the client accepts
+    pub limit: Option<usize>,
+}
+
+/// Maximum number of candidates a SEARCH asks the search service for
+const SEARCH_CANDIDATES: usize = 10_000;
+
+impl BasicSearch {
+    /// Criteria for the search service that select every resource the
+    /// condition can match, and possibly some more.
+    ///
+    /// Only the top-level conjuncts narrow the criteria; the condition
+    /// itself still has to be checked against each candidate
+    pub fn search_criteria(&self, folder_id: Option<String>) -> SearchCriteriaDto {
+        let mut criteria = SearchCriteriaDto {
+            folder_id,
+            recursive: self.depth == "infinity",
+            limit: SEARCH_CANDIDATES,
+            ..Default::default()
+        };
+        let conjuncts = self.condition.iter().flat_map(|c| c.conjuncts());
+        for condition in conjuncts {
+            match condition {
+                SearchCondition::Like {
+                    property: SearchProperty::DisplayName,
+                    pattern,
+                } => {
+                    let literal = like_literal(pattern);
+                    if criteria
+                        .name_contains
+                        .as_ref()
+                        .is_none_or(|name| name.len() < literal.len())
+                    {
+                        criteria.name_contains = Some(literal).filter(|l| !l.is_empty());
+                    }
+                }
+                SearchCondition::Like {
+                    property: SearchProperty::ContentType,
+                    pattern,
+                } => {
+                    // Only "type/%" maps to a prefix of the MIME type
+                    let prefix = like_literal(pattern);
+                    if prefix.ends_with('/') && *pattern == format!("{}%", prefix) {
+                        criteria.content_type = Some(prefix);
+                    }
+                }
+                SearchCondition::Compare {
+                    property,
+                    comparison,
+                    value,
+                } => match (property, value) {
+                    (SearchProperty::DisplayName, SearchValue::Text(name))
+                        if *comparison == Comparison::Eq =>
+                    {
+                        criteria.name_contains = Some(name.clone());
+                    }
+                    (SearchProperty::ContentType, SearchValue::Text(content_type))
+                        if *comparison == Comparison::Eq
+                            && content_type != COLLECTION_CONTENT_TYPE =>
+                    {
+                        criteria.content_type = Some(content_type.clone());
+                    }
+                    (SearchProperty::LastModified, SearchValue::Number(value)) => {
+                        Self::narrow(
+                            *comparison,
+                            *value,
+                            &mut criteria.modified_after,
+                            &mut criteria.modified_before,
+                        );
+                    }
+                    (SearchProperty::ContentLength, SearchValue::Number(value)) => {
+                        Self::narrow(
+                            *comparison,
+                            *value,
+                            &mut criteria.min_size,
+                            &mut criteria.max_size,
+                        );
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        criteria
+    }
+
+    /// Narrows an inclusive range with a comparison against a value
+    fn narrow(comparison: Comparison, value: u64, min: &mut Option<u64>, max: &mut Option<u64>) {
+        if matches!(
+            comparison,
+            Comparison::Eq | Comparison::Gt | Comparison::Gte
+        ) {
+            *min = Some(min.map_or(value, |min| min.max(value)));
+        }
+        if matches!(
+            comparison,
+            Comparison::Eq | Comparison::Lt | Comparison::Lte
+        ) {
+            *max = Some(max.map_or(value, |max| max.min(value)));
+        }
+    }
+}
+
+/// Resource found by a SEARCH
+#[derive(Debug, Clone)]
+pub enum SearchHit {
+    Folder(FolderDto),
+    File(FileDto),
+}
+
+impl SearchHit {
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Folder(folder) => &folder.id,
+            Self::File(file) => &file.id,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Folder(folder) => &folder.path,
+            Self::File(file) => &file.path,
+        }
+    }
+
+    /// The values a search condition and ordering are evaluated on
+    pub fn resource(&self) -> SearchedResource<'_> {
+        match self {
+            Self::Folder(folder) => SearchedResource {
+                name: &folder.name,
+                content_type: COLLECTION_CONTENT_TYPE,
+                modified_at: folder.modified_at,
+                size: 0,
+                is_collection: true,
+            },
+            Self::File(file) => SearchedResource {
+                name: &file.name,
+                content_type: &file.mime_type,
+                modified_at: file.modified_at,
+                size: file.size,
+                is_collection: false,
+            },
+        }
+    }
+}
+
 /// WebDAV property value
 #[derive(Debug, Clone)]
 pub struct PropValue {
@@ -94,37 +370,126 @@ pub struct PropValue {
     pub value: Option<String>,
 }
 
-/// WebDAV lock information
-#[derive(Debug, Clone)]
-pub struct LockInfo {
-    pub token: String,
-    pub owner: Option<String>,
-    pub depth: String,
-    pub timeout: Option<String>,
-    pub scope: LockScope,
-    pub type_: LockType,
+/// Outcome of a single property in a PROPPATCH request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropPatchStatus {
+    /// The change was applied
+    Ok,
+    /// The property is protected and cannot be changed
+    Forbidden,
+    /// Not applied because another change in the same request failed
+    FailedDependency,
 }
 
-/// Lock scope (exclusive or shared)
-#[derive(Debug, Clone, PartialEq)]
-pub enum LockScope {
-    Exclusive,
-    Shared,
+impl PropPatchStatus {
+    fn status_line(&self) -> &'static str {
+        match self {
+            PropPatchStatus::Ok => "HTTP/1.1 200 OK",
+            PropPatchStatus::Forbidden => "HTTP/1.1 403 Forbidden",
+            PropPatchStatus::FailedDependency => "HTTP/1.1 424 Failed Dependency",
+        }
+    }
 }
 
+/// Lock scope (exclusive or shared)
+pub use crate::domain::entities::webdav_lock::LockScope;
+
 /// Lock type (currently only write)
 #[derive(Debug, Clone, PartialEq)]
 pub enum LockType {
     Write,
 }
 
+/// Condition in an `If` header list (RFC 4918, section 10.4)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfCondition {
+    /// State token, usually a lock token
+    Token(String),
+    /// Entity tag, including its quotes
+    ETag(String),
+}
+
+/// Parenthesized list of conditions that must all hold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IfList {
+    /// Tagged resource the list applies to, or `None` for the request URI
+    pub resource: Option<String>,
+    /// Conditions with their `Not` flag
+    pub conditions: Vec<(bool, IfCondition)>,
+}
+
+/// Parsed `If` header; it holds when any of its lists holds
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IfHeader {
+    pub lists: Vec<IfList>,
+}
+
+impl IfHeader {
+    /// Lock tokens submitted by the client, i.e. those not negated
+    pub fn submitted_tokens(&self) -> Vec<String> {
+        let mut tokens = Vec::new();
+        for list in &self.lists {
+            for (negated, condition) in &list.conditions {
+                if let (false, IfCondition::Token(token)) = (negated, condition) {
+                    if !tokens.contains(token) {
+                        tokens.push(token.clone());
+                    }
+                }
+            }
+        }
+        tokens
+    }
+}
+
+/// Privilege in the tree reported by `supported-privilege-set`
+struct SupportedPrivilege {
+    name: &'static str,
+    description: &'static str,
+    aggregates: &'static [SupportedPrivilege],
+}
+
+impl SupportedPrivilege {
+    const fn leaf(name: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            description,
+            aggregates: &[],
+        }
+    }
+}
+
+/// Privileges the server understands (RFC 3744, section 3.12)
+const SUPPORTED_PRIVILEGES: SupportedPrivilege = SupportedPrivilege {
+    name: "all",
+    description: "Any operation",
+    aggregates: &[
+        SupportedPrivilege::leaf("read", "Read any object"),
+        SupportedPrivilege::leaf("read-acl", "Read the access control list"),
+        SupportedPrivilege::leaf(
+            "read-current-user-privilege-set",
+            "Read the privileges of the current user",
+        ),
+        SupportedPrivilege {
+            name: "write",
+            description: "Write any object",
+            aggregates: &[
+                SupportedPrivilege::leaf("write-properties", "Write properties"),
+                SupportedPrivilege::leaf("write-content", "Write resource content"),
+                SupportedPrivilege::leaf("bind", "Add members to a collection"),
+                SupportedPrivilege::leaf("unbind", "Remove members from a collection"),
+            ],
+        },
+        SupportedPrivilege::leaf("unlock", "Unlock a resource locked by another user"),
+    ],
+};
+
 /// WebDAV adapter for converting between XML and domain objects
 pub struct WebDavAdapter;
 
 impl WebDavAdapter {
     /// Parse a PROPFIND XML request
     pub fn parse_propfind<R: Read>(reader: R) -> Result<PropFindRequest> {
-        let mut xml_reader = Reader::from_reader(BufReader::new(reader));
+        let mut xml_reader = NsReader::from_reader(BufReader::new(reader));
         xml_reader.config_mut().trim_text(true);
 
         let mut buffer = Vec::new();
@@ -154,10 +519,7 @@ impl WebDavAdapter {
                         in_propname = true;
                     } else if in_prop {
                         // Add property to request
-                        let namespace = Self::extract_namespace(name_str);
-                        let prop_name = Self::extract_local_name(name_str);
-
-                        props.push(QualifiedName::new(namespace, prop_name));
+                        props.push(Self::resolve_name(&xml_reader, name));
                     }
                 }
                 Ok(Event::End(ref e)) => {
@@ -186,10 +548,7 @@ impl WebDavAdapter {
                         in_propname = true;
                     } else if in_prop {
                         // Add property to request (empty element)
-                        let namespace = Self::extract_namespace(name_str);
-                        let prop_name = Self::extract_local_name(name_str);
-
-                        props.push(QualifiedName::new(namespace, prop_name));
+                        props.push(Self::resolve_name(&xml_reader, name));
                     }
                 }
                 Ok(Event::Eof) => break,
@@ -211,15 +570,15 @@ impl WebDavAdapter {
         Ok(PropFindRequest { prop_find_type })
     }
 
-    /// Generate a PROPFIND response for files and folders
+    /// Generate a PROPFIND response for files and folders; for Depth: 0 pass no members
     pub fn generate_propfind_response<W: Write>(
         writer: W,
         folder: Option<&FolderDto>,
         files: &[FileDto],
         subfolders: &[FolderDto],
         request: &PropFindRequest,
-        _depth: &str,
         base_href: &str,
+        context: &PropFindContext,
     ) -> Result<()> {
         let mut xml_writer = Writer::new(writer);
 
@@ -235,30 +594,30 @@ impl WebDavAdapter {
                 folder,
                 request,
                 &format!("{}", base_href),
+                context,
             )?;
         }
 
-        // If depth allows, add responses for files and subfolders
-        if _depth != "0" {
-            // Add responses for files
-            for file in files {
-                Self::write_file_response(
-                    &mut xml_writer,
-                    file,
-                    request,
-                    &format!("{}{}", base_href, file.name),
-                )?;
-            }
+        // Add responses for files
+        for file in files {
+            Self::write_file_response(
+                &mut xml_writer,
+                file,
+                request,
+                &format!("{}{}", base_href, file.name),
+                context,
+            )?;
+        }
 
-            // Add responses for subfolders
-            for subfolder in subfolders {
-                Self::write_folder_response(
-                    &mut xml_writer,
-                    subfolder,
-                    request,
-                    &format!("{}{}/", base_href, subfolder.name),
-                )?;
-            }
+        // Add responses for subfolders
+        for subfolder in subfolders {
+            Self::write_folder_response(
+                &mut xml_writer,
+                subfolder,
+                request,
+                &format!("{}{}/", base_href, subfolder.name),
+                context,
+            )?;
         }
 
         // End multistatus
@@ -274,6 +633,7 @@ impl WebDavAdapter {
         request: &PropFindRequest,
         _depth: &str,
         href: &str,
+        context: &PropFindContext,
     ) -> Result<()> {
         let mut xml_writer = Writer::new(writer);
 
@@ -283,7 +643,7 @@ impl WebDavAdapter {
         ))?;
 
         // Add response for file
-        Self::write_file_response(&mut xml_writer, file, request, href)?;
+        Self::write_file_response(&mut xml_writer, file, request, href, context)?;
 
         // End multistatus
         xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
@@ -291,13 +651,55 @@ impl WebDavAdapter {
         Ok(())
     }
 
+    /// Write the opening tag of a multistatus whose responses are written
+    /// separately, as when a response is streamed page by page
+    pub fn generate_multistatus_start<W: Write>(writer: W) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+        xml_writer.write_event(Event::Start(
+            BytesStart::new("D:multistatus").with_attributes([("xmlns:D", "DAV:")]),
+        ))?;
+        Ok(())
+    }
+
+    /// Write the closing tag of a multistatus started with
+    /// `generate_multistatus_start`
+    pub fn generate_multistatus_end<W: Write>(writer: W) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+        xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
+        Ok(())
+    }
+
+    /// Write the PROPFIND responses of a page of resources, each with its
+    /// href, without the enclosing multistatus
+    pub fn generate_propfind_page<W: Write>(
+        writer: W,
+        folders: &[(FolderDto, String)],
+        files: &[(FileDto, String)],
+        request: &PropFindRequest,
+        context: &PropFindContext,
+    ) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+
+        for (folder, href) in folders {
+            Self::write_folder_response(&mut xml_writer, folder, request, href, context)?;
+        }
+        for (file, href) in files {
+            Self::write_file_response(&mut xml_writer, file, request, href, context)?;
+        }
+
+        Ok(())
+    }
+
     /// Write folder properties as a response
     fn write_folder_response<W: Write>(
         xml_writer: &mut Writer<W>,
         folder: &FolderDto,
         request: &PropFindRequest,
         href: &str,
+        context: &PropFindContext,
     ) -> Result<()> {
+        let properties = Self::dead_properties_of(&context.dead_properties, &folder.id);
+
         // Start response element
         xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
 
@@ -317,16 +719,31 @@ impl WebDavAdapter {
             PropFindType::AllProp => {
                 // Write all standard properties for a folder
                 Self::write_folder_standard_props(xml_writer, folder)?;
+                Self::write_dead_props(xml_writer, properties)?;
             }
             PropFindType::PropName => {
                 // Write only property names (empty elements)
                 Self::write_folder_prop_names(xml_writer)?;
+                for property in properties {
+                    Self::write_empty_prop(xml_writer, &Self::dead_property_name(property))?;
+                }
             }
             PropFindType::Prop(props) => {
                 // Write requested properties
-                Self::write_folder_requested_props(xml_writer, folder, props)?;
+                let sync_token = context.sync_tokens.get(&folder.id).map(String::as_str);
+                Self::write_folder_requested_props(
+                    xml_writer,
+                    folder,
+                    props,
+                    properties,
+                    sync_token,
+                    context.quota.as_ref(),
+                    context.privileges.get(&folder.id),
+                )?;
             }
         }
+        Self::write_lock_props(xml_writer, request, href, &context.locks)?;
+        Self::write_acl_props(xml_writer, request, &folder.id, href, context)?;
 
         // End prop
         xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
@@ -351,7 +768,10 @@ impl WebDavAdapter {
         file: &FileDto,
         request: &PropFindRequest,
         href: &str,
+        context: &PropFindContext,
     ) -> Result<()> {
+        let properties = Self::dead_properties_of(&context.dead_properties, &file.id);
+
         // Start response element
         xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
 
@@ -371,16 +791,28 @@ impl WebDavAdapter {
             PropFindType::AllProp => {
                 // Write all standard properties for a file
                 Self::write_file_standard_props(xml_writer, file)?;
+                Self::write_dead_props(xml_writer, properties)?;
             }
             PropFindType::PropName => {
                 // Write only property names (empty elements)
                 Self::write_file_prop_names(xml_writer)?;
+                for property in properties {
+                    Self::write_empty_prop(xml_writer, &Self::dead_property_name(property))?;
+                }
             }
             PropFindType::Prop(props) => {
                 // Write requested properties
-                Self::write_file_requested_props(xml_writer, file, props)?;
+                Self::write_file_requested_props(
+                    xml_writer,
+                    file,
+                    props,
+                    properties,
+                    context.privileges.get(&file.id),
+                )?;
             }
         }
+        Self::write_lock_props(xml_writer, request, href, &context.locks)?;
+        Self::write_acl_props(xml_writer, request, &file.id, href, context)?;
 
         // End prop
         xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
@@ -497,7 +929,7 @@ impl WebDavAdapter {
 
         // ETag
         xml_writer.write_event(Event::Start(BytesStart::new("D:getetag")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(&format!("\"{}\"", file.id))))?;
+        xml_writer.write_event(Event::Text(BytesText::new(&format!("\"{}\"", file.etag))))?;
         xml_writer.write_event(Event::End(BytesEnd::new("D:getetag")))?;
 
         Ok(())
@@ -513,6 +945,13 @@ impl WebDavAdapter {
         xml_writer.write_event(Event::Empty(BytesStart::new("D:getetag")))?;
         xml_writer.write_event(Event::Empty(BytesStart::new("D:getcontentlength")))?;
         xml_writer.write_event(Event::Empty(BytesStart::new("D:getcontenttype")))?;
+        xml_writer.write_event(Event::Empty(BytesStart::new("D:supported-report-set")))?;
+        xml_writer.write_event(Event::Empty(BytesStart::new(
+            "D:supported-query-grammar-set",
+        )))?;
+        xml_writer.write_event(Event::Empty(BytesStart::new("D:sync-token")))?;
+        xml_writer.write_event(Event::Empty(BytesStart::new("D:quota-available-bytes")))?;
+        xml_writer.write_event(Event::Empty(BytesStart::new("D:quota-used-bytes")))?;
 
         Ok(())
     }
@@ -536,6 +975,10 @@ impl WebDavAdapter {
         xml_writer: &mut Writer<W>,
         folder: &FolderDto,
         props: &[QualifiedName],
+        properties: &[DeadProperty],
+        sync_token: Option<&str>,
+        quota: Option<&StorageQuota>,
+        privileges: Option<&PrivilegeSet>,
     ) -> Result<()> {
         for prop in props {
             if prop.namespace == "DAV:" {
@@ -596,31 +1039,186 @@ impl WebDavAdapter {
                             .write_event(Event::Text(BytesText::new("httpd/unix-directory")))?;
                         xml_writer.write_event(Event::End(BytesEnd::new("D:getcontenttype")))?;
                     }
+                    "sync-token" => match sync_token {
+                        Some(token) => {
+                            xml_writer
+                                .write_event(Event::Start(BytesStart::new("D:sync-token")))?;
+                            xml_writer.write_event(Event::Text(BytesText::new(token)))?;
+                            xml_writer.write_event(Event::End(BytesEnd::new("D:sync-token")))?;
+                        }
+                        None => {
+                            xml_writer.write_event(Event::Empty(BytesStart::new("D:sync-token")))?
+                        }
+                    },
+                    "quota-used-bytes" | "quota-available-bytes" => {
+                        // RFC 4331: no value when the user has no limit
+                        let value = quota.and_then(|quota| match prop.name.as_str() {
+                            "quota-used-bytes" => Some(quota.used_bytes),
+                            _ => quota.available_bytes(),
+                        });
+                        let name = format!("D:{}", prop.name);
+                        match value {
+                            Some(value) => {
+                                xml_writer.write_event(Event::Start(BytesStart::new(&name)))?;
+                                xml_writer
+                                    .write_event(Event::Text(BytesText::new(&value.to_string())))?;
+                                xml_writer.write_event(Event::End(BytesEnd::new(&name)))?;
+                            }
+                            None => xml_writer.write_event(Event::Empty(BytesStart::new(&name)))?,
+                        }
+                    }
+                    "supported-report-set" => {
+                        xml_writer
+                            .write_event(Event::Start(BytesStart::new("D:supported-report-set")))?;
+                        for report in [
+                            "D:sync-collection",
+                            "D:acl-principal-prop-set",
+                            "D:principal-property-search",
+                        ] {
+                            xml_writer
+                                .write_event(Event::Start(BytesStart::new("D:supported-report")))?;
+                            xml_writer.write_event(Event::Start(BytesStart::new("D:report")))?;
+                            xml_writer.write_event(Event::Empty(BytesStart::new(report)))?;
+                            xml_writer.write_event(Event::End(BytesEnd::new("D:report")))?;
+                            xml_writer
+                                .write_event(Event::End(BytesEnd::new("D:supported-report")))?;
+                        }
+                        xml_writer
+                            .write_event(Event::End(BytesEnd::new("D:supported-report-set")))?;
+                    }
+                    "supported-query-grammar-set" => {
+                        // RFC 5323, section 3.7: only basicsearch is understood
+                        xml_writer.write_event(Event::Start(BytesStart::new(
+                            "D:supported-query-grammar-set",
+                        )))?;
+                        xml_writer.write_event(Event::Start(BytesStart::new(
+                            "D:supported-query-grammar",
+                        )))?;
+                        xml_writer.write_event(Event::Start(BytesStart::new("D:grammar")))?;
+                        xml_writer.write_event(Event::Empty(BytesStart::new("D:basicsearch")))?;
+                        xml_writer.write_event(Event::End(BytesEnd::new("D:grammar")))?;
+                        xml_writer
+                            .write_event(Event::End(BytesEnd::new("D:supported-query-grammar")))?;
+                        xml_writer.write_event(Event::End(BytesEnd::new(
+                            "D:supported-query-grammar-set",
+                        )))?;
+                    }
+                    "lockdiscovery" | "supportedlock" => {
+                        // Written by write_lock_props
+                    }
+                    name if Self::ACL_PROPERTIES.contains(&name) => {
+                        // Written by write_acl_props
+                    }
                     _ => {
-                        // Property not supported - write empty element
-                        xml_writer.write_event(Event::Empty(BytesStart::new(&format!(
-                            "D:{}",
-                            prop.name
-                        ))))?;
+                        // Not a live property - look for a dead one
+                        Self::write_dead_prop_or_empty(xml_writer, prop, properties)?;
                     }
                 }
+            } else if prop.namespace == OWNCLOUD_NS || prop.namespace == NEXTCLOUD_NS {
+                Self::write_cloud_prop(
+                    xml_writer, prop, &folder.id, 0, true, properties, privileges,
+                )?;
             } else {
-                // Non-DAV namespace, not supported
-                xml_writer.write_event(Event::Empty(BytesStart::new(&format!(
-                    "{}:{}",
-                    prop.namespace, prop.name
-                ))))?;
+                // Non-DAV namespace, only dead properties
+                Self::write_dead_prop_or_empty(xml_writer, prop, properties)?;
             }
         }
 
         Ok(())
     }
 
+    /// Numeric file ID reported to Nextcloud clients, which expect an
+    /// integer. Derived from the resource ID and kept within the integers
+    /// JavaScript represents exactly
+    pub fn numeric_file_id(id: &str) -> u64 {
+        let digest = Sha256::digest(id.as_bytes());
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&digest[..8]);
+        u64::from_be_bytes(bytes) & ((1 << 53) - 1)
+    }
+
+    /// Write an ownCloud or Nextcloud property read by Nextcloud clients
+    fn write_cloud_prop<W: Write>(
+        xml_writer: &mut Writer<W>,
+        prop: &QualifiedName,
+        id: &str,
+        size: u64,
+        is_folder: bool,
+        properties: &[DeadProperty],
+        privileges: Option<&PrivilegeSet>,
+    ) -> Result<()> {
+        let value = match (prop.namespace.as_str(), prop.name.as_str()) {
+            (OWNCLOUD_NS, "fileid") => Self::numeric_file_id(id).to_string(),
+            (OWNCLOUD_NS, "id") => id.to_string(),
+            (OWNCLOUD_NS, "size") => size.to_string(),
+            (OWNCLOUD_NS, "permissions") => Self::cloud_permissions(
+                privileges.unwrap_or(&PrivilegeSet::read_write()),
+                is_folder,
+            ),
+            (NEXTCLOUD_NS, "has-preview") => "false".to_string(),
+            (OWNCLOUD_NS, "checksums") => {
+                // Stored as a dead property when the client uploads the file
+                let checksum = properties
+                    .iter()
+                    .find(|property| property.is_named(OWNCLOUD_NS, "checksums"))
+                    .and_then(|property| property.value.as_deref());
+                let (start, end) = Self::prop_element(prop);
+                match checksum {
+                    Some(checksum) => {
+                        xml_writer.write_event(Event::Start(start))?;
+                        xml_writer.write_event(Event::Start(BytesStart::new("x:checksum")))?;
+                        xml_writer.write_event(Event::Text(BytesText::new(checksum)))?;
+                        xml_writer.write_event(Event::End(BytesEnd::new("x:checksum")))?;
+                        xml_writer.write_event(Event::End(BytesEnd::new(end)))?;
+                    }
+                    None => xml_writer.write_event(Event::Empty(start))?,
+                }
+                return Ok(());
+            }
+            _ => return Self::write_dead_prop_or_empty(xml_writer, prop, properties),
+        };
+
+        let (start, end) = Self::prop_element(prop);
+        xml_writer.write_event(Event::Start(start))?;
+        xml_writer.write_event(Event::Text(BytesText::new(&value)))?;
+        xml_writer.write_event(Event::End(BytesEnd::new(end)))?;
+        Ok(())
+    }
+
+    /// Permissions string of Nextcloud clients: share, read, delete,
+    /// rename and move, plus write for files and create file/folder for
+    /// collections
+    fn cloud_permissions(privileges: &PrivilegeSet, is_folder: bool) -> String {
+        let mut permissions = String::new();
+        let write = privileges.contains(DavPrivilege::Write);
+        if write {
+            permissions.push('R');
+        }
+        if privileges.contains(DavPrivilege::Read) {
+            permissions.push('G');
+        }
+        if privileges.contains(DavPrivilege::Unbind) {
+            permissions.push('D');
+        }
+        if write {
+            permissions.push_str("NV");
+        }
+        if is_folder && privileges.contains(DavPrivilege::Bind) {
+            permissions.push_str("CK");
+        }
+        if !is_folder && privileges.contains(DavPrivilege::WriteContent) {
+            permissions.push('W');
+        }
+        permissions
+    }
+
     /// Write requested file properties
     fn write_file_requested_props<W: Write>(
         xml_writer: &mut Writer<W>,
         file: &FileDto,
         props: &[QualifiedName],
+        properties: &[DeadProperty],
+        privileges: Option<&PrivilegeSet>,
     ) -> Result<()> {
         for prop in props {
             if prop.namespace == "DAV:" {
@@ -675,122 +1273,604 @@ impl WebDavAdapter {
                         xml_writer.write_event(Event::Start(BytesStart::new("D:getetag")))?;
                         xml_writer.write_event(Event::Text(BytesText::new(&format!(
                             "\"{}\"",
-                            file.id
+                            file.etag
                         ))))?;
                         xml_writer.write_event(Event::End(BytesEnd::new("D:getetag")))?;
                     }
+                    "lockdiscovery" | "supportedlock" => {
+                        // Written by write_lock_props
+                    }
+                    name if Self::ACL_PROPERTIES.contains(&name) => {
+                        // Written by write_acl_props
+                    }
                     _ => {
-                        // Property not supported - write empty element
-                        xml_writer.write_event(Event::Empty(BytesStart::new(&format!(
-                            "D:{}",
-                            prop.name
-                        ))))?;
+                        // Not a live property - look for a dead one
+                        Self::write_dead_prop_or_empty(xml_writer, prop, properties)?;
                     }
                 }
+            } else if prop.namespace == OWNCLOUD_NS || prop.namespace == NEXTCLOUD_NS {
+                Self::write_cloud_prop(
+                    xml_writer, prop, &file.id, file.size, false, properties, privileges,
+                )?;
             } else {
-                // Non-DAV namespace, not supported
-                xml_writer.write_event(Event::Empty(BytesStart::new(&format!(
-                    "{}:{}",
-                    prop.namespace, prop.name
-                ))))?;
+                // Non-DAV namespace, only dead properties
+                Self::write_dead_prop_or_empty(xml_writer, prop, properties)?;
             }
         }
 
         Ok(())
     }
 
-    /// Parse a PROPPATCH XML request
-    pub fn parse_proppatch<R: Read>(reader: R) -> Result<(Vec<PropValue>, Vec<QualifiedName>)> {
-        let mut xml_reader = Reader::from_reader(BufReader::new(reader));
+    /// Parse a REPORT body, returning `None` for reports other than
+    /// `DAV:sync-collection`
+    pub fn parse_sync_collection<R: Read>(reader: R) -> Result<Option<SyncCollectionRequest>> {
+        let mut xml_reader = NsReader::from_reader(BufReader::new(reader));
         xml_reader.config_mut().trim_text(true);
 
         let mut buffer = Vec::new();
-        let mut in_propertyupdate = false;
-        let mut in_set = false;
-        let mut in_remove = false;
-        let mut in_prop = false;
-        let mut current_prop: Option<QualifiedName> = None;
-        let mut props_to_set = Vec::new();
-        let mut props_to_remove = Vec::new();
-        let mut current_text = String::new();
+        let mut open: Vec<QualifiedName> = Vec::new();
+        let mut sync_token = None;
+        let mut infinite = false;
+        let mut limit = None;
+        let mut prop_find_type = None;
+        let mut props = Vec::new();
 
         loop {
-            match xml_reader.read_event_into(&mut buffer) {
-                Ok(Event::Start(ref e)) => {
-                    let name = e.name();
-                    let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("");
-
-                    match name_str {
-                        s if s == "propertyupdate" || s.ends_with(":propertyupdate") => {
-                            in_propertyupdate = true
-                        }
-                        s if (in_propertyupdate && (s == "set" || s.ends_with(":set"))) => {
-                            in_set = true
-                        }
-                        s if (in_propertyupdate && (s == "remove" || s.ends_with(":remove"))) => {
-                            in_remove = true
-                        }
-                        s if ((in_set || in_remove) && (s == "prop" || s.ends_with(":prop"))) => {
-                            in_prop = true
-                        }
-                        _ if in_prop => {
-                            // This is a property element
-                            let namespace = Self::extract_namespace(name_str);
-                            let prop_name = Self::extract_local_name(name_str);
-
-                            current_prop = Some(QualifiedName::new(namespace, prop_name));
-                            current_text.clear();
+            let event = xml_reader.read_event_into(&mut buffer)?;
+            match &event {
+                Event::Start(e) | Event::Empty(e) => {
+                    let name = Self::resolve_name(&xml_reader, e.name());
+                    let parent = open.last().filter(|parent| parent.namespace == "DAV:");
+                    match (open.len(), parent.map(|parent| parent.name.as_str())) {
+                        (0, _) if name.namespace != "DAV:" || name.name != "sync-collection" => {
+                            return Ok(None);
                         }
-                        _ => (),
+                        (1, _) if name.namespace == "DAV:" => match name.name.as_str() {
+                            "allprop" => prop_find_type = Some(PropFindType::AllProp),
+                            "propname" => prop_find_type = Some(PropFindType::PropName),
+                            "prop" => prop_find_type = Some(PropFindType::Prop(Vec::new())),
+                            _ => {}
+                        },
+                        (2, Some("prop")) => props.push(name.clone()),
+                        _ => {}
                     }
-                }
-                Ok(Event::Text(e)) => {
-                    if current_prop.is_some() {
-                        current_text.push_str(&e.unescape().unwrap_or_default());
+                    if matches!(event, Event::Start(_)) {
+                        open.push(name);
                     }
                 }
-                Ok(Event::End(ref e)) => {
-                    let name = e.name();
-                    let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("");
-
-                    match name_str {
-                        s if s == "propertyupdate" || s.ends_with(":propertyupdate") => {
-                            in_propertyupdate = false
-                        }
-                        s if s == "set" || s.ends_with(":set") => in_set = false,
-                        s if s == "remove" || s.ends_with(":remove") => in_remove = false,
-                        s if s == "prop" || s.ends_with(":prop") => in_prop = false,
-                        _ if in_prop => {
-                            // End of property element
-                            if let Some(prop_name) = current_prop.take() {
-                                if in_set {
-                                    props_to_set.push(PropValue {
-                                        name: prop_name,
-                                        value: if current_text.is_empty() {
-                                            None
-                                        } else {
-                                            Some(current_text.clone())
-                                        },
-                                    });
-                                } else if in_remove {
-                                    props_to_remove.push(prop_name);
+                Event::Text(e) => {
+                    let text = e.unescape().unwrap_or_default().trim().to_string();
+                    let element = open
+                        .last()
+                        .filter(|element| element.namespace == "DAV:")
+                        .map(|element| element.name.as_str());
+                    match element {
+                        Some("sync-token") if !text.is_empty() => sync_token = Some(text),
+                        Some("sync-level") => {
+                            infinite = match text.as_str() {
+                                "1" => false,
+                                "infinite" => true,
+                                other => {
+                                    return Err(WebDavError::ParseError(format!(
+                                        "Invalid sync-level: {}",
+                                        other
+                                    )))
                                 }
                             }
-                            current_text.clear();
                         }
-                        _ => (),
+                        Some("nresults") => {
+                            limit = Some(text.parse().map_err(|_| {
+                                WebDavError::ParseError(format!("Invalid nresults: {}", text))
+                            })?)
+                        }
+                        _ => {}
                     }
                 }
-                Ok(Event::Empty(ref e)) => {
-                    let name = e.name();
-                    let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("");
-
-                    if in_prop {
-                        // Empty property element
-                        let namespace = Self::extract_namespace(name_str);
-                        let prop_name = Self::extract_local_name(name_str);
+                Event::End(_) => {
+                    open.pop();
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buffer.clear();
+        }
+
+        let prop_find_type = match prop_find_type {
+            Some(PropFindType::Prop(_)) => PropFindType::Prop(props),
+            Some(other) => other,
+            None => PropFindType::AllProp,
+        };
+
+        Ok(Some(SyncCollectionRequest {
+            sync_token,
+            infinite,
+            limit,
+            properties: PropFindRequest { prop_find_type },
+        }))
+    }
+
+    /// Href of a member of the WebDAV tree from its storage path
+    pub fn member_href(path: &str, is_folder: bool) -> String {
+        let path = path.trim_matches('/');
+        match (path.is_empty(), is_folder) {
+            (true, _) => "/webdav/".to_string(),
+            (false, true) => format!("/webdav/{}/", path),
+            (false, false) => format!("/webdav/{}", path),
+        }
+    }
+
+    /// Generate the multistatus of a sync-collection REPORT (RFC 6578,
+    /// section 3.2): changed members, removed members and the new token
+    pub fn generate_sync_collection_response<W: Write>(
+        writer: W,
+        result: &SyncCollectionResult,
+        request: &PropFindRequest,
+        context: &PropFindContext,
+    ) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+
+        xml_writer.write_event(Event::Start(
+            BytesStart::new("D:multistatus").with_attributes([("xmlns:D", "DAV:")]),
+        ))?;
+
+        for folder in &result.folders {
+            let href = Self::member_href(&folder.path, true);
+            Self::write_folder_response(&mut xml_writer, folder, request, &href, context)?;
+        }
+        for file in &result.files {
+            let href = Self::member_href(&file.path, false);
+            Self::write_file_response(&mut xml_writer, file, request, &href, context)?;
+        }
+
+        for href in &result.removed {
+            xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
+            xml_writer.write_event(Event::Text(BytesText::new(href)))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
+            xml_writer.write_event(Event::Text(BytesText::new("HTTP/1.1 404 Not Found")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:response")))?;
+        }
+
+        // Tells the client to ask again with the returned token
+        if let Some(href) = &result.truncated {
+            xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
+            xml_writer.write_event(Event::Text(BytesText::new(href)))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
+            xml_writer.write_event(Event::Text(BytesText::new(
+                "HTTP/1.1 507 Insufficient Storage",
+            )))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:error")))?;
+            xml_writer.write_event(Event::Empty(BytesStart::new(
+                "D:number-of-matches-within-limits",
+            )))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:error")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:response")))?;
+        }
+
+        xml_writer.write_event(Event::Start(BytesStart::new("D:sync-token")))?;
+        xml_writer.write_event(Event::Text(BytesText::new(&result.sync_token)))?;
+        xml_writer.write_event(Event::End(BytesEnd::new("D:sync-token")))?;
+
+        xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
+
+        Ok(())
+    }
+
+    /// Parse the body of a SEARCH request holding a `DAV:basicsearch`
+    /// query (RFC 5323, section 5).
+    ///
+    /// Conditions may only refer to `displayname`, `getcontenttype`,
+    /// `getlastmodified` and `getcontentlength`; any other property, operator
+    /// or grammar is rejected
+    pub fn parse_search<R: Read>(reader: R) -> Result<BasicSearch> {
+        let mut xml_reader = NsReader::from_reader(BufReader::new(reader));
+        xml_reader.config_mut().trim_text(true);
+
+        let mut buffer = Vec::new();
+        let mut open: Vec<QualifiedName> = Vec::new();
+        let mut prop_find_type = None;
+        let mut props = Vec::new();
+        let mut scope = None;
+        let mut depth = "infinity".to_string();
+        // Operands of the open and/or/not operators; the first one is `where`
+        let mut groups: Vec<Vec<SearchCondition>> = vec![Vec::new()];
+        // Property and literal of the open comparison
+        let mut operand: (Option<SearchProperty>, String) = (None, String::new());
+        let mut order = Vec::new();
+        let mut ordered_by = None;
+        let mut descending = false;
+        let mut limit = None;
+
+        loop {
+            let event = xml_reader.read_event_into(&mut buffer)?;
+            match &event {
+                Event::Start(e) | Event::Empty(e) => {
+                    let name = Self::resolve_name(&xml_reader, e.name());
+                    let section = open.get(2).map(|section| section.name.as_str());
+                    let parent = open.last().map(|parent| parent.name.as_str());
+                    match (open.len(), section, parent) {
+                        (0, _, _) if name.namespace != "DAV:" || name.name != "searchrequest" => {
+                            return Err(WebDavError::ParseError(
+                                "Expected a searchrequest element".to_string(),
+                            ));
+                        }
+                        (1, _, _) if name.namespace != "DAV:" || name.name != "basicsearch" => {
+                            return Err(WebDavError::ParseError(format!(
+                                "Unsupported query grammar: {}",
+                                name.to_string()
+                            )));
+                        }
+                        (3, Some("select"), _) => match name.name.as_str() {
+                            "allprop" => prop_find_type = Some(PropFindType::AllProp),
+                            "propname" => prop_find_type = Some(PropFindType::PropName),
+                            "prop" => prop_find_type = Some(PropFindType::Prop(Vec::new())),
+                            _ => {}
+                        },
+                        (4, Some("select"), Some("prop")) => props.push(name.clone()),
+                        (_, Some("where"), Some("prop")) | (_, Some("orderby"), Some("prop")) => {
+                            let property = Some(name.clone())
+                                .filter(|name| name.namespace == "DAV:")
+                                .and_then(|name| SearchProperty::from_name(&name.name))
+                                .ok_or_else(|| {
+                                    WebDavError::ParseError(format!(
+                                        "Unsupported search property: {}",
+                                        name.to_string()
+                                    ))
+                                })?;
+                            if section == Some("where") {
+                                operand.0 = Some(property);
+                            } else {
+                                ordered_by = Some(property);
+                            }
+                        }
+                        (_, Some("where"), _) if open.len() > 2 => match name.name.as_str() {
+                            "and" | "or" | "not" if matches!(event, Event::Start(_)) => {
+                                groups.push(Vec::new())
+                            }
+                            "is-collection" => {
+                                if let Some(group) = groups.last_mut() {
+                                    group.push(SearchCondition::IsCollection);
+                                }
+                            }
+                            "and" | "or" | "not" | "prop" | "literal" | "typed-literal" => {}
+                            other if other == "like" || Comparison::from_name(other).is_some() => {
+                                operand = (None, String::new());
+                            }
+                            // is-defined holds for every property that can be searched
+                            "is-defined" => operand = (None, String::new()),
+                            other => {
+                                return Err(WebDavError::ParseError(format!(
+                                    "Unsupported search operator: {}",
+                                    other
+                                )))
+                            }
+                        },
+                        (_, Some("orderby"), Some("order")) if name.name == "descending" => {
+                            descending = true;
+                        }
+                        (_, Some("orderby"), _) if name.name == "order" => {
+                            ordered_by = None;
+                            descending = false;
+                        }
+                        _ => {}
+                    }
+                    if matches!(event, Event::Start(_)) {
+                        open.push(name);
+                    }
+                }
+                Event::Text(e) => {
+                    let text = e.unescape().unwrap_or_default().to_string();
+                    let section = open.get(2).map(|section| section.name.as_str());
+                    match (section, open.last().map(|element| element.name.as_str())) {
+                        (Some("from"), Some("href")) => scope = Some(text.trim().to_string()),
+                        (Some("from"), Some("depth")) => depth = text.trim().to_lowercase(),
+                        (Some("where"), Some("literal" | "typed-literal")) => {
+                            operand.1.push_str(&text)
+                        }
+                        (Some("limit"), Some("nresults")) => {
+                            limit = Some(text.trim().parse().map_err(|_| {
+                                WebDavError::ParseError(format!("Invalid nresults: {}", text))
+                            })?)
+                        }
+                        _ => {}
+                    }
+                }
+                Event::End(_) => {
+                    let section = open.get(2).map(|section| section.name.as_str().to_string());
+                    let Some(element) = open.pop() else {
+                        continue;
+                    };
+                    match section.as_deref() {
+                        Some("where") if open.len() > 2 => {
+                            if let Some(condition) =
+                                Self::close_search_operator(&element.name, &mut groups, &operand)?
+                            {
+                                if let Some(group) = groups.last_mut() {
+                                    group.push(condition);
+                                }
+                            }
+                        }
+                        Some("orderby") if element.name == "order" => {
+                            let property = ordered_by.take().ok_or_else(|| {
+                                WebDavError::ParseError("Missing order property".to_string())
+                            })?;
+                            order.push(SearchOrder {
+                                property,
+                                descending,
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buffer.clear();
+        }
+
+        if !matches!(depth.as_str(), "0" | "1" | "infinity") {
+            return Err(WebDavError::ParseError(format!("Invalid depth: {}", depth)));
+        }
+        let scope =
+            scope.ok_or_else(|| WebDavError::ParseError("Missing search scope".to_string()))?;
+        let prop_find_type = match prop_find_type {
+            Some(PropFindType::Prop(_)) => PropFindType::Prop(props),
+            Some(other) => other,
+            None => PropFindType::AllProp,
+        };
+
+        Ok(BasicSearch {
+            select: PropFindRequest { prop_find_type },
+            scope,
+            depth,
+            condition: groups.pop().and_then(|mut conditions| conditions.pop()),
+            order,
+            limit,
+        })
+    }
+
+    /// Builds the condition of a `where` operator once its element ends
+    fn close_search_operator(
+        operator: &str,
+        groups: &mut Vec<Vec<SearchCondition>>,
+        operand: &(Option<SearchProperty>, String),
+    ) -> Result<Option<SearchCondition>> {
+        let property = || {
+            operand
+                .0
+                .ok_or_else(|| WebDavError::ParseError(format!("Missing property in {}", operator)))
+        };
+        Ok(Some(match operator {
+            "and" | "or" | "not" => {
+                let conditions = groups.pop().unwrap_or_default();
+                match operator {
+                    "and" => SearchCondition::And(conditions),
+                    "or" => SearchCondition::Or(conditions),
+                    _ => match <[SearchCondition; 1]>::try_from(conditions) {
+                        Ok([condition]) => SearchCondition::Not(Box::new(condition)),
+                        Err(_) => {
+                            return Err(WebDavError::ParseError(
+                                "not takes exactly one operand".to_string(),
+                            ))
+                        }
+                    },
+                }
+            }
+            "like" => SearchCondition::Like {
+                property: property()?,
+                pattern: operand.1.clone(),
+            },
+            "is-defined" => {
+                property()?;
+                SearchCondition::And(Vec::new())
+            }
+            other => match Comparison::from_name(other) {
+                Some(comparison) => {
+                    let property = property()?;
+                    let value = property.parse_literal(&operand.1).ok_or_else(|| {
+                        WebDavError::ParseError(format!(
+                            "Invalid literal for {}: {}",
+                            property.name(),
+                            operand.1
+                        ))
+                    })?;
+                    SearchCondition::Compare {
+                        property,
+                        comparison,
+                        value,
+                    }
+                }
+                None => return Ok(None),
+            },
+        }))
+    }
+
+    /// Generate the multistatus of a SEARCH (RFC 5323, section 2.3) with
+    /// one response per result, in order
+    pub fn generate_search_response<W: Write>(
+        writer: W,
+        hits: &[SearchHit],
+        request: &PropFindRequest,
+        context: &PropFindContext,
+    ) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+
+        xml_writer.write_event(Event::Start(
+            BytesStart::new("D:multistatus").with_attributes([("xmlns:D", "DAV:")]),
+        ))?;
+
+        for hit in hits {
+            match hit {
+                SearchHit::Folder(folder) => {
+                    let href = Self::member_href(&folder.path, true);
+                    Self::write_folder_response(&mut xml_writer, folder, request, &href, context)?
+                }
+                SearchHit::File(file) => {
+                    let href = Self::member_href(&file.path, false);
+                    Self::write_file_response(&mut xml_writer, file, request, &href, context)?
+                }
+            }
+        }
+
+        xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
+
+        Ok(())
+    }
+
+    /// Generate a `DAV:error` body naming the precondition that failed
+    pub fn generate_error_response<W: Write>(writer: W, condition: &str) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+        xml_writer.write_event(Event::Start(
+            BytesStart::new("D:error").with_attributes([("xmlns:D", "DAV:")]),
+        ))?;
+        xml_writer.write_event(Event::Empty(BytesStart::new(format!("D:{}", condition))))?;
+        xml_writer.write_event(Event::End(BytesEnd::new("D:error")))?;
+        Ok(())
+    }
+
+    /// Generate the PROPFIND response for a chunked upload in progress: the
+    /// upload collection and, below depth 0, the chunks received so far
+    pub fn generate_upload_chunks_response<W: Write>(
+        writer: W,
+        base_href: &str,
+        chunks: &[UploadedChunk],
+        depth: &str,
+    ) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+        xml_writer.write_event(Event::Start(
+            BytesStart::new("D:multistatus").with_attributes([("xmlns:D", "DAV:")]),
+        ))?;
+
+        let mut members = vec![(base_href.to_string(), None)];
+        if depth != "0" {
+            members.extend(
+                chunks
+                    .iter()
+                    .map(|chunk| (format!("{}{}", base_href, chunk.name), Some(chunk.size))),
+            );
+        }
+        for (href, size) in members {
+            xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
+            xml_writer.write_event(Event::Text(BytesText::new(&href)))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:propstat")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
+            match size {
+                Some(size) => {
+                    xml_writer.write_event(Event::Empty(BytesStart::new("D:resourcetype")))?;
+                    xml_writer.write_event(Event::Start(BytesStart::new("D:getcontentlength")))?;
+                    xml_writer.write_event(Event::Text(BytesText::new(&size.to_string())))?;
+                    xml_writer.write_event(Event::End(BytesEnd::new("D:getcontentlength")))?;
+                }
+                None => {
+                    xml_writer.write_event(Event::Start(BytesStart::new("D:resourcetype")))?;
+                    xml_writer.write_event(Event::Empty(BytesStart::new("D:collection")))?;
+                    xml_writer.write_event(Event::End(BytesEnd::new("D:resourcetype")))?;
+                }
+            }
+            xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
+            xml_writer.write_event(Event::Text(BytesText::new("HTTP/1.1 200 OK")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:propstat")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:response")))?;
+        }
+
+        xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
+        Ok(())
+    }
+
+    /// Parse a PROPPATCH XML request
+    pub fn parse_proppatch<R: Read>(reader: R) -> Result<(Vec<PropValue>, Vec<QualifiedName>)> {
+        let mut xml_reader = NsReader::from_reader(BufReader::new(reader));
+        xml_reader.config_mut().trim_text(true);
+
+        let mut buffer = Vec::new();
+        let mut in_propertyupdate = false;
+        let mut in_set = false;
+        let mut in_remove = false;
+        let mut in_prop = false;
+        let mut current_prop: Option<QualifiedName> = None;
+        let mut props_to_set = Vec::new();
+        let mut props_to_remove = Vec::new();
+        let mut current_text = String::new();
 
-                        let qname = QualifiedName::new(namespace, prop_name);
+        loop {
+            match xml_reader.read_event_into(&mut buffer) {
+                Ok(Event::Start(ref e)) => {
+                    let name = e.name();
+                    let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("");
+
+                    match name_str {
+                        s if s == "propertyupdate" || s.ends_with(":propertyupdate") => {
+                            in_propertyupdate = true
+                        }
+                        s if (in_propertyupdate && (s == "set" || s.ends_with(":set"))) => {
+                            in_set = true
+                        }
+                        s if (in_propertyupdate && (s == "remove" || s.ends_with(":remove"))) => {
+                            in_remove = true
+                        }
+                        s if ((in_set || in_remove) && (s == "prop" || s.ends_with(":prop"))) => {
+                            in_prop = true
+                        }
+                        _ if in_prop => {
+                            // This is a property element
+                            current_prop = Some(Self::resolve_name(&xml_reader, name));
+                            current_text.clear();
+                        }
+                        _ => (),
+                    }
+                }
+                Ok(Event::Text(e)) => {
+                    if current_prop.is_some() {
+                        current_text.push_str(&e.unescape().unwrap_or_default());
+                    }
+                }
+                Ok(Event::End(ref e)) => {
+                    let name = e.name();
+                    let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("");
+
+                    match name_str {
+                        s if s == "propertyupdate" || s.ends_with(":propertyupdate") => {
+                            in_propertyupdate = false
+                        }
+                        s if s == "set" || s.ends_with(":set") => in_set = false,
+                        s if s == "remove" || s.ends_with(":remove") => in_remove = false,
+                        s if s == "prop" || s.ends_with(":prop") => in_prop = false,
+                        _ if in_prop => {
+                            // End of property element
+                            if let Some(prop_name) = current_prop.take() {
+                                if in_set {
+                                    props_to_set.push(PropValue {
+                                        name: prop_name,
+                                        value: if current_text.is_empty() {
+                                            None
+                                        } else {
+                                            Some(current_text.clone())
+                                        },
+                                    });
+                                } else if in_remove {
+                                    props_to_remove.push(prop_name);
+                                }
+                            }
+                            current_text.clear();
+                        }
+                        _ => (),
+                    }
+                }
+                Ok(Event::Empty(ref e)) => {
+                    let name = e.name();
+
+                    if in_prop {
+                        // Empty property element
+                        let qname = Self::resolve_name(&xml_reader, name);
 
                         if in_set {
                             props_to_set.push(PropValue {
@@ -817,7 +1897,7 @@ impl WebDavAdapter {
     pub fn generate_proppatch_response<W: Write>(
         writer: W,
         href: &str,
-        results: &[(&QualifiedName, bool)],
+        results: &[(&QualifiedName, PropPatchStatus)],
     ) -> Result<()> {
         let mut xml_writer = Writer::new(writer);
 
@@ -834,73 +1914,44 @@ impl WebDavAdapter {
         xml_writer.write_event(Event::Text(BytesText::new(href)))?;
         xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
 
-        // Group results by status
-        let mut success_props = Vec::new();
-        let mut failed_props = Vec::new();
-
-        for (prop, success) in results {
-            if *success {
-                success_props.push(prop);
-            } else {
-                failed_props.push(prop);
+        // Write one propstat per status
+        for status in [
+            PropPatchStatus::Ok,
+            PropPatchStatus::Forbidden,
+            PropPatchStatus::FailedDependency,
+        ] {
+            let props: Vec<&QualifiedName> = results
+                .iter()
+                .filter(|(_, result)| *result == status)
+                .map(|(prop, _)| *prop)
+                .collect();
+            if props.is_empty() {
+                continue;
             }
-        }
 
-        // Write successful properties
-        if !success_props.is_empty() {
             xml_writer.write_event(Event::Start(BytesStart::new("D:propstat")))?;
 
-            // Start prop
-            xml_writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
-
             // Write property names
-            for prop in success_props {
-                let prop_name = if prop.namespace == "DAV:" {
-                    format!("D:{}", prop.name)
-                } else {
-                    format!("{}:{}", prop.namespace, prop.name)
-                };
-                xml_writer.write_event(Event::Empty(BytesStart::new(&prop_name)))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
+            for prop in props {
+                Self::write_empty_prop(&mut xml_writer, prop)?;
             }
-
-            // End prop
             xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
 
             // Write status
             xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
-            xml_writer.write_event(Event::Text(BytesText::new("HTTP/1.1 200 OK")))?;
+            xml_writer.write_event(Event::Text(BytesText::new(status.status_line())))?;
             xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
 
-            // End propstat
-            xml_writer.write_event(Event::End(BytesEnd::new("D:propstat")))?;
-        }
-
-        // Write failed properties
-        if !failed_props.is_empty() {
-            xml_writer.write_event(Event::Start(BytesStart::new("D:propstat")))?;
-
-            // Start prop
-            xml_writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
-
-            // Write property names
-            for prop in failed_props {
-                let prop_name = if prop.namespace == "DAV:" {
-                    format!("D:{}", prop.name)
-                } else {
-                    format!("{}:{}", prop.namespace, prop.name)
-                };
-                xml_writer.write_event(Event::Empty(BytesStart::new(&prop_name)))?;
+            // Cannot-modify-protected-property precondition (RFC 4918, section 16)
+            if status == PropPatchStatus::Forbidden {
+                xml_writer.write_event(Event::Start(BytesStart::new("D:error")))?;
+                xml_writer.write_event(Event::Empty(BytesStart::new(
+                    "D:cannot-modify-protected-property",
+                )))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:error")))?;
             }
 
-            // End prop
-            xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
-
-            // Write status
-            xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
-            xml_writer.write_event(Event::Text(BytesText::new("HTTP/1.1 403 Forbidden")))?;
-            xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
-
-            // End propstat
             xml_writer.write_event(Event::End(BytesEnd::new("D:propstat")))?;
         }
 
@@ -1008,11 +2059,7 @@ impl WebDavAdapter {
     }
 
     /// Generate a LOCK response (lockdiscovery)
-    pub fn generate_lock_response<W: Write>(
-        writer: W,
-        lock_info: &LockInfo,
-        href: &str,
-    ) -> Result<()> {
+    pub fn generate_lock_response<W: Write>(writer: W, lock: &WebDavLock) -> Result<()> {
         let mut xml_writer = Writer::new(writer);
 
         // Start prop element (direct response, not multistatus)
@@ -1020,10 +2067,77 @@ impl WebDavAdapter {
             BytesStart::new("D:prop").with_attributes([("xmlns:D", "DAV:")]),
         ))?;
 
-        // Start lockdiscovery
         xml_writer.write_event(Event::Start(BytesStart::new("D:lockdiscovery")))?;
+        Self::write_active_lock(&mut xml_writer, lock)?;
+        xml_writer.write_event(Event::End(BytesEnd::new("D:lockdiscovery")))?;
+
+        xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
+
+        Ok(())
+    }
+
+    /// Path of the resource behind a PROPFIND href, as used by lock roots
+    fn resource_path(href: &str) -> &str {
+        href.strip_prefix("/webdav")
+            .unwrap_or(href)
+            .trim_matches('/')
+    }
+
+    /// Write `lockdiscovery` and `supportedlock` for the resource at `href`
+    fn write_lock_props<W: Write>(
+        xml_writer: &mut Writer<W>,
+        request: &PropFindRequest,
+        href: &str,
+        locks: &[WebDavLock],
+    ) -> Result<()> {
+        let (discovery, supported) = match &request.prop_find_type {
+            PropFindType::AllProp => (true, true),
+            PropFindType::PropName => {
+                xml_writer.write_event(Event::Empty(BytesStart::new("D:lockdiscovery")))?;
+                xml_writer.write_event(Event::Empty(BytesStart::new("D:supportedlock")))?;
+                return Ok(());
+            }
+            PropFindType::Prop(props) => {
+                let requested = |name: &str| {
+                    props
+                        .iter()
+                        .any(|prop| prop.namespace == "DAV:" && prop.name == name)
+                };
+                (requested("lockdiscovery"), requested("supportedlock"))
+            }
+        };
+
+        if discovery {
+            let path = Self::resource_path(href);
+            xml_writer.write_event(Event::Start(BytesStart::new("D:lockdiscovery")))?;
+            for lock in locks.iter().filter(|lock| lock.covers(path)) {
+                Self::write_active_lock(xml_writer, lock)?;
+            }
+            xml_writer.write_event(Event::End(BytesEnd::new("D:lockdiscovery")))?;
+        }
+
+        if supported {
+            xml_writer.write_event(Event::Start(BytesStart::new("D:supportedlock")))?;
+            for scope in ["D:exclusive", "D:shared"] {
+                xml_writer.write_event(Event::Start(BytesStart::new("D:lockentry")))?;
+                xml_writer.write_event(Event::Start(BytesStart::new("D:lockscope")))?;
+                xml_writer.write_event(Event::Empty(BytesStart::new(scope)))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:lockscope")))?;
+                xml_writer.write_event(Event::Start(BytesStart::new("D:locktype")))?;
+                xml_writer.write_event(Event::Empty(BytesStart::new("D:write")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:locktype")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:lockentry")))?;
+            }
+            xml_writer.write_event(Event::End(BytesEnd::new("D:supportedlock")))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a single `activelock` element
+    fn write_active_lock<W: Write>(xml_writer: &mut Writer<W>, lock: &WebDavLock) -> Result<()> {
+        let now = Utc::now().timestamp().max(0) as u64;
 
-        // Start activelock
         xml_writer.write_event(Event::Start(BytesStart::new("D:activelock")))?;
 
         // Write locktype
@@ -1033,7 +2147,7 @@ impl WebDavAdapter {
 
         // Write lockscope
         xml_writer.write_event(Event::Start(BytesStart::new("D:lockscope")))?;
-        match lock_info.scope {
+        match lock.scope {
             LockScope::Exclusive => {
                 xml_writer.write_event(Event::Empty(BytesStart::new("D:exclusive")))?;
             }
@@ -1045,45 +2159,736 @@ impl WebDavAdapter {
 
         // Write depth
         xml_writer.write_event(Event::Start(BytesStart::new("D:depth")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(&lock_info.depth)))?;
+        xml_writer.write_event(Event::Text(BytesText::new(lock.depth.as_str())))?;
         xml_writer.write_event(Event::End(BytesEnd::new("D:depth")))?;
 
-        // Write owner (if provided)
-        if let Some(owner) = &lock_info.owner {
+        // Write owner (if provided), keeping URLs as hrefs
+        if let Some(owner) = &lock.owner {
             xml_writer.write_event(Event::Start(BytesStart::new("D:owner")))?;
-            xml_writer.write_event(Event::Text(BytesText::new(owner)))?;
+            if owner.contains("://") || owner.starts_with("mailto:") {
+                xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
+                xml_writer.write_event(Event::Text(BytesText::new(owner)))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
+            } else {
+                xml_writer.write_event(Event::Text(BytesText::new(owner)))?;
+            }
             xml_writer.write_event(Event::End(BytesEnd::new("D:owner")))?;
         }
 
-        // Write timeout (if provided)
-        if let Some(timeout) = &lock_info.timeout {
-            xml_writer.write_event(Event::Start(BytesStart::new("D:timeout")))?;
-            xml_writer.write_event(Event::Text(BytesText::new(timeout)))?;
-            xml_writer.write_event(Event::End(BytesEnd::new("D:timeout")))?;
-        }
+        // Write remaining timeout
+        xml_writer.write_event(Event::Start(BytesStart::new("D:timeout")))?;
+        xml_writer.write_event(Event::Text(BytesText::new(&format!(
+            "Second-{}",
+            lock.remaining_secs(now)
+        ))))?;
+        xml_writer.write_event(Event::End(BytesEnd::new("D:timeout")))?;
 
         // Write locktoken
         xml_writer.write_event(Event::Start(BytesStart::new("D:locktoken")))?;
         xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(&lock_info.token)))?;
+        xml_writer.write_event(Event::Text(BytesText::new(&lock.token)))?;
         xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
         xml_writer.write_event(Event::End(BytesEnd::new("D:locktoken")))?;
 
         // Write lockroot
         xml_writer.write_event(Event::Start(BytesStart::new("D:lockroot")))?;
         xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
-        xml_writer.write_event(Event::Text(BytesText::new(href)))?;
+        xml_writer.write_event(Event::Text(BytesText::new(&format!(
+            "/webdav/{}",
+            lock.path
+        ))))?;
         xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
         xml_writer.write_event(Event::End(BytesEnd::new("D:lockroot")))?;
 
-        // End activelock, lockdiscovery, and prop
         xml_writer.write_event(Event::End(BytesEnd::new("D:activelock")))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:lockdiscovery")))?;
-        xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
 
         Ok(())
     }
 
+    /// Live properties of RFC 3744 written by `write_acl_props`
+    const ACL_PROPERTIES: [&'static str; 6] = [
+        "current-user-privilege-set",
+        "owner",
+        "current-user-principal",
+        "principal-collection-set",
+        "acl",
+        "supported-privilege-set",
+    ];
+
+    /// Write the access control properties (RFC 3744, section 5) of the
+    /// resource at `href`. Like other computed properties, `allprop` leaves
+    /// them out
+    fn write_acl_props<W: Write>(
+        xml_writer: &mut Writer<W>,
+        request: &PropFindRequest,
+        id: &str,
+        href: &str,
+        context: &PropFindContext,
+    ) -> Result<()> {
+        let props = match &request.prop_find_type {
+            PropFindType::AllProp => return Ok(()),
+            PropFindType::PropName => {
+                for name in Self::ACL_PROPERTIES {
+                    xml_writer.write_event(Event::Empty(BytesStart::new(format!("D:{}", name))))?;
+                }
+                return Ok(());
+            }
+            PropFindType::Prop(props) => props,
+        };
+
+        for prop in props.iter().filter(|prop| prop.namespace == "DAV:") {
+            match prop.name.as_str() {
+                "current-user-privilege-set" => {
+                    // Without access control everybody may do everything
+                    let privileges = context
+                        .privileges
+                        .get(id)
+                        .cloned()
+                        .unwrap_or_else(PrivilegeSet::read_write);
+                    Self::write_privileges(
+                        xml_writer,
+                        "D:current-user-privilege-set",
+                        &privileges,
+                    )?;
+                }
+                "owner" => {
+                    let owner = resource_owner(Self::resource_path(href))
+                        .map(|owner| Principal::href_for(&owner));
+                    Self::write_href_prop(xml_writer, "D:owner", owner.as_deref())?;
+                }
+                "current-user-principal" => Self::write_href_prop(
+                    xml_writer,
+                    "D:current-user-principal",
+                    context.principal.as_deref(),
+                )?,
+                "principal-collection-set" => Self::write_href_prop(
+                    xml_writer,
+                    "D:principal-collection-set",
+                    Some(USER_PRINCIPALS_PATH),
+                )?,
+                "acl" => {
+                    let acl = context.acls.get(id).map(Vec::as_slice).unwrap_or(&[]);
+                    Self::write_acl(xml_writer, acl)?;
+                }
+                "supported-privilege-set" => {
+                    xml_writer
+                        .write_event(Event::Start(BytesStart::new("D:supported-privilege-set")))?;
+                    Self::write_supported_privilege(xml_writer, &SUPPORTED_PRIVILEGES)?;
+                    xml_writer
+                        .write_event(Event::End(BytesEnd::new("D:supported-privilege-set")))?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write a property holding a single href, or an empty one without it
+    pub(crate) fn write_href_prop<W: Write>(
+        xml_writer: &mut Writer<W>,
+        element: &str,
+        href: Option<&str>,
+    ) -> Result<()> {
+        match href {
+            Some(href) => {
+                xml_writer.write_event(Event::Start(BytesStart::new(element)))?;
+                xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
+                xml_writer.write_event(Event::Text(BytesText::new(href)))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new(element)))?;
+            }
+            None => xml_writer.write_event(Event::Empty(BytesStart::new(element)))?,
+        }
+        Ok(())
+    }
+
+    /// Write a set of privileges as `privilege` elements inside `element`
+    pub(crate) fn write_privileges<W: Write>(
+        xml_writer: &mut Writer<W>,
+        element: &str,
+        privileges: &PrivilegeSet,
+    ) -> Result<()> {
+        xml_writer.write_event(Event::Start(BytesStart::new(element)))?;
+        for privilege in privileges.reported() {
+            xml_writer.write_event(Event::Start(BytesStart::new("D:privilege")))?;
+            xml_writer.write_event(Event::Empty(BytesStart::new(format!(
+                "D:{}",
+                privilege.name()
+            ))))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:privilege")))?;
+        }
+        xml_writer.write_event(Event::End(BytesEnd::new(element)))?;
+        Ok(())
+    }
+
+    /// Write the `acl` property; every entry is a grant the server derives
+    /// from ownership and shares, so all of them are protected
+    fn write_acl<W: Write>(xml_writer: &mut Writer<W>, acl: &[AccessControlEntry]) -> Result<()> {
+        xml_writer.write_event(Event::Start(BytesStart::new("D:acl")))?;
+        for entry in acl {
+            xml_writer.write_event(Event::Start(BytesStart::new("D:ace")))?;
+            match &entry.principal {
+                AclPrincipal::User(name) => Self::write_href_prop(
+                    xml_writer,
+                    "D:principal",
+                    Some(&Principal::href_for(name)),
+                )?,
+                AclPrincipal::Authenticated => {
+                    xml_writer.write_event(Event::Start(BytesStart::new("D:principal")))?;
+                    xml_writer.write_event(Event::Empty(BytesStart::new("D:authenticated")))?;
+                    xml_writer.write_event(Event::End(BytesEnd::new("D:principal")))?;
+                }
+            }
+            Self::write_privileges(xml_writer, "D:grant", &entry.grant)?;
+            xml_writer.write_event(Event::Empty(BytesStart::new("D:protected")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:ace")))?;
+        }
+        xml_writer.write_event(Event::End(BytesEnd::new("D:acl")))?;
+        Ok(())
+    }
+
+    /// Write a `supported-privilege` element and the privileges it aggregates
+    fn write_supported_privilege<W: Write>(
+        xml_writer: &mut Writer<W>,
+        privilege: &SupportedPrivilege,
+    ) -> Result<()> {
+        xml_writer.write_event(Event::Start(BytesStart::new("D:supported-privilege")))?;
+        xml_writer.write_event(Event::Start(BytesStart::new("D:privilege")))?;
+        xml_writer.write_event(Event::Empty(BytesStart::new(format!(
+            "D:{}",
+            privilege.name
+        ))))?;
+        xml_writer.write_event(Event::End(BytesEnd::new("D:privilege")))?;
+        xml_writer.write_event(Event::Start(
+            BytesStart::new("D:description").with_attributes([("xml:lang", "en")]),
+        ))?;
+        xml_writer.write_event(Event::Text(BytesText::new(privilege.description)))?;
+        xml_writer.write_event(Event::End(BytesEnd::new("D:description")))?;
+        for aggregated in privilege.aggregates {
+            Self::write_supported_privilege(xml_writer, aggregated)?;
+        }
+        xml_writer.write_event(Event::End(BytesEnd::new("D:supported-privilege")))?;
+        Ok(())
+    }
+
+    /// Parse a REPORT body, returning `None` for reports other than
+    /// `DAV:acl-principal-prop-set` and `DAV:principal-property-search`.
+    ///
+    /// Search criteria on properties that principals do not have are left
+    /// out, and every match is a case-insensitive substring match
+    pub fn parse_acl_report<R: Read>(reader: R) -> Result<Option<AclReport>> {
+        let mut xml_reader = NsReader::from_reader(BufReader::new(reader));
+        xml_reader.config_mut().trim_text(true);
+
+        let mut buffer = Vec::new();
+        let mut open: Vec<QualifiedName> = Vec::new();
+        let mut search = false;
+        let mut all_of = false;
+        let mut criteria = Vec::new();
+        let mut searched_property = None;
+        let mut prop_find_type = None;
+        let mut props = Vec::new();
+
+        loop {
+            let event = xml_reader.read_event_into(&mut buffer)?;
+            match &event {
+                Event::Start(e) | Event::Empty(e) => {
+                    let name = Self::resolve_name(&xml_reader, e.name());
+                    let parent = open.last().filter(|parent| parent.namespace == "DAV:");
+                    match (open.len(), parent.map(|parent| parent.name.as_str())) {
+                        (0, _) => match (name.namespace.as_str(), name.name.as_str()) {
+                            ("DAV:", "acl-principal-prop-set") => {}
+                            ("DAV:", "principal-property-search") => {
+                                search = true;
+                                all_of = e
+                                    .attributes()
+                                    .flatten()
+                                    .find(|attribute| attribute.key.as_ref() == b"test")
+                                    .is_some_and(|attribute| attribute.value.as_ref() == b"allof");
+                            }
+                            _ => return Ok(None),
+                        },
+                        (1, _) if name.namespace == "DAV:" => match name.name.as_str() {
+                            "allprop" => prop_find_type = Some(PropFindType::AllProp),
+                            "propname" => prop_find_type = Some(PropFindType::PropName),
+                            "prop" => prop_find_type = Some(PropFindType::Prop(Vec::new())),
+                            _ => {}
+                        },
+                        (2, Some("prop")) => props.push(name.clone()),
+                        // A property inside property-search/prop
+                        (3, Some("prop")) => {
+                            searched_property = match (name.namespace.as_str(), name.name.as_str())
+                            {
+                                ("DAV:", "displayname") => Some(PrincipalProperty::DisplayName),
+                                (_, "calendar-user-address-set" | "email-address-set") => {
+                                    Some(PrincipalProperty::Email)
+                                }
+                                _ => None,
+                            }
+                        }
+                        _ => {}
+                    }
+                    if matches!(event, Event::Start(_)) {
+                        open.push(name);
+                    }
+                }
+                Event::Text(e) => {
+                    let in_match = open.last().is_some_and(|element| {
+                        element.namespace == "DAV:" && element.name == "match"
+                    });
+                    if let (true, Some(property)) = (in_match, searched_property) {
+                        let text = e.unescape().unwrap_or_default().trim().to_string();
+                        // Address searches often come as mailto: URIs
+                        let text = text.strip_prefix("mailto:").unwrap_or(&text).to_string();
+                        criteria.push(PrincipalMatch { property, text });
+                    }
+                }
+                Event::End(_) => {
+                    let closed = open.pop();
+                    if closed.is_some_and(|element| element.name == "property-search") {
+                        searched_property = None;
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buffer.clear();
+        }
+
+        let prop_find_type = match prop_find_type {
+            Some(PropFindType::Prop(_)) => PropFindType::Prop(props),
+            Some(other) => other,
+            None => PropFindType::AllProp,
+        };
+        let properties = PropFindRequest { prop_find_type };
+
+        Ok(Some(if search {
+            AclReport::PrincipalPropertySearch {
+                criteria,
+                all_of,
+                properties,
+            }
+        } else {
+            AclReport::PrincipalPropSet(properties)
+        }))
+    }
+
+    /// Properties of principal resources (RFC 3744, section 4)
+    const PRINCIPAL_PROPERTIES: [&'static str; 10] = [
+        "resourcetype",
+        "displayname",
+        "principal-URL",
+        "alternate-URI-set",
+        "group-membership",
+        "owner",
+        "current-user-principal",
+        "principal-collection-set",
+        "current-user-privilege-set",
+        "supported-report-set",
+    ];
+
+    /// Indicates whether a principal resource has a property
+    fn principal_has_property(resource: &PrincipalResource, prop: &QualifiedName) -> bool {
+        let is_user = matches!(resource, PrincipalResource::User(_));
+        if prop.namespace == CALDAV_NS {
+            // Calendar users (RFC 4791, section 6.2) and their scheduling
+            // collections (RFC 6638, section 2)
+            return is_user
+                && matches!(
+                    prop.name.as_str(),
+                    "calendar-home-set"
+                        | "calendar-user-address-set"
+                        | "calendar-user-type"
+                        | "schedule-inbox-URL"
+                        | "schedule-outbox-URL"
+                );
+        }
+        prop.namespace == "DAV:"
+            && match prop.name.as_str() {
+                "principal-URL" | "alternate-URI-set" | "group-membership" => is_user,
+                name => Self::PRINCIPAL_PROPERTIES.contains(&name),
+            }
+    }
+
+    /// Generate the multistatus for principal resources: the requested
+    /// properties they have, and a 404 propstat for the rest
+    pub fn generate_principal_response<W: Write>(
+        writer: W,
+        resources: &[PrincipalResource],
+        request: &PropFindRequest,
+        current_principal: &str,
+    ) -> Result<()> {
+        let mut xml_writer = Writer::new(writer);
+
+        xml_writer.write_event(Event::Start(
+            BytesStart::new("D:multistatus")
+                .with_attributes([("xmlns:D", "DAV:"), ("xmlns:C", CALDAV_NS)]),
+        ))?;
+
+        for resource in resources {
+            let href = match resource {
+                PrincipalResource::Collection { href, .. } => href.clone(),
+                PrincipalResource::User(principal) => principal.href(),
+            };
+            let requested: Vec<QualifiedName> = match &request.prop_find_type {
+                PropFindType::AllProp => ["resourcetype", "displayname", "principal-URL"]
+                    .into_iter()
+                    .map(|name| QualifiedName::new("DAV:", name))
+                    .collect(),
+                PropFindType::PropName => Self::PRINCIPAL_PROPERTIES
+                    .into_iter()
+                    .map(|name| QualifiedName::new("DAV:", name))
+                    .collect(),
+                PropFindType::Prop(props) => props.clone(),
+            };
+            let (found, missing): (Vec<_>, Vec<_>) = requested
+                .into_iter()
+                .partition(|prop| Self::principal_has_property(resource, prop));
+
+            xml_writer.write_event(Event::Start(BytesStart::new("D:response")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:href")))?;
+            xml_writer.write_event(Event::Text(BytesText::new(&href)))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:href")))?;
+
+            xml_writer.write_event(Event::Start(BytesStart::new("D:propstat")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
+            for prop in &found {
+                if request.prop_find_type == PropFindType::PropName {
+                    Self::write_empty_prop(&mut xml_writer, prop)?;
+                } else if let (CALDAV_NS, PrincipalResource::User(principal)) =
+                    (prop.namespace.as_str(), resource)
+                {
+                    Self::write_calendar_user_prop(&mut xml_writer, principal, &prop.name)?;
+                } else {
+                    Self::write_principal_prop(
+                        &mut xml_writer,
+                        resource,
+                        &href,
+                        &prop.name,
+                        current_principal,
+                    )?;
+                }
+            }
+            xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
+            xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
+            xml_writer.write_event(Event::Text(BytesText::new("HTTP/1.1 200 OK")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
+            xml_writer.write_event(Event::End(BytesEnd::new("D:propstat")))?;
+
+            if !missing.is_empty() {
+                xml_writer.write_event(Event::Start(BytesStart::new("D:propstat")))?;
+                xml_writer.write_event(Event::Start(BytesStart::new("D:prop")))?;
+                for prop in &missing {
+                    Self::write_empty_prop(&mut xml_writer, prop)?;
+                }
+                xml_writer.write_event(Event::End(BytesEnd::new("D:prop")))?;
+                xml_writer.write_event(Event::Start(BytesStart::new("D:status")))?;
+                xml_writer.write_event(Event::Text(BytesText::new("HTTP/1.1 404 Not Found")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:status")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:propstat")))?;
+            }
+
+            xml_writer.write_event(Event::End(BytesEnd::new("D:response")))?;
+        }
+
+        xml_writer.write_event(Event::End(BytesEnd::new("D:multistatus")))?;
+
+        Ok(())
+    }
+
+    /// Write a CalDAV property of a user principal
+    fn write_calendar_user_prop<W: Write>(
+        xml_writer: &mut Writer<W>,
+        principal: &Principal,
+        name: &str,
+    ) -> Result<()> {
+        let element = format!("C:{}", name);
+        match name {
+            "calendar-home-set" => Self::write_href_prop(
+                xml_writer,
+                &element,
+                Some(&Principal::calendar_home_for(&principal.name)),
+            ),
+            "schedule-inbox-URL" => Self::write_href_prop(
+                xml_writer,
+                &element,
+                Some(&Principal::schedule_inbox_for(&principal.name)),
+            ),
+            "schedule-outbox-URL" => Self::write_href_prop(
+                xml_writer,
+                &element,
+                Some(&Principal::schedule_outbox_for(&principal.name)),
+            ),
+            "calendar-user-type" => {
+                xml_writer.write_event(Event::Start(BytesStart::new(element.as_str())))?;
+                xml_writer.write_event(Event::Text(BytesText::new("INDIVIDUAL")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new(element.as_str())))?;
+                Ok(())
+            }
+            _ => {
+                let mailto = principal
+                    .email
+                    .as_ref()
+                    .map(|email| format!("mailto:{}", email));
+                Self::write_href_prop(xml_writer, &element, mailto.as_deref())
+            }
+        }
+    }
+
+    /// Write a DAV: property of a principal resource
+    fn write_principal_prop<W: Write>(
+        xml_writer: &mut Writer<W>,
+        resource: &PrincipalResource,
+        href: &str,
+        name: &str,
+        current_principal: &str,
+    ) -> Result<()> {
+        let element = format!("D:{}", name);
+        match (name, resource) {
+            ("resourcetype", PrincipalResource::Collection { .. }) => {
+                xml_writer.write_event(Event::Start(BytesStart::new("D:resourcetype")))?;
+                xml_writer.write_event(Event::Empty(BytesStart::new("D:collection")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:resourcetype")))?;
+            }
+            ("resourcetype", PrincipalResource::User(_)) => {
+                xml_writer.write_event(Event::Start(BytesStart::new("D:resourcetype")))?;
+                xml_writer.write_event(Event::Empty(BytesStart::new("D:principal")))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:resourcetype")))?;
+            }
+            ("displayname", resource) => {
+                let display_name = match resource {
+                    PrincipalResource::Collection { name, .. } => name,
+                    PrincipalResource::User(principal) => &principal.display_name,
+                };
+                xml_writer.write_event(Event::Start(BytesStart::new("D:displayname")))?;
+                xml_writer.write_event(Event::Text(BytesText::new(display_name)))?;
+                xml_writer.write_event(Event::End(BytesEnd::new("D:displayname")))?;
+            }
+            ("principal-URL", _) => Self::write_href_prop(xml_writer, &element, Some(href))?,
+            ("alternate-URI-set", PrincipalResource::User(principal)) => {
+                let mailto = principal
+                    .email
+                    .as_ref()
+                    .map(|email| format!("mailto:{}", email));
+                Self::write_href_prop(xml_writer, &element, mailto.as_deref())?;
+            }
+            // Principals are all owned by themselves; collections by nobody
+            ("owner", PrincipalResource::User(_)) => {
+                Self::write_href_prop(xml_writer, &element, Some(href))?
+            }
+            ("current-user-principal", _) => {
+                Self::write_href_prop(xml_writer, &element, Some(current_principal))?
+            }
+            ("principal-collection-set", _) => {
+                Self::write_href_prop(xml_writer, &element, Some(USER_PRINCIPALS_PATH))?
+            }
+            // Every user may look up the others, but nobody changes them here
+            ("current-user-privilege-set", _) => {
+                Self::write_privileges(xml_writer, &element, &PrivilegeSet::read())?
+            }
+            ("supported-report-set", _) => {
+                xml_writer.write_event(Event::Start(BytesStart::new("D:supported-report-set")))?;
+                for report in ["D:principal-property-search", "D:acl-principal-prop-set"] {
+                    xml_writer.write_event(Event::Start(BytesStart::new("D:supported-report")))?;
+                    xml_writer.write_event(Event::Start(BytesStart::new("D:report")))?;
+                    xml_writer.write_event(Event::Empty(BytesStart::new(report)))?;
+                    xml_writer.write_event(Event::End(BytesEnd::new("D:report")))?;
+                    xml_writer.write_event(Event::End(BytesEnd::new("D:supported-report")))?;
+                }
+                xml_writer.write_event(Event::End(BytesEnd::new("D:supported-report-set")))?;
+            }
+            // Memberships, and the owner of a collection, are empty
+            _ => xml_writer.write_event(Event::Empty(BytesStart::new(element)))?,
+        }
+        Ok(())
+    }
+
+    /// Parse an `If` request header (RFC 4918, section 10.4)
+    pub fn parse_if_header(value: &str) -> Result<IfHeader> {
+        let invalid = || WebDavError::ParseError(format!("Invalid If header: {}", value));
+        let mut header = IfHeader::default();
+        let mut resource: Option<String> = None;
+        let mut rest = value.trim_start();
+
+        // Reads up to `close`, returning the enclosed text and what follows
+        let enclosed = |input: &str, close: char| -> Option<(String, usize)> {
+            input[1..]
+                .find(close)
+                .map(|end| (input[1..end + 1].to_string(), end + 2))
+        };
+
+        while !rest.is_empty() {
+            if rest.starts_with('<') {
+                // Resource tag for the lists that follow
+                let (tag, used) = enclosed(rest, '>').ok_or_else(invalid)?;
+                resource = Some(tag);
+                rest = rest[used..].trim_start();
+            } else if rest.starts_with('(') {
+                let mut conditions = Vec::new();
+                rest = rest[1..].trim_start();
+                loop {
+                    let mut negated = false;
+                    if rest.get(..3).is_some_and(|w| w.eq_ignore_ascii_case("not")) {
+                        negated = true;
+                        rest = rest[3..].trim_start();
+                    }
+                    if rest.starts_with('<') {
+                        let (token, used) = enclosed(rest, '>').ok_or_else(invalid)?;
+                        conditions.push((negated, IfCondition::Token(token)));
+                        rest = rest[used..].trim_start();
+                    } else if rest.starts_with('[') {
+                        let (etag, used) = enclosed(rest, ']').ok_or_else(invalid)?;
+                        conditions.push((negated, IfCondition::ETag(etag)));
+                        rest = rest[used..].trim_start();
+                    } else if rest.starts_with(')') && !negated && !conditions.is_empty() {
+                        rest = rest[1..].trim_start();
+                        break;
+                    } else {
+                        return Err(invalid());
+                    }
+                }
+                header.lists.push(IfList {
+                    resource: resource.clone(),
+                    conditions,
+                });
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        if header.lists.is_empty() {
+            return Err(invalid());
+        }
+        Ok(header)
+    }
+
+    /// Indicates whether a property is computed by the server and cannot be
+    /// set or removed with PROPPATCH
+    pub fn is_protected_property(prop: &QualifiedName) -> bool {
+        if matches!(
+            (prop.namespace.as_str(), prop.name.as_str()),
+            (OWNCLOUD_NS, "fileid" | "id" | "size" | "permissions") | (NEXTCLOUD_NS, "has-preview")
+        ) {
+            return true;
+        }
+        prop.namespace == "DAV:"
+            && (matches!(
+                prop.name.as_str(),
+                "creationdate"
+                    | "displayname"
+                    | "getcontentlength"
+                    | "getcontenttype"
+                    | "getetag"
+                    | "getlastmodified"
+                    | "lockdiscovery"
+                    | "resourcetype"
+                    | "supportedlock"
+                    | "supported-report-set"
+                    | "supported-query-grammar-set"
+                    | "sync-token"
+                    | "quota-available-bytes"
+                    | "quota-used-bytes"
+            ) || Self::ACL_PROPERTIES.contains(&prop.name.as_str()))
+    }
+
+    /// Resolves an element name to its namespace URI and local name
+    pub(crate) fn resolve_name<R>(xml_reader: &NsReader<R>, name: QName) -> QualifiedName {
+        let (resolved, local_name) = xml_reader.resolve_element(name);
+        let namespace = match resolved {
+            ResolveResult::Bound(Namespace(namespace)) => {
+                String::from_utf8_lossy(namespace).into_owned()
+            }
+            // Some clients omit the namespace declaration for DAV: properties
+            ResolveResult::Unbound => "DAV:".to_string(),
+            ResolveResult::Unknown(prefix) => String::from_utf8_lossy(&prefix).into_owned(),
+        };
+        QualifiedName::new(
+            namespace,
+            String::from_utf8_lossy(local_name.as_ref()).into_owned(),
+        )
+    }
+
+    /// Dead properties of a resource in a PROPFIND response
+    fn dead_properties_of<'a>(
+        properties: &'a HashMap<String, Vec<DeadProperty>>,
+        resource_id: &str,
+    ) -> &'a [DeadProperty] {
+        properties
+            .get(resource_id)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    fn dead_property_name(property: &DeadProperty) -> QualifiedName {
+        QualifiedName::new(property.namespace.clone(), property.name.clone())
+    }
+
+    /// Element for a property, declaring its namespace when it is not DAV:
+    pub(crate) fn prop_element(prop: &QualifiedName) -> (BytesStart<'static>, String) {
+        if prop.namespace == "DAV:" {
+            let name = format!("D:{}", prop.name);
+            (BytesStart::new(name.clone()), name)
+        } else if prop.namespace.is_empty() {
+            (
+                BytesStart::new(prop.name.clone()).with_attributes([("xmlns", "")]),
+                prop.name.clone(),
+            )
+        } else {
+            let name = format!("x:{}", prop.name);
+            (
+                BytesStart::new(name.clone())
+                    .with_attributes([("xmlns:x", prop.namespace.as_str())]),
+                name,
+            )
+        }
+    }
+
+    /// Write a property as an empty element
+    pub(crate) fn write_empty_prop<W: Write>(
+        xml_writer: &mut Writer<W>,
+        prop: &QualifiedName,
+    ) -> Result<()> {
+        let (start, _) = Self::prop_element(prop);
+        xml_writer.write_event(Event::Empty(start))?;
+        Ok(())
+    }
+
+    /// Write dead properties with their values
+    fn write_dead_props<W: Write>(
+        xml_writer: &mut Writer<W>,
+        properties: &[DeadProperty],
+    ) -> Result<()> {
+        for property in properties {
+            let (start, end) = Self::prop_element(&Self::dead_property_name(property));
+            match &property.value {
+                Some(value) => {
+                    xml_writer.write_event(Event::Start(start))?;
+                    xml_writer.write_event(Event::Text(BytesText::new(value)))?;
+                    xml_writer.write_event(Event::End(BytesEnd::new(end)))?;
+                }
+                None => xml_writer.write_event(Event::Empty(start))?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a requested property that is not a live one: its dead value if
+    /// the resource has it, or an empty element otherwise
+    fn write_dead_prop_or_empty<W: Write>(
+        xml_writer: &mut Writer<W>,
+        prop: &QualifiedName,
+        properties: &[DeadProperty],
+    ) -> Result<()> {
+        match properties
+            .iter()
+            .find(|property| property.is_named(&prop.namespace, &prop.name))
+        {
+            Some(property) => Self::write_dead_props(xml_writer, std::slice::from_ref(property)),
+            None => Self::write_empty_prop(xml_writer, prop),
+        }
+    }
+
     /// Helper method to extract namespace from tag name
     pub fn extract_namespace(name: &str) -> String {
         if let Some(idx) = name.rfind(':') {
@@ -1,10 +1,21 @@
 use crate::domain::entities::calendar::Calendar;
 use crate::domain::entities::calendar_event::CalendarEvent;
+use crate::domain::entities::calendar_task::CalendarTask;
+use crate::domain::entities::icalendar::ICalProperty;
+use crate::domain::entities::recurrence::Occurrence;
+use crate::domain::entities::reminder::ReminderNotification;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+/// Calendar property listing the components a calendar can hold, as
+/// comma-separated names; calendars without it hold events and tasks
+pub const SUPPORTED_COMPONENTS_PROPERTY: &str = "_supported-components";
+
+/// Components a calendar holds unless it was created with a narrower set
+pub const DEFAULT_COMPONENTS: [&str; 2] = ["VEVENT", "VTODO"];
+
 /// DTO for calendar data transfer
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CalendarDto {
@@ -35,6 +46,27 @@ impl Default for CalendarDto {
     }
 }
 
+impl CalendarDto {
+    /// Components the calendar can hold, such as `VEVENT` or `VTODO`
+    pub fn supported_components(&self) -> Vec<String> {
+        match self.custom_properties.get(SUPPORTED_COMPONENTS_PROPERTY) {
+            Some(components) => components
+                .split(',')
+                .map(|component| component.trim().to_ascii_uppercase())
+                .filter(|component| !component.is_empty())
+                .collect(),
+            None => DEFAULT_COMPONENTS.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Indicates whether the calendar can hold a component
+    pub fn supports_component(&self, component: &str) -> bool {
+        self.supported_components()
+            .iter()
+            .any(|supported| supported.eq_ignore_ascii_case(component))
+    }
+}
+
 impl From<Calendar> for CalendarDto {
     fn from(calendar: Calendar) -> Self {
         Self {
@@ -54,6 +86,9 @@ impl From<Calendar> for CalendarDto {
 /// DTO for calendar creation
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateCalendarDto {
+    /// Identifier chosen by the client (e.g. the MKCALENDAR URL segment)
+    #[serde(default)]
+    pub id: Option<String>,
     pub name: String,
     pub description: Option<String>,
     pub color: Option<String>,
@@ -88,12 +123,51 @@ pub struct CalendarEventDto {
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
     pub all_day: bool,
+    /// Time zone of the event's local times, if any
+    #[serde(default)]
+    pub timezone: Option<String>,
     pub rrule: Option<String>,
     pub ical_uid: String,
+    pub ical_data: String,
+    /// Original start of this instance when the event is expanded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_id: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl CalendarEventDto {
+    /// Copy of the event for one instance of its recurrence set, taking the
+    /// texts from the RECURRENCE-ID override when there is one
+    pub fn instance(&self, occurrence: &Occurrence) -> Self {
+        let component = occurrence.component;
+        let (summary, description, location) = if component.property("RECURRENCE-ID").is_some() {
+            let text = |name: &str| component.property(name).map(ICalProperty::text);
+            (
+                text("SUMMARY").unwrap_or_else(|| self.summary.clone()),
+                text("DESCRIPTION"),
+                text("LOCATION"),
+            )
+        } else {
+            (
+                self.summary.clone(),
+                self.description.clone(),
+                self.location.clone(),
+            )
+        };
+
+        Self {
+            summary,
+            description,
+            location,
+            start_time: occurrence.start,
+            end_time: occurrence.end,
+            recurrence_id: occurrence.recurrence_id,
+            ..self.clone()
+        }
+    }
+}
+
 impl Default for CalendarEventDto {
     fn default() -> Self {
         Self {
@@ -105,8 +179,11 @@ impl Default for CalendarEventDto {
             start_time: Utc::now(),
             end_time: Utc::now(),
             all_day: false,
+            timezone: None,
             rrule: None,
             ical_uid: String::new(),
+            ical_data: String::new(),
+            recurrence_id: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -124,8 +201,11 @@ impl From<CalendarEvent> for CalendarEventDto {
             start_time: *event.start_time(),
             end_time: *event.end_time(),
             all_day: event.all_day(),
+            timezone: event.timezone().map(|s| s.to_string()),
             rrule: event.rrule().map(|s| s.to_string()),
             ical_uid: event.ical_uid().to_string(),
+            ical_data: event.ical_data().to_string(),
+            recurrence_id: None,
             created_at: *event.created_at(),
             updated_at: *event.updated_at(),
         }
@@ -150,6 +230,9 @@ pub struct CreateEventDto {
     pub end_time: DateTime<Utc>,
     pub all_day: Option<bool>,
     pub rrule: Option<String>,
+    /// Time zone to write the times in, so recurrences follow its DST rules
+    #[serde(default)]
+    pub timezone: Option<String>,
     pub user_id: String, // Added for authorization
 }
 
@@ -166,6 +249,196 @@ pub struct UpdateEventDto {
     pub user_id: String, // Added for authorization
 }
 
+/// DTO for calendar task data transfer
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CalendarTaskDto {
+    pub id: String,
+    pub calendar_id: String,
+    pub summary: String,
+    pub description: Option<String>,
+    /// `NEEDS-ACTION`, `IN-PROCESS`, `COMPLETED` or `CANCELLED`
+    pub status: String,
+    pub priority: Option<u8>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub due_time: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub percent_complete: Option<u8>,
+    /// UID of the parent task when this is a subtask
+    pub parent_uid: Option<String>,
+    pub rrule: Option<String>,
+    pub timezone: Option<String>,
+    pub ical_uid: String,
+    pub ical_data: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<CalendarTask> for CalendarTaskDto {
+    fn from(task: CalendarTask) -> Self {
+        Self {
+            id: task.id().to_string(),
+            calendar_id: task.calendar_id().to_string(),
+            summary: task.summary().to_string(),
+            description: task.description().map(|s| s.to_string()),
+            status: task.status().as_str().to_string(),
+            priority: task.priority(),
+            start_time: task.start_time().copied(),
+            due_time: task.due_time().copied(),
+            completed_at: task.completed_at().copied(),
+            percent_complete: task.percent_complete(),
+            parent_uid: task.parent_uid().map(|s| s.to_string()),
+            rrule: task.rrule().map(|s| s.to_string()),
+            timezone: task.timezone().map(|s| s.to_string()),
+            ical_uid: task.ical_uid().to_string(),
+            ical_data: task.ical_data().to_string(),
+            created_at: *task.created_at(),
+            updated_at: *task.updated_at(),
+        }
+    }
+}
+
+/// DTO for calendar task creation with structured data
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTaskDto {
+    pub summary: String,
+    pub description: Option<String>,
+    /// Defaults to `NEEDS-ACTION`
+    pub status: Option<String>,
+    pub priority: Option<u8>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub due_time: Option<DateTime<Utc>>,
+    pub percent_complete: Option<u8>,
+    /// UID of the parent task, to create a subtask
+    pub parent_uid: Option<String>,
+    pub rrule: Option<String>,
+    /// Time zone to write the times in, so recurrences follow its DST rules
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+/// DTO for updating a calendar task; empty strings clear the description,
+/// parent and recurrence rule
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateTaskDto {
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<String>,
+    pub priority: Option<u8>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub due_time: Option<DateTime<Utc>>,
+    pub percent_complete: Option<u8>,
+    pub parent_uid: Option<String>,
+    pub rrule: Option<String>,
+}
+
+/// A calendar object resource: an event or a task
+#[derive(Debug, Clone)]
+pub enum CalendarObjectDto {
+    Event(CalendarEventDto),
+    Task(CalendarTaskDto),
+}
+
+impl CalendarObjectDto {
+    pub fn id(&self) -> &str {
+        match self {
+            CalendarObjectDto::Event(event) => &event.id,
+            CalendarObjectDto::Task(task) => &task.id,
+        }
+    }
+
+    pub fn ical_uid(&self) -> &str {
+        match self {
+            CalendarObjectDto::Event(event) => &event.ical_uid,
+            CalendarObjectDto::Task(task) => &task.ical_uid,
+        }
+    }
+
+    pub fn ical_data(&self) -> &str {
+        match self {
+            CalendarObjectDto::Event(event) => &event.ical_data,
+            CalendarObjectDto::Task(task) => &task.ical_data,
+        }
+    }
+
+    pub fn timezone(&self) -> Option<&str> {
+        match self {
+            CalendarObjectDto::Event(event) => event.timezone.as_deref(),
+            CalendarObjectDto::Task(task) => task.timezone.as_deref(),
+        }
+    }
+
+    pub fn updated_at(&self) -> &DateTime<Utc> {
+        match self {
+            CalendarObjectDto::Event(event) => &event.updated_at,
+            CalendarObjectDto::Task(task) => &task.updated_at,
+        }
+    }
+
+    /// Name of the iCalendar component the object holds
+    pub fn component(&self) -> &'static str {
+        match self {
+            CalendarObjectDto::Event(_) => "VEVENT",
+            CalendarObjectDto::Task(_) => "VTODO",
+        }
+    }
+}
+
+/// Outcome of delivering a scheduling message to one recipient
+/// (RFC 6638, section 3.2.9)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduleResponseDto {
+    /// Calendar user address of the recipient, e.g. `mailto:bob@example.com`
+    pub recipient: String,
+    /// iTIP request status, e.g. `1.2;Delivered`
+    pub request_status: String,
+    /// Answer of the recipient, for free-busy requests
+    pub calendar_data: Option<String>,
+}
+
+/// In-app notification of a reminder that fired
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReminderNotificationDto {
+    pub id: String,
+    pub calendar_id: String,
+    /// Event or task holding the alarm
+    pub object_id: String,
+    /// `VEVENT` or `VTODO`
+    pub component: String,
+    pub summary: String,
+    pub description: Option<String>,
+    /// Alarm action, `DISPLAY` or `AUDIO`
+    pub action: String,
+    pub trigger_at: DateTime<Utc>,
+    /// Start of the instance the reminder is about
+    pub occurrence_start: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ReminderNotification> for ReminderNotificationDto {
+    fn from(notification: ReminderNotification) -> Self {
+        let reminder = notification.reminder;
+        Self {
+            id: notification.id.to_string(),
+            calendar_id: reminder.calendar_id,
+            object_id: reminder.object_id,
+            component: reminder.component,
+            summary: reminder.summary,
+            description: reminder.description,
+            action: reminder.action.as_str().to_string(),
+            trigger_at: reminder.trigger_at,
+            occurrence_start: reminder.occurrence_start,
+            created_at: notification.created_at,
+        }
+    }
+}
+
+/// DTO for snoozing a reminder
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnoozeReminderDto {
+    /// Minutes until the reminder fires again
+    pub minutes: i64,
+}
+
 /// DTO for querying events in a time range
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EventQueryDto {
@@ -27,6 +27,10 @@ pub struct FileDto {
 
     /// Last modification timestamp
     pub modified_at: u64,
+
+    /// Content version, without the quotes of an HTTP entity tag
+    #[serde(default)]
+    pub etag: String,
 }
 
 impl From<File> for FileDto {
@@ -40,6 +44,7 @@ impl From<File> for FileDto {
             folder_id: file.folder_id().map(String::from),
             created_at: file.created_at(),
             modified_at: file.modified_at(),
+            etag: file.etag(),
         }
     }
 }
@@ -50,7 +55,7 @@ impl From<FileDto> for File {
         // Usar constructor para crear una entidad desde DTO
         // Nota: esto debe simplificarse si File tiene un constructor adecuado
         // Si no, deberías hacer la conversión de la mejor manera posible
-        File::from_dto(
+        let file = File::from_dto(
             dto.id,
             dto.name,
             dto.path,
@@ -59,7 +64,12 @@ impl From<FileDto> for File {
             dto.folder_id,
             dto.created_at,
             dto.modified_at,
-        )
+        );
+        if dto.etag.is_empty() {
+            file
+        } else {
+            file.with_etag(dto.etag)
+        }
     }
 }
 
@@ -75,6 +85,7 @@ impl FileDto {
             folder_id: None,
             created_at: 0,
             modified_at: 0,
+            etag: String::new(),
         }
     }
 }
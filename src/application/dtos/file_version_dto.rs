@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::file_version::FileVersion;
+
+/// DTO para una versión anterior de un archivo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileVersionDto {
+    pub id: String,
+    pub file_id: String,
+    pub name: String,
+    pub version_number: u32,
+    pub size: u64,
+    pub mime_type: String,
+    pub modified_at: u64,
+    pub created_at: u64,
+}
+
+impl From<FileVersion> for FileVersionDto {
+    fn from(version: FileVersion) -> Self {
+        Self {
+            id: version.id,
+            file_id: version.file_id,
+            name: version.name,
+            version_number: version.version_number,
+            size: version.size,
+            mime_type: version.mime_type,
+            modified_at: version.modified_at,
+            created_at: version.created_at,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use crate::domain::entities::webdav_lock::{LockDepth, LockScope, WebDavLock};
+
+/// DTO de un bloqueo WebDAV activo, para mostrar "bloqueado por" en la
+/// interfaz web. No incluye el token, que solo conoce quien bloqueó.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockDto {
+    pub path: String,
+    pub scope: LockScope,
+    pub depth: LockDepth,
+    /// Usuario que creó el bloqueo
+    pub locked_by: Option<String>,
+    /// Contenido de `DAV:owner` enviado por el cliente
+    pub owner: Option<String>,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+impl From<WebDavLock> for LockDto {
+    fn from(lock: WebDavLock) -> Self {
+        Self {
+            path: lock.path,
+            scope: lock.scope,
+            depth: lock.depth,
+            locked_by: lock.principal,
+            owner: lock.owner,
+            created_at: lock.created_at,
+            expires_at: lock.expires_at,
+        }
+    }
+}
@@ -3,11 +3,14 @@ pub mod calendar_dto;
 pub mod contact_dto;
 pub mod favorites_dto;
 pub mod file_dto;
+pub mod file_version_dto;
 pub mod folder_dto;
 pub mod i18n_dto;
+pub mod lock_dto;
 pub mod pagination;
 pub mod recent_dto;
 pub mod search_dto;
 pub mod share_dto;
 pub mod trash_dto;
+pub mod upload_dto;
 pub mod user_dto;
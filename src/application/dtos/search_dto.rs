@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::domain::entities::content_index::ContentMatch;
+
 /**
  * Data Transfer Object for file search criteria.
  *
@@ -13,10 +15,20 @@ pub struct SearchCriteriaDto {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name_contains: Option<String>,
 
+    /// Optional words that must all appear in the indexed text of a file.
+    /// Only files are returned, ranked by relevance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_contains: Option<String>,
+
     /// Optional list of file extensions to include (e.g., "pdf", "jpg")
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_types: Option<Vec<String>>,
 
+    /// Optional MIME type to include: an exact type, or a prefix ending in
+    /// "/" (e.g., "image/"). Only files are returned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
     /// Optional minimum creation date (seconds since epoch)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_after: Option<u64>,
@@ -72,7 +84,9 @@ impl Default for SearchCriteriaDto {
     fn default() -> Self {
         Self {
             name_contains: None,
+            content_contains: None,
             file_types: None,
+            content_type: None,
             created_after: None,
             created_before: None,
             modified_after: None,
@@ -112,6 +126,47 @@ pub struct SearchResultsDto {
 
     /// Whether there are more results available
     pub has_more: bool,
+
+    /// Relevance and highlighted snippet of each file, for content searches
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub content_matches: Vec<ContentMatchDto>,
+}
+
+/**
+ * Data Transfer Object for a file found by its content.
+ *
+ * The snippet is plain text taken from the file; `highlights` holds the
+ * `[start, end)` character offsets of the matched words within it.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentMatchDto {
+    /// ID of the matching file (also present in `files`)
+    pub file_id: String,
+
+    /// Relevance score, only comparable within the same search
+    pub score: f64,
+
+    /// Excerpt of the file text around the best match
+    pub snippet: String,
+
+    /// Character ranges of the matched words within the snippet
+    pub highlights: Vec<[usize; 2]>,
+}
+
+impl From<ContentMatch> for ContentMatchDto {
+    fn from(content_match: ContentMatch) -> Self {
+        Self {
+            file_id: content_match.document.file_id,
+            score: content_match.score,
+            snippet: content_match.snippet.text,
+            highlights: content_match
+                .snippet
+                .highlights
+                .into_iter()
+                .map(|(start, end)| [start, end])
+                .collect(),
+        }
+    }
 }
 
 impl SearchResultsDto {
@@ -124,6 +179,7 @@ impl SearchResultsDto {
             limit: 0,
             offset: 0,
             has_more: false,
+            content_matches: Vec::new(),
         }
     }
 
@@ -147,6 +203,13 @@ impl SearchResultsDto {
             limit,
             offset,
             has_more,
+            content_matches: Vec::new(),
         }
     }
+
+    /// Attaches the content match details of the returned files
+    pub fn with_content_matches(mut self, content_matches: Vec<ContentMatchDto>) -> Self {
+        self.content_matches = content_matches;
+        self
+    }
 }
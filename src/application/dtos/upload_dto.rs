@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::application::dtos::file_dto::FileDto;
+use crate::domain::entities::upload_session::UploadSession;
+
+/// DTO para crear una subida reanudable
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CreateUploadDto {
+    /// Tamaño total del archivo en bytes
+    pub length: u64,
+    /// Nombre del archivo final
+    pub name: String,
+    /// Tipo MIME del archivo final (se deduce del nombre si falta)
+    pub content_type: Option<String>,
+    /// Carpeta de destino
+    pub folder_id: Option<String>,
+    /// Metadatos adicionales enviados por el cliente
+    pub metadata: HashMap<String, String>,
+}
+
+/// DTO con el estado de una subida reanudable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSessionDto {
+    pub id: String,
+    pub offset: u64,
+    pub length: u64,
+    pub name: String,
+    pub folder_id: Option<String>,
+    pub expires_at: u64,
+    /// Archivo creado al completarse la subida
+    pub file: Option<FileDto>,
+}
+
+impl From<UploadSession> for UploadSessionDto {
+    fn from(session: UploadSession) -> Self {
+        Self {
+            id: session.id,
+            offset: session.offset,
+            length: session.length,
+            name: session.name,
+            folder_id: session.folder_id,
+            expires_at: session.expires_at,
+            file: None,
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::common::errors::DomainError;
+use crate::domain::entities::app_password::AppPassword;
 use crate::domain::entities::session::Session;
 use crate::domain::entities::user::User;
 use async_trait::async_trait;
@@ -57,3 +58,15 @@ pub trait SessionStoragePort: Send + Sync + 'static {
     /// Revoca todas las sesiones de un usuario
     async fn revoke_all_user_sessions(&self, user_id: &str) -> Result<u64, DomainError>;
 }
+
+#[async_trait]
+pub trait AppPasswordStoragePort: Send + Sync + 'static {
+    /// Guarda una contraseña de aplicación nueva
+    async fn create_app_password(&self, app_password: AppPassword) -> Result<(), DomainError>;
+
+    /// Contraseñas de aplicación de un usuario
+    async fn list_app_passwords(&self, user_id: &str) -> Result<Vec<AppPassword>, DomainError>;
+
+    /// Revoca una contraseña de aplicación del usuario
+    async fn delete_app_password(&self, user_id: &str, id: &str) -> Result<(), DomainError>;
+}
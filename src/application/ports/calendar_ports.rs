@@ -1,8 +1,9 @@
 use crate::application::dtos::calendar_dto::{
-    CalendarDto, CalendarEventDto, CreateCalendarDto, CreateEventDto, CreateEventICalDto,
-    UpdateCalendarDto, UpdateEventDto,
+    CalendarDto, CalendarEventDto, CalendarTaskDto, CreateCalendarDto, CreateEventDto,
+    CreateEventICalDto, CreateTaskDto, UpdateCalendarDto, UpdateEventDto, UpdateTaskDto,
 };
 use crate::common::errors::DomainError;
+use crate::domain::entities::scheduling::FreeBusyPeriod;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
@@ -74,6 +75,19 @@ pub trait CalendarStoragePort: Send + Sync + 'static {
         &self,
         calendar_id: &str,
     ) -> Result<std::collections::HashMap<String, String>, DomainError>;
+    async fn remove_calendar_property(
+        &self,
+        calendar_id: &str,
+        property_name: &str,
+    ) -> Result<(), DomainError>;
+
+    // User settings
+    async fn get_default_timezone(&self, user_id: &str) -> Result<Option<String>, DomainError>;
+    async fn set_default_timezone(
+        &self,
+        user_id: &str,
+        timezone: Option<&str>,
+    ) -> Result<(), DomainError>;
 
     // Event operations
     async fn create_event(&self, event: CreateEventDto) -> Result<CalendarEventDto, DomainError>;
@@ -86,6 +100,16 @@ pub trait CalendarStoragePort: Send + Sync + 'static {
         event_id: &str,
         update: UpdateEventDto,
     ) -> Result<CalendarEventDto, DomainError>;
+    async fn update_event_from_ical(
+        &self,
+        event_id: &str,
+        ical_data: &str,
+    ) -> Result<CalendarEventDto, DomainError>;
+    async fn find_event_by_uid(
+        &self,
+        calendar_id: &str,
+        ical_uid: &str,
+    ) -> Result<Option<CalendarEventDto>, DomainError>;
     async fn delete_event(&self, event_id: &str) -> Result<(), DomainError>;
     async fn get_event(&self, event_id: &str) -> Result<CalendarEventDto, DomainError>;
     async fn list_events_by_calendar(
@@ -104,71 +128,189 @@ pub trait CalendarStoragePort: Send + Sync + 'static {
         start: &DateTime<Utc>,
         end: &DateTime<Utc>,
     ) -> Result<Vec<CalendarEventDto>, DomainError>;
+
+    // Task operations
+    async fn create_task(
+        &self,
+        calendar_id: &str,
+        task: CreateTaskDto,
+    ) -> Result<CalendarTaskDto, DomainError>;
+    async fn create_task_from_ical(
+        &self,
+        calendar_id: &str,
+        ical_data: &str,
+    ) -> Result<CalendarTaskDto, DomainError>;
+    async fn update_task(
+        &self,
+        task_id: &str,
+        update: UpdateTaskDto,
+    ) -> Result<CalendarTaskDto, DomainError>;
+    async fn update_task_from_ical(
+        &self,
+        task_id: &str,
+        ical_data: &str,
+    ) -> Result<CalendarTaskDto, DomainError>;
+    async fn find_task_by_uid(
+        &self,
+        calendar_id: &str,
+        ical_uid: &str,
+    ) -> Result<Option<CalendarTaskDto>, DomainError>;
+    async fn delete_task(&self, task_id: &str) -> Result<(), DomainError>;
+    async fn get_task(&self, task_id: &str) -> Result<CalendarTaskDto, DomainError>;
+    async fn list_tasks_by_calendar(
+        &self,
+        calendar_id: &str,
+    ) -> Result<Vec<CalendarTaskDto>, DomainError>;
+    async fn get_tasks_in_time_range(
+        &self,
+        calendar_id: &str,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Result<Vec<CalendarTaskDto>, DomainError>;
 }
 
-/// Port for calendar use cases
+/// Port for calendar use cases, on behalf of the user given by `user_id`
 #[async_trait]
 pub trait CalendarUseCase: Send + Sync + 'static {
     // Calendar operations
     async fn create_calendar(
         &self,
         calendar: CreateCalendarDto,
+        user_id: &str,
     ) -> Result<CalendarDto, DomainError>;
     async fn update_calendar(
         &self,
         calendar_id: &str,
         update: UpdateCalendarDto,
+        user_id: &str,
     ) -> Result<CalendarDto, DomainError>;
-    async fn delete_calendar(&self, calendar_id: &str) -> Result<(), DomainError>;
-    async fn get_calendar(&self, calendar_id: &str) -> Result<CalendarDto, DomainError>;
-    async fn list_my_calendars(&self) -> Result<Vec<CalendarDto>, DomainError>;
-    async fn list_shared_calendars(&self) -> Result<Vec<CalendarDto>, DomainError>;
-    async fn list_public_calendars(
+    async fn delete_calendar(&self, calendar_id: &str, user_id: &str) -> Result<(), DomainError>;
+    async fn get_calendar(
         &self,
-        limit: Option<i64>,
-        offset: Option<i64>,
-    ) -> Result<Vec<CalendarDto>, DomainError>;
+        calendar_id: &str,
+        user_id: &str,
+    ) -> Result<CalendarDto, DomainError>;
+    async fn list_my_calendars(&self, user_id: &str) -> Result<Vec<CalendarDto>, DomainError>;
 
-    // Calendar sharing
-    async fn share_calendar(
+    // Calendar properties
+    async fn set_calendar_property(
         &self,
         calendar_id: &str,
+        property_name: &str,
+        property_value: &str,
         user_id: &str,
-        access_level: &str,
     ) -> Result<(), DomainError>;
-    async fn remove_calendar_sharing(
+    async fn remove_calendar_property(
         &self,
         calendar_id: &str,
+        property_name: &str,
         user_id: &str,
     ) -> Result<(), DomainError>;
-    async fn get_calendar_shares(
+
+    // User settings
+    /// Time zone used for the user's floating event times; UTC when unset
+    async fn get_default_timezone(&self, user_id: &str) -> Result<String, DomainError>;
+    /// Sets the user's default time zone, which must be a known zone, or
+    /// resets it to UTC with `None`
+    async fn set_default_timezone(
         &self,
-        calendar_id: &str,
-    ) -> Result<Vec<(String, String)>, DomainError>;
+        user_id: &str,
+        timezone: Option<&str>,
+    ) -> Result<(), DomainError>;
 
     // Event operations
-    async fn create_event(&self, event: CreateEventDto) -> Result<CalendarEventDto, DomainError>;
     async fn create_event_from_ical(
         &self,
         event: CreateEventICalDto,
+        user_id: &str,
     ) -> Result<CalendarEventDto, DomainError>;
-    async fn update_event(
+    async fn update_event_from_ical(
         &self,
         event_id: &str,
-        update: UpdateEventDto,
+        ical_data: &str,
+        user_id: &str,
     ) -> Result<CalendarEventDto, DomainError>;
-    async fn delete_event(&self, event_id: &str) -> Result<(), DomainError>;
-    async fn get_event(&self, event_id: &str) -> Result<CalendarEventDto, DomainError>;
+    async fn delete_event(&self, event_id: &str, user_id: &str) -> Result<(), DomainError>;
+    async fn get_event(
+        &self,
+        event_id: &str,
+        user_id: &str,
+    ) -> Result<CalendarEventDto, DomainError>;
+    async fn get_event_by_uid(
+        &self,
+        calendar_id: &str,
+        ical_uid: &str,
+        user_id: &str,
+    ) -> Result<Option<CalendarEventDto>, DomainError>;
     async fn list_events(
         &self,
         calendar_id: &str,
         limit: Option<i64>,
         offset: Option<i64>,
+        user_id: &str,
     ) -> Result<Vec<CalendarEventDto>, DomainError>;
     async fn get_events_in_range(
         &self,
         calendar_id: &str,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
+        user_id: &str,
     ) -> Result<Vec<CalendarEventDto>, DomainError>;
+    /// Busy periods of a calendar within a time range, from its events that
+    /// are neither cancelled nor transparent (RFC 4791, section 7.10)
+    async fn get_free_busy(
+        &self,
+        calendar_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        user_id: &str,
+    ) -> Result<Vec<FreeBusyPeriod>, DomainError>;
+
+    // Task operations
+    async fn create_task(
+        &self,
+        calendar_id: &str,
+        task: CreateTaskDto,
+        user_id: &str,
+    ) -> Result<CalendarTaskDto, DomainError>;
+    async fn create_task_from_ical(
+        &self,
+        calendar_id: &str,
+        ical_data: &str,
+        user_id: &str,
+    ) -> Result<CalendarTaskDto, DomainError>;
+    async fn update_task(
+        &self,
+        task_id: &str,
+        update: UpdateTaskDto,
+        user_id: &str,
+    ) -> Result<CalendarTaskDto, DomainError>;
+    async fn update_task_from_ical(
+        &self,
+        task_id: &str,
+        ical_data: &str,
+        user_id: &str,
+    ) -> Result<CalendarTaskDto, DomainError>;
+    async fn delete_task(&self, task_id: &str, user_id: &str) -> Result<(), DomainError>;
+    async fn get_task(&self, task_id: &str, user_id: &str) -> Result<CalendarTaskDto, DomainError>;
+    async fn get_task_by_uid(
+        &self,
+        calendar_id: &str,
+        ical_uid: &str,
+        user_id: &str,
+    ) -> Result<Option<CalendarTaskDto>, DomainError>;
+    async fn list_tasks(
+        &self,
+        calendar_id: &str,
+        user_id: &str,
+    ) -> Result<Vec<CalendarTaskDto>, DomainError>;
+    /// Tasks that overlap a time range, following the VTODO rules of
+    /// RFC 4791, section 9.9
+    async fn get_tasks_in_range(
+        &self,
+        calendar_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        user_id: &str,
+    ) -> Result<Vec<CalendarTaskDto>, DomainError>;
 }
@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+
+use crate::common::errors::DomainError;
+use crate::domain::entities::content_index::{ContentMatch, IndexedDocument};
+
+/// Puerto secundario que obtiene el texto de un documento para indexarlo
+pub trait TextExtractorPort: Send + Sync + 'static {
+    /// Indica si se sabe extraer texto de este tipo de archivo
+    fn supports(&self, name: &str, mime_type: &str) -> bool;
+
+    /// Extrae el texto plano del contenido, o `None` si no tiene texto útil
+    fn extract(&self, name: &str, mime_type: &str, content: &[u8]) -> Option<String>;
+}
+
+/// Puerto secundario para el índice invertido del contenido de los archivos
+#[async_trait]
+pub trait ContentIndexStoragePort: Send + Sync + 'static {
+    /// Añade un documento o sustituye su versión anterior
+    async fn upsert_document(
+        &self,
+        document: IndexedDocument,
+        text: String,
+    ) -> Result<(), DomainError>;
+
+    /// Quita un documento del índice; devuelve si estaba indexado
+    async fn remove_document(&self, file_id: &str) -> Result<bool, DomainError>;
+
+    /// Quita los documentos cuya ruta está bajo `path_prefix` y devuelve cuántos
+    async fn remove_under_path(&self, path_prefix: &str) -> Result<usize, DomainError>;
+
+    /// Documentos de `owners` que contienen todos los términos, ordenados por
+    /// relevancia y con un fragmento resaltado
+    async fn search(
+        &self,
+        owners: &[String],
+        terms: &[String],
+        limit: usize,
+    ) -> Result<Vec<ContentMatch>, DomainError>;
+
+    /// Número de documentos indexados
+    async fn document_count(&self) -> Result<usize, DomainError>;
+
+    /// Persiste los cambios pendientes
+    async fn flush(&self) -> Result<(), DomainError>;
+}
+
+/// Puerto primario para la búsqueda por contenido.
+///
+/// Los cambios se encolan y se aplican en orden en segundo plano.
+#[async_trait]
+pub trait ContentIndexUseCase: Send + Sync + 'static {
+    /// Encola la (re)indexación de un archivo cuyo contenido o ruta cambió
+    async fn index_file(&self, file_id: &str);
+
+    /// Encola la retirada de un archivo borrado o enviado a la papelera
+    async fn remove_file(&self, file_id: &str);
+
+    /// Encola la retirada de todos los archivos bajo una carpeta
+    async fn remove_folder(&self, folder_path: &str);
+
+    /// Encola la indexación de todos los archivos bajo una carpeta, o de todo
+    /// el almacenamiento con `None`
+    async fn index_folder(&self, folder_id: Option<&str>);
+
+    /// Busca en el contenido de los archivos visibles para `owner`
+    async fn search(
+        &self,
+        owner: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<ContentMatch>, DomainError>;
+}
@@ -1,9 +1,11 @@
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::Stream;
+use std::pin::Pin;
 use std::sync::Arc;
 
 use crate::application::dtos::file_dto::FileDto;
+use crate::application::ports::storage_ports::collect_byte_stream;
 use crate::common::errors::DomainError;
 
 /// Puerto primario para operaciones de subida de archivos
@@ -17,6 +19,30 @@ pub trait FileUploadUseCase: Send + Sync + 'static {
         content_type: String,
         content: Vec<u8>,
     ) -> Result<FileDto, DomainError>;
+
+    /// Sube un nuevo archivo de `size` bytes leyendo su contenido de un stream.
+    /// Por defecto reúne el stream en memoria y lo sube como bytes
+    async fn upload_file_stream(
+        &self,
+        name: String,
+        folder_id: Option<String>,
+        content_type: String,
+        _size: u64,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<FileDto, DomainError> {
+        let content = collect_byte_stream(stream).await?;
+        self.upload_file(name, folder_id, content_type, content)
+            .await
+    }
+
+    /// Comprueba que caben `size` bytes más en la cuota del dueño de la carpeta
+    async fn check_upload_quota(
+        &self,
+        _folder_id: Option<&str>,
+        _size: u64,
+    ) -> Result<(), DomainError> {
+        Ok(())
+    }
 }
 
 /// Puerto primario para operaciones de recuperación de archivos
@@ -36,6 +62,14 @@ pub trait FileRetrievalUseCase: Send + Sync + 'static {
         &self,
         id: &str,
     ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>;
+
+    /// Obtiene el rango de bytes `[start, end)` del contenido como stream
+    async fn get_file_range_stream(
+        &self,
+        id: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>;
 }
 
 /// Puerto primario para operaciones de gestión de archivos
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::Stream;
+use std::pin::Pin;
 use std::sync::Arc;
 
 use crate::application::dtos::file_dto::FileDto;
@@ -8,8 +9,12 @@ use crate::application::dtos::folder_dto::{
     CreateFolderDto, FolderDto, MoveFolderDto, RenameFolderDto,
 };
 use crate::application::dtos::search_dto::{SearchCriteriaDto, SearchResultsDto};
+use crate::application::ports::storage_ports::collect_byte_stream;
 use crate::common::errors::DomainError;
 
+/// Stream de los archivos de una carpeta
+pub type FileDtoStream<'a> = Pin<Box<dyn Stream<Item = Result<FileDto, DomainError>> + Send + 'a>>;
+
 /// Puerto primario para operaciones de archivos
 #[async_trait]
 pub trait FileUseCase: Send + Sync + 'static {
@@ -40,9 +45,55 @@ pub trait FileUseCase: Send + Sync + 'static {
     /// Actualiza el contenido de un archivo existente (para WebDAV)
     async fn update_file(&self, path: &str, content: &[u8]) -> Result<(), DomainError>;
 
+    /// Crea un archivo leyendo su contenido de un stream (para WebDAV).
+    /// Por defecto reúne el stream en memoria
+    async fn create_file_stream(
+        &self,
+        parent_path: &str,
+        filename: &str,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+        content_type: &str,
+    ) -> Result<FileDto, DomainError> {
+        let content = collect_byte_stream(stream).await?;
+        self.create_file(parent_path, filename, &content, content_type)
+            .await
+    }
+
+    /// Actualiza el contenido de un archivo, o lo crea, leyéndolo de un
+    /// stream (para WebDAV). Por defecto reúne el stream en memoria
+    async fn update_file_stream(
+        &self,
+        path: &str,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<(), DomainError> {
+        let content = collect_byte_stream(stream).await?;
+        self.update_file(path, &content).await
+    }
+
     /// Lista archivos en una carpeta
     async fn list_files(&self, folder_id: Option<&str>) -> Result<Vec<FileDto>, DomainError>;
 
+    /// Lista como mucho `limit` archivos de una carpeta a partir de `offset`
+    async fn list_files_paginated(
+        &self,
+        folder_id: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<FileDto>, DomainError> {
+        let files = self.list_files(folder_id).await?;
+        Ok(files.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// Recorre los archivos de una carpeta como stream. Por defecto carga el
+    /// listado completo
+    async fn list_files_stream<'a>(
+        &'a self,
+        folder_id: Option<&str>,
+    ) -> Result<FileDtoStream<'a>, DomainError> {
+        let files = self.list_files(folder_id).await?;
+        Ok(Box::pin(futures::stream::iter(files.into_iter().map(Ok))))
+    }
+
     /// Elimina un archivo
     async fn delete_file(&self, id: &str) -> Result<(), DomainError>;
 
@@ -108,9 +159,15 @@ pub trait SearchUseCase: Send + Sync + 'static {
      * Realiza una búsqueda basada en los criterios especificados
      *
      * @param criteria Criterios de búsqueda que incluyen texto, fechas, tamaños, etc.
+     * @param username Usuario que busca, o `None` si no está autenticado; la
+     *        búsqueda por contenido solo devuelve sus archivos y los compartidos
      * @return Resultados de la búsqueda que contienen archivos y carpetas coincidentes
      */
-    async fn search(&self, criteria: SearchCriteriaDto) -> Result<SearchResultsDto, DomainError>;
+    async fn search(
+        &self,
+        criteria: SearchCriteriaDto,
+        username: Option<&str>,
+    ) -> Result<SearchResultsDto, DomainError>;
 
     /**
      * Limpia la caché de resultados de búsqueda
@@ -1,11 +1,24 @@
 pub mod auth_ports;
 pub mod calendar_ports;
 pub mod carddav_ports;
+pub mod content_index_ports;
 pub mod favorites_ports;
 pub mod file_ports;
 pub mod inbound;
 pub mod outbound;
 pub mod recent_ports;
+pub mod reminder_ports;
+pub mod scheduling_ports;
 pub mod share_ports;
 pub mod storage_ports;
+pub mod sync_ports;
+pub mod thumbnail_ports;
 pub mod trash_ports;
+pub mod upload_ports;
+pub mod version_ports;
+pub mod webdav_acl_ports;
+pub mod webdav_lock_ports;
+pub mod webdav_property_ports;
+
+#[cfg(test)]
+pub mod test_doubles;
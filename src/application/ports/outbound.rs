@@ -2,7 +2,9 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use futures::Stream;
 use std::path::PathBuf;
+use std::pin::Pin;
 
+use crate::application::ports::storage_ports::{collect_byte_stream, slice_byte_stream};
 use crate::common::errors::DomainError;
 use crate::domain::entities::file::File;
 use crate::domain::entities::folder::Folder;
@@ -24,6 +26,9 @@ pub trait StoragePort: Send + Sync + 'static {
     async fn directory_exists(&self, storage_path: &StoragePath) -> Result<bool, DomainError>;
 }
 
+/// Stream de los archivos de una carpeta
+pub type FileStream<'a> = Pin<Box<dyn Stream<Item = Result<File, DomainError>> + Send + 'a>>;
+
 /// Puerto secundario para persistencia de archivos
 #[async_trait]
 pub trait FileStoragePort: Send + Sync + 'static {
@@ -36,12 +41,50 @@ pub trait FileStoragePort: Send + Sync + 'static {
         content: Vec<u8>,
     ) -> Result<File, DomainError>;
 
+    /// Guarda un nuevo archivo leyendo su contenido de un stream.
+    /// Por defecto reúne el stream en memoria; los backends que pueden
+    /// escribirlo a medida que llega deben hacerlo
+    async fn save_file_stream(
+        &self,
+        name: String,
+        folder_id: Option<String>,
+        content_type: String,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<File, DomainError> {
+        let content = collect_byte_stream(stream).await?;
+        self.save_file(name, folder_id, content_type, content).await
+    }
+
     /// Obtiene un archivo por su ID
     async fn get_file(&self, id: &str) -> Result<File, DomainError>;
 
     /// Lista archivos en una carpeta
     async fn list_files(&self, folder_id: Option<&str>) -> Result<Vec<File>, DomainError>;
 
+    /// Lista como mucho `limit` archivos de una carpeta a partir de `offset`.
+    /// Las implementaciones que pueden recorrer la carpeta sin cargarla
+    /// entera deben hacerlo en lugar de recortar el listado completo.
+    async fn list_files_paginated(
+        &self,
+        folder_id: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<File>, DomainError> {
+        let files = self.list_files(folder_id).await?;
+        Ok(files.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// Recorre los archivos de una carpeta como stream. Por defecto carga el
+    /// listado completo; las implementaciones que pueden leer la carpeta a
+    /// medida que se consume deben hacerlo
+    async fn list_files_stream<'a>(
+        &'a self,
+        folder_id: Option<&str>,
+    ) -> Result<FileStream<'a>, DomainError> {
+        let files = self.list_files(folder_id).await?;
+        Ok(Box::pin(futures::stream::iter(files.into_iter().map(Ok))))
+    }
+
     /// Elimina un archivo
     async fn delete_file(&self, id: &str) -> Result<(), DomainError>;
 
@@ -54,6 +97,21 @@ pub trait FileStoragePort: Send + Sync + 'static {
         id: &str,
     ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>;
 
+    /// Obtiene el rango de bytes `[start, end)` del contenido como stream
+    async fn get_file_range_stream(
+        &self,
+        id: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
+        let stream = self.get_file_stream(id).await?;
+        Ok(Box::new(slice_byte_stream(
+            Box::into_pin(stream),
+            start,
+            end,
+        )))
+    }
+
     /// Mueve un archivo a otra carpeta
     async fn move_file(
         &self,
@@ -61,6 +119,25 @@ pub trait FileStoragePort: Send + Sync + 'static {
         target_folder_id: Option<String>,
     ) -> Result<File, DomainError>;
 
+    /// Copia un archivo a otra carpeta conservando su nombre.
+    /// Los backends que comparten contenido deben copiar solo la referencia
+    /// en lugar de volver a escribir los bytes.
+    async fn copy_file(
+        &self,
+        file_id: &str,
+        target_folder_id: Option<String>,
+    ) -> Result<File, DomainError> {
+        let file = self.get_file(file_id).await?;
+        let content = self.get_file_content(file_id).await?;
+        self.save_file(
+            file.name().to_string(),
+            target_folder_id,
+            file.mime_type().to_string(),
+            content,
+        )
+        .await
+    }
+
     /// Obtiene la ruta de almacenamiento de un archivo
     async fn get_file_path(&self, id: &str) -> Result<StoragePath, DomainError>;
 
@@ -70,6 +147,17 @@ pub trait FileStoragePort: Send + Sync + 'static {
     /// Actualiza el contenido de un archivo existente
     async fn update_file_content(&self, file_id: &str, content: Vec<u8>)
         -> Result<(), DomainError>;
+
+    /// Actualiza el contenido de un archivo existente leyéndolo de un stream.
+    /// Por defecto reúne el stream en memoria, como `save_file_stream`
+    async fn update_file_content_stream(
+        &self,
+        file_id: &str,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<(), DomainError> {
+        let content = collect_byte_stream(stream).await?;
+        self.update_file_content(file_id, content).await
+    }
 }
 
 /// Puerto secundario para persistencia de carpetas
@@ -138,6 +226,16 @@ pub trait IdMappingPort: Send + Sync + 'static {
     /// Guarda cambios pendientes
     async fn save_changes(&self) -> Result<(), DomainError>;
 
+    /// Actualiza la ruta de un ID y la de todos los IDs que cuelgan de ella,
+    /// para renombrados y movimientos de carpetas
+    async fn update_path_with_descendants(
+        &self,
+        id: &str,
+        new_path: &StoragePath,
+    ) -> Result<(), DomainError> {
+        self.update_path(id, new_path).await
+    }
+
     /// Obtiene la ruta de archivo como PathBuf
     async fn get_file_path(&self, file_id: &str) -> Result<PathBuf, DomainError> {
         let storage_path = self.get_path_by_id(file_id).await?;
@@ -0,0 +1,100 @@
+use crate::application::dtos::calendar_dto::ReminderNotificationDto;
+use crate::common::errors::DomainError;
+use crate::domain::entities::reminder::{AlarmAction, AlarmSource, Reminder, ReminderNotification};
+use crate::domain::entities::user::User;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// Port for the index of upcoming alarm triggers and the in-app
+/// notifications of those already sent
+#[async_trait]
+pub trait ReminderStoragePort: Send + Sync + 'static {
+    /// Events and tasks with alarms; only those changed since `changed_since`
+    /// when given
+    async fn alarm_sources(
+        &self,
+        changed_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<AlarmSource>, DomainError>;
+    async fn find_alarm_source(&self, object_id: &str) -> Result<Option<AlarmSource>, DomainError>;
+
+    /// Replaces the pending reminders of an object. Reminders already sent
+    /// are kept, and a reminder for the same alarm and time is not added
+    /// again.
+    async fn replace_reminders(
+        &self,
+        object_id: &str,
+        reminders: Vec<Reminder>,
+    ) -> Result<(), DomainError>;
+
+    /// Drops the pending reminders of deleted objects and those due before
+    /// `before`; returns how many were dropped
+    async fn prune_reminders(&self, before: DateTime<Utc>) -> Result<u64, DomainError>;
+
+    /// Marks as sent, and returns, up to `limit` pending reminders due at
+    /// `now`. Each reminder is claimed once, also across server instances.
+    async fn claim_due_reminders(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Reminder>, DomainError>;
+
+    // In-app notifications
+    async fn save_notification(
+        &self,
+        notification: ReminderNotification,
+    ) -> Result<ReminderNotification, DomainError>;
+    /// Notifications of a user, oldest first
+    async fn list_notifications(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<ReminderNotification>, DomainError>;
+    async fn find_notification(
+        &self,
+        user_id: &str,
+        notification_id: &str,
+    ) -> Result<Option<ReminderNotification>, DomainError>;
+    async fn delete_notification(
+        &self,
+        user_id: &str,
+        notification_id: &str,
+    ) -> Result<(), DomainError>;
+}
+
+/// Port for a way of delivering due reminders to their user
+#[async_trait]
+pub trait ReminderChannel: Send + Sync + 'static {
+    /// Name of the channel, for logs
+    fn name(&self) -> &'static str;
+    /// Whether the channel delivers alarms with this action
+    fn accepts(&self, action: AlarmAction) -> bool;
+    async fn deliver(&self, reminder: &Reminder, user: &User) -> Result<(), DomainError>;
+}
+
+/// Port for the reminders of calendar alarms (VALARM)
+#[async_trait]
+pub trait ReminderUseCase: Send + Sync + 'static {
+    /// Indexes the upcoming alarm triggers of the objects changed since the
+    /// previous call, or of every object on the first call and when `full`;
+    /// returns how many objects were indexed
+    async fn sync(&self, full: bool) -> Result<usize, DomainError>;
+
+    /// Delivers the reminders that are due through the channels accepting
+    /// them; returns how many were delivered
+    async fn deliver_due(&self) -> Result<usize, DomainError>;
+
+    // In-app notifications
+    async fn list_notifications(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<ReminderNotificationDto>, DomainError>;
+    /// Dismisses a notification and marks its alarm as acknowledged in the
+    /// calendar object, so that clients stop showing it too
+    async fn acknowledge(&self, notification_id: &str, user_id: &str) -> Result<(), DomainError>;
+    /// Dismisses a notification and snoozes its alarm in the calendar object
+    async fn snooze(
+        &self,
+        notification_id: &str,
+        minutes: i64,
+        user_id: &str,
+    ) -> Result<(), DomainError>;
+}
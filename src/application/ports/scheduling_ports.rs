@@ -0,0 +1,64 @@
+use crate::application::dtos::calendar_dto::ScheduleResponseDto;
+use crate::common::errors::DomainError;
+use crate::domain::entities::scheduling::{FreeBusyPeriod, ScheduleMessage};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// Port for the storage of the scheduling inbox of each user
+#[async_trait]
+pub trait ScheduleInboxStoragePort: Send + Sync + 'static {
+    async fn save_message(&self, message: ScheduleMessage) -> Result<ScheduleMessage, DomainError>;
+    /// Messages delivered to a user, oldest first
+    async fn list_messages(&self, user_id: &str) -> Result<Vec<ScheduleMessage>, DomainError>;
+    async fn find_message(
+        &self,
+        user_id: &str,
+        message_id: &str,
+    ) -> Result<Option<ScheduleMessage>, DomainError>;
+    async fn delete_message(&self, user_id: &str, message_id: &str) -> Result<(), DomainError>;
+}
+
+/// Port for scheduling between the users of this server (RFC 6638), on
+/// behalf of the user given by `user_id`
+#[async_trait]
+pub trait SchedulingUseCase: Send + Sync + 'static {
+    /// Busy periods of a user across the calendars they own
+    async fn free_busy(
+        &self,
+        user_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<FreeBusyPeriod>, DomainError>;
+
+    /// Implicit scheduling after a user stored or deleted an event, given
+    /// its iCalendar data before and after the change: the organizer's
+    /// changes go out to the attendees as REQUEST or CANCEL, and an
+    /// attendee's answers go back to the organizer as REPLY
+    async fn schedule_change(
+        &self,
+        user_id: &str,
+        previous: Option<&str>,
+        current: Option<&str>,
+    ) -> Result<(), DomainError>;
+
+    /// Processes an iTIP message POSTed to the user's outbox: a VFREEBUSY
+    /// request, or a REQUEST, REPLY or CANCEL for the local recipients
+    async fn send_message(
+        &self,
+        user_id: &str,
+        ical_data: &str,
+    ) -> Result<Vec<ScheduleResponseDto>, DomainError>;
+
+    // Inbox
+    async fn list_inbox(&self, user_id: &str) -> Result<Vec<ScheduleMessage>, DomainError>;
+    async fn get_inbox_message(
+        &self,
+        message_id: &str,
+        user_id: &str,
+    ) -> Result<ScheduleMessage, DomainError>;
+    async fn delete_inbox_message(
+        &self,
+        message_id: &str,
+        user_id: &str,
+    ) -> Result<(), DomainError>;
+}
@@ -1,8 +1,10 @@
 use async_trait::async_trait;
 use bytes::Bytes;
-use futures::Stream;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::PathBuf;
+use std::pin::Pin;
 
 use crate::common::errors::DomainError;
 use crate::domain::entities::file::File;
@@ -25,6 +27,60 @@ pub trait FileReadPort: Send + Sync + 'static {
         &self,
         id: &str,
     ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>;
+
+    /// Obtiene el rango de bytes `[start, end)` del contenido como stream.
+    /// Las implementaciones con acceso aleatorio deben posicionarse en `start`
+    /// en lugar de descartar los bytes anteriores.
+    async fn get_file_range_stream(
+        &self,
+        id: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
+        let stream = self.get_file_stream(id).await?;
+        Ok(Box::new(slice_byte_stream(
+            Box::into_pin(stream),
+            start,
+            end,
+        )))
+    }
+}
+
+/// Recorta un stream de bytes al rango `[start, end)` descartando lo que queda fuera
+pub fn slice_byte_stream(
+    mut stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    start: u64,
+    end: u64,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static {
+    async_stream::try_stream! {
+        let mut position = 0u64;
+        while position < end {
+            let Some(chunk) = stream.next().await else {
+                break;
+            };
+            let chunk = chunk?;
+            let chunk_start = position;
+            position += chunk.len() as u64;
+
+            let from = start.saturating_sub(chunk_start).min(chunk.len() as u64) as usize;
+            let to = end.saturating_sub(chunk_start).min(chunk.len() as u64) as usize;
+            if from < to {
+                yield chunk.slice(from..to);
+            }
+        }
+    }
+}
+
+/// Reúne en memoria el contenido de un stream de bytes
+pub async fn collect_byte_stream(
+    mut stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+) -> Result<Vec<u8>, DomainError> {
+    let mut content = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| DomainError::internal_error("Storage", e.to_string()))?;
+        content.extend_from_slice(&chunk);
+    }
+    Ok(content)
 }
 
 /// Puerto secundario para escritura de archivos
@@ -39,6 +95,20 @@ pub trait FileWritePort: Send + Sync + 'static {
         content: Vec<u8>,
     ) -> Result<File, DomainError>;
 
+    /// Guarda un nuevo archivo leyendo su contenido de un stream.
+    /// Por defecto reúne el stream en memoria; las implementaciones que
+    /// pueden escribirlo a medida que llega deben hacerlo
+    async fn save_file_stream(
+        &self,
+        name: String,
+        folder_id: Option<String>,
+        content_type: String,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<File, DomainError> {
+        let content = collect_byte_stream(stream).await?;
+        self.save_file(name, folder_id, content_type, content).await
+    }
+
     /// Mueve un archivo a otra carpeta
     async fn move_file(
         &self,
@@ -83,16 +153,147 @@ pub trait DirectoryManagementPort: Send + Sync + 'static {
     async fn ensure_directory(&self, storage_path: &StoragePath) -> Result<(), DomainError>;
 }
 
+/// Espacio ocupado por un usuario y límite que tiene asignado (RFC 4331)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageQuota {
+    pub used_bytes: u64,
+    /// `None` cuando el usuario no tiene límite
+    pub limit_bytes: Option<u64>,
+}
+
+impl StorageQuota {
+    /// Bytes que el usuario aún puede ocupar; `None` si no tiene límite
+    pub fn available_bytes(&self) -> Option<u64> {
+        self.limit_bytes
+            .map(|limit| limit.saturating_sub(self.used_bytes))
+    }
+}
+
 /// Puerto secundario para gestión de uso de almacenamiento
 #[async_trait]
 pub trait StorageUsagePort: Send + Sync + 'static {
     /// Actualiza estadísticas de uso de almacenamiento para un usuario
     async fn update_user_storage_usage(&self, user_id: &str) -> Result<i64, DomainError>;
 
+    /// Obtiene el uso registrado y la cuota de un usuario, sin recalcularlo
+    async fn get_user_quota(&self, user_id: &str) -> Result<StorageQuota, DomainError>;
+
     /// Actualiza estadísticas de uso de almacenamiento para todos los usuarios
     async fn update_all_users_storage_usage(&self) -> Result<(), DomainError>;
 }
 
+/// Puerto secundario para la recolección de bloques sin referencias
+#[async_trait]
+pub trait BlockGarbageCollectorPort: Send + Sync + 'static {
+    /// Elimina los bloques huérfanos y devuelve (bloques eliminados, bytes liberados)
+    async fn collect_garbage(&self) -> Result<(usize, u64), DomainError>;
+}
+
+/// Referencia a un contenido guardado por un `ContentStoragePort`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredContent {
+    /// Backend que guarda el contenido
+    pub backend: String,
+    /// Localización del contenido dentro del backend
+    pub locator: Value,
+    /// Tamaño del contenido en bytes
+    pub size: u64,
+}
+
+/// Puerto secundario para backends que guardan el contenido de los archivos
+/// fuera del árbol de almacenamiento.
+///
+/// El árbol conserva nombres, carpetas e identificadores; en la ruta de cada
+/// archivo queda solo la referencia a su contenido.
+#[async_trait]
+pub trait ContentStoragePort: Send + Sync + 'static {
+    /// Nombre del backend, guardado en las referencias que crea
+    fn backend_name(&self) -> &'static str;
+
+    /// Guarda un contenido a partir de un stream sin cargarlo entero en memoria
+    async fn put_content(
+        &self,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<StoredContent, DomainError>;
+
+    /// Obtiene un contenido como stream
+    async fn get_content_stream(
+        &self,
+        content: &StoredContent,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>;
+
+    /// Obtiene el rango de bytes `[start, end)` de un contenido como stream
+    async fn get_content_range_stream(
+        &self,
+        content: &StoredContent,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>;
+
+    /// Duplica un contenido sin transferir sus bytes
+    async fn copy_content(&self, content: &StoredContent) -> Result<StoredContent, DomainError>;
+
+    /// Libera un contenido que ya no usa ningún archivo
+    async fn release_content(&self, content: &StoredContent) -> Result<(), DomainError>;
+}
+
+/// Puerto secundario para almacenes de objetos (S3 y compatibles).
+///
+/// Las claves son rutas lógicas separadas por `/`, independientes del sistema
+/// de archivos local; el backend decide dónde y cómo se guardan los bytes.
+#[async_trait]
+pub trait ObjectStoragePort: Send + Sync + 'static {
+    /// Guarda un objeto a partir de un stream sin cargarlo entero en memoria.
+    /// Devuelve el número de bytes escritos
+    async fn put_object_stream(
+        &self,
+        key: &str,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<u64, DomainError>;
+
+    /// Obtiene el contenido de un objeto como stream
+    async fn get_object_stream(
+        &self,
+        key: &str,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>;
+
+    /// Obtiene el rango de bytes `[start, end)` de un objeto como stream
+    async fn get_object_range_stream(
+        &self,
+        key: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>;
+
+    /// Copia un objeto de `size` bytes dentro del almacén, sin pasar los bytes
+    /// por el servidor
+    async fn copy_object(
+        &self,
+        source_key: &str,
+        target_key: &str,
+        size: u64,
+    ) -> Result<(), DomainError>;
+
+    /// Elimina un objeto; no es un error si no existe
+    async fn delete_object(&self, key: &str) -> Result<(), DomainError>;
+}
+
+/// Puerto secundario para la gestión de claves del cifrado en reposo
+#[async_trait]
+pub trait EncryptionKeyPort: Send + Sync + 'static {
+    /// Sustituye la clave maestra y vuelve a envolver las claves de usuario.
+    /// `new_master_key` solo se admite, y es obligatoria, cuando la clave
+    /// maestra viene de la configuración; si no, se genera una clave nueva.
+    /// Devuelve (identificador de la nueva clave maestra, claves re-envueltas)
+    async fn rotate_master_key(
+        &self,
+        new_master_key: Option<Vec<u8>>,
+    ) -> Result<(String, usize), DomainError>;
+
+    /// Cifra los archivos que siguen en claro y devuelve (cifrados, omitidos por error)
+    async fn migrate_plaintext_files(&self) -> Result<(usize, usize), DomainError>;
+}
+
 /// Generic storage service interface for calendar and contact services
 #[async_trait]
 pub trait StorageUseCase: Send + Sync + 'static {
@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+
+use crate::common::errors::DomainError;
+use crate::domain::entities::sync_change::{ChangeKind, SyncChange};
+
+/// Puerto secundario para persistir el registro de cambios
+#[async_trait]
+pub trait ChangeLogStoragePort: Send + Sync + 'static {
+    /// Carga todas las entradas guardadas, en orden de secuencia
+    async fn load_changes(&self) -> Result<Vec<SyncChange>, DomainError>;
+
+    /// Añade una entrada al final del registro
+    async fn append_change(&self, change: &SyncChange) -> Result<(), DomainError>;
+
+    /// Sustituye el registro completo, p. ej. tras descartar entradas antiguas
+    async fn replace_changes(&self, changes: &[SyncChange]) -> Result<(), DomainError>;
+}
+
+/// Puerto primario del registro de cambios usado por la sincronización WebDAV
+#[async_trait]
+pub trait ChangeLogUseCase: Send + Sync + 'static {
+    /// Registra un cambio en un archivo o carpeta
+    async fn record(
+        &self,
+        kind: ChangeKind,
+        resource_id: &str,
+        is_folder: bool,
+        path: &str,
+        previous_path: Option<&str>,
+    );
+
+    /// Número de secuencia que identifica el estado actual de una colección
+    async fn current_seq(&self, collection: &str) -> u64;
+
+    /// Cambios posteriores a `since` que afectan a la colección, en orden.
+    /// Falla si `since` ya no está en el registro o nunca fue emitido.
+    async fn changes_since(
+        &self,
+        collection: &str,
+        since: u64,
+    ) -> Result<Vec<SyncChange>, DomainError>;
+}
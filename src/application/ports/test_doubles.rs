@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::Stream;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::application::dtos::file_dto::FileDto;
+use crate::application::ports::inbound::FileUseCase;
+use crate::application::ports::outbound::FileStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::file::File;
+use crate::domain::services::path_service::StoragePath;
+
+type Result<T> = std::result::Result<T, DomainError>;
+
+/// Tamaño de los bloques en que `get_file_stream` entrega el contenido
+const STREAM_CHUNK_SIZE: usize = 1000;
+
+/// Archivos en memoria para las pruebas, con su entidad y su contenido por
+/// ID. Sirve como `FileStoragePort` y como `FileUseCase`; las operaciones
+/// que ninguna prueba usa no están implementadas.
+#[derive(Default)]
+pub struct FakeFiles {
+    files: Mutex<HashMap<String, (File, Vec<u8>)>>,
+}
+
+impl FakeFiles {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Añade un archivo en la ruta `path`, con su nombre tomado de ella
+    pub fn add(&self, id: &str, path: &str, mime_type: &str, content: &[u8], modified_at: u64) {
+        let name = path.rsplit('/').next().unwrap_or(path).to_string();
+        let file = File::with_timestamps(
+            id.to_string(),
+            name,
+            StoragePath::from_string(path),
+            content.len() as u64,
+            mime_type.to_string(),
+            None,
+            modified_at,
+            modified_at,
+        )
+        .unwrap();
+        self.files
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), (file, content.to_vec()));
+    }
+
+    /// Cambia la fecha de modificación de un archivo, como si se reescribiera
+    pub fn touch(&self, id: &str, modified_at: u64) {
+        let mut files = self.files.lock().unwrap();
+        let (file, _) = files.get_mut(id).unwrap();
+        *file = File::with_timestamps(
+            id.to_string(),
+            file.name().to_string(),
+            file.storage_path().clone(),
+            file.size(),
+            file.mime_type().to_string(),
+            None,
+            file.created_at(),
+            modified_at,
+        )
+        .unwrap();
+    }
+
+    pub fn remove(&self, id: &str) {
+        self.files.lock().unwrap().remove(id);
+    }
+
+    fn entry(&self, id: &str) -> Result<(File, Vec<u8>)> {
+        self.files
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| DomainError::not_found("File", id))
+    }
+
+    /// Contenido de un archivo en varios bloques
+    fn stream(
+        &self,
+        id: &str,
+    ) -> Result<Box<dyn Stream<Item = std::result::Result<Bytes, std::io::Error>> + Send>> {
+        let (_, content) = self.entry(id)?;
+        let chunks: Vec<std::io::Result<Bytes>> = content
+            .chunks(STREAM_CHUNK_SIZE)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect();
+        Ok(Box::new(futures::stream::iter(chunks)))
+    }
+}
+
+#[async_trait]
+impl FileStoragePort for FakeFiles {
+    async fn save_file(
+        &self,
+        _name: String,
+        _folder_id: Option<String>,
+        _content_type: String,
+        _content: Vec<u8>,
+    ) -> Result<File> {
+        unimplemented!()
+    }
+
+    async fn get_file(&self, id: &str) -> Result<File> {
+        Ok(self.entry(id)?.0)
+    }
+
+    /// Todos los archivos están en la raíz
+    async fn list_files(&self, folder_id: Option<&str>) -> Result<Vec<File>> {
+        assert!(folder_id.is_none());
+        let files = self.files.lock().unwrap();
+        Ok(files.values().map(|(file, _)| file.clone()).collect())
+    }
+
+    async fn delete_file(&self, _id: &str) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn get_file_content(&self, id: &str) -> Result<Vec<u8>> {
+        Ok(self.entry(id)?.1)
+    }
+
+    async fn get_file_stream(
+        &self,
+        id: &str,
+    ) -> Result<Box<dyn Stream<Item = std::result::Result<Bytes, std::io::Error>> + Send>> {
+        self.stream(id)
+    }
+
+    async fn move_file(&self, _file_id: &str, _folder_id: Option<String>) -> Result<File> {
+        unimplemented!()
+    }
+
+    async fn get_file_path(&self, _id: &str) -> Result<StoragePath> {
+        unimplemented!()
+    }
+
+    async fn get_parent_folder_id(&self, _path: &str) -> Result<String> {
+        unimplemented!()
+    }
+
+    async fn update_file_content(&self, _file_id: &str, _content: Vec<u8>) -> Result<()> {
+        unimplemented!()
+    }
+}
+
+#[async_trait]
+impl FileUseCase for FakeFiles {
+    async fn upload_file(
+        &self,
+        _name: String,
+        _folder_id: Option<String>,
+        _content_type: String,
+        _content: Vec<u8>,
+    ) -> Result<FileDto> {
+        unimplemented!()
+    }
+
+    async fn get_file(&self, id: &str) -> Result<FileDto> {
+        Ok(FileDto::from(self.entry(id)?.0))
+    }
+
+    async fn get_file_by_path(&self, _path: &str) -> Result<FileDto> {
+        unimplemented!()
+    }
+
+    async fn create_file(
+        &self,
+        _parent_path: &str,
+        _filename: &str,
+        _content: &[u8],
+        _content_type: &str,
+    ) -> Result<FileDto> {
+        unimplemented!()
+    }
+
+    async fn update_file(&self, _path: &str, _content: &[u8]) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn list_files(&self, folder_id: Option<&str>) -> Result<Vec<FileDto>> {
+        let files = FileStoragePort::list_files(self, folder_id).await?;
+        Ok(files.into_iter().map(FileDto::from).collect())
+    }
+
+    async fn delete_file(&self, _id: &str) -> Result<()> {
+        unimplemented!()
+    }
+
+    async fn get_file_content(&self, id: &str) -> Result<Vec<u8>> {
+        Ok(self.entry(id)?.1)
+    }
+
+    async fn get_file_stream(
+        &self,
+        id: &str,
+    ) -> Result<Box<dyn Stream<Item = std::result::Result<Bytes, std::io::Error>> + Send>> {
+        self.stream(id)
+    }
+
+    async fn move_file(&self, _file_id: &str, _folder_id: Option<String>) -> Result<FileDto> {
+        unimplemented!()
+    }
+}
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+
+use crate::common::errors::DomainError;
+use crate::domain::entities::thumbnail::{Thumbnail, ThumbnailFormat, ThumbnailSize};
+
+/// Puerto secundario que decodifica una imagen y genera una miniatura
+#[async_trait]
+pub trait ThumbnailRendererPort: Send + Sync + 'static {
+    /// Redimensiona `source`, del tipo `mime_type`, al tamaño indicado
+    /// aplicando la orientación EXIF
+    async fn render(
+        &self,
+        source: &[u8],
+        mime_type: &str,
+        size: ThumbnailSize,
+        format: ThumbnailFormat,
+    ) -> Result<Vec<u8>, DomainError>;
+}
+
+/// Puerto secundario para la caché de miniaturas generadas
+#[async_trait]
+pub trait ThumbnailStoragePort: Send + Sync + 'static {
+    /// Obtiene una miniatura si existe para esa huella del contenido
+    async fn get_thumbnail(
+        &self,
+        file_id: &str,
+        fingerprint: &str,
+        size: ThumbnailSize,
+        format: ThumbnailFormat,
+    ) -> Result<Option<Vec<u8>>, DomainError>;
+
+    /// Guarda una miniatura, sustituyendo las de contenidos anteriores
+    async fn store_thumbnail(&self, thumbnail: &Thumbnail) -> Result<(), DomainError>;
+
+    /// Elimina todas las miniaturas de un archivo
+    async fn delete_thumbnails(&self, file_id: &str) -> Result<(), DomainError>;
+}
+
+/// Puerto primario para las miniaturas de imágenes
+#[async_trait]
+pub trait ThumbnailUseCase: Send + Sync + 'static {
+    /// Obtiene una miniatura, generándola si aún no existe
+    async fn get_thumbnail(
+        &self,
+        file_id: &str,
+        size: ThumbnailSize,
+        format: ThumbnailFormat,
+    ) -> Result<Thumbnail, DomainError>;
+
+    /// Encola la generación de todas las miniaturas de un archivo sin esperar
+    fn schedule_generation(&self, file_id: &str);
+
+    /// Descarta las miniaturas de un archivo tras cambiar o borrar su contenido
+    async fn invalidate(&self, file_id: &str) -> Result<(), DomainError>;
+}
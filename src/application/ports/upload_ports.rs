@@ -0,0 +1,234 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::Stream;
+use std::pin::Pin;
+use thiserror::Error;
+
+use crate::application::dtos::upload_dto::{CreateUploadDto, UploadSessionDto};
+use crate::common::errors::{DomainError, ErrorKind};
+use crate::domain::entities::upload_session::{ChecksumAlgorithm, UploadSession};
+
+/// Stream de bytes de un fragmento de subida
+pub type UploadChunkStream =
+    Pin<Box<dyn Stream<Item = std::result::Result<Bytes, std::io::Error>> + Send>>;
+
+/// Resultado de anexar un fragmento a una subida
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkWrite {
+    /// Bytes escritos en el almacén
+    pub written: u64,
+    /// Resumen de los bytes escritos si se pidió un algoritmo
+    pub digest: Option<Vec<u8>>,
+    /// Falso si el stream del cliente se cortó antes de terminar
+    pub complete: bool,
+}
+
+/// Suma de control enviada por el cliente para un fragmento
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadChecksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub digest: Vec<u8>,
+}
+
+/// Puerto secundario para persistir las subidas reanudables en curso
+#[async_trait]
+pub trait UploadSessionStoragePort: Send + Sync + 'static {
+    /// Registra una nueva subida vacía
+    async fn create_session(&self, session: &UploadSession) -> Result<(), DomainError>;
+
+    /// Obtiene una subida por su ID
+    async fn get_session(&self, id: &str) -> Result<UploadSession, DomainError>;
+
+    /// Guarda el estado de una subida
+    async fn save_session(&self, session: &UploadSession) -> Result<(), DomainError>;
+
+    /// Lista todas las subidas registradas
+    async fn list_sessions(&self) -> Result<Vec<UploadSession>, DomainError>;
+
+    /// Escribe el fragmento a partir de `offset`, descartando lo que hubiera
+    /// detrás, y lee como mucho `limit` bytes del stream
+    async fn append_chunk(
+        &self,
+        id: &str,
+        offset: u64,
+        chunk: UploadChunkStream,
+        limit: u64,
+        checksum: Option<ChecksumAlgorithm>,
+    ) -> Result<ChunkWrite, DomainError>;
+
+    /// Recorta el contenido recibido a `length` bytes
+    async fn truncate(&self, id: &str, length: u64) -> Result<(), DomainError>;
+
+    /// Lee el contenido completo recibido como stream
+    async fn read_content_stream(&self, id: &str) -> Result<UploadChunkStream, DomainError>;
+
+    /// Elimina una subida y su contenido
+    async fn delete_session(&self, id: &str) -> Result<(), DomainError>;
+}
+
+/// Errores de las subidas reanudables
+#[derive(Debug, Error)]
+pub enum UploadError {
+    /// La subida no existe o ha caducado
+    #[error("Upload not found: {0}")]
+    NotFound(String),
+
+    /// El desplazamiento indicado no coincide con el recibido
+    #[error("Upload offset mismatch: expected {expected}, got {actual}")]
+    OffsetMismatch { expected: u64, actual: u64 },
+
+    /// La suma de control del fragmento no coincide
+    #[error("Checksum mismatch for upload {0}")]
+    ChecksumMismatch(String),
+
+    /// El tamaño supera el máximo admitido
+    #[error("Upload too large: {0}")]
+    TooLarge(String),
+
+    /// El dueño de la carpeta de destino no tiene cuota suficiente
+    #[error("Storage quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    /// Petición mal formada
+    #[error("Invalid upload request: {0}")]
+    InvalidRequest(String),
+
+    /// Error interno
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl From<DomainError> for UploadError {
+    fn from(err: DomainError) -> Self {
+        match err.kind {
+            ErrorKind::NotFound => UploadError::NotFound(err.message),
+            ErrorKind::InvalidInput => UploadError::InvalidRequest(err.message),
+            ErrorKind::QuotaExceeded => UploadError::QuotaExceeded(err.message),
+            _ => UploadError::Internal(err.to_string()),
+        }
+    }
+}
+
+/// Puerto primario para las subidas reanudables por fragmentos
+#[async_trait]
+pub trait ResumableUploadUseCase: Send + Sync + 'static {
+    /// Tamaño máximo admitido por subida, si hay límite
+    fn max_size(&self) -> Option<u64>;
+
+    /// Crea una subida vacía de `owner`
+    async fn create_upload(
+        &self,
+        owner: &str,
+        dto: CreateUploadDto,
+    ) -> Result<UploadSessionDto, UploadError>;
+
+    /// Obtiene el estado de una subida. Las subidas de otros usuarios se
+    /// tratan como inexistentes en este y los demás métodos
+    async fn get_upload(&self, owner: &str, id: &str) -> Result<UploadSessionDto, UploadError>;
+
+    /// Anexa un fragmento en `offset`. Al completarse la subida se crea el
+    /// archivo y se devuelve en el DTO
+    async fn append_chunk(
+        &self,
+        owner: &str,
+        id: &str,
+        offset: u64,
+        chunk: UploadChunkStream,
+        checksum: Option<UploadChecksum>,
+    ) -> Result<UploadSessionDto, UploadError>;
+
+    /// Cancela una subida y descarta lo recibido
+    async fn terminate_upload(&self, owner: &str, id: &str) -> Result<(), UploadError>;
+
+    /// Elimina las subidas caducadas y devuelve cuántas se eliminaron
+    async fn cleanup_expired(&self) -> Result<usize, UploadError>;
+}
+
+/// Fragmento recibido de una subida por partes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedChunk {
+    /// Nombre del fragmento, su número de orden
+    pub name: String,
+    pub size: u64,
+}
+
+/// Contenido completo de una subida por partes
+pub struct AssembledUpload {
+    /// Tamaño total de los fragmentos
+    pub size: u64,
+    pub content: UploadChunkStream,
+}
+
+/// Puerto secundario para los fragmentos de las subidas por partes de los
+/// clientes de Nextcloud (chunking v2), agrupados por usuario y transferencia
+#[async_trait]
+pub trait ChunkedUploadStoragePort: Send + Sync + 'static {
+    /// Crea una transferencia vacía; falla con `AlreadyExists` si ya existe
+    async fn create_transfer(&self, owner: &str, transfer: &str) -> Result<(), DomainError>;
+
+    /// Fragmentos recibidos; falla con `NotFound` si la transferencia no existe
+    async fn list_chunks(
+        &self,
+        owner: &str,
+        transfer: &str,
+    ) -> Result<Vec<UploadedChunk>, DomainError>;
+
+    /// Guarda un fragmento, sustituyéndolo si ya se había recibido
+    async fn write_chunk(
+        &self,
+        owner: &str,
+        transfer: &str,
+        chunk: &str,
+        content: &[u8],
+    ) -> Result<(), DomainError>;
+
+    /// Lee el contenido de un fragmento como stream
+    async fn read_chunk(
+        &self,
+        owner: &str,
+        transfer: &str,
+        chunk: &str,
+    ) -> Result<UploadChunkStream, DomainError>;
+
+    /// Elimina una transferencia y sus fragmentos
+    async fn delete_transfer(&self, owner: &str, transfer: &str) -> Result<(), DomainError>;
+
+    /// Elimina las transferencias sin cambios desde hace más de `max_age_secs`
+    /// y devuelve cuántas se eliminaron
+    async fn delete_stale_transfers(&self, max_age_secs: u64) -> Result<usize, DomainError>;
+}
+
+/// Puerto primario para las subidas por partes de los clientes de Nextcloud
+#[async_trait]
+pub trait ChunkedUploadUseCase: Send + Sync + 'static {
+    /// Empieza una transferencia
+    async fn begin(&self, owner: &str, transfer: &str) -> Result<(), DomainError>;
+
+    /// Fragmentos recibidos hasta ahora, en orden
+    async fn chunks(&self, owner: &str, transfer: &str) -> Result<Vec<UploadedChunk>, DomainError>;
+
+    /// Guarda un fragmento de una transferencia existente
+    async fn store_chunk(
+        &self,
+        owner: &str,
+        transfer: &str,
+        chunk: &str,
+        content: &[u8],
+    ) -> Result<(), DomainError>;
+
+    /// Une los fragmentos en orden en un stream que los lee a medida que se
+    /// consume; con `expected_length` comprueba además que se ha recibido el
+    /// tamaño total anunciado por el cliente
+    async fn assemble(
+        &self,
+        owner: &str,
+        transfer: &str,
+        expected_length: Option<u64>,
+    ) -> Result<AssembledUpload, DomainError>;
+
+    /// Cancela una transferencia y descarta sus fragmentos
+    async fn abort(&self, owner: &str, transfer: &str) -> Result<(), DomainError>;
+
+    /// Elimina las transferencias abandonadas y devuelve cuántas se eliminaron
+    async fn cleanup_expired(&self) -> Result<usize, DomainError>;
+}
@@ -0,0 +1,88 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::Stream;
+use std::pin::Pin;
+
+use crate::application::dtos::file_version_dto::FileVersionDto;
+use crate::common::errors::{DomainError, Result};
+use crate::domain::entities::file::File;
+use crate::domain::entities::file_version::FileVersion;
+
+/// Stream de bytes del contenido de una versión
+pub type VersionContentStream =
+    Pin<Box<dyn Stream<Item = std::result::Result<Bytes, std::io::Error>> + Send>>;
+
+/// Puerto secundario para el almacén de versiones anteriores de archivos
+#[async_trait]
+pub trait FileVersionStoragePort: Send + Sync + 'static {
+    /// Archiva el contenido actual de un archivo como nueva versión
+    async fn store_version(
+        &self,
+        file: &File,
+        content: VersionContentStream,
+    ) -> std::result::Result<FileVersion, DomainError>;
+
+    /// Lista las versiones de un archivo, de la más reciente a la más antigua
+    async fn list_versions(
+        &self,
+        file_id: &str,
+    ) -> std::result::Result<Vec<FileVersion>, DomainError>;
+
+    /// Obtiene los metadatos de una versión
+    async fn get_version(
+        &self,
+        file_id: &str,
+        version_id: &str,
+    ) -> std::result::Result<FileVersion, DomainError>;
+
+    /// Obtiene el contenido de una versión como stream
+    async fn get_version_stream(
+        &self,
+        file_id: &str,
+        version_id: &str,
+    ) -> std::result::Result<VersionContentStream, DomainError>;
+
+    /// Obtiene el contenido completo de una versión
+    async fn get_version_content(
+        &self,
+        file_id: &str,
+        version_id: &str,
+    ) -> std::result::Result<Vec<u8>, DomainError>;
+
+    /// Elimina una versión concreta
+    async fn delete_version(
+        &self,
+        file_id: &str,
+        version_id: &str,
+    ) -> std::result::Result<(), DomainError>;
+
+    /// Elimina todo el historial de un archivo
+    async fn delete_all_versions(&self, file_id: &str) -> std::result::Result<(), DomainError>;
+
+    /// Bytes ocupados por las versiones de un archivo
+    async fn total_version_size(&self, file_id: &str) -> std::result::Result<u64, DomainError>;
+}
+
+/// Puerto primario para el historial de versiones de archivos
+#[async_trait]
+pub trait FileVersionUseCase: Send + Sync + 'static {
+    /// Archiva el contenido actual de un archivo antes de sobrescribirlo
+    async fn snapshot_current(&self, file_id: &str) -> Result<Option<FileVersionDto>>;
+
+    /// Lista las versiones de un archivo
+    async fn list_versions(&self, file_id: &str) -> Result<Vec<FileVersionDto>>;
+
+    /// Obtiene una versión y su contenido como stream
+    async fn get_version_stream(
+        &self,
+        file_id: &str,
+        version_id: &str,
+    ) -> Result<(FileVersionDto, VersionContentStream)>;
+
+    /// Restaura una versión como contenido actual del archivo.
+    /// El contenido reemplazado se conserva a su vez como versión.
+    async fn restore_version(&self, file_id: &str, version_id: &str) -> Result<()>;
+
+    /// Elimina todo el historial de un archivo
+    async fn delete_all_versions(&self, file_id: &str) -> Result<()>;
+}
@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+
+use crate::common::errors::DomainError;
+use crate::domain::entities::webdav_acl::{
+    AccessControlEntry, Principal, PrincipalMatch, PrivilegeSet,
+};
+
+/// Puerto primario para los principales y el control de acceso WebDAV (RFC 3744)
+#[async_trait]
+pub trait WebDavAclUseCase: Send + Sync + 'static {
+    /// ACL de un recurso por su ruta WebDAV
+    async fn acl(&self, path: &str) -> Vec<AccessControlEntry>;
+
+    /// Privilegios de un usuario sobre un recurso; los administradores los tienen todos
+    async fn privileges(&self, path: &str, username: &str, is_admin: bool) -> PrivilegeSet;
+
+    /// Principal de un usuario activo
+    async fn principal(&self, name: &str) -> Result<Principal, DomainError>;
+
+    /// Principales de todos los usuarios activos
+    async fn principals(&self) -> Result<Vec<Principal>, DomainError>;
+
+    /// Principales que cumplen todos los criterios, o alguno si `all_of` es falso
+    async fn search_principals(
+        &self,
+        criteria: &[PrincipalMatch],
+        all_of: bool,
+    ) -> Result<Vec<Principal>, DomainError>;
+}
@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+
+use crate::common::errors::DomainError;
+use crate::domain::entities::webdav_lock::{LockDepth, LockScope, LockedOperation, WebDavLock};
+
+/// Puerto secundario para persistir los bloqueos WebDAV activos
+#[async_trait]
+pub trait WebDavLockStoragePort: Send + Sync + 'static {
+    /// Carga todos los bloqueos guardados
+    async fn load_locks(&self) -> Result<Vec<WebDavLock>, DomainError>;
+
+    /// Sustituye los bloqueos guardados por `locks`
+    async fn save_locks(&self, locks: &[WebDavLock]) -> Result<(), DomainError>;
+}
+
+/// Petición de un bloqueo nuevo
+#[derive(Debug, Clone)]
+pub struct LockRequest {
+    pub path: String,
+    pub scope: LockScope,
+    pub depth: LockDepth,
+    /// Contenido de `DAV:owner`
+    pub owner: Option<String>,
+    /// Usuario autenticado que pide el bloqueo
+    pub principal: Option<String>,
+    /// Duración pedida en segundos; `None` usa la predeterminada
+    pub timeout_secs: Option<u64>,
+}
+
+/// Puerto primario para gestionar y hacer cumplir los bloqueos WebDAV
+#[async_trait]
+pub trait WebDavLockUseCase: Send + Sync + 'static {
+    /// Crea un bloqueo; falla con `Locked` si choca con otro existente
+    async fn lock(&self, request: LockRequest) -> Result<WebDavLock, DomainError>;
+
+    /// Renueva el bloqueo `token`, que debe aplicarse a `path`
+    async fn refresh(
+        &self,
+        path: &str,
+        token: &str,
+        principal: Option<&str>,
+        timeout_secs: Option<u64>,
+    ) -> Result<WebDavLock, DomainError>;
+
+    /// Libera el bloqueo `token`, que debe aplicarse a `path`
+    async fn unlock(
+        &self,
+        path: &str,
+        token: &str,
+        principal: Option<&str>,
+    ) -> Result<(), DomainError>;
+
+    /// Bloqueos activos que se aplican a `path`
+    async fn locks_covering(&self, path: &str) -> Vec<WebDavLock>;
+
+    /// Bloqueos activos con raíz en `path` o por debajo, o todos con `None`
+    async fn active_locks(&self, path: Option<&str>) -> Vec<WebDavLock>;
+
+    /// Comprueba que la petición presenta el token de cada bloqueo que
+    /// protege la escritura; falla con `Locked` si falta alguno
+    async fn check_write(
+        &self,
+        path: &str,
+        operation: LockedOperation,
+        tokens: &[String],
+        principal: Option<&str>,
+    ) -> Result<(), DomainError>;
+
+    /// Descarta los bloqueos de un recurso borrado o movido y de su contenido
+    async fn release_path(&self, path: &str);
+}
@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+use crate::common::errors::DomainError;
+use crate::domain::entities::webdav_property::{DeadProperty, PropertyUpdate};
+
+/// Puerto secundario para persistir las propiedades muertas de cada recurso.
+///
+/// Las propiedades se guardan por ID de recurso, que se conserva al renombrar
+/// o mover gracias al servicio de mapeo de IDs.
+#[async_trait]
+pub trait DeadPropertyStoragePort: Send + Sync + 'static {
+    /// Carga las propiedades de un recurso; vacío si no tiene ninguna
+    async fn load_properties(&self, resource_id: &str) -> Result<Vec<DeadProperty>, DomainError>;
+
+    /// Sustituye las propiedades de un recurso; una lista vacía las borra
+    async fn save_properties(
+        &self,
+        resource_id: &str,
+        properties: &[DeadProperty],
+    ) -> Result<(), DomainError>;
+}
+
+/// Puerto primario para leer y modificar propiedades muertas WebDAV
+#[async_trait]
+pub trait DeadPropertyUseCase: Send + Sync + 'static {
+    /// Propiedades de varios recursos a la vez, omitiendo los que no tienen
+    async fn get_properties_for(
+        &self,
+        resource_ids: &[String],
+    ) -> HashMap<String, Vec<DeadProperty>>;
+
+    /// Aplica los cambios en orden y de forma atómica: o se guardan todos o ninguno
+    async fn patch_properties(
+        &self,
+        resource_id: &str,
+        updates: Vec<PropertyUpdate>,
+    ) -> Result<Vec<DeadProperty>, DomainError>;
+
+    /// Copia las propiedades de un recurso a su copia
+    async fn copy_properties(&self, source_id: &str, target_id: &str) -> Result<(), DomainError>;
+
+    /// Borra las propiedades de recursos eliminados
+    async fn delete_properties(&self, resource_ids: &[String]) -> Result<(), DomainError>;
+}
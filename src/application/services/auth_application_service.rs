@@ -2,9 +2,12 @@ use crate::application::dtos::folder_dto::CreateFolderDto;
 use crate::application::dtos::user_dto::{
     AuthResponseDto, ChangePasswordDto, LoginDto, RefreshTokenDto, RegisterDto, UserDto,
 };
-use crate::application::ports::auth_ports::{SessionStoragePort, UserStoragePort};
+use crate::application::ports::auth_ports::{
+    AppPasswordStoragePort, SessionStoragePort, UserStoragePort,
+};
 use crate::application::ports::inbound::FolderUseCase;
 use crate::common::errors::{DomainError, ErrorKind};
+use crate::domain::entities::app_password::AppPassword;
 use crate::domain::entities::session::Session;
 use crate::domain::entities::user::{User, UserRole};
 use crate::domain::services::auth_service::AuthService;
@@ -15,6 +18,7 @@ pub struct AuthApplicationService {
     session_storage: Arc<dyn SessionStoragePort>,
     auth_service: Arc<AuthService>,
     folder_service: Option<Arc<dyn FolderUseCase>>,
+    app_passwords: Option<Arc<dyn AppPasswordStoragePort>>,
 }
 
 impl AuthApplicationService {
@@ -28,6 +32,7 @@ impl AuthApplicationService {
             session_storage,
             auth_service,
             folder_service: None,
+            app_passwords: None,
         }
     }
 
@@ -37,6 +42,12 @@ impl AuthApplicationService {
         self
     }
 
+    /// Configura el almacén de contraseñas de aplicación de los clientes de sincronización
+    pub fn with_app_passwords(mut self, app_passwords: Arc<dyn AppPasswordStoragePort>) -> Self {
+        self.app_passwords = Some(app_passwords);
+        self
+    }
+
     pub async fn register(&self, dto: RegisterDto) -> Result<UserDto, DomainError> {
         // Verificar usuario duplicado
         if self
@@ -197,11 +208,11 @@ impl AuthApplicationService {
         Ok(UserDto::from(created_user))
     }
 
-    pub async fn login(&self, dto: LoginDto) -> Result<AuthResponseDto, DomainError> {
-        // Buscar usuario
-        let mut user = self
+    /// Busca un usuario activo por nombre
+    async fn active_user(&self, username: &str) -> Result<User, DomainError> {
+        let user = self
             .user_storage
-            .get_user_by_username(&dto.username)
+            .get_user_by_username(username)
             .await
             .map_err(|_| {
                 DomainError::new(ErrorKind::AccessDenied, "Auth", "Credenciales inválidas")
@@ -216,8 +227,14 @@ impl AuthApplicationService {
             ));
         }
 
-        // Verificar contraseña
-        let is_valid = user.verify_password(&dto.password).map_err(|_| {
+        Ok(user)
+    }
+
+    /// Comprueba la contraseña de la cuenta de un usuario activo
+    async fn verify_account(&self, username: &str, password: &str) -> Result<User, DomainError> {
+        let user = self.active_user(username).await?;
+
+        let is_valid = user.verify_password(password).map_err(|_| {
             DomainError::new(ErrorKind::AccessDenied, "Auth", "Credenciales inválidas")
         })?;
 
@@ -229,6 +246,111 @@ impl AuthApplicationService {
             ));
         }
 
+        Ok(user)
+    }
+
+    /// Comprueba usuario y contraseña de la cuenta sin abrir una sesión
+    pub async fn check_credentials(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<UserDto, DomainError> {
+        Ok(UserDto::from(
+            self.verify_account(username, password).await?,
+        ))
+    }
+
+    /// Autenticación básica HTTP de los clientes de sincronización: acepta una
+    /// contraseña de aplicación del usuario o, si no lo es, la de la cuenta
+    pub async fn authenticate_basic(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<UserDto, DomainError> {
+        if let Some(app_passwords) = &self.app_passwords {
+            let user = self.active_user(username).await?;
+            let passwords = app_passwords.list_app_passwords(user.id()).await?;
+            if passwords
+                .iter()
+                .any(|app_password| app_password.verify(password))
+            {
+                return Ok(UserDto::from(user));
+            }
+        }
+        self.check_credentials(username, password).await
+    }
+
+    /// Crea una contraseña de aplicación para el usuario y la devuelve en claro,
+    /// la única vez que se puede obtener
+    pub async fn create_app_password(
+        &self,
+        user_id: &str,
+        name: &str,
+    ) -> Result<String, DomainError> {
+        let app_passwords = self.app_passwords.as_ref().ok_or_else(|| {
+            DomainError::operation_not_supported(
+                "AppPassword",
+                "No hay almacén de contraseñas de aplicación configurado",
+            )
+        })?;
+        let (app_password, password) = AppPassword::generate(user_id, name);
+        app_passwords.create_app_password(app_password).await?;
+        tracing::info!(
+            "Contraseña de aplicación creada para el usuario {}",
+            user_id
+        );
+        Ok(password)
+    }
+
+    /// Revoca la contraseña de aplicación con la que se autentica un cliente,
+    /// como hacen los clientes de Nextcloud al desconectar la cuenta
+    pub async fn revoke_app_password(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<(), DomainError> {
+        let app_passwords = self
+            .app_passwords
+            .as_ref()
+            .ok_or_else(|| DomainError::not_found("AppPassword", username))?;
+        let user = self.active_user(username).await?;
+        let app_password = app_passwords
+            .list_app_passwords(user.id())
+            .await?
+            .into_iter()
+            .find(|app_password| app_password.verify(password))
+            .ok_or_else(|| DomainError::not_found("AppPassword", username))?;
+        app_passwords
+            .delete_app_password(user.id(), &app_password.id)
+            .await?;
+        tracing::info!(
+            "Contraseña de aplicación revocada para el usuario {}",
+            user.id()
+        );
+        Ok(())
+    }
+
+    /// Usuario activo al que pertenece un token de acceso
+    pub async fn user_from_access_token(&self, token: &str) -> Result<UserDto, DomainError> {
+        let claims = self
+            .auth_service
+            .validate_token(token)
+            .map_err(DomainError::from)?;
+        let user = self.user_storage.get_user_by_id(&claims.sub).await?;
+        if !user.is_active() {
+            return Err(DomainError::new(
+                ErrorKind::AccessDenied,
+                "Auth",
+                "Cuenta desactivada",
+            ));
+        }
+        Ok(UserDto::from(user))
+    }
+
+    pub async fn login(&self, dto: LoginDto) -> Result<AuthResponseDto, DomainError> {
+        // Buscar usuario y verificar contraseña
+        let mut user = self.verify_account(&dto.username, &dto.password).await?;
+
         // Actualizar último login
         user.register_login();
         self.user_storage.update_user(user.clone()).await?;
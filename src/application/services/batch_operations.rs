@@ -166,7 +166,7 @@ impl BatchOperationService {
                 let permit = semaphore.acquire().await.unwrap();
 
                 let copy_result = file_service
-                    .move_file(&file_id, target_folder.clone())
+                    .copy_file(&file_id, target_folder.clone())
                     .await;
 
                 // Liberar el permiso explícitamente (también se libera al hacer drop)
@@ -734,61 +734,139 @@ impl BatchOperationService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mockall::mock;
-    use mockall::predicate::*;
-    use std::sync::Arc;
-    use tokio::sync::Mutex;
-
-    // Crear mocks para los servicios
-    mock! {
-        FileSvcMock {}
-
-        #[async_trait]
-        impl FileService for FileSvcMock {
-            async fn create_file(&self, name: String, folder_id: Option<String>, content_type: String, content: Vec<u8>) -> Result<File, DomainError>;
-            async fn get_file(&self, id: &str) -> Result<File, DomainError>;
-            async fn delete_file(&self, id: &str) -> Result<(), DomainError>;
-            async fn move_file(&self, id: &str, target_folder_id: Option<String>) -> Result<File, DomainError>;
-            async fn copy_file(&self, id: &str, target_folder_id: Option<String>) -> Result<File, DomainError>;
-            async fn list_files(&self, folder_id: Option<&str>) -> Result<Vec<File>, DomainError>;
-            async fn get_file_content(&self, id: &str) -> Result<Vec<u8>, DomainError>;
+    use crate::application::ports::outbound::{FileStoragePort, FolderStoragePort};
+    use crate::domain::entities::file::File;
+    use crate::domain::entities::folder::Folder;
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use futures::Stream;
+
+    type Result<T> = std::result::Result<T, DomainError>;
+
+    /// Almacenamiento que solo sabe borrar, y falla con `error-id`
+    struct DeletingFiles;
+
+    #[async_trait]
+    impl FileStoragePort for DeletingFiles {
+        async fn save_file(
+            &self,
+            _name: String,
+            _folder_id: Option<String>,
+            _content_type: String,
+            _content: Vec<u8>,
+        ) -> Result<File> {
+            unimplemented!()
         }
-    }
 
-    mock! {
-        FolderSvcMock {}
-
-        #[async_trait]
-        impl FolderService for FolderSvcMock {
-            async fn create_folder(&self, name: String, parent_id: Option<String>) -> Result<Folder, DomainError>;
-            async fn get_folder(&self, id: &str) -> Result<Folder, DomainError>;
-            async fn delete_folder(&self, id: &str) -> Result<(), DomainError>;
-            async fn delete_folder_recursive(&self, id: &str) -> Result<(), DomainError>;
-            async fn list_folders(&self, parent_id: Option<&str>) -> Result<Vec<Folder>, DomainError>;
-            async fn move_folder(&self, id: &str, target_parent_id: Option<String>) -> Result<Folder, DomainError>;
+        async fn get_file(&self, _id: &str) -> Result<File> {
+            unimplemented!()
         }
-    }
 
-    #[tokio::test]
-    async fn test_batch_delete_files() {
-        // Crear mocks
-        let mut file_service = MockFileSvcMock::new();
+        async fn list_files(&self, _folder_id: Option<&str>) -> Result<Vec<File>> {
+            unimplemented!()
+        }
 
-        // Configurar comportamiento esperado
-        file_service.expect_delete_file().times(3).returning(|id| {
+        async fn delete_file(&self, id: &str) -> Result<()> {
             if id == "error-id" {
-                Err(DomainError::not_found("FileService", "File not found"))
+                Err(DomainError::not_found("File", id))
             } else {
                 Ok(())
             }
-        });
+        }
+
+        async fn get_file_content(&self, _id: &str) -> Result<Vec<u8>> {
+            unimplemented!()
+        }
+
+        async fn get_file_stream(
+            &self,
+            _id: &str,
+        ) -> Result<Box<dyn Stream<Item = std::result::Result<Bytes, std::io::Error>> + Send>>
+        {
+            unimplemented!()
+        }
+
+        async fn move_file(&self, _file_id: &str, _folder_id: Option<String>) -> Result<File> {
+            unimplemented!()
+        }
+
+        async fn get_file_path(&self, _id: &str) -> Result<StoragePath> {
+            unimplemented!()
+        }
+
+        async fn get_parent_folder_id(&self, _path: &str) -> Result<String> {
+            unimplemented!()
+        }
+
+        async fn update_file_content(&self, _file_id: &str, _content: Vec<u8>) -> Result<()> {
+            unimplemented!()
+        }
+    }
+
+    /// Almacenamiento de carpetas que no se usa en estas pruebas
+    struct NoFolders;
+
+    #[async_trait]
+    impl FolderStoragePort for NoFolders {
+        async fn create_folder(&self, _name: String, _parent_id: Option<String>) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn get_folder(&self, _id: &str) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn get_folder_by_path(&self, _storage_path: &StoragePath) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn list_folders(&self, _parent_id: Option<&str>) -> Result<Vec<Folder>> {
+            unimplemented!()
+        }
+
+        async fn list_folders_paginated(
+            &self,
+            _parent_id: Option<&str>,
+            _offset: usize,
+            _limit: usize,
+            _include_total: bool,
+        ) -> Result<(Vec<Folder>, Option<usize>)> {
+            unimplemented!()
+        }
+
+        async fn rename_folder(&self, _id: &str, _new_name: String) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn move_folder(&self, _id: &str, _new_parent_id: Option<&str>) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn delete_folder(&self, _id: &str) -> Result<()> {
+            unimplemented!()
+        }
 
-        // Crear el servicio de batch con los mocks
-        let batch_service = BatchOperationService::new(
-            Arc::new(file_service),
-            Arc::new(MockFolderSvcMock::new()),
+        async fn folder_exists(&self, _storage_path: &StoragePath) -> Result<bool> {
+            unimplemented!()
+        }
+
+        async fn get_folder_path(&self, _id: &str) -> Result<StoragePath> {
+            unimplemented!()
+        }
+    }
+
+    fn batch_service() -> BatchOperationService {
+        BatchOperationService::new(
+            Arc::new(FileService::new(Arc::new(DeletingFiles))),
+            Arc::new(FolderService::new(Arc::new(NoFolders))),
             AppConfig::default(),
-        );
+        )
+    }
+
+    #[tokio::test]
+    async fn test_batch_delete_files() {
+        // Crear el servicio de batch sobre el almacenamiento falso
+        let batch_service = batch_service();
 
         // Ejecutar la operación de batch
         let file_ids = vec!["id1".to_string(), "id2".to_string(), "error-id".to_string()];
@@ -807,11 +885,7 @@ mod tests {
     #[tokio::test]
     async fn test_generic_batch_operation() {
         // Crear el servicio de batch
-        let batch_service = BatchOperationService::new(
-            Arc::new(MockFileSvcMock::new()),
-            Arc::new(MockFolderSvcMock::new()),
-            AppConfig::default(),
-        );
+        let batch_service = batch_service();
 
         // Definir una operación genérica de prueba
         let operation = |item: i32, semaphore: Arc<Semaphore>| async move {
@@ -823,7 +897,7 @@ mod tests {
                 Ok(item * 2)
             } else {
                 // Simular error para números impares
-                Err(DomainError::invalid_input("Test", "Odd number not allowed"))
+                Err(DomainError::validation_error("Odd number not allowed"))
             }
         };
 
@@ -1,15 +1,17 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use std::sync::Arc;
-use uuid::Uuid;
 
 use crate::application::dtos::calendar_dto::{
-    CalendarDto, CalendarEventDto, CreateCalendarDto, CreateEventDto, CreateEventICalDto,
-    UpdateCalendarDto, UpdateEventDto,
+    CalendarDto, CalendarEventDto, CalendarTaskDto, CreateCalendarDto, CreateEventICalDto,
+    CreateTaskDto, UpdateCalendarDto, UpdateTaskDto,
 };
 use crate::application::ports::calendar_ports::{CalendarStoragePort, CalendarUseCase};
 use crate::common::errors::{DomainError, ErrorKind};
-use crate::interfaces::middleware::auth::CurrentUser;
+use crate::domain::entities::icalendar::ICalComponent;
+use crate::domain::entities::recurrence;
+use crate::domain::entities::scheduling::{self, FreeBusyPeriod};
+use crate::domain::entities::timezone::{CalendarTimeZone, TimeZones, DEFAULT_TIMEZONE};
 
 pub struct CalendarService {
     calendar_storage: Arc<dyn CalendarStoragePort>,
@@ -19,38 +21,14 @@ impl CalendarService {
     pub fn new(calendar_storage: Arc<dyn CalendarStoragePort>) -> Self {
         Self { calendar_storage }
     }
-}
 
-#[async_trait]
-impl CalendarUseCase for CalendarService {
-    async fn create_calendar(
-        &self,
-        calendar: CreateCalendarDto,
-    ) -> Result<CalendarDto, DomainError> {
-        // This function requires the current user context which will come from middleware
-        // For now, we'll use a dummy implementation that needs to be completed
-
-        // In a real implementation, get user_id from current user context
-        let user_id = "current_user_id"; // This should come from middleware
-
-        self.calendar_storage
-            .create_calendar(calendar, user_id)
-            .await
-    }
-
-    async fn update_calendar(
+    /// Fails with `AccessDenied` unless the user owns the calendar or it is shared with them
+    async fn ensure_access(
         &self,
         calendar_id: &str,
-        update: UpdateCalendarDto,
-    ) -> Result<CalendarDto, DomainError> {
-        // In a real implementation, we would:
-        // 1. Get the current user ID from middleware
-        // 2. Verify that the user has access to this calendar
-        // 3. Update the calendar if they have permission
-
-        let user_id = "current_user_id"; // This should come from middleware
-
-        // Check if user has access
+        user_id: &str,
+        message: &str,
+    ) -> Result<(), DomainError> {
         let has_access = self
             .calendar_storage
             .check_calendar_access(calendar_id, user_id)
@@ -60,290 +38,280 @@ impl CalendarUseCase for CalendarService {
             return Err(DomainError::new(
                 ErrorKind::AccessDenied,
                 "Calendar",
-                "You don't have permission to update this calendar",
+                message,
             ));
         }
 
-        self.calendar_storage
-            .update_calendar(calendar_id, update)
-            .await
+        Ok(())
     }
 
-    async fn delete_calendar(&self, calendar_id: &str) -> Result<(), DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
+    /// Like `ensure_access`, but public calendars can be read by anyone
+    async fn ensure_readable(
+        &self,
+        calendar_id: &str,
+        user_id: &str,
+        message: &str,
+    ) -> Result<CalendarDto, DomainError> {
+        let calendar = self.calendar_storage.get_calendar(calendar_id).await?;
 
-        // Check if user has access
         let has_access = self
             .calendar_storage
             .check_calendar_access(calendar_id, user_id)
             .await?;
 
-        if !has_access {
+        if !has_access && !calendar.is_public {
             return Err(DomainError::new(
                 ErrorKind::AccessDenied,
                 "Calendar",
-                "You don't have permission to delete this calendar",
+                message,
             ));
         }
 
-        self.calendar_storage.delete_calendar(calendar_id).await
+        Ok(calendar)
     }
 
-    async fn get_calendar(&self, calendar_id: &str) -> Result<CalendarDto, DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
-        // Get the calendar
+    /// Fails with `InvalidInput` unless the calendar can hold the component
+    async fn ensure_component(
+        &self,
+        calendar_id: &str,
+        component: &str,
+    ) -> Result<(), DomainError> {
         let calendar = self.calendar_storage.get_calendar(calendar_id).await?;
 
-        // Check if user has access or if calendar is public
-        let has_access = self
-            .calendar_storage
-            .check_calendar_access(calendar_id, user_id)
-            .await?;
+        if !calendar.supports_component(component) {
+            return Err(DomainError::validation_error(format!(
+                "Calendar {} does not accept {} components",
+                calendar_id, component
+            )));
+        }
 
-        if !has_access && !calendar.is_public {
+        Ok(())
+    }
+
+    /// Only the owner can change or view sharing settings
+    async fn ensure_owner(
+        &self,
+        calendar_id: &str,
+        user_id: &str,
+        message: &str,
+    ) -> Result<(), DomainError> {
+        let calendar = self.calendar_storage.get_calendar(calendar_id).await?;
+
+        if calendar.owner_id != user_id {
             return Err(DomainError::new(
                 ErrorKind::AccessDenied,
                 "Calendar",
-                "You don't have permission to view this calendar",
+                message,
             ));
         }
 
-        Ok(calendar)
-    }
-
-    async fn list_my_calendars(&self) -> Result<Vec<CalendarDto>, DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
-        self.calendar_storage.list_calendars_by_owner(user_id).await
+        Ok(())
     }
+}
 
-    async fn list_shared_calendars(&self) -> Result<Vec<CalendarDto>, DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
+#[async_trait]
+impl CalendarUseCase for CalendarService {
+    async fn create_calendar(
+        &self,
+        calendar: CreateCalendarDto,
+        user_id: &str,
+    ) -> Result<CalendarDto, DomainError> {
         self.calendar_storage
-            .list_calendars_shared_with_user(user_id)
+            .create_calendar(calendar, user_id)
             .await
     }
 
-    async fn list_public_calendars(
+    async fn update_calendar(
         &self,
-        limit: Option<i64>,
-        offset: Option<i64>,
-    ) -> Result<Vec<CalendarDto>, DomainError> {
-        let limit = limit.unwrap_or(100);
-        let offset = offset.unwrap_or(0);
+        calendar_id: &str,
+        update: UpdateCalendarDto,
+        user_id: &str,
+    ) -> Result<CalendarDto, DomainError> {
+        self.ensure_access(
+            calendar_id,
+            user_id,
+            "You don't have permission to update this calendar",
+        )
+        .await?;
 
         self.calendar_storage
-            .list_public_calendars(limit, offset)
+            .update_calendar(calendar_id, update)
             .await
     }
 
-    async fn share_calendar(
+    async fn delete_calendar(&self, calendar_id: &str, user_id: &str) -> Result<(), DomainError> {
+        self.ensure_owner(
+            calendar_id,
+            user_id,
+            "Only the calendar owner can delete this calendar",
+        )
+        .await?;
+
+        self.calendar_storage.delete_calendar(calendar_id).await
+    }
+
+    async fn get_calendar(
         &self,
         calendar_id: &str,
         user_id: &str,
-        access_level: &str,
-    ) -> Result<(), DomainError> {
-        let current_user_id = "current_user_id"; // This should come from middleware
-
-        // Check if current user has access
-        let calendar = self.calendar_storage.get_calendar(calendar_id).await?;
-
-        // Only the owner can share the calendar
-        if calendar.owner_id != current_user_id {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "Only the calendar owner can change sharing settings",
-            ));
-        }
-
-        // Validate access_level
-        match access_level {
-            "read" | "write" | "owner" => {}
-            _ => {
-                return Err(DomainError::new(
-                    ErrorKind::InvalidInput,
-                    "Calendar",
-                    format!(
-                        "Invalid access level: {}. Valid values are: read, write, owner",
-                        access_level
-                    ),
-                ))
-            }
-        }
+    ) -> Result<CalendarDto, DomainError> {
+        self.ensure_readable(
+            calendar_id,
+            user_id,
+            "You don't have permission to view this calendar",
+        )
+        .await
+    }
 
-        self.calendar_storage
-            .share_calendar(calendar_id, user_id, access_level)
-            .await
+    async fn list_my_calendars(&self, user_id: &str) -> Result<Vec<CalendarDto>, DomainError> {
+        self.calendar_storage.list_calendars_by_owner(user_id).await
     }
 
-    async fn remove_calendar_sharing(
+    async fn set_calendar_property(
         &self,
         calendar_id: &str,
+        property_name: &str,
+        property_value: &str,
         user_id: &str,
     ) -> Result<(), DomainError> {
-        let current_user_id = "current_user_id"; // This should come from middleware
-
-        // Check if current user has access
-        let calendar = self.calendar_storage.get_calendar(calendar_id).await?;
-
-        // Only the owner can change sharing settings
-        if calendar.owner_id != current_user_id {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "Only the calendar owner can change sharing settings",
-            ));
-        }
+        self.ensure_access(
+            calendar_id,
+            user_id,
+            "You don't have permission to update this calendar",
+        )
+        .await?;
 
         self.calendar_storage
-            .remove_calendar_sharing(calendar_id, user_id)
+            .set_calendar_property(calendar_id, property_name, property_value)
             .await
     }
 
-    async fn get_calendar_shares(
+    async fn remove_calendar_property(
         &self,
         calendar_id: &str,
-    ) -> Result<Vec<(String, String)>, DomainError> {
-        let current_user_id = "current_user_id"; // This should come from middleware
-
-        // Check if current user has access
-        let calendar = self.calendar_storage.get_calendar(calendar_id).await?;
-
-        // Only the owner can view sharing settings
-        if calendar.owner_id != current_user_id {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "Only the calendar owner can view sharing settings",
-            ));
-        }
+        property_name: &str,
+        user_id: &str,
+    ) -> Result<(), DomainError> {
+        self.ensure_access(
+            calendar_id,
+            user_id,
+            "You don't have permission to update this calendar",
+        )
+        .await?;
 
-        self.calendar_storage.get_calendar_shares(calendar_id).await
+        self.calendar_storage
+            .remove_calendar_property(calendar_id, property_name)
+            .await
     }
 
-    async fn create_event(&self, event: CreateEventDto) -> Result<CalendarEventDto, DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
-        // Check if user has access to the calendar
-        let has_access = self
+    async fn get_default_timezone(&self, user_id: &str) -> Result<String, DomainError> {
+        Ok(self
             .calendar_storage
-            .check_calendar_access(&event.calendar_id, user_id)
-            .await?;
+            .get_default_timezone(user_id)
+            .await?
+            .unwrap_or_else(|| DEFAULT_TIMEZONE.to_string()))
+    }
 
-        if !has_access {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "You don't have permission to add events to this calendar",
-            ));
-        }
+    async fn set_default_timezone(
+        &self,
+        user_id: &str,
+        timezone: Option<&str>,
+    ) -> Result<(), DomainError> {
+        // Store the canonical zone name so later lookups find it
+        let timezone = match timezone {
+            Some(tzid) => Some(CalendarTimeZone::bundled(tzid).ok_or_else(|| {
+                DomainError::validation_error(format!("Unknown time zone: {}", tzid))
+            })?),
+            None => None,
+        };
 
-        self.calendar_storage.create_event(event).await
+        self.calendar_storage
+            .set_default_timezone(user_id, timezone.as_ref().map(CalendarTimeZone::tzid))
+            .await
     }
 
     async fn create_event_from_ical(
         &self,
         event: CreateEventICalDto,
+        user_id: &str,
     ) -> Result<CalendarEventDto, DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
-        // Check if user has access to the calendar
-        let has_access = self
-            .calendar_storage
-            .check_calendar_access(&event.calendar_id, user_id)
-            .await?;
-
-        if !has_access {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "You don't have permission to add events to this calendar",
-            ));
-        }
+        self.ensure_access(
+            &event.calendar_id,
+            user_id,
+            "You don't have permission to add events to this calendar",
+        )
+        .await?;
+        self.ensure_component(&event.calendar_id, "VEVENT").await?;
 
         self.calendar_storage.create_event_from_ical(event).await
     }
 
-    async fn update_event(
+    async fn update_event_from_ical(
         &self,
         event_id: &str,
-        update: UpdateEventDto,
+        ical_data: &str,
+        user_id: &str,
     ) -> Result<CalendarEventDto, DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
-        // Get the event to find its calendar
         let event = self.calendar_storage.get_event(event_id).await?;
 
-        // Check if user has access to the calendar
-        let has_access = self
-            .calendar_storage
-            .check_calendar_access(&event.calendar_id, user_id)
-            .await?;
-
-        if !has_access {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "You don't have permission to update events in this calendar",
-            ));
-        }
+        self.ensure_access(
+            &event.calendar_id,
+            user_id,
+            "You don't have permission to update events in this calendar",
+        )
+        .await?;
 
-        self.calendar_storage.update_event(event_id, update).await
+        self.calendar_storage
+            .update_event_from_ical(event_id, ical_data)
+            .await
     }
 
-    async fn delete_event(&self, event_id: &str) -> Result<(), DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
-        // Get the event to find its calendar
+    async fn delete_event(&self, event_id: &str, user_id: &str) -> Result<(), DomainError> {
         let event = self.calendar_storage.get_event(event_id).await?;
 
-        // Check if user has access to the calendar
-        let has_access = self
-            .calendar_storage
-            .check_calendar_access(&event.calendar_id, user_id)
-            .await?;
-
-        if !has_access {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "You don't have permission to delete events in this calendar",
-            ));
-        }
+        self.ensure_access(
+            &event.calendar_id,
+            user_id,
+            "You don't have permission to delete events in this calendar",
+        )
+        .await?;
 
         self.calendar_storage.delete_event(event_id).await
     }
 
-    async fn get_event(&self, event_id: &str) -> Result<CalendarEventDto, DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
-        // Get the event
+    async fn get_event(
+        &self,
+        event_id: &str,
+        user_id: &str,
+    ) -> Result<CalendarEventDto, DomainError> {
         let event = self.calendar_storage.get_event(event_id).await?;
 
-        // Check if user has access to the calendar
-        let has_access = self
-            .calendar_storage
-            .check_calendar_access(&event.calendar_id, user_id)
-            .await?;
+        self.ensure_readable(
+            &event.calendar_id,
+            user_id,
+            "You don't have permission to view events in this calendar",
+        )
+        .await?;
 
-        // Check if calendar is public
-        let calendar = self
-            .calendar_storage
-            .get_calendar(&event.calendar_id)
-            .await?;
+        Ok(event)
+    }
 
-        if !has_access && !calendar.is_public {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "You don't have permission to view events in this calendar",
-            ));
-        }
+    async fn get_event_by_uid(
+        &self,
+        calendar_id: &str,
+        ical_uid: &str,
+        user_id: &str,
+    ) -> Result<Option<CalendarEventDto>, DomainError> {
+        self.ensure_readable(
+            calendar_id,
+            user_id,
+            "You don't have permission to view events in this calendar",
+        )
+        .await?;
 
-        Ok(event)
+        self.calendar_storage
+            .find_event_by_uid(calendar_id, ical_uid)
+            .await
     }
 
     async fn list_events(
@@ -351,25 +319,14 @@ impl CalendarUseCase for CalendarService {
         calendar_id: &str,
         limit: Option<i64>,
         offset: Option<i64>,
+        user_id: &str,
     ) -> Result<Vec<CalendarEventDto>, DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
-        // Check if user has access to the calendar
-        let has_access = self
-            .calendar_storage
-            .check_calendar_access(calendar_id, user_id)
-            .await?;
-
-        // Check if calendar is public
-        let calendar = self.calendar_storage.get_calendar(calendar_id).await?;
-
-        if !has_access && !calendar.is_public {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "You don't have permission to view events in this calendar",
-            ));
-        }
+        self.ensure_readable(
+            calendar_id,
+            user_id,
+            "You don't have permission to view events in this calendar",
+        )
+        .await?;
 
         // Use pagination if provided
         if limit.is_some() || offset.is_some() {
@@ -391,28 +348,234 @@ impl CalendarUseCase for CalendarService {
         calendar_id: &str,
         start: DateTime<Utc>,
         end: DateTime<Utc>,
+        user_id: &str,
     ) -> Result<Vec<CalendarEventDto>, DomainError> {
-        let user_id = "current_user_id"; // This should come from middleware
-
-        // Check if user has access to the calendar
-        let has_access = self
+        self.ensure_readable(
+            calendar_id,
+            user_id,
+            "You don't have permission to view events in this calendar",
+        )
+        .await?;
+
+        let events = self
             .calendar_storage
-            .check_calendar_access(calendar_id, user_id)
+            .get_events_in_time_range(calendar_id, &start, &end)
             .await?;
 
-        // Check if calendar is public
-        let calendar = self.calendar_storage.get_calendar(calendar_id).await?;
+        // Expand recurring events into one entry per instance in the range
+        let mut instances = Vec::with_capacity(events.len());
+        for event in events {
+            match ICalComponent::parse(&event.ical_data) {
+                Ok(calendar) => {
+                    let zones = TimeZones::for_calendar(&calendar, event.timezone.as_deref());
+                    instances.extend(
+                        recurrence::occurrences(&calendar, &zones, start, end)
+                            .iter()
+                            .map(|occurrence| event.instance(occurrence)),
+                    )
+                }
+                Err(_) => instances.push(event),
+            }
+        }
+        instances.sort_by_key(|instance| instance.start_time);
 
-        if !has_access && !calendar.is_public {
-            return Err(DomainError::new(
-                ErrorKind::AccessDenied,
-                "Calendar",
-                "You don't have permission to view events in this calendar",
-            ));
+        Ok(instances)
+    }
+
+    async fn get_free_busy(
+        &self,
+        calendar_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        user_id: &str,
+    ) -> Result<Vec<FreeBusyPeriod>, DomainError> {
+        self.ensure_readable(
+            calendar_id,
+            user_id,
+            "You don't have permission to view events in this calendar",
+        )
+        .await?;
+
+        let events = self
+            .calendar_storage
+            .get_events_in_time_range(calendar_id, &start, &end)
+            .await?;
+
+        let mut periods = Vec::new();
+        for event in events {
+            let Ok(calendar) = ICalComponent::parse(&event.ical_data) else {
+                continue;
+            };
+            let zones = TimeZones::for_calendar(&calendar, event.timezone.as_deref());
+            for occurrence in recurrence::occurrences(&calendar, &zones, start, end) {
+                let (period_start, period_end) =
+                    (occurrence.start.max(start), occurrence.end.min(end));
+                if let Some(kind) = scheduling::busy_type(occurrence.component) {
+                    if period_start < period_end {
+                        periods.push(FreeBusyPeriod {
+                            start: period_start,
+                            end: period_end,
+                            kind,
+                        });
+                    }
+                }
+            }
         }
 
+        Ok(scheduling::merge_periods(periods))
+    }
+
+    async fn create_task(
+        &self,
+        calendar_id: &str,
+        task: CreateTaskDto,
+        user_id: &str,
+    ) -> Result<CalendarTaskDto, DomainError> {
+        self.ensure_access(
+            calendar_id,
+            user_id,
+            "You don't have permission to add tasks to this calendar",
+        )
+        .await?;
+        self.ensure_component(calendar_id, "VTODO").await?;
+
+        self.calendar_storage.create_task(calendar_id, task).await
+    }
+
+    async fn create_task_from_ical(
+        &self,
+        calendar_id: &str,
+        ical_data: &str,
+        user_id: &str,
+    ) -> Result<CalendarTaskDto, DomainError> {
+        self.ensure_access(
+            calendar_id,
+            user_id,
+            "You don't have permission to add tasks to this calendar",
+        )
+        .await?;
+        self.ensure_component(calendar_id, "VTODO").await?;
+
         self.calendar_storage
-            .get_events_in_time_range(calendar_id, &start, &end)
+            .create_task_from_ical(calendar_id, ical_data)
+            .await
+    }
+
+    async fn update_task(
+        &self,
+        task_id: &str,
+        update: UpdateTaskDto,
+        user_id: &str,
+    ) -> Result<CalendarTaskDto, DomainError> {
+        let task = self.calendar_storage.get_task(task_id).await?;
+
+        self.ensure_access(
+            &task.calendar_id,
+            user_id,
+            "You don't have permission to update tasks in this calendar",
+        )
+        .await?;
+
+        self.calendar_storage.update_task(task_id, update).await
+    }
+
+    async fn update_task_from_ical(
+        &self,
+        task_id: &str,
+        ical_data: &str,
+        user_id: &str,
+    ) -> Result<CalendarTaskDto, DomainError> {
+        let task = self.calendar_storage.get_task(task_id).await?;
+
+        self.ensure_access(
+            &task.calendar_id,
+            user_id,
+            "You don't have permission to update tasks in this calendar",
+        )
+        .await?;
+
+        self.calendar_storage
+            .update_task_from_ical(task_id, ical_data)
+            .await
+    }
+
+    async fn delete_task(&self, task_id: &str, user_id: &str) -> Result<(), DomainError> {
+        let task = self.calendar_storage.get_task(task_id).await?;
+
+        self.ensure_access(
+            &task.calendar_id,
+            user_id,
+            "You don't have permission to delete tasks in this calendar",
+        )
+        .await?;
+
+        self.calendar_storage.delete_task(task_id).await
+    }
+
+    async fn get_task(&self, task_id: &str, user_id: &str) -> Result<CalendarTaskDto, DomainError> {
+        let task = self.calendar_storage.get_task(task_id).await?;
+
+        self.ensure_readable(
+            &task.calendar_id,
+            user_id,
+            "You don't have permission to view tasks in this calendar",
+        )
+        .await?;
+
+        Ok(task)
+    }
+
+    async fn get_task_by_uid(
+        &self,
+        calendar_id: &str,
+        ical_uid: &str,
+        user_id: &str,
+    ) -> Result<Option<CalendarTaskDto>, DomainError> {
+        self.ensure_readable(
+            calendar_id,
+            user_id,
+            "You don't have permission to view tasks in this calendar",
+        )
+        .await?;
+
+        self.calendar_storage
+            .find_task_by_uid(calendar_id, ical_uid)
+            .await
+    }
+
+    async fn list_tasks(
+        &self,
+        calendar_id: &str,
+        user_id: &str,
+    ) -> Result<Vec<CalendarTaskDto>, DomainError> {
+        self.ensure_readable(
+            calendar_id,
+            user_id,
+            "You don't have permission to view tasks in this calendar",
+        )
+        .await?;
+
+        self.calendar_storage
+            .list_tasks_by_calendar(calendar_id)
+            .await
+    }
+
+    async fn get_tasks_in_range(
+        &self,
+        calendar_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        user_id: &str,
+    ) -> Result<Vec<CalendarTaskDto>, DomainError> {
+        self.ensure_readable(
+            calendar_id,
+            user_id,
+            "You don't have permission to view tasks in this calendar",
+        )
+        .await?;
+
+        self.calendar_storage
+            .get_tasks_in_time_range(calendar_id, &start, &end)
             .await
     }
 }
@@ -0,0 +1,287 @@
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::application::ports::upload_ports::{
+    AssembledUpload, ChunkedUploadStoragePort, ChunkedUploadUseCase, UploadedChunk,
+};
+use crate::common::config::ResumableUploadConfig;
+use crate::common::errors::{DomainError, ErrorKind, Result};
+
+/// Longest chunk name accepted; Nextcloud numbers chunks up to 10000
+const MAX_CHUNK_NAME_LEN: usize = 20;
+
+/**
+ * Service for the chunked uploads of Nextcloud clients (chunking v2).
+ *
+ * Clients create a transfer, PUT numbered chunks into it and finally MOVE
+ * its `.file` member onto the destination. The chunks are kept by the
+ * storage port until the transfer is assembled, aborted or expires; the
+ * assembled content is written by the caller through the regular WebDAV
+ * path, which applies quotas, locks and preconditions.
+ */
+pub struct ChunkedUploadService {
+    storage: Arc<dyn ChunkedUploadStoragePort>,
+    expiration_secs: u64,
+}
+
+impl ChunkedUploadService {
+    /// Creates a new chunked upload service, expiring transfers like
+    /// resumable uploads
+    pub fn new(storage: Arc<dyn ChunkedUploadStoragePort>, config: &ResumableUploadConfig) -> Self {
+        Self {
+            storage,
+            expiration_secs: config.expiration_hours.max(1) * 3600,
+        }
+    }
+
+    /// Chunks are named after their position in the file
+    fn chunk_number(name: &str) -> Option<u64> {
+        if name.is_empty()
+            || name.len() > MAX_CHUNK_NAME_LEN
+            || !name.bytes().all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        name.parse().ok()
+    }
+}
+
+#[async_trait]
+impl ChunkedUploadUseCase for ChunkedUploadService {
+    async fn begin(&self, owner: &str, transfer: &str) -> Result<()> {
+        self.storage.create_transfer(owner, transfer).await
+    }
+
+    async fn chunks(&self, owner: &str, transfer: &str) -> Result<Vec<UploadedChunk>> {
+        let mut chunks = self.storage.list_chunks(owner, transfer).await?;
+        chunks.retain(|chunk| Self::chunk_number(&chunk.name).is_some());
+        chunks.sort_by_key(|chunk| Self::chunk_number(&chunk.name));
+        Ok(chunks)
+    }
+
+    async fn store_chunk(
+        &self,
+        owner: &str,
+        transfer: &str,
+        chunk: &str,
+        content: &[u8],
+    ) -> Result<()> {
+        if Self::chunk_number(chunk).is_none() {
+            return Err(DomainError::new(
+                ErrorKind::InvalidInput,
+                "Upload",
+                format!("Invalid chunk name: {}", chunk),
+            ));
+        }
+        self.storage
+            .write_chunk(owner, transfer, chunk, content)
+            .await
+    }
+
+    async fn assemble(
+        &self,
+        owner: &str,
+        transfer: &str,
+        expected_length: Option<u64>,
+    ) -> Result<AssembledUpload> {
+        let chunks = self.chunks(owner, transfer).await?;
+        let total: u64 = chunks.iter().map(|chunk| chunk.size).sum();
+        if let Some(expected) = expected_length {
+            if expected != total {
+                return Err(DomainError::new(
+                    ErrorKind::InvalidInput,
+                    "Upload",
+                    format!(
+                        "Upload {} has {} bytes but the client announced {}",
+                        transfer, total, expected
+                    ),
+                ));
+            }
+        }
+
+        // Each chunk is opened once the previous one has been read
+        let storage = self.storage.clone();
+        let (owner, transfer) = (owner.to_string(), transfer.to_string());
+        let content = futures::stream::iter(chunks)
+            .then(move |chunk| {
+                let (storage, owner, transfer) = (storage.clone(), owner.clone(), transfer.clone());
+                async move {
+                    storage
+                        .read_chunk(&owner, &transfer, &chunk.name)
+                        .await
+                        .map_err(std::io::Error::other)
+                }
+            })
+            .try_flatten();
+        Ok(AssembledUpload {
+            size: total,
+            content: Box::pin(content),
+        })
+    }
+
+    async fn abort(&self, owner: &str, transfer: &str) -> Result<()> {
+        self.storage.delete_transfer(owner, transfer).await
+    }
+
+    async fn cleanup_expired(&self) -> Result<usize> {
+        let removed = self
+            .storage
+            .delete_stale_transfers(self.expiration_secs)
+            .await?;
+        if removed > 0 {
+            info!("Removed {} abandoned chunked uploads", removed);
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::ports::storage_ports::collect_byte_stream;
+    use crate::application::ports::upload_ports::UploadChunkStream;
+    use bytes::Bytes;
+    use std::collections::HashMap;
+    use tokio::sync::Mutex;
+
+    /// In-memory transfers keyed by owner and transfer ID
+    #[derive(Default)]
+    struct MemoryStorage {
+        transfers: Mutex<HashMap<(String, String), HashMap<String, Vec<u8>>>>,
+    }
+
+    #[async_trait]
+    impl ChunkedUploadStoragePort for MemoryStorage {
+        async fn create_transfer(&self, owner: &str, transfer: &str) -> Result<()> {
+            let mut transfers = self.transfers.lock().await;
+            let key = (owner.to_string(), transfer.to_string());
+            if transfers.contains_key(&key) {
+                return Err(DomainError::already_exists("Upload", transfer));
+            }
+            transfers.insert(key, HashMap::new());
+            Ok(())
+        }
+
+        async fn list_chunks(&self, owner: &str, transfer: &str) -> Result<Vec<UploadedChunk>> {
+            let transfers = self.transfers.lock().await;
+            let chunks = transfers
+                .get(&(owner.to_string(), transfer.to_string()))
+                .ok_or_else(|| DomainError::not_found("Upload", transfer))?;
+            Ok(chunks
+                .iter()
+                .map(|(name, content)| UploadedChunk {
+                    name: name.clone(),
+                    size: content.len() as u64,
+                })
+                .collect())
+        }
+
+        async fn write_chunk(
+            &self,
+            owner: &str,
+            transfer: &str,
+            chunk: &str,
+            content: &[u8],
+        ) -> Result<()> {
+            let mut transfers = self.transfers.lock().await;
+            transfers
+                .get_mut(&(owner.to_string(), transfer.to_string()))
+                .ok_or_else(|| DomainError::not_found("Upload", transfer))?
+                .insert(chunk.to_string(), content.to_vec());
+            Ok(())
+        }
+
+        async fn read_chunk(
+            &self,
+            owner: &str,
+            transfer: &str,
+            chunk: &str,
+        ) -> Result<UploadChunkStream> {
+            let transfers = self.transfers.lock().await;
+            let content = transfers
+                .get(&(owner.to_string(), transfer.to_string()))
+                .and_then(|chunks| chunks.get(chunk).cloned())
+                .ok_or_else(|| DomainError::not_found("Upload", chunk))?;
+            Ok(Box::pin(futures::stream::iter(vec![Ok(Bytes::from(
+                content,
+            ))])))
+        }
+
+        async fn delete_transfer(&self, owner: &str, transfer: &str) -> Result<()> {
+            let mut transfers = self.transfers.lock().await;
+            transfers
+                .remove(&(owner.to_string(), transfer.to_string()))
+                .map(|_| ())
+                .ok_or_else(|| DomainError::not_found("Upload", transfer))
+        }
+
+        async fn delete_stale_transfers(&self, _max_age_secs: u64) -> Result<usize> {
+            Ok(0)
+        }
+    }
+
+    fn service() -> ChunkedUploadService {
+        ChunkedUploadService::new(
+            Arc::new(MemoryStorage::default()),
+            &ResumableUploadConfig::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_chunks_are_assembled_in_numeric_order() {
+        let service = service();
+        service.begin("alice", "t1").await.unwrap();
+        // Offsets of the legacy naming sort wrongly as strings
+        service
+            .store_chunk("alice", "t1", "10", b"c")
+            .await
+            .unwrap();
+        service.store_chunk("alice", "t1", "9", b"b").await.unwrap();
+        service.store_chunk("alice", "t1", "1", b"a").await.unwrap();
+
+        let names: Vec<_> = service
+            .chunks("alice", "t1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|chunk| chunk.name)
+            .collect();
+        assert_eq!(names, vec!["1", "9", "10"]);
+        let assembled = service.assemble("alice", "t1", Some(3)).await.unwrap();
+        assert_eq!(assembled.size, 3);
+        assert_eq!(
+            collect_byte_stream(assembled.content).await.unwrap(),
+            b"abc"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_or_invalid_uploads_are_rejected() {
+        let service = service();
+        assert!(service.store_chunk("alice", "t1", "1", b"a").await.is_err());
+        service.begin("alice", "t1").await.unwrap();
+
+        let err = service
+            .store_chunk("alice", "t1", ".file", b"a")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidInput);
+
+        service
+            .store_chunk("alice", "t1", "1", b"abc")
+            .await
+            .unwrap();
+        let err = service
+            .assemble("alice", "t1", Some(5))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, ErrorKind::InvalidInput);
+        assert!(service.assemble("bob", "t1", None).await.is_err());
+
+        service.abort("alice", "t1").await.unwrap();
+        assert!(service.chunks("alice", "t1").await.is_err());
+    }
+}
@@ -0,0 +1,473 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::application::ports::content_index_ports::{
+    ContentIndexStoragePort, ContentIndexUseCase, TextExtractorPort,
+};
+use crate::application::ports::outbound::{FileStoragePort, FolderStoragePort};
+use crate::common::config::ContentIndexConfig;
+use crate::common::errors::{ErrorKind, Result};
+use crate::domain::entities::content_index::{
+    query_terms, ContentMatch, IndexedDocument, SHARED_OWNER,
+};
+
+/// Cambios que pueden esperar en cola antes de frenar a quien los encola
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Documentos indexados durante un recorrido completo entre cada persistencia
+const CRAWL_FLUSH_INTERVAL: usize = 100;
+
+/// Cambio pendiente en el índice
+enum IndexJob {
+    Index(String),
+    Remove(String),
+    RemoveFolder(String),
+    IndexFolder(Option<String>),
+}
+
+/**
+ * Keeps the full-text index of document contents up to date.
+ *
+ * File changes are queued and applied in order by a single worker task, so a
+ * delete that follows an upload never races with it. Text extraction runs on
+ * the blocking pool, and the index is persisted whenever the queue drains
+ * instead of after every change.
+ */
+pub struct ContentIndexService {
+    indexer: Arc<ContentIndexer>,
+    jobs: mpsc::Sender<IndexJob>,
+}
+
+/// Parte del servicio que usa el worker
+struct ContentIndexer {
+    file_repository: Arc<dyn FileStoragePort>,
+    folder_repository: Arc<dyn FolderStoragePort>,
+    extractor: Arc<dyn TextExtractorPort>,
+    storage: Arc<dyn ContentIndexStoragePort>,
+    max_file_size: u64,
+    max_text_chars: usize,
+}
+
+impl ContentIndexService {
+    /// Creates the service and starts its worker task
+    pub fn new(
+        file_repository: Arc<dyn FileStoragePort>,
+        folder_repository: Arc<dyn FolderStoragePort>,
+        extractor: Arc<dyn TextExtractorPort>,
+        storage: Arc<dyn ContentIndexStoragePort>,
+        config: &ContentIndexConfig,
+    ) -> Self {
+        let indexer = Arc::new(ContentIndexer {
+            file_repository,
+            folder_repository,
+            extractor,
+            storage,
+            max_file_size: config.max_file_size,
+            max_text_chars: config.max_text_chars,
+        });
+
+        let (jobs, mut receiver) = mpsc::channel::<IndexJob>(QUEUE_CAPACITY);
+        let worker = indexer.clone();
+        tokio::spawn(async move {
+            while let Some(job) = receiver.recv().await {
+                worker.run(job).await;
+                if receiver.is_empty() {
+                    worker.flush().await;
+                }
+            }
+        });
+
+        Self { indexer, jobs }
+    }
+
+    /// Indexes the whole storage in the background if the index is empty,
+    /// e.g. the first time the server starts with content search enabled
+    pub async fn index_existing_files(&self) -> Result<()> {
+        if self.indexer.storage.document_count().await? == 0 {
+            info!("Content index is empty, indexing existing files");
+            self.index_folder(None).await;
+        }
+        Ok(())
+    }
+
+    async fn enqueue(&self, job: IndexJob) {
+        if self.jobs.send(job).await.is_err() {
+            warn!("Content index worker stopped, change not indexed");
+        }
+    }
+}
+
+impl ContentIndexer {
+    async fn run(&self, job: IndexJob) {
+        let result = match job {
+            IndexJob::Index(file_id) => self.index(&file_id).await,
+            IndexJob::Remove(file_id) => self.storage.remove_document(&file_id).await.map(|_| ()),
+            IndexJob::RemoveFolder(path) => {
+                self.storage.remove_under_path(&path).await.map(|removed| {
+                    debug!(
+                        "Removed {} documents under {} from content index",
+                        removed, path
+                    )
+                })
+            }
+            IndexJob::IndexFolder(folder_id) => self.crawl(folder_id).await,
+        };
+        if let Err(e) = result {
+            warn!("Content index update failed: {}", e);
+        }
+    }
+
+    async fn flush(&self) {
+        if let Err(e) = self.storage.flush().await {
+            warn!("Could not persist content index: {}", e);
+        }
+    }
+
+    /// Extracts and indexes the text of a file, or drops it from the index if
+    /// it is gone or has no indexable text any more
+    async fn index(&self, file_id: &str) -> Result<()> {
+        let file = match self.file_repository.get_file(file_id).await {
+            Ok(file) => file,
+            Err(e) if e.kind == ErrorKind::NotFound => {
+                self.storage.remove_document(file_id).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let name = file.name().to_string();
+        let mime_type = file.mime_type().to_string();
+        if !self.extractor.supports(&name, &mime_type)
+            || (self.max_file_size > 0 && file.size() > self.max_file_size)
+        {
+            self.storage.remove_document(file_id).await?;
+            return Ok(());
+        }
+
+        let content = self.file_repository.get_file_content(file_id).await?;
+        let extractor = self.extractor.clone();
+        let (text_name, text_mime) = (name.clone(), mime_type.clone());
+        let text = tokio::task::spawn_blocking(move || {
+            extractor.extract(&text_name, &text_mime, &content)
+        })
+        .await
+        .ok()
+        .flatten()
+        .filter(|text| !text.is_empty());
+
+        let Some(mut text) = text else {
+            self.storage.remove_document(file_id).await?;
+            return Ok(());
+        };
+        if let Some((cut, _)) = text.char_indices().nth(self.max_text_chars) {
+            text.truncate(cut);
+        }
+
+        let document = IndexedDocument::new(
+            file.id().to_string(),
+            name,
+            file.path_string().to_string(),
+            mime_type,
+            file.size(),
+            file.modified_at(),
+        );
+        debug!("Indexed content of {} ({} chars)", file_id, text.len());
+        self.storage.upsert_document(document, text).await
+    }
+
+    /// Indexes every file under a folder, depth first
+    async fn crawl(&self, folder_id: Option<String>) -> Result<()> {
+        let mut pending = vec![folder_id];
+        let mut indexed = 0;
+
+        while let Some(folder_id) = pending.pop() {
+            for file in self
+                .file_repository
+                .list_files(folder_id.as_deref())
+                .await?
+            {
+                if let Err(e) = self.index(file.id()).await {
+                    warn!("Could not index content of {}: {}", file.id(), e);
+                }
+                indexed += 1;
+                if indexed % CRAWL_FLUSH_INTERVAL == 0 {
+                    self.flush().await;
+                }
+            }
+            for folder in self
+                .folder_repository
+                .list_folders(folder_id.as_deref())
+                .await?
+            {
+                pending.push(Some(folder.id().to_string()));
+            }
+        }
+
+        info!("Content index crawl finished, {} files visited", indexed);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ContentIndexUseCase for ContentIndexService {
+    async fn index_file(&self, file_id: &str) {
+        self.enqueue(IndexJob::Index(file_id.to_string())).await;
+    }
+
+    async fn remove_file(&self, file_id: &str) {
+        self.enqueue(IndexJob::Remove(file_id.to_string())).await;
+    }
+
+    async fn remove_folder(&self, folder_path: &str) {
+        self.enqueue(IndexJob::RemoveFolder(folder_path.to_string()))
+            .await;
+    }
+
+    async fn index_folder(&self, folder_id: Option<&str>) {
+        self.enqueue(IndexJob::IndexFolder(folder_id.map(String::from)))
+            .await;
+    }
+
+    async fn search(&self, owner: &str, query: &str, limit: usize) -> Result<Vec<ContentMatch>> {
+        let terms = query_terms(query);
+        if terms.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Cada usuario ve sus documentos y los que están fuera de las carpetas personales
+        let mut owners = vec![SHARED_OWNER.to_string()];
+        if owner != SHARED_OWNER {
+            owners.push(owner.to_string());
+        }
+        self.indexer.storage.search(&owners, &terms, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::ports::test_doubles::FakeFiles;
+    use crate::domain::entities::content_index::Snippet;
+    use crate::domain::entities::folder::Folder;
+    use crate::domain::services::path_service::StoragePath;
+    use std::collections::HashMap;
+    use std::sync::Mutex as StdMutex;
+    use std::time::Duration;
+
+    /// Archivos de texto en la raíz con su ID, ruta y contenido
+    fn files_with(files: Vec<(&str, &str, &str)>) -> Arc<FakeFiles> {
+        let fake = FakeFiles::new();
+        for (id, path, content) in files {
+            fake.add(id, path, "text/plain", content.as_bytes(), 1);
+        }
+        fake
+    }
+
+    /// Almacenamiento sin subcarpetas
+    struct NoFolders;
+
+    #[async_trait]
+    impl FolderStoragePort for NoFolders {
+        async fn create_folder(&self, _name: String, _parent_id: Option<String>) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn get_folder(&self, _id: &str) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn get_folder_by_path(&self, _storage_path: &StoragePath) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn list_folders(&self, _parent_id: Option<&str>) -> Result<Vec<Folder>> {
+            Ok(Vec::new())
+        }
+
+        async fn list_folders_paginated(
+            &self,
+            _parent_id: Option<&str>,
+            _offset: usize,
+            _limit: usize,
+            _include_total: bool,
+        ) -> Result<(Vec<Folder>, Option<usize>)> {
+            unimplemented!()
+        }
+
+        async fn rename_folder(&self, _id: &str, _new_name: String) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn move_folder(&self, _id: &str, _new_parent_id: Option<&str>) -> Result<Folder> {
+            unimplemented!()
+        }
+
+        async fn delete_folder(&self, _id: &str) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn folder_exists(&self, _storage_path: &StoragePath) -> Result<bool> {
+            unimplemented!()
+        }
+
+        async fn get_folder_path(&self, _id: &str) -> Result<StoragePath> {
+            unimplemented!()
+        }
+    }
+
+    /// Extrae el contenido tal cual de los `.txt`
+    struct PlainText;
+
+    impl TextExtractorPort for PlainText {
+        fn supports(&self, name: &str, _mime_type: &str) -> bool {
+            name.ends_with(".txt")
+        }
+
+        fn extract(&self, _name: &str, _mime_type: &str, content: &[u8]) -> Option<String> {
+            String::from_utf8(content.to_vec()).ok()
+        }
+    }
+
+    /// Índice en memoria que registra los documentos y las búsquedas
+    #[derive(Default)]
+    struct MemoryIndex {
+        documents: StdMutex<HashMap<String, (IndexedDocument, String)>>,
+        searched_owners: StdMutex<Vec<String>>,
+        flushes: StdMutex<usize>,
+    }
+
+    #[async_trait]
+    impl ContentIndexStoragePort for MemoryIndex {
+        async fn upsert_document(&self, document: IndexedDocument, text: String) -> Result<()> {
+            self.documents
+                .lock()
+                .unwrap()
+                .insert(document.file_id.clone(), (document, text));
+            Ok(())
+        }
+
+        async fn remove_document(&self, file_id: &str) -> Result<bool> {
+            Ok(self.documents.lock().unwrap().remove(file_id).is_some())
+        }
+
+        async fn remove_under_path(&self, path_prefix: &str) -> Result<usize> {
+            let mut documents = self.documents.lock().unwrap();
+            let before = documents.len();
+            let prefix = format!("/{}/", path_prefix.trim_matches('/'));
+            documents.retain(|_, (document, _)| !document.path.starts_with(&prefix));
+            Ok(before - documents.len())
+        }
+
+        async fn search(
+            &self,
+            owners: &[String],
+            terms: &[String],
+            _limit: usize,
+        ) -> Result<Vec<ContentMatch>> {
+            *self.searched_owners.lock().unwrap() = owners.to_vec();
+            Ok(self
+                .documents
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|(document, text)| {
+                    owners.contains(&document.owner) && terms.iter().all(|t| text.contains(t))
+                })
+                .map(|(document, _)| ContentMatch {
+                    document: document.clone(),
+                    score: 1.0,
+                    snippet: Snippet::default(),
+                })
+                .collect())
+        }
+
+        async fn document_count(&self) -> Result<usize> {
+            Ok(self.documents.lock().unwrap().len())
+        }
+
+        async fn flush(&self) -> Result<()> {
+            *self.flushes.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
+
+    fn service(files: Arc<FakeFiles>, index: Arc<MemoryIndex>) -> ContentIndexService {
+        ContentIndexService::new(
+            files,
+            Arc::new(NoFolders),
+            Arc::new(PlainText),
+            index,
+            &ContentIndexConfig::default(),
+        )
+    }
+
+    /// Espera a que el worker deje el índice con `expected` documentos y lo persista
+    async fn wait_for_documents(index: &MemoryIndex, expected: usize) {
+        for _ in 0..100 {
+            let flushed = *index.flushes.lock().unwrap() > 0;
+            if flushed && index.documents.lock().unwrap().len() == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("content index did not reach {} documents", expected);
+    }
+
+    #[tokio::test]
+    async fn test_index_and_remove_files() {
+        let files = files_with(vec![
+            ("a", "Mi Carpeta - alice/notes.txt", "presupuesto anual"),
+            ("b", "Mi Carpeta - alice/photo.jpg", "presupuesto"),
+        ]);
+        let index = Arc::new(MemoryIndex::default());
+        let service = service(files.clone(), index.clone());
+
+        service.index_file("a").await;
+        service.index_file("b").await;
+        wait_for_documents(&index, 1).await;
+        assert_eq!(index.documents.lock().unwrap()["a"].0.owner, "alice");
+
+        // Un archivo que ya no existe sale del índice al reindexarlo
+        files.remove("a");
+        service.index_file("a").await;
+        wait_for_documents(&index, 0).await;
+    }
+
+    #[tokio::test]
+    async fn test_search_is_scoped_to_owner_and_shared() {
+        let files = files_with(vec![
+            ("a", "Mi Carpeta - alice/a.txt", "informe trimestral"),
+            ("b", "Mi Carpeta - bob/b.txt", "informe trimestral"),
+            ("c", "Equipo/c.txt", "informe trimestral"),
+        ]);
+        let index = Arc::new(MemoryIndex::default());
+        let service = service(files, index.clone());
+
+        service.index_existing_files().await.unwrap();
+        wait_for_documents(&index, 3).await;
+
+        let mut found: Vec<String> = service
+            .search("alice", "Informe", 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.document.file_id)
+            .collect();
+        found.sort();
+        assert_eq!(found, vec!["a", "c"]);
+        assert_eq!(
+            *index.searched_owners.lock().unwrap(),
+            vec![SHARED_OWNER.to_string(), "alice".to_string()]
+        );
+
+        service.remove_folder("Mi Carpeta - alice").await;
+        wait_for_documents(&index, 2).await;
+        assert_eq!(
+            service.search("alice", "informe", 10).await.unwrap().len(),
+            1
+        );
+        assert!(service.search("alice", "  ", 10).await.unwrap().is_empty());
+    }
+}
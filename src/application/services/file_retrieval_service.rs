@@ -51,4 +51,15 @@ impl FileRetrievalUseCase for FileRetrievalService {
     ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
         self.file_repository.get_file_stream(id).await
     }
+
+    async fn get_file_range_stream(
+        &self,
+        id: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
+        self.file_repository
+            .get_file_range_stream(id, start, end)
+            .await
+    }
 }
@@ -3,12 +3,19 @@ use std::sync::Arc;
 use thiserror::Error;
 
 use crate::application::dtos::file_dto::FileDto;
-use crate::application::ports::inbound::FileUseCase;
+use crate::application::ports::content_index_ports::ContentIndexUseCase;
+use crate::application::ports::inbound::{FileDtoStream, FileUseCase};
 use crate::application::ports::outbound::FileStoragePort;
+use crate::application::ports::sync_ports::ChangeLogUseCase;
+use crate::application::ports::thumbnail_ports::ThumbnailUseCase;
+use crate::application::ports::version_ports::FileVersionUseCase;
 use crate::common::errors::DomainError;
+use crate::domain::entities::sync_change::ChangeKind;
+use crate::domain::entities::thumbnail::supports_mime_type;
 use crate::domain::repositories::file_repository::FileRepositoryError;
 use bytes::Bytes;
-use futures::Stream;
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
 
 /**
  * File service-specific error types.
@@ -124,12 +131,84 @@ pub type FileServiceResult<T> = Result<T, FileServiceError>;
 pub struct FileService {
     /// Repository responsible for file storage operations
     file_repository: Arc<dyn FileStoragePort>,
+
+    /// Optional version history, archived before content is overwritten
+    version_service: Option<Arc<dyn FileVersionUseCase>>,
+
+    /// Optional image thumbnails, refreshed when content changes
+    thumbnail_service: Option<Arc<dyn ThumbnailUseCase>>,
+
+    /// Optional full-text index, updated when content or location changes
+    content_index: Option<Arc<dyn ContentIndexUseCase>>,
+
+    /// Optional change log read by WebDAV sync clients
+    change_log: Option<Arc<dyn ChangeLogUseCase>>,
 }
 
 impl FileService {
     /// Creates a new file service
     pub fn new(file_repository: Arc<dyn FileStoragePort>) -> Self {
-        Self { file_repository }
+        Self {
+            file_repository,
+            version_service: None,
+            thumbnail_service: None,
+            content_index: None,
+            change_log: None,
+        }
+    }
+
+    /// Keeps previous versions of files when their content is overwritten
+    pub fn with_version_service(mut self, version_service: Arc<dyn FileVersionUseCase>) -> Self {
+        self.version_service = Some(version_service);
+        self
+    }
+
+    /// Generates thumbnails of new images and discards them when content changes
+    pub fn with_thumbnail_service(mut self, thumbnail_service: Arc<dyn ThumbnailUseCase>) -> Self {
+        self.thumbnail_service = Some(thumbnail_service);
+        self
+    }
+
+    /// Keeps the text of documents searchable as they are written, moved or deleted
+    pub fn with_content_index(mut self, content_index: Arc<dyn ContentIndexUseCase>) -> Self {
+        self.content_index = Some(content_index);
+        self
+    }
+
+    /// Records every create, update, delete and move for incremental sync
+    pub fn with_change_log(mut self, change_log: Arc<dyn ChangeLogUseCase>) -> Self {
+        self.change_log = Some(change_log);
+        self
+    }
+
+    /// Appends a change to the sync log
+    async fn record_change(&self, kind: ChangeKind, file: &FileDto, previous_path: Option<&str>) {
+        if let Some(change_log) = &self.change_log {
+            change_log
+                .record(kind, &file.id, false, &file.path, previous_path)
+                .await;
+        }
+    }
+
+    /// Queues a file for (re)indexing of its text
+    async fn refresh_content_index(&self, file_id: &str) {
+        if let Some(content_index) = &self.content_index {
+            content_index.index_file(file_id).await;
+        }
+    }
+
+    /// Queues thumbnail generation for a file with new content
+    async fn refresh_thumbnails(&self, file: &FileDto, replaced: bool) {
+        if let Some(thumbnails) = &self.thumbnail_service {
+            if replaced {
+                if let Err(e) = thumbnails.invalidate(&file.id).await {
+                    tracing::warn!("Could not discard thumbnails of {}: {}", file.id, e);
+                }
+            }
+            if supports_mime_type(&file.mime_type) {
+                thumbnails.schedule_generation(&file.id);
+            }
+        }
     }
 
     /// Creates a stub implementation for testing and middleware
@@ -219,7 +298,11 @@ impl FileService {
             .save_file(name, folder_id, content_type, content)
             .await
             .map_err(FileServiceError::from)?;
-        Ok(FileDto::from(file))
+        let file = FileDto::from(file);
+        self.refresh_thumbnails(&file, false).await;
+        self.refresh_content_index(&file.id).await;
+        self.record_change(ChangeKind::Created, &file, None).await;
+        Ok(file)
     }
 
     /// Gets a file by ID
@@ -266,15 +349,7 @@ impl FileService {
         content: &[u8],
         content_type: &str,
     ) -> FileServiceResult<FileDto> {
-        // Get parent folder ID if parent path is not empty
-        let parent_id = if !parent_path.is_empty() {
-            match self.file_repository.get_parent_folder_id(parent_path).await {
-                Ok(id) => Some(id),
-                Err(_) => None, // If parent doesn't exist, use root
-            }
-        } else {
-            None // Root folder
-        };
+        let parent_id = self.parent_folder_id(parent_path).await;
 
         // Save the file with the provided filename and parent folder
         let file = self
@@ -288,7 +363,105 @@ impl FileService {
             .await
             .map_err(FileServiceError::from)?;
 
-        Ok(FileDto::from(file))
+        Ok(self.file_created(FileDto::from(file)).await)
+    }
+
+    /// Creates a file at a specific path, writing its content as it arrives
+    pub async fn create_file_stream(
+        &self,
+        parent_path: &str,
+        filename: &str,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+        content_type: &str,
+    ) -> FileServiceResult<FileDto> {
+        let parent_id = self.parent_folder_id(parent_path).await;
+        let file = self
+            .file_repository
+            .save_file_stream(
+                filename.to_string(),
+                parent_id,
+                content_type.to_string(),
+                stream,
+            )
+            .await
+            .map_err(FileServiceError::from)?;
+
+        Ok(self.file_created(FileDto::from(file)).await)
+    }
+
+    /// Gets the ID of the folder at a WebDAV parent path, or the root when
+    /// the path is empty or does not exist
+    async fn parent_folder_id(&self, parent_path: &str) -> Option<String> {
+        if parent_path.is_empty() {
+            return None;
+        }
+        self.file_repository
+            .get_parent_folder_id(parent_path)
+            .await
+            .ok()
+    }
+
+    /// Refreshes the derived data of a new file and records its creation
+    async fn file_created(&self, file: FileDto) -> FileDto {
+        self.refresh_thumbnails(&file, false).await;
+        self.refresh_content_index(&file.id).await;
+        self.record_change(ChangeKind::Created, &file, None).await;
+        file
+    }
+
+    /// Replaces the content of an existing file and returns it with its new version
+    pub async fn update_file_content(
+        &self,
+        id: &str,
+        content: &[u8],
+    ) -> FileServiceResult<FileDto> {
+        let file = self.get_file(id).await?;
+        self.replace_content(&file, content).await?;
+        self.get_file(id).await
+    }
+
+    async fn replace_content(&self, file: &FileDto, content: &[u8]) -> FileServiceResult<()> {
+        self.archive_current(file).await;
+
+        // Update the file content
+        self.file_repository
+            .update_file_content(&file.id, content.to_vec())
+            .await
+            .map_err(FileServiceError::from)?;
+
+        self.content_replaced(file).await;
+        Ok(())
+    }
+
+    async fn replace_content_stream(
+        &self,
+        file: &FileDto,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> FileServiceResult<()> {
+        self.archive_current(file).await;
+        self.file_repository
+            .update_file_content_stream(&file.id, stream)
+            .await
+            .map_err(FileServiceError::from)?;
+
+        self.content_replaced(file).await;
+        Ok(())
+    }
+
+    /// Archives the current content of a file before replacing it
+    async fn archive_current(&self, file: &FileDto) {
+        if let Some(versions) = &self.version_service {
+            if let Err(e) = versions.snapshot_current(&file.id).await {
+                tracing::warn!("Could not archive previous version of {}: {}", file.id, e);
+            }
+        }
+    }
+
+    /// Refreshes the derived data of a file with new content and records the change
+    async fn content_replaced(&self, file: &FileDto) {
+        self.refresh_thumbnails(file, true).await;
+        self.refresh_content_index(&file.id).await;
+        self.record_change(ChangeKind::Updated, file, None).await;
     }
 
     /// Updates an existing file (needed for WebDAV)
@@ -296,11 +469,8 @@ impl FileService {
         // First, try to get the file by path
         match self.get_file_by_path(path).await {
             Ok(file) => {
-                // Update the file content
-                self.file_repository
-                    .update_file_content(&file.id, content.to_vec())
-                    .await
-                    .map_err(FileServiceError::from)
+                self.replace_content(&file, content).await?;
+                Ok(())
             }
             Err(_) => {
                 // If file doesn't exist, extract filename and parent path and create it
@@ -320,6 +490,28 @@ impl FileService {
         }
     }
 
+    /// Replaces the content of the file at a path, or creates it, writing the
+    /// content as it arrives (needed for WebDAV)
+    pub async fn update_file_stream(
+        &self,
+        path: &str,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> FileServiceResult<()> {
+        match self.get_file_by_path(path).await {
+            Ok(file) => self.replace_content_stream(&file, stream).await,
+            Err(_) => {
+                let path = path.trim_start_matches('/').trim_end_matches('/');
+                let (parent_path, filename) = match path.rfind('/') {
+                    Some(idx) => (&path[..idx], &path[idx + 1..]),
+                    None => ("", path),
+                };
+                self.create_file_stream(parent_path, filename, stream, "application/octet-stream")
+                    .await?;
+                Ok(())
+            }
+        }
+    }
+
     /// Lists files in a folder
     pub async fn list_files(&self, folder_id: Option<&str>) -> FileServiceResult<Vec<FileDto>> {
         let files = self
@@ -332,10 +524,38 @@ impl FileService {
 
     /// Deletes a file
     pub async fn delete_file(&self, id: &str) -> FileServiceResult<()> {
+        // The path is gone after deletion, but sync clients need it
+        let deleted = match &self.change_log {
+            Some(_) => self.get_file(id).await.ok(),
+            None => None,
+        };
+
         self.file_repository
             .delete_file(id)
             .await
-            .map_err(FileServiceError::from)
+            .map_err(FileServiceError::from)?;
+
+        if let Some(versions) = &self.version_service {
+            if let Err(e) = versions.delete_all_versions(id).await {
+                tracing::warn!("Could not delete version history of {}: {}", id, e);
+            }
+        }
+
+        if let Some(thumbnails) = &self.thumbnail_service {
+            if let Err(e) = thumbnails.invalidate(id).await {
+                tracing::warn!("Could not delete thumbnails of {}: {}", id, e);
+            }
+        }
+
+        if let Some(content_index) = &self.content_index {
+            content_index.remove_file(id).await;
+        }
+
+        if let Some(file) = deleted {
+            self.record_change(ChangeKind::Deleted, &file, None).await;
+        }
+
+        Ok(())
     }
 
     /// Gets file content as bytes - use for small files only
@@ -357,6 +577,19 @@ impl FileService {
             .map_err(FileServiceError::from)
     }
 
+    /// Gets the byte range `[start, end)` of a file as a stream
+    pub async fn get_file_range_stream(
+        &self,
+        id: &str,
+        start: u64,
+        end: u64,
+    ) -> FileServiceResult<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>> {
+        self.file_repository
+            .get_file_range_stream(id, start, end)
+            .await
+            .map_err(FileServiceError::from)
+    }
+
     /// Moves a file to a new folder using filesystem operations directly
     pub async fn move_file(
         &self,
@@ -369,6 +602,11 @@ impl FileService {
             folder_id
         );
 
+        let previous_path = match &self.change_log {
+            Some(_) => self.get_file(file_id).await.ok().map(|file| file.path),
+            None => None,
+        };
+
         // Use the efficient repository implementation that uses rename
         let moved_file = self
             .file_repository
@@ -386,7 +624,42 @@ impl FileService {
             moved_file.folder_id()
         );
 
-        Ok(FileDto::from(moved_file))
+        // The indexed path decides who can find the file
+        self.refresh_content_index(moved_file.id()).await;
+
+        let moved_file = FileDto::from(moved_file);
+        self.record_change(ChangeKind::Moved, &moved_file, previous_path.as_deref())
+            .await;
+        Ok(moved_file)
+    }
+
+    /// Copies a file into another folder, keeping its name
+    pub async fn copy_file(
+        &self,
+        file_id: &str,
+        folder_id: Option<String>,
+    ) -> FileServiceResult<FileDto> {
+        tracing::info!(
+            "Copying file with ID: {} to folder: {:?}",
+            file_id,
+            folder_id
+        );
+
+        let copied_file = self
+            .file_repository
+            .copy_file(file_id, folder_id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Error copying file (ID: {}): {}", file_id, e);
+                FileServiceError::from(e)
+            })?;
+
+        let copied_file = FileDto::from(copied_file);
+        self.refresh_thumbnails(&copied_file, false).await;
+        self.refresh_content_index(&copied_file.id).await;
+        self.record_change(ChangeKind::Created, &copied_file, None)
+            .await;
+        Ok(copied_file)
     }
 }
 
@@ -434,12 +707,55 @@ impl FileUseCase for FileService {
             .map_err(DomainError::from)
     }
 
+    async fn create_file_stream(
+        &self,
+        parent_path: &str,
+        filename: &str,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+        content_type: &str,
+    ) -> Result<FileDto, DomainError> {
+        FileService::create_file_stream(self, parent_path, filename, stream, content_type)
+            .await
+            .map_err(DomainError::from)
+    }
+
+    async fn update_file_stream(
+        &self,
+        path: &str,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<(), DomainError> {
+        FileService::update_file_stream(self, path, stream)
+            .await
+            .map_err(DomainError::from)
+    }
+
     async fn list_files(&self, folder_id: Option<&str>) -> Result<Vec<FileDto>, DomainError> {
         FileService::list_files(self, folder_id)
             .await
             .map_err(DomainError::from)
     }
 
+    async fn list_files_paginated(
+        &self,
+        folder_id: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<FileDto>, DomainError> {
+        let files = self
+            .file_repository
+            .list_files_paginated(folder_id, offset, limit)
+            .await?;
+        Ok(files.into_iter().map(FileDto::from).collect())
+    }
+
+    async fn list_files_stream<'a>(
+        &'a self,
+        folder_id: Option<&str>,
+    ) -> Result<FileDtoStream<'a>, DomainError> {
+        let files = self.file_repository.list_files_stream(folder_id).await?;
+        Ok(Box::pin(files.map(|file| file.map(FileDto::from))))
+    }
+
     async fn delete_file(&self, id: &str) -> Result<(), DomainError> {
         FileService::delete_file(self, id)
             .await
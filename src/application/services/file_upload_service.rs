@@ -1,11 +1,17 @@
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures::Stream;
+use std::pin::Pin;
 use std::sync::Arc;
 
 use crate::application::dtos::file_dto::FileDto;
+use crate::application::ports::auth_ports::UserStoragePort;
+use crate::application::ports::content_index_ports::ContentIndexUseCase;
 use crate::application::ports::file_ports::FileUploadUseCase;
 use crate::application::ports::storage_ports::FileWritePort;
 use crate::application::ports::storage_ports::StorageUsagePort;
 use crate::common::errors::DomainError;
+use crate::domain::entities::file::File;
 use tracing::{debug, warn};
 
 /// Helper function to extract username from folder path string
@@ -21,14 +27,16 @@ fn extract_username_from_path(path: &str) -> Option<String> {
         return None;
     }
 
-    // Trim and return as owned String
-    Some(parts[1].trim().to_string())
+    // Keep only the home folder segment, trimmed and owned
+    Some(parts[1].split('/').next().unwrap_or("").trim().to_string())
 }
 
 /// Servicio para operaciones de subida de archivos
 pub struct FileUploadService {
     file_repository: Arc<dyn FileWritePort>,
     storage_usage_service: Option<Arc<dyn StorageUsagePort>>,
+    user_repository: Option<Arc<dyn UserStoragePort>>,
+    content_index: Option<Arc<dyn ContentIndexUseCase>>,
 }
 
 impl FileUploadService {
@@ -37,6 +45,8 @@ impl FileUploadService {
         Self {
             file_repository,
             storage_usage_service: None,
+            user_repository: None,
+            content_index: None,
         }
     }
 
@@ -49,31 +59,23 @@ impl FileUploadService {
         self
     }
 
-    /// Crea un stub para pruebas
-    pub fn default_stub() -> Self {
-        Self {
-            file_repository: Arc::new(
-                crate::infrastructure::repositories::FileFsWriteRepository::default_stub(),
-            ),
-            storage_usage_service: None,
-        }
+    /// Rechaza las subidas que superen la cuota del dueño de la carpeta
+    pub fn with_quota_enforcement(mut self, user_repository: Arc<dyn UserStoragePort>) -> Self {
+        self.user_repository = Some(user_repository);
+        self
     }
-}
 
-#[async_trait]
-impl FileUploadUseCase for FileUploadService {
-    async fn upload_file(
-        &self,
-        name: String,
-        folder_id: Option<String>,
-        content_type: String,
-        content: Vec<u8>,
-    ) -> Result<FileDto, DomainError> {
-        // Upload the file
-        let file = self
-            .file_repository
-            .save_file(name, folder_id, content_type, content)
-            .await?;
+    /// Indexa el texto de los archivos subidos para la búsqueda por contenido
+    pub fn with_content_index(mut self, content_index: Arc<dyn ContentIndexUseCase>) -> Self {
+        self.content_index = Some(content_index);
+        self
+    }
+
+    /// Indexes a newly saved file and refreshes its owner's storage usage
+    async fn register_upload(&self, file: File) -> Result<FileDto, DomainError> {
+        if let Some(content_index) = &self.content_index {
+            content_index.index_file(file.id()).await;
+        }
 
         // Extract the owner's user ID if available
         // We could make this more explicit by adding a user_id parameter
@@ -118,4 +120,102 @@ impl FileUploadUseCase for FileUploadService {
 
         Ok(FileDto::from(file))
     }
+
+    /// Crea un stub para pruebas
+    pub fn default_stub() -> Self {
+        Self {
+            file_repository: Arc::new(
+                crate::infrastructure::repositories::FileFsWriteRepository::default_stub(),
+            ),
+            storage_usage_service: None,
+            user_repository: None,
+            content_index: None,
+        }
+    }
+}
+
+#[async_trait]
+impl FileUploadUseCase for FileUploadService {
+    async fn upload_file(
+        &self,
+        name: String,
+        folder_id: Option<String>,
+        content_type: String,
+        content: Vec<u8>,
+    ) -> Result<FileDto, DomainError> {
+        self.check_upload_quota(folder_id.as_deref(), content.len() as u64)
+            .await?;
+
+        // Upload the file
+        let file = self
+            .file_repository
+            .save_file(name, folder_id, content_type, content)
+            .await?;
+
+        self.register_upload(file).await
+    }
+
+    async fn upload_file_stream(
+        &self,
+        name: String,
+        folder_id: Option<String>,
+        content_type: String,
+        size: u64,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<FileDto, DomainError> {
+        self.check_upload_quota(folder_id.as_deref(), size).await?;
+
+        let file = self
+            .file_repository
+            .save_file_stream(name, folder_id, content_type, stream)
+            .await?;
+
+        self.register_upload(file).await
+    }
+
+    async fn check_upload_quota(
+        &self,
+        folder_id: Option<&str>,
+        size: u64,
+    ) -> Result<(), DomainError> {
+        let (Some(user_repository), Some(folder_id)) = (&self.user_repository, folder_id) else {
+            return Ok(());
+        };
+
+        // Only folders inside a user's home count against a quota
+        let folder_path = match self.file_repository.get_folder_path_str(folder_id).await {
+            Ok(path) => path,
+            Err(e) => {
+                warn!(
+                    "Could not resolve folder {} for quota check: {}",
+                    folder_id, e
+                );
+                return Ok(());
+            }
+        };
+        let Some(username) = extract_username_from_path(&folder_path) else {
+            return Ok(());
+        };
+
+        let user = user_repository.get_user_by_username(&username).await?;
+        let quota = user.storage_quota_bytes();
+        if quota <= 0 {
+            return Ok(());
+        }
+
+        let required = user.storage_used_bytes().max(0) as u64 + size;
+        if required > quota as u64 {
+            return Err(DomainError::quota_exceeded(
+                "User",
+                format!(
+                    "Storage quota of user {} exceeded: {} of {} bytes used, {} more requested",
+                    username,
+                    user.storage_used_bytes(),
+                    quota,
+                    size
+                ),
+            ));
+        }
+        Ok(())
+    }
 }
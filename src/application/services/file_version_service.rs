@@ -0,0 +1,275 @@
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+use crate::application::dtos::file_version_dto::FileVersionDto;
+use crate::application::ports::outbound::FileStoragePort;
+use crate::application::ports::version_ports::{
+    FileVersionStoragePort, FileVersionUseCase, VersionContentStream,
+};
+use crate::common::config::VersionRetentionConfig;
+use crate::common::errors::Result;
+use crate::domain::entities::file_version::FileVersion;
+
+/**
+ * Application service for file version history.
+ *
+ * Archives the current content of a file before it is overwritten, applies the
+ * configured retention policy after each new version, and restores old versions
+ * by writing them back through the regular file storage port.
+ */
+pub struct FileVersionService {
+    /// Storage for the live files being versioned
+    file_repository: Arc<dyn FileStoragePort>,
+
+    /// Storage for archived versions
+    version_repository: Arc<dyn FileVersionStoragePort>,
+
+    /// Retention policy applied after every new version
+    retention: VersionRetentionConfig,
+}
+
+impl FileVersionService {
+    pub fn new(
+        file_repository: Arc<dyn FileStoragePort>,
+        version_repository: Arc<dyn FileVersionStoragePort>,
+        retention: VersionRetentionConfig,
+    ) -> Self {
+        Self {
+            file_repository,
+            version_repository,
+            retention,
+        }
+    }
+
+    /// Deletes the versions of a file that fall outside the retention policy
+    async fn apply_retention(&self, file_id: &str) -> Result<()> {
+        let versions = self.version_repository.list_versions(file_id).await?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        for version_id in select_versions_to_prune(&self.retention, &versions, now) {
+            debug!("Pruning version {} of file {}", version_id, file_id);
+            self.version_repository
+                .delete_version(file_id, &version_id)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Selects the versions that the retention policy no longer keeps.
+///
+/// Thinning rules split the timeline into buckets of `interval_secs` once a
+/// version is older than `after_secs`, keeping only the newest version of each
+/// bucket. Versions older than `keep_days` are dropped, and finally only the
+/// `keep_count` newest survivors are kept.
+pub fn select_versions_to_prune(
+    policy: &VersionRetentionConfig,
+    versions: &[FileVersion],
+    now: u64,
+) -> Vec<String> {
+    let mut ordered: Vec<&FileVersion> = versions.iter().collect();
+    ordered.sort_by(|a, b| {
+        b.created_at
+            .cmp(&a.created_at)
+            .then(b.version_number.cmp(&a.version_number))
+    });
+
+    let mut rules = policy.thinning.clone();
+    rules.sort_by_key(|rule| rule.after_secs);
+
+    let max_age = u64::from(policy.keep_days) * 86400;
+    let mut seen_buckets = HashSet::new();
+    let mut kept = 0usize;
+    let mut prune = Vec::new();
+
+    for version in ordered {
+        let age = now.saturating_sub(version.created_at);
+
+        if policy.keep_days > 0 && age > max_age {
+            prune.push(version.id.clone());
+            continue;
+        }
+
+        let rule = rules
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, rule)| age >= rule.after_secs);
+        if let Some((index, rule)) = rule {
+            let bucket = (index, version.created_at / rule.interval_secs.max(1));
+            if !seen_buckets.insert(bucket) {
+                prune.push(version.id.clone());
+                continue;
+            }
+        }
+
+        if policy.keep_count > 0 && kept >= policy.keep_count {
+            prune.push(version.id.clone());
+            continue;
+        }
+        kept += 1;
+    }
+
+    prune
+}
+
+#[async_trait]
+impl FileVersionUseCase for FileVersionService {
+    async fn snapshot_current(&self, file_id: &str) -> Result<Option<FileVersionDto>> {
+        let file = self.file_repository.get_file(file_id).await?;
+
+        // Empty files are usually placeholders created right before the first
+        // real write (e.g. WebDAV clients), so there is nothing worth keeping
+        if file.size() == 0 {
+            return Ok(None);
+        }
+
+        let content = self.file_repository.get_file_stream(file_id).await?;
+        let version = self
+            .version_repository
+            .store_version(&file, Box::into_pin(content))
+            .await?;
+
+        info!(
+            "Archived version {} of file {} ({} bytes)",
+            version.version_number, file_id, version.size
+        );
+
+        if let Err(e) = self.apply_retention(file_id).await {
+            warn!("Failed to apply version retention for {}: {}", file_id, e);
+        }
+
+        Ok(Some(FileVersionDto::from(version)))
+    }
+
+    async fn list_versions(&self, file_id: &str) -> Result<Vec<FileVersionDto>> {
+        // Make sure the file itself still exists
+        self.file_repository.get_file(file_id).await?;
+
+        let versions = self.version_repository.list_versions(file_id).await?;
+        Ok(versions.into_iter().map(FileVersionDto::from).collect())
+    }
+
+    async fn get_version_stream(
+        &self,
+        file_id: &str,
+        version_id: &str,
+    ) -> Result<(FileVersionDto, VersionContentStream)> {
+        let version = self
+            .version_repository
+            .get_version(file_id, version_id)
+            .await?;
+        let stream = self
+            .version_repository
+            .get_version_stream(file_id, version_id)
+            .await?;
+        Ok((FileVersionDto::from(version), stream))
+    }
+
+    async fn restore_version(&self, file_id: &str, version_id: &str) -> Result<()> {
+        // Read the old content first: archiving the current one may prune it
+        let content = self
+            .version_repository
+            .get_version_content(file_id, version_id)
+            .await?;
+
+        self.snapshot_current(file_id).await?;
+        self.file_repository
+            .update_file_content(file_id, content)
+            .await?;
+
+        info!("Restored version {} of file {}", version_id, file_id);
+        Ok(())
+    }
+
+    async fn delete_all_versions(&self, file_id: &str) -> Result<()> {
+        self.version_repository.delete_all_versions(file_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::config::VersionThinningRule;
+
+    const HOUR: u64 = 3600;
+    const DAY: u64 = 86400;
+
+    fn version(number: u32, created_at: u64) -> FileVersion {
+        let mut version = FileVersion::new(
+            "file".to_string(),
+            "file.txt".to_string(),
+            number,
+            10,
+            "text/plain".to_string(),
+            created_at,
+            created_at,
+        );
+        version.id = format!("v{}", number);
+        version
+    }
+
+    fn policy(
+        keep_count: usize,
+        keep_days: u32,
+        thinning: Vec<VersionThinningRule>,
+    ) -> VersionRetentionConfig {
+        VersionRetentionConfig {
+            keep_count,
+            keep_days,
+            thinning,
+        }
+    }
+
+    #[test]
+    fn test_keep_count_prunes_oldest() {
+        let now = 10 * DAY;
+        let versions: Vec<_> = (1..=5)
+            .map(|n| version(n, now - (6 - n as u64) * 60))
+            .collect();
+
+        let pruned = select_versions_to_prune(&policy(3, 0, vec![]), &versions, now);
+
+        assert_eq!(pruned, vec!["v2".to_string(), "v1".to_string()]);
+    }
+
+    #[test]
+    fn test_keep_days_prunes_expired() {
+        let now = 100 * DAY;
+        let versions = vec![version(1, now - 40 * DAY), version(2, now - 10 * DAY)];
+
+        let pruned = select_versions_to_prune(&policy(0, 30, vec![]), &versions, now);
+
+        assert_eq!(pruned, vec!["v1".to_string()]);
+    }
+
+    #[test]
+    fn test_thinning_keeps_newest_per_bucket() {
+        let now = 100 * DAY;
+        let base = now - 2 * DAY - (now - 2 * DAY) % HOUR;
+        let versions = vec![
+            // Same hour, older than a day: only v2 survives
+            version(1, base + 60),
+            version(2, base + 120),
+            // Next hour
+            version(3, base + HOUR + 60),
+            // Recent versions are never thinned
+            version(4, now - 60),
+            version(5, now - 30),
+        ];
+        let rules = vec![VersionThinningRule {
+            after_secs: DAY,
+            interval_secs: HOUR,
+        }];
+
+        let pruned = select_versions_to_prune(&policy(0, 0, rules), &versions, now);
+
+        assert_eq!(pruned, vec!["v1".to_string()]);
+    }
+}
@@ -3,8 +3,10 @@ use crate::application::dtos::folder_dto::{
 };
 use crate::application::ports::inbound::FolderUseCase;
 use crate::application::ports::outbound::FolderStoragePort;
+use crate::application::ports::sync_ports::ChangeLogUseCase;
 use crate::application::transactions::storage_transaction::StorageTransaction;
 use crate::common::errors::{DomainError, ErrorKind};
+use crate::domain::entities::sync_change::ChangeKind;
 use crate::domain::services::path_service::StoragePath;
 use async_trait::async_trait;
 use std::sync::Arc;
@@ -12,12 +14,37 @@ use std::sync::Arc;
 /// Implementación del caso de uso para operaciones de carpetas
 pub struct FolderService {
     folder_storage: Arc<dyn FolderStoragePort>,
+    /// Registro de cambios opcional para clientes de sincronización WebDAV
+    change_log: Option<Arc<dyn ChangeLogUseCase>>,
 }
 
 impl FolderService {
     /// Crea un nuevo servicio de carpetas
     pub fn new(folder_storage: Arc<dyn FolderStoragePort>) -> Self {
-        Self { folder_storage }
+        Self {
+            folder_storage,
+            change_log: None,
+        }
+    }
+
+    /// Registra las altas, bajas, renombrados y movimientos de carpetas
+    pub fn with_change_log(mut self, change_log: Arc<dyn ChangeLogUseCase>) -> Self {
+        self.change_log = Some(change_log);
+        self
+    }
+
+    /// Añade un cambio al registro de sincronización
+    async fn record_change(
+        &self,
+        kind: ChangeKind,
+        folder: &FolderDto,
+        previous_path: Option<&str>,
+    ) {
+        if let Some(change_log) = &self.change_log {
+            change_log
+                .record(kind, &folder.id, true, &folder.path, previous_path)
+                .await;
+        }
     }
 
     /// Creates a stub implementation for testing and middleware
@@ -121,8 +148,9 @@ impl FolderUseCase for FolderService {
                 )
             })?;
 
-        // Convertir a DTO
-        Ok(FolderDto::from(folder))
+        let folder = FolderDto::from(folder);
+        self.record_change(ChangeKind::Created, &folder, None).await;
+        Ok(folder)
     }
 
     /// Obtiene una carpeta por su ID
@@ -289,7 +317,14 @@ impl FolderUseCase for FolderService {
             )
         })?;
 
-        Ok(FolderDto::from(folder))
+        let folder = FolderDto::from(folder);
+        self.record_change(
+            ChangeKind::Moved,
+            &folder,
+            Some(existing_folder.path_string()),
+        )
+        .await;
+        Ok(folder)
     }
 
     /// Mueve una carpeta a un nuevo padre
@@ -374,13 +409,20 @@ impl FolderUseCase for FolderService {
             )
         })?;
 
-        Ok(FolderDto::from(folder))
+        let folder = FolderDto::from(folder);
+        self.record_change(
+            ChangeKind::Moved,
+            &folder,
+            Some(source_folder.path_string()),
+        )
+        .await;
+        Ok(folder)
     }
 
     /// Elimina una carpeta
     async fn delete_folder(&self, id: &str) -> Result<(), DomainError> {
         // Verificar que la carpeta existe
-        let _folder = self.folder_storage.get_folder(id).await.map_err(|e| {
+        let folder = self.folder_storage.get_folder(id).await.map_err(|e| {
             DomainError::internal_error(
                 "FolderStorage",
                 format!("Failed to get folder with ID: {} for deletion: {}", id, e),
@@ -395,6 +437,10 @@ impl FolderUseCase for FolderService {
                 "FolderStorage",
                 format!("Failed to delete folder with ID: {}: {}", id, e),
             )
-        })
+        })?;
+
+        self.record_change(ChangeKind::Deleted, &FolderDto::from(folder), None)
+            .await;
+        Ok(())
     }
 }
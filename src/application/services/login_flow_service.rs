@@ -0,0 +1,179 @@
+use rand::distr::{Alphanumeric, SampleString};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::common::errors::{DomainError, Result};
+
+/// Length of the login and poll tokens, as issued by Nextcloud
+const TOKEN_LENGTH: usize = 128;
+
+/// Credentials handed to the client once the user grants access
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginFlowCredentials {
+    /// Base URL the client should use from now on
+    pub server: String,
+    pub login_name: String,
+    pub app_password: String,
+}
+
+/// A login started by a client and waiting for the user
+#[derive(Debug, Clone)]
+pub struct LoginFlow {
+    /// Token in the URL the user opens in the browser
+    pub login_token: String,
+    /// Token the client polls with
+    pub poll_token: String,
+    pub server: String,
+    /// Name of the client, shown to the user and given to the app password
+    pub client_name: String,
+    started_at: Instant,
+    credentials: Option<LoginFlowCredentials>,
+}
+
+/**
+ * Service tracking Nextcloud Login Flow v2 logins.
+ *
+ * A client starts a flow, sends the user to the login page and polls until
+ * the user has signed in there, at which point it receives an app password.
+ * Flows only live in memory: a restart merely makes pending clients start
+ * over. Credentials are handed out once and flows expire after a while.
+ */
+pub struct LoginFlowService {
+    flows: RwLock<HashMap<String, LoginFlow>>,
+    ttl: Duration,
+}
+
+impl LoginFlowService {
+    /// Creates a new login flow service whose flows expire after `ttl_secs`
+    pub fn new(ttl_secs: u64) -> Self {
+        Self {
+            flows: RwLock::new(HashMap::new()),
+            ttl: Duration::from_secs(ttl_secs),
+        }
+    }
+
+    fn token() -> String {
+        Alphanumeric.sample_string(&mut rand::rng(), TOKEN_LENGTH)
+    }
+
+    fn is_live(&self, flow: &LoginFlow) -> bool {
+        flow.started_at.elapsed() < self.ttl
+    }
+
+    /// Starts a flow for a client that will talk to `server`
+    pub async fn start(&self, server: &str, client_name: &str) -> LoginFlow {
+        let flow = LoginFlow {
+            login_token: Self::token(),
+            poll_token: Self::token(),
+            server: server.to_string(),
+            client_name: client_name.to_string(),
+            started_at: Instant::now(),
+            credentials: None,
+        };
+
+        let mut flows = self.flows.write().await;
+        flows.retain(|_, flow| self.is_live(flow));
+        flows.insert(flow.poll_token.clone(), flow.clone());
+        flow
+    }
+
+    /// Flow waiting for the user behind a login token
+    pub async fn pending(&self, login_token: &str) -> Option<LoginFlow> {
+        let flows = self.flows.read().await;
+        flows
+            .values()
+            .find(|flow| {
+                flow.login_token == login_token && flow.credentials.is_none() && self.is_live(flow)
+            })
+            .cloned()
+    }
+
+    /// Completes a flow once the user has signed in
+    pub async fn grant(
+        &self,
+        login_token: &str,
+        login_name: &str,
+        app_password: &str,
+    ) -> Result<()> {
+        let mut flows = self.flows.write().await;
+        let flow = flows
+            .values_mut()
+            .find(|flow| flow.login_token == login_token && flow.credentials.is_none())
+            .filter(|flow| flow.started_at.elapsed() < self.ttl)
+            .ok_or_else(|| DomainError::not_found("LoginFlow", "login token"))?;
+        flow.credentials = Some(LoginFlowCredentials {
+            server: flow.server.clone(),
+            login_name: login_name.to_string(),
+            app_password: app_password.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Credentials of a granted flow, handed out only once
+    pub async fn poll(&self, poll_token: &str) -> Option<LoginFlowCredentials> {
+        let mut flows = self.flows.write().await;
+        let granted = flows
+            .get(poll_token)
+            .is_some_and(|flow| flow.credentials.is_some() && self.is_live(flow));
+        if granted {
+            flows.remove(poll_token).and_then(|flow| flow.credentials)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_credentials_are_handed_out_once() {
+        let service = LoginFlowService::new(1200);
+        let flow = service.start("https://cloud.example.com", "Desktop").await;
+        assert_ne!(flow.login_token, flow.poll_token);
+
+        assert!(service.poll(&flow.poll_token).await.is_none());
+        assert_eq!(
+            service
+                .pending(&flow.login_token)
+                .await
+                .unwrap()
+                .client_name,
+            "Desktop"
+        );
+        // The poll token cannot be used to grant access
+        assert!(service
+            .grant(&flow.poll_token, "alice", "pw")
+            .await
+            .is_err());
+
+        service
+            .grant(&flow.login_token, "alice", "pw")
+            .await
+            .unwrap();
+        assert!(service.pending(&flow.login_token).await.is_none());
+        assert_eq!(
+            service.poll(&flow.poll_token).await,
+            Some(LoginFlowCredentials {
+                server: "https://cloud.example.com".to_string(),
+                login_name: "alice".to_string(),
+                app_password: "pw".to_string(),
+            })
+        );
+        assert!(service.poll(&flow.poll_token).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_flows_cannot_be_granted() {
+        let service = LoginFlowService::new(0);
+        let flow = service.start("https://cloud.example.com", "Desktop").await;
+        assert!(service.pending(&flow.login_token).await.is_none());
+        assert!(service
+            .grant(&flow.login_token, "alice", "pw")
+            .await
+            .is_err());
+        assert!(service.poll(&flow.poll_token).await.is_none());
+    }
+}
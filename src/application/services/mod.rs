@@ -1,21 +1,33 @@
 pub mod auth_application_service;
 pub mod batch_operations;
 pub mod calendar_service;
+pub mod chunked_upload_service;
 pub mod contact_service;
+pub mod content_index_service;
 pub mod favorites_service;
 pub mod file_management_service;
 pub mod file_retrieval_service;
 pub mod file_service;
 pub mod file_upload_service;
 pub mod file_use_case_factory;
+pub mod file_version_service;
 pub mod folder_service;
 pub mod i18n_application_service;
+pub mod login_flow_service;
 pub mod recent_service;
+pub mod reminder_service;
+pub mod resumable_upload_service;
+pub mod scheduling_service;
 pub mod search_service;
 pub mod share_service;
 pub mod storage_mediator;
 pub mod storage_usage_service;
+pub mod sync_service;
+pub mod thumbnail_service;
 pub mod trash_service;
+pub mod webdav_acl_service;
+pub mod webdav_lock_service;
+pub mod webdav_property_service;
 
 #[cfg(test)]
 mod trash_service_test;
@@ -0,0 +1,306 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::application::dtos::calendar_dto::ReminderNotificationDto;
+use crate::application::ports::auth_ports::UserStoragePort;
+use crate::application::ports::calendar_ports::CalendarUseCase;
+use crate::application::ports::reminder_ports::{
+    ReminderChannel, ReminderStoragePort, ReminderUseCase,
+};
+use crate::common::config::ReminderConfig;
+use crate::common::errors::{DomainError, ErrorKind, Result};
+use crate::domain::entities::icalendar::ICalComponent;
+use crate::domain::entities::reminder::{
+    self, AlarmAction, AlarmSource, Reminder, ReminderNotification,
+};
+use crate::domain::entities::user::User;
+
+/// Reminders more than this late, e.g. after the server was down, are
+/// dropped instead of delivered
+const MAX_LATENESS_MINUTES: i64 = 60;
+
+/// Reminders claimed per round of `deliver_due`
+const DELIVERY_BATCH: usize = 100;
+
+/// Changes are looked up again from a bit before the previous sync, as an
+/// object stored while it ran may carry an earlier `updated_at`
+const SYNC_OVERLAP_SECS: i64 = 60;
+
+/// Sent reminders are kept this long, well past the window in which their
+/// alarm could be indexed again
+const SENT_RETENTION_HOURS: i64 = 24;
+
+/**
+ * Service turning the alarms (VALARM) of events and tasks into reminders.
+ *
+ * Upcoming triggers, relative or absolute and for every instance of
+ * recurring objects, are kept in an index that survives restarts. The
+ * index is refreshed from the objects changed since the previous sync and
+ * rebuilt periodically to move its horizon forward. Due reminders are
+ * claimed before being delivered, so each trigger goes out once even if
+ * a channel fails or the object is indexed again; alarms acknowledged or
+ * snoozed (RFC 9074) are left out when indexing.
+ */
+pub struct ReminderService {
+    calendars: Arc<dyn CalendarUseCase>,
+    users: Arc<dyn UserStoragePort>,
+    storage: Arc<dyn ReminderStoragePort>,
+    channels: Vec<Arc<dyn ReminderChannel>>,
+    horizon: Duration,
+    /// Start of the previous sync; also keeps syncs from overlapping
+    last_sync: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl ReminderService {
+    /// Creates a new reminder service without delivery channels
+    pub fn new(
+        calendars: Arc<dyn CalendarUseCase>,
+        users: Arc<dyn UserStoragePort>,
+        storage: Arc<dyn ReminderStoragePort>,
+        config: &ReminderConfig,
+    ) -> Self {
+        Self {
+            calendars,
+            users,
+            storage,
+            channels: Vec::new(),
+            horizon: Duration::hours(config.horizon_hours as i64),
+            last_sync: Mutex::new(None),
+        }
+    }
+
+    /// Adds a channel delivering the reminders whose action it accepts
+    pub fn with_channel(mut self, channel: Arc<dyn ReminderChannel>) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    /// Replaces the pending reminders of an object with its current triggers
+    async fn index(&self, source: &AlarmSource, now: DateTime<Utc>) -> Result<()> {
+        let reminders = source.reminders(
+            now - Duration::minutes(MAX_LATENESS_MINUTES),
+            now + self.horizon,
+        );
+        self.storage
+            .replace_reminders(&source.object_id, reminders)
+            .await
+    }
+
+    /// Sends a claimed reminder through every channel accepting it; returns
+    /// whether any of them delivered it
+    async fn deliver(&self, reminder: &Reminder, now: DateTime<Utc>) -> Result<bool> {
+        if now - reminder.trigger_at > Duration::minutes(MAX_LATENESS_MINUTES) {
+            info!(
+                "Dropping reminder {} due at {}: too late",
+                reminder.id, reminder.trigger_at
+            );
+            return Ok(false);
+        }
+        // The object may have been deleted after it was indexed
+        if self
+            .storage
+            .find_alarm_source(&reminder.object_id)
+            .await?
+            .is_none()
+        {
+            return Ok(false);
+        }
+        let user = self.users.get_user_by_id(&reminder.user_id).await?;
+        if !user.is_active() {
+            return Ok(false);
+        }
+
+        let mut delivered = false;
+        for channel in self
+            .channels
+            .iter()
+            .filter(|channel| channel.accepts(reminder.action))
+        {
+            match channel.deliver(reminder, &user).await {
+                Ok(()) => delivered = true,
+                Err(e) => warn!(
+                    "Could not deliver reminder {} through {}: {}",
+                    reminder.id,
+                    channel.name(),
+                    e
+                ),
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Applies a change to the alarm of a notification in its calendar
+    /// object, indexes the object again and dismisses the notification
+    async fn update_alarm(
+        &self,
+        notification_id: &str,
+        user_id: &str,
+        change: impl FnOnce(&mut ICalComponent, &str) -> bool,
+    ) -> Result<()> {
+        let notification = self
+            .storage
+            .find_notification(user_id, notification_id)
+            .await?
+            .ok_or_else(|| DomainError::not_found("Reminder", notification_id))?;
+        let reminder = &notification.reminder;
+        let is_task = reminder.component == "VTODO";
+
+        let ical_data = if is_task {
+            self.calendars
+                .get_task(&reminder.object_id, user_id)
+                .await
+                .map(|task| task.ical_data)
+        } else {
+            self.calendars
+                .get_event(&reminder.object_id, user_id)
+                .await
+                .map(|event| event.ical_data)
+        };
+        let ical_data = match ical_data {
+            Ok(ical_data) => Some(ical_data),
+            // Nothing left to update once the object is gone
+            Err(e) if e.kind == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        if let Some(ical_data) = ical_data {
+            let mut calendar =
+                ICalComponent::parse(&ical_data).map_err(DomainError::validation_error)?;
+            // The alarm may have been removed or changed in a client since
+            if change(&mut calendar, &reminder.alarm_key) {
+                let ical_data = calendar.to_ical();
+                if is_task {
+                    self.calendars
+                        .update_task_from_ical(&reminder.object_id, &ical_data, user_id)
+                        .await?;
+                } else {
+                    self.calendars
+                        .update_event_from_ical(&reminder.object_id, &ical_data, user_id)
+                        .await?;
+                }
+                if let Some(source) = self.storage.find_alarm_source(&reminder.object_id).await? {
+                    self.index(&source, Utc::now()).await?;
+                }
+            }
+        }
+
+        self.storage
+            .delete_notification(user_id, notification_id)
+            .await
+    }
+}
+
+#[async_trait]
+impl ReminderUseCase for ReminderService {
+    async fn sync(&self, full: bool) -> Result<usize> {
+        let mut last_sync = self.last_sync.lock().await;
+        let now = Utc::now();
+        let since = match *last_sync {
+            Some(last_sync) if !full => Some(last_sync - Duration::seconds(SYNC_OVERLAP_SECS)),
+            _ => None,
+        };
+
+        let sources = self.storage.alarm_sources(since).await?;
+        for source in &sources {
+            if let Err(e) = self.index(source, now).await {
+                warn!("Could not index the alarms of {}: {}", source.object_id, e);
+            }
+        }
+        if since.is_none() {
+            let pruned = self
+                .storage
+                .prune_reminders(now - Duration::hours(SENT_RETENTION_HOURS))
+                .await?;
+            debug!("Pruned {} reminders from the index", pruned);
+        }
+
+        *last_sync = Some(now);
+        Ok(sources.len())
+    }
+
+    async fn deliver_due(&self) -> Result<usize> {
+        let now = Utc::now();
+        let mut delivered = 0;
+        loop {
+            let due = self
+                .storage
+                .claim_due_reminders(now, DELIVERY_BATCH)
+                .await?;
+            for reminder in &due {
+                match self.deliver(reminder, now).await {
+                    Ok(true) => delivered += 1,
+                    Ok(false) => {}
+                    Err(e) => warn!("Could not deliver reminder {}: {}", reminder.id, e),
+                }
+            }
+            if due.len() < DELIVERY_BATCH {
+                return Ok(delivered);
+            }
+        }
+    }
+
+    async fn list_notifications(&self, user_id: &str) -> Result<Vec<ReminderNotificationDto>> {
+        let notifications = self.storage.list_notifications(user_id).await?;
+        Ok(notifications.into_iter().map(Into::into).collect())
+    }
+
+    async fn acknowledge(&self, notification_id: &str, user_id: &str) -> Result<()> {
+        let now = Utc::now();
+        self.update_alarm(notification_id, user_id, |calendar, alarm_key| {
+            reminder::acknowledge(calendar, alarm_key, now)
+        })
+        .await
+    }
+
+    async fn snooze(&self, notification_id: &str, minutes: i64, user_id: &str) -> Result<()> {
+        if !(1..=7 * 24 * 60).contains(&minutes) {
+            return Err(DomainError::validation_error(
+                "Reminders can be snoozed from one minute to a week",
+            ));
+        }
+        let now = Utc::now();
+        self.update_alarm(notification_id, user_id, |calendar, alarm_key| {
+            reminder::snooze(calendar, alarm_key, now + Duration::minutes(minutes), now)
+        })
+        .await
+    }
+}
+
+/// Channel showing reminders of display and sound alarms as notifications
+/// in the web interface
+pub struct InAppReminderChannel {
+    storage: Arc<dyn ReminderStoragePort>,
+}
+
+impl InAppReminderChannel {
+    pub fn new(storage: Arc<dyn ReminderStoragePort>) -> Self {
+        Self { storage }
+    }
+}
+
+#[async_trait]
+impl ReminderChannel for InAppReminderChannel {
+    fn name(&self) -> &'static str {
+        "in-app"
+    }
+
+    fn accepts(&self, action: AlarmAction) -> bool {
+        action != AlarmAction::Email
+    }
+
+    async fn deliver(&self, reminder: &Reminder, user: &User) -> Result<()> {
+        self.storage
+            .save_notification(ReminderNotification {
+                id: Uuid::new_v4(),
+                user_id: user.id().to_string(),
+                reminder: reminder.clone(),
+                created_at: Utc::now(),
+            })
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,464 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::application::dtos::upload_dto::{CreateUploadDto, UploadSessionDto};
+use crate::application::ports::file_ports::FileUploadUseCase;
+use crate::application::ports::upload_ports::{
+    ResumableUploadUseCase, UploadChecksum, UploadChunkStream, UploadError,
+    UploadSessionStoragePort,
+};
+use crate::common::config::ResumableUploadConfig;
+use crate::domain::entities::upload_session::UploadSession;
+
+/**
+ * Service for resumable, chunked uploads.
+ *
+ * Upload sessions are persisted by the storage port so clients can resume
+ * after a dropped connection or a server restart. Each session belongs to
+ * the user who created it. Once the last byte arrives the assembled content
+ * is streamed to the regular upload use case, which applies the quota
+ * checks, and the session is discarded.
+ */
+pub struct ResumableUploadService {
+    storage: Arc<dyn UploadSessionStoragePort>,
+    upload_service: Arc<dyn FileUploadUseCase>,
+    max_size: u64,
+    expiration_secs: u64,
+    locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl ResumableUploadService {
+    /// Creates a new resumable upload service
+    pub fn new(
+        storage: Arc<dyn UploadSessionStoragePort>,
+        upload_service: Arc<dyn FileUploadUseCase>,
+        config: &ResumableUploadConfig,
+    ) -> Self {
+        Self {
+            storage,
+            upload_service,
+            max_size: config.max_size,
+            expiration_secs: config.expiration_hours.max(1) * 3600,
+            locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    async fn upload_lock(&self, id: &str) -> Arc<Mutex<()>> {
+        let mut locks = self.locks.lock().await;
+        locks.entry(id.to_string()).or_default().clone()
+    }
+
+    async fn release_lock(&self, id: &str) {
+        self.locks.lock().await.remove(id);
+    }
+
+    /// Loads a session of `owner`, treating expired ones and those of other
+    /// users as gone
+    async fn load_session(&self, owner: &str, id: &str) -> Result<UploadSession, UploadError> {
+        let session = self.storage.get_session(id).await?;
+        if session.owner != owner || session.is_expired(Self::now()) {
+            return Err(UploadError::NotFound(id.to_string()));
+        }
+        Ok(session)
+    }
+
+    /// Streams the assembled content to the upload use case and drops the session
+    async fn finish_upload(&self, session: UploadSession) -> Result<UploadSessionDto, UploadError> {
+        let content = self.storage.read_content_stream(&session.id).await?;
+        let file = self
+            .upload_service
+            .upload_file_stream(
+                session.name.clone(),
+                session.folder_id.clone(),
+                session.content_type.clone(),
+                session.length,
+                content,
+            )
+            .await?;
+
+        info!(
+            "Resumable upload {} completed as file {} ({} bytes)",
+            session.id, file.id, session.length
+        );
+
+        if let Err(e) = self.storage.delete_session(&session.id).await {
+            warn!("Failed to remove finished upload {}: {}", session.id, e);
+        }
+
+        let mut dto = UploadSessionDto::from(session);
+        dto.file = Some(file);
+        Ok(dto)
+    }
+}
+
+#[async_trait]
+impl ResumableUploadUseCase for ResumableUploadService {
+    fn max_size(&self) -> Option<u64> {
+        (self.max_size > 0).then_some(self.max_size)
+    }
+
+    async fn create_upload(
+        &self,
+        owner: &str,
+        dto: CreateUploadDto,
+    ) -> Result<UploadSessionDto, UploadError> {
+        let name = dto.name.trim().to_string();
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(UploadError::InvalidRequest(format!(
+                "Invalid file name: '{}'",
+                dto.name
+            )));
+        }
+
+        if let Some(max_size) = self.max_size() {
+            if dto.length > max_size {
+                return Err(UploadError::TooLarge(format!(
+                    "{} bytes exceeds the maximum of {} bytes",
+                    dto.length, max_size
+                )));
+            }
+        }
+
+        // Fail early instead of after the whole file has been transferred
+        self.upload_service
+            .check_upload_quota(dto.folder_id.as_deref(), dto.length)
+            .await?;
+
+        let content_type = dto.content_type.unwrap_or_else(|| {
+            mime_guess::from_path(&name)
+                .first_or_octet_stream()
+                .to_string()
+        });
+
+        let now = Self::now();
+        let session = UploadSession::new(
+            dto.length,
+            name,
+            content_type,
+            dto.folder_id,
+            dto.metadata,
+            now,
+            now + self.expiration_secs,
+        )
+        .with_owner(owner);
+        self.storage.create_session(&session).await?;
+        debug!(
+            "Created resumable upload {} for '{}' ({} bytes)",
+            session.id, session.name, session.length
+        );
+
+        // Empty files are complete as soon as they are announced
+        if session.is_complete() {
+            return self.finish_upload(session).await;
+        }
+
+        Ok(UploadSessionDto::from(session))
+    }
+
+    async fn get_upload(&self, owner: &str, id: &str) -> Result<UploadSessionDto, UploadError> {
+        Ok(UploadSessionDto::from(self.load_session(owner, id).await?))
+    }
+
+    async fn append_chunk(
+        &self,
+        owner: &str,
+        id: &str,
+        offset: u64,
+        chunk: UploadChunkStream,
+        checksum: Option<UploadChecksum>,
+    ) -> Result<UploadSessionDto, UploadError> {
+        let lock = self.upload_lock(id).await;
+        let _guard = lock.lock().await;
+
+        let mut session = self.load_session(owner, id).await?;
+        if offset != session.offset {
+            return Err(UploadError::OffsetMismatch {
+                expected: session.offset,
+                actual: offset,
+            });
+        }
+
+        let write = self
+            .storage
+            .append_chunk(
+                id,
+                offset,
+                chunk,
+                session.remaining(),
+                checksum.as_ref().map(|c| c.algorithm),
+            )
+            .await?;
+
+        if let Some(checksum) = &checksum {
+            // A partial chunk cannot be verified, so it is discarded as a whole
+            if !write.complete || write.digest.as_deref() != Some(checksum.digest.as_slice()) {
+                self.storage.truncate(id, offset).await?;
+                return Err(UploadError::ChecksumMismatch(id.to_string()));
+            }
+        }
+
+        session.offset += write.written;
+        session.expires_at = Self::now() + self.expiration_secs;
+        self.storage.save_session(&session).await?;
+
+        if !write.complete {
+            debug!(
+                "Upload {} interrupted at offset {} of {}",
+                id, session.offset, session.length
+            );
+        }
+
+        if session.is_complete() {
+            let result = self.finish_upload(session).await;
+            if result.is_ok() {
+                drop(_guard);
+                self.release_lock(id).await;
+            }
+            return result;
+        }
+
+        Ok(UploadSessionDto::from(session))
+    }
+
+    async fn terminate_upload(&self, owner: &str, id: &str) -> Result<(), UploadError> {
+        let lock = self.upload_lock(id).await;
+        let _guard = lock.lock().await;
+
+        self.load_session(owner, id).await?;
+        self.storage.delete_session(id).await?;
+        drop(_guard);
+        self.release_lock(id).await;
+
+        debug!("Resumable upload {} terminated", id);
+        Ok(())
+    }
+
+    async fn cleanup_expired(&self) -> Result<usize, UploadError> {
+        let now = Self::now();
+        let mut removed = 0;
+
+        for session in self.storage.list_sessions().await? {
+            if !session.is_expired(now) {
+                continue;
+            }
+
+            let lock = self.upload_lock(&session.id).await;
+            let _guard = lock.lock().await;
+            match self.storage.delete_session(&session.id).await {
+                Ok(()) => removed += 1,
+                Err(e) => warn!("Failed to remove expired upload {}: {}", session.id, e),
+            }
+            drop(_guard);
+            self.release_lock(&session.id).await;
+        }
+
+        if removed > 0 {
+            info!("Removed {} expired resumable uploads", removed);
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::dtos::file_dto::FileDto;
+    use crate::common::errors::DomainError;
+    use crate::domain::entities::upload_session::ChecksumAlgorithm;
+    use crate::infrastructure::repositories::upload_session_fs_repository::UploadSessionFsRepository;
+    use bytes::Bytes;
+    use tempfile::tempdir;
+
+    const OWNER: &str = "user-1";
+
+    /// Records the files handed over by the resumable upload service
+    #[derive(Default)]
+    struct RecordingUploadService {
+        uploads: Mutex<Vec<(String, Vec<u8>)>>,
+        quota: Option<u64>,
+    }
+
+    #[async_trait]
+    impl FileUploadUseCase for RecordingUploadService {
+        async fn upload_file(
+            &self,
+            name: String,
+            folder_id: Option<String>,
+            content_type: String,
+            content: Vec<u8>,
+        ) -> Result<FileDto, DomainError> {
+            let size = content.len() as u64;
+            self.uploads.lock().await.push((name.clone(), content));
+            Ok(FileDto {
+                id: "file-1".to_string(),
+                name: name.clone(),
+                path: format!("/{}", name),
+                size,
+                mime_type: content_type,
+                folder_id,
+                created_at: 0,
+                modified_at: 0,
+                etag: String::new(),
+            })
+        }
+
+        async fn check_upload_quota(
+            &self,
+            _folder_id: Option<&str>,
+            size: u64,
+        ) -> Result<(), DomainError> {
+            match self.quota {
+                Some(quota) if size > quota => Err(DomainError::quota_exceeded("User", "full")),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn service(
+        dir: &std::path::Path,
+        uploads: Arc<RecordingUploadService>,
+    ) -> ResumableUploadService {
+        ResumableUploadService::new(
+            Arc::new(UploadSessionFsRepository::new(dir)),
+            uploads,
+            &ResumableUploadConfig {
+                enabled: true,
+                max_size: 100,
+                expiration_hours: 1,
+            },
+        )
+    }
+
+    fn chunk(data: &'static [u8]) -> UploadChunkStream {
+        Box::pin(futures::stream::iter(vec![Ok(Bytes::from_static(data))]))
+    }
+
+    fn create_dto(length: u64) -> CreateUploadDto {
+        CreateUploadDto {
+            length,
+            name: "notes.txt".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunks_are_assembled_into_file() {
+        let dir = tempdir().unwrap();
+        let uploads = Arc::new(RecordingUploadService::default());
+        let service = service(dir.path(), uploads.clone());
+
+        let upload = service.create_upload(OWNER, create_dto(11)).await.unwrap();
+        let partial = service
+            .append_chunk(OWNER, &upload.id, 0, chunk(b"hello "), None)
+            .await
+            .unwrap();
+        assert_eq!(partial.offset, 6);
+        assert!(partial.file.is_none());
+
+        // Wrong offsets are rejected without touching the upload
+        assert!(matches!(
+            service
+                .append_chunk(OWNER, &upload.id, 3, chunk(b"world"), None)
+                .await,
+            Err(UploadError::OffsetMismatch {
+                expected: 6,
+                actual: 3
+            })
+        ));
+
+        let done = service
+            .append_chunk(OWNER, &upload.id, 6, chunk(b"world"), None)
+            .await
+            .unwrap();
+        assert_eq!(done.file.unwrap().size, 11);
+        assert_eq!(uploads.uploads.lock().await[0].1, b"hello world");
+        assert!(matches!(
+            service.get_upload(OWNER, &upload.id).await,
+            Err(UploadError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_checksum_mismatch_discards_chunk() {
+        let dir = tempdir().unwrap();
+        let service = service(dir.path(), Arc::new(RecordingUploadService::default()));
+        let upload = service.create_upload(OWNER, create_dto(3)).await.unwrap();
+
+        let wrong = UploadChecksum {
+            algorithm: ChecksumAlgorithm::Sha256,
+            digest: vec![0; 32],
+        };
+        assert!(matches!(
+            service
+                .append_chunk(OWNER, &upload.id, 0, chunk(b"abc"), Some(wrong))
+                .await,
+            Err(UploadError::ChecksumMismatch(_))
+        ));
+        assert_eq!(
+            service.get_upload(OWNER, &upload.id).await.unwrap().offset,
+            0
+        );
+
+        let right = UploadChecksum {
+            algorithm: ChecksumAlgorithm::Sha1,
+            digest: hex::decode("a9993e364706816aba3e25717850c26c9cd0d89d").unwrap(),
+        };
+        let done = service
+            .append_chunk(OWNER, &upload.id, 0, chunk(b"abc"), Some(right))
+            .await
+            .unwrap();
+        assert!(done.file.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_limits_and_termination() {
+        let dir = tempdir().unwrap();
+        let uploads = Arc::new(RecordingUploadService {
+            quota: Some(50),
+            ..Default::default()
+        });
+        let service = service(dir.path(), uploads);
+
+        assert!(matches!(
+            service.create_upload(OWNER, create_dto(101)).await,
+            Err(UploadError::TooLarge(_))
+        ));
+        assert!(matches!(
+            service.create_upload(OWNER, create_dto(60)).await,
+            Err(UploadError::QuotaExceeded(_))
+        ));
+
+        let upload = service.create_upload(OWNER, create_dto(10)).await.unwrap();
+
+        // Other users cannot see, continue or cancel the upload
+        assert!(matches!(
+            service.get_upload("user-2", &upload.id).await,
+            Err(UploadError::NotFound(_))
+        ));
+        assert!(matches!(
+            service
+                .append_chunk("user-2", &upload.id, 0, chunk(b"abc"), None)
+                .await,
+            Err(UploadError::NotFound(_))
+        ));
+        assert!(service
+            .terminate_upload("user-2", &upload.id)
+            .await
+            .is_err());
+
+        service.terminate_upload(OWNER, &upload.id).await.unwrap();
+        assert!(service.get_upload(OWNER, &upload.id).await.is_err());
+        assert_eq!(service.cleanup_expired().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,423 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::application::dtos::calendar_dto::{
+    CalendarEventDto, CreateCalendarDto, CreateEventICalDto, ScheduleResponseDto,
+};
+use crate::application::ports::auth_ports::UserStoragePort;
+use crate::application::ports::calendar_ports::CalendarUseCase;
+use crate::application::ports::scheduling_ports::{ScheduleInboxStoragePort, SchedulingUseCase};
+use crate::common::errors::{DomainError, Result};
+use crate::domain::entities::icalendar::ICalComponent;
+use crate::domain::entities::scheduling::{self, FreeBusyPeriod, ItipMethod, ScheduleMessage};
+use crate::domain::entities::user::User;
+
+/// Request statuses of RFC 5546, section 3.6
+const STATUS_SUCCESS: &str = "2.0;Success";
+const STATUS_DELIVERED: &str = "1.2;Delivered";
+const STATUS_INVALID_USER: &str = "3.7;Invalid calendar user";
+const STATUS_UNAVAILABLE: &str = "5.1;Service unavailable";
+
+/// Name of the calendar created for users who get an invitation before
+/// having any calendar for events
+const DEFAULT_CALENDAR_NAME: &str = "Calendar";
+
+/**
+ * Service delivering iTIP messages (RFC 5546) between the users of this
+ * server, following CalDAV scheduling (RFC 6638).
+ *
+ * Calendar users are addressed by their email as `mailto:` URIs. A message
+ * for a local user updates their copy of the object, in the calendar that
+ * holds it or in their first calendar for events, and lands in their
+ * inbox. Recipients outside the server are reported as invalid calendar
+ * users: nothing is sent by email.
+ */
+pub struct SchedulingService {
+    calendars: Arc<dyn CalendarUseCase>,
+    users: Arc<dyn UserStoragePort>,
+    inbox: Arc<dyn ScheduleInboxStoragePort>,
+}
+
+impl SchedulingService {
+    /// Creates a new scheduling service
+    pub fn new(
+        calendars: Arc<dyn CalendarUseCase>,
+        users: Arc<dyn UserStoragePort>,
+        inbox: Arc<dyn ScheduleInboxStoragePort>,
+    ) -> Self {
+        Self {
+            calendars,
+            users,
+            inbox,
+        }
+    }
+
+    /// Email address of a user, in lower case
+    async fn email_of(&self, user_id: &str) -> Result<String> {
+        let user = self.users.get_user_by_id(user_id).await?;
+        Ok(user.email().to_lowercase())
+    }
+
+    /// Active user of this server with the given email
+    async fn local_user(&self, email: &str) -> Option<User> {
+        self.users
+            .get_user_by_email(email)
+            .await
+            .ok()
+            .filter(User::is_active)
+    }
+
+    /// A user's copy of the event with this UID, in any calendar they own
+    async fn find_copy(&self, user_id: &str, uid: &str) -> Result<Option<CalendarEventDto>> {
+        for calendar in self.calendars.list_my_calendars(user_id).await? {
+            if let Some(event) = self
+                .calendars
+                .get_event_by_uid(&calendar.id, uid, user_id)
+                .await?
+            {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Calendar that receives a user's new invitations: the first one they
+    /// own that holds events, created if there is none
+    async fn default_calendar(&self, user_id: &str) -> Result<String> {
+        let calendars = self.calendars.list_my_calendars(user_id).await?;
+        if let Some(calendar) = calendars
+            .iter()
+            .find(|calendar| calendar.supports_component("VEVENT"))
+        {
+            return Ok(calendar.id.clone());
+        }
+        let calendar = self
+            .calendars
+            .create_calendar(
+                CreateCalendarDto {
+                    id: None,
+                    name: DEFAULT_CALENDAR_NAME.to_string(),
+                    description: None,
+                    color: None,
+                    is_public: None,
+                },
+                user_id,
+            )
+            .await?;
+        Ok(calendar.id)
+    }
+
+    /// Applies a message from `sender` to the recipient's copy of the object
+    /// and stores it in their inbox
+    async fn deliver(&self, recipient: &User, sender: &str, message: &ICalComponent) -> Result<()> {
+        let entry = ScheduleMessage::new(recipient.id(), message).ok_or_else(|| {
+            DomainError::validation_error("Scheduling messages need a METHOD and a UID")
+        })?;
+        let user_id = recipient.id();
+        let copy = match self.find_copy(user_id, &entry.ical_uid).await? {
+            Some(copy) => {
+                let calendar =
+                    ICalComponent::parse(&copy.ical_data).map_err(DomainError::validation_error)?;
+                // Only the organizer of the stored object may change it: a
+                // message reusing the UID of someone else's object only
+                // lands in the inbox
+                if scheduling::organizer(&calendar) != scheduling::organizer(message) {
+                    warn!(
+                        "Scheduling message for {} does not come from the organizer of {}",
+                        user_id, entry.ical_uid
+                    );
+                    self.inbox.save_message(entry).await?;
+                    return Ok(());
+                }
+                Some((copy.id, calendar))
+            }
+            None => None,
+        };
+
+        match (entry.method, copy) {
+            (ItipMethod::Request, None) => {
+                let event = CreateEventICalDto {
+                    calendar_id: self.default_calendar(user_id).await?,
+                    ical_data: scheduling::without_method(message).to_ical(),
+                };
+                self.calendars
+                    .create_event_from_ical(event, user_id)
+                    .await?;
+            }
+            (ItipMethod::Request, Some((copy_id, mut calendar))) => {
+                if scheduling::has_master(message) {
+                    calendar = scheduling::without_method(message);
+                } else {
+                    scheduling::merge_instances(&mut calendar, message);
+                }
+                self.calendars
+                    .update_event_from_ical(&copy_id, &calendar.to_ical(), user_id)
+                    .await?;
+            }
+            (ItipMethod::Cancel, Some((copy_id, _))) if scheduling::has_master(message) => {
+                self.calendars.delete_event(&copy_id, user_id).await?;
+            }
+            (ItipMethod::Cancel, Some((copy_id, mut calendar))) => {
+                scheduling::merge_instances(&mut calendar, message);
+                self.calendars
+                    .update_event_from_ical(&copy_id, &calendar.to_ical(), user_id)
+                    .await?;
+            }
+            (ItipMethod::Reply, Some((copy_id, mut calendar))) => {
+                if scheduling::apply_reply(&mut calendar, message, sender) {
+                    self.calendars
+                        .update_event_from_ical(&copy_id, &calendar.to_ical(), user_id)
+                        .await?;
+                }
+            }
+            // Nothing to cancel or answer: the message only goes to the inbox
+            (ItipMethod::Cancel | ItipMethod::Reply, None) => {}
+        }
+
+        self.inbox.save_message(entry).await?;
+        Ok(())
+    }
+
+    /// Delivers a message from `sender` to a calendar user and returns the
+    /// request status
+    async fn deliver_to(&self, email: &str, sender: &str, message: &ICalComponent) -> String {
+        let Some(recipient) = self.local_user(email).await else {
+            return STATUS_INVALID_USER.to_string();
+        };
+        match self.deliver(&recipient, sender, message).await {
+            Ok(()) => STATUS_DELIVERED.to_string(),
+            Err(e) => {
+                warn!("Could not deliver scheduling message to {}: {}", email, e);
+                STATUS_UNAVAILABLE.to_string()
+            }
+        }
+    }
+
+    /// Answers a VFREEBUSY request with the busy periods of each attendee
+    async fn answer_free_busy(&self, request: &ICalComponent) -> Result<Vec<ScheduleResponseDto>> {
+        let range = request
+            .property("DTSTART")
+            .and_then(|start| start.date_time())
+            .zip(request.property("DTEND").and_then(|end| end.date_time()))
+            .filter(|(start, end)| start < end);
+        let Some((start, end)) = range else {
+            return Err(DomainError::validation_error(
+                "A VFREEBUSY request needs DTSTART before DTEND",
+            ));
+        };
+
+        let mut responses = Vec::new();
+        for attendee in request
+            .properties
+            .iter()
+            .filter(|property| property.name == "ATTENDEE")
+        {
+            let recipient = match scheduling::calendar_user_email(&attendee.value) {
+                Some(email) => self.local_user(&email).await,
+                None => None,
+            };
+            let Some(recipient) = recipient else {
+                responses.push(ScheduleResponseDto {
+                    recipient: attendee.value.clone(),
+                    request_status: STATUS_INVALID_USER.to_string(),
+                    calendar_data: None,
+                });
+                continue;
+            };
+
+            let periods = self.free_busy(recipient.id(), start, end).await?;
+            let mut answer = scheduling::free_busy_component(&periods, start, end);
+            answer.properties.extend(
+                ["UID", "ORGANIZER"]
+                    .iter()
+                    .filter_map(|name| request.property(name))
+                    .cloned(),
+            );
+            answer.properties.push(attendee.clone());
+            responses.push(ScheduleResponseDto {
+                recipient: attendee.value.clone(),
+                request_status: STATUS_SUCCESS.to_string(),
+                calendar_data: Some(
+                    scheduling::vcalendar(Some(ItipMethod::Reply), vec![answer]).to_ical(),
+                ),
+            });
+        }
+        Ok(responses)
+    }
+}
+
+#[async_trait]
+impl SchedulingUseCase for SchedulingService {
+    async fn free_busy(
+        &self,
+        user_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<FreeBusyPeriod>> {
+        let mut periods = Vec::new();
+        for calendar in self.calendars.list_my_calendars(user_id).await? {
+            if calendar.supports_component("VEVENT") {
+                periods.extend(
+                    self.calendars
+                        .get_free_busy(&calendar.id, start, end, user_id)
+                        .await?,
+                );
+            }
+        }
+        Ok(scheduling::merge_periods(periods))
+    }
+
+    async fn schedule_change(
+        &self,
+        user_id: &str,
+        previous: Option<&str>,
+        current: Option<&str>,
+    ) -> Result<()> {
+        let previous = previous.and_then(|data| ICalComponent::parse(data).ok());
+        let current = current.and_then(|data| ICalComponent::parse(data).ok());
+        let Some(organizer) = current
+            .as_ref()
+            .or(previous.as_ref())
+            .and_then(scheduling::organizer)
+        else {
+            return Ok(());
+        };
+        let email = self.email_of(user_id).await?;
+
+        if organizer == email {
+            let before = previous
+                .as_ref()
+                .map(scheduling::attendees)
+                .unwrap_or_default();
+            let after = current
+                .as_ref()
+                .map(scheduling::attendees)
+                .unwrap_or_default();
+            if let Some(current) = current.as_ref().filter(|_| previous != current) {
+                let request = scheduling::organizer_message(current, ItipMethod::Request);
+                for attendee in &after {
+                    self.deliver_to(attendee, &email, &request).await;
+                }
+            }
+            if let Some(previous) = &previous {
+                let cancel = scheduling::organizer_message(previous, ItipMethod::Cancel);
+                for attendee in before.iter().filter(|email| !after.contains(email)) {
+                    self.deliver_to(attendee, &email, &cancel).await;
+                }
+            }
+            return Ok(());
+        }
+
+        let reply = match (&previous, &current) {
+            (_, Some(current)) => {
+                let before = previous
+                    .as_ref()
+                    .map(|previous| scheduling::participation(previous, &email))
+                    .unwrap_or_default();
+                let changed: Vec<Option<String>> = scheduling::participation(current, &email)
+                    .into_iter()
+                    .filter(|answer| !before.contains(answer))
+                    .map(|(recurrence_id, _)| recurrence_id)
+                    .collect();
+                if changed.is_empty() {
+                    return Ok(());
+                }
+                scheduling::reply(current, &email, &changed)
+            }
+            (Some(previous), None) => {
+                let declined = scheduling::declined(previous, &email);
+                let instances: Vec<Option<String>> = scheduling::participation(&declined, &email)
+                    .into_iter()
+                    .map(|(recurrence_id, _)| recurrence_id)
+                    .collect();
+                if instances.is_empty() {
+                    return Ok(());
+                }
+                scheduling::reply(&declined, &email, &instances)
+            }
+            (None, None) => return Ok(()),
+        };
+        self.deliver_to(&organizer, &email, &reply).await;
+        Ok(())
+    }
+
+    async fn send_message(
+        &self,
+        user_id: &str,
+        ical_data: &str,
+    ) -> Result<Vec<ScheduleResponseDto>> {
+        let message = ICalComponent::parse(ical_data).map_err(DomainError::validation_error)?;
+        let method = message
+            .property("METHOD")
+            .and_then(|method| ItipMethod::parse(&method.value))
+            .ok_or_else(|| {
+                DomainError::validation_error(
+                    "Scheduling messages need a METHOD of REQUEST, REPLY or CANCEL",
+                )
+            })?;
+        let Some(component) = message.calendar_components().next() else {
+            return Err(DomainError::validation_error(
+                "Scheduling messages need a calendar component",
+            ));
+        };
+        if component.name == "VFREEBUSY" {
+            if method != ItipMethod::Request {
+                return Err(DomainError::validation_error(
+                    "Free-busy lookups are sent as REQUEST",
+                ));
+            }
+            return self.answer_free_busy(component).await;
+        }
+
+        let email = self.email_of(user_id).await?;
+        let organizer = scheduling::organizer(&message);
+        let recipients = match method {
+            ItipMethod::Request | ItipMethod::Cancel => {
+                if organizer.as_ref() != Some(&email) {
+                    return Err(DomainError::access_denied(
+                        "Calendar",
+                        "Only the organizer can send invitations and cancellations",
+                    ));
+                }
+                scheduling::attendees(&message)
+            }
+            ItipMethod::Reply => {
+                if scheduling::participation(&message, &email).is_empty() {
+                    return Err(DomainError::access_denied(
+                        "Calendar",
+                        "Only attendees can reply to an invitation",
+                    ));
+                }
+                organizer.into_iter().collect()
+            }
+        };
+
+        let mut responses = Vec::with_capacity(recipients.len());
+        for recipient in recipients {
+            responses.push(ScheduleResponseDto {
+                request_status: self.deliver_to(&recipient, &email, &message).await,
+                recipient: format!("mailto:{}", recipient),
+                calendar_data: None,
+            });
+        }
+        Ok(responses)
+    }
+
+    async fn list_inbox(&self, user_id: &str) -> Result<Vec<ScheduleMessage>> {
+        self.inbox.list_messages(user_id).await
+    }
+
+    async fn get_inbox_message(&self, message_id: &str, user_id: &str) -> Result<ScheduleMessage> {
+        self.inbox
+            .find_message(user_id, message_id)
+            .await?
+            .ok_or_else(|| DomainError::not_found("Schedule message", message_id))
+    }
+
+    async fn delete_inbox_message(&self, message_id: &str, user_id: &str) -> Result<()> {
+        self.get_inbox_message(message_id, user_id).await?;
+        self.inbox.delete_message(user_id, message_id).await
+    }
+}
@@ -7,10 +7,16 @@ use tokio::time;
 
 use crate::application::dtos::file_dto::FileDto;
 use crate::application::dtos::folder_dto::FolderDto;
-use crate::application::dtos::search_dto::{SearchCriteriaDto, SearchResultsDto};
+use crate::application::dtos::search_dto::{ContentMatchDto, SearchCriteriaDto, SearchResultsDto};
+use crate::application::ports::content_index_ports::ContentIndexUseCase;
 use crate::application::ports::inbound::SearchUseCase;
 use crate::application::ports::outbound::{FileStoragePort, FolderStoragePort};
-use crate::common::errors::Result;
+use crate::common::errors::{DomainError, ErrorKind, Result};
+use crate::domain::entities::content_index::{owner_for_path, SHARED_OWNER};
+
+/// Máximo de coincidencias que se piden al índice de contenido antes de
+/// aplicar el resto de criterios y la paginación
+const MAX_CONTENT_CANDIDATES: usize = 1000;
 
 /**
  * Implementación del servicio de búsqueda para archivos y carpetas.
@@ -19,6 +25,9 @@ use crate::common::errors::Result;
  * a los usuarios encontrar archivos y carpetas basados en diversos criterios
  * como nombre, tipo, fecha y tamaño. También incluye una caché para mejorar
  * el rendimiento de búsquedas repetidas.
+ *
+ * Con un índice de contenido configurado, `content_contains` busca en el
+ * texto de los documentos en lugar de recorrer el árbol de carpetas.
  */
 pub struct SearchService {
     /// Repositorio para operaciones con archivos
@@ -35,6 +44,9 @@ pub struct SearchService {
 
     /// Tamaño máximo de la caché (número de resultados almacenados)
     max_cache_size: usize,
+
+    /// Índice opcional del texto de los documentos
+    content_index: Option<Arc<dyn ContentIndexUseCase>>,
 }
 
 /// Clave para la caché de búsqueda
@@ -77,6 +89,7 @@ impl SearchService {
             search_cache: Arc::new(Mutex::new(HashMap::new())),
             cache_ttl,
             max_cache_size,
+            content_index: None,
         };
 
         // Iniciar tarea de limpieza de caché si TTL > 0
@@ -87,6 +100,16 @@ impl SearchService {
         search_service
     }
 
+    /**
+     * Habilita la búsqueda por contenido con el índice indicado.
+     *
+     * @param content_index Índice del texto de los documentos
+     */
+    pub fn with_content_index(mut self, content_index: Arc<dyn ContentIndexUseCase>) -> Self {
+        self.content_index = Some(content_index);
+        self
+    }
+
     /**
      * Inicia una tarea asíncrona para limpiar entradas expiradas de la caché.
      *
@@ -239,6 +262,20 @@ impl SearchService {
                     }
                 }
 
+                // Filtrar por tipo MIME, exacto o por prefijo ("image/")
+                if let Some(content_type) = &criteria.content_type {
+                    let matches = match content_type.strip_suffix('/') {
+                        Some(_) => file
+                            .mime_type
+                            .to_lowercase()
+                            .starts_with(&content_type.to_lowercase()),
+                        None => file.mime_type.eq_ignore_ascii_case(content_type),
+                    };
+                    if !matches {
+                        return false;
+                    }
+                }
+
                 // Filtrar por fecha de creación
                 if let Some(created_after) = criteria.created_after {
                     if file.created_at < created_after {
@@ -298,6 +335,11 @@ impl SearchService {
         folders
             .into_iter()
             .filter(|folder| {
+                // Las carpetas no tienen tipo MIME
+                if criteria.content_type.is_some() {
+                    return false;
+                }
+
                 // Filtrar por nombre
                 if let Some(name_query) = &criteria.name_contains {
                     if !folder
@@ -340,6 +382,93 @@ impl SearchService {
             .collect()
     }
 
+    /**
+     * Busca archivos por su contenido usando el índice de texto.
+     *
+     * Cada coincidencia del índice se comprueba contra el estado actual del
+     * archivo: se descartan los que ya no existen, los que ahora están en la
+     * carpeta personal de otro usuario y los que no cumplen el resto de criterios.
+     *
+     * @param query Palabras que deben aparecer en el texto
+     * @param criteria Criterios de búsqueda
+     * @param username Usuario que busca
+     * @return Archivos ordenados por relevancia y sus coincidencias
+     */
+    async fn search_content(
+        &self,
+        query: &str,
+        criteria: &SearchCriteriaDto,
+        username: Option<&str>,
+    ) -> Result<(Vec<FileDto>, Vec<ContentMatchDto>)> {
+        let content_index = self.content_index.as_ref().ok_or_else(|| {
+            DomainError::new(
+                ErrorKind::NotImplemented,
+                "Search",
+                "Content search is not enabled",
+            )
+        })?;
+        let owner = username.unwrap_or(SHARED_OWNER);
+
+        // Ruta de la carpeta que limita la búsqueda recursiva
+        let folder_path = match (&criteria.folder_id, criteria.recursive) {
+            (Some(folder_id), true) => Some(
+                self.folder_repository
+                    .get_folder(folder_id)
+                    .await?
+                    .path_string()
+                    .trim_matches('/')
+                    .to_string(),
+            ),
+            _ => None,
+        };
+
+        let matches = content_index
+            .search(owner, query, MAX_CONTENT_CANDIDATES)
+            .await?;
+
+        let mut candidates = Vec::with_capacity(matches.len());
+        for content_match in matches {
+            let Ok(file) = self
+                .file_repository
+                .get_file(&content_match.document.file_id)
+                .await
+            else {
+                continue;
+            };
+            let file = FileDto::from(file);
+
+            let file_owner = owner_for_path(&file.path);
+            if file_owner != SHARED_OWNER && file_owner != owner {
+                continue;
+            }
+            let in_scope = match (&criteria.folder_id, &folder_path) {
+                (None, _) => true,
+                (Some(_), Some(folder_path)) => file
+                    .path
+                    .trim_matches('/')
+                    .strip_prefix(folder_path.as_str())
+                    .is_some_and(|rest| rest.starts_with('/')),
+                (Some(folder_id), None) => file.folder_id.as_deref() == Some(folder_id.as_str()),
+            };
+            if in_scope {
+                candidates.push((file, content_match));
+            }
+        }
+
+        // El resto de criterios se aplica sobre los archivos actuales
+        let files = self.filter_files(
+            candidates.iter().map(|(file, _)| file.clone()).collect(),
+            criteria,
+        );
+        let matches = candidates
+            .into_iter()
+            .filter(|(file, _)| files.iter().any(|kept| kept.id == file.id))
+            .map(|(_, content_match)| ContentMatchDto::from(content_match))
+            .collect();
+
+        Ok((files, matches))
+    }
+
     /**
      * Implementación de la búsqueda recursiva a través de carpetas.
      *
@@ -364,23 +493,22 @@ impl SearchService {
                 self.filter_files(files.into_iter().map(FileDto::from).collect(), criteria);
             found_files.extend(filtered_files);
 
-            // Si la búsqueda es recursiva, procesar subcarpetas
-            if criteria.recursive {
-                // Listar subcarpetas
-                let folders = self
-                    .folder_repository
-                    .list_folders(current_folder_id)
-                    .await?;
-
-                // Filtrar carpetas según criterios y agregarlas a los resultados
-                let filtered_folders: Vec<FolderDto> = self
-                    .filter_folders(folders.into_iter().map(FolderDto::from).collect(), criteria);
+            // Listar subcarpetas
+            let folders: Vec<FolderDto> = self
+                .folder_repository
+                .list_folders(current_folder_id)
+                .await?
+                .into_iter()
+                .map(FolderDto::from)
+                .collect();
 
-                // Añadir las carpetas filtradas a los resultados
-                found_folders.extend(filtered_folders.iter().cloned());
+            // Filtrar carpetas según criterios y agregarlas a los resultados
+            found_folders.extend(self.filter_folders(folders.clone(), criteria));
 
-                // Buscar recursivamente en cada subcarpeta
-                for folder in filtered_folders {
+            // Si la búsqueda es recursiva, buscar en todas las subcarpetas,
+            // cumplan o no los criterios
+            if criteria.recursive {
+                for folder in folders {
                     self.search_recursive(Some(&folder.id), criteria, found_files, found_folders)
                         .await?;
                 }
@@ -400,16 +528,49 @@ impl SearchUseCase for SearchService {
      * @param criteria Criterios de búsqueda
      * @return Resultados de la búsqueda
      */
-    async fn search(&self, criteria: SearchCriteriaDto) -> Result<SearchResultsDto> {
-        // TODO: Obtener ID de usuario del contexto de autenticación
-        let user_id = "default-user";
-        let cache_key = self.create_cache_key(&criteria, user_id);
+    async fn search(
+        &self,
+        criteria: SearchCriteriaDto,
+        username: Option<&str>,
+    ) -> Result<SearchResultsDto> {
+        let cache_key = self.create_cache_key(&criteria, username.unwrap_or(SHARED_OWNER));
 
         // Intentar obtener resultados de la caché
         if let Some(cached_results) = self.get_from_cache(&cache_key) {
             return Ok(cached_results);
         }
 
+        // Búsqueda por contenido: solo archivos, ordenados por relevancia
+        if let Some(query) = criteria
+            .content_contains
+            .as_deref()
+            .filter(|query| !query.trim().is_empty())
+        {
+            let (files, matches) = self.search_content(query, &criteria, username).await?;
+            let total_count = files.len();
+            let files: Vec<FileDto> = files
+                .into_iter()
+                .skip(criteria.offset)
+                .take(criteria.limit)
+                .collect();
+            let matches = matches
+                .into_iter()
+                .skip(criteria.offset)
+                .take(criteria.limit)
+                .collect();
+
+            let search_results = SearchResultsDto::new(
+                files,
+                Vec::new(),
+                criteria.limit,
+                criteria.offset,
+                Some(total_count),
+            )
+            .with_content_matches(matches);
+            self.store_in_cache(cache_key, search_results.clone());
+            return Ok(search_results);
+        }
+
         // Inicializar colecciones para resultados
         let mut found_files: Vec<FileDto> = Vec::new();
         let mut found_folders: Vec<FolderDto> = Vec::new();
@@ -497,7 +658,11 @@ impl SearchService {
 
         #[async_trait]
         impl SearchUseCase for SearchServiceStub {
-            async fn search(&self, _criteria: SearchCriteriaDto) -> Result<SearchResultsDto> {
+            async fn search(
+                &self,
+                _criteria: SearchCriteriaDto,
+                _username: Option<&str>,
+            ) -> Result<SearchResultsDto> {
                 Ok(SearchResultsDto::empty())
             }
 
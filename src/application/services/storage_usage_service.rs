@@ -1,5 +1,6 @@
 use crate::application::ports::auth_ports::UserStoragePort;
-use crate::application::ports::storage_ports::StorageUsagePort;
+use crate::application::ports::storage_ports::{StorageQuota, StorageUsagePort};
+use crate::application::ports::version_ports::FileVersionStoragePort;
 use crate::common::errors::DomainError;
 use crate::domain::repositories::file_repository::FileRepository;
 use async_trait::async_trait;
@@ -16,6 +17,7 @@ use tracing::{debug, error, info};
 pub struct StorageUsageService {
     file_repository: Arc<dyn FileRepository>,
     user_repository: Arc<dyn UserStoragePort>,
+    version_repository: Option<Arc<dyn FileVersionStoragePort>>,
 }
 
 impl StorageUsageService {
@@ -27,9 +29,19 @@ impl StorageUsageService {
         Self {
             file_repository,
             user_repository,
+            version_repository: None,
         }
     }
 
+    /// Counts archived file versions towards the user's storage usage
+    pub fn with_version_repository(
+        mut self,
+        version_repository: Arc<dyn FileVersionStoragePort>,
+    ) -> Self {
+        self.version_repository = Some(version_repository);
+        self
+    }
+
     /// Calculates and updates storage usage for a specific user
     pub async fn update_user_storage_usage(&self, user_id: &str) -> Result<i64, DomainError> {
         info!("Updating storage usage for user: {}", user_id);
@@ -103,6 +115,7 @@ impl StorageUsageService {
         // Implementation with explicit boxing to handle recursion in async functions
         async fn inner_calculate_size(
             repo: Arc<dyn FileRepository>,
+            versions: Option<Arc<dyn FileVersionStoragePort>>,
             folder_id: &str,
         ) -> Result<i64, DomainError> {
             let mut total_size: i64 = 0;
@@ -122,8 +135,11 @@ impl StorageUsageService {
                     let repo_clone = repo.clone(); // Clone the repository
 
                     // Use Box::pin to handle recursive async call
-                    let subfolder_size_future =
-                        Box::pin(inner_calculate_size(repo_clone, &subfolder_id));
+                    let subfolder_size_future = Box::pin(inner_calculate_size(
+                        repo_clone,
+                        versions.clone(),
+                        &subfolder_id,
+                    ));
 
                     match subfolder_size_future.await {
                         Ok(size) => {
@@ -140,6 +156,18 @@ impl StorageUsageService {
                 } else {
                     // Add file size to total
                     total_size += file.size() as i64;
+
+                    // Archived versions count towards the quota too
+                    if let Some(versions) = &versions {
+                        match versions.total_version_size(file.id()).await {
+                            Ok(size) => total_size += size as i64,
+                            Err(e) => error!(
+                                "Error calculating version size for file {}: {}",
+                                file.id(),
+                                e
+                            ),
+                        }
+                    }
                 }
             }
 
@@ -148,7 +176,7 @@ impl StorageUsageService {
 
         // Start the calculation with a clone of our repository reference
         let repo_clone = Arc::clone(&self.file_repository);
-        inner_calculate_size(repo_clone, folder_id).await
+        inner_calculate_size(repo_clone, self.version_repository.clone(), folder_id).await
     }
 }
 
@@ -162,6 +190,16 @@ impl StorageUsagePort for StorageUsageService {
         StorageUsageService::update_user_storage_usage(self, user_id).await
     }
 
+    async fn get_user_quota(&self, user_id: &str) -> Result<StorageQuota, DomainError> {
+        let user = self.user_repository.get_user_by_id(user_id).await?;
+        let quota = user.storage_quota_bytes();
+        Ok(StorageQuota {
+            used_bytes: user.storage_used_bytes().max(0) as u64,
+            // A quota of zero or less means the user has no limit
+            limit_bytes: (quota > 0).then_some(quota as u64),
+        })
+    }
+
     async fn update_all_users_storage_usage(&self) -> Result<(), DomainError> {
         info!("Starting batch update of all users' storage usage");
 
@@ -212,6 +250,7 @@ impl Clone for StorageUsageService {
         Self {
             file_repository: Arc::clone(&self.file_repository),
             user_repository: Arc::clone(&self.user_repository),
+            version_repository: self.version_repository.clone(),
         }
     }
 }
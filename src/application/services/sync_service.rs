@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use crate::application::ports::sync_ports::{ChangeLogStoragePort, ChangeLogUseCase};
+use crate::common::config::WebDavConfig;
+use crate::common::errors::{DomainError, Result};
+use crate::domain::entities::sync_change::{ChangeKind, SyncChange};
+
+/// In-memory view of the change log
+struct ChangeLog {
+    changes: VecDeque<SyncChange>,
+    next_seq: u64,
+    /// Oldest sequence number a client can still sync from
+    floor: u64,
+}
+
+/**
+ * Service keeping the change log behind WebDAV sync-collection reports.
+ *
+ * Every create, update, delete and move gets a server-wide sequence number
+ * and is appended to storage. Sync tokens are those sequence numbers, so
+ * answering a report is a scan of the retained entries. Only the newest
+ * entries are kept; clients holding an older token get an error and fall
+ * back to a full sync.
+ */
+pub struct ChangeLogService {
+    storage: Arc<dyn ChangeLogStoragePort>,
+    log: RwLock<ChangeLog>,
+    max_entries: usize,
+}
+
+impl ChangeLogService {
+    /// Creates a new change log service with an empty log
+    pub fn new(storage: Arc<dyn ChangeLogStoragePort>, config: &WebDavConfig) -> Self {
+        Self {
+            storage,
+            log: RwLock::new(ChangeLog {
+                changes: VecDeque::new(),
+                next_seq: 1,
+                floor: 0,
+            }),
+            max_entries: config.sync_log_max_entries.max(1),
+        }
+    }
+
+    /// Restores the changes recorded before the server stopped
+    pub async fn load_persisted_changes(&self) -> Result<usize> {
+        let mut log = self.log.write().await;
+        let changes = self.storage.load_changes().await?;
+        if let Some(last) = changes.last() {
+            log.next_seq = last.seq + 1;
+            log.floor = changes[0].seq - 1;
+        }
+        log.changes = changes.into();
+        self.trim(&mut log).await;
+        info!(
+            "Restored {} WebDAV sync changes (current sequence {})",
+            log.changes.len(),
+            log.next_seq - 1
+        );
+        Ok(log.changes.len())
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    /// Drops the oldest entries once the log grows a tenth past its limit,
+    /// so the storage is not rewritten on every change
+    async fn trim(&self, log: &mut ChangeLog) {
+        if log.changes.len() <= self.max_entries + self.max_entries / 10 {
+            return;
+        }
+
+        let excess = log.changes.len() - self.max_entries;
+        log.changes.drain(..excess);
+        if let Some(first) = log.changes.front() {
+            log.floor = first.seq - 1;
+        }
+
+        let changes: Vec<SyncChange> = log.changes.iter().cloned().collect();
+        if let Err(e) = self.storage.replace_changes(&changes).await {
+            warn!("Failed to compact the WebDAV change log: {}", e);
+        }
+        debug!("Dropped {} old WebDAV sync changes", excess);
+    }
+}
+
+#[async_trait]
+impl ChangeLogUseCase for ChangeLogService {
+    async fn record(
+        &self,
+        kind: ChangeKind,
+        resource_id: &str,
+        is_folder: bool,
+        path: &str,
+        previous_path: Option<&str>,
+    ) {
+        let mut log = self.log.write().await;
+        let change = SyncChange {
+            seq: log.next_seq,
+            kind,
+            resource_id: resource_id.to_string(),
+            is_folder,
+            path: path.trim_matches('/').to_string(),
+            previous_path: previous_path.map(|p| p.trim_matches('/').to_string()),
+            changed_at: Self::now(),
+        };
+        log.next_seq += 1;
+
+        // Tokens stay valid even if this write fails; the change is only lost
+        // after a restart, where clients resync anyway from the stored floor
+        if let Err(e) = self.storage.append_change(&change).await {
+            warn!("Failed to persist WebDAV sync change: {}", e);
+        }
+        log.changes.push_back(change);
+        self.trim(&mut log).await;
+    }
+
+    async fn current_seq(&self, collection: &str) -> u64 {
+        let log = self.log.read().await;
+        log.changes
+            .iter()
+            .rev()
+            .find(|change| change.affects(collection))
+            .map(|change| change.seq)
+            .unwrap_or(log.floor)
+    }
+
+    async fn changes_since(&self, collection: &str, since: u64) -> Result<Vec<SyncChange>> {
+        let log = self.log.read().await;
+        if since < log.floor || since >= log.next_seq {
+            return Err(DomainError::validation_error(format!(
+                "Sync token {} is no longer valid",
+                since
+            )));
+        }
+
+        Ok(log
+            .changes
+            .iter()
+            .filter(|change| change.seq > since && change.affects(collection))
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    /// Almacén en memoria que cuenta las reescrituras
+    #[derive(Default)]
+    struct MemoryChangeLog {
+        saved: StdMutex<Vec<SyncChange>>,
+        rewrites: StdMutex<usize>,
+    }
+
+    #[async_trait]
+    impl ChangeLogStoragePort for MemoryChangeLog {
+        async fn load_changes(&self) -> Result<Vec<SyncChange>> {
+            Ok(self.saved.lock().unwrap().clone())
+        }
+
+        async fn append_change(&self, change: &SyncChange) -> Result<()> {
+            self.saved.lock().unwrap().push(change.clone());
+            Ok(())
+        }
+
+        async fn replace_changes(&self, changes: &[SyncChange]) -> Result<()> {
+            *self.saved.lock().unwrap() = changes.to_vec();
+            *self.rewrites.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
+
+    fn service(storage: Arc<MemoryChangeLog>, max_entries: usize) -> ChangeLogService {
+        let config = WebDavConfig {
+            sync_log_max_entries: max_entries,
+            ..WebDavConfig::default()
+        };
+        ChangeLogService::new(storage, &config)
+    }
+
+    #[tokio::test]
+    async fn test_changes_are_scoped_to_collection() {
+        let service = service(Arc::new(MemoryChangeLog::default()), 100);
+        service
+            .record(ChangeKind::Created, "a", false, "/docs/a.txt", None)
+            .await;
+        let token = service.current_seq("docs").await;
+        service
+            .record(ChangeKind::Created, "b", false, "/photos/b.jpg", None)
+            .await;
+        service
+            .record(
+                ChangeKind::Moved,
+                "a",
+                false,
+                "/photos/a.txt",
+                Some("/docs/a.txt"),
+            )
+            .await;
+
+        assert_eq!(token, 1);
+        assert_eq!(service.current_seq("photos").await, 3);
+        assert_eq!(service.current_seq("music").await, 0);
+
+        let changes = service.changes_since("docs", token).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].previous_path.as_deref(), Some("docs/a.txt"));
+        assert_eq!(service.changes_since("photos", 0).await.unwrap().len(), 2);
+        assert!(service.changes_since("docs", 4).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_trimmed_tokens_are_rejected_and_log_survives_restart() {
+        let storage = Arc::new(MemoryChangeLog::default());
+        let first = service(storage.clone(), 10);
+        for i in 0..12 {
+            first
+                .record(ChangeKind::Updated, "a", false, "docs/a.txt", None)
+                .await;
+            assert_eq!(first.current_seq("docs").await, i + 1);
+        }
+        assert_eq!(*storage.rewrites.lock().unwrap(), 1);
+        assert!(first.changes_since("docs", 1).await.is_err());
+        assert_eq!(first.changes_since("docs", 2).await.unwrap().len(), 10);
+
+        let restarted = service(storage, 10);
+        assert_eq!(restarted.load_persisted_changes().await.unwrap(), 10);
+        assert_eq!(restarted.current_seq("docs").await, 12);
+        assert!(restarted.changes_since("docs", 1).await.is_err());
+        restarted
+            .record(ChangeKind::Deleted, "a", false, "docs/a.txt", None)
+            .await;
+        let changes = restarted.changes_since("docs", 12).await.unwrap();
+        assert_eq!(changes[0].seq, 13);
+    }
+}
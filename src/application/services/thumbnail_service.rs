@@ -0,0 +1,411 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tracing::{debug, warn};
+
+use crate::application::ports::outbound::FileStoragePort;
+use crate::application::ports::thumbnail_ports::{
+    ThumbnailRendererPort, ThumbnailStoragePort, ThumbnailUseCase,
+};
+use crate::common::config::ThumbnailConfig;
+use crate::common::errors::{DomainError, Result};
+use crate::domain::entities::file::File;
+use crate::domain::entities::thumbnail::{
+    content_fingerprint, supports_mime_type, Thumbnail, ThumbnailFormat, ThumbnailSize,
+};
+
+/// Trabajos de generación que pueden esperar en cola
+const QUEUE_CAPACITY: usize = 256;
+
+/// Trabajo de generación para uno o varios tamaños de un archivo
+struct ThumbnailJob {
+    file_id: String,
+    /// La primera variante es la que se devuelve por `reply`
+    variants: Vec<(ThumbnailSize, ThumbnailFormat)>,
+    reply: Option<oneshot::Sender<Result<Thumbnail>>>,
+}
+
+/**
+ * Generates and caches image thumbnails.
+ *
+ * Thumbnails are keyed by a fingerprint of the original content, so a cached
+ * thumbnail is never served for content that changed. Rendering runs on a
+ * fixed number of worker tasks fed by a bounded queue: request handlers only
+ * read the cache and wait for a worker on a miss, and uploads enqueue all
+ * sizes without waiting.
+ */
+pub struct ThumbnailService {
+    generator: Arc<ThumbnailGenerator>,
+    jobs: mpsc::Sender<ThumbnailJob>,
+    generate_on_upload: bool,
+}
+
+/// Parte del servicio que comparten los workers
+struct ThumbnailGenerator {
+    file_repository: Arc<dyn FileStoragePort>,
+    renderer: Arc<dyn ThumbnailRendererPort>,
+    storage: Arc<dyn ThumbnailStoragePort>,
+    max_source_size: u64,
+}
+
+impl ThumbnailService {
+    /// Creates the service and starts its worker tasks
+    pub fn new(
+        file_repository: Arc<dyn FileStoragePort>,
+        renderer: Arc<dyn ThumbnailRendererPort>,
+        storage: Arc<dyn ThumbnailStoragePort>,
+        config: &ThumbnailConfig,
+    ) -> Self {
+        let generator = Arc::new(ThumbnailGenerator {
+            file_repository,
+            renderer,
+            storage,
+            max_source_size: config.max_source_size,
+        });
+
+        let (jobs, receiver) = mpsc::channel::<ThumbnailJob>(QUEUE_CAPACITY);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..config.workers.max(1) {
+            let generator = generator.clone();
+            let receiver = receiver.clone();
+            tokio::spawn(async move {
+                loop {
+                    let job = receiver.lock().await.recv().await;
+                    let Some(job) = job else { break };
+                    let result = generator.generate(&job.file_id, &job.variants).await;
+                    match job.reply {
+                        Some(reply) => {
+                            let _ = reply.send(result);
+                        }
+                        None => {
+                            if let Err(e) = result {
+                                debug!("Background thumbnails of {} failed: {}", job.file_id, e);
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
+        Self {
+            generator,
+            jobs,
+            generate_on_upload: config.generate_on_upload,
+        }
+    }
+}
+
+impl ThumbnailGenerator {
+    /// Loads a file and checks that thumbnails can be made from it
+    async fn thumbnail_source(&self, file_id: &str) -> Result<File> {
+        let file = self.file_repository.get_file(file_id).await?;
+        if !supports_mime_type(file.mime_type()) {
+            return Err(DomainError::not_found(
+                "Thumbnail",
+                format!("{} ({} has no thumbnails)", file_id, file.mime_type()),
+            ));
+        }
+        if self.max_source_size > 0 && file.size() > self.max_source_size {
+            return Err(DomainError::not_found(
+                "Thumbnail",
+                format!("{} (image too large for thumbnails)", file_id),
+            ));
+        }
+        Ok(file)
+    }
+
+    async fn cached(
+        &self,
+        file: &File,
+        size: ThumbnailSize,
+        format: ThumbnailFormat,
+    ) -> Result<Option<Thumbnail>> {
+        let fingerprint = content_fingerprint(file.size(), file.modified_at());
+        let content = self
+            .storage
+            .get_thumbnail(file.id(), &fingerprint, size, format)
+            .await?;
+        Ok(content.map(|content| Thumbnail {
+            file_id: file.id().to_string(),
+            size,
+            format,
+            fingerprint,
+            content,
+        }))
+    }
+
+    /// Renders the missing variants of a file and returns the first one
+    async fn generate(
+        &self,
+        file_id: &str,
+        variants: &[(ThumbnailSize, ThumbnailFormat)],
+    ) -> Result<Thumbnail> {
+        let file = self.thumbnail_source(file_id).await?;
+        let fingerprint = content_fingerprint(file.size(), file.modified_at());
+
+        let mut source: Option<Vec<u8>> = None;
+        let mut first = None;
+        for &(size, format) in variants {
+            let thumbnail = match self.cached(&file, size, format).await? {
+                Some(thumbnail) => thumbnail,
+                None => {
+                    if source.is_none() {
+                        source = Some(self.file_repository.get_file_content(file_id).await?);
+                    }
+                    let content = self
+                        .renderer
+                        .render(
+                            source.as_deref().unwrap_or_default(),
+                            file.mime_type(),
+                            size,
+                            format,
+                        )
+                        .await?;
+                    let thumbnail = Thumbnail {
+                        file_id: file_id.to_string(),
+                        size,
+                        format,
+                        fingerprint: fingerprint.clone(),
+                        content,
+                    };
+                    self.storage.store_thumbnail(&thumbnail).await?;
+                    debug!(
+                        "Generated {} {} thumbnail of {}",
+                        size.name(),
+                        format.extension(),
+                        file_id
+                    );
+                    thumbnail
+                }
+            };
+            first.get_or_insert(thumbnail);
+        }
+
+        first.ok_or_else(|| DomainError::validation_error("No thumbnail variants requested"))
+    }
+}
+
+#[async_trait]
+impl ThumbnailUseCase for ThumbnailService {
+    async fn get_thumbnail(
+        &self,
+        file_id: &str,
+        size: ThumbnailSize,
+        format: ThumbnailFormat,
+    ) -> Result<Thumbnail> {
+        let file = self.generator.thumbnail_source(file_id).await?;
+        if let Some(thumbnail) = self.generator.cached(&file, size, format).await? {
+            return Ok(thumbnail);
+        }
+
+        let (reply, response) = oneshot::channel();
+        self.jobs
+            .send(ThumbnailJob {
+                file_id: file_id.to_string(),
+                variants: vec![(size, format)],
+                reply: Some(reply),
+            })
+            .await
+            .map_err(|_| DomainError::internal_error("Thumbnail", "Thumbnail workers stopped"))?;
+
+        response.await.map_err(|_| {
+            DomainError::internal_error("Thumbnail", "Thumbnail worker dropped the request")
+        })?
+    }
+
+    fn schedule_generation(&self, file_id: &str) {
+        if !self.generate_on_upload {
+            return;
+        }
+
+        let variants = ThumbnailSize::ALL
+            .iter()
+            .flat_map(|&size| {
+                ThumbnailFormat::ALL
+                    .iter()
+                    .map(move |&format| (size, format))
+            })
+            .collect();
+        let job = ThumbnailJob {
+            file_id: file_id.to_string(),
+            variants,
+            reply: None,
+        };
+        if self.jobs.try_send(job).is_err() {
+            // Se generarán en la primera petición
+            warn!(
+                "Thumbnail queue full, skipping eager generation for {}",
+                file_id
+            );
+        }
+    }
+
+    async fn invalidate(&self, file_id: &str) -> Result<()> {
+        self.generator.storage.delete_thumbnails(file_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::ports::test_doubles::FakeFiles;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex as StdMutex;
+
+    /// Archivo de 4 bytes con fecha de modificación 100
+    fn files_with(id: &str, mime_type: &str) -> Arc<FakeFiles> {
+        let files = FakeFiles::new();
+        files.add(id, &format!("/{}.bin", id), mime_type, b"img!", 100);
+        files
+    }
+
+    #[derive(Default)]
+    struct CountingRenderer {
+        renders: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ThumbnailRendererPort for CountingRenderer {
+        async fn render(
+            &self,
+            source: &[u8],
+            _mime_type: &str,
+            size: ThumbnailSize,
+            format: ThumbnailFormat,
+        ) -> Result<Vec<u8>> {
+            self.renders.fetch_add(1, Ordering::SeqCst);
+            Ok(format!("{}:{}:{}", source.len(), size.name(), format.extension()).into_bytes())
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        thumbnails: StdMutex<HashMap<String, Vec<u8>>>,
+    }
+
+    fn storage_key(
+        file_id: &str,
+        fingerprint: &str,
+        size: ThumbnailSize,
+        format: ThumbnailFormat,
+    ) -> String {
+        format!("{}/{}/{:?}/{:?}", file_id, fingerprint, size, format)
+    }
+
+    #[async_trait]
+    impl ThumbnailStoragePort for MemoryStorage {
+        async fn get_thumbnail(
+            &self,
+            file_id: &str,
+            fingerprint: &str,
+            size: ThumbnailSize,
+            format: ThumbnailFormat,
+        ) -> Result<Option<Vec<u8>>> {
+            let key = storage_key(file_id, fingerprint, size, format);
+            Ok(self.thumbnails.lock().unwrap().get(&key).cloned())
+        }
+
+        async fn store_thumbnail(&self, thumbnail: &Thumbnail) -> Result<()> {
+            let key = storage_key(
+                &thumbnail.file_id,
+                &thumbnail.fingerprint,
+                thumbnail.size,
+                thumbnail.format,
+            );
+            self.thumbnails
+                .lock()
+                .unwrap()
+                .insert(key, thumbnail.content.clone());
+            Ok(())
+        }
+
+        async fn delete_thumbnails(&self, file_id: &str) -> Result<()> {
+            let prefix = format!("{}/", file_id);
+            self.thumbnails
+                .lock()
+                .unwrap()
+                .retain(|key, _| !key.starts_with(&prefix));
+            Ok(())
+        }
+    }
+
+    fn service(
+        files: Arc<FakeFiles>,
+    ) -> (ThumbnailService, Arc<CountingRenderer>, Arc<MemoryStorage>) {
+        let renderer = Arc::new(CountingRenderer::default());
+        let storage = Arc::new(MemoryStorage::default());
+        let service = ThumbnailService::new(
+            files,
+            renderer.clone(),
+            storage.clone(),
+            &ThumbnailConfig::default(),
+        );
+        (service, renderer, storage)
+    }
+
+    #[tokio::test]
+    async fn test_thumbnail_is_cached_until_content_changes() {
+        let files = files_with("photo", "image/jpeg");
+        let (service, renderer, _) = service(files.clone());
+
+        let first = service
+            .get_thumbnail("photo", ThumbnailSize::Small, ThumbnailFormat::Webp)
+            .await
+            .unwrap();
+        assert_eq!(first.content, b"4:small:webp");
+        service
+            .get_thumbnail("photo", ThumbnailSize::Small, ThumbnailFormat::Webp)
+            .await
+            .unwrap();
+        assert_eq!(renderer.renders.load(Ordering::SeqCst), 1);
+
+        // New content means a new fingerprint and a fresh render
+        files.touch("photo", 200);
+        let second = service
+            .get_thumbnail("photo", ThumbnailSize::Small, ThumbnailFormat::Webp)
+            .await
+            .unwrap();
+        assert_ne!(first.fingerprint, second.fingerprint);
+        assert_eq!(renderer.renders.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_non_images_have_no_thumbnails() {
+        let files = files_with("doc", "application/pdf");
+        let (service, renderer, _) = service(files);
+
+        let err = service
+            .get_thumbnail("doc", ThumbnailSize::Medium, ThumbnailFormat::Jpeg)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, crate::common::errors::ErrorKind::NotFound);
+        assert_eq!(renderer.renders.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_generation_and_invalidation() {
+        let files = files_with("photo", "image/png");
+        let (service, renderer, storage) = service(files);
+
+        service.schedule_generation("photo");
+        let expected = ThumbnailSize::ALL.len() * ThumbnailFormat::ALL.len();
+        for _ in 0..100 {
+            if storage.thumbnails.lock().unwrap().len() == expected {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(storage.thumbnails.lock().unwrap().len(), expected);
+
+        // Served from the cache without rendering again
+        service
+            .get_thumbnail("photo", ThumbnailSize::Large, ThumbnailFormat::Jpeg)
+            .await
+            .unwrap();
+        assert_eq!(renderer.renders.load(Ordering::SeqCst), expected);
+
+        service.invalidate("photo").await.unwrap();
+        assert!(storage.thumbnails.lock().unwrap().is_empty());
+    }
+}
@@ -4,7 +4,10 @@ use tracing::{debug, error, info, instrument};
 use uuid::Uuid;
 
 use crate::application::dtos::trash_dto::TrashedItemDto;
+use crate::application::ports::content_index_ports::ContentIndexUseCase;
+use crate::application::ports::storage_ports::BlockGarbageCollectorPort;
 use crate::application::ports::trash_ports::TrashUseCase;
+use crate::application::ports::version_ports::FileVersionStoragePort;
 use crate::common::errors::{DomainError, ErrorKind, Result};
 use crate::domain::entities::trashed_item::{TrashedItem, TrashedItemType};
 use crate::domain::repositories::file_repository::FileRepository;
@@ -36,6 +39,15 @@ pub struct TrashService {
 
     /// Number of days items should be kept in trash before automatic cleanup
     retention_days: u32,
+
+    /// Optional collector for content blocks left unreferenced by permanent deletions
+    garbage_collector: Option<Arc<dyn BlockGarbageCollectorPort>>,
+
+    /// Optional version storage whose history is dropped with permanently deleted files
+    version_storage: Option<Arc<dyn FileVersionStoragePort>>,
+
+    /// Optional full-text index that must not return trashed files
+    content_index: Option<Arc<dyn ContentIndexUseCase>>,
 }
 
 impl TrashService {
@@ -50,6 +62,56 @@ impl TrashService {
             file_repository,
             folder_repository,
             retention_days,
+            garbage_collector: None,
+            version_storage: None,
+            content_index: None,
+        }
+    }
+
+    /// Runs block garbage collection after permanent deletions
+    pub fn with_garbage_collector(
+        mut self,
+        garbage_collector: Arc<dyn BlockGarbageCollectorPort>,
+    ) -> Self {
+        self.garbage_collector = Some(garbage_collector);
+        self
+    }
+
+    /// Deletes the version history of files when they are permanently deleted
+    pub fn with_version_storage(
+        mut self,
+        version_storage: Arc<dyn FileVersionStoragePort>,
+    ) -> Self {
+        self.version_storage = Some(version_storage);
+        self
+    }
+
+    /// Hides trashed files from content search and indexes them again on restore
+    pub fn with_content_index(mut self, content_index: Arc<dyn ContentIndexUseCase>) -> Self {
+        self.content_index = Some(content_index);
+        self
+    }
+
+    /// Removes the archived versions of a permanently deleted file, logging failures
+    async fn purge_versions(&self, file_id: &str) {
+        if let Some(versions) = &self.version_storage {
+            if let Err(e) = versions.delete_all_versions(file_id).await {
+                error!("Error deleting version history of file {}: {}", file_id, e);
+            }
+        }
+    }
+
+    /// Releases storage for blocks no longer referenced by any file.
+    ///
+    /// Failures are logged and never propagated: the deletion itself already succeeded.
+    async fn collect_garbage(&self) {
+        if let Some(collector) = &self.garbage_collector {
+            match collector.collect_garbage().await {
+                Ok((chunks, bytes)) => {
+                    debug!("Block GC freed {} chunks ({} bytes)", chunks, bytes)
+                }
+                Err(e) => error!("Block garbage collection failed: {}", e),
+            }
         }
     }
 
@@ -213,6 +275,10 @@ impl TrashUseCase for TrashService {
                     }
                 }
 
+                if let Some(content_index) = &self.content_index {
+                    content_index.remove_file(item_id).await;
+                }
+
                 info!("File completely moved to trash: {}", item_id);
                 Ok(())
             }
@@ -238,7 +304,7 @@ impl TrashUseCase for TrashService {
                     user_uuid,
                     TrashedItemType::Folder,
                     folder.name().to_string(),
-                    original_path,
+                    original_path.clone(),
                     self.retention_days,
                 );
 
@@ -267,6 +333,10 @@ impl TrashUseCase for TrashService {
                         )
                     })?;
 
+                if let Some(content_index) = &self.content_index {
+                    content_index.remove_folder(&original_path).await;
+                }
+
                 debug!("Folder moved to trash: {}", item_id);
                 Ok(())
             }
@@ -341,6 +411,9 @@ impl TrashUseCase for TrashService {
                         {
                             Ok(_) => {
                                 info!("Successfully restored file from trash: {}", file_id);
+                                if let Some(content_index) = &self.content_index {
+                                    content_index.index_file(&file_id).await;
+                                }
                             }
                             Err(e) => {
                                 // Check if the error is because the file is not found
@@ -378,6 +451,9 @@ impl TrashUseCase for TrashService {
                         {
                             Ok(_) => {
                                 info!("Successfully restored folder from trash: {}", folder_id);
+                                if let Some(content_index) = &self.content_index {
+                                    content_index.index_folder(Some(&folder_id)).await;
+                                }
                             }
                             Err(e) => {
                                 // Check if the error is because the folder is not found
@@ -534,6 +610,7 @@ impl TrashUseCase for TrashService {
                                 }
                             }
                         }
+                        self.purge_versions(&file_id).await;
                     }
                     TrashedItemType::Folder => {
                         // Eliminar la carpeta permanentemente
@@ -598,6 +675,8 @@ impl TrashUseCase for TrashService {
                     }
                 };
 
+                self.collect_garbage().await;
+
                 info!("Item permanently deleted from trash: {}", trash_id);
                 Ok(())
             }
@@ -639,6 +718,7 @@ impl TrashUseCase for TrashService {
                     if let Err(e) = self.file_repository.delete_file_permanently(&file_id).await {
                         error!("Error permanently deleting file {}: {}", file_id, e);
                     }
+                    self.purge_versions(&file_id).await;
                 }
                 TrashedItemType::Folder => {
                     // Permanently delete the folder
@@ -657,6 +737,8 @@ impl TrashUseCase for TrashService {
         // Clear all trash records for this user
         self.trash_repository.clear_trash(&user_uuid).await?;
 
+        self.collect_garbage().await;
+
         info!("Trash completely emptied for user {}", user_id);
         Ok(())
     }
@@ -1,17 +1,25 @@
 use async_trait::async_trait;
+use bytes::Bytes;
 use chrono::Utc;
+use futures::Stream;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+use crate::application::ports::trash_ports::TrashUseCase;
 use crate::application::services::trash_service::TrashService;
-use crate::common::errors::{DomainError, Result};
+use crate::common::errors::Result;
 use crate::domain::entities::file::File;
 use crate::domain::entities::folder::Folder;
 use crate::domain::entities::trashed_item::{TrashedItem, TrashedItemType};
-use crate::domain::repositories::file_repository::{FileRepository, FileRepositoryResult};
-use crate::domain::repositories::folder_repository::{FolderRepository, FolderRepositoryResult};
+use crate::domain::repositories::file_repository::{
+    FileRepository, FileRepositoryError, FileRepositoryResult,
+};
+use crate::domain::repositories::folder_repository::{
+    FolderRepository, FolderRepositoryError, FolderRepositoryResult,
+};
 use crate::domain::repositories::trash_repository::TrashRepository;
+use crate::domain::services::path_service::StoragePath;
 
 // Mock repositories for testing
 struct MockTrashRepository {
@@ -106,12 +114,11 @@ impl MockFileRepository {
 
     fn add_test_file(&self, id: &str, name: &str, path: &str) {
         let file = File::new(
-            Uuid::parse_str(id).unwrap(),
+            id.to_string(),
             name.to_string(),
-            path.to_string(),
-            "text/plain".to_string(),
+            StoragePath::from_string(path),
             100,
-            Uuid::new_v4(),
+            "text/plain".to_string(),
             None,
         )
         .unwrap();
@@ -128,7 +135,7 @@ impl FileRepository for MockFileRepository {
         if let Some(file) = files.get(id) {
             Ok(file.clone())
         } else {
-            Err("File not found".into())
+            Err(FileRepositoryError::NotFound(id.to_string()))
         }
     }
 
@@ -140,11 +147,11 @@ impl FileRepository for MockFileRepository {
             trashed.insert(id.to_string(), file);
             Ok(())
         } else {
-            Err("File not found".into())
+            Err(FileRepositoryError::NotFound(id.to_string()))
         }
     }
 
-    async fn restore_from_trash(&self, id: &str, original_path: &str) -> FileRepositoryResult<()> {
+    async fn restore_from_trash(&self, id: &str, _original_path: &str) -> FileRepositoryResult<()> {
         let mut files = self.files.lock().unwrap();
         let mut trashed = self.trashed_files.lock().unwrap();
 
@@ -152,7 +159,7 @@ impl FileRepository for MockFileRepository {
             files.insert(id.to_string(), file);
             Ok(())
         } else {
-            Err("File not found in trash".into())
+            Err(FileRepositoryError::NotFound(id.to_string()))
         }
     }
 
@@ -161,32 +168,67 @@ impl FileRepository for MockFileRepository {
         if trashed.remove(id).is_some() {
             Ok(())
         } else {
-            Err("File not found in trash".into())
+            Err(FileRepositoryError::NotFound(id.to_string()))
         }
     }
 
     // Other methods required by the trait (not used in tests)
-    async fn save_file(&self, _file: &File) -> FileRepositoryResult<()> {
-        Ok(())
-    }
-    async fn delete_file(&self, _id: &str) -> FileRepositoryResult<()> {
-        Ok(())
-    }
-    async fn get_files_in_folder(
+    async fn save_file_from_bytes(
+        &self,
+        _name: String,
+        _folder_id: Option<String>,
+        _content_type: String,
+        _content: Vec<u8>,
+    ) -> FileRepositoryResult<File> {
+        unimplemented!()
+    }
+    async fn save_file_with_id(
         &self,
-        _folder_id: Option<&str>,
-    ) -> FileRepositoryResult<Vec<File>> {
+        _id: String,
+        _name: String,
+        _folder_id: Option<String>,
+        _content_type: String,
+        _content: Vec<u8>,
+    ) -> FileRepositoryResult<File> {
+        unimplemented!()
+    }
+    async fn list_files(&self, _folder_id: Option<&str>) -> FileRepositoryResult<Vec<File>> {
         Ok(vec![])
     }
-    async fn move_file(&self, _id: &str, _new_folder_id: Option<&str>) -> FileRepositoryResult<()> {
+    async fn delete_file(&self, _id: &str) -> FileRepositoryResult<()> {
         Ok(())
     }
-    async fn update_file_data(&self, _id: &str, _new_data: &[u8]) -> FileRepositoryResult<()> {
+    async fn delete_file_entry(&self, _id: &str) -> FileRepositoryResult<()> {
         Ok(())
     }
-    async fn get_file_data(&self, _id: &str) -> FileRepositoryResult<Vec<u8>> {
+    async fn get_file_content(&self, _id: &str) -> FileRepositoryResult<Vec<u8>> {
         Ok(vec![])
     }
+    async fn get_file_stream(
+        &self,
+        _id: &str,
+    ) -> FileRepositoryResult<
+        Box<dyn Stream<Item = std::result::Result<Bytes, std::io::Error>> + Send>,
+    > {
+        unimplemented!()
+    }
+    async fn move_file(
+        &self,
+        _id: &str,
+        _target_folder_id: Option<String>,
+    ) -> FileRepositoryResult<File> {
+        unimplemented!()
+    }
+    async fn get_file_path(&self, _id: &str) -> FileRepositoryResult<StoragePath> {
+        unimplemented!()
+    }
+    async fn update_file_content(
+        &self,
+        _file_id: &str,
+        _content: Vec<u8>,
+    ) -> FileRepositoryResult<()> {
+        Ok(())
+    }
 }
 
 struct MockFolderRepository {
@@ -204,9 +246,9 @@ impl MockFolderRepository {
 
     fn add_test_folder(&self, id: &str, name: &str, path: &str) {
         let folder = Folder::new(
-            Uuid::parse_str(id).unwrap(),
+            id.to_string(),
             name.to_string(),
-            path.to_string(),
+            StoragePath::from_string(path),
             None,
         )
         .unwrap();
@@ -223,7 +265,7 @@ impl FolderRepository for MockFolderRepository {
         if let Some(folder) = folders.get(id) {
             Ok(folder.clone())
         } else {
-            Err("Folder not found".into())
+            Err(FolderRepositoryError::NotFound(id.to_string()))
         }
     }
 
@@ -235,14 +277,14 @@ impl FolderRepository for MockFolderRepository {
             trashed.insert(id.to_string(), folder);
             Ok(())
         } else {
-            Err("Folder not found".into())
+            Err(FolderRepositoryError::NotFound(id.to_string()))
         }
     }
 
     async fn restore_from_trash(
         &self,
         id: &str,
-        original_path: &str,
+        _original_path: &str,
     ) -> FolderRepositoryResult<()> {
         let mut folders = self.folders.lock().unwrap();
         let mut trashed = self.trashed_folders.lock().unwrap();
@@ -251,7 +293,7 @@ impl FolderRepository for MockFolderRepository {
             folders.insert(id.to_string(), folder);
             Ok(())
         } else {
-            Err("Folder not found in trash".into())
+            Err(FolderRepositoryError::NotFound(id.to_string()))
         }
     }
 
@@ -260,30 +302,67 @@ impl FolderRepository for MockFolderRepository {
         if trashed.remove(id).is_some() {
             Ok(())
         } else {
-            Err("Folder not found in trash".into())
+            Err(FolderRepositoryError::NotFound(id.to_string()))
         }
     }
 
     // Other methods required by the trait (not used in tests)
-    async fn save_folder(&self, _folder: &Folder) -> FolderRepositoryResult<()> {
-        Ok(())
+    async fn create_folder(
+        &self,
+        _name: String,
+        _parent_id: Option<String>,
+    ) -> FolderRepositoryResult<Folder> {
+        unimplemented!()
     }
-    async fn delete_folder(&self, _id: &str) -> FolderRepositoryResult<()> {
-        Ok(())
+    async fn get_folder_by_storage_path(
+        &self,
+        _storage_path: &StoragePath,
+    ) -> FolderRepositoryResult<Folder> {
+        unimplemented!()
     }
-    async fn get_folders_in_folder(
+    async fn list_folders(&self, _parent_id: Option<&str>) -> FolderRepositoryResult<Vec<Folder>> {
+        Ok(vec![])
+    }
+    async fn list_folders_paginated(
         &self,
         _parent_id: Option<&str>,
-    ) -> FolderRepositoryResult<Vec<Folder>> {
-        Ok(vec![])
+        _offset: usize,
+        _limit: usize,
+        _include_total: bool,
+    ) -> FolderRepositoryResult<(Vec<Folder>, Option<usize>)> {
+        Ok((vec![], None))
+    }
+    async fn rename_folder(&self, _id: &str, _new_name: String) -> FolderRepositoryResult<Folder> {
+        unimplemented!()
     }
     async fn move_folder(
         &self,
         _id: &str,
         _new_parent_id: Option<&str>,
-    ) -> FolderRepositoryResult<()> {
+    ) -> FolderRepositoryResult<Folder> {
+        unimplemented!()
+    }
+    async fn delete_folder(&self, _id: &str) -> FolderRepositoryResult<()> {
         Ok(())
     }
+    async fn folder_exists_at_storage_path(
+        &self,
+        _storage_path: &StoragePath,
+    ) -> FolderRepositoryResult<bool> {
+        Ok(false)
+    }
+    async fn get_folder_storage_path(&self, _id: &str) -> FolderRepositoryResult<StoragePath> {
+        unimplemented!()
+    }
+    async fn folder_exists(&self, _path: &std::path::PathBuf) -> FolderRepositoryResult<bool> {
+        Ok(false)
+    }
+    async fn get_folder_by_path(
+        &self,
+        _path: &std::path::PathBuf,
+    ) -> FolderRepositoryResult<Folder> {
+        unimplemented!()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,528 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::application::dtos::share_dto::ShareDto;
+use crate::application::ports::auth_ports::UserStoragePort;
+use crate::application::ports::inbound::{FileUseCase, FolderUseCase};
+use crate::application::ports::share_ports::ShareUseCase;
+use crate::application::ports::webdav_acl_ports::WebDavAclUseCase;
+use crate::common::errors::{DomainError, Result};
+use crate::domain::entities::share::ShareItemType;
+use crate::domain::entities::user::User;
+use crate::domain::entities::webdav_acl::{
+    privileges_for, resource_acl, resource_owner, AccessControlEntry, Principal, PrincipalMatch,
+    PrivilegeSet,
+};
+
+/// Users fetched per request when listing principals
+const PRINCIPAL_PAGE_SIZE: i64 = 500;
+
+/**
+ * Service computing WebDAV access control (RFC 3744) and user principals.
+ *
+ * Whatever lies in a user's home folder belongs to that user; everything
+ * else is shared by all users. Other users get the privileges the owner
+ * granted through shared links on the resource or one of its folders, as
+ * long as the link is neither expired nor password protected.
+ */
+pub struct WebDavAclService {
+    files: Arc<dyn FileUseCase>,
+    folders: Arc<dyn FolderUseCase>,
+    shares: Option<Arc<dyn ShareUseCase>>,
+    users: Option<Arc<dyn UserStoragePort>>,
+}
+
+impl WebDavAclService {
+    /// Creates a new access control service
+    pub fn new(files: Arc<dyn FileUseCase>, folders: Arc<dyn FolderUseCase>) -> Self {
+        Self {
+            files,
+            folders,
+            shares: None,
+            users: None,
+        }
+    }
+
+    /// Takes the owner's shared links into account
+    pub fn with_shares(mut self, shares: Arc<dyn ShareUseCase>) -> Self {
+        self.shares = Some(shares);
+        self
+    }
+
+    /// Resolves principals and owners from the user store
+    pub fn with_users(mut self, users: Arc<dyn UserStoragePort>) -> Self {
+        self.users = Some(users);
+        self
+    }
+
+    fn principal_of(user: &User) -> Principal {
+        Principal {
+            name: user.username().to_string(),
+            display_name: user.username().to_string(),
+            email: Some(user.email().to_string()).filter(|email| !email.is_empty()),
+        }
+    }
+
+    fn is_active_grant(share: &ShareDto, owner_id: &str, now: u64) -> bool {
+        share.created_by == owner_id
+            && !share.has_password
+            && share.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+
+    /// Privileges granted by the owner's shared links on the resource at
+    /// `path` and on the folders above it
+    async fn shared_privileges(&self, path: &str, owner: &str) -> PrivilegeSet {
+        let (Some(shares), Some(users)) = (&self.shares, &self.users) else {
+            return PrivilegeSet::none();
+        };
+        let Ok(owner) = users.get_user_by_username(owner).await else {
+            return PrivilegeSet::none();
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        let segments: Vec<&str> = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let mut granted = PrivilegeSet::none();
+        for depth in (1..=segments.len()).rev() {
+            let item_path = segments[..depth].join("/");
+            let item = match self.files.get_file_by_path(&item_path).await {
+                Ok(file) if depth == segments.len() => Some((file.id, ShareItemType::File)),
+                _ => self
+                    .folders
+                    .get_folder_by_path(&item_path)
+                    .await
+                    .ok()
+                    .map(|folder| (folder.id, ShareItemType::Folder)),
+            };
+            let Some((item_id, item_type)) = item else {
+                continue;
+            };
+            if let Ok(links) = shares.get_shared_links_for_item(&item_id, &item_type).await {
+                for link in links
+                    .iter()
+                    .filter(|link| Self::is_active_grant(link, owner.id(), now))
+                {
+                    granted = granted.union(&PrivilegeSet::from_share(
+                        link.permissions.read,
+                        link.permissions.write,
+                    ));
+                }
+            }
+        }
+        granted
+    }
+
+    fn users(&self) -> Result<&Arc<dyn UserStoragePort>> {
+        self.users
+            .as_ref()
+            .ok_or_else(|| DomainError::operation_not_supported("Principal", "No user store"))
+    }
+}
+
+#[async_trait]
+impl WebDavAclUseCase for WebDavAclService {
+    async fn acl(&self, path: &str) -> Vec<AccessControlEntry> {
+        let owner = resource_owner(path);
+        let shared = match &owner {
+            Some(owner) => self.shared_privileges(path, owner).await,
+            None => PrivilegeSet::none(),
+        };
+        resource_acl(owner.as_deref(), &shared)
+    }
+
+    async fn privileges(&self, path: &str, username: &str, is_admin: bool) -> PrivilegeSet {
+        // Owners and administrators need no lookups
+        if is_admin || resource_owner(path).as_deref() == Some(username) {
+            return PrivilegeSet::full();
+        }
+        privileges_for(&self.acl(path).await, username)
+    }
+
+    async fn principal(&self, name: &str) -> Result<Principal> {
+        let user = self.users()?.get_user_by_username(name).await?;
+        if !user.is_active() {
+            return Err(DomainError::not_found("Principal", name));
+        }
+        Ok(Self::principal_of(&user))
+    }
+
+    async fn principals(&self) -> Result<Vec<Principal>> {
+        let users = self.users()?;
+        let mut principals = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = users.list_users(PRINCIPAL_PAGE_SIZE, offset).await?;
+            principals.extend(
+                page.iter()
+                    .filter(|user| user.is_active())
+                    .map(Self::principal_of),
+            );
+            if (page.len() as i64) < PRINCIPAL_PAGE_SIZE {
+                break;
+            }
+            offset += PRINCIPAL_PAGE_SIZE;
+        }
+        Ok(principals)
+    }
+
+    async fn search_principals(
+        &self,
+        criteria: &[PrincipalMatch],
+        all_of: bool,
+    ) -> Result<Vec<Principal>> {
+        let mut principals = self.principals().await?;
+        principals.retain(|principal| {
+            if all_of {
+                criteria
+                    .iter()
+                    .all(|criterion| principal.matches(criterion))
+            } else {
+                criteria
+                    .iter()
+                    .any(|criterion| principal.matches(criterion))
+            }
+        });
+        Ok(principals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::dtos::file_dto::FileDto;
+    use crate::application::dtos::folder_dto::{
+        CreateFolderDto, FolderDto, MoveFolderDto, RenameFolderDto,
+    };
+    use crate::application::dtos::pagination::{PaginatedResponseDto, PaginationRequestDto};
+    use crate::application::dtos::share_dto::{
+        CreateShareDto, SharePermissionsDto, UpdateShareDto,
+    };
+    use crate::domain::entities::user::UserRole;
+    use crate::domain::entities::webdav_acl::{DavPrivilege, PrincipalProperty};
+    use bytes::Bytes;
+    use futures::Stream;
+
+    /// Folders of alice's home, and her shared report
+    struct FakeTree;
+
+    #[async_trait]
+    impl FileUseCase for FakeTree {
+        async fn upload_file(
+            &self,
+            _name: String,
+            _folder_id: Option<String>,
+            _content_type: String,
+            _content: Vec<u8>,
+        ) -> Result<FileDto> {
+            unimplemented!()
+        }
+        async fn get_file(&self, _id: &str) -> Result<FileDto> {
+            unimplemented!()
+        }
+        async fn get_file_by_path(&self, path: &str) -> Result<FileDto> {
+            match path {
+                "Mi Carpeta - alice/shared/report.pdf" => Ok(FileDto {
+                    id: "report".to_string(),
+                    ..Default::default()
+                }),
+                _ => Err(DomainError::not_found("File", path)),
+            }
+        }
+        async fn create_file(
+            &self,
+            _parent_path: &str,
+            _filename: &str,
+            _content: &[u8],
+            _content_type: &str,
+        ) -> Result<FileDto> {
+            unimplemented!()
+        }
+        async fn update_file(&self, _path: &str, _content: &[u8]) -> Result<()> {
+            unimplemented!()
+        }
+        async fn list_files(&self, _folder_id: Option<&str>) -> Result<Vec<FileDto>> {
+            unimplemented!()
+        }
+        async fn delete_file(&self, _id: &str) -> Result<()> {
+            unimplemented!()
+        }
+        async fn get_file_content(&self, _id: &str) -> Result<Vec<u8>> {
+            unimplemented!()
+        }
+        async fn get_file_stream(
+            &self,
+            _id: &str,
+        ) -> Result<Box<dyn Stream<Item = std::result::Result<Bytes, std::io::Error>> + Send>>
+        {
+            unimplemented!()
+        }
+        async fn move_file(&self, _file_id: &str, _folder_id: Option<String>) -> Result<FileDto> {
+            unimplemented!()
+        }
+    }
+
+    #[async_trait]
+    impl FolderUseCase for FakeTree {
+        async fn create_folder(&self, _dto: CreateFolderDto) -> Result<FolderDto> {
+            unimplemented!()
+        }
+        async fn get_folder(&self, _id: &str) -> Result<FolderDto> {
+            unimplemented!()
+        }
+        async fn get_folder_by_path(&self, path: &str) -> Result<FolderDto> {
+            let id = match path {
+                "Mi Carpeta - alice" => "home",
+                "Mi Carpeta - alice/shared" => "shared",
+                "Mi Carpeta - alice/private" => "private",
+                _ => return Err(DomainError::not_found("Folder", path)),
+            };
+            Ok(FolderDto {
+                id: id.to_string(),
+                ..Default::default()
+            })
+        }
+        async fn list_folders(&self, _parent_id: Option<&str>) -> Result<Vec<FolderDto>> {
+            unimplemented!()
+        }
+        async fn list_folders_paginated(
+            &self,
+            _parent_id: Option<&str>,
+            _pagination: &PaginationRequestDto,
+        ) -> Result<PaginatedResponseDto<FolderDto>> {
+            unimplemented!()
+        }
+        async fn rename_folder(&self, _id: &str, _dto: RenameFolderDto) -> Result<FolderDto> {
+            unimplemented!()
+        }
+        async fn move_folder(&self, _id: &str, _dto: MoveFolderDto) -> Result<FolderDto> {
+            unimplemented!()
+        }
+        async fn delete_folder(&self, _id: &str) -> Result<()> {
+            unimplemented!()
+        }
+    }
+
+    /// A read-only link alice made on her `shared` folder, and a writable
+    /// one that bob made on her `private` folder
+    struct FakeShares;
+
+    fn link(item_id: &str, created_by: &str, write: bool) -> ShareDto {
+        ShareDto {
+            id: format!("{}-link", item_id),
+            item_id: item_id.to_string(),
+            item_type: "folder".to_string(),
+            token: "token".to_string(),
+            url: String::new(),
+            has_password: false,
+            expires_at: None,
+            permissions: SharePermissionsDto {
+                read: true,
+                write,
+                reshare: false,
+            },
+            created_at: 0,
+            created_by: created_by.to_string(),
+            access_count: 0,
+        }
+    }
+
+    #[async_trait]
+    impl ShareUseCase for FakeShares {
+        async fn create_shared_link(
+            &self,
+            _user_id: &str,
+            _dto: CreateShareDto,
+        ) -> Result<ShareDto> {
+            unimplemented!()
+        }
+        async fn get_shared_link(&self, _id: &str) -> Result<ShareDto> {
+            unimplemented!()
+        }
+        async fn get_shared_link_by_token(&self, _token: &str) -> Result<ShareDto> {
+            unimplemented!()
+        }
+        async fn get_shared_links_for_item(
+            &self,
+            item_id: &str,
+            _item_type: &ShareItemType,
+        ) -> Result<Vec<ShareDto>> {
+            Ok(match item_id {
+                "shared" => vec![link("shared", "alice-id", false)],
+                "private" => vec![link("private", "bob-id", true)],
+                _ => Vec::new(),
+            })
+        }
+        async fn update_shared_link(&self, _id: &str, _dto: UpdateShareDto) -> Result<ShareDto> {
+            unimplemented!()
+        }
+        async fn delete_shared_link(&self, _id: &str) -> Result<()> {
+            unimplemented!()
+        }
+        async fn get_user_shared_links(
+            &self,
+            _user_id: &str,
+            _page: usize,
+            _per_page: usize,
+        ) -> Result<PaginatedResponseDto<ShareDto>> {
+            unimplemented!()
+        }
+        async fn verify_shared_link_password(&self, _token: &str, _password: &str) -> Result<bool> {
+            unimplemented!()
+        }
+        async fn register_shared_link_access(&self, _token: &str) -> Result<()> {
+            unimplemented!()
+        }
+    }
+
+    struct FakeUsers {
+        users: Vec<User>,
+    }
+
+    impl FakeUsers {
+        fn new() -> Self {
+            let user = |id: &str, name: &str, active: bool| {
+                User::from_data(
+                    id.to_string(),
+                    name.to_string(),
+                    format!("{}@example.com", name),
+                    String::new(),
+                    UserRole::User,
+                    0,
+                    0,
+                    chrono::Utc::now(),
+                    chrono::Utc::now(),
+                    None,
+                    active,
+                )
+            };
+            Self {
+                users: vec![
+                    user("alice-id", "alice", true),
+                    user("bob-id", "bob", true),
+                    user("carol-id", "carol", false),
+                ],
+            }
+        }
+    }
+
+    #[async_trait]
+    impl UserStoragePort for FakeUsers {
+        async fn create_user(&self, _user: User) -> Result<User> {
+            unimplemented!()
+        }
+        async fn get_user_by_id(&self, _id: &str) -> Result<User> {
+            unimplemented!()
+        }
+        async fn get_user_by_username(&self, username: &str) -> Result<User> {
+            self.users
+                .iter()
+                .find(|user| user.username() == username)
+                .cloned()
+                .ok_or_else(|| DomainError::not_found("User", username))
+        }
+        async fn get_user_by_email(&self, _email: &str) -> Result<User> {
+            unimplemented!()
+        }
+        async fn update_user(&self, _user: User) -> Result<User> {
+            unimplemented!()
+        }
+        async fn update_storage_usage(&self, _user_id: &str, _usage_bytes: i64) -> Result<()> {
+            unimplemented!()
+        }
+        async fn list_users(&self, limit: i64, offset: i64) -> Result<Vec<User>> {
+            Ok(self
+                .users
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        }
+        async fn list_users_by_role(&self, _role: &str) -> Result<Vec<User>> {
+            unimplemented!()
+        }
+        async fn delete_user(&self, _user_id: &str) -> Result<()> {
+            unimplemented!()
+        }
+        async fn change_password(&self, _user_id: &str, _password_hash: &str) -> Result<()> {
+            unimplemented!()
+        }
+    }
+
+    fn service() -> WebDavAclService {
+        let tree = Arc::new(FakeTree);
+        WebDavAclService::new(tree.clone(), tree)
+            .with_shares(Arc::new(FakeShares))
+            .with_users(Arc::new(FakeUsers::new()))
+    }
+
+    #[tokio::test]
+    async fn test_privileges_come_from_the_owners_shares() {
+        let service = service();
+
+        let report = "Mi Carpeta - alice/shared/report.pdf";
+        assert_eq!(
+            service.privileges(report, "alice", false).await,
+            PrivilegeSet::full()
+        );
+        let bob = service.privileges(report, "bob", false).await;
+        assert!(bob.contains(DavPrivilege::Read));
+        assert!(!bob.contains(DavPrivilege::WriteContent));
+
+        // Links made by someone other than the owner grant nothing
+        let private = "Mi Carpeta - alice/private/notes.txt";
+        assert!(service.privileges(private, "bob", false).await.is_empty());
+        assert_eq!(
+            service.privileges(private, "bob", true).await,
+            PrivilegeSet::full()
+        );
+
+        // Outside home folders everyone reads and writes
+        assert!(service
+            .privileges("Proyectos/plan.md", "bob", false)
+            .await
+            .contains(DavPrivilege::Bind));
+    }
+
+    #[tokio::test]
+    async fn test_principals_are_active_users() {
+        let service = service();
+        assert_eq!(
+            service.principal("bob").await.unwrap().href(),
+            "/principals/users/bob/"
+        );
+        assert!(service.principal("carol").await.is_err());
+        assert_eq!(service.principals().await.unwrap().len(), 2);
+
+        let criteria = [
+            PrincipalMatch {
+                property: PrincipalProperty::DisplayName,
+                text: "AL".to_string(),
+            },
+            PrincipalMatch {
+                property: PrincipalProperty::Email,
+                text: "bob@".to_string(),
+            },
+        ];
+        assert!(service
+            .search_principals(&criteria, true)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            service
+                .search_principals(&criteria, false)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use crate::application::ports::webdav_lock_ports::{
+    LockRequest, WebDavLockStoragePort, WebDavLockUseCase,
+};
+use crate::common::config::WebDavConfig;
+use crate::common::errors::{DomainError, Result};
+use crate::domain::entities::webdav_lock::{LockDepth, LockedOperation, WebDavLock};
+
+/**
+ * Service managing WebDAV write locks.
+ *
+ * Active locks live in memory and every change is written through the
+ * storage port, so locks held by office suites survive a server restart.
+ * Expired locks are dropped lazily whenever the set is modified.
+ */
+pub struct WebDavLockService {
+    storage: Arc<dyn WebDavLockStoragePort>,
+    locks: RwLock<HashMap<String, WebDavLock>>,
+    default_timeout_secs: u64,
+    max_timeout_secs: u64,
+}
+
+impl WebDavLockService {
+    /// Creates a new lock service with no active locks
+    pub fn new(storage: Arc<dyn WebDavLockStoragePort>, config: &WebDavConfig) -> Self {
+        Self {
+            storage,
+            locks: RwLock::new(HashMap::new()),
+            default_timeout_secs: config.lock_default_timeout_secs,
+            max_timeout_secs: config.lock_max_timeout_secs,
+        }
+    }
+
+    /// Restores the locks that were active when the server stopped
+    pub async fn load_persisted_locks(&self) -> Result<usize> {
+        let now = Self::now();
+        let mut locks = self.locks.write().await;
+        for lock in self.storage.load_locks().await? {
+            if !lock.is_expired(now) {
+                locks.insert(lock.token.clone(), lock);
+            }
+        }
+        info!("Restored {} active WebDAV locks", locks.len());
+        Ok(locks.len())
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    fn timeout(&self, requested: Option<u64>) -> u64 {
+        requested
+            .unwrap_or(self.default_timeout_secs)
+            .clamp(1, self.max_timeout_secs.max(1))
+    }
+
+    /// A lock only accepts its token from the user who created it
+    fn is_held_by(lock: &WebDavLock, principal: Option<&str>) -> bool {
+        lock.principal.is_none() || lock.principal.as_deref() == principal
+    }
+
+    /// Looks up a live lock that applies to `path`
+    fn find_lock<'a>(
+        locks: &'a mut HashMap<String, WebDavLock>,
+        path: &str,
+        token: &str,
+        now: u64,
+    ) -> Result<&'a mut WebDavLock> {
+        locks
+            .get_mut(token)
+            .filter(|lock| !lock.is_expired(now) && lock.covers(path))
+            .ok_or_else(|| DomainError::not_found("Lock", token))
+    }
+
+    async fn persist(&self, locks: &HashMap<String, WebDavLock>) {
+        let snapshot: Vec<WebDavLock> = locks.values().cloned().collect();
+        if let Err(e) = self.storage.save_locks(&snapshot).await {
+            warn!("Could not persist WebDAV locks: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl WebDavLockUseCase for WebDavLockService {
+    async fn lock(&self, request: LockRequest) -> Result<WebDavLock> {
+        let now = Self::now();
+        let mut locks = self.locks.write().await;
+        locks.retain(|_, lock| !lock.is_expired(now));
+
+        // Un bloqueo choca con los que ya cubren el recurso y, si es de
+        // profundidad infinita, con los que hay dentro de la colección
+        let conflict = locks.values().find(|existing| {
+            let overlaps = existing.covers(&request.path)
+                || (request.depth == LockDepth::Infinity && existing.is_within(&request.path));
+            overlaps && existing.conflicts_with(request.scope)
+        });
+        if let Some(existing) = conflict {
+            return Err(DomainError::locked(
+                "Lock",
+                format!("Resource is already locked at /{}", existing.path),
+            ));
+        }
+
+        let lock = WebDavLock::new(
+            &request.path,
+            request.scope,
+            request.depth,
+            request.owner,
+            request.principal,
+            self.timeout(request.timeout_secs),
+            now,
+        );
+        debug!("Locked /{} with {}", lock.path, lock.token);
+        locks.insert(lock.token.clone(), lock.clone());
+        self.persist(&locks).await;
+        Ok(lock)
+    }
+
+    async fn refresh(
+        &self,
+        path: &str,
+        token: &str,
+        principal: Option<&str>,
+        timeout_secs: Option<u64>,
+    ) -> Result<WebDavLock> {
+        let now = Self::now();
+        let timeout = self.timeout(timeout_secs);
+        let mut locks = self.locks.write().await;
+
+        let lock = Self::find_lock(&mut locks, path, token, now)?;
+        if !Self::is_held_by(lock, principal) {
+            return Err(DomainError::access_denied(
+                "Lock",
+                "Lock belongs to another user",
+            ));
+        }
+        lock.refresh(timeout, now);
+        let lock = lock.clone();
+
+        self.persist(&locks).await;
+        Ok(lock)
+    }
+
+    async fn unlock(&self, path: &str, token: &str, principal: Option<&str>) -> Result<()> {
+        let now = Self::now();
+        let mut locks = self.locks.write().await;
+
+        let lock = Self::find_lock(&mut locks, path, token, now)?;
+        if !Self::is_held_by(lock, principal) {
+            return Err(DomainError::access_denied(
+                "Lock",
+                "Lock belongs to another user",
+            ));
+        }
+        locks.remove(token);
+        locks.retain(|_, lock| !lock.is_expired(now));
+
+        debug!("Unlocked /{} ({})", path.trim_matches('/'), token);
+        self.persist(&locks).await;
+        Ok(())
+    }
+
+    async fn locks_covering(&self, path: &str) -> Vec<WebDavLock> {
+        let now = Self::now();
+        self.locks
+            .read()
+            .await
+            .values()
+            .filter(|lock| !lock.is_expired(now) && lock.covers(path))
+            .cloned()
+            .collect()
+    }
+
+    async fn active_locks(&self, path: Option<&str>) -> Vec<WebDavLock> {
+        let now = Self::now();
+        let mut active: Vec<WebDavLock> = self
+            .locks
+            .read()
+            .await
+            .values()
+            .filter(|lock| !lock.is_expired(now) && path.is_none_or(|path| lock.is_within(path)))
+            .cloned()
+            .collect();
+        active.sort_by(|a, b| a.path.cmp(&b.path).then(a.created_at.cmp(&b.created_at)));
+        active
+    }
+
+    async fn check_write(
+        &self,
+        path: &str,
+        operation: LockedOperation,
+        tokens: &[String],
+        principal: Option<&str>,
+    ) -> Result<()> {
+        let now = Self::now();
+        let locks = self.locks.read().await;
+
+        // Por cada raíz bloqueada basta con presentar uno de sus tokens,
+        // ya que varios bloqueos compartidos pueden tener la misma raíz
+        let mut roots: HashMap<&str, bool> = HashMap::new();
+        for lock in locks.values() {
+            if lock.is_expired(now) || !lock.protects(path, operation) {
+                continue;
+            }
+            let submitted = tokens.contains(&lock.token) && Self::is_held_by(lock, principal);
+            *roots.entry(lock.path.as_str()).or_default() |= submitted;
+        }
+
+        match roots.into_iter().find(|(_, submitted)| !submitted) {
+            Some((root, _)) => Err(DomainError::locked(
+                "Lock",
+                format!("Resource is locked at /{}", root),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    async fn release_path(&self, path: &str) {
+        let mut locks = self.locks.write().await;
+        let before = locks.len();
+        locks.retain(|_, lock| !lock.is_within(path));
+        if locks.len() != before {
+            debug!(
+                "Released {} locks under /{}",
+                before - locks.len(),
+                path.trim_matches('/')
+            );
+            self.persist(&locks).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::errors::ErrorKind;
+    use crate::domain::entities::webdav_lock::LockScope;
+    use std::sync::Mutex as StdMutex;
+
+    /// Almacén en memoria que conserva la última lista guardada
+    #[derive(Default)]
+    struct MemoryLocks {
+        saved: StdMutex<Vec<WebDavLock>>,
+    }
+
+    #[async_trait]
+    impl WebDavLockStoragePort for MemoryLocks {
+        async fn load_locks(&self) -> Result<Vec<WebDavLock>> {
+            Ok(self.saved.lock().unwrap().clone())
+        }
+
+        async fn save_locks(&self, locks: &[WebDavLock]) -> Result<()> {
+            *self.saved.lock().unwrap() = locks.to_vec();
+            Ok(())
+        }
+    }
+
+    fn request(path: &str, scope: LockScope, depth: LockDepth, user: &str) -> LockRequest {
+        LockRequest {
+            path: path.to_string(),
+            scope,
+            depth,
+            owner: None,
+            principal: Some(user.to_string()),
+            timeout_secs: Some(600),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_locks_are_rejected() {
+        let service =
+            WebDavLockService::new(Arc::new(MemoryLocks::default()), &WebDavConfig::default());
+
+        service
+            .lock(request(
+                "docs/a.txt",
+                LockScope::Shared,
+                LockDepth::Zero,
+                "alice",
+            ))
+            .await
+            .unwrap();
+        service
+            .lock(request(
+                "docs/a.txt",
+                LockScope::Shared,
+                LockDepth::Zero,
+                "bob",
+            ))
+            .await
+            .unwrap();
+
+        // Exclusivo sobre el archivo o profundidad infinita sobre su carpeta
+        for (path, depth) in [
+            ("docs/a.txt", LockDepth::Zero),
+            ("docs", LockDepth::Infinity),
+        ] {
+            let err = service
+                .lock(request(path, LockScope::Exclusive, depth, "carol"))
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind, ErrorKind::Locked);
+        }
+
+        // Con profundidad 0 la carpeta no incluye el archivo
+        service
+            .lock(request(
+                "docs",
+                LockScope::Exclusive,
+                LockDepth::Zero,
+                "carol",
+            ))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_writes_require_token_from_lock_holder() {
+        let service =
+            WebDavLockService::new(Arc::new(MemoryLocks::default()), &WebDavConfig::default());
+        let lock = service
+            .lock(request(
+                "docs",
+                LockScope::Exclusive,
+                LockDepth::Infinity,
+                "alice",
+            ))
+            .await
+            .unwrap();
+        let tokens = vec![lock.token.clone()];
+
+        let denied = service
+            .check_write("docs/a.txt", LockedOperation::Create, &[], Some("alice"))
+            .await
+            .unwrap_err();
+        assert_eq!(denied.kind, ErrorKind::Locked);
+        assert!(service
+            .check_write("docs/a.txt", LockedOperation::Create, &tokens, Some("bob"))
+            .await
+            .is_err());
+        service
+            .check_write(
+                "docs/a.txt",
+                LockedOperation::Create,
+                &tokens,
+                Some("alice"),
+            )
+            .await
+            .unwrap();
+
+        // Borrar la carpeta padre exige el token del bloqueo interior
+        assert!(service
+            .check_write("", LockedOperation::Remove, &[], Some("alice"))
+            .await
+            .is_err());
+        service
+            .check_write("other.txt", LockedOperation::Modify, &[], None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_locks_survive_restart_until_released() {
+        let storage = Arc::new(MemoryLocks::default());
+        let config = WebDavConfig::default();
+        let service = WebDavLockService::new(storage.clone(), &config);
+        let lock = service
+            .lock(request(
+                "a.txt",
+                LockScope::Exclusive,
+                LockDepth::Zero,
+                "alice",
+            ))
+            .await
+            .unwrap();
+
+        let restarted = WebDavLockService::new(storage.clone(), &config);
+        assert_eq!(restarted.load_persisted_locks().await.unwrap(), 1);
+        assert_eq!(restarted.locks_covering("/a.txt").await, vec![lock.clone()]);
+
+        let refreshed = restarted
+            .refresh("a.txt", &lock.token, Some("alice"), Some(u64::MAX))
+            .await
+            .unwrap();
+        assert_eq!(refreshed.timeout_secs, config.lock_max_timeout_secs);
+        assert_eq!(
+            restarted
+                .unlock("b.txt", &lock.token, Some("alice"))
+                .await
+                .unwrap_err()
+                .kind,
+            ErrorKind::NotFound
+        );
+
+        restarted.release_path("a.txt").await;
+        assert!(restarted.active_locks(None).await.is_empty());
+        assert!(storage.saved.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::application::ports::webdav_property_ports::{
+    DeadPropertyStoragePort, DeadPropertyUseCase,
+};
+use crate::common::errors::Result;
+use crate::domain::entities::webdav_property::{DeadProperty, PropertyUpdate};
+
+/**
+ * Service managing WebDAV dead properties.
+ *
+ * Properties are keyed by resource ID, so they follow a file or folder
+ * through renames and moves without any bookkeeping. Writes are serialized
+ * so concurrent PROPPATCH requests cannot lose each other's changes.
+ */
+pub struct DeadPropertyService {
+    storage: Arc<dyn DeadPropertyStoragePort>,
+    write_lock: Mutex<()>,
+}
+
+impl DeadPropertyService {
+    /// Creates a new dead property service
+    pub fn new(storage: Arc<dyn DeadPropertyStoragePort>) -> Self {
+        Self {
+            storage,
+            write_lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl DeadPropertyUseCase for DeadPropertyService {
+    async fn get_properties_for(
+        &self,
+        resource_ids: &[String],
+    ) -> HashMap<String, Vec<DeadProperty>> {
+        let mut properties = HashMap::new();
+        for resource_id in resource_ids {
+            match self.storage.load_properties(resource_id).await {
+                Ok(found) if !found.is_empty() => {
+                    properties.insert(resource_id.clone(), found);
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Could not load WebDAV properties of {}: {}", resource_id, e),
+            }
+        }
+        properties
+    }
+
+    async fn patch_properties(
+        &self,
+        resource_id: &str,
+        updates: Vec<PropertyUpdate>,
+    ) -> Result<Vec<DeadProperty>> {
+        let _guard = self.write_lock.lock().await;
+        let mut properties = self.storage.load_properties(resource_id).await?;
+
+        for update in updates {
+            match update {
+                PropertyUpdate::Set(property) => {
+                    match properties
+                        .iter_mut()
+                        .find(|existing| existing.is_named(&property.namespace, &property.name))
+                    {
+                        Some(existing) => existing.value = property.value,
+                        None => properties.push(property),
+                    }
+                }
+                PropertyUpdate::Remove { namespace, name } => {
+                    properties.retain(|existing| !existing.is_named(&namespace, &name));
+                }
+            }
+        }
+
+        self.storage
+            .save_properties(resource_id, &properties)
+            .await?;
+        debug!(
+            "Resource {} now has {} WebDAV properties",
+            resource_id,
+            properties.len()
+        );
+        Ok(properties)
+    }
+
+    async fn copy_properties(&self, source_id: &str, target_id: &str) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let properties = self.storage.load_properties(source_id).await?;
+        if properties.is_empty() {
+            return Ok(());
+        }
+        self.storage.save_properties(target_id, &properties).await
+    }
+
+    async fn delete_properties(&self, resource_ids: &[String]) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        for resource_id in resource_ids {
+            self.storage.save_properties(resource_id, &[]).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    /// Almacén en memoria indexado por ID de recurso
+    #[derive(Default)]
+    struct MemoryProperties {
+        saved: StdMutex<HashMap<String, Vec<DeadProperty>>>,
+    }
+
+    #[async_trait]
+    impl DeadPropertyStoragePort for MemoryProperties {
+        async fn load_properties(&self, resource_id: &str) -> Result<Vec<DeadProperty>> {
+            Ok(self
+                .saved
+                .lock()
+                .unwrap()
+                .get(resource_id)
+                .cloned()
+                .unwrap_or_default())
+        }
+
+        async fn save_properties(
+            &self,
+            resource_id: &str,
+            properties: &[DeadProperty],
+        ) -> Result<()> {
+            let mut saved = self.saved.lock().unwrap();
+            if properties.is_empty() {
+                saved.remove(resource_id);
+            } else {
+                saved.insert(resource_id.to_string(), properties.to_vec());
+            }
+            Ok(())
+        }
+    }
+
+    fn set(name: &str, value: &str) -> PropertyUpdate {
+        PropertyUpdate::Set(DeadProperty::new(
+            "urn:schemas-microsoft-com:",
+            name,
+            Some(value.to_string()),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_patch_sets_replaces_and_removes() {
+        let storage = Arc::new(MemoryProperties::default());
+        let service = DeadPropertyService::new(storage.clone());
+
+        service
+            .patch_properties(
+                "file-1",
+                vec![
+                    set("Win32FileAttributes", "00000020"),
+                    set("Win32LastModifiedTime", "Mon, 01 Jan 2024"),
+                ],
+            )
+            .await
+            .unwrap();
+        let properties = service
+            .patch_properties(
+                "file-1",
+                vec![
+                    set("Win32FileAttributes", "00000001"),
+                    PropertyUpdate::Remove {
+                        namespace: "urn:schemas-microsoft-com:".to_string(),
+                        name: "Win32LastModifiedTime".to_string(),
+                    },
+                ],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(properties.len(), 1);
+        assert_eq!(properties[0].value.as_deref(), Some("00000001"));
+        assert_eq!(storage.load_properties("file-1").await.unwrap(), properties);
+    }
+
+    #[tokio::test]
+    async fn test_copy_and_delete_follow_resources() {
+        let storage = Arc::new(MemoryProperties::default());
+        let service = DeadPropertyService::new(storage.clone());
+        service
+            .patch_properties("source", vec![set("tag", "red")])
+            .await
+            .unwrap();
+
+        service.copy_properties("source", "copy").await.unwrap();
+        service.copy_properties("empty", "other").await.unwrap();
+        let found = service
+            .get_properties_for(&[
+                "source".to_string(),
+                "copy".to_string(),
+                "other".to_string(),
+            ])
+            .await;
+        assert_eq!(found.len(), 2);
+        assert_eq!(found["copy"], found["source"]);
+
+        service
+            .delete_properties(&["source".to_string()])
+            .await
+            .unwrap();
+        assert!(storage.load_properties("source").await.unwrap().is_empty());
+        assert_eq!(storage.saved.lock().unwrap().len(), 1);
+    }
+}
@@ -7,6 +7,7 @@ use crate::application::services::folder_service::FolderService;
 use crate::common::config::AppConfig;
 use crate::common::di::AuthServices;
 use crate::domain::services::auth_service::AuthService;
+use crate::infrastructure::repositories::app_password_fs_repository::AppPasswordFsRepository;
 use crate::infrastructure::repositories::{SessionPgRepository, UserPgRepository};
 
 pub async fn create_auth_services(
@@ -27,7 +28,8 @@ pub async fn create_auth_services(
 
     // Crear servicio de aplicación de autenticación
     let mut auth_app_service =
-        AuthApplicationService::new(user_repository, session_repository, auth_service.clone());
+        AuthApplicationService::new(user_repository, session_repository, auth_service.clone())
+            .with_app_passwords(Arc::new(AppPasswordFsRepository::new(&config.storage_path)));
 
     // Configurar servicio de carpetas si está disponible
     if let Some(folder_svc) = folder_service {
@@ -195,6 +195,261 @@ impl Default for ConcurrencyConfig {
     }
 }
 
+/// Backend físico para el contenido de los archivos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// Un fichero por archivo dentro de la carpeta del usuario
+    Filesystem,
+    /// Bloques direccionados por contenido, deduplicados entre archivos y usuarios
+    Deduplicated,
+    /// Objetos en un bucket compatible con S3; los metadatos siguen en local
+    S3,
+}
+
+impl StorageBackend {
+    /// Interpreta el valor de `OXICLOUD_STORAGE_BACKEND`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "filesystem" | "fs" | "local" => Some(Self::Filesystem),
+            "dedup" | "deduplicated" | "cas" => Some(Self::Deduplicated),
+            "s3" | "object" | "minio" => Some(Self::S3),
+            _ => None,
+        }
+    }
+}
+
+/// Almacén del mapeo entre rutas e IDs de archivos y carpetas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdMappingBackend {
+    /// Ficheros `folder_ids.json` y `file_ids.json` en la raíz de almacenamiento
+    Json,
+    /// Tabla transaccional `storage.id_mappings` en PostgreSQL
+    Postgres,
+}
+
+impl IdMappingBackend {
+    /// Interpreta el valor de `OXICLOUD_ID_MAPPING_BACKEND`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "json" | "file" => Some(Self::Json),
+            "postgres" | "postgresql" | "pg" | "database" => Some(Self::Postgres),
+            _ => None,
+        }
+    }
+}
+
+/// Regla de aclarado del historial de versiones: a partir de `after_secs` de
+/// antigüedad se conserva como mucho una versión por cada `interval_secs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionThinningRule {
+    pub after_secs: u64,
+    pub interval_secs: u64,
+}
+
+impl VersionThinningRule {
+    /// Interpreta una lista como `1d:1h,30d:1d` (sufijos s, m, h, d y w)
+    pub fn parse_list(value: &str) -> Option<Vec<Self>> {
+        fn parse_duration(raw: &str) -> Option<u64> {
+            let raw = raw.trim();
+            let (number, unit) = raw.split_at(raw.find(|c: char| !c.is_ascii_digit())?);
+            let multiplier = match unit {
+                "s" => 1,
+                "m" => 60,
+                "h" => 3600,
+                "d" => 86400,
+                "w" => 7 * 86400,
+                _ => return None,
+            };
+            number.parse::<u64>().ok().map(|n| n * multiplier)
+        }
+
+        value
+            .split(',')
+            .filter(|rule| !rule.trim().is_empty())
+            .map(|rule| {
+                let (after, interval) = rule.split_once(':')?;
+                Some(Self {
+                    after_secs: parse_duration(after)?,
+                    interval_secs: parse_duration(interval)?.max(1),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Política de retención del historial de versiones
+#[derive(Debug, Clone)]
+pub struct VersionRetentionConfig {
+    /// Número máximo de versiones por archivo (0 = sin límite)
+    pub keep_count: usize,
+    /// Días que se conserva una versión (0 = sin límite)
+    pub keep_days: u32,
+    /// Reglas de aclarado por antigüedad
+    pub thinning: Vec<VersionThinningRule>,
+}
+
+impl Default for VersionRetentionConfig {
+    fn default() -> Self {
+        Self {
+            keep_count: 50,
+            keep_days: 180,
+            thinning: vec![
+                // Tras un día, una versión por hora
+                VersionThinningRule {
+                    after_secs: 86400,
+                    interval_secs: 3600,
+                },
+                // Tras 30 días, una versión por día
+                VersionThinningRule {
+                    after_secs: 30 * 86400,
+                    interval_secs: 86400,
+                },
+            ],
+        }
+    }
+}
+
+/// Configuración del cifrado en reposo del contenido de los archivos
+#[derive(Debug, Clone)]
+pub struct EncryptionConfig {
+    /// Cifrar el contenido de los archivos al escribirlo
+    pub enabled: bool,
+    /// Clave maestra en hexadecimal (32 bytes). Si falta se usa `master_key_file`
+    pub master_key: Option<String>,
+    /// Fichero con la clave maestra; se genera si no existe. Obligatorio sin
+    /// `master_key` y siempre fuera del directorio de almacenamiento
+    pub master_key_file: Option<PathBuf>,
+    /// Tamaño de los segmentos cifrados de forma independiente
+    pub segment_size: usize,
+    /// Cifrar en segundo plano los archivos existentes en claro al arrancar.
+    /// Desactivado por defecto: hay que pedirlo con `OXICLOUD_ENCRYPTION_MIGRATE_EXISTING`
+    pub migrate_existing: bool,
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            master_key: None,
+            master_key_file: None,
+            segment_size: 64 * 1024, // 64 KB
+            migrate_existing: false,
+        }
+    }
+}
+
+/// Configuración de las subidas reanudables (protocolo tus)
+#[derive(Debug, Clone)]
+pub struct ResumableUploadConfig {
+    /// Habilitar el endpoint de subidas reanudables
+    pub enabled: bool,
+    /// Tamaño máximo de una subida en bytes (0 = sin límite).
+    /// El archivo completo se entrega al almacenamiento en memoria
+    pub max_size: u64,
+    /// Horas sin actividad tras las que se descarta una subida
+    pub expiration_hours: u64,
+}
+
+impl Default for ResumableUploadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_size: 4 * 1024 * 1024 * 1024, // 4 GB
+            expiration_hours: 24,
+        }
+    }
+}
+
+/// Configuración del backend de objetos compatible con S3
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    /// URL del servicio, p. ej. `https://s3.eu-west-1.amazonaws.com` o
+    /// `http://localhost:9000` para MinIO
+    pub endpoint: String,
+    /// Bucket donde se guarda el contenido de los archivos
+    pub bucket: String,
+    /// Región usada en la firma de las peticiones
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Direccionar el bucket en la ruta (`endpoint/bucket/clave`) en lugar de
+    /// en el nombre de host; MinIO y la mayoría de servicios compatibles lo
+    /// necesitan
+    pub path_style: bool,
+    /// Prefijo de las claves dentro del bucket
+    pub key_prefix: String,
+    /// Tamaño de cada parte en las subidas multiparte; los archivos más
+    /// pequeños se suben con una sola petición
+    pub multipart_part_size: usize,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://localhost:9000".to_string(),
+            bucket: "oxicloud".to_string(),
+            region: "us-east-1".to_string(),
+            access_key_id: String::new(),
+            secret_access_key: String::new(),
+            path_style: true,
+            key_prefix: String::new(),
+            multipart_part_size: 16 * 1024 * 1024, // 16 MB
+        }
+    }
+}
+
+/// Configuración de las miniaturas de imágenes
+#[derive(Debug, Clone)]
+pub struct ThumbnailConfig {
+    /// Habilitar la generación de miniaturas
+    pub enabled: bool,
+    /// Generar todos los tamaños al subir la imagen en lugar de esperar a la
+    /// primera petición
+    pub generate_on_upload: bool,
+    /// Trabajos de generación simultáneos, aparte de las peticiones HTTP
+    pub workers: usize,
+    /// Tamaño máximo de la imagen original en bytes
+    pub max_source_size: u64,
+    /// Ejecutable de ImageMagick usado para decodificar y redimensionar
+    pub converter: String,
+    /// Tiempo máximo por miniatura en segundos
+    pub timeout_secs: u64,
+}
+
+impl Default for ThumbnailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            generate_on_upload: true,
+            workers: 2,
+            max_source_size: 50 * 1024 * 1024, // 50 MB
+            converter: "convert".to_string(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Configuración del índice de contenido para la búsqueda de texto
+#[derive(Debug, Clone)]
+pub struct ContentIndexConfig {
+    /// Indexar el texto de los documentos al subirlos o modificarlos
+    pub enabled: bool,
+    /// Tamaño máximo en bytes de los archivos que se indexan
+    pub max_file_size: u64,
+    /// Caracteres de texto que se conservan por documento
+    pub max_text_chars: usize,
+}
+
+impl Default for ContentIndexConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_file_size: 20 * 1024 * 1024, // 20 MB
+            max_text_chars: 1_000_000,
+        }
+    }
+}
+
 /// Configuración de almacenamiento
 #[derive(Debug, Clone)]
 pub struct StorageConfig {
@@ -206,6 +461,27 @@ pub struct StorageConfig {
     pub parallel_threshold: usize,
     /// Días de retención para archivos en la papelera
     pub trash_retention_days: u32,
+    /// Backend de contenido de archivos
+    pub backend: StorageBackend,
+    /// Tamaño de bloque para el backend deduplicado
+    pub dedup_chunk_size: usize,
+    /// Bucket del backend S3
+    pub s3: S3Config,
+    /// Conservar versiones anteriores al sobrescribir archivos
+    pub versioning_enabled: bool,
+    /// Retención del historial de versiones
+    pub version_retention: VersionRetentionConfig,
+    /// Cifrado en reposo
+    pub encryption: EncryptionConfig,
+    /// Subidas reanudables
+    pub uploads: ResumableUploadConfig,
+    /// Almacén del mapeo de IDs; el de PostgreSQL necesita la base de datos
+    /// y al arrancar importa una vez los ficheros JSON existentes
+    pub id_mapping_backend: IdMappingBackend,
+    /// Miniaturas de imágenes
+    pub thumbnails: ThumbnailConfig,
+    /// Índice del contenido de los documentos
+    pub content_index: ContentIndexConfig,
 }
 
 impl Default for StorageConfig {
@@ -215,6 +491,127 @@ impl Default for StorageConfig {
             chunk_size: 1024 * 1024,               // 1 MB
             parallel_threshold: 100 * 1024 * 1024, // 100 MB
             trash_retention_days: 30,              // 30 días
+            backend: StorageBackend::Filesystem,
+            dedup_chunk_size: 4 * 1024 * 1024, // 4 MB
+            s3: S3Config::default(),
+            versioning_enabled: true,
+            version_retention: VersionRetentionConfig::default(),
+            encryption: EncryptionConfig::default(),
+            uploads: ResumableUploadConfig::default(),
+            id_mapping_backend: IdMappingBackend::Json,
+            thumbnails: ThumbnailConfig::default(),
+            content_index: ContentIndexConfig::default(),
+        }
+    }
+}
+
+/// Configuración del servidor WebDAV
+#[derive(Debug, Clone)]
+pub struct WebDavConfig {
+    /// Duración en segundos de un bloqueo cuando el cliente no pide ninguna
+    pub lock_default_timeout_secs: u64,
+    /// Duración máxima en segundos de un bloqueo, también para `Infinite`
+    pub lock_max_timeout_secs: u64,
+    /// Número de cambios que conserva el registro de sincronización; los
+    /// tokens anteriores obligan al cliente a sincronizar de nuevo
+    pub sync_log_max_entries: usize,
+    /// Número máximo de recursos de un PROPFIND con `Depth: infinity`; por
+    /// encima se responde `propfind-finite-depth`, y con 0 nunca se permite
+    pub propfind_infinity_limit: usize,
+}
+
+impl Default for WebDavConfig {
+    fn default() -> Self {
+        Self {
+            lock_default_timeout_secs: 3600,      // 1 hora
+            lock_max_timeout_secs: 7 * 24 * 3600, // 7 días
+            sync_log_max_entries: 50_000,
+            propfind_infinity_limit: 10_000,
+        }
+    }
+}
+
+/// Seguridad de la conexión con el servidor SMTP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Conexión sin cifrar, para un relé local
+    None,
+    /// Conexión sin cifrar que pasa a TLS con `STARTTLS`, normalmente en el
+    /// puerto 587
+    StartTls,
+    /// TLS desde el principio, normalmente en el puerto 465
+    Tls,
+}
+
+impl SmtpTls {
+    /// Interpreta el valor de `OXICLOUD_SMTP_TLS`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" | "off" | "plain" => Some(Self::None),
+            "starttls" => Some(Self::StartTls),
+            "tls" | "ssl" | "smtps" => Some(Self::Tls),
+            _ => None,
+        }
+    }
+}
+
+/// Servidor SMTP por el que se envían los correos
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    /// Host del servidor; sin él no se envían correos
+    pub host: Option<String>,
+    pub port: u16,
+    pub tls: SmtpTls,
+    /// Credenciales para `AUTH PLAIN`, si el servidor las pide
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Remitente de los correos
+    pub from: String,
+    /// Tiempo máximo de cada envío en segundos
+    pub timeout_secs: u64,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: None,
+            port: 587,
+            tls: SmtpTls::StartTls,
+            username: None,
+            password: None,
+            from: "oxicloud@localhost".to_string(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Configuración de los recordatorios de las alarmas de los calendarios
+#[derive(Debug, Clone)]
+pub struct ReminderConfig {
+    /// Enviar los avisos de las alarmas (`VALARM`)
+    pub enabled: bool,
+    /// Segundos entre cada revisión de cambios y avisos pendientes
+    pub poll_interval_secs: u64,
+    /// Horas por delante que se indexan los disparos; el índice completo se
+    /// rehace cada hora
+    pub horizon_hours: u64,
+    /// URL a la que se envía cada aviso como JSON
+    pub webhook_url: Option<String>,
+    /// Clave para firmar los avisos del webhook con HMAC-SHA256
+    pub webhook_secret: Option<String>,
+    /// Servidor para las alarmas de correo
+    pub smtp: SmtpConfig,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: 30,
+            horizon_hours: 48,
+            webhook_url: None,
+            webhook_secret: None,
+            smtp: SmtpConfig::default(),
         }
     }
 }
@@ -274,6 +671,9 @@ pub struct FeaturesConfig {
     pub enable_file_sharing: bool,
     pub enable_trash: bool,
     pub enable_search: bool,
+    /// Endpoints de compatibilidad para los clientes de escritorio y móviles
+    /// de Nextcloud (`status.php`, `remote.php`, OCS y Login Flow v2)
+    pub enable_nextcloud_compat: bool,
 }
 
 impl Default for FeaturesConfig {
@@ -284,6 +684,7 @@ impl Default for FeaturesConfig {
             enable_file_sharing: true, // Enable file sharing by default
             enable_trash: true,        // Enable trash feature
             enable_search: true,       // Enable search feature
+            enable_nextcloud_compat: true,
         }
     }
 }
@@ -309,6 +710,10 @@ pub struct AppConfig {
     pub concurrency: ConcurrencyConfig,
     /// Configuración de almacenamiento
     pub storage: StorageConfig,
+    /// Configuración de WebDAV
+    pub webdav: WebDavConfig,
+    /// Configuración de los recordatorios de calendario
+    pub reminders: ReminderConfig,
     /// Configuración de base de datos
     pub database: DatabaseConfig,
     /// Configuración de autenticación
@@ -329,6 +734,8 @@ impl Default for AppConfig {
             resources: ResourceConfig::default(),
             concurrency: ConcurrencyConfig::default(),
             storage: StorageConfig::default(),
+            webdav: WebDavConfig::default(),
+            reminders: ReminderConfig::default(),
             database: DatabaseConfig::default(),
             auth: AuthConfig::default(),
             features: FeaturesConfig::default(),
@@ -359,6 +766,245 @@ impl AppConfig {
             config.server_host = server_host;
         }
 
+        // Configuración de almacenamiento
+        if let Ok(backend) = env::var("OXICLOUD_STORAGE_BACKEND") {
+            match StorageBackend::parse(&backend) {
+                Some(val) => config.storage.backend = val,
+                None => tracing::warn!("Unknown storage backend '{}', using default", backend),
+            }
+        }
+
+        if let Ok(backend) = env::var("OXICLOUD_ID_MAPPING_BACKEND") {
+            match IdMappingBackend::parse(&backend) {
+                Some(val) => config.storage.id_mapping_backend = val,
+                None => tracing::warn!("Unknown ID mapping backend '{}', using default", backend),
+            }
+        }
+
+        if let Ok(Ok(chunk_size)) =
+            env::var("OXICLOUD_DEDUP_CHUNK_SIZE").map(|v| v.parse::<usize>())
+        {
+            config.storage.dedup_chunk_size = chunk_size;
+        }
+
+        if let Ok(endpoint) = env::var("OXICLOUD_S3_ENDPOINT") {
+            config.storage.s3.endpoint = endpoint;
+        }
+
+        if let Ok(bucket) = env::var("OXICLOUD_S3_BUCKET") {
+            config.storage.s3.bucket = bucket;
+        }
+
+        if let Ok(region) = env::var("OXICLOUD_S3_REGION") {
+            config.storage.s3.region = region;
+        }
+
+        if let Ok(access_key_id) = env::var("OXICLOUD_S3_ACCESS_KEY_ID") {
+            config.storage.s3.access_key_id = access_key_id;
+        }
+
+        if let Ok(secret_access_key) = env::var("OXICLOUD_S3_SECRET_ACCESS_KEY") {
+            config.storage.s3.secret_access_key = secret_access_key;
+        }
+
+        if let Ok(Ok(path_style)) = env::var("OXICLOUD_S3_PATH_STYLE").map(|v| v.parse::<bool>()) {
+            config.storage.s3.path_style = path_style;
+        }
+
+        if let Ok(key_prefix) = env::var("OXICLOUD_S3_KEY_PREFIX") {
+            config.storage.s3.key_prefix = key_prefix;
+        }
+
+        if let Ok(Ok(part_size)) =
+            env::var("OXICLOUD_S3_MULTIPART_PART_SIZE").map(|v| v.parse::<usize>())
+        {
+            // S3 no admite partes de menos de 5 MB salvo la última
+            config.storage.s3.multipart_part_size = part_size.max(5 * 1024 * 1024);
+        }
+
+        if let Ok(Ok(enabled)) = env::var("OXICLOUD_VERSIONING_ENABLED").map(|v| v.parse::<bool>())
+        {
+            config.storage.versioning_enabled = enabled;
+        }
+
+        if let Ok(Ok(keep_count)) =
+            env::var("OXICLOUD_VERSION_KEEP_COUNT").map(|v| v.parse::<usize>())
+        {
+            config.storage.version_retention.keep_count = keep_count;
+        }
+
+        if let Ok(Ok(keep_days)) = env::var("OXICLOUD_VERSION_KEEP_DAYS").map(|v| v.parse::<u32>())
+        {
+            config.storage.version_retention.keep_days = keep_days;
+        }
+
+        if let Ok(thinning) = env::var("OXICLOUD_VERSION_THINNING") {
+            match VersionThinningRule::parse_list(&thinning) {
+                Some(rules) => config.storage.version_retention.thinning = rules,
+                None => tracing::warn!("Invalid version thinning schedule '{}'", thinning),
+            }
+        }
+
+        if let Ok(Ok(enabled)) = env::var("OXICLOUD_ENCRYPTION_ENABLED").map(|v| v.parse::<bool>())
+        {
+            config.storage.encryption.enabled = enabled;
+        }
+
+        if let Ok(master_key) = env::var("OXICLOUD_ENCRYPTION_MASTER_KEY") {
+            config.storage.encryption.master_key = Some(master_key);
+        }
+
+        if let Ok(key_file) = env::var("OXICLOUD_ENCRYPTION_MASTER_KEY_FILE") {
+            config.storage.encryption.master_key_file = Some(PathBuf::from(key_file));
+        }
+
+        if let Ok(Ok(migrate)) =
+            env::var("OXICLOUD_ENCRYPTION_MIGRATE_EXISTING").map(|v| v.parse::<bool>())
+        {
+            config.storage.encryption.migrate_existing = migrate;
+        }
+
+        if let Ok(Ok(enabled)) = env::var("OXICLOUD_UPLOADS_ENABLED").map(|v| v.parse::<bool>()) {
+            config.storage.uploads.enabled = enabled;
+        }
+
+        if let Ok(Ok(max_size)) = env::var("OXICLOUD_UPLOAD_MAX_SIZE").map(|v| v.parse::<u64>()) {
+            config.storage.uploads.max_size = max_size;
+        }
+
+        if let Ok(Ok(hours)) =
+            env::var("OXICLOUD_UPLOAD_EXPIRATION_HOURS").map(|v| v.parse::<u64>())
+        {
+            config.storage.uploads.expiration_hours = hours;
+        }
+
+        if let Ok(Ok(enabled)) = env::var("OXICLOUD_THUMBNAILS_ENABLED").map(|v| v.parse::<bool>())
+        {
+            config.storage.thumbnails.enabled = enabled;
+        }
+
+        if let Ok(Ok(enabled)) =
+            env::var("OXICLOUD_THUMBNAILS_ON_UPLOAD").map(|v| v.parse::<bool>())
+        {
+            config.storage.thumbnails.generate_on_upload = enabled;
+        }
+
+        if let Ok(Ok(workers)) = env::var("OXICLOUD_THUMBNAIL_WORKERS").map(|v| v.parse::<usize>())
+        {
+            config.storage.thumbnails.workers = workers.max(1);
+        }
+
+        if let Ok(Ok(max_size)) =
+            env::var("OXICLOUD_THUMBNAIL_MAX_SOURCE_SIZE").map(|v| v.parse::<u64>())
+        {
+            config.storage.thumbnails.max_source_size = max_size;
+        }
+
+        if let Ok(converter) = env::var("OXICLOUD_THUMBNAIL_CONVERTER") {
+            config.storage.thumbnails.converter = converter;
+        }
+
+        if let Ok(Ok(timeout)) = env::var("OXICLOUD_THUMBNAIL_TIMEOUT").map(|v| v.parse::<u64>()) {
+            config.storage.thumbnails.timeout_secs = timeout;
+        }
+
+        if let Ok(Ok(enabled)) =
+            env::var("OXICLOUD_CONTENT_INDEX_ENABLED").map(|v| v.parse::<bool>())
+        {
+            config.storage.content_index.enabled = enabled;
+        }
+
+        if let Ok(Ok(max_size)) =
+            env::var("OXICLOUD_CONTENT_INDEX_MAX_FILE_SIZE").map(|v| v.parse::<u64>())
+        {
+            config.storage.content_index.max_file_size = max_size;
+        }
+
+        if let Ok(Ok(max_chars)) =
+            env::var("OXICLOUD_CONTENT_INDEX_MAX_CHARS").map(|v| v.parse::<usize>())
+        {
+            config.storage.content_index.max_text_chars = max_chars;
+        }
+
+        // Configuración de WebDAV
+        if let Ok(Ok(timeout)) = env::var("OXICLOUD_WEBDAV_LOCK_TIMEOUT").map(|v| v.parse::<u64>())
+        {
+            config.webdav.lock_default_timeout_secs = timeout.max(1);
+        }
+
+        if let Ok(Ok(timeout)) =
+            env::var("OXICLOUD_WEBDAV_LOCK_MAX_TIMEOUT").map(|v| v.parse::<u64>())
+        {
+            config.webdav.lock_max_timeout_secs = timeout.max(1);
+        }
+
+        if let Ok(Ok(entries)) =
+            env::var("OXICLOUD_WEBDAV_SYNC_LOG_ENTRIES").map(|v| v.parse::<usize>())
+        {
+            config.webdav.sync_log_max_entries = entries.max(1);
+        }
+
+        if let Ok(Ok(limit)) =
+            env::var("OXICLOUD_WEBDAV_PROPFIND_INFINITY_LIMIT").map(|v| v.parse::<usize>())
+        {
+            config.webdav.propfind_infinity_limit = limit;
+        }
+
+        // Configuración de los recordatorios
+        if let Ok(Ok(enabled)) = env::var("OXICLOUD_REMINDERS_ENABLED").map(|v| v.parse::<bool>()) {
+            config.reminders.enabled = enabled;
+        }
+
+        if let Ok(Ok(interval)) =
+            env::var("OXICLOUD_REMINDER_POLL_INTERVAL").map(|v| v.parse::<u64>())
+        {
+            config.reminders.poll_interval_secs = interval.max(1);
+        }
+
+        if let Ok(Ok(hours)) = env::var("OXICLOUD_REMINDER_HORIZON_HOURS").map(|v| v.parse::<u64>())
+        {
+            config.reminders.horizon_hours = hours.max(2);
+        }
+
+        if let Ok(url) = env::var("OXICLOUD_REMINDER_WEBHOOK_URL") {
+            config.reminders.webhook_url = Some(url);
+        }
+
+        if let Ok(secret) = env::var("OXICLOUD_REMINDER_WEBHOOK_SECRET") {
+            config.reminders.webhook_secret = Some(secret);
+        }
+
+        if let Ok(host) = env::var("OXICLOUD_SMTP_HOST") {
+            config.reminders.smtp.host = Some(host);
+        }
+
+        if let Ok(Ok(port)) = env::var("OXICLOUD_SMTP_PORT").map(|v| v.parse::<u16>()) {
+            config.reminders.smtp.port = port;
+        }
+
+        if let Ok(tls) = env::var("OXICLOUD_SMTP_TLS") {
+            match SmtpTls::parse(&tls) {
+                Some(val) => config.reminders.smtp.tls = val,
+                None => tracing::warn!("Unknown SMTP TLS mode '{}', using default", tls),
+            }
+        }
+
+        if let Ok(username) = env::var("OXICLOUD_SMTP_USERNAME") {
+            config.reminders.smtp.username = Some(username);
+        }
+
+        if let Ok(password) = env::var("OXICLOUD_SMTP_PASSWORD") {
+            config.reminders.smtp.password = Some(password);
+        }
+
+        if let Ok(from) = env::var("OXICLOUD_SMTP_FROM") {
+            config.reminders.smtp.from = from;
+        }
+
+        if let Ok(Ok(timeout)) = env::var("OXICLOUD_SMTP_TIMEOUT").map(|v| v.parse::<u64>()) {
+            config.reminders.smtp.timeout_secs = timeout.max(1);
+        }
+
         // Configuración de Database
         if let Ok(connection_string) = env::var("OXICLOUD_DB_CONNECTION_STRING") {
             config.database.connection_string = connection_string;
@@ -436,6 +1082,12 @@ impl AppConfig {
             }
         }
 
+        if let Ok(Ok(enable_nextcloud_compat)) =
+            env::var("OXICLOUD_ENABLE_NEXTCLOUD_COMPAT").map(|v| v.parse::<bool>())
+        {
+            config.features.enable_nextcloud_compat = enable_nextcloud_compat;
+        }
+
         config
     }
 
@@ -13,8 +13,14 @@ use crate::application::ports::file_ports::{
 use crate::application::ports::inbound::{FileUseCase, FolderUseCase, SearchUseCase};
 use crate::application::ports::outbound::{FileStoragePort, FolderStoragePort};
 use crate::application::ports::recent_ports::RecentItemsUseCase;
+use crate::application::ports::reminder_ports::ReminderUseCase;
+use crate::application::ports::scheduling_ports::SchedulingUseCase;
 use crate::application::ports::storage_ports::{FileReadPort, FileWritePort};
+use crate::application::ports::sync_ports::ChangeLogUseCase;
 use crate::application::ports::trash_ports::TrashUseCase;
+use crate::application::ports::webdav_acl_ports::WebDavAclUseCase;
+use crate::application::ports::webdav_lock_ports::WebDavLockUseCase;
+use crate::application::ports::webdav_property_ports::DeadPropertyUseCase;
 use crate::application::services::file_service::FileService;
 use crate::application::services::folder_service::FolderService;
 use crate::application::services::i18n_application_service::I18nApplicationService;
@@ -314,8 +320,15 @@ pub struct AppState {
     pub recent_service: Option<Arc<dyn RecentItemsUseCase>>,
     pub storage_usage_service:
         Option<Arc<dyn crate::application::ports::storage_ports::StorageUsagePort>>,
-    pub calendar_service: Option<Arc<dyn crate::application::ports::storage_ports::StorageUseCase>>,
+    pub calendar_service:
+        Option<Arc<dyn crate::application::ports::calendar_ports::CalendarUseCase>>,
+    pub scheduling_service: Option<Arc<dyn SchedulingUseCase>>,
+    pub reminder_service: Option<Arc<dyn ReminderUseCase>>,
     pub contact_service: Option<Arc<dyn crate::application::ports::storage_ports::StorageUseCase>>,
+    pub lock_service: Option<Arc<dyn WebDavLockUseCase>>,
+    pub property_service: Option<Arc<dyn DeadPropertyUseCase>>,
+    pub change_log: Option<Arc<dyn ChangeLogUseCase>>,
+    pub acl_service: Option<Arc<dyn WebDavAclUseCase>>,
 }
 
 impl Default for AppState {
@@ -1021,6 +1034,19 @@ impl Default for AppState {
                 let empty_stream = futures::stream::empty::<Result<bytes::Bytes, std::io::Error>>();
                 Ok(Box::new(empty_stream))
             }
+
+            async fn get_file_range_stream(
+                &self,
+                _id: &str,
+                _start: u64,
+                _end: u64,
+            ) -> Result<
+                Box<dyn futures::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send>,
+                crate::common::errors::DomainError,
+            > {
+                let empty_stream = futures::stream::empty::<Result<bytes::Bytes, std::io::Error>>();
+                Ok(Box::new(empty_stream))
+            }
         }
 
         struct DummyFileManagementUseCase;
@@ -1146,6 +1172,7 @@ impl Default for AppState {
             async fn search(
                 &self,
                 _criteria: crate::application::dtos::search_dto::SearchCriteriaDto,
+                _username: Option<&str>,
             ) -> Result<
                 crate::application::dtos::search_dto::SearchResultsDto,
                 crate::common::errors::DomainError,
@@ -1188,7 +1215,13 @@ impl Default for AppState {
             recent_service: None,
             storage_usage_service: None,
             calendar_service: None,
+            scheduling_service: None,
+            reminder_service: None,
             contact_service: None,
+            lock_service: None,
+            property_service: None,
+            change_log: None,
+            acl_service: None,
         }
     }
 }
@@ -1211,7 +1244,13 @@ impl AppState {
             recent_service: None,
             storage_usage_service: None,
             calendar_service: None,
+            scheduling_service: None,
+            reminder_service: None,
             contact_service: None,
+            lock_service: None,
+            property_service: None,
+            change_log: None,
+            acl_service: None,
         }
     }
 
@@ -1258,12 +1297,25 @@ impl AppState {
 
     pub fn with_calendar_service(
         mut self,
-        calendar_service: Arc<dyn crate::application::ports::storage_ports::StorageUseCase>,
+        calendar_service: Arc<dyn crate::application::ports::calendar_ports::CalendarUseCase>,
     ) -> Self {
         self.calendar_service = Some(calendar_service);
         self
     }
 
+    pub fn with_scheduling_service(
+        mut self,
+        scheduling_service: Arc<dyn SchedulingUseCase>,
+    ) -> Self {
+        self.scheduling_service = Some(scheduling_service);
+        self
+    }
+
+    pub fn with_reminder_service(mut self, reminder_service: Arc<dyn ReminderUseCase>) -> Self {
+        self.reminder_service = Some(reminder_service);
+        self
+    }
+
     pub fn with_contact_service(
         mut self,
         contact_service: Arc<dyn crate::application::ports::storage_ports::StorageUseCase>,
@@ -1271,4 +1323,24 @@ impl AppState {
         self.contact_service = Some(contact_service);
         self
     }
+
+    pub fn with_lock_service(mut self, lock_service: Arc<dyn WebDavLockUseCase>) -> Self {
+        self.lock_service = Some(lock_service);
+        self
+    }
+
+    pub fn with_property_service(mut self, property_service: Arc<dyn DeadPropertyUseCase>) -> Self {
+        self.property_service = Some(property_service);
+        self
+    }
+
+    pub fn with_change_log(mut self, change_log: Arc<dyn ChangeLogUseCase>) -> Self {
+        self.change_log = Some(change_log);
+        self
+    }
+
+    pub fn with_acl_service(mut self, acl_service: Arc<dyn WebDavAclUseCase>) -> Self {
+        self.acl_service = Some(acl_service);
+        self
+    }
 }
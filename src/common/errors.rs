@@ -26,6 +26,10 @@ pub enum ErrorKind {
     UnsupportedOperation,
     /// Error de base de datos
     DatabaseError,
+    /// Cuota de almacenamiento superada
+    QuotaExceeded,
+    /// Recurso bloqueado por otro cliente
+    Locked,
 }
 
 impl Display for ErrorKind {
@@ -40,6 +44,8 @@ impl Display for ErrorKind {
             ErrorKind::NotImplemented => write!(f, "Not Implemented"),
             ErrorKind::UnsupportedOperation => write!(f, "Unsupported Operation"),
             ErrorKind::DatabaseError => write!(f, "Database Error"),
+            ErrorKind::QuotaExceeded => write!(f, "Quota Exceeded"),
+            ErrorKind::Locked => write!(f, "Locked"),
         }
     }
 }
@@ -157,6 +163,16 @@ impl DomainError {
         }
     }
 
+    /// Crea un error de cuota de almacenamiento superada
+    pub fn quota_exceeded<S: Into<String>>(entity_type: &'static str, message: S) -> Self {
+        Self::new(ErrorKind::QuotaExceeded, entity_type, message)
+    }
+
+    /// Crea un error de recurso bloqueado
+    pub fn locked<S: Into<String>>(entity_type: &'static str, message: S) -> Self {
+        Self::new(ErrorKind::Locked, entity_type, message)
+    }
+
     /// Crea un error de validación
     pub fn validation_error<S: Into<String>>(message: S) -> Self {
         Self {
@@ -338,6 +354,14 @@ impl AppError {
             "UnsupportedMediaType",
         )
     }
+
+    pub fn precondition_failed(message: impl Into<String>) -> Self {
+        Self::new(
+            axum::http::StatusCode::PRECONDITION_FAILED,
+            message,
+            "PreconditionFailed",
+        )
+    }
 }
 
 impl From<DomainError> for AppError {
@@ -352,6 +376,8 @@ impl From<DomainError> for AppError {
             ErrorKind::NotImplemented => axum::http::StatusCode::NOT_IMPLEMENTED,
             ErrorKind::UnsupportedOperation => axum::http::StatusCode::METHOD_NOT_ALLOWED,
             ErrorKind::DatabaseError => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorKind::QuotaExceeded => axum::http::StatusCode::INSUFFICIENT_STORAGE,
+            ErrorKind::Locked => axum::http::StatusCode::LOCKED,
         };
 
         Self {
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use rand::distr::{Alphanumeric, SampleString};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Longitud de las contraseñas generadas, la misma que usa Nextcloud
+const APP_PASSWORD_LENGTH: usize = 72;
+
+/// Contraseña de aplicación: credencial propia de un cliente (escritorio,
+/// móvil) que se puede revocar sin cambiar la contraseña de la cuenta.
+///
+/// Solo se guarda el resumen SHA-256; el valor en claro se entrega una única
+/// vez al crearla. Al ser aleatoria y larga no necesita un hash lento, lo que
+/// permite comprobarla en cada petición con autenticación básica.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AppPassword {
+    pub id: String,
+    pub user_id: String,
+    /// Nombre del cliente que la pidió, p. ej. su User-Agent
+    pub name: String,
+    pub password_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AppPassword {
+    /// Genera una contraseña nueva y la devuelve en claro junto a la entidad
+    pub fn generate(user_id: impl Into<String>, name: impl Into<String>) -> (Self, String) {
+        let password = Alphanumeric.sample_string(&mut rand::rng(), APP_PASSWORD_LENGTH);
+        let app_password = Self {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.into(),
+            name: name.into(),
+            password_hash: Self::hash(&password),
+            created_at: Utc::now(),
+        };
+        (app_password, password)
+    }
+
+    /// Resumen con el que se guarda y se busca una contraseña
+    pub fn hash(password: &str) -> String {
+        hex::encode(Sha256::digest(password.as_bytes()))
+    }
+
+    /// Indica si `password` es esta contraseña
+    pub fn verify(&self, password: &str) -> bool {
+        self.password_hash == Self::hash(password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_password_verifies() {
+        let (app_password, password) = AppPassword::generate("user-1", "Desktop client");
+        assert_eq!(password.len(), APP_PASSWORD_LENGTH);
+        assert!(app_password.verify(&password));
+        assert!(!app_password.verify("something else"));
+        assert!(!app_password.password_hash.contains(&password));
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono::{DateTime, Duration, Utc};
 use thiserror::Error;
 /**
  * Calendar Event Entity
@@ -13,6 +13,9 @@ use thiserror::Error;
 use uuid::Uuid;
 
 use crate::common::errors::{DomainError, ErrorKind, Result};
+use crate::domain::entities::icalendar::{ICalComponent, ICalProperty};
+use crate::domain::entities::recurrence::{self, RecurrenceRule};
+use crate::domain::entities::timezone::TimeZones;
 
 /**
  * Error types specific to calendar event operations.
@@ -68,6 +71,10 @@ pub struct CalendarEvent {
     /// Whether this is an all-day event
     all_day: bool,
 
+    /// Time zone the event's local times are written in: the TZID of its
+    /// DTSTART, or the owner's default zone for floating times (optional)
+    timezone: Option<String>,
+
     /// Recurrence rule in iCalendar RRULE format (optional)
     rrule: Option<String>,
 
@@ -127,15 +134,10 @@ impl CalendarEvent {
             ));
         }
 
-        // Validate RRULE if provided (basic validation)
         if let Some(ref rule) = rrule {
-            if !rule.starts_with("FREQ=") {
-                return Err(DomainError::new(
-                    ErrorKind::InvalidInput,
-                    "CalendarEvent",
-                    "Recurrence rule must start with FREQ=",
-                ));
-            }
+            RecurrenceRule::parse(rule).map_err(|message| {
+                DomainError::new(ErrorKind::InvalidInput, "CalendarEvent", message)
+            })?;
         }
 
         // Validate iCalendar data (basic validation)
@@ -158,6 +160,7 @@ impl CalendarEvent {
             start_time,
             end_time,
             all_day,
+            timezone: None,
             rrule,
             ical_uid: Uuid::new_v4().to_string(),
             ical_data,
@@ -226,6 +229,7 @@ impl CalendarEvent {
             start_time,
             end_time,
             all_day,
+            timezone: None,
             rrule,
             ical_uid,
             ical_data,
@@ -240,85 +244,47 @@ impl CalendarEvent {
      *
      * @param calendar_id ID of the calendar this event belongs to
      * @param ical_data Complete iCalendar data (VEVENT component)
+     * @param default_timezone Zone for floating times, usually the owner's default (optional)
      * @return Result containing the new CalendarEvent or a domain error
      */
-    pub fn from_ical(calendar_id: Uuid, ical_data: String) -> Result<Self> {
-        // This implementation would require a proper iCalendar parser
-        // For brevity, we're using a simplified version here
-
-        // Extract required fields from iCalendar data
-        let summary = Self::extract_ical_property(&ical_data, "SUMMARY").ok_or_else(|| {
-            DomainError::new(
-                ErrorKind::InvalidInput,
-                "CalendarEvent",
-                "Missing SUMMARY in iCalendar data",
-            )
-        })?;
-
-        let dtstart = Self::extract_ical_property(&ical_data, "DTSTART").ok_or_else(|| {
-            DomainError::new(
-                ErrorKind::InvalidInput,
-                "CalendarEvent",
-                "Missing DTSTART in iCalendar data",
-            )
-        })?;
-
-        let dtend = Self::extract_ical_property(&ical_data, "DTEND").ok_or_else(|| {
-            DomainError::new(
-                ErrorKind::InvalidInput,
-                "CalendarEvent",
-                "Missing DTEND in iCalendar data",
-            )
-        })?;
-
-        // Parse dates (simplified)
-        let start_time = Self::parse_ical_datetime(&dtstart).map_err(|e| {
-            DomainError::new(
-                ErrorKind::InvalidInput,
-                "CalendarEvent",
-                format!("Invalid DTSTART: {}", e),
-            )
-        })?;
-
-        let end_time = Self::parse_ical_datetime(&dtend).map_err(|e| {
-            DomainError::new(
-                ErrorKind::InvalidInput,
-                "CalendarEvent",
-                format!("Invalid DTEND: {}", e),
-            )
-        })?;
-
-        // Determine if all-day event (simplified check)
-        let all_day = dtstart.contains("VALUE=DATE") && !dtstart.contains("T");
-
-        // Extract optional fields
-        let description = Self::extract_ical_property(&ical_data, "DESCRIPTION");
-        let location = Self::extract_ical_property(&ical_data, "LOCATION");
-        let rrule = Self::extract_ical_property(&ical_data, "RRULE");
-
-        // Extract UID or generate a new one
-        let ical_uid = Self::extract_ical_property(&ical_data, "UID")
-            .unwrap_or_else(|| Uuid::new_v4().to_string());
-
+    pub fn from_ical(
+        calendar_id: Uuid,
+        ical_data: String,
+        default_timezone: Option<&str>,
+    ) -> Result<Self> {
+        let parsed = ParsedEvent::parse(&ical_data, default_timezone)?;
         let now = Utc::now();
 
         Ok(Self {
             id: Uuid::new_v4(),
             calendar_id,
-            summary,
-            description,
-            location,
-            start_time,
-            end_time,
-            all_day,
-            rrule,
-            ical_uid,
+            summary: parsed.summary,
+            description: parsed.description,
+            location: parsed.location,
+            start_time: parsed.start_time,
+            end_time: parsed.end_time,
+            all_day: parsed.all_day,
+            timezone: parsed.timezone,
+            rrule: parsed.rrule,
+            ical_uid: parsed.ical_uid,
             ical_data,
             created_at: now,
             updated_at: now,
         })
     }
 
+    /**
+     * Sets the time zone the event's local times are written in.
+     * Typically used when reconstructing from storage.
+     *
+     * @param timezone IANA or VTIMEZONE identifier (optional)
+     * @return The event with the time zone set
+     */
+    pub fn with_timezone(mut self, timezone: Option<String>) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
     // Getters
 
     /// Returns the event's unique identifier
@@ -361,6 +327,11 @@ impl CalendarEvent {
         self.all_day
     }
 
+    /// Returns the time zone of the event's local times, if any
+    pub fn timezone(&self) -> Option<&str> {
+        self.timezone.as_deref()
+    }
+
     /// Returns the event's recurrence rule, if any
     pub fn rrule(&self) -> Option<&str> {
         self.rrule.as_deref()
@@ -527,15 +498,10 @@ impl CalendarEvent {
      * @return Result indicating success or containing a domain error
      */
     pub fn update_rrule(&mut self, rrule: Option<String>) -> Result<()> {
-        // Validate RRULE if provided (basic validation)
         if let Some(ref rule) = rrule {
-            if !rule.starts_with("FREQ=") {
-                return Err(DomainError::new(
-                    ErrorKind::InvalidInput,
-                    "CalendarEvent",
-                    "Recurrence rule must start with FREQ=",
-                ));
-            }
+            RecurrenceRule::parse(rule).map_err(|message| {
+                DomainError::new(ErrorKind::InvalidInput, "CalendarEvent", message)
+            })?;
         }
 
         self.rrule = rrule.clone();
@@ -555,49 +521,25 @@ impl CalendarEvent {
      * Also updates the event properties based on the new iCalendar data.
      *
      * @param ical_data New iCalendar data for the event
+     * @param default_timezone Zone for floating times, usually the owner's default (optional)
      * @return Result indicating success or containing a domain error
      */
-    pub fn update_ical_data(&mut self, ical_data: String) -> Result<()> {
-        // Validate iCalendar data (basic validation)
-        if !ical_data.contains("BEGIN:VEVENT") || !ical_data.contains("END:VEVENT") {
-            return Err(DomainError::new(
-                ErrorKind::InvalidInput,
-                "CalendarEvent",
-                "iCalendar data must contain a VEVENT component",
-            ));
-        }
-
-        // Extract and update properties from iCalendar data
-        if let Some(summary) = Self::extract_ical_property(&ical_data, "SUMMARY") {
-            self.summary = summary;
-        }
-
-        self.description = Self::extract_ical_property(&ical_data, "DESCRIPTION");
-        self.location = Self::extract_ical_property(&ical_data, "LOCATION");
-
-        if let Some(dtstart) = Self::extract_ical_property(&ical_data, "DTSTART") {
-            if let Ok(start_time) = Self::parse_ical_datetime(&dtstart) {
-                self.start_time = start_time;
-            }
-        }
-
-        if let Some(dtend) = Self::extract_ical_property(&ical_data, "DTEND") {
-            if let Ok(end_time) = Self::parse_ical_datetime(&dtend) {
-                self.end_time = end_time;
-            }
-        }
-
-        // Update all-day status based on DTSTART
-        if let Some(dtstart) = Self::extract_ical_property(&ical_data, "DTSTART") {
-            self.all_day = dtstart.contains("VALUE=DATE") && !dtstart.contains("T");
-        }
-
-        self.rrule = Self::extract_ical_property(&ical_data, "RRULE");
-
-        if let Some(uid) = Self::extract_ical_property(&ical_data, "UID") {
-            self.ical_uid = uid;
-        }
-
+    pub fn update_ical_data(
+        &mut self,
+        ical_data: String,
+        default_timezone: Option<&str>,
+    ) -> Result<()> {
+        let parsed = ParsedEvent::parse(&ical_data, default_timezone)?;
+
+        self.summary = parsed.summary;
+        self.description = parsed.description;
+        self.location = parsed.location;
+        self.start_time = parsed.start_time;
+        self.end_time = parsed.end_time;
+        self.all_day = parsed.all_day;
+        self.timezone = parsed.timezone;
+        self.rrule = parsed.rrule;
+        self.ical_uid = parsed.ical_uid;
         self.ical_data = ical_data;
         self.updated_at = Utc::now();
 
@@ -616,152 +558,25 @@ impl CalendarEvent {
 
     /**
      * Checks if this event occurs within the specified time range.
+     * Recurrences are expanded in the event's own time zone.
      *
      * @param start Start of the time range to check
      * @param end End of the time range to check
      * @return true if the event occurs within the range, false otherwise
      */
     pub fn occurs_in_range(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> bool {
-        // Basic case: event directly overlaps with range
-        if self.start_time <= *end && self.end_time >= *start {
-            return true;
-        }
-
-        // If event has recurrence, check if any recurrence occurs in range
-        // Note: A full implementation would need a proper recurrence rule parser
-        if let Some(rrule) = &self.rrule {
-            // Simplified check for demonstration
-            // A real implementation would need to generate recurrence instances
-            // and check if any fall within the range
-
-            // For now, we'll just check if the recurrence hasn't ended
-            // or if it ended after the start of our range
-            if let Some(until_pos) = rrule.find("UNTIL=") {
-                let until_start = until_pos + 6; // "UNTIL=" is 6 chars
-                if let Some(until_end) = rrule[until_start..].find(';') {
-                    let until_str = &rrule[until_start..until_start + until_end];
-                    if let Ok(until_date) = Self::parse_ical_datetime(&until_str) {
-                        return until_date >= *start;
-                    }
-                } else {
-                    // UNTIL is the last part of the rule
-                    let until_str = &rrule[until_start..];
-                    if let Ok(until_date) = Self::parse_ical_datetime(&until_str) {
-                        return until_date >= *start;
-                    }
-                }
-            } else {
-                // No UNTIL specified, so recurrence continues indefinitely
-                return true;
+        match ICalComponent::parse(&self.ical_data) {
+            Ok(calendar) => {
+                let zones = TimeZones::for_calendar(&calendar, self.timezone.as_deref());
+                !recurrence::occurrences(&calendar, &zones, *start, *end).is_empty()
             }
+            // Without parseable data, fall back to the stored time span
+            Err(_) => self.start_time < *end && self.end_time > *start,
         }
-
-        false
     }
 
     // Helper methods for iCalendar operations
 
-    /**
-     * Extracts a property value from iCalendar data.
-     *
-     * @param ical_data The iCalendar data to search in
-     * @param property_name The name of the property to extract
-     * @return Option containing the property value if found
-     */
-    fn extract_ical_property(ical_data: &str, property_name: &str) -> Option<String> {
-        // Find the property in the iCalendar data
-        let search_str = format!("\n{}:", property_name);
-        let search_str_alt = format!("\r\n{}:", property_name);
-
-        let pos = ical_data
-            .find(&search_str)
-            .or_else(|| ical_data.find(&search_str_alt));
-
-        if let Some(pos) = pos {
-            // Find the start of the value
-            let value_start = pos + search_str.len();
-
-            // Find the end of the value (next line or end of string)
-            let value_end = ical_data[value_start..]
-                .find('\n')
-                .map(|p| value_start + p)
-                .unwrap_or_else(|| ical_data.len());
-
-            // Extract and return the value
-            let value = ical_data[value_start..value_end].trim();
-            if !value.is_empty() {
-                return Some(value.to_string());
-            }
-        }
-
-        None
-    }
-
-    /**
-     * Parses an iCalendar datetime string into a DateTime object.
-     *
-     * @param datetime The iCalendar datetime string to parse
-     * @return Result containing the parsed DateTime or an error
-     */
-    fn parse_ical_datetime(datetime: &str) -> std::result::Result<DateTime<Utc>, String> {
-        // Handle VALUE=DATE format
-        if datetime.contains("VALUE=DATE") {
-            let date_str = datetime.split(':').last().unwrap_or("");
-            if date_str.len() != 8 {
-                return Err("Invalid date format".to_string());
-            }
-
-            let year = date_str[0..4]
-                .parse::<i32>()
-                .map_err(|_| "Invalid year".to_string())?;
-            let month = date_str[4..6]
-                .parse::<u32>()
-                .map_err(|_| "Invalid month".to_string())?;
-            let day = date_str[6..8]
-                .parse::<u32>()
-                .map_err(|_| "Invalid day".to_string())?;
-
-            return match chrono::NaiveDate::from_ymd_opt(year, month, day) {
-                Some(date) => Ok(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())),
-                None => Err("Invalid date components".to_string()),
-            };
-        }
-
-        // Handle standard UTC format (20230101T120000Z)
-        let datetime_str = datetime.split(':').last().unwrap_or(datetime);
-        if datetime_str.len() < 15 || !datetime_str.ends_with('Z') {
-            return Err("Invalid datetime format".to_string());
-        }
-
-        let year = datetime_str[0..4]
-            .parse::<i32>()
-            .map_err(|_| "Invalid year".to_string())?;
-        let month = datetime_str[4..6]
-            .parse::<u32>()
-            .map_err(|_| "Invalid month".to_string())?;
-        let day = datetime_str[6..8]
-            .parse::<u32>()
-            .map_err(|_| "Invalid day".to_string())?;
-
-        let hour = datetime_str[9..11]
-            .parse::<u32>()
-            .map_err(|_| "Invalid hour".to_string())?;
-        let minute = datetime_str[11..13]
-            .parse::<u32>()
-            .map_err(|_| "Invalid minute".to_string())?;
-        let second = datetime_str[13..15]
-            .parse::<u32>()
-            .map_err(|_| "Invalid second".to_string())?;
-
-        match chrono::NaiveDate::from_ymd_opt(year, month, day) {
-            Some(date) => match date.and_hms_opt(hour, minute, second) {
-                Some(datetime) => Ok(Utc.from_utc_datetime(&datetime)),
-                None => Err("Invalid time components".to_string()),
-            },
-            None => Err("Invalid date components".to_string()),
-        }
-    }
-
     /**
      * Updates an iCalendar property in the event's iCalendar data.
      *
@@ -834,3 +649,64 @@ impl CalendarEvent {
         }
     }
 }
+
+/**
+ * Event properties read from the master VEVENT of an iCalendar object, the
+ * first one without a RECURRENCE-ID.
+ */
+struct ParsedEvent {
+    summary: String,
+    description: Option<String>,
+    location: Option<String>,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    all_day: bool,
+    timezone: Option<String>,
+    rrule: Option<String>,
+    ical_uid: String,
+}
+
+impl ParsedEvent {
+    fn parse(ical_data: &str, default_timezone: Option<&str>) -> Result<Self> {
+        let invalid =
+            |message: String| DomainError::new(ErrorKind::InvalidInput, "CalendarEvent", message);
+
+        let calendar = ICalComponent::parse(ical_data).map_err(invalid)?;
+        let event = calendar
+            .components_named("VEVENT")
+            .find(|event| event.property("RECURRENCE-ID").is_none())
+            .or_else(|| calendar.components_named("VEVENT").next())
+            .ok_or_else(|| invalid("iCalendar data must contain a VEVENT component".into()))?;
+
+        let required = |name: &str| {
+            event
+                .property(name)
+                .filter(|property| !property.value.trim().is_empty())
+                .ok_or_else(|| invalid(format!("Missing {} in iCalendar data", name)))
+        };
+        let summary = required("SUMMARY")?.text();
+        let ical_uid = required("UID")?.value.trim().to_string();
+        let dtstart = required("DTSTART")?;
+        let zones = TimeZones::for_calendar(&calendar, default_timezone);
+        let (start_time, end_time) = event
+            .time_span(&zones)
+            .ok_or_else(|| invalid(format!("Invalid DTSTART: {}", dtstart.value)))?;
+        let timezone = match dtstart.param("TZID") {
+            Some(tzid) => Some(tzid.to_string()),
+            None if zones.zone_of(dtstart).is_some() => default_timezone.map(str::to_string),
+            None => None,
+        };
+
+        Ok(Self {
+            summary,
+            description: event.property("DESCRIPTION").map(ICalProperty::text),
+            location: event.property("LOCATION").map(ICalProperty::text),
+            start_time,
+            end_time,
+            all_day: dtstart.is_date(),
+            timezone,
+            rrule: event.property("RRULE").map(|rrule| rrule.value.clone()),
+            ical_uid,
+        })
+    }
+}
@@ -0,0 +1,800 @@
+use chrono::{DateTime, Utc};
+/**
+ * Calendar Task Entity
+ *
+ * This module defines the CalendarTask entity, which represents a to-do
+ * (VTODO, RFC 5545 section 3.6.2) kept in a calendar next to its events.
+ *
+ * Tasks have a status, a priority, optional start, due and completion times
+ * and a percent-complete. A task can be a subtask of another one through
+ * RELATED-TO, and can repeat with a recurrence rule. The iCalendar data is
+ * the source of truth: every other field is read from its master VTODO.
+ */
+use uuid::Uuid;
+
+use crate::common::errors::{DomainError, ErrorKind, Result};
+use crate::domain::entities::icalendar::{
+    escape_text, format_date_time, parse_duration, ICalComponent, ICalProperty,
+};
+use crate::domain::entities::recurrence::{self, RecurrenceRule, RecurrenceSet};
+use crate::domain::entities::timezone::TimeZones;
+
+/**
+ * Progress of a task, as given by its STATUS property.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    /// Not started yet (the default)
+    NeedsAction,
+    /// Started but not finished
+    InProcess,
+    /// Finished
+    Completed,
+    /// Will not be done
+    Cancelled,
+}
+
+impl TaskStatus {
+    /// Returns the iCalendar value of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::NeedsAction => "NEEDS-ACTION",
+            TaskStatus::InProcess => "IN-PROCESS",
+            TaskStatus::Completed => "COMPLETED",
+            TaskStatus::Cancelled => "CANCELLED",
+        }
+    }
+
+    /// Parses an iCalendar status value, ignoring case
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "NEEDS-ACTION" => Some(TaskStatus::NeedsAction),
+            "IN-PROCESS" => Some(TaskStatus::InProcess),
+            "COMPLETED" => Some(TaskStatus::Completed),
+            "CANCELLED" => Some(TaskStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * CalendarTask entity.
+ *
+ * Represents a to-do that can be synced via CalDAV, stored with its complete
+ * iCalendar representation.
+ */
+#[derive(Debug, Clone)]
+pub struct CalendarTask {
+    /// Unique identifier for the task
+    id: Uuid,
+
+    /// ID of the calendar this task belongs to
+    calendar_id: Uuid,
+
+    /// Short summary/title of the task (may be empty)
+    summary: String,
+
+    /// Detailed description of the task (optional)
+    description: Option<String>,
+
+    /// Progress of the task
+    status: TaskStatus,
+
+    /// Priority from 1 (highest) to 9 (lowest); none when undefined
+    priority: Option<u8>,
+
+    /// Time the task starts (optional)
+    start_time: Option<DateTime<Utc>>,
+
+    /// Time the task is due (optional)
+    due_time: Option<DateTime<Utc>>,
+
+    /// Time the task was completed (optional)
+    completed_at: Option<DateTime<Utc>>,
+
+    /// Percent of the task that is done, from 0 to 100 (optional)
+    percent_complete: Option<u8>,
+
+    /// UID of the parent task when this is a subtask (optional)
+    parent_uid: Option<String>,
+
+    /// Recurrence rule in iCalendar RRULE format (optional)
+    rrule: Option<String>,
+
+    /// Time zone the task's local times are written in (optional)
+    timezone: Option<String>,
+
+    /// Unique identifier in iCalendar format (used for CalDAV sync)
+    ical_uid: String,
+
+    /// Complete iCalendar data (VTODO component)
+    ical_data: String,
+
+    /// Time when the task was created
+    created_at: DateTime<Utc>,
+
+    /// Time when the task was last modified
+    updated_at: DateTime<Utc>,
+}
+
+impl CalendarTask {
+    /**
+     * Creates a calendar task from an iCalendar VTODO component.
+     *
+     * @param calendar_id ID of the calendar this task belongs to
+     * @param ical_data Complete iCalendar data (VTODO component)
+     * @param default_timezone Zone for floating times, usually the owner's default (optional)
+     * @return Result containing the new CalendarTask or a domain error
+     */
+    pub fn from_ical(
+        calendar_id: Uuid,
+        ical_data: String,
+        default_timezone: Option<&str>,
+    ) -> Result<Self> {
+        let now = Utc::now();
+        Self::with_id(Uuid::new_v4(), calendar_id, ical_data, now, now)
+            .and_then(|task| task.reparse(default_timezone))
+    }
+
+    /**
+     * Creates a calendar task with specific ID and timestamps.
+     * Typically used when reconstructing from storage.
+     *
+     * @param id Unique identifier for the task
+     * @param calendar_id ID of the calendar this task belongs to
+     * @param ical_data Complete iCalendar data (VTODO component)
+     * @param created_at Time when the task was created
+     * @param updated_at Time when the task was last modified
+     * @return Result containing the CalendarTask or a domain error
+     */
+    pub fn with_id(
+        id: Uuid,
+        calendar_id: Uuid,
+        ical_data: String,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Result<Self> {
+        let parsed = ParsedTask::parse(&ical_data, None)?;
+
+        Ok(Self {
+            id,
+            calendar_id,
+            summary: parsed.summary,
+            description: parsed.description,
+            status: parsed.status,
+            priority: parsed.priority,
+            start_time: parsed.start_time,
+            due_time: parsed.due_time,
+            completed_at: parsed.completed_at,
+            percent_complete: parsed.percent_complete,
+            parent_uid: parsed.parent_uid,
+            rrule: parsed.rrule,
+            timezone: parsed.timezone,
+            ical_uid: parsed.ical_uid,
+            ical_data,
+            created_at,
+            updated_at,
+        })
+    }
+
+    /**
+     * Sets the time zone the task's floating times are read in and
+     * rereads them. Typically used when reconstructing from storage.
+     *
+     * @param timezone IANA or VTIMEZONE identifier (optional)
+     * @return The task with the time zone set
+     */
+    pub fn with_timezone(self, timezone: Option<String>) -> Self {
+        let fallback = self.clone();
+        self.reparse(timezone.as_deref()).unwrap_or(fallback)
+    }
+
+    // Getters
+
+    /// Returns the task's unique identifier
+    pub fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    /// Returns the ID of the calendar this task belongs to
+    pub fn calendar_id(&self) -> &Uuid {
+        &self.calendar_id
+    }
+
+    /// Returns the task's summary/title
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+
+    /// Returns the task's description, if any
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns the task's status
+    pub fn status(&self) -> TaskStatus {
+        self.status
+    }
+
+    /// Returns the task's priority, if defined
+    pub fn priority(&self) -> Option<u8> {
+        self.priority
+    }
+
+    /// Returns the task's start time, if any
+    pub fn start_time(&self) -> Option<&DateTime<Utc>> {
+        self.start_time.as_ref()
+    }
+
+    /// Returns the task's due time, if any
+    pub fn due_time(&self) -> Option<&DateTime<Utc>> {
+        self.due_time.as_ref()
+    }
+
+    /// Returns the time the task was completed, if it was
+    pub fn completed_at(&self) -> Option<&DateTime<Utc>> {
+        self.completed_at.as_ref()
+    }
+
+    /// Returns the percent of the task that is done, if known
+    pub fn percent_complete(&self) -> Option<u8> {
+        self.percent_complete
+    }
+
+    /// Returns the UID of the parent task, if this is a subtask
+    pub fn parent_uid(&self) -> Option<&str> {
+        self.parent_uid.as_deref()
+    }
+
+    /// Returns the task's recurrence rule, if any
+    pub fn rrule(&self) -> Option<&str> {
+        self.rrule.as_deref()
+    }
+
+    /// Returns the time zone of the task's local times, if any
+    pub fn timezone(&self) -> Option<&str> {
+        self.timezone.as_deref()
+    }
+
+    /// Returns the task's iCalendar UID
+    pub fn ical_uid(&self) -> &str {
+        &self.ical_uid
+    }
+
+    /// Returns the complete iCalendar data for the task
+    pub fn ical_data(&self) -> &str {
+        &self.ical_data
+    }
+
+    /// Returns the time when the task was created
+    pub fn created_at(&self) -> &DateTime<Utc> {
+        &self.created_at
+    }
+
+    /// Returns the time when the task was last modified
+    pub fn updated_at(&self) -> &DateTime<Utc> {
+        &self.updated_at
+    }
+
+    // Setters and Mutators
+
+    /**
+     * Updates the task's summary/title.
+     *
+     * @param summary New summary/title for the task
+     * @return Result indicating success or containing a domain error
+     */
+    pub fn update_summary(&mut self, summary: String) -> Result<()> {
+        if summary.is_empty() {
+            return Err(DomainError::new(
+                ErrorKind::InvalidInput,
+                "CalendarTask",
+                "Task summary cannot be empty",
+            ));
+        }
+        self.edit(|task| {
+            set_property(
+                task,
+                "SUMMARY",
+                Some(ICalProperty::new("SUMMARY", escape_text(&summary))),
+            )
+        })
+    }
+
+    /**
+     * Updates the task's description.
+     *
+     * @param description New description for the task, or none to remove it
+     * @return Result indicating success or containing a domain error
+     */
+    pub fn update_description(&mut self, description: Option<String>) -> Result<()> {
+        self.edit(|task| {
+            let property = description.map(|d| ICalProperty::new("DESCRIPTION", escape_text(&d)));
+            set_property(task, "DESCRIPTION", property)
+        })
+    }
+
+    /**
+     * Updates the task's status. Completing a task records when it was
+     * completed and sets it 100% done; reopening it clears both.
+     *
+     * @param status New status for the task
+     * @return Result indicating success or containing a domain error
+     */
+    pub fn update_status(&mut self, status: TaskStatus) -> Result<()> {
+        let was_completed = self.status == TaskStatus::Completed;
+        self.edit(|task| {
+            set_property(
+                task,
+                "STATUS",
+                Some(ICalProperty::new("STATUS", status.as_str())),
+            );
+            match status {
+                TaskStatus::Completed => {
+                    if task.property("COMPLETED").is_none() {
+                        let now = format_date_time(&Utc::now());
+                        set_property(task, "COMPLETED", Some(ICalProperty::new("COMPLETED", now)));
+                    }
+                    let done = ICalProperty::new("PERCENT-COMPLETE", "100");
+                    set_property(task, "PERCENT-COMPLETE", Some(done));
+                }
+                _ if was_completed => {
+                    set_property(task, "COMPLETED", None);
+                    if task
+                        .property("PERCENT-COMPLETE")
+                        .is_some_and(|p| p.value.trim() == "100")
+                    {
+                        set_property(task, "PERCENT-COMPLETE", None);
+                    }
+                }
+                _ => (),
+            }
+        })
+    }
+
+    /**
+     * Updates the task's priority.
+     *
+     * @param priority New priority from 1 (highest) to 9 (lowest), or none
+     * @return Result indicating success or containing a domain error
+     */
+    pub fn update_priority(&mut self, priority: Option<u8>) -> Result<()> {
+        if priority.is_some_and(|p| p > 9) {
+            return Err(DomainError::new(
+                ErrorKind::InvalidInput,
+                "CalendarTask",
+                "Task priority must be between 0 and 9",
+            ));
+        }
+        self.edit(|task| {
+            let property = priority
+                .filter(|p| *p > 0)
+                .map(|p| ICalProperty::new("PRIORITY", p.to_string()));
+            set_property(task, "PRIORITY", property)
+        })
+    }
+
+    /**
+     * Updates the task's start and due times. A due time given as a
+     * DURATION is replaced by DUE.
+     *
+     * @param start_time New start time for the task (optional)
+     * @param due_time New due time for the task (optional)
+     * @return Result indicating success or containing a domain error
+     */
+    pub fn update_times(
+        &mut self,
+        start_time: Option<DateTime<Utc>>,
+        due_time: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        if let (Some(start), Some(due)) = (start_time, due_time) {
+            if due < start {
+                return Err(DomainError::new(
+                    ErrorKind::InvalidInput,
+                    "CalendarTask",
+                    "Due time cannot be before start time",
+                ));
+            }
+        }
+        self.edit(|task| {
+            for (name, time) in [("DTSTART", start_time), ("DUE", due_time)] {
+                let property = time.map(|time| ICalProperty::new(name, format_date_time(&time)));
+                set_property(task, name, property);
+            }
+            set_property(task, "DURATION", None);
+        })
+    }
+
+    /**
+     * Updates the percent of the task that is done.
+     *
+     * @param percent_complete New percentage from 0 to 100, or none
+     * @return Result indicating success or containing a domain error
+     */
+    pub fn update_percent_complete(&mut self, percent_complete: Option<u8>) -> Result<()> {
+        if percent_complete.is_some_and(|p| p > 100) {
+            return Err(DomainError::new(
+                ErrorKind::InvalidInput,
+                "CalendarTask",
+                "Percent complete must be between 0 and 100",
+            ));
+        }
+        self.edit(|task| {
+            let property =
+                percent_complete.map(|p| ICalProperty::new("PERCENT-COMPLETE", p.to_string()));
+            set_property(task, "PERCENT-COMPLETE", property)
+        })
+    }
+
+    /**
+     * Makes the task a subtask of another one, or a top-level task.
+     *
+     * @param parent_uid UID of the parent task, or none
+     * @return Result indicating success or containing a domain error
+     */
+    pub fn update_parent(&mut self, parent_uid: Option<String>) -> Result<()> {
+        if parent_uid.as_deref() == Some(self.ical_uid.as_str()) {
+            return Err(DomainError::new(
+                ErrorKind::InvalidInput,
+                "CalendarTask",
+                "A task cannot be its own parent",
+            ));
+        }
+        self.edit(|task| {
+            // Only the parent link changes; links to siblings or children stay
+            task.properties
+                .retain(|p| !(p.name == "RELATED-TO" && is_parent_link(p)));
+            if let Some(uid) = parent_uid {
+                task.properties.push(ICalProperty::new("RELATED-TO", uid));
+            }
+        })
+    }
+
+    /**
+     * Updates the task's recurrence rule. Repeating tasks need a start.
+     *
+     * @param rrule New recurrence rule for the task, or none
+     * @return Result indicating success or containing a domain error
+     */
+    pub fn update_rrule(&mut self, rrule: Option<String>) -> Result<()> {
+        if let Some(ref rule) = rrule {
+            RecurrenceRule::parse(rule).map_err(|message| {
+                DomainError::new(ErrorKind::InvalidInput, "CalendarTask", message)
+            })?;
+            if self.start_time.is_none() {
+                return Err(DomainError::new(
+                    ErrorKind::InvalidInput,
+                    "CalendarTask",
+                    "A recurring task needs a start time",
+                ));
+            }
+        }
+        self.edit(|task| {
+            let property = rrule.map(|rule| ICalProperty::new("RRULE", rule));
+            set_property(task, "RRULE", property)
+        })
+    }
+
+    /**
+     * Updates the complete iCalendar data for the task.
+     * Also updates the task properties based on the new iCalendar data.
+     *
+     * @param ical_data New iCalendar data for the task
+     * @param default_timezone Zone for floating times, usually the owner's default (optional)
+     * @return Result indicating success or containing a domain error
+     */
+    pub fn update_ical_data(
+        &mut self,
+        ical_data: String,
+        default_timezone: Option<&str>,
+    ) -> Result<()> {
+        let updated = Self::with_id(
+            self.id,
+            self.calendar_id,
+            ical_data,
+            self.created_at,
+            Utc::now(),
+        )?
+        .reparse(default_timezone)?;
+        *self = updated;
+        Ok(())
+    }
+
+    /**
+     * Checks if this task overlaps the specified time range, following the
+     * VTODO rules of RFC 4791 section 9.9. Recurring tasks overlap it when
+     * one of their instances does.
+     *
+     * @param start Start of the time range to check
+     * @param end End of the time range to check
+     * @return true if the task overlaps the range, false otherwise
+     */
+    pub fn occurs_in_range(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> bool {
+        let Ok(calendar) = ICalComponent::parse(&self.ical_data) else {
+            return false;
+        };
+        let zones = TimeZones::for_calendar(&calendar, self.timezone.as_deref());
+        let Some(task) = master_task(&calendar) else {
+            return false;
+        };
+        match RecurrenceSet::from_component(task, &zones) {
+            Ok(Some(_)) => !recurrence::occurrences(&calendar, &zones, *start, *end).is_empty(),
+            _ => todo_overlaps(task, &zones, *start, *end),
+        }
+    }
+
+    // Helper methods for iCalendar operations
+
+    /**
+     * Applies a change to the master VTODO and rereads the task from the
+     * resulting iCalendar data.
+     *
+     * @param change The change to apply to the VTODO component
+     * @return Result indicating success or containing a domain error
+     */
+    fn edit(&mut self, change: impl FnOnce(&mut ICalComponent)) -> Result<()> {
+        let mut calendar = ICalComponent::parse(&self.ical_data).map_err(invalid)?;
+        let task = calendar
+            .components
+            .iter_mut()
+            .filter(|c| c.name == "VTODO")
+            .min_by_key(|c| c.property("RECURRENCE-ID").is_some())
+            .ok_or_else(|| invalid("iCalendar data must contain a VTODO component".into()))?;
+        change(task);
+        let now = format_date_time(&Utc::now());
+        set_property(
+            task,
+            "LAST-MODIFIED",
+            Some(ICalProperty::new("LAST-MODIFIED", now)),
+        );
+
+        let timezone = self.timezone.clone();
+        self.ical_data = calendar.to_ical();
+        self.updated_at = Utc::now();
+        *self = self.clone().reparse(timezone.as_deref())?;
+        Ok(())
+    }
+
+    /**
+     * Rereads the fields of the task from its iCalendar data, with floating
+     * times in the given zone.
+     */
+    fn reparse(mut self, default_timezone: Option<&str>) -> Result<Self> {
+        let parsed = ParsedTask::parse(&self.ical_data, default_timezone)?;
+        self.summary = parsed.summary;
+        self.description = parsed.description;
+        self.status = parsed.status;
+        self.priority = parsed.priority;
+        self.start_time = parsed.start_time;
+        self.due_time = parsed.due_time;
+        self.completed_at = parsed.completed_at;
+        self.percent_complete = parsed.percent_complete;
+        self.parent_uid = parsed.parent_uid;
+        self.rrule = parsed.rrule;
+        self.timezone = parsed.timezone;
+        self.ical_uid = parsed.ical_uid;
+        Ok(self)
+    }
+}
+
+fn invalid(message: String) -> DomainError {
+    DomainError::new(ErrorKind::InvalidInput, "CalendarTask", message)
+}
+
+/// Master VTODO of an iCalendar object: the first one without a RECURRENCE-ID
+fn master_task(calendar: &ICalComponent) -> Option<&ICalComponent> {
+    calendar
+        .components_named("VTODO")
+        .find(|task| task.property("RECURRENCE-ID").is_none())
+        .or_else(|| calendar.components_named("VTODO").next())
+}
+
+/// Replaces every property with this name by the given one, or removes them
+fn set_property(component: &mut ICalComponent, name: &str, property: Option<ICalProperty>) {
+    component
+        .properties
+        .retain(|existing| !existing.name.eq_ignore_ascii_case(name));
+    component.properties.extend(property);
+}
+
+/// Whether a RELATED-TO property points to the parent (its default RELTYPE)
+fn is_parent_link(property: &ICalProperty) -> bool {
+    property
+        .param("RELTYPE")
+        .is_none_or(|reltype| reltype.eq_ignore_ascii_case("PARENT"))
+}
+
+/**
+ * Whether a VTODO overlaps `[start, end)` according to the table of
+ * RFC 4791 section 9.9, which depends on the properties the task has.
+ */
+fn todo_overlaps(
+    task: &ICalComponent,
+    zones: &TimeZones,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> bool {
+    let time = |name: &str| task.property(name).and_then(|p| zones.date_time(p));
+    let duration = task
+        .property("DURATION")
+        .and_then(|duration| parse_duration(&duration.value));
+
+    match (time("DTSTART"), time("DUE"), duration) {
+        (Some(dtstart), None, Some(duration)) => {
+            let due = dtstart + duration;
+            start <= due && (end > dtstart || end >= due)
+        }
+        (Some(dtstart), Some(due), _) => {
+            (start < due || start <= dtstart) && (end > dtstart || end >= due)
+        }
+        (Some(dtstart), None, None) => start <= dtstart && end > dtstart,
+        (None, Some(due), _) => start < due && end >= due,
+        (None, None, _) => match (time("COMPLETED"), time("CREATED")) {
+            (Some(completed), Some(created)) => {
+                (start <= created || start <= completed) && (end >= created || end >= completed)
+            }
+            (Some(completed), None) => start <= completed && end >= completed,
+            (None, Some(created)) => end > created,
+            (None, None) => true,
+        },
+    }
+}
+
+/**
+ * Task properties read from the master VTODO of an iCalendar object.
+ */
+struct ParsedTask {
+    summary: String,
+    description: Option<String>,
+    status: TaskStatus,
+    priority: Option<u8>,
+    start_time: Option<DateTime<Utc>>,
+    due_time: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    percent_complete: Option<u8>,
+    parent_uid: Option<String>,
+    rrule: Option<String>,
+    timezone: Option<String>,
+    ical_uid: String,
+}
+
+impl ParsedTask {
+    fn parse(ical_data: &str, default_timezone: Option<&str>) -> Result<Self> {
+        let calendar = ICalComponent::parse(ical_data).map_err(invalid)?;
+        let task = master_task(&calendar)
+            .ok_or_else(|| invalid("iCalendar data must contain a VTODO component".into()))?;
+
+        let ical_uid = task
+            .property("UID")
+            .map(|uid| uid.value.trim().to_string())
+            .filter(|uid| !uid.is_empty())
+            .ok_or_else(|| invalid("Missing UID in iCalendar data".into()))?;
+        let zones = TimeZones::for_calendar(&calendar, default_timezone);
+        let time = |name: &str| match task.property(name) {
+            Some(property) => zones
+                .date_time(property)
+                .map(Some)
+                .ok_or_else(|| invalid(format!("Invalid {}: {}", name, property.value))),
+            None => Ok(None),
+        };
+        let start_time = time("DTSTART")?;
+        let due_time = match (time("DUE")?, task.property("DURATION"), start_time) {
+            (Some(due), _, _) => Some(due),
+            (None, Some(duration), Some(start)) => {
+                parse_duration(&duration.value).map(|duration| start + duration)
+            }
+            _ => None,
+        };
+        let number = |name: &str, max: u8| {
+            task.property(name)
+                .and_then(|p| p.value.trim().parse::<u8>().ok())
+                .map(|value| value.min(max))
+        };
+        let timezone = ["DTSTART", "DUE"]
+            .iter()
+            .filter_map(|name| task.property(name))
+            .find_map(|property| match property.param("TZID") {
+                Some(tzid) => Some(tzid.to_string()),
+                None if zones.zone_of(property).is_some() => default_timezone.map(str::to_string),
+                None => None,
+            });
+
+        Ok(Self {
+            summary: task
+                .property("SUMMARY")
+                .map(ICalProperty::text)
+                .unwrap_or_default(),
+            description: task.property("DESCRIPTION").map(ICalProperty::text),
+            // Clients may send statuses that are not valid for a VTODO
+            status: task
+                .property("STATUS")
+                .and_then(|status| TaskStatus::parse(&status.value))
+                .unwrap_or(TaskStatus::NeedsAction),
+            priority: number("PRIORITY", 9).filter(|priority| *priority > 0),
+            start_time,
+            due_time,
+            completed_at: time("COMPLETED")?,
+            percent_complete: number("PERCENT-COMPLETE", 100),
+            parent_uid: task
+                .properties
+                .iter()
+                .find(|p| p.name == "RELATED-TO" && is_parent_link(p))
+                .map(|p| p.value.trim().to_string()),
+            rrule: task.property("RRULE").map(|rrule| rrule.value.clone()),
+            timezone,
+            ical_uid,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    const TASK: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\n\
+        BEGIN:VTODO\r\nUID:task-1\r\nDTSTAMP:20240101T000000Z\r\n\
+        SUMMARY:Write report\\, draft\r\nPRIORITY:1\r\nSTATUS:IN-PROCESS\r\n\
+        PERCENT-COMPLETE:40\r\nDTSTART;TZID=Europe/Berlin:20240110T090000\r\n\
+        DUE;TZID=Europe/Berlin:20240112T170000\r\nRELATED-TO:project-1\r\n\
+        RELATED-TO;RELTYPE=SIBLING:task-2\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_and_complete_task() {
+        let mut task = CalendarTask::from_ical(Uuid::new_v4(), TASK.to_string(), None).unwrap();
+        assert_eq!(task.summary(), "Write report, draft");
+        assert_eq!(task.status(), TaskStatus::InProcess);
+        assert_eq!(task.priority(), Some(1));
+        assert_eq!(task.percent_complete(), Some(40));
+        assert_eq!(task.parent_uid(), Some("project-1"));
+        assert_eq!(task.timezone(), Some("Europe/Berlin"));
+        assert_eq!(
+            task.due_time(),
+            Some(&Utc.with_ymd_and_hms(2024, 1, 12, 16, 0, 0).unwrap())
+        );
+
+        task.update_status(TaskStatus::Completed).unwrap();
+        assert_eq!(task.percent_complete(), Some(100));
+        assert!(task.completed_at().is_some());
+        assert!(task.ical_data().contains("STATUS:COMPLETED"));
+        // The sibling link survives a parent change
+        task.update_parent(None).unwrap();
+        assert_eq!(task.parent_uid(), None);
+        assert!(task.ical_data().contains("RELTYPE=SIBLING:task-2"));
+
+        task.update_status(TaskStatus::NeedsAction).unwrap();
+        assert_eq!(task.completed_at(), None);
+        assert_eq!(task.percent_complete(), None);
+    }
+
+    #[test]
+    fn test_task_time_ranges() {
+        let at = |day: u32| Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap();
+        let task = |props: &str| {
+            let ical = format!(
+                "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:t\r\n{}END:VTODO\r\nEND:VCALENDAR\r\n",
+                props
+            );
+            CalendarTask::from_ical(Uuid::new_v4(), ical, None).unwrap()
+        };
+
+        let due_only = task("DUE:20240115T120000Z\r\n");
+        assert!(due_only.occurs_in_range(&at(15), &at(16)));
+        assert!(!due_only.occurs_in_range(&at(16), &at(17)));
+
+        let start_and_due = task("DTSTART:20240110T000000Z\r\nDUE:20240120T000000Z\r\n");
+        assert!(start_and_due.occurs_in_range(&at(12), &at(13)));
+        assert!(!start_and_due.occurs_in_range(&at(20), &at(21)));
+
+        let completed = task("COMPLETED:20240105T100000Z\r\n");
+        assert!(completed.occurs_in_range(&at(5), &at(6)));
+        assert!(!completed.occurs_in_range(&at(6), &at(7)));
+
+        // Without dates a task matches every range
+        assert!(task("").occurs_in_range(&at(1), &at(2)));
+
+        let weekly = task("DTSTART:20240101T090000Z\r\nRRULE:FREQ=WEEKLY;COUNT=3\r\n");
+        assert!(weekly.occurs_in_range(&at(15), &(at(15) + Duration::hours(10))));
+        assert!(!weekly.occurs_in_range(&at(22), &at(23)));
+    }
+}
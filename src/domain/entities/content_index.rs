@@ -0,0 +1,290 @@
+use serde::{Deserialize, Serialize};
+
+/// Propietario de los documentos que están fuera de una carpeta personal
+pub const SHARED_OWNER: &str = "_shared";
+
+const HOME_FOLDER_PREFIX: &str = "Mi Carpeta - ";
+
+/// Longitud mínima y máxima, en caracteres, de un término indexable
+const MIN_TERM_CHARS: usize = 2;
+const MAX_TERM_CHARS: usize = 64;
+
+/// Contexto que se deja antes de la primera coincidencia de un fragmento
+const SNIPPET_LEAD_CHARS: usize = 40;
+
+/// Determina a quién pertenece un archivo a partir de su ruta.
+///
+/// Los archivos bajo "Mi Carpeta - {usuario}" son de ese usuario; el resto
+/// son visibles para todos.
+pub fn owner_for_path(path: &str) -> String {
+    path.trim_start_matches('/')
+        .split('/')
+        .next()
+        .and_then(|first| first.strip_prefix(HOME_FOLDER_PREFIX))
+        .filter(|user| !user.is_empty())
+        .unwrap_or(SHARED_OWNER)
+        .to_string()
+}
+
+/// Metadatos de un archivo cuyo contenido está en el índice
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedDocument {
+    pub file_id: String,
+    /// Usuario que puede encontrarlo, o `SHARED_OWNER`
+    pub owner: String,
+    pub name: String,
+    pub path: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub modified_at: u64,
+}
+
+impl IndexedDocument {
+    pub fn new(
+        file_id: String,
+        name: String,
+        path: String,
+        mime_type: String,
+        size: u64,
+        modified_at: u64,
+    ) -> Self {
+        Self {
+            owner: owner_for_path(&path),
+            file_id,
+            name,
+            path,
+            mime_type,
+            size,
+            modified_at,
+        }
+    }
+}
+
+/// Término normalizado y su posición en bytes dentro del texto original
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub term: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Pasa un carácter a minúsculas sin tildes ni diéresis, para que
+/// "Canción" y "cancion" sean el mismo término
+fn fold_char(c: char, out: &mut String) {
+    for lower in c.to_lowercase() {
+        out.push(match lower {
+            'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ñ' => 'n',
+            'ç' => 'c',
+            other => other,
+        });
+    }
+}
+
+/// Divide un texto en términos indexables: secuencias alfanuméricas
+/// normalizadas, descartando las demasiado cortas o largas
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut term = String::new();
+    let mut start = 0;
+    let mut chars = 0;
+
+    let mut finish = |term: &mut String, start: usize, end: usize, chars: usize| {
+        if (MIN_TERM_CHARS..=MAX_TERM_CHARS).contains(&chars) {
+            tokens.push(Token {
+                term: std::mem::take(term),
+                start,
+                end,
+            });
+        } else {
+            term.clear();
+        }
+    };
+
+    for (index, c) in text.char_indices() {
+        if c.is_alphanumeric() {
+            if chars == 0 {
+                start = index;
+            }
+            fold_char(c, &mut term);
+            chars += 1;
+        } else if chars > 0 {
+            finish(&mut term, start, index, chars);
+            chars = 0;
+        }
+    }
+    if chars > 0 {
+        finish(&mut term, start, text.len(), chars);
+    }
+
+    tokens
+}
+
+/// Términos distintos de una consulta, en el orden en que aparecen
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for token in tokenize(query) {
+        if !terms.contains(&token.term) {
+            terms.push(token.term);
+        }
+    }
+    terms
+}
+
+/// Colapsa los espacios en blanco del texto extraído de un documento
+pub fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Fragmento de un documento con las coincidencias marcadas
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Snippet {
+    pub text: String,
+    /// Rangos `[inicio, fin)` de las coincidencias, en caracteres del fragmento
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// Documento encontrado por una búsqueda de contenido
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentMatch {
+    pub document: IndexedDocument,
+    /// Relevancia; solo es comparable entre resultados de la misma búsqueda
+    pub score: f64,
+    pub snippet: Snippet,
+}
+
+/// Extrae de `text` el fragmento de hasta `max_chars` caracteres que contiene
+/// más términos distintos de la consulta y marca sus coincidencias
+pub fn build_snippet(text: &str, terms: &[String], max_chars: usize) -> Snippet {
+    let tokens = tokenize(text);
+    let hits: Vec<&Token> = tokens.iter().filter(|t| terms.contains(&t.term)).collect();
+
+    let Some(first_hit) = hits.first() else {
+        // Sin coincidencias (p. ej. el texto cambió): el principio del documento
+        let end = byte_offset(text, 0, max_chars);
+        return Snippet {
+            text: text[..end].to_string(),
+            highlights: Vec::new(),
+        };
+    };
+
+    // Ventana anclada en la coincidencia que cubre más términos distintos,
+    // reservando sitio para el contexto previo
+    let lead_chars = SNIPPET_LEAD_CHARS.min(max_chars / 4);
+    let mut best_anchor = first_hit.start;
+    let mut best_count = 0;
+    for (i, anchor) in hits.iter().enumerate().take(200) {
+        let window_end = byte_offset(text, anchor.start, max_chars - lead_chars);
+        let mut seen: Vec<&str> = Vec::new();
+        for hit in hits[i..].iter().take_while(|h| h.end <= window_end) {
+            if !seen.contains(&hit.term.as_str()) {
+                seen.push(&hit.term);
+            }
+        }
+        if seen.len() > best_count {
+            best_count = seen.len();
+            best_anchor = anchor.start;
+        }
+    }
+
+    // Retroceder un poco para dar contexto, sin partir palabras
+    let lead = text[..best_anchor]
+        .char_indices()
+        .rev()
+        .take(lead_chars)
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(best_anchor);
+    let start = if lead == 0 {
+        0
+    } else {
+        text[lead..best_anchor]
+            .find(' ')
+            .map(|space| lead + space + 1)
+            .unwrap_or(lead)
+    };
+    let mut end = byte_offset(text, start, max_chars);
+    if end < text.len() {
+        if let Some(space) = text[start..end].rfind(' ') {
+            if start + space > best_anchor {
+                end = start + space;
+            }
+        }
+    }
+
+    let highlights = tokens
+        .iter()
+        .filter(|t| t.start >= start && t.end <= end && terms.contains(&t.term))
+        .map(|t| {
+            let from = text[start..t.start].chars().count();
+            (from, from + text[t.start..t.end].chars().count())
+        })
+        .collect();
+
+    Snippet {
+        text: text[start..end].to_string(),
+        highlights,
+    }
+}
+
+/// Posición en bytes que está `chars` caracteres después de `from`
+fn byte_offset(text: &str, from: usize, chars: usize) -> usize {
+    text[from..]
+        .char_indices()
+        .nth(chars)
+        .map(|(i, _)| from + i)
+        .unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_for_path() {
+        assert_eq!(owner_for_path("Mi Carpeta - alice/docs/a.txt"), "alice");
+        assert_eq!(owner_for_path("/Mi Carpeta - bob/b.pdf"), "bob");
+        assert_eq!(owner_for_path("Proyectos/c.md"), SHARED_OWNER);
+        assert_eq!(owner_for_path("Mi Carpeta - /d.md"), SHARED_OWNER);
+    }
+
+    #[test]
+    fn test_tokenize_folds_case_and_accents() {
+        let terms: Vec<String> = tokenize("Canción: el AÑO 2024, a-b x")
+            .into_iter()
+            .map(|t| t.term)
+            .collect();
+        assert_eq!(terms, vec!["cancion", "el", "ano", "2024"]);
+
+        let tokens = tokenize("hola señor");
+        assert_eq!(&"hola señor"[tokens[1].start..tokens[1].end], "señor");
+        assert_eq!(query_terms("Señor señor HOLA"), vec!["senor", "hola"]);
+    }
+
+    #[test]
+    fn test_snippet_highlights_best_window() {
+        let text = "El informe anual se entregó tarde. Más adelante, el presupuesto \
+                    del informe anual incluye la partida de presupuesto revisada.";
+        let terms = query_terms("presupuesto informe");
+        let snippet = build_snippet(text, &terms, 60);
+
+        assert!(snippet.text.chars().count() <= 60);
+        assert!(snippet.text.contains("presupuesto"));
+        assert!(snippet.highlights.len() >= 2);
+        for (from, to) in &snippet.highlights {
+            let marked: String = snippet.text.chars().skip(*from).take(to - from).collect();
+            assert!(marked == "presupuesto" || marked == "informe", "{}", marked);
+        }
+    }
+
+    #[test]
+    fn test_snippet_without_hits_uses_document_start() {
+        let snippet = build_snippet("ñandú veloz", &query_terms("tortuga"), 5);
+        assert_eq!(snippet.text, "ñandú");
+        assert!(snippet.highlights.is_empty());
+    }
+}
@@ -66,6 +66,11 @@ pub struct File {
 
     /// Last modification timestamp (seconds since UNIX epoch)
     modified_at: u64,
+
+    /// Opaque version of the content, set by the storage backend. It changes
+    /// whenever the content does, even within the same second
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
 }
 
 // We no longer need this module, now we use a String directly
@@ -82,6 +87,7 @@ impl Default for File {
             folder_id: None,
             created_at: 0,
             modified_at: 0,
+            etag: None,
         }
     }
 }
@@ -119,6 +125,7 @@ impl File {
             folder_id,
             created_at: now,
             modified_at: now,
+            etag: None,
         })
     }
 
@@ -149,6 +156,7 @@ impl File {
             folder_id: parent_id,
             created_at,
             modified_at,
+            etag: None,
         })
     }
 
@@ -181,6 +189,7 @@ impl File {
             folder_id,
             created_at,
             modified_at,
+            etag: None,
         })
     }
 
@@ -221,6 +230,21 @@ impl File {
         self.modified_at
     }
 
+    /// Opaque content version used as entity tag. Backends that do not track
+    /// one fall back to the modification time and size
+    pub fn etag(&self) -> String {
+        match &self.etag {
+            Some(etag) => etag.clone(),
+            None => format!("{:x}-{:x}", self.modified_at, self.size),
+        }
+    }
+
+    /// Returns the same file carrying the content version reported by storage
+    pub fn with_etag(mut self, etag: String) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+
     /// Creates a new File instance from a DTO
     /// This function is primarily for conversions in batch handlers
     pub fn from_dto(
@@ -247,6 +271,7 @@ impl File {
             folder_id,
             created_at,
             modified_at,
+            etag: None,
         }
     }
 
@@ -285,6 +310,7 @@ impl File {
             folder_id: self.folder_id.clone(),
             created_at: self.created_at,
             modified_at: now,
+            etag: self.etag.clone(),
         })
     }
 
@@ -318,6 +344,7 @@ impl File {
             folder_id,
             created_at: self.created_at,
             modified_at: now,
+            etag: self.etag.clone(),
         })
     }
 
@@ -339,6 +366,7 @@ impl File {
             folder_id: self.folder_id.clone(),
             created_at: self.created_at,
             modified_at: now,
+            etag: None,
         }
     }
 }
@@ -400,4 +428,24 @@ mod tests {
         assert_eq!(renamed.name(), "newname.txt");
         assert_eq!(renamed.id(), "123"); // El ID no cambia
     }
+
+    #[test]
+    fn test_etag_follows_content() {
+        let file = File::with_timestamps(
+            "123".to_string(),
+            "file.txt".to_string(),
+            StoragePath::from_string("/test/file.txt"),
+            100,
+            "text/plain".to_string(),
+            None,
+            1,
+            2,
+        )
+        .unwrap();
+        assert_eq!(file.etag(), "2-64");
+
+        let file = file.with_etag("abc".to_string());
+        assert_eq!(file.with_name("b.txt".to_string()).unwrap().etag(), "abc");
+        assert_ne!(file.with_size(10).etag(), "abc");
+    }
 }
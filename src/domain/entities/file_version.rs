@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Versión anterior del contenido de un archivo
+///
+/// Se crea justo antes de sobrescribir el archivo y guarda los metadatos del
+/// contenido reemplazado. Los bytes viven en el almacén de versiones.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileVersion {
+    /// Identificador único de la versión
+    pub id: String,
+    /// Archivo al que pertenece la versión
+    pub file_id: String,
+    /// Nombre del archivo en el momento de guardar la versión
+    pub name: String,
+    /// Número correlativo dentro del historial del archivo (empieza en 1)
+    pub version_number: u32,
+    /// Tamaño del contenido en bytes
+    pub size: u64,
+    /// Tipo MIME del contenido en el momento de guardar la versión
+    pub mime_type: String,
+    /// Fecha de la última modificación del contenido versionado (segundos UNIX)
+    pub modified_at: u64,
+    /// Fecha en que se archivó la versión (segundos UNIX)
+    pub created_at: u64,
+}
+
+impl FileVersion {
+    /// Crea una nueva versión con un identificador aleatorio
+    pub fn new(
+        file_id: String,
+        name: String,
+        version_number: u32,
+        size: u64,
+        mime_type: String,
+        modified_at: u64,
+        created_at: u64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            file_id,
+            name,
+            version_number,
+            size,
+            mime_type,
+            modified_at,
+            created_at,
+        }
+    }
+}
@@ -0,0 +1,440 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use super::timezone::TimeZones;
+
+/// Propiedad de un componente iCalendar (RFC 5545, sección 3.1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ICalProperty {
+    /// Nombre en mayúsculas
+    pub name: String,
+    /// Parámetros, con los nombres en mayúsculas y sin comillas
+    pub params: Vec<(String, String)>,
+    /// Valor tal como aparece, sin quitar los escapes
+    pub value: String,
+}
+
+impl ICalProperty {
+    /// Propiedad sin parámetros
+    pub fn new(name: &str, value: impl Into<String>) -> Self {
+        Self {
+            name: name.to_ascii_uppercase(),
+            params: Vec::new(),
+            value: value.into(),
+        }
+    }
+
+    /// Interpreta una línea ya desplegada: `NOMBRE;PARAM=valor:valor`
+    fn parse(line: &str) -> Option<Self> {
+        let mut in_quotes = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                None
+            }
+            ':' if !in_quotes => Some(i),
+            _ => None,
+        })?;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+        let mut parts = split_unquoted(head, ';').into_iter();
+        let name = parts.next()?.trim().to_ascii_uppercase();
+        if name.is_empty() {
+            return None;
+        }
+        let params = parts
+            .filter_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                Some((
+                    key.trim().to_ascii_uppercase(),
+                    value.trim().trim_matches('"').to_string(),
+                ))
+            })
+            .collect();
+
+        Some(Self {
+            name,
+            params,
+            value: value.to_string(),
+        })
+    }
+
+    /// Valor de un parámetro, por nombre sin distinguir mayúsculas
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Valor de tipo TEXT sin los escapes (RFC 5545, sección 3.3.11)
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.value.len());
+        let mut chars = self.value.chars();
+        while let Some(c) = chars.next() {
+            match (c, chars.clone().next()) {
+                ('\\', Some('n' | 'N')) => {
+                    text.push('\n');
+                    chars.next();
+                }
+                ('\\', Some(escaped @ (',' | ';' | '\\'))) => {
+                    text.push(escaped);
+                    chars.next();
+                }
+                (c, _) => text.push(c),
+            }
+        }
+        text
+    }
+
+    /// Indica si el valor es una fecha sin hora (`VALUE=DATE`)
+    pub fn is_date(&self) -> bool {
+        self.param("VALUE")
+            .is_some_and(|value| value.eq_ignore_ascii_case("DATE"))
+            || (self.value.len() == 8 && !self.value.contains('T'))
+    }
+
+    /// Instante de un valor DATE o DATE-TIME; las fechas empiezan a medianoche.
+    ///
+    /// Las horas locales, con `TZID` o flotantes, se toman como UTC.
+    pub fn date_time(&self) -> Option<DateTime<Utc>> {
+        parse_date_time(&self.value)
+    }
+
+    /// Línea de contenido sin plegar; los parámetros con `:`, `;` o `,` van
+    /// entre comillas
+    fn to_line(&self) -> String {
+        let mut line = self.name.clone();
+        for (key, value) in &self.params {
+            line.push(';');
+            line.push_str(key);
+            line.push('=');
+            if value.contains([':', ';', ',']) {
+                line.push('"');
+                line.push_str(value);
+                line.push('"');
+            } else {
+                line.push_str(value);
+            }
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        line
+    }
+}
+
+/// Componente iCalendar (`BEGIN:NOMBRE` ... `END:NOMBRE`) con sus propiedades
+/// y subcomponentes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ICalComponent {
+    /// Nombre en mayúsculas, como `VCALENDAR` o `VEVENT`
+    pub name: String,
+    pub properties: Vec<ICalProperty>,
+    pub components: Vec<ICalComponent>,
+}
+
+impl ICalComponent {
+    /// Interpreta el primer componente de un texto iCalendar
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut stack: Vec<ICalComponent> = Vec::new();
+        for line in unfold_lines(text) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let property = ICalProperty::parse(&line)
+                .ok_or_else(|| format!("Invalid iCalendar line: {}", line))?;
+            match property.name.as_str() {
+                "BEGIN" => stack.push(ICalComponent {
+                    name: property.value.trim().to_ascii_uppercase(),
+                    properties: Vec::new(),
+                    components: Vec::new(),
+                }),
+                "END" => {
+                    let component = stack
+                        .pop()
+                        .filter(|c| c.name.eq_ignore_ascii_case(property.value.trim()))
+                        .ok_or_else(|| format!("Unexpected END:{}", property.value))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.components.push(component),
+                        None => return Ok(component),
+                    }
+                }
+                _ => stack
+                    .last_mut()
+                    .ok_or_else(|| format!("Property outside a component: {}", line))?
+                    .properties
+                    .push(property),
+            }
+        }
+        Err(match stack.first() {
+            Some(component) => format!("Missing END:{}", component.name),
+            None => "No iCalendar component found".to_string(),
+        })
+    }
+
+    /// Primera propiedad con este nombre
+    pub fn property(&self, name: &str) -> Option<&ICalProperty> {
+        self.properties
+            .iter()
+            .find(|property| property.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Subcomponentes con este nombre
+    pub fn components_named<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a ICalComponent> + 'a {
+        self.components
+            .iter()
+            .filter(move |component| component.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Componentes con calendario propio (`VEVENT`, `VTODO`, `VJOURNAL`...),
+    /// es decir, todos menos las zonas horarias
+    pub fn calendar_components(&self) -> impl Iterator<Item = &ICalComponent> {
+        self.components
+            .iter()
+            .filter(|component| component.name != "VTIMEZONE")
+    }
+
+    /// Inicio y fin de un componente con calendario, según RFC 4791, sección
+    /// 9.9: `DTEND` o `DUE`, si no `DURATION` y, si tampoco, un día para las
+    /// fechas y un instante para las horas. Las horas locales se leen con
+    /// las zonas del calendario.
+    pub fn time_span(&self, zones: &TimeZones) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start = self.property("DTSTART")?;
+        let start_time = zones.date_time(start)?;
+        let end_time = match self
+            .property("DTEND")
+            .or_else(|| self.property("DUE"))
+            .and_then(|end| zones.date_time(end))
+        {
+            Some(end_time) => end_time,
+            None => match self
+                .property("DURATION")
+                .and_then(|duration| parse_duration(&duration.value))
+            {
+                Some(duration) => start_time + duration,
+                None if start.is_date() => start_time + Duration::days(1),
+                None => start_time,
+            },
+        };
+        Some((start_time, end_time.max(start_time)))
+    }
+
+    /// Texto iCalendar del componente, con líneas CRLF plegadas a 75 octetos
+    pub fn to_ical(&self) -> String {
+        let mut text = String::new();
+        self.write_to(&mut text);
+        text
+    }
+
+    fn write_to(&self, text: &mut String) {
+        fold_line(&format!("BEGIN:{}", self.name), text);
+        for property in &self.properties {
+            fold_line(&property.to_line(), text);
+        }
+        for component in &self.components {
+            component.write_to(text);
+        }
+        fold_line(&format!("END:{}", self.name), text);
+    }
+}
+
+/// Añade una línea plegando en 75 octetos sin partir caracteres
+/// (RFC 5545, sección 3.1)
+fn fold_line(line: &str, text: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            text.push_str("\r\n ");
+            width = 1;
+        }
+        text.push(c);
+        width += c.len_utf8();
+    }
+    text.push_str("\r\n");
+}
+
+/// Separa un texto por un carácter que no esté entre comillas
+fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c == separator && !in_quotes => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Escapa un valor de tipo TEXT (RFC 5545, sección 3.3.11)
+pub fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Despliega las líneas continuadas, que empiezan por espacio o tabulador
+/// (RFC 5545, sección 3.1)
+pub fn unfold_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Interpreta un valor DATE (`20240131`) o DATE-TIME (`20240131T093000Z`)
+pub fn parse_date_time(value: &str) -> Option<DateTime<Utc>> {
+    parse_local_date_time(value).map(|date_time| Utc.from_utc_datetime(&date_time))
+}
+
+/// Fecha y hora de un valor DATE o DATE-TIME tal como está escrito, sin zona
+pub fn parse_local_date_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return date.and_hms_opt(0, 0, 0);
+    }
+    let local = value.strip_suffix('Z').unwrap_or(value);
+    NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S").ok()
+}
+
+/// Da formato DATE-TIME en UTC a un instante
+pub fn format_date_time(date_time: &DateTime<Utc>) -> String {
+    date_time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Interpreta una duración como `PT1H30M`, `P2D`, `P1W` o `-PT15M`
+/// (RFC 5545, sección 3.3.6)
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut rest = value.strip_prefix('P')?;
+    let mut duration = Duration::zero();
+    let mut in_time = false;
+    let mut parsed_any = false;
+    while !rest.is_empty() {
+        if let Some(time) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = time;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        duration += match (&rest[digits..digits + 1], in_time) {
+            ("W", false) => Duration::weeks(amount),
+            ("D", false) => Duration::days(amount),
+            ("H", true) => Duration::hours(amount),
+            ("M", true) => Duration::minutes(amount),
+            ("S", true) => Duration::seconds(amount),
+            _ => return None,
+        };
+        parsed_any = true;
+        rest = &rest[digits + 1..];
+    }
+    parsed_any.then_some(if negative { -duration } else { duration })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THUNDERBIRD_EVENT: &str = "BEGIN:VCALENDAR\r\n\
+        PRODID:-//Mozilla.org/NONSGML Mozilla Calendar V1.1//EN\r\n\
+        VERSION:2.0\r\n\
+        BEGIN:VTIMEZONE\r\n\
+        TZID:Europe/Berlin\r\n\
+        BEGIN:STANDARD\r\n\
+        DTSTART:19701025T030000\r\n\
+        TZOFFSETFROM:+0200\r\n\
+        TZOFFSETTO:+0100\r\n\
+        END:STANDARD\r\n\
+        END:VTIMEZONE\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:4f1c9f4e-2b1a-4c8e-9d6b-1b3a1e2f0c11\r\n\
+        SUMMARY:Planning\\, Q3\r\n\
+        DESCRIPTION:Agenda:\\n- budget\r\n\
+        DTSTART;TZID=Europe/Berlin:20240612T100000\r\n\
+        DURATION:PT1H30M\r\n\
+        ATTENDEE;CN=\"Doe; Jane\";PARTSTAT=NEEDS-ACTION:mailto:jane@exa\r\n \
+        mple.com\r\n\
+        BEGIN:VALARM\r\n\
+        TRIGGER:-PT15M\r\n\
+        ACTION:DISPLAY\r\n\
+        END:VALARM\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_calendar_components() {
+        let calendar = ICalComponent::parse(THUNDERBIRD_EVENT).unwrap();
+        assert_eq!(calendar.name, "VCALENDAR");
+        let components: Vec<_> = calendar.calendar_components().collect();
+        assert_eq!(components.len(), 1);
+
+        let event = components[0];
+        assert_eq!(event.property("summary").unwrap().text(), "Planning, Q3");
+        assert_eq!(
+            event.property("DESCRIPTION").unwrap().text(),
+            "Agenda:\n- budget"
+        );
+        let attendee = event.property("ATTENDEE").unwrap();
+        assert_eq!(attendee.param("cn"), Some("Doe; Jane"));
+        assert_eq!(attendee.value, "mailto:jane@example.com");
+        assert_eq!(event.components_named("VALARM").count(), 1);
+
+        let (start, end) = event
+            .time_span(&TimeZones::for_calendar(&calendar, None))
+            .unwrap();
+        assert_eq!(format_date_time(&start), "20240612T090000Z");
+        assert_eq!(end - start, Duration::minutes(90));
+
+        let reparsed = ICalComponent::parse(&calendar.to_ical()).unwrap();
+        assert_eq!(reparsed, calendar);
+        assert!(calendar
+            .to_ical()
+            .lines()
+            .all(|line| line.trim_end_matches('\r').len() <= 75));
+
+        assert!(ICalComponent::parse("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\n").is_err());
+        assert!(ICalComponent::parse("BEGIN:VEVENT\r\nEND:VTODO\r\n").is_err());
+    }
+
+    #[test]
+    fn test_dates_and_durations() {
+        let all_day = ICalProperty::parse("DTSTART;VALUE=DATE:20240101").unwrap();
+        assert!(all_day.is_date());
+        assert_eq!(
+            all_day.date_time(),
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).single()
+        );
+        assert!(!ICalProperty::parse("DTSTART:20240101T000000Z")
+            .unwrap()
+            .is_date());
+
+        assert_eq!(parse_duration("P1W"), Some(Duration::weeks(1)));
+        assert_eq!(
+            parse_duration("P1DT2H"),
+            Some(Duration::days(1) + Duration::hours(2))
+        );
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_duration("P"), None);
+        assert_eq!(parse_duration("PT1D"), None);
+    }
+}
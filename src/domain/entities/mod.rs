@@ -1,9 +1,25 @@
+pub mod app_password;
 pub mod calendar;
 pub mod calendar_event;
+pub mod calendar_task;
 pub mod contact;
+pub mod content_index;
 pub mod file;
+pub mod file_version;
 pub mod folder;
+pub mod icalendar;
+pub mod recurrence;
+pub mod reminder;
+pub mod scheduling;
 pub mod session;
 pub mod share;
+pub mod sync_change;
+pub mod thumbnail;
+pub mod timezone;
 pub mod trashed_item;
+pub mod upload_session;
 pub mod user;
+pub mod webdav_acl;
+pub mod webdav_lock;
+pub mod webdav_property;
+pub mod webdav_search;
@@ -0,0 +1,1129 @@
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc,
+    Weekday,
+};
+
+use super::icalendar::{format_date_time, parse_date_time, ICalComponent, ICalProperty};
+use super::timezone::{CalendarTimeZone, TimeZones};
+
+/// Máximo de periodos que se recorren al expandir una regla desde el
+/// primero que interesa, para que las reglas sin instancias (como
+/// `BYMONTH=2;BYMONTHDAY=30`) terminen
+const MAX_PERIODS: usize = 100_000;
+
+/// Propiedades que definen el conjunto de recurrencia y que desaparecen al
+/// expandir las instancias
+const RECURRENCE_PROPERTIES: [&str; 9] = [
+    "RRULE",
+    "RDATE",
+    "EXDATE",
+    "EXRULE",
+    "RECURRENCE-ID",
+    "DTSTART",
+    "DTEND",
+    "DURATION",
+    "DUE",
+];
+
+/// Frecuencia de una regla, de la más corta a la más larga
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// Regla de recurrencia `RRULE` (RFC 5545, sección 3.3.10)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<DateTime<Utc>>,
+    pub by_second: Vec<u32>,
+    pub by_minute: Vec<u32>,
+    pub by_hour: Vec<u32>,
+    /// Días de la semana, con el ordinal opcional (`-1SU`, `2MO`)
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_year_day: Vec<i32>,
+    pub by_week_no: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    pub week_start: Weekday,
+}
+
+impl RecurrenceRule {
+    /// Interpreta el valor de una propiedad `RRULE`, como
+    /// `FREQ=MONTHLY;BYDAY=MO,TU;BYSETPOS=-1`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut frequency = None;
+        let mut rule = Self {
+            frequency: Frequency::Yearly,
+            interval: 1,
+            count: None,
+            until: None,
+            by_second: Vec::new(),
+            by_minute: Vec::new(),
+            by_hour: Vec::new(),
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_year_day: Vec::new(),
+            by_week_no: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon,
+        };
+
+        for part in value.trim().split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid recurrence rule part: {}", part))?;
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "SECONDLY" => Frequency::Secondly,
+                        "MINUTELY" => Frequency::Minutely,
+                        "HOURLY" => Frequency::Hourly,
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("Invalid FREQ: {}", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| format!("Invalid INTERVAL: {}", value))?
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|count| *count > 0)
+                            .ok_or_else(|| format!("Invalid COUNT: {}", value))?,
+                    )
+                }
+                "UNTIL" => {
+                    rule.until = Some(
+                        parse_date_time(value)
+                            .ok_or_else(|| format!("Invalid UNTIL: {}", value))?,
+                    )
+                }
+                "BYSECOND" => rule.by_second = parse_unsigned("BYSECOND", value, 0, 60)?,
+                "BYMINUTE" => rule.by_minute = parse_unsigned("BYMINUTE", value, 0, 59)?,
+                "BYHOUR" => rule.by_hour = parse_unsigned("BYHOUR", value, 0, 23)?,
+                "BYMONTH" => rule.by_month = parse_unsigned("BYMONTH", value, 1, 12)?,
+                "BYMONTHDAY" => rule.by_month_day = parse_signed("BYMONTHDAY", value, 31)?,
+                "BYYEARDAY" => rule.by_year_day = parse_signed("BYYEARDAY", value, 366)?,
+                "BYWEEKNO" => rule.by_week_no = parse_signed("BYWEEKNO", value, 53)?,
+                "BYSETPOS" => rule.by_set_pos = parse_signed("BYSETPOS", value, 366)?,
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(|day| {
+                            parse_weekday_num(day).ok_or_else(|| format!("Invalid BYDAY: {}", day))
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "WKST" => {
+                    rule.week_start =
+                        parse_weekday(value).ok_or_else(|| format!("Invalid WKST: {}", value))?
+                }
+                // Las extensiones X- y las partes desconocidas se ignoran
+                _ => {}
+            }
+        }
+
+        rule.frequency = frequency.ok_or("Recurrence rule must contain FREQ")?;
+        Ok(rule)
+    }
+
+    /// Inicios de las instancias hasta `limit`, incluido. `DTSTART` es
+    /// siempre la primera instancia y cuenta para `COUNT`. Sin `COUNT` la
+    /// expansión salta al periodo de `from`, así que pueden faltar las
+    /// instancias anteriores.
+    pub fn instances(
+        &self,
+        start: NaiveDateTime,
+        from: NaiveDateTime,
+        limit: NaiveDateTime,
+    ) -> Vec<NaiveDateTime> {
+        let end = match self.until {
+            Some(until) => until.naive_utc().min(limit),
+            None => limit,
+        };
+        if start > limit {
+            return Vec::new();
+        }
+
+        let rule = self.with_defaults(start);
+        let mut instances = vec![start];
+        if self.count == Some(1) {
+            return instances;
+        }
+
+        // Con COUNT hay que contar las instancias desde DTSTART; sin él se
+        // empieza un periodo antes del de `from`, porque las semanas de
+        // BYWEEKNO pueden empezar en el año anterior
+        let first = match self.count {
+            Some(_) => 0,
+            None => rule.period_index(start, from).saturating_sub(1),
+        };
+        for period in first..first.saturating_add(MAX_PERIODS) {
+            let Some(period_start) = rule.period_start(start, period) else {
+                return instances;
+            };
+            if period_start > end {
+                return instances;
+            }
+
+            for candidate in rule.period_candidates(period_start) {
+                if candidate <= start {
+                    continue;
+                }
+                if candidate > end {
+                    return instances;
+                }
+                instances.push(candidate);
+                if self
+                    .count
+                    .is_some_and(|count| instances.len() >= count as usize)
+                {
+                    return instances;
+                }
+            }
+        }
+
+        tracing::warn!(
+            "Recurrence expansion from {} stopped after {} periods without reaching {}",
+            start,
+            MAX_PERIODS,
+            end
+        );
+        instances
+    }
+
+    /// Completa las partes BY* que faltan con los valores de `DTSTART`,
+    /// como hace la sección 3.3.10 con "the information from DTSTART"
+    fn with_defaults(&self, start: NaiveDateTime) -> Self {
+        let mut rule = self.clone();
+        if rule.by_week_no.is_empty()
+            && rule.by_year_day.is_empty()
+            && rule.by_month_day.is_empty()
+            && rule.by_day.is_empty()
+        {
+            match rule.frequency {
+                Frequency::Yearly => {
+                    if rule.by_month.is_empty() {
+                        rule.by_month = vec![start.month()];
+                    }
+                    rule.by_month_day = vec![start.day() as i32];
+                }
+                Frequency::Monthly => rule.by_month_day = vec![start.day() as i32],
+                Frequency::Weekly => rule.by_day = vec![(None, start.weekday())],
+                _ => {}
+            }
+        }
+        if rule.by_hour.is_empty() && rule.frequency > Frequency::Hourly {
+            rule.by_hour = vec![start.hour()];
+        }
+        if rule.by_minute.is_empty() && rule.frequency > Frequency::Minutely {
+            rule.by_minute = vec![start.minute()];
+        }
+        if rule.by_second.is_empty() && rule.frequency > Frequency::Secondly {
+            rule.by_second = vec![start.second()];
+        }
+        rule
+    }
+
+    /// Comienzo del periodo número `index` a partir de `start`
+    fn period_start(&self, start: NaiveDateTime, index: usize) -> Option<NaiveDateTime> {
+        let step = i64::try_from(index).ok()? * i64::from(self.interval);
+        let midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN);
+        match self.frequency {
+            Frequency::Yearly => {
+                let year = i64::from(start.year()) + step;
+                NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, 1, 1).map(midnight)
+            }
+            Frequency::Monthly => {
+                let months = i64::from(start.year()) * 12 + i64::from(start.month0()) + step;
+                let year = i32::try_from(months.div_euclid(12)).ok()?;
+                NaiveDate::from_ymd_opt(year, months.rem_euclid(12) as u32 + 1, 1).map(midnight)
+            }
+            Frequency::Weekly => {
+                let offset = days_since_week_start(start.weekday(), self.week_start);
+                let week = start.date() - Duration::days(offset);
+                week.checked_add_signed(Duration::try_weeks(step)?)
+                    .map(midnight)
+            }
+            Frequency::Daily => {
+                midnight(start.date()).checked_add_signed(Duration::try_days(step)?)
+            }
+            Frequency::Hourly => start
+                .with_minute(0)?
+                .with_second(0)?
+                .checked_add_signed(Duration::try_hours(step)?),
+            Frequency::Minutely => start
+                .with_second(0)?
+                .checked_add_signed(Duration::try_minutes(step)?),
+            Frequency::Secondly => start.checked_add_signed(Duration::try_seconds(step)?),
+        }
+    }
+
+    /// Número del periodo que contiene `at`, contando desde el de `start`
+    fn period_index(&self, start: NaiveDateTime, at: NaiveDateTime) -> usize {
+        let Some(first) = self.period_start(start, 0) else {
+            return 0;
+        };
+        let units = match self.frequency {
+            Frequency::Yearly => i64::from(at.year() - first.year()),
+            Frequency::Monthly => {
+                i64::from(at.year() - first.year()) * 12 + i64::from(at.month0())
+                    - i64::from(first.month0())
+            }
+            Frequency::Weekly => (at.date() - first.date()).num_days().div_euclid(7),
+            Frequency::Daily => (at.date() - first.date()).num_days(),
+            Frequency::Hourly => (at - first).num_hours(),
+            Frequency::Minutely => (at - first).num_minutes(),
+            Frequency::Secondly => (at - first).num_seconds(),
+        };
+        usize::try_from(units / i64::from(self.interval)).unwrap_or(0)
+    }
+
+    /// Instantes de un periodo que cumplen todas las partes BY*, ordenados
+    /// y ya filtrados por `BYSETPOS`
+    fn period_candidates(&self, period_start: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let first_day = period_start.date();
+        let (first_day, last_day) = match self.frequency {
+            Frequency::Yearly if !self.by_week_no.is_empty() => {
+                // Las semanas del año pueden empezar en diciembre del anterior
+                // o terminar en enero del siguiente
+                let last = NaiveDate::from_ymd_opt(first_day.year(), 12, 31).unwrap_or(first_day);
+                (first_day - Duration::days(7), last + Duration::days(7))
+            }
+            Frequency::Yearly => (
+                first_day,
+                NaiveDate::from_ymd_opt(first_day.year(), 12, 31).unwrap_or(first_day),
+            ),
+            Frequency::Monthly => (
+                first_day,
+                first_day + Duration::days(days_in_month(first_day) as i64 - 1),
+            ),
+            Frequency::Weekly => (first_day, first_day + Duration::days(6)),
+            _ => (first_day, first_day),
+        };
+
+        let hours = self.time_values(Frequency::Hourly, &self.by_hour, period_start.hour());
+        let minutes = self.time_values(Frequency::Minutely, &self.by_minute, period_start.minute());
+        let seconds = self.time_values(Frequency::Secondly, &self.by_second, period_start.second());
+        let mut times = Vec::new();
+        for hour in &hours {
+            for minute in &minutes {
+                times.extend(
+                    seconds
+                        .iter()
+                        .filter_map(|second| NaiveTime::from_hms_opt(*hour, *minute, *second)),
+                );
+            }
+        }
+
+        let mut candidates: Vec<NaiveDateTime> = first_day
+            .iter_days()
+            .take_while(|day| *day <= last_day)
+            .filter(|day| self.matches_date(*day, period_start.year()))
+            .flat_map(|day| times.iter().map(move |time| day.and_time(*time)))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        if self.by_set_pos.is_empty() {
+            return candidates;
+        }
+        let mut selected: Vec<NaiveDateTime> = self
+            .by_set_pos
+            .iter()
+            .filter_map(|position| {
+                let index = if *position > 0 {
+                    *position as usize - 1
+                } else {
+                    candidates
+                        .len()
+                        .checked_sub(position.unsigned_abs() as usize)?
+                };
+                candidates.get(index).copied()
+            })
+            .collect();
+        selected.sort();
+        selected.dedup();
+        selected
+    }
+
+    /// Valores de hora, minuto o segundo de un periodo: las partes BY* amplían
+    /// las frecuencias más largas que `unit` y limitan las demás
+    fn time_values(&self, unit: Frequency, values: &[u32], current: u32) -> Vec<u32> {
+        if self.frequency > unit {
+            values.to_vec()
+        } else if values.is_empty() || values.contains(&current) {
+            vec![current]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Comprueba las partes BY* que se refieren al día
+    fn matches_date(&self, date: NaiveDate, period_year: i32) -> bool {
+        if !self.by_month.is_empty() && !self.by_month.contains(&date.month()) {
+            return false;
+        }
+
+        if !self.by_week_no.is_empty() {
+            let (week_year, week, weeks) = week_number(date, self.week_start);
+            if self.frequency == Frequency::Yearly && week_year != period_year {
+                return false;
+            }
+            if !self
+                .by_week_no
+                .iter()
+                .any(|n| matches_ordinal(*n, week, weeks))
+            {
+                return false;
+            }
+        } else if self.frequency == Frequency::Yearly && date.year() != period_year {
+            return false;
+        }
+
+        if !self.by_year_day.is_empty()
+            && !self.by_year_day.iter().any(|n| {
+                matches_ordinal(*n, date.ordinal() as i32, days_in_year(date.year()) as i32)
+            })
+        {
+            return false;
+        }
+
+        if !self.by_month_day.is_empty()
+            && !self
+                .by_month_day
+                .iter()
+                .any(|n| matches_ordinal(*n, date.day() as i32, days_in_month(date) as i32))
+        {
+            return false;
+        }
+
+        if !self.by_day.is_empty() {
+            // Los ordinales cuentan dentro del mes en las reglas mensuales y en
+            // las anuales con BYMONTH, y dentro del año en el resto de anuales
+            let (position, total) = match self.frequency {
+                Frequency::Monthly => (date.day(), days_in_month(date)),
+                Frequency::Yearly if !self.by_month.is_empty() => (date.day(), days_in_month(date)),
+                Frequency::Yearly => (date.ordinal(), days_in_year(date.year())),
+                _ => (0, 0),
+            };
+            let nth = ((position as i32 - 1) / 7) + 1;
+            let nth_from_end = ((total as i32 - position as i32) / 7) + 1;
+            let matches = self.by_day.iter().any(|(ordinal, weekday)| {
+                *weekday == date.weekday()
+                    && match ordinal {
+                        Some(n) if total > 0 && *n > 0 => *n == nth,
+                        Some(n) if total > 0 => -*n == nth_from_end,
+                        _ => true,
+                    }
+            });
+            if !matches {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Conjunto de recurrencia de un componente (RFC 5545, sección 3.8.5):
+/// `DTSTART`, las reglas y las fechas `RDATE`, menos las `EXDATE`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceSet {
+    pub start: DateTime<Utc>,
+    /// `DTSTART` en la hora local de su zona, sobre la que se aplican las
+    /// reglas para que las instancias no se muevan con el horario de verano
+    pub local_start: NaiveDateTime,
+    pub zone: Option<CalendarTimeZone>,
+    pub rules: Vec<RecurrenceRule>,
+    pub dates: Vec<DateTime<Utc>>,
+    pub exceptions: Vec<DateTime<Utc>>,
+}
+
+impl RecurrenceSet {
+    /// Conjunto de recurrencia de un componente, o `None` si no se repite
+    pub fn from_component(
+        component: &ICalComponent,
+        zones: &TimeZones,
+    ) -> Result<Option<Self>, String> {
+        let rules = component
+            .properties
+            .iter()
+            .filter(|property| property.name == "RRULE")
+            .map(|property| RecurrenceRule::parse(&property.value))
+            .collect::<Result<Vec<_>, _>>()?;
+        let dates = date_values(component, "RDATE", zones);
+        if rules.is_empty() && dates.is_empty() {
+            return Ok(None);
+        }
+
+        let dtstart = component
+            .property("DTSTART")
+            .ok_or("Recurring component without a valid DTSTART")?;
+        let start = zones
+            .date_time(dtstart)
+            .ok_or("Recurring component without a valid DTSTART")?;
+        let zone = zones.zone_of(dtstart).cloned();
+
+        Ok(Some(Self {
+            start,
+            local_start: zone
+                .as_ref()
+                .map_or_else(|| start.naive_utc(), |zone| zone.to_local(start)),
+            zone,
+            rules,
+            dates,
+            exceptions: date_values(component, "EXDATE", zones),
+        }))
+    }
+
+    /// Inicios de las instancias entre `from` y `limit`, incluidos, en orden
+    pub fn starts_between(&self, from: DateTime<Utc>, limit: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let mut starts = vec![self.start];
+        for rule in &self.rules {
+            let instances = match &self.zone {
+                None => rule.instances(self.local_start, from.naive_utc(), limit.naive_utc()),
+                Some(zone) => {
+                    // UNTIL va en UTC; las reglas se aplican en hora local
+                    let mut rule = rule.clone();
+                    rule.until = rule
+                        .until
+                        .map(|until| Utc.from_utc_datetime(&zone.to_local(until)));
+                    rule.instances(
+                        self.local_start,
+                        zone.to_local(from) - Duration::days(1),
+                        zone.to_local(limit) + Duration::days(1),
+                    )
+                }
+            };
+            starts.extend(instances.into_iter().map(|instance| match &self.zone {
+                Some(zone) => zone.to_utc(instance),
+                None => Utc.from_utc_datetime(&instance),
+            }));
+        }
+        starts.extend(self.dates.iter().copied());
+        starts.retain(|start| (from..=limit).contains(start) && !self.exceptions.contains(start));
+        starts.sort();
+        starts.dedup();
+        starts
+    }
+}
+
+/// Instancia de un componente de calendario
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence<'a> {
+    /// Inicio original de la instancia (`RECURRENCE-ID`), o `None` si el
+    /// componente no se repite
+    pub recurrence_id: Option<DateTime<Utc>>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Componente con los datos de la instancia: el maestro o la excepción
+    /// con su `RECURRENCE-ID`
+    pub component: &'a ICalComponent,
+}
+
+/// Instancias de un objeto de calendario que coinciden con `[start, end)`,
+/// según RFC 4791, sección 9.9, con las excepciones `RECURRENCE-ID`
+/// aplicadas
+pub fn occurrences<'a>(
+    calendar: &'a ICalComponent,
+    zones: &TimeZones,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<Occurrence<'a>> {
+    let Some(kind) = calendar
+        .calendar_components()
+        .next()
+        .map(|c| c.name.as_str())
+    else {
+        return Vec::new();
+    };
+    let components: Vec<&ICalComponent> = calendar.components_named(kind).collect();
+    let overrides: Vec<(DateTime<Utc>, &ICalComponent)> = components
+        .iter()
+        .filter_map(|component| {
+            let recurrence_id = zones.date_time(component.property("RECURRENCE-ID")?)?;
+            Some((recurrence_id, *component))
+        })
+        .collect();
+
+    let mut occurrences = Vec::new();
+    match components
+        .iter()
+        .find(|component| component.property("RECURRENCE-ID").is_none())
+    {
+        Some(master) => {
+            let Some((master_start, master_end)) = master.time_span(zones) else {
+                return Vec::new();
+            };
+            match RecurrenceSet::from_component(master, zones) {
+                Ok(Some(set)) => {
+                    let duration = master_end - master_start;
+                    // Una excepción puede traer al rango una instancia que
+                    // empezaba antes o después
+                    let from = overrides
+                        .iter()
+                        .map(|(id, _)| *id)
+                        .fold(start - duration, Ord::min);
+                    let limit = overrides.iter().map(|(id, _)| *id).fold(end, Ord::max);
+                    for instance in set.starts_between(from, limit) {
+                        let (component, (start, end)) =
+                            match overrides.iter().find(|(id, _)| *id == instance) {
+                                Some((_, component)) => (
+                                    *component,
+                                    component
+                                        .time_span(zones)
+                                        .unwrap_or((instance, instance + duration)),
+                                ),
+                                None => (*master, (instance, instance + duration)),
+                            };
+                        occurrences.push(Occurrence {
+                            recurrence_id: Some(instance),
+                            start,
+                            end,
+                            component,
+                        });
+                    }
+                }
+                _ => occurrences.push(Occurrence {
+                    recurrence_id: None,
+                    start: master_start,
+                    end: master_end,
+                    component: master,
+                }),
+            }
+        }
+        // Sin maestro solo hay instancias sueltas, como en una invitación
+        None => {
+            for (recurrence_id, component) in overrides {
+                if let Some((start, end)) = component.time_span(zones) {
+                    occurrences.push(Occurrence {
+                        recurrence_id: Some(recurrence_id),
+                        start,
+                        end,
+                        component,
+                    });
+                }
+            }
+        }
+    }
+
+    occurrences.retain(|occurrence| overlaps(occurrence.start, occurrence.end, start, end));
+    occurrences.sort_by_key(|occurrence| occurrence.start);
+    occurrences
+}
+
+/// Calendario con cada instancia del rango como un componente propio, sin
+/// reglas de recurrencia y con las horas en UTC (RFC 4791, sección 9.6.5)
+pub fn expand_calendar(
+    calendar: &ICalComponent,
+    zones: &TimeZones,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> ICalComponent {
+    let mut expanded = ICalComponent {
+        name: calendar.name.clone(),
+        properties: calendar.properties.clone(),
+        components: Vec::new(),
+    };
+
+    for occurrence in occurrences(calendar, zones, start, end) {
+        let source = occurrence.component;
+        let all_day = source
+            .property("DTSTART")
+            .is_some_and(ICalProperty::is_date);
+        let end_name = if source.property("DUE").is_some() {
+            Some("DUE")
+        } else if source.property("DTEND").is_some() || source.property("DURATION").is_some() {
+            Some("DTEND")
+        } else {
+            None
+        };
+
+        let mut instance = source.clone();
+        instance
+            .properties
+            .retain(|property| !RECURRENCE_PROPERTIES.contains(&property.name.as_str()));
+        let mut times = vec![date_time_property("DTSTART", &occurrence.start, all_day)];
+        if let Some(name) = end_name {
+            times.push(date_time_property(name, &occurrence.end, all_day));
+        }
+        if let Some(recurrence_id) = occurrence.recurrence_id {
+            times.push(date_time_property("RECURRENCE-ID", &recurrence_id, all_day));
+        }
+        instance.properties.splice(0..0, times);
+        expanded.components.push(instance);
+    }
+
+    expanded
+}
+
+/// Calendario con el maestro y solo las excepciones cuya instancia original
+/// coincide con el rango (RFC 4791, sección 9.6.6)
+pub fn limit_recurrence_set(
+    calendar: &ICalComponent,
+    zones: &TimeZones,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> ICalComponent {
+    let duration = calendar
+        .calendar_components()
+        .find(|component| component.property("RECURRENCE-ID").is_none())
+        .and_then(|component| component.time_span(zones))
+        .map_or_else(Duration::zero, |(start, end)| end - start);
+
+    let mut limited = calendar.clone();
+    limited.components.retain(|component| {
+        match component
+            .property("RECURRENCE-ID")
+            .and_then(|recurrence_id| zones.date_time(recurrence_id))
+        {
+            Some(recurrence_id) => overlaps(recurrence_id, recurrence_id + duration, start, end),
+            None => true,
+        }
+    });
+    limited
+}
+
+/// Intersección de un intervalo con un rango `[start, end)`; los intervalos
+/// vacíos coinciden si empiezan dentro del rango
+fn overlaps(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+) -> bool {
+    start < range_end && (end > range_start || (start == end && start >= range_start))
+}
+
+/// Propiedad con una fecha en UTC, o con `VALUE=DATE` para todo el día
+fn date_time_property(name: &str, date_time: &DateTime<Utc>, all_day: bool) -> ICalProperty {
+    if all_day {
+        ICalProperty {
+            name: name.to_string(),
+            params: vec![("VALUE".to_string(), "DATE".to_string())],
+            value: date_time.format("%Y%m%d").to_string(),
+        }
+    } else {
+        ICalProperty::new(name, format_date_time(date_time))
+    }
+}
+
+/// Fechas de todas las propiedades con este nombre, que pueden llevar varias
+/// separadas por comas o periodos `inicio/fin`
+fn date_values(component: &ICalComponent, name: &str, zones: &TimeZones) -> Vec<DateTime<Utc>> {
+    component
+        .properties
+        .iter()
+        .filter(|property| property.name == name)
+        .flat_map(|property| zones.date_times(property))
+        .collect()
+}
+
+fn parse_unsigned(name: &str, value: &str, min: u32, max: u32) -> Result<Vec<u32>, String> {
+    value
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .parse()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| format!("Invalid {}: {}", name, item))
+        })
+        .collect()
+}
+
+/// Lista de ordinales distintos de cero entre `-max` y `max`
+fn parse_signed(name: &str, value: &str, max: i32) -> Result<Vec<i32>, String> {
+    value
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .trim_start_matches('+')
+                .parse::<i32>()
+                .ok()
+                .filter(|n| *n != 0 && n.abs() <= max)
+                .ok_or_else(|| format!("Invalid {}: {}", name, item))
+        })
+        .collect()
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    Some(match value.trim().to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// Interpreta un elemento de `BYDAY`, como `MO`, `+2TU` o `-1SU`
+fn parse_weekday_num(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let value = value.trim();
+    let split = value.len().checked_sub(2)?;
+    let weekday = parse_weekday(value.get(split..)?)?;
+    let ordinal = match &value[..split] {
+        "" => None,
+        ordinal => Some(
+            ordinal
+                .trim_start_matches('+')
+                .parse::<i32>()
+                .ok()
+                .filter(|n| *n != 0 && n.abs() <= 53)?,
+        ),
+    };
+    Some((ordinal, weekday))
+}
+
+/// Compara un ordinal de una regla, negativo si cuenta desde el final, con
+/// una posición entre 1 y `total`
+fn matches_ordinal(ordinal: i32, position: i32, total: i32) -> bool {
+    if ordinal > 0 {
+        ordinal == position
+    } else {
+        total + ordinal + 1 == position
+    }
+}
+
+fn days_since_week_start(weekday: Weekday, week_start: Weekday) -> i64 {
+    i64::from((weekday.num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7)
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        month => (date.year(), month + 1),
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|next| next.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+fn days_in_year(year: i32) -> u32 {
+    if NaiveDate::from_ymd_opt(year, 2, 29).is_some() {
+        366
+    } else {
+        365
+    }
+}
+
+/// Año, número de semana y semanas del año de una fecha: la semana 1 es la
+/// primera con al menos cuatro días del año (RFC 5545, `BYWEEKNO`)
+fn week_number(date: NaiveDate, week_start: Weekday) -> (i32, i32, i32) {
+    let first_week = |year: i32| {
+        let january_first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or(date);
+        let offset = days_since_week_start(january_first.weekday(), week_start);
+        if offset <= 3 {
+            january_first - Duration::days(offset)
+        } else {
+            january_first + Duration::days(7 - offset)
+        }
+    };
+
+    let mut year = date.year();
+    if date < first_week(year) {
+        year -= 1;
+    } else if date >= first_week(year + 1) {
+        year += 1;
+    }
+    let start = first_week(year);
+    let week = (date - start).num_days() / 7 + 1;
+    let weeks = (first_week(year + 1) - start).num_days() / 7;
+    (year, week as i32, weeks as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").unwrap()
+    }
+
+    fn expand(rule: &str, start: &str, limit: &str) -> Vec<String> {
+        RecurrenceRule::parse(rule)
+            .unwrap()
+            .instances(at(start), at(start), at(limit))
+            .iter()
+            .map(|instance| instance.format("%Y%m%dT%H%M%S").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let rule =
+            RecurrenceRule::parse("FREQ=MONTHLY;INTERVAL=2;BYDAY=-1SU,+2MO;WKST=SU").unwrap();
+        assert_eq!(rule.frequency, Frequency::Monthly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(
+            rule.by_day,
+            vec![(Some(-1), Weekday::Sun), (Some(2), Weekday::Mon)]
+        );
+        assert_eq!(rule.week_start, Weekday::Sun);
+
+        assert!(RecurrenceRule::parse("INTERVAL=2").is_err());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;BYMONTHDAY=0").is_err());
+        assert!(RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=XX").is_err());
+    }
+
+    #[test]
+    fn test_rfc5545_examples() {
+        // Diariamente, 10 veces
+        assert_eq!(
+            expand("FREQ=DAILY;COUNT=10", "19970902T090000", "19980101T000000").len(),
+            10
+        );
+        // Cada dos semanas, lunes, miércoles y viernes hasta el 24 de diciembre
+        let biweekly = expand(
+            "FREQ=WEEKLY;INTERVAL=2;UNTIL=19971224T000000Z;WKST=SU;BYDAY=MO,WE,FR",
+            "19970901T090000",
+            "19980101T000000",
+        );
+        assert_eq!(biweekly.len(), 25);
+        assert_eq!(
+            &biweekly[..3],
+            ["19970901T090000", "19970903T090000", "19970905T090000"]
+        );
+        assert_eq!(biweekly.last().unwrap(), "19971222T090000");
+        // Primer y último domingo del mes, 10 veces
+        assert_eq!(
+            &expand(
+                "FREQ=MONTHLY;COUNT=10;BYDAY=1SU,-1SU",
+                "19970907T090000",
+                "19990101T000000"
+            )[..4],
+            [
+                "19970907T090000",
+                "19970928T090000",
+                "19971005T090000",
+                "19971026T090000"
+            ]
+        );
+        // Antepenúltimo día de cada mes
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYMONTHDAY=-3",
+                "19970928T090000",
+                "19971231T000000"
+            ),
+            [
+                "19970928T090000",
+                "19971029T090000",
+                "19971128T090000",
+                "19971229T090000"
+            ]
+        );
+        // Último día laborable del mes
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "19970930T090000",
+                "19971231T235959"
+            ),
+            [
+                "19970930T090000",
+                "19971031T090000",
+                "19971128T090000",
+                "19971231T090000"
+            ]
+        );
+        // Lunes de la semana 20 de cada año
+        assert_eq!(
+            expand(
+                "FREQ=YEARLY;BYWEEKNO=20;BYDAY=MO",
+                "19970512T090000",
+                "19991231T000000"
+            ),
+            ["19970512T090000", "19980511T090000", "19990517T090000"]
+        );
+        // Viernes 13
+        assert_eq!(
+            &expand(
+                "FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13",
+                "19980213T090000",
+                "19991231T000000"
+            )[..3],
+            ["19980213T090000", "19980313T090000", "19981113T090000"]
+        );
+        // Cada 29 de febrero
+        assert_eq!(
+            expand("FREQ=YEARLY", "20240229T120000", "20330101T000000"),
+            ["20240229T120000", "20280229T120000", "20320229T120000"]
+        );
+        // Cada 3 horas entre las 9:00 y las 17:00
+        assert_eq!(
+            expand(
+                "FREQ=HOURLY;INTERVAL=3;UNTIL=19970902T170000Z",
+                "19970902T090000",
+                "19971231T000000"
+            ),
+            ["19970902T090000", "19970902T120000", "19970902T150000"]
+        );
+    }
+
+    #[test]
+    fn test_occurrences_with_exceptions_and_overrides() {
+        let calendar = ICalComponent::parse(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:standup\r\n\
+             SUMMARY:Standup\r\n\
+             DTSTART:20240101T090000Z\r\n\
+             DTEND:20240101T091500Z\r\n\
+             RRULE:FREQ=DAILY;COUNT=5\r\n\
+             EXDATE:20240102T090000Z\r\n\
+             RDATE:20240110T090000Z\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:standup\r\n\
+             RECURRENCE-ID:20240103T090000Z\r\n\
+             SUMMARY:Standup (moved)\r\n\
+             DTSTART:20240103T140000Z\r\n\
+             DTEND:20240103T141500Z\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        )
+        .unwrap();
+
+        let window = |start: &str, end: &str| {
+            (
+                Utc.from_utc_datetime(&at(start)),
+                Utc.from_utc_datetime(&at(end)),
+            )
+        };
+        let (start, end) = window("20240101T000000", "20240201T000000");
+        let zones = TimeZones::default();
+        let found = occurrences(&calendar, &zones, start, end);
+        let starts: Vec<String> = found
+            .iter()
+            .map(|occurrence| format_date_time(&occurrence.start))
+            .collect();
+        assert_eq!(
+            starts,
+            [
+                "20240101T090000Z",
+                "20240103T140000Z",
+                "20240104T090000Z",
+                "20240105T090000Z",
+                "20240110T090000Z"
+            ]
+        );
+        assert_eq!(
+            found[1].component.property("SUMMARY").unwrap().text(),
+            "Standup (moved)"
+        );
+
+        // La excepción movida a la tarde ya no coincide con la mañana del día 3
+        let (start, end) = window("20240103T080000", "20240103T100000");
+        assert!(occurrences(&calendar, &zones, start, end).is_empty());
+
+        let (start, end) = window("20240103T000000", "20240105T000000");
+        let expanded = expand_calendar(&calendar, &zones, start, end);
+        assert_eq!(expanded.components.len(), 2);
+        let moved = &expanded.components[0];
+        assert_eq!(
+            moved.property("RECURRENCE-ID").unwrap().value,
+            "20240103T090000Z"
+        );
+        assert_eq!(moved.property("DTSTART").unwrap().value, "20240103T140000Z");
+        assert!(expanded.components[1].property("RRULE").is_none());
+        assert_eq!(
+            expanded.components[1].property("DTEND").unwrap().value,
+            "20240104T091500Z"
+        );
+
+        let (start, end) = window("20240104T000000", "20240201T000000");
+        let limited = limit_recurrence_set(&calendar, &zones, start, end);
+        assert_eq!(limited.components.len(), 1);
+    }
+
+    #[test]
+    fn test_expansion_jumps_to_the_range() {
+        // Más de MAX_PERIODS minutos separan DTSTART del rango
+        let rule = RecurrenceRule::parse("FREQ=MINUTELY;INTERVAL=15").unwrap();
+        let instances = rule.instances(
+            at("20000101T000000"),
+            at("20240301T100000"),
+            at("20240301T103000"),
+        );
+        assert_eq!(
+            &instances[instances.len() - 3..],
+            [
+                at("20240301T100000"),
+                at("20240301T101500"),
+                at("20240301T103000")
+            ]
+        );
+
+        let rule = RecurrenceRule::parse("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU").unwrap();
+        let instances = rule.instances(
+            at("19700106T090000"),
+            at("20240301T000000"),
+            at("20240315T000000"),
+        );
+        assert_eq!(instances.last(), Some(&at("20240305T090000")));
+    }
+
+    #[test]
+    fn test_expansion_in_local_time() {
+        let calendar = ICalComponent::parse(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:weekly\r\n\
+             DTSTART;TZID=Europe/Berlin:20240321T090000\r\n\
+             DTEND;TZID=Europe/Berlin:20240321T100000\r\n\
+             RRULE:FREQ=WEEKLY;UNTIL=20240404T070000Z\r\n\
+             EXDATE;TZID=Europe/Berlin:20240328T090000\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        )
+        .unwrap();
+        let zones = TimeZones::for_calendar(&calendar, None);
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+
+        // Las 9:00 de Berlín son las 8:00 UTC en invierno y las 7:00 en verano
+        let starts: Vec<String> = occurrences(&calendar, &zones, start, end)
+            .iter()
+            .map(|occurrence| format_date_time(&occurrence.start))
+            .collect();
+        assert_eq!(starts, ["20240321T080000Z", "20240404T070000Z"]);
+
+        let expanded = expand_calendar(&calendar, &zones, start, end);
+        assert_eq!(
+            expanded.components[1].property("DTEND").unwrap().value,
+            "20240404T080000Z"
+        );
+
+        // Sin la zona, las horas se toman como UTC
+        let floating: Vec<_> = occurrences(&calendar, &TimeZones::default(), start, end)
+            .iter()
+            .map(|occurrence| occurrence.start)
+            .collect();
+        assert_eq!(floating.len(), 1);
+    }
+}
@@ -0,0 +1,550 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use super::icalendar::{format_date_time, parse_duration, ICalComponent, ICalProperty};
+use super::recurrence;
+use super::scheduling::set_property;
+use super::timezone::TimeZones;
+
+/// Repeticiones que se atienden como mucho de una alarma con `REPEAT`
+const MAX_REPEAT: u32 = 100;
+
+/// Acción de una alarma (RFC 5545, sección 3.8.6.1); las de tipo
+/// `PROCEDURE`, obsoletas, no se atienden
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlarmAction {
+    Display,
+    Audio,
+    Email,
+}
+
+impl AlarmAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlarmAction::Display => "DISPLAY",
+            AlarmAction::Audio => "AUDIO",
+            AlarmAction::Email => "EMAIL",
+        }
+    }
+
+    /// Acción de un valor `ACTION`, sin distinguir mayúsculas
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "DISPLAY" => Some(AlarmAction::Display),
+            "AUDIO" => Some(AlarmAction::Audio),
+            "EMAIL" => Some(AlarmAction::Email),
+            _ => None,
+        }
+    }
+}
+
+/// Disparo de una alarma de un objeto de calendario
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlarmTrigger {
+    /// Alarma dentro del objeto: su UID o, si no tiene, el `RECURRENCE-ID`
+    /// de su componente y su posición en él
+    pub alarm_key: String,
+    pub trigger_at: DateTime<Utc>,
+    /// Inicio de la instancia sobre la que avisa
+    pub occurrence_start: DateTime<Utc>,
+    pub action: AlarmAction,
+    /// Resumen del evento o tarea
+    pub summary: String,
+    /// Texto de la alarma o, si no tiene, la descripción del objeto
+    pub description: Option<String>,
+}
+
+/// Momento de disparo de una alarma (RFC 5545, sección 3.8.6.3)
+enum Trigger {
+    Absolute(DateTime<Utc>),
+    /// Desplazamiento respecto al inicio de la instancia o, con
+    /// `RELATED=END`, a su fin
+    Relative {
+        offset: Duration,
+        from_end: bool,
+    },
+}
+
+fn trigger(alarm: &ICalComponent) -> Option<Trigger> {
+    let property = alarm.property("TRIGGER")?;
+    if property
+        .param("VALUE")
+        .is_some_and(|value| value.eq_ignore_ascii_case("DATE-TIME"))
+    {
+        return property.date_time().map(Trigger::Absolute);
+    }
+    Some(Trigger::Relative {
+        offset: parse_duration(&property.value)?,
+        from_end: property
+            .param("RELATED")
+            .is_some_and(|related| related.eq_ignore_ascii_case("END")),
+    })
+}
+
+/// Disparos de una alarma a partir del primero, con sus repeticiones
+/// (`REPEAT` y `DURATION`)
+fn repetitions(alarm: &ICalComponent, first: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let repeat = alarm
+        .property("REPEAT")
+        .and_then(|repeat| repeat.value.trim().parse::<u32>().ok())
+        .unwrap_or(0)
+        .min(MAX_REPEAT);
+    let interval = alarm
+        .property("DURATION")
+        .and_then(|duration| parse_duration(&duration.value))
+        .filter(|interval| *interval > Duration::zero());
+    match interval {
+        Some(interval) => (0..=repeat).map(|n| first + interval * n as i32).collect(),
+        None => vec![first],
+    }
+}
+
+/// Tiempo entre un disparo relativo y el inicio o fin de su instancia,
+/// contando las repeticiones
+fn reach(alarm: &ICalComponent) -> Duration {
+    match trigger(alarm) {
+        Some(Trigger::Relative { offset, .. }) => {
+            let first = DateTime::<Utc>::UNIX_EPOCH + offset;
+            let last = repetitions(alarm, first).last().copied().unwrap_or(first);
+            offset.abs().max((last - DateTime::<Utc>::UNIX_EPOCH).abs())
+        }
+        _ => Duration::zero(),
+    }
+}
+
+fn alarm_key(component: &ICalComponent, index: usize, alarm: &ICalComponent) -> String {
+    match alarm.property("UID") {
+        Some(uid) => uid.value.clone(),
+        None => format!(
+            "{}#{}",
+            component
+                .property("RECURRENCE-ID")
+                .map(|id| id.value.as_str())
+                .unwrap_or(""),
+            index
+        ),
+    }
+}
+
+/// Los eventos cancelados y las tareas terminadas no avisan
+fn is_inactive(component: &ICalComponent) -> bool {
+    component.property("STATUS").is_some_and(|status| {
+        let status = status.value.trim();
+        status.eq_ignore_ascii_case("CANCELLED")
+            || (component.name == "VTODO" && status.eq_ignore_ascii_case("COMPLETED"))
+    })
+}
+
+/// Instancias `(inicio, fin, componente)` de un objeto que coinciden con
+/// `[start, end)`; las tareas sin `DTSTART` se sitúan en su `DUE`
+fn instances<'a>(
+    calendar: &'a ICalComponent,
+    zones: &TimeZones,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>, &'a ICalComponent)> {
+    let has_start = calendar
+        .calendar_components()
+        .next()
+        .is_some_and(|component| component.property("DTSTART").is_some());
+    if has_start {
+        return recurrence::occurrences(calendar, zones, start, end)
+            .into_iter()
+            .map(|occurrence| (occurrence.start, occurrence.end, occurrence.component))
+            .collect();
+    }
+    calendar
+        .calendar_components()
+        .filter_map(|component| {
+            let due = zones.date_time(component.property("DUE")?)?;
+            (due >= start && due < end).then_some((due, due, component))
+        })
+        .collect()
+}
+
+fn alarm_trigger(
+    component: &ICalComponent,
+    index: usize,
+    alarm: &ICalComponent,
+    trigger_at: DateTime<Utc>,
+    occurrence_start: DateTime<Utc>,
+) -> Option<AlarmTrigger> {
+    let action = AlarmAction::parse(&alarm.property("ACTION")?.value)?;
+    Some(AlarmTrigger {
+        alarm_key: alarm_key(component, index, alarm),
+        trigger_at,
+        occurrence_start,
+        action,
+        summary: component
+            .property("SUMMARY")
+            .map(ICalProperty::text)
+            .unwrap_or_default(),
+        description: alarm
+            .property("DESCRIPTION")
+            .or_else(|| component.property("DESCRIPTION"))
+            .map(ICalProperty::text),
+    })
+}
+
+/// Disparos de las alarmas de un objeto `VCALENDAR` en `[from, until)`,
+/// ordenados: los relativos de cada instancia, también de las recurrentes,
+/// y los absolutos una sola vez. No se cuentan los disparos anteriores al
+/// `ACKNOWLEDGED` de su alarma (RFC 9074, sección 6) ni los de instancias
+/// canceladas o tareas terminadas.
+pub fn alarm_triggers(
+    calendar: &ICalComponent,
+    zones: &TimeZones,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<AlarmTrigger> {
+    let margin = calendar
+        .calendar_components()
+        .flat_map(|component| component.components_named("VALARM"))
+        .map(reach)
+        .max()
+        .unwrap_or_else(Duration::zero);
+
+    let mut candidates = Vec::new();
+    for (start, end, component) in instances(calendar, zones, from - margin, until + margin) {
+        for (index, alarm) in component.components_named("VALARM").enumerate() {
+            if let Some(Trigger::Relative { offset, from_end }) = trigger(alarm) {
+                let first = if from_end { end } else { start } + offset;
+                for trigger_at in repetitions(alarm, first) {
+                    candidates.push((component, index, alarm, trigger_at, start));
+                }
+            }
+        }
+    }
+    for component in calendar.calendar_components() {
+        for (index, alarm) in component.components_named("VALARM").enumerate() {
+            if let Some(Trigger::Absolute(first)) = trigger(alarm) {
+                let start = component
+                    .time_span(zones)
+                    .map(|(start, _)| start)
+                    .or_else(|| zones.date_time(component.property("DUE")?))
+                    .unwrap_or(first);
+                for trigger_at in repetitions(alarm, first) {
+                    candidates.push((component, index, alarm, trigger_at, start));
+                }
+            }
+        }
+    }
+
+    let mut triggers: Vec<AlarmTrigger> = candidates
+        .into_iter()
+        .filter(|(component, _, alarm, trigger_at, _)| {
+            let acknowledged = alarm
+                .property("ACKNOWLEDGED")
+                .and_then(ICalProperty::date_time);
+            *trigger_at >= from
+                && *trigger_at < until
+                && !is_inactive(component)
+                && acknowledged.is_none_or(|acknowledged| *trigger_at > acknowledged)
+        })
+        .filter_map(|(component, index, alarm, trigger_at, start)| {
+            alarm_trigger(component, index, alarm, trigger_at, start)
+        })
+        .collect();
+    triggers.sort_by(|a, b| (a.trigger_at, &a.alarm_key).cmp(&(b.trigger_at, &b.alarm_key)));
+    triggers.dedup_by(|a, b| a.alarm_key == b.alarm_key && a.trigger_at == b.trigger_at);
+    triggers
+}
+
+/// Posición `(componente, alarma)` en `components` de la alarma con la
+/// clave de `AlarmTrigger::alarm_key`
+fn locate_alarm(calendar: &ICalComponent, alarm_key: &str) -> Option<(usize, usize)> {
+    calendar
+        .components
+        .iter()
+        .enumerate()
+        .filter(|(_, component)| component.name != "VTIMEZONE")
+        .find_map(|(position, component)| {
+            component
+                .components
+                .iter()
+                .enumerate()
+                .filter(|(_, alarm)| alarm.name == "VALARM")
+                .enumerate()
+                .find(|(index, (_, alarm))| self::alarm_key(component, *index, alarm) == alarm_key)
+                .map(|(_, (alarm_position, _))| (position, alarm_position))
+        })
+}
+
+/// Marca una alarma como atendida en `at` (RFC 9074, sección 6): sus
+/// disparos hasta ese momento ya no avisan. Devuelve `false` si el objeto
+/// no tiene la alarma.
+pub fn acknowledge(calendar: &mut ICalComponent, alarm_key: &str, at: DateTime<Utc>) -> bool {
+    let Some((component, alarm)) = locate_alarm(calendar, alarm_key) else {
+        return false;
+    };
+    set_property(
+        &mut calendar.components[component].components[alarm],
+        "ACKNOWLEDGED",
+        &format_date_time(&at),
+    );
+    true
+}
+
+/// Pospone una alarma hasta `until` (RFC 9074, sección 5): la original
+/// queda atendida en `now` y una alarma nueva, con
+/// `RELATED-TO;RELTYPE=SNOOZE` hacia ella, se dispara a esa hora. Posponer
+/// una alarma ya pospuesta solo cambia su hora. Devuelve `false` si el
+/// objeto no tiene la alarma.
+pub fn snooze(
+    calendar: &mut ICalComponent,
+    alarm_key: &str,
+    until: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> bool {
+    let Some((component, alarm)) = locate_alarm(calendar, alarm_key) else {
+        return false;
+    };
+    let component = &mut calendar.components[component];
+    let alarm = &mut component.components[alarm];
+    let mut trigger = ICalProperty::new("TRIGGER", format_date_time(&until));
+    trigger
+        .params
+        .push(("VALUE".to_string(), "DATE-TIME".to_string()));
+
+    let is_snooze = alarm.properties.iter().any(|property| {
+        property.name == "RELATED-TO"
+            && property
+                .param("RELTYPE")
+                .is_some_and(|reltype| reltype.eq_ignore_ascii_case("SNOOZE"))
+    });
+    if is_snooze {
+        alarm
+            .properties
+            .retain(|property| property.name != "TRIGGER" && property.name != "ACKNOWLEDGED");
+        alarm.properties.push(trigger);
+        return true;
+    }
+
+    let uid = match alarm.property("UID") {
+        Some(uid) => uid.value.clone(),
+        None => {
+            let uid = Uuid::new_v4().to_string();
+            alarm.properties.push(ICalProperty::new("UID", uid.clone()));
+            uid
+        }
+    };
+    set_property(alarm, "ACKNOWLEDGED", &format_date_time(&now));
+
+    let mut related = ICalProperty::new("RELATED-TO", uid);
+    related
+        .params
+        .push(("RELTYPE".to_string(), "SNOOZE".to_string()));
+    let mut snoozed = ICalComponent {
+        name: "VALARM".to_string(),
+        properties: vec![
+            ICalProperty::new("UID", Uuid::new_v4().to_string()),
+            related,
+            trigger,
+        ],
+        components: Vec::new(),
+    };
+    snoozed.properties.extend(
+        alarm
+            .properties
+            .iter()
+            .filter(|property| {
+                matches!(
+                    property.name.as_str(),
+                    "ACTION" | "DESCRIPTION" | "SUMMARY" | "ATTENDEE"
+                )
+            })
+            .cloned(),
+    );
+    component.components.push(snoozed);
+    true
+}
+
+/// Evento o tarea cuyas alarmas se indexan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlarmSource {
+    pub object_id: String,
+    pub calendar_id: String,
+    /// Propietario del calendario, que recibe los avisos
+    pub owner_id: String,
+    /// Zona por defecto del propietario, para las horas flotantes
+    pub timezone: Option<String>,
+    pub ical_data: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AlarmSource {
+    /// Recordatorios pendientes del objeto con disparo en `[from, until)`
+    pub fn reminders(&self, from: DateTime<Utc>, until: DateTime<Utc>) -> Vec<Reminder> {
+        let Ok(calendar) = ICalComponent::parse(&self.ical_data) else {
+            return Vec::new();
+        };
+        let Some(component) = calendar.calendar_components().next() else {
+            return Vec::new();
+        };
+        let zones = TimeZones::for_calendar(&calendar, self.timezone.as_deref());
+        alarm_triggers(&calendar, &zones, from, until)
+            .into_iter()
+            .map(|trigger| Reminder {
+                id: Uuid::new_v4(),
+                user_id: self.owner_id.clone(),
+                calendar_id: self.calendar_id.clone(),
+                object_id: self.object_id.clone(),
+                component: component.name.clone(),
+                alarm_key: trigger.alarm_key,
+                action: trigger.action,
+                trigger_at: trigger.trigger_at,
+                occurrence_start: trigger.occurrence_start,
+                summary: trigger.summary,
+                description: trigger.description,
+                sent_at: None,
+            })
+            .collect()
+    }
+}
+
+/// Aviso de una alarma en el índice de recordatorios. Cada disparo se
+/// identifica por el objeto, la alarma y la hora, y solo se envía una vez.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reminder {
+    pub id: Uuid,
+    /// Usuario que recibe el aviso
+    pub user_id: String,
+    pub calendar_id: String,
+    /// Evento o tarea con la alarma
+    pub object_id: String,
+    /// `VEVENT` o `VTODO`
+    pub component: String,
+    pub alarm_key: String,
+    pub action: AlarmAction,
+    pub trigger_at: DateTime<Utc>,
+    pub occurrence_start: DateTime<Utc>,
+    pub summary: String,
+    pub description: Option<String>,
+    /// Momento del envío; `None` mientras está pendiente
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// Aviso en la aplicación de un recordatorio enviado, hasta que el usuario
+/// lo atiende o lo pospone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReminderNotification {
+    pub id: Uuid,
+    pub user_id: String,
+    pub reminder: Reminder,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const STANDUP: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:standup\r\n\
+        SUMMARY:Standup\r\n\
+        DTSTART;TZID=Europe/Madrid:20250106T090000\r\n\
+        DTEND;TZID=Europe/Madrid:20250106T091500\r\n\
+        RRULE:FREQ=DAILY;COUNT=3\r\n\
+        BEGIN:VALARM\r\n\
+        TRIGGER:-PT10M\r\n\
+        ACTION:DISPLAY\r\n\
+        DESCRIPTION:Standup soon\r\n\
+        END:VALARM\r\n\
+        BEGIN:VALARM\r\n\
+        UID:mail\r\n\
+        TRIGGER;RELATED=END:PT5M\r\n\
+        ACTION:EMAIL\r\n\
+        ATTENDEE:mailto:Alice@example.com\r\n\
+        ACKNOWLEDGED:20250107T000000Z\r\n\
+        END:VALARM\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:standup\r\n\
+        RECURRENCE-ID;TZID=Europe/Madrid:20250108T090000\r\n\
+        SUMMARY:Standup\r\n\
+        STATUS:CANCELLED\r\n\
+        DTSTART;TZID=Europe/Madrid:20250108T090000\r\n\
+        DTEND;TZID=Europe/Madrid:20250108T091500\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    fn utc(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_alarm_triggers() {
+        let calendar = ICalComponent::parse(STANDUP).unwrap();
+        let zones = TimeZones::for_calendar(&calendar, None);
+        let triggers = alarm_triggers(&calendar, &zones, utc(1, 0, 0), utc(31, 0, 0));
+
+        // Madrid está en UTC+1; la instancia del 8 está cancelada y el aviso
+        // por correo del 6 ya está atendido
+        let fired: Vec<(&str, DateTime<Utc>)> = triggers
+            .iter()
+            .map(|trigger| (trigger.alarm_key.as_str(), trigger.trigger_at))
+            .collect();
+        assert_eq!(
+            fired,
+            vec![
+                ("#0", utc(6, 7, 50)),
+                ("#0", utc(7, 7, 50)),
+                ("mail", utc(7, 8, 20)),
+            ]
+        );
+        assert_eq!(triggers[1].occurrence_start, utc(7, 8, 0));
+        assert_eq!(triggers[1].description.as_deref(), Some("Standup soon"));
+        assert_eq!(triggers[2].action, AlarmAction::Email);
+
+        // Solo los disparos dentro de la ventana
+        let window = alarm_triggers(&calendar, &zones, utc(7, 8, 0), utc(7, 9, 0));
+        assert_eq!(window.len(), 1);
+        assert_eq!(window[0].alarm_key, "mail");
+    }
+
+    #[test]
+    fn test_acknowledge_and_snooze() {
+        let mut calendar = ICalComponent::parse(STANDUP).unwrap();
+        let zones = TimeZones::for_calendar(&calendar, None);
+        assert!(acknowledge(&mut calendar, "#0", utc(6, 8, 0)));
+        assert!(!acknowledge(&mut calendar, "#5", utc(6, 8, 0)));
+        let keys: Vec<DateTime<Utc>> =
+            alarm_triggers(&calendar, &zones, utc(1, 0, 0), utc(31, 0, 0))
+                .iter()
+                .filter(|trigger| trigger.action == AlarmAction::Display)
+                .map(|trigger| trigger.trigger_at)
+                .collect();
+        assert_eq!(keys, vec![utc(7, 7, 50)]);
+
+        // Posponer el aviso del día 7 lo atiende y añade una alarma nueva
+        assert!(snooze(&mut calendar, "#0", utc(7, 8, 5), utc(7, 7, 51)));
+        let calendar = ICalComponent::parse(&calendar.to_ical()).unwrap();
+        let triggers = alarm_triggers(&calendar, &zones, utc(7, 0, 0), utc(8, 0, 0));
+        let display: Vec<&AlarmTrigger> = triggers
+            .iter()
+            .filter(|trigger| trigger.action == AlarmAction::Display)
+            .collect();
+        assert_eq!(display.len(), 1);
+        assert_eq!(display[0].trigger_at, utc(7, 8, 5));
+        assert_eq!(display[0].description.as_deref(), Some("Standup soon"));
+
+        let mut snoozed = calendar.clone();
+        let key = display[0].alarm_key.clone();
+        assert!(snooze(&mut snoozed, &key, utc(7, 8, 30), utc(7, 8, 6)));
+        let triggers = alarm_triggers(&snoozed, &zones, utc(7, 0, 0), utc(8, 0, 0));
+        assert!(triggers
+            .iter()
+            .any(|trigger| trigger.alarm_key == key && trigger.trigger_at == utc(7, 8, 30)));
+        assert_eq!(
+            snoozed
+                .calendar_components()
+                .next()
+                .unwrap()
+                .components
+                .len(),
+            3
+        );
+    }
+}
@@ -0,0 +1,600 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::icalendar::{format_date_time, ICalComponent, ICalProperty};
+
+/// Identificador de producto de los objetos que genera el servidor
+pub const PRODUCT_ID: &str = "-//OxiCloud//CalDAV//EN";
+
+/// Propiedades de un componente que conserva una respuesta: las que
+/// identifican la instancia a la que se responde
+const REPLY_PROPERTIES: [&str; 8] = [
+    "UID",
+    "RECURRENCE-ID",
+    "SEQUENCE",
+    "DTSTART",
+    "DTEND",
+    "DURATION",
+    "ORGANIZER",
+    "SUMMARY",
+];
+
+/// Método iTIP de un mensaje de planificación (RFC 5546, sección 1.4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItipMethod {
+    /// Invitación del organizador o cambio de un objeto ya enviado
+    Request,
+    /// Respuesta de un participante, con su `PARTSTAT`
+    Reply,
+    /// Anulación del objeto, o de algunas instancias, por el organizador
+    Cancel,
+}
+
+impl ItipMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItipMethod::Request => "REQUEST",
+            ItipMethod::Reply => "REPLY",
+            ItipMethod::Cancel => "CANCEL",
+        }
+    }
+
+    /// Método de un valor `METHOD`, sin distinguir mayúsculas
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "REQUEST" => Some(ItipMethod::Request),
+            "REPLY" => Some(ItipMethod::Reply),
+            "CANCEL" => Some(ItipMethod::Cancel),
+            _ => None,
+        }
+    }
+}
+
+/// Tipo de ocupación de un periodo (`FBTYPE`, RFC 5545, sección 3.2.9)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FreeBusyType {
+    Busy,
+    /// Eventos con `STATUS:TENTATIVE`
+    BusyTentative,
+}
+
+impl FreeBusyType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FreeBusyType::Busy => "BUSY",
+            FreeBusyType::BusyTentative => "BUSY-TENTATIVE",
+        }
+    }
+}
+
+/// Periodo ocupado `[start, end)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreeBusyPeriod {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub kind: FreeBusyType,
+}
+
+/// Ocupación que supone una instancia (RFC 4791, sección 7.10): ninguna si
+/// está cancelada o es transparente, provisional si es `TENTATIVE`
+pub fn busy_type(component: &ICalComponent) -> Option<FreeBusyType> {
+    let value = |name: &str| {
+        component
+            .property(name)
+            .map(|property| property.value.trim().to_ascii_uppercase())
+    };
+    let status = value("STATUS");
+    if status.as_deref() == Some("CANCELLED") || value("TRANSP").as_deref() == Some("TRANSPARENT") {
+        return None;
+    }
+    Some(match status.as_deref() {
+        Some("TENTATIVE") => FreeBusyType::BusyTentative,
+        _ => FreeBusyType::Busy,
+    })
+}
+
+/// Periodos ordenados por inicio, con los del mismo tipo que se solapan o
+/// se tocan unidos en uno
+pub fn merge_periods(mut periods: Vec<FreeBusyPeriod>) -> Vec<FreeBusyPeriod> {
+    periods.sort_by_key(|period| (period.kind, period.start));
+    let mut merged: Vec<FreeBusyPeriod> = Vec::with_capacity(periods.len());
+    for period in periods {
+        match merged.last_mut() {
+            Some(last) if last.kind == period.kind && period.start <= last.end => {
+                last.end = last.end.max(period.end);
+            }
+            _ => merged.push(period),
+        }
+    }
+    merged.sort_by_key(|period| (period.start, period.kind));
+    merged
+}
+
+/// Componente `VFREEBUSY` (RFC 5545, sección 3.6.4) con los periodos
+/// ocupados de `[start, end)`
+pub fn free_busy_component(
+    periods: &[FreeBusyPeriod],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> ICalComponent {
+    let mut properties = vec![
+        ICalProperty::new("DTSTAMP", format_date_time(&Utc::now())),
+        ICalProperty::new("DTSTART", format_date_time(&start)),
+        ICalProperty::new("DTEND", format_date_time(&end)),
+    ];
+    for period in periods {
+        let mut property = ICalProperty::new(
+            "FREEBUSY",
+            format!(
+                "{}/{}",
+                format_date_time(&period.start),
+                format_date_time(&period.end)
+            ),
+        );
+        set_param(&mut property, "FBTYPE", period.kind.as_str());
+        properties.push(property);
+    }
+    ICalComponent {
+        name: "VFREEBUSY".to_string(),
+        properties,
+        components: Vec::new(),
+    }
+}
+
+/// Objeto `VCALENDAR` con estos componentes y, si es un mensaje iTIP, su
+/// método
+pub fn vcalendar(method: Option<ItipMethod>, components: Vec<ICalComponent>) -> ICalComponent {
+    let mut properties = vec![
+        ICalProperty::new("VERSION", "2.0"),
+        ICalProperty::new("PRODID", PRODUCT_ID),
+    ];
+    if let Some(method) = method {
+        properties.push(ICalProperty::new("METHOD", method.as_str()));
+    }
+    ICalComponent {
+        name: "VCALENDAR".to_string(),
+        properties,
+        components,
+    }
+}
+
+/// Correo de una dirección de usuario de calendario `mailto:`, en minúsculas
+pub fn calendar_user_email(address: &str) -> Option<String> {
+    let address = address.trim();
+    let scheme = address.get(..7)?;
+    if !scheme.eq_ignore_ascii_case("mailto:") {
+        return None;
+    }
+    let email = address[7..].trim().to_lowercase();
+    email.contains('@').then_some(email)
+}
+
+/// Correo del organizador de un objeto, el `ORGANIZER` de su primer
+/// componente
+pub fn organizer(calendar: &ICalComponent) -> Option<String> {
+    calendar
+        .calendar_components()
+        .find_map(|component| component.property("ORGANIZER"))
+        .and_then(|organizer| calendar_user_email(&organizer.value))
+}
+
+/// Participantes a los que el servidor entrega los mensajes del
+/// organizador: los `ATTENDEE` de todas las instancias, sin repetir, salvo
+/// el propio organizador y los que planifica el cliente
+/// (`SCHEDULE-AGENT=CLIENT`, RFC 6638, sección 7.1)
+pub fn attendees(calendar: &ICalComponent) -> Vec<String> {
+    let organizer = organizer(calendar);
+    let mut emails: Vec<String> = Vec::new();
+    for property in calendar
+        .calendar_components()
+        .flat_map(|component| component.properties.iter())
+        .filter(|property| property.name == "ATTENDEE")
+    {
+        if property
+            .param("SCHEDULE-AGENT")
+            .is_some_and(|agent| !agent.eq_ignore_ascii_case("SERVER"))
+        {
+            continue;
+        }
+        if let Some(email) = calendar_user_email(&property.value) {
+            if organizer.as_ref() != Some(&email) && !emails.contains(&email) {
+                emails.push(email);
+            }
+        }
+    }
+    emails
+}
+
+/// Respuesta de un participante en cada instancia en que aparece: el
+/// `RECURRENCE-ID` (ninguno en el maestro) y su `PARTSTAT`, que por defecto
+/// es `NEEDS-ACTION`
+pub fn participation(calendar: &ICalComponent, email: &str) -> Vec<(Option<String>, String)> {
+    calendar
+        .calendar_components()
+        .filter_map(|component| {
+            let attendee = find_attendee(component, email)?;
+            Some((
+                component
+                    .property("RECURRENCE-ID")
+                    .map(|property| property.value.clone()),
+                partstat(attendee),
+            ))
+        })
+        .collect()
+}
+
+/// Mensaje del organizador con el objeto entero: sin las alarmas, que son
+/// de cada usuario, y con `STATUS:CANCELLED` si es una anulación
+pub fn organizer_message(calendar: &ICalComponent, method: ItipMethod) -> ICalComponent {
+    let mut message = without_method(calendar);
+    message
+        .properties
+        .push(ICalProperty::new("METHOD", method.as_str()));
+    for component in message
+        .components
+        .iter_mut()
+        .filter(|component| component.name != "VTIMEZONE")
+    {
+        component
+            .components
+            .retain(|subcomponent| subcomponent.name != "VALARM");
+        if method == ItipMethod::Cancel {
+            set_property(component, "STATUS", "CANCELLED");
+        }
+    }
+    message
+}
+
+/// Objeto de un mensaje sin `METHOD`, que los recursos de calendario no
+/// pueden llevar (RFC 4791, sección 4.1)
+pub fn without_method(message: &ICalComponent) -> ICalComponent {
+    let mut calendar = message.clone();
+    calendar
+        .properties
+        .retain(|property| property.name != "METHOD");
+    calendar
+}
+
+/// Respuesta (`REPLY`) de un participante a las instancias con estos
+/// `RECURRENCE-ID`: cada una solo con los datos que la identifican y con el
+/// `ATTENDEE` de quien responde
+pub fn reply(
+    calendar: &ICalComponent,
+    email: &str,
+    recurrence_ids: &[Option<String>],
+) -> ICalComponent {
+    let mut components: Vec<ICalComponent> =
+        calendar.components_named("VTIMEZONE").cloned().collect();
+    for component in calendar.calendar_components() {
+        let Some(attendee) = find_attendee(component, email) else {
+            continue;
+        };
+        let recurrence_id = component
+            .property("RECURRENCE-ID")
+            .map(|property| property.value.clone());
+        if !recurrence_ids.contains(&recurrence_id) {
+            continue;
+        }
+        let mut properties: Vec<ICalProperty> = component
+            .properties
+            .iter()
+            .filter(|property| REPLY_PROPERTIES.contains(&property.name.as_str()))
+            .cloned()
+            .collect();
+        properties.push(ICalProperty::new("DTSTAMP", format_date_time(&Utc::now())));
+        properties.push(attendee.clone());
+        components.push(ICalComponent {
+            name: component.name.clone(),
+            properties,
+            components: Vec::new(),
+        });
+    }
+    vcalendar(Some(ItipMethod::Reply), components)
+}
+
+/// El objeto con el participante rechazando todas las instancias, para
+/// responder cuando lo borra de su calendario
+pub fn declined(calendar: &ICalComponent, email: &str) -> ICalComponent {
+    let mut declined = calendar.clone();
+    for component in declined.components.iter_mut() {
+        if let Some(attendee) = find_attendee_mut(component, email) {
+            set_param(attendee, "PARTSTAT", "DECLINED");
+        }
+    }
+    declined
+}
+
+/// Lleva la respuesta del participante con este correo a la copia del
+/// organizador: el `PARTSTAT` de su `ATTENDEE` en cada instancia de la
+/// respuesta pasa a la instancia con el mismo `RECURRENCE-ID`. Los
+/// `ATTENDEE` de otros participantes se ignoran. Devuelve si ha cambiado
+/// algo.
+pub fn apply_reply(calendar: &mut ICalComponent, reply: &ICalComponent, email: &str) -> bool {
+    let mut changed = false;
+    for answer in reply.calendar_components() {
+        let Some(attendee) = find_attendee(answer, email) else {
+            continue;
+        };
+        let status = partstat(attendee);
+        let recurrence_id = answer
+            .property("RECURRENCE-ID")
+            .map(|property| property.value.as_str());
+        let target = calendar.components.iter_mut().find(|component| {
+            component.name == answer.name
+                && component
+                    .property("RECURRENCE-ID")
+                    .map(|property| property.value.as_str())
+                    == recurrence_id
+        });
+        if let Some(property) = target.and_then(|component| find_attendee_mut(component, email)) {
+            if partstat(property) != status {
+                set_param(property, "PARTSTAT", &status);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Indica si un mensaje trae el componente maestro o solo instancias
+/// sueltas con su `RECURRENCE-ID`
+pub fn has_master(message: &ICalComponent) -> bool {
+    message
+        .calendar_components()
+        .any(|component| component.property("RECURRENCE-ID").is_none())
+}
+
+/// Pone en una copia las instancias sueltas de un mensaje, en lugar de las
+/// que tengan el mismo `RECURRENCE-ID`
+pub fn merge_instances(calendar: &mut ICalComponent, message: &ICalComponent) {
+    for instance in message.calendar_components() {
+        let recurrence_id = instance
+            .property("RECURRENCE-ID")
+            .map(|property| property.value.as_str());
+        calendar.components.retain(|component| {
+            component.name != instance.name
+                || component
+                    .property("RECURRENCE-ID")
+                    .map(|property| property.value.as_str())
+                    != recurrence_id
+        });
+        calendar.components.push(instance.clone());
+    }
+}
+
+/// `PARTSTAT` de un `ATTENDEE`, en mayúsculas
+fn partstat(attendee: &ICalProperty) -> String {
+    attendee
+        .param("PARTSTAT")
+        .unwrap_or("NEEDS-ACTION")
+        .to_ascii_uppercase()
+}
+
+fn find_attendee<'a>(component: &'a ICalComponent, email: &str) -> Option<&'a ICalProperty> {
+    component.properties.iter().find(|property| {
+        property.name == "ATTENDEE"
+            && calendar_user_email(&property.value).as_deref() == Some(email)
+    })
+}
+
+fn find_attendee_mut<'a>(
+    component: &'a mut ICalComponent,
+    email: &str,
+) -> Option<&'a mut ICalProperty> {
+    component.properties.iter_mut().find(|property| {
+        property.name == "ATTENDEE"
+            && calendar_user_email(&property.value).as_deref() == Some(email)
+    })
+}
+
+fn set_param(property: &mut ICalProperty, name: &str, value: &str) {
+    match property
+        .params
+        .iter_mut()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    {
+        Some((_, current)) => *current = value.to_string(),
+        None => property.params.push((name.to_string(), value.to_string())),
+    }
+}
+
+/// Sustituye el valor y los parámetros de una propiedad, o la añade
+pub(crate) fn set_property(component: &mut ICalComponent, name: &str, value: &str) {
+    match component
+        .properties
+        .iter_mut()
+        .find(|property| property.name == name)
+    {
+        Some(property) => {
+            property.params.clear();
+            property.value = value.to_string();
+        }
+        None => component.properties.push(ICalProperty::new(name, value)),
+    }
+}
+
+/// Mensaje de planificación entregado en la bandeja de entrada de un
+/// usuario (RFC 6638, sección 2.2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleMessage {
+    pub id: Uuid,
+    /// Usuario que lo recibe
+    pub user_id: String,
+    pub method: ItipMethod,
+    /// UID del objeto al que se refiere
+    pub ical_uid: String,
+    /// Texto iCalendar del mensaje, con su `METHOD`
+    pub ical_data: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ScheduleMessage {
+    /// Mensaje nuevo para un usuario; el método y el UID salen del propio
+    /// mensaje, que debe tenerlos
+    pub fn new(user_id: &str, message: &ICalComponent) -> Option<Self> {
+        let method = ItipMethod::parse(&message.property("METHOD")?.value)?;
+        let ical_uid = message
+            .calendar_components()
+            .find_map(|component| component.property("UID"))?
+            .text();
+        Some(Self {
+            id: Uuid::new_v4(),
+            user_id: user_id.to_string(),
+            method,
+            ical_uid,
+            ical_data: message.to_ical(),
+            created_at: Utc::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const MEETING: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:meeting-1\r\n\
+        DTSTART:20250106T090000Z\r\n\
+        DTEND:20250106T100000Z\r\n\
+        RRULE:FREQ=DAILY;COUNT=3\r\n\
+        ORGANIZER:mailto:Alice@example.com\r\n\
+        ATTENDEE;PARTSTAT=ACCEPTED:mailto:alice@example.com\r\n\
+        ATTENDEE;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:bob@example.com\r\n\
+        ATTENDEE;SCHEDULE-AGENT=CLIENT:mailto:carol@example.com\r\n\
+        BEGIN:VALARM\r\n\
+        TRIGGER:-PT15M\r\n\
+        ACTION:DISPLAY\r\n\
+        END:VALARM\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:meeting-1\r\n\
+        RECURRENCE-ID:20250107T090000Z\r\n\
+        DTSTART:20250107T110000Z\r\n\
+        DTEND:20250107T120000Z\r\n\
+        ORGANIZER:mailto:alice@example.com\r\n\
+        ATTENDEE:mailto:bob@example.com\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_reply_round_trip() {
+        let calendar = ICalComponent::parse(MEETING).unwrap();
+        assert_eq!(organizer(&calendar).as_deref(), Some("alice@example.com"));
+        assert_eq!(attendees(&calendar), vec!["bob@example.com".to_string()]);
+
+        let request = organizer_message(&calendar, ItipMethod::Request);
+        assert_eq!(request.property("METHOD").unwrap().value, "REQUEST");
+        assert!(request
+            .calendar_components()
+            .all(|c| c.components.is_empty()));
+
+        // Bob acepta la serie y rechaza la instancia cambiada
+        let mut answered = without_method(&request);
+        for (component, status) in answered.components.iter_mut().zip(["ACCEPTED", "DECLINED"]) {
+            set_param(
+                find_attendee_mut(component, "bob@example.com").unwrap(),
+                "PARTSTAT",
+                status,
+            );
+        }
+        let before = participation(&calendar, "bob@example.com");
+        let changed: Vec<Option<String>> = participation(&answered, "bob@example.com")
+            .into_iter()
+            .filter(|answer| !before.contains(answer))
+            .map(|(recurrence_id, _)| recurrence_id)
+            .collect();
+        assert_eq!(changed.len(), 2);
+
+        let message = reply(&answered, "bob@example.com", &changed);
+        let entry = ScheduleMessage::new("bob-id", &message).unwrap();
+        assert_eq!(entry.method, ItipMethod::Reply);
+        assert_eq!(entry.ical_uid, "meeting-1");
+        assert!(message.calendar_components().all(|c| c
+            .properties
+            .iter()
+            .filter(|p| p.name == "ATTENDEE")
+            .count()
+            == 1));
+
+        // Bob no puede contestar por otro participante poniendo su
+        // ATTENDEE delante del suyo
+        let mut forged = message.clone();
+        for component in forged.components.iter_mut() {
+            component.properties.insert(
+                0,
+                ICalProperty {
+                    name: "ATTENDEE".to_string(),
+                    params: vec![("PARTSTAT".to_string(), "DECLINED".to_string())],
+                    value: "mailto:alice@example.com".to_string(),
+                },
+            );
+        }
+        let mut organizer_copy = calendar.clone();
+        assert!(apply_reply(&mut organizer_copy, &forged, "bob@example.com"));
+        assert_eq!(
+            participation(&organizer_copy, "alice@example.com"),
+            participation(&calendar, "alice@example.com")
+        );
+        assert!(!apply_reply(
+            &mut organizer_copy,
+            &message,
+            "bob@example.com"
+        ));
+        assert_eq!(
+            participation(&organizer_copy, "bob@example.com"),
+            vec![
+                (None, "ACCEPTED".to_string()),
+                (Some("20250107T090000Z".to_string()), "DECLINED".to_string()),
+            ]
+        );
+        assert!(
+            participation(&declined(&calendar, "bob@example.com"), "bob@example.com")
+                .iter()
+                .all(|(_, status)| status == "DECLINED")
+        );
+    }
+
+    #[test]
+    fn test_free_busy_periods() {
+        let at = |hour: u32| Utc.with_ymd_and_hms(2025, 1, 6, hour, 0, 0).unwrap();
+        let period = |start: u32, end: u32, kind: FreeBusyType| FreeBusyPeriod {
+            start: at(start),
+            end: at(end),
+            kind,
+        };
+        let merged = merge_periods(vec![
+            period(14, 15, FreeBusyType::Busy),
+            period(9, 10, FreeBusyType::Busy),
+            period(9, 11, FreeBusyType::BusyTentative),
+            period(10, 12, FreeBusyType::Busy),
+        ]);
+        assert_eq!(
+            merged,
+            vec![
+                period(9, 12, FreeBusyType::Busy),
+                period(9, 11, FreeBusyType::BusyTentative),
+                period(14, 15, FreeBusyType::Busy),
+            ]
+        );
+
+        let text = free_busy_component(&merged, at(0), at(23)).to_ical();
+        assert!(text.contains("FREEBUSY;FBTYPE=BUSY:20250106T090000Z/20250106T120000Z\r\n"));
+        assert!(
+            text.contains("FREEBUSY;FBTYPE=BUSY-TENTATIVE:20250106T090000Z/20250106T110000Z\r\n")
+        );
+
+        let event = |extra: &str| {
+            ICalComponent::parse(&format!("BEGIN:VEVENT\r\n{}END:VEVENT\r\n", extra)).unwrap()
+        };
+        assert_eq!(busy_type(&event("")), Some(FreeBusyType::Busy));
+        assert_eq!(
+            busy_type(&event("STATUS:TENTATIVE\r\n")),
+            Some(FreeBusyType::BusyTentative)
+        );
+        assert_eq!(busy_type(&event("TRANSP:TRANSPARENT\r\n")), None);
+        assert_eq!(busy_type(&event("STATUS:CANCELLED\r\n")), None);
+    }
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+/// Prefijo de los tokens de sincronización WebDAV, que deben ser URIs
+const SYNC_TOKEN_PREFIX: &str = "urn:oxicloud:sync:";
+
+/// Tipo de cambio registrado en un recurso
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+    /// Movido o renombrado; la ruta anterior queda en `previous_path`
+    Moved,
+}
+
+/// Entrada del registro de cambios que alimenta la sincronización incremental
+/// (RFC 6578)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncChange {
+    /// Número de secuencia, creciente en todo el servidor
+    pub seq: u64,
+    pub kind: ChangeKind,
+    pub resource_id: String,
+    pub is_folder: bool,
+    /// Ruta normalizada del recurso tras el cambio
+    pub path: String,
+    /// Ruta normalizada antes de moverlo, solo en `Moved`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_path: Option<String>,
+    /// Fecha del cambio (segundos UNIX)
+    pub changed_at: u64,
+}
+
+impl SyncChange {
+    /// Indica si el cambio afecta a algún miembro de la colección `collection`,
+    /// ya sea en su ruta actual o en la que tenía antes de moverse
+    pub fn affects(&self, collection: &str) -> bool {
+        is_inside(&self.path, collection)
+            || self
+                .previous_path
+                .as_deref()
+                .is_some_and(|previous| is_inside(previous, collection))
+    }
+}
+
+/// Indica si `path` está por debajo de `collection` (sin ser ella misma)
+pub fn is_inside(path: &str, collection: &str) -> bool {
+    let path = path.trim_matches('/');
+    let collection = collection.trim_matches('/');
+    if collection.is_empty() {
+        return !path.is_empty();
+    }
+    path.strip_prefix(collection)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Indica si `path` es un miembro directo de `collection`
+pub fn is_direct_member(path: &str, collection: &str) -> bool {
+    let path = path.trim_matches('/');
+    is_inside(path, collection)
+        && !path[collection.trim_matches('/').len()..]
+            .trim_start_matches('/')
+            .contains('/')
+}
+
+/// Token de sincronización para el número de secuencia `seq`
+pub fn format_sync_token(seq: u64) -> String {
+    format!("{}{}", SYNC_TOKEN_PREFIX, seq)
+}
+
+/// Número de secuencia de un token de sincronización emitido por el servidor
+pub fn parse_sync_token(token: &str) -> Option<u64> {
+    token.trim().strip_prefix(SYNC_TOKEN_PREFIX)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_membership() {
+        assert!(is_inside("docs/a.txt", "docs"));
+        assert!(is_inside("/docs/sub/a.txt", "/docs/"));
+        assert!(!is_inside("docs", "docs"));
+        assert!(!is_inside("docs2/a.txt", "docs"));
+        assert!(is_inside("a.txt", ""));
+
+        assert!(is_direct_member("docs/a.txt", "docs"));
+        assert!(!is_direct_member("docs/sub/a.txt", "docs"));
+        assert!(is_direct_member("a.txt", ""));
+        assert!(!is_direct_member("docs/a.txt", ""));
+    }
+
+    #[test]
+    fn test_moves_affect_both_collections() {
+        let change = SyncChange {
+            seq: 7,
+            kind: ChangeKind::Moved,
+            resource_id: "f1".to_string(),
+            is_folder: false,
+            path: "archive/a.txt".to_string(),
+            previous_path: Some("docs/a.txt".to_string()),
+            changed_at: 0,
+        };
+        assert!(change.affects("docs"));
+        assert!(change.affects("archive"));
+        assert!(!change.affects("other"));
+    }
+
+    #[test]
+    fn test_sync_token_round_trip() {
+        assert_eq!(parse_sync_token(&format_sync_token(42)), Some(42));
+        assert_eq!(parse_sync_token("http://example.com/sync/42"), None);
+    }
+}
@@ -0,0 +1,132 @@
+/// Tamaños de miniatura disponibles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThumbnailSize {
+    /// Iconos de la vista en cuadrícula
+    Small,
+    /// Vista previa en la cuadrícula grande
+    Medium,
+    /// Vista previa a pantalla
+    Large,
+}
+
+impl ThumbnailSize {
+    pub const ALL: [ThumbnailSize; 3] = [Self::Small, Self::Medium, Self::Large];
+
+    /// Interpreta el valor del parámetro `size`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "small" | "s" | "128" => Some(Self::Small),
+            "medium" | "m" | "512" => Some(Self::Medium),
+            "large" | "l" | "1024" => Some(Self::Large),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Small => "small",
+            Self::Medium => "medium",
+            Self::Large => "large",
+        }
+    }
+
+    /// Lado mayor de la miniatura en píxeles
+    pub fn max_dimension(&self) -> u32 {
+        match self {
+            Self::Small => 128,
+            Self::Medium => 512,
+            Self::Large => 1024,
+        }
+    }
+}
+
+/// Formatos de salida de las miniaturas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThumbnailFormat {
+    Jpeg,
+    Webp,
+}
+
+impl ThumbnailFormat {
+    pub const ALL: [ThumbnailFormat; 2] = [Self::Jpeg, Self::Webp];
+
+    /// Interpreta el valor del parámetro `format`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "jpeg" | "jpg" => Some(Self::Jpeg),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+}
+
+/// Miniatura generada para una versión concreta del contenido de un archivo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub file_id: String,
+    pub size: ThumbnailSize,
+    pub format: ThumbnailFormat,
+    /// Huella del contenido original; cambia cuando se modifica el archivo
+    pub fingerprint: String,
+    pub content: Vec<u8>,
+}
+
+/// Tipos MIME de imagen a partir de los que se generan miniaturas
+const SUPPORTED_MIME_TYPES: &[&str] = &[
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/webp",
+    "image/bmp",
+    "image/tiff",
+];
+
+/// Indica si se pueden generar miniaturas para un tipo MIME
+pub fn supports_mime_type(mime_type: &str) -> bool {
+    let mime_type = mime_type.split(';').next().unwrap_or("").trim();
+    SUPPORTED_MIME_TYPES
+        .iter()
+        .any(|supported| supported.eq_ignore_ascii_case(mime_type))
+}
+
+/// Huella del contenido de un archivo a partir de su tamaño y fecha de
+/// modificación
+pub fn content_fingerprint(size: u64, modified_at: u64) -> String {
+    format!("{:x}-{:x}", modified_at, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sizes_and_formats() {
+        assert_eq!(ThumbnailSize::parse("Medium"), Some(ThumbnailSize::Medium));
+        assert_eq!(ThumbnailSize::parse("128"), Some(ThumbnailSize::Small));
+        assert_eq!(ThumbnailSize::parse("huge"), None);
+        assert_eq!(ThumbnailFormat::parse("jpg"), Some(ThumbnailFormat::Jpeg));
+        assert_eq!(ThumbnailFormat::Webp.content_type(), "image/webp");
+    }
+
+    #[test]
+    fn test_supported_mime_types() {
+        assert!(supports_mime_type("image/jpeg"));
+        assert!(supports_mime_type("IMAGE/PNG; charset=binary"));
+        assert!(!supports_mime_type("image/svg+xml"));
+        assert!(!supports_mime_type("application/pdf"));
+    }
+}
@@ -0,0 +1,783 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+
+use super::icalendar::{parse_local_date_time, ICalComponent, ICalProperty};
+use super::recurrence::RecurrenceRule;
+
+/// Zona que se usa cuando no hay otra
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// Reglas POSIX (como la última línea de los ficheros TZif) de las zonas
+/// IANA más usadas. Describen las reglas vigentes, no el histórico, y solo
+/// incluyen zonas cuyos cambios caen dentro del día local.
+const BUNDLED_ZONES: &[(&str, &str)] = &[
+    ("UTC", "UTC0"),
+    ("Etc/UTC", "UTC0"),
+    ("GMT", "GMT0"),
+    ("Etc/GMT", "GMT0"),
+    ("Europe/London", "GMT0BST,M3.5.0/1,M10.5.0"),
+    ("Europe/Dublin", "IST-1GMT0,M10.5.0,M3.5.0/1"),
+    ("Europe/Lisbon", "WET0WEST,M3.5.0/1,M10.5.0"),
+    ("Atlantic/Canary", "WET0WEST,M3.5.0/1,M10.5.0"),
+    ("Atlantic/Reykjavik", "GMT0"),
+    ("Europe/Madrid", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Paris", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Brussels", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Amsterdam", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Luxembourg", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Berlin", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Zurich", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Vienna", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Rome", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Copenhagen", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Oslo", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Stockholm", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Warsaw", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Prague", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Budapest", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Belgrade", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Zagreb", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Athens", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Bucharest", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Sofia", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Helsinki", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Riga", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Tallinn", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Vilnius", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Kyiv", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Kiev", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Istanbul", "<+03>-3"),
+    ("Europe/Minsk", "<+03>-3"),
+    ("Europe/Moscow", "MSK-3"),
+    ("Africa/Algiers", "CET-1"),
+    ("Africa/Lagos", "WAT-1"),
+    ("Africa/Johannesburg", "SAST-2"),
+    ("Africa/Nairobi", "EAT-3"),
+    ("America/St_Johns", "NST3:30NDT,M3.2.0,M11.1.0"),
+    ("America/Halifax", "AST4ADT,M3.2.0,M11.1.0"),
+    ("America/Puerto_Rico", "AST4"),
+    ("America/New_York", "EST5EDT,M3.2.0,M11.1.0"),
+    ("America/Toronto", "EST5EDT,M3.2.0,M11.1.0"),
+    ("America/Detroit", "EST5EDT,M3.2.0,M11.1.0"),
+    ("America/Indiana/Indianapolis", "EST5EDT,M3.2.0,M11.1.0"),
+    ("America/Chicago", "CST6CDT,M3.2.0,M11.1.0"),
+    ("America/Winnipeg", "CST6CDT,M3.2.0,M11.1.0"),
+    ("America/Mexico_City", "CST6"),
+    ("America/Denver", "MST7MDT,M3.2.0,M11.1.0"),
+    ("America/Edmonton", "MST7MDT,M3.2.0,M11.1.0"),
+    ("America/Phoenix", "MST7"),
+    ("America/Los_Angeles", "PST8PDT,M3.2.0,M11.1.0"),
+    ("America/Vancouver", "PST8PDT,M3.2.0,M11.1.0"),
+    ("America/Tijuana", "PST8PDT,M3.2.0,M11.1.0"),
+    ("America/Anchorage", "AKST9AKDT,M3.2.0,M11.1.0"),
+    ("Pacific/Honolulu", "HST10"),
+    ("America/Sao_Paulo", "<-03>3"),
+    ("America/Argentina/Buenos_Aires", "<-03>3"),
+    ("America/Caracas", "<-04>4"),
+    ("America/Bogota", "<-05>5"),
+    ("America/Lima", "<-05>5"),
+    ("Asia/Riyadh", "<+03>-3"),
+    ("Asia/Tehran", "<+0330>-3:30"),
+    ("Asia/Dubai", "<+04>-4"),
+    ("Asia/Karachi", "PKT-5"),
+    ("Asia/Kolkata", "IST-5:30"),
+    ("Asia/Calcutta", "IST-5:30"),
+    ("Asia/Kathmandu", "<+0545>-5:45"),
+    ("Asia/Dhaka", "<+06>-6"),
+    ("Asia/Bangkok", "<+07>-7"),
+    ("Asia/Ho_Chi_Minh", "<+07>-7"),
+    ("Asia/Jakarta", "WIB-7"),
+    ("Asia/Shanghai", "CST-8"),
+    ("Asia/Hong_Kong", "HKT-8"),
+    ("Asia/Taipei", "CST-8"),
+    ("Asia/Singapore", "<+08>-8"),
+    ("Asia/Manila", "PST-8"),
+    ("Australia/Perth", "AWST-8"),
+    ("Asia/Tokyo", "JST-9"),
+    ("Asia/Seoul", "KST-9"),
+    ("Australia/Darwin", "ACST-9:30"),
+    ("Australia/Adelaide", "ACST-9:30ACDT,M10.1.0,M4.1.0/3"),
+    ("Australia/Brisbane", "AEST-10"),
+    ("Australia/Sydney", "AEST-10AEDT,M10.1.0,M4.1.0/3"),
+    ("Australia/Melbourne", "AEST-10AEDT,M10.1.0,M4.1.0/3"),
+    ("Australia/Hobart", "AEST-10AEDT,M10.1.0,M4.1.0/3"),
+    ("Pacific/Auckland", "NZST-12NZDT,M9.5.0,M4.1.0/3"),
+];
+
+/// Zona horaria de un calendario: una del paquete de zonas o la definida
+/// por un componente `VTIMEZONE`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarTimeZone {
+    tzid: String,
+    rules: ZoneRules,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ZoneRules {
+    Posix(PosixRule),
+    /// Observancias `STANDARD` y `DAYLIGHT` de un `VTIMEZONE`, junto con el
+    /// componente original
+    Observances(Vec<Observance>, ICalComponent),
+}
+
+/// Regla POSIX: horario estándar y, si lo hay, de verano
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PosixRule {
+    std_name: String,
+    /// Segundos al este de UTC
+    std_offset: i32,
+    dst: Option<DaylightRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DaylightRule {
+    name: String,
+    offset: i32,
+    start: Transition,
+    end: Transition,
+}
+
+/// Cambio de horario `Mm.w.d/hora`: el día `d` de la semana `w` (5 es la
+/// última) del mes `m`, a una hora local en segundos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Transition {
+    month: u32,
+    week: u8,
+    weekday: Weekday,
+    time: i32,
+}
+
+/// Observancia de un `VTIMEZONE` (RFC 5545, sección 3.6.5)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Observance {
+    name: Option<String>,
+    /// Inicio en hora local, con el desplazamiento anterior
+    start: NaiveDateTime,
+    offset_from: i32,
+    offset_to: i32,
+    rules: Vec<RecurrenceRule>,
+    dates: Vec<NaiveDateTime>,
+}
+
+impl CalendarTimeZone {
+    /// Zona del paquete de zonas por su nombre IANA. También reconoce los
+    /// TZID con prefijo, como `/mozilla.org/20050126_1/Europe/Berlin`.
+    pub fn bundled(tzid: &str) -> Option<Self> {
+        let tzid = tzid.trim();
+        let segments: Vec<&str> = tzid.split('/').collect();
+        (0..segments.len()).find_map(|skip| {
+            let name = segments[skip..].join("/");
+            let (name, rule) = BUNDLED_ZONES
+                .iter()
+                .find(|(zone, _)| zone.eq_ignore_ascii_case(&name))?;
+            Some(Self {
+                tzid: name.to_string(),
+                rules: ZoneRules::Posix(PosixRule::parse(rule)?),
+            })
+        })
+    }
+
+    /// Zona definida por un componente `VTIMEZONE`
+    pub fn from_vtimezone(component: &ICalComponent) -> Option<Self> {
+        let tzid = component.property("TZID")?.value.trim().to_string();
+        let observances: Vec<Observance> = component
+            .components
+            .iter()
+            .filter(|c| c.name == "STANDARD" || c.name == "DAYLIGHT")
+            .filter_map(Observance::parse)
+            .collect();
+        if tzid.is_empty() || observances.is_empty() {
+            return None;
+        }
+        Some(Self {
+            tzid,
+            rules: ZoneRules::Observances(observances, component.clone()),
+        })
+    }
+
+    pub fn tzid(&self) -> &str {
+        &self.tzid
+    }
+
+    /// Desplazamiento en segundos al este de UTC en un instante
+    pub fn offset_at(&self, instant: DateTime<Utc>) -> i32 {
+        let utc = instant.naive_utc();
+        match &self.rules {
+            ZoneRules::Posix(rule) => rule.offset_at(utc),
+            ZoneRules::Observances(observances, _) => {
+                let mut latest: Option<(NaiveDateTime, i32)> = None;
+                for observance in observances {
+                    if let Some(onset) = observance.last_onset(utc) {
+                        if latest.is_none_or(|(time, _)| onset > time) {
+                            latest = Some((onset, observance.offset_to));
+                        }
+                    }
+                }
+                match latest {
+                    Some((_, offset)) => offset,
+                    // Antes de la primera observancia rige su desplazamiento anterior
+                    None => observances
+                        .iter()
+                        .min_by_key(|observance| observance.start)
+                        .map_or(0, |observance| observance.offset_from),
+                }
+            }
+        }
+    }
+
+    /// Hora local de un instante
+    pub fn to_local(&self, instant: DateTime<Utc>) -> NaiveDateTime {
+        instant.naive_utc() + Duration::seconds(self.offset_at(instant).into())
+    }
+
+    /// Instante de una hora local. Las horas repetidas al atrasar el reloj
+    /// son la primera vez; las que no existen al adelantarlo se leen con el
+    /// desplazamiento anterior (RFC 5545, sección 3.3.5).
+    pub fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let around =
+            |days: i64| self.offset_at(Utc.from_utc_datetime(&(local + Duration::days(days))));
+        let (before, after) = (around(-1), around(1));
+        let at = |offset: i32| Utc.from_utc_datetime(&(local - Duration::seconds(offset.into())));
+
+        [before, after]
+            .into_iter()
+            .map(at)
+            .filter(|instant| self.to_local(*instant) == local)
+            .min()
+            .unwrap_or_else(|| at(before))
+    }
+
+    /// Componente `VTIMEZONE` de la zona: el original o uno generado a
+    /// partir de la regla del paquete
+    pub fn to_vtimezone(&self) -> ICalComponent {
+        match &self.rules {
+            ZoneRules::Observances(_, component) => component.clone(),
+            ZoneRules::Posix(rule) => {
+                let mut observances = Vec::new();
+                match &rule.dst {
+                    None => observances.push(observance_component(
+                        "STANDARD",
+                        &rule.std_name,
+                        NaiveDate::from_ymd_opt(1970, 1, 1)
+                            .unwrap_or_default()
+                            .and_time(NaiveTime::MIN),
+                        rule.std_offset,
+                        rule.std_offset,
+                        None,
+                    )),
+                    Some(dst) => {
+                        observances.push(observance_component(
+                            "DAYLIGHT",
+                            &dst.name,
+                            dst.start.local(1970),
+                            rule.std_offset,
+                            dst.offset,
+                            Some(&dst.start),
+                        ));
+                        observances.push(observance_component(
+                            "STANDARD",
+                            &rule.std_name,
+                            dst.end.local(1970),
+                            dst.offset,
+                            rule.std_offset,
+                            Some(&dst.end),
+                        ));
+                    }
+                }
+                ICalComponent {
+                    name: "VTIMEZONE".to_string(),
+                    properties: vec![ICalProperty::new("TZID", self.tzid.clone())],
+                    components: observances,
+                }
+            }
+        }
+    }
+
+    /// Objeto `VCALENDAR` con solo la zona, como el valor de la propiedad
+    /// `calendar-timezone` de CalDAV (RFC 4791, sección 5.2.2)
+    pub fn to_vcalendar(&self) -> ICalComponent {
+        ICalComponent {
+            name: "VCALENDAR".to_string(),
+            properties: vec![
+                ICalProperty::new("VERSION", "2.0"),
+                ICalProperty::new("PRODID", "-//OxiCloud//CalDAV//EN"),
+            ],
+            components: vec![self.to_vtimezone()],
+        }
+    }
+}
+
+/// Subcomponente `STANDARD` o `DAYLIGHT` generado
+fn observance_component(
+    name: &str,
+    tzname: &str,
+    start: NaiveDateTime,
+    offset_from: i32,
+    offset_to: i32,
+    transition: Option<&Transition>,
+) -> ICalComponent {
+    let mut properties = vec![
+        ICalProperty::new("DTSTART", start.format("%Y%m%dT%H%M%S").to_string()),
+        ICalProperty::new("TZOFFSETFROM", format_offset(offset_from)),
+        ICalProperty::new("TZOFFSETTO", format_offset(offset_to)),
+        ICalProperty::new("TZNAME", tzname),
+    ];
+    if let Some(transition) = transition {
+        let week = if transition.week == 5 {
+            -1
+        } else {
+            i32::from(transition.week)
+        };
+        properties.push(ICalProperty::new(
+            "RRULE",
+            format!(
+                "FREQ=YEARLY;BYMONTH={};BYDAY={}{}",
+                transition.month,
+                week,
+                weekday_code(transition.weekday)
+            ),
+        ));
+    }
+    ICalComponent {
+        name: name.to_string(),
+        properties,
+        components: Vec::new(),
+    }
+}
+
+impl PosixRule {
+    /// Interpreta una cadena como `CET-1CEST,M3.5.0,M10.5.0/3`
+    fn parse(value: &str) -> Option<Self> {
+        let mut rest = value;
+        let std_name = take_name(&mut rest)?;
+        let std_offset = -take_offset(&mut rest)?;
+        if rest.is_empty() {
+            return Some(Self {
+                std_name,
+                std_offset,
+                dst: None,
+            });
+        }
+
+        let dst_name = take_name(&mut rest)?;
+        let dst_offset = match rest.starts_with(',') {
+            true => std_offset + 3600,
+            false => -take_offset(&mut rest)?,
+        };
+        let mut transitions = rest.strip_prefix(',')?.split(',');
+        let start = Transition::parse(transitions.next()?)?;
+        let end = Transition::parse(transitions.next()?)?;
+        Some(Self {
+            std_name,
+            std_offset,
+            dst: Some(DaylightRule {
+                name: dst_name,
+                offset: dst_offset,
+                start,
+                end,
+            }),
+        })
+    }
+
+    fn offset_at(&self, utc: NaiveDateTime) -> i32 {
+        let Some(dst) = &self.dst else {
+            return self.std_offset;
+        };
+        let year = utc.year();
+        let start = dst.start.local(year) - Duration::seconds(self.std_offset.into());
+        let end = dst.end.local(year) - Duration::seconds(dst.offset.into());
+        // En el hemisferio sur el horario de verano cruza el año
+        let in_dst = if start < end {
+            utc >= start && utc < end
+        } else {
+            utc >= start || utc < end
+        };
+        if in_dst {
+            dst.offset
+        } else {
+            self.std_offset
+        }
+    }
+}
+
+impl Transition {
+    fn parse(value: &str) -> Option<Self> {
+        let (date, time) = match value.split_once('/') {
+            Some((date, time)) => (date, parse_offset_seconds(time)?),
+            None => (value, 7200),
+        };
+        let mut parts = date.strip_prefix('M')?.split('.');
+        let month = parts
+            .next()?
+            .parse()
+            .ok()
+            .filter(|m| (1..=12).contains(m))?;
+        let week = parts.next()?.parse().ok().filter(|w| (1..=5).contains(w))?;
+        let weekday = match parts.next()?.parse::<u8>().ok()? {
+            0 => Weekday::Sun,
+            day @ 1..=6 => Weekday::try_from(day - 1).ok()?,
+            _ => return None,
+        };
+        Some(Self {
+            month,
+            week,
+            weekday,
+            time,
+        })
+    }
+
+    /// Hora local del cambio en un año
+    fn local(&self, year: i32) -> NaiveDateTime {
+        let date = NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, self.week)
+            .or_else(|| {
+                NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, self.week - 1)
+            })
+            .unwrap_or_default();
+        date.and_time(NaiveTime::MIN) + Duration::seconds(self.time.into())
+    }
+}
+
+impl Observance {
+    fn parse(component: &ICalComponent) -> Option<Self> {
+        let offset = |name: &str| {
+            component
+                .property(name)
+                .and_then(|property| parse_utc_offset(&property.value))
+        };
+        let rules = component
+            .properties
+            .iter()
+            .filter(|property| property.name == "RRULE")
+            .filter_map(|property| RecurrenceRule::parse(&property.value).ok())
+            .collect();
+        let dates = component
+            .properties
+            .iter()
+            .filter(|property| property.name == "RDATE")
+            .flat_map(|property| property.value.split(','))
+            .filter_map(parse_local_date_time)
+            .collect();
+        Some(Self {
+            name: component.property("TZNAME").map(ICalProperty::text),
+            start: parse_local_date_time(&component.property("DTSTART")?.value)?,
+            offset_from: offset("TZOFFSETFROM")?,
+            offset_to: offset("TZOFFSETTO")?,
+            rules,
+            dates,
+        })
+    }
+
+    /// Último comienzo de la observancia hasta un instante, en UTC
+    fn last_onset(&self, utc: NaiveDateTime) -> Option<NaiveDateTime> {
+        let limit = utc + Duration::seconds(self.offset_from.into());
+        let mut onsets = vec![self.start];
+        for rule in &self.rules {
+            onsets.extend(rule.instances(self.start, self.start, limit));
+        }
+        onsets.extend(self.dates.iter().copied());
+        onsets
+            .into_iter()
+            .filter(|onset| *onset <= limit)
+            .max()
+            .map(|onset| onset - Duration::seconds(self.offset_from.into()))
+    }
+}
+
+/// Zonas con las que se leen las horas de un objeto de calendario: las de
+/// sus `VTIMEZONE`, las del paquete para los TZID sin definición y, para las
+/// horas flotantes, una zona por defecto
+#[derive(Debug, Clone, Default)]
+pub struct TimeZones {
+    zones: HashMap<String, CalendarTimeZone>,
+    floating: Option<CalendarTimeZone>,
+}
+
+impl TimeZones {
+    /// Zonas de un objeto `VCALENDAR`; `default_timezone` se aplica a las
+    /// horas flotantes, que sin ella se toman como UTC
+    pub fn for_calendar(calendar: &ICalComponent, default_timezone: Option<&str>) -> Self {
+        let mut zones: HashMap<String, CalendarTimeZone> = calendar
+            .components_named("VTIMEZONE")
+            .filter_map(CalendarTimeZone::from_vtimezone)
+            .map(|zone| (zone.tzid.clone(), zone))
+            .collect();
+
+        let mut referenced = Vec::new();
+        collect_tzids(calendar, &mut referenced);
+        for tzid in referenced {
+            if let Entry::Vacant(entry) = zones.entry(tzid) {
+                if let Some(zone) = CalendarTimeZone::bundled(entry.key()) {
+                    entry.insert(zone);
+                }
+            }
+        }
+
+        Self {
+            zones,
+            floating: default_timezone.and_then(CalendarTimeZone::bundled),
+        }
+    }
+
+    /// Zona de una propiedad de fecha; `None` para las horas en UTC, las
+    /// fechas sin hora y los TZID desconocidos
+    pub fn zone_of(&self, property: &ICalProperty) -> Option<&CalendarTimeZone> {
+        if property.is_date() || property.value.trim().ends_with('Z') {
+            return None;
+        }
+        match property.param("TZID") {
+            Some(tzid) => self.zones.get(tzid),
+            None => self.floating.as_ref(),
+        }
+    }
+
+    /// Instante de una propiedad DATE o DATE-TIME
+    pub fn date_time(&self, property: &ICalProperty) -> Option<DateTime<Utc>> {
+        self.resolve(property, &property.value)
+    }
+
+    /// Instantes de una propiedad con varios valores, como `RDATE` o
+    /// `EXDATE`; de los periodos `inicio/fin` se toma el inicio
+    pub fn date_times(&self, property: &ICalProperty) -> Vec<DateTime<Utc>> {
+        property
+            .value
+            .split(',')
+            .filter_map(|value| self.resolve(property, value.split('/').next().unwrap_or(value)))
+            .collect()
+    }
+
+    fn resolve(&self, property: &ICalProperty, value: &str) -> Option<DateTime<Utc>> {
+        let local = parse_local_date_time(value)?;
+        Some(match self.zone_of(property) {
+            Some(zone) => zone.to_utc(local),
+            None => Utc.from_utc_datetime(&local),
+        })
+    }
+}
+
+/// TZID usados en las propiedades de un componente y sus subcomponentes
+fn collect_tzids(component: &ICalComponent, tzids: &mut Vec<String>) {
+    for property in &component.properties {
+        if let Some(tzid) = property.param("TZID") {
+            if !tzids.iter().any(|known| known == tzid) {
+                tzids.push(tzid.to_string());
+            }
+        }
+    }
+    for child in &component.components {
+        collect_tzids(child, tzids);
+    }
+}
+
+/// Nombre de zona POSIX: letras o `<...>`
+fn take_name(rest: &mut &str) -> Option<String> {
+    let (name, remaining) = match rest.strip_prefix('<') {
+        Some(quoted) => {
+            let end = quoted.find('>')?;
+            (&quoted[..end], &quoted[end + 1..])
+        }
+        None => {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        }
+    };
+    if name.len() < 3 {
+        return None;
+    }
+    *rest = remaining;
+    Some(name.to_string())
+}
+
+/// Desplazamiento POSIX `[+-]hh[:mm[:ss]]`, en segundos al oeste de UTC
+fn take_offset(rest: &mut &str) -> Option<i32> {
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '+' | '-' | ':')))
+        .unwrap_or(rest.len());
+    let offset = parse_offset_seconds(&rest[..end])?;
+    *rest = &rest[end..];
+    Some(offset)
+}
+
+fn parse_offset_seconds(value: &str) -> Option<i32> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut parts = value.split(':');
+    let hours: i32 = parts.next()?.parse().ok()?;
+    let minutes: i32 = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    let seconds: i32 = parts.next().map_or(Some(0), |s| s.parse().ok())?;
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// Interpreta un valor UTC-OFFSET como `+0100` o `-033000`
+fn parse_utc_offset(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let sign = match value.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = &value[1..];
+    if !(digits.len() == 4 || digits.len() == 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| digits.get(range).map_or(Ok(0), str::parse::<i32>);
+    let (hours, minutes, seconds) = (field(0..2).ok()?, field(2..4).ok()?, field(4..6).ok()?);
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// Da formato UTC-OFFSET a un desplazamiento en segundos
+fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    let (hours, minutes, seconds) = (offset / 3600, offset % 3600 / 60, offset % 60);
+    if seconds == 0 {
+        format!("{}{:02}{:02}", sign, hours, minutes)
+    } else {
+        format!("{}{:02}{:02}{:02}", sign, hours, minutes, seconds)
+    }
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(value: &str) -> NaiveDateTime {
+        parse_local_date_time(value).unwrap()
+    }
+
+    #[test]
+    fn test_bundled_zone_transitions() {
+        let berlin = CalendarTimeZone::bundled("/mozilla.org/20050126_1/Europe/Berlin").unwrap();
+        assert_eq!(berlin.tzid(), "Europe/Berlin");
+        assert_eq!(
+            berlin.to_utc(local("20240115T100000")),
+            Utc.with_ymd_and_hms(2024, 1, 15, 9, 0, 0).unwrap()
+        );
+        assert_eq!(
+            berlin.to_utc(local("20240715T100000")),
+            Utc.with_ymd_and_hms(2024, 7, 15, 8, 0, 0).unwrap()
+        );
+        // 02:30 no existe el 31 de marzo; se lee con el horario de invierno
+        assert_eq!(
+            berlin.to_utc(local("20240331T023000")),
+            Utc.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap()
+        );
+        // 02:30 se repite el 27 de octubre; cuenta la primera vez
+        assert_eq!(
+            berlin.to_utc(local("20241027T023000")),
+            Utc.with_ymd_and_hms(2024, 10, 27, 0, 30, 0).unwrap()
+        );
+
+        let sydney = CalendarTimeZone::bundled("Australia/Sydney").unwrap();
+        assert_eq!(
+            sydney.offset_at(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()),
+            11 * 3600
+        );
+        assert_eq!(
+            sydney.offset_at(Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap()),
+            10 * 3600
+        );
+        assert_eq!(
+            CalendarTimeZone::bundled("Asia/Kolkata")
+                .unwrap()
+                .offset_at(Utc::now()),
+            5 * 3600 + 1800
+        );
+        assert!(CalendarTimeZone::bundled("Mars/Olympus_Mons").is_none());
+    }
+
+    #[test]
+    fn test_generated_vtimezone_round_trip() {
+        let new_york = CalendarTimeZone::bundled("America/New_York").unwrap();
+        let text = new_york.to_vcalendar().to_ical();
+        assert!(text.contains("RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU"));
+        assert!(text.contains("TZOFFSETTO:-0400"));
+
+        let calendar = ICalComponent::parse(&text).unwrap();
+        let parsed = CalendarTimeZone::from_vtimezone(
+            calendar.components_named("VTIMEZONE").next().unwrap(),
+        )
+        .unwrap();
+        for value in [
+            "20240310T013000",
+            "20240310T033000",
+            "20240801T120000",
+            "20241103T013000",
+        ] {
+            assert_eq!(parsed.to_utc(local(value)), new_york.to_utc(local(value)));
+        }
+    }
+
+    #[test]
+    fn test_time_zones_of_a_calendar() {
+        let calendar = ICalComponent::parse(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VTIMEZONE\r\n\
+             TZID:W. Europe Standard Time\r\n\
+             BEGIN:STANDARD\r\n\
+             DTSTART:16010101T030000\r\n\
+             TZOFFSETFROM:+0200\r\n\
+             TZOFFSETTO:+0100\r\n\
+             RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=10\r\n\
+             END:STANDARD\r\n\
+             BEGIN:DAYLIGHT\r\n\
+             DTSTART:16010101T020000\r\n\
+             TZOFFSETFROM:+0100\r\n\
+             TZOFFSETTO:+0200\r\n\
+             RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3\r\n\
+             END:DAYLIGHT\r\n\
+             END:VTIMEZONE\r\n\
+             BEGIN:VEVENT\r\n\
+             DTSTART;TZID=W. Europe Standard Time:20240601T090000\r\n\
+             DTEND;TZID=Asia/Tokyo:20240601T180000\r\n\
+             RDATE:20240602T090000\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        )
+        .unwrap();
+        let event = calendar.components_named("VEVENT").next().unwrap();
+        let zones = TimeZones::for_calendar(&calendar, Some("America/Chicago"));
+
+        let start = event.property("DTSTART").unwrap();
+        assert_eq!(
+            zones.zone_of(start).unwrap().tzid(),
+            "W. Europe Standard Time"
+        );
+        assert_eq!(
+            zones.date_time(start),
+            Utc.with_ymd_and_hms(2024, 6, 1, 7, 0, 0).single()
+        );
+        assert_eq!(
+            zones.date_time(event.property("DTEND").unwrap()),
+            Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).single()
+        );
+        // Las horas flotantes usan la zona por defecto
+        assert_eq!(
+            zones.date_times(event.property("RDATE").unwrap()),
+            vec![Utc.with_ymd_and_hms(2024, 6, 2, 14, 0, 0).unwrap()]
+        );
+        assert_eq!(
+            TimeZones::default().date_time(event.property("RDATE").unwrap()),
+            Utc.with_ymd_and_hms(2024, 6, 2, 9, 0, 0).single()
+        );
+    }
+}
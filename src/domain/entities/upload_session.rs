@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Subida reanudable en curso
+///
+/// Se crea al anunciar la subida con su tamaño total y se completa a base de
+/// anexar fragmentos. Al llegar a `length` el contenido se entrega al
+/// almacenamiento definitivo y la sesión se elimina.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UploadSession {
+    /// Identificador único de la subida
+    pub id: String,
+    /// Usuario que creó la subida, el único que puede continuarla
+    #[serde(default)]
+    pub owner: String,
+    /// Tamaño total anunciado en bytes
+    pub length: u64,
+    /// Bytes recibidos hasta ahora
+    pub offset: u64,
+    /// Nombre del archivo final
+    pub name: String,
+    /// Tipo MIME del archivo final
+    pub content_type: String,
+    /// Carpeta de destino
+    pub folder_id: Option<String>,
+    /// Metadatos enviados por el cliente al crear la subida
+    pub metadata: HashMap<String, String>,
+    /// Fecha de creación (segundos UNIX)
+    pub created_at: u64,
+    /// Fecha a partir de la cual la subida se considera abandonada (segundos UNIX)
+    pub expires_at: u64,
+}
+
+impl UploadSession {
+    /// Crea una nueva sesión de subida con un identificador aleatorio
+    pub fn new(
+        length: u64,
+        name: String,
+        content_type: String,
+        folder_id: Option<String>,
+        metadata: HashMap<String, String>,
+        created_at: u64,
+        expires_at: u64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().simple().to_string(),
+            owner: String::new(),
+            length,
+            offset: 0,
+            name,
+            content_type,
+            folder_id,
+            metadata,
+            created_at,
+            expires_at,
+        }
+    }
+
+    /// Asigna el usuario propietario de la subida
+    pub fn with_owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = owner.into();
+        self
+    }
+
+    /// Indica si ya se ha recibido todo el contenido
+    pub fn is_complete(&self) -> bool {
+        self.offset >= self.length
+    }
+
+    /// Indica si la subida ha caducado en el instante `now`
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Bytes que faltan por recibir
+    pub fn remaining(&self) -> u64 {
+        self.length.saturating_sub(self.offset)
+    }
+}
+
+/// Algoritmos admitidos para verificar la integridad de los fragmentos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Sha1,
+    Sha256,
+    Md5,
+}
+
+impl ChecksumAlgorithm {
+    /// Todos los algoritmos admitidos
+    pub const ALL: [ChecksumAlgorithm; 3] = [
+        ChecksumAlgorithm::Sha1,
+        ChecksumAlgorithm::Sha256,
+        ChecksumAlgorithm::Md5,
+    ];
+
+    /// Interpreta el nombre del algoritmo (`sha1`, `sha256`, `md5`)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha1" => Some(ChecksumAlgorithm::Sha1),
+            "sha256" => Some(ChecksumAlgorithm::Sha256),
+            "md5" => Some(ChecksumAlgorithm::Md5),
+            _ => None,
+        }
+    }
+
+    /// Nombre del algoritmo
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha1 => "sha1",
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Md5 => "md5",
+        }
+    }
+}
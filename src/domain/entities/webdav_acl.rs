@@ -0,0 +1,333 @@
+use std::collections::BTreeSet;
+
+use crate::domain::entities::content_index::{owner_for_path, SHARED_OWNER};
+
+/// Colección con los principales de los usuarios
+pub const USER_PRINCIPALS_PATH: &str = "/principals/users/";
+
+/// Colección con las colecciones de calendarios de los usuarios
+pub const CALENDAR_HOMES_PATH: &str = "/caldav/calendars/";
+
+/// Privilegios WebDAV (RFC 3744, sección 3) que el servidor concede
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DavPrivilege {
+    Read,
+    ReadAcl,
+    ReadCurrentUserPrivilegeSet,
+    Write,
+    WriteProperties,
+    WriteContent,
+    Bind,
+    Unbind,
+    Unlock,
+}
+
+impl DavPrivilege {
+    /// Nombre del elemento XML en el espacio de nombres `DAV:`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::ReadAcl => "read-acl",
+            Self::ReadCurrentUserPrivilegeSet => "read-current-user-privilege-set",
+            Self::Write => "write",
+            Self::WriteProperties => "write-properties",
+            Self::WriteContent => "write-content",
+            Self::Bind => "bind",
+            Self::Unbind => "unbind",
+            Self::Unlock => "unlock",
+        }
+    }
+}
+
+/// Conjunto de privilegios de un principal sobre un recurso
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrivilegeSet {
+    privileges: BTreeSet<DavPrivilege>,
+}
+
+impl PrivilegeSet {
+    fn of(privileges: &[DavPrivilege]) -> Self {
+        Self {
+            privileges: privileges.iter().copied().collect(),
+        }
+    }
+
+    /// Sin ningún privilegio
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Lectura del contenido y de los propios privilegios
+    pub fn read() -> Self {
+        Self::of(&[
+            DavPrivilege::Read,
+            DavPrivilege::ReadCurrentUserPrivilegeSet,
+        ])
+    }
+
+    /// Lectura y escritura: `DAV:write` agrega el resto de privilegios de escritura
+    pub fn read_write() -> Self {
+        Self::read().union(&Self::of(&[
+            DavPrivilege::Write,
+            DavPrivilege::WriteProperties,
+            DavPrivilege::WriteContent,
+            DavPrivilege::Bind,
+            DavPrivilege::Unbind,
+            DavPrivilege::Unlock,
+        ]))
+    }
+
+    /// Todo lo que tiene el propietario; la ACL nunca se puede modificar
+    pub fn full() -> Self {
+        Self::read_write().union(&Self::of(&[DavPrivilege::ReadAcl]))
+    }
+
+    /// Privilegios que concede un enlace compartido con estos permisos
+    pub fn from_share(read: bool, write: bool) -> Self {
+        match (read, write) {
+            (_, true) => Self::read_write(),
+            (true, false) => Self::read(),
+            (false, false) => Self::none(),
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            privileges: self.privileges.union(&other.privileges).copied().collect(),
+        }
+    }
+
+    pub fn contains(&self, privilege: DavPrivilege) -> bool {
+        self.privileges.contains(&privilege)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.privileges.is_empty()
+    }
+
+    /// Privilegios a informar: los agregados ocultan a los que contienen
+    pub fn reported(&self) -> Vec<DavPrivilege> {
+        let aggregated = [
+            DavPrivilege::WriteProperties,
+            DavPrivilege::WriteContent,
+            DavPrivilege::Bind,
+            DavPrivilege::Unbind,
+        ];
+        self.privileges
+            .iter()
+            .copied()
+            .filter(|privilege| {
+                !(self.contains(DavPrivilege::Write) && aggregated.contains(privilege))
+            })
+            .collect()
+    }
+}
+
+/// Principal al que se aplica una entrada de la ACL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AclPrincipal {
+    /// Un usuario concreto, por nombre
+    User(String),
+    /// Cualquier usuario autenticado (`DAV:authenticated`)
+    Authenticated,
+}
+
+/// Entrada de la ACL de un recurso; el servidor solo concede, nunca deniega
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessControlEntry {
+    pub principal: AclPrincipal,
+    pub grant: PrivilegeSet,
+}
+
+impl AccessControlEntry {
+    pub fn new(principal: AclPrincipal, grant: PrivilegeSet) -> Self {
+        Self { principal, grant }
+    }
+
+    fn applies_to(&self, username: &str) -> bool {
+        match &self.principal {
+            AclPrincipal::User(name) => name == username,
+            AclPrincipal::Authenticated => true,
+        }
+    }
+}
+
+/// Privilegios de un usuario según una ACL: la unión de sus entradas
+pub fn privileges_for(acl: &[AccessControlEntry], username: &str) -> PrivilegeSet {
+    acl.iter()
+        .filter(|entry| entry.applies_to(username))
+        .fold(PrivilegeSet::none(), |privileges, entry| {
+            privileges.union(&entry.grant)
+        })
+}
+
+/// ACL de un recurso a partir de su propietario y de lo que conceden los
+/// enlaces compartidos sobre él o sus carpetas.
+///
+/// Lo que está fuera de las carpetas personales lo leen y escriben todos los
+/// usuarios; lo que está dentro es de su propietario y del resto de usuarios
+/// solo en la medida en que él lo haya compartido.
+pub fn resource_acl(owner: Option<&str>, shared: &PrivilegeSet) -> Vec<AccessControlEntry> {
+    match owner {
+        Some(owner) => {
+            let mut acl = vec![AccessControlEntry::new(
+                AclPrincipal::User(owner.to_string()),
+                PrivilegeSet::full(),
+            )];
+            if !shared.is_empty() {
+                acl.push(AccessControlEntry::new(
+                    AclPrincipal::Authenticated,
+                    shared.clone(),
+                ));
+            }
+            acl
+        }
+        None => vec![AccessControlEntry::new(
+            AclPrincipal::Authenticated,
+            PrivilegeSet::read_write(),
+        )],
+    }
+}
+
+/// Propietario de un recurso WebDAV por su ruta, tal como llega en la URL;
+/// `None` si está fuera de las carpetas personales
+pub fn resource_owner(path: &str) -> Option<String> {
+    Some(owner_for_path(&percent_decode(path))).filter(|owner| owner != SHARED_OWNER)
+}
+
+/// Decodifica los `%XX` de una ruta, dejando intactas las secuencias inválidas
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Principal de un usuario (RFC 3744, sección 2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    /// Nombre de usuario, que forma la URL del principal
+    pub name: String,
+    pub display_name: String,
+    pub email: Option<String>,
+}
+
+impl Principal {
+    /// URL del principal de un usuario
+    pub fn href_for(name: &str) -> String {
+        format!("{}{}/", USER_PRINCIPALS_PATH, name)
+    }
+
+    pub fn href(&self) -> String {
+        Self::href_for(&self.name)
+    }
+
+    /// Colección con los calendarios de un usuario (RFC 4791, sección 6.2.1)
+    pub fn calendar_home_for(name: &str) -> String {
+        format!("{}{}/", CALENDAR_HOMES_PATH, name)
+    }
+
+    /// Bandeja de entrada de planificación de un usuario (RFC 6638,
+    /// sección 2.2), dentro de su colección de calendarios
+    pub fn schedule_inbox_for(name: &str) -> String {
+        format!("{}inbox/", Self::calendar_home_for(name))
+    }
+
+    /// Bandeja de salida de planificación de un usuario (RFC 6638,
+    /// sección 2.1)
+    pub fn schedule_outbox_for(name: &str) -> String {
+        format!("{}outbox/", Self::calendar_home_for(name))
+    }
+
+    /// Indica si el principal cumple un criterio de `principal-property-search`
+    pub fn matches(&self, criterion: &PrincipalMatch) -> bool {
+        let needle = criterion.text.to_lowercase();
+        let value = match criterion.property {
+            PrincipalProperty::DisplayName => Some(&self.display_name),
+            PrincipalProperty::Email => self.email.as_ref(),
+        };
+        value.is_some_and(|value| value.to_lowercase().contains(&needle))
+    }
+}
+
+/// Propiedades de los principales por las que se puede buscar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrincipalProperty {
+    /// `DAV:displayname`
+    DisplayName,
+    /// `CALDAV:calendar-user-address-set` o la dirección de correo
+    Email,
+}
+
+/// Criterio de un `principal-property-search`: la propiedad contiene el texto
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrincipalMatch {
+    pub property: PrincipalProperty,
+    pub text: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_privileges_follow_ownership_and_shares() {
+        let shared_area = resource_acl(None, &PrivilegeSet::none());
+        assert!(privileges_for(&shared_area, "bob").contains(DavPrivilege::WriteContent));
+
+        let private = resource_acl(Some("alice"), &PrivilegeSet::none());
+        assert_eq!(privileges_for(&private, "alice"), PrivilegeSet::full());
+        assert!(privileges_for(&private, "bob").is_empty());
+
+        let read_only = resource_acl(Some("alice"), &PrivilegeSet::from_share(true, false));
+        let bob = privileges_for(&read_only, "bob");
+        assert!(bob.contains(DavPrivilege::Read));
+        assert!(!bob.contains(DavPrivilege::Write));
+        assert!(!bob.contains(DavPrivilege::Bind));
+        assert_eq!(privileges_for(&read_only, "alice"), PrivilegeSet::full());
+    }
+
+    #[test]
+    fn test_owner_comes_from_the_home_folder() {
+        assert_eq!(
+            resource_owner("Mi Carpeta - alice/a.txt"),
+            Some("alice".into())
+        );
+        assert_eq!(
+            resource_owner("Mi%20Carpeta%20-%20bob/"),
+            Some("bob".into())
+        );
+        assert_eq!(resource_owner("Proyectos/plan.md"), None);
+        assert_eq!(resource_owner(""), None);
+        assert_eq!(percent_decode("a%2"), "a%2");
+    }
+
+    #[test]
+    fn test_write_hides_the_privileges_it_aggregates() {
+        let reported: Vec<_> = PrivilegeSet::read_write()
+            .reported()
+            .iter()
+            .map(|privilege| privilege.name())
+            .collect();
+        assert_eq!(
+            reported,
+            vec!["read", "read-current-user-privilege-set", "write", "unlock"]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Alcance de un bloqueo de escritura
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockScope {
+    /// Solo el titular del bloqueo puede escribir
+    Exclusive,
+    /// Varios bloqueos compartidos pueden convivir sobre el mismo recurso
+    Shared,
+}
+
+/// Profundidad de un bloqueo sobre una colección
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockDepth {
+    /// Solo el recurso bloqueado
+    Zero,
+    /// El recurso y todo lo que contiene
+    Infinity,
+}
+
+impl LockDepth {
+    /// Interpreta la cabecera `Depth` de una petición LOCK
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "0" => Some(Self::Zero),
+            "infinity" => Some(Self::Infinity),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Zero => "0",
+            Self::Infinity => "infinity",
+        }
+    }
+}
+
+/// Tipo de escritura que se comprueba contra los bloqueos existentes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockedOperation {
+    /// Modifica el propio recurso (PUT sobre un archivo existente, PROPPATCH)
+    Modify,
+    /// Añade un miembro a una colección (PUT nuevo, MKCOL, destino de COPY/MOVE)
+    Create,
+    /// Quita el recurso y todo lo que contiene (DELETE, origen de MOVE)
+    Remove,
+}
+
+/// Normaliza la ruta de un recurso WebDAV: sin barras al principio ni al final
+pub fn normalize_lock_path(path: &str) -> String {
+    path.trim_matches('/').to_string()
+}
+
+/// Indica si `path` es `ancestor` o está dentro de él
+fn is_within(path: &str, ancestor: &str) -> bool {
+    ancestor.is_empty()
+        || path == ancestor
+        || path
+            .strip_prefix(ancestor)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Colección que contiene a `path`, o la raíz
+fn parent_path(path: &str) -> &str {
+    path.rfind('/').map(|idx| &path[..idx]).unwrap_or("")
+}
+
+/// Bloqueo de escritura WebDAV (RFC 4918, sección 6)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebDavLock {
+    /// Token `opaquelocktoken:` que el cliente debe presentar para escribir
+    pub token: String,
+    /// Ruta normalizada del recurso bloqueado (la raíz del bloqueo)
+    pub path: String,
+    pub scope: LockScope,
+    pub depth: LockDepth,
+    /// Contenido de `DAV:owner` enviado por el cliente, si lo hubo
+    pub owner: Option<String>,
+    /// Usuario autenticado que creó el bloqueo
+    pub principal: Option<String>,
+    /// Duración pedida en segundos, que se renueva con cada refresco
+    pub timeout_secs: u64,
+    /// Fecha de creación (segundos UNIX)
+    pub created_at: u64,
+    /// Fecha de caducidad (segundos UNIX)
+    pub expires_at: u64,
+}
+
+impl WebDavLock {
+    /// Crea un bloqueo nuevo con un token aleatorio
+    pub fn new(
+        path: &str,
+        scope: LockScope,
+        depth: LockDepth,
+        owner: Option<String>,
+        principal: Option<String>,
+        timeout_secs: u64,
+        now: u64,
+    ) -> Self {
+        Self {
+            token: format!("opaquelocktoken:{}", Uuid::new_v4()),
+            path: normalize_lock_path(path),
+            scope,
+            depth,
+            owner,
+            principal,
+            timeout_secs,
+            created_at: now,
+            expires_at: now.saturating_add(timeout_secs),
+        }
+    }
+
+    /// Indica si el bloqueo ha caducado en el instante `now`
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Segundos que le quedan al bloqueo
+    pub fn remaining_secs(&self, now: u64) -> u64 {
+        self.expires_at.saturating_sub(now)
+    }
+
+    /// Amplía la vida del bloqueo desde `now`
+    pub fn refresh(&mut self, timeout_secs: u64, now: u64) {
+        self.timeout_secs = timeout_secs;
+        self.expires_at = now.saturating_add(timeout_secs);
+    }
+
+    /// Indica si el bloqueo se aplica al recurso `path`, ya sea porque es su
+    /// raíz o porque lo hereda de una colección bloqueada con profundidad infinita
+    pub fn covers(&self, path: &str) -> bool {
+        let path = path.trim_matches('/');
+        match self.depth {
+            LockDepth::Zero => self.path == path,
+            LockDepth::Infinity => is_within(path, &self.path),
+        }
+    }
+
+    /// Indica si la raíz del bloqueo está en `path` o por debajo
+    pub fn is_within(&self, path: &str) -> bool {
+        is_within(&self.path, path.trim_matches('/'))
+    }
+
+    /// Indica si este bloqueo impide crear otro de `scope` sobre el mismo recurso
+    pub fn conflicts_with(&self, scope: LockScope) -> bool {
+        self.scope == LockScope::Exclusive || scope == LockScope::Exclusive
+    }
+
+    /// Indica si una escritura del tipo `operation` sobre `path` necesita el
+    /// token de este bloqueo.
+    ///
+    /// Además del propio recurso, cambiar los miembros de una colección exige
+    /// el token de la colección aunque esté bloqueada con profundidad 0, y
+    /// quitar un recurso exige los tokens de todo lo que hay por debajo.
+    pub fn protects(&self, path: &str, operation: LockedOperation) -> bool {
+        let path = path.trim_matches('/');
+        if self.covers(path) {
+            return true;
+        }
+        match operation {
+            LockedOperation::Modify => false,
+            LockedOperation::Create => !path.is_empty() && self.path == parent_path(path),
+            LockedOperation::Remove => {
+                (!path.is_empty() && self.path == parent_path(path)) || self.is_within(path)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(path: &str, depth: LockDepth) -> WebDavLock {
+        WebDavLock::new(path, LockScope::Exclusive, depth, None, None, 60, 1000)
+    }
+
+    #[test]
+    fn test_covers_depends_on_depth() {
+        let shallow = lock("/docs/", LockDepth::Zero);
+        assert_eq!(shallow.path, "docs");
+        assert!(shallow.covers("docs"));
+        assert!(!shallow.covers("docs/a.txt"));
+
+        let deep = lock("docs", LockDepth::Infinity);
+        assert!(deep.covers("/docs/a/b.txt"));
+        assert!(!deep.covers("docs2/a.txt"));
+        assert!(lock("", LockDepth::Infinity).covers("any/thing"));
+    }
+
+    #[test]
+    fn test_protects_membership_and_subtree() {
+        let collection = lock("docs", LockDepth::Zero);
+        assert!(!collection.protects("docs/a.txt", LockedOperation::Modify));
+        assert!(collection.protects("docs/a.txt", LockedOperation::Create));
+        assert!(collection.protects("docs/a.txt", LockedOperation::Remove));
+        assert!(!collection.protects("docs/sub/a.txt", LockedOperation::Create));
+
+        let file = lock("docs/sub/a.txt", LockDepth::Zero);
+        assert!(file.protects("docs", LockedOperation::Remove));
+        assert!(!file.protects("docs", LockedOperation::Modify));
+        assert!(!file.protects("docs/sub/b.txt", LockedOperation::Remove));
+    }
+
+    #[test]
+    fn test_expiry_and_refresh() {
+        let mut lock = lock("a.txt", LockDepth::Zero);
+        assert!(!lock.is_expired(1059));
+        assert!(lock.is_expired(1060));
+        lock.refresh(120, 1050);
+        assert_eq!(lock.remaining_secs(1100), 70);
+        assert!(lock.token.starts_with("opaquelocktoken:"));
+        assert!(lock.conflicts_with(LockScope::Shared));
+    }
+}
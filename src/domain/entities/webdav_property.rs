@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// Propiedad muerta WebDAV: metadatos arbitrarios que un cliente guarda en un
+/// recurso mediante PROPPATCH y que el servidor conserva sin interpretarlos
+/// (RFC 4918, sección 4.2)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadProperty {
+    /// URI del espacio de nombres XML de la propiedad
+    pub namespace: String,
+    /// Nombre local de la propiedad
+    pub name: String,
+    /// Contenido de texto; `None` para una propiedad vacía
+    pub value: Option<String>,
+}
+
+impl DeadProperty {
+    pub fn new(
+        namespace: impl Into<String>,
+        name: impl Into<String>,
+        value: Option<String>,
+    ) -> Self {
+        Self {
+            namespace: namespace.into(),
+            name: name.into(),
+            value,
+        }
+    }
+
+    /// Indica si la propiedad tiene el nombre cualificado dado
+    pub fn is_named(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+}
+
+/// Cambio pedido en una propiedad muerta
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyUpdate {
+    /// Crea la propiedad o sustituye su valor
+    Set(DeadProperty),
+    /// Elimina la propiedad; no es un error si no existía
+    Remove { namespace: String, name: String },
+}
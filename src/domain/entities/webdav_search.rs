@@ -0,0 +1,342 @@
+use std::cmp::Ordering;
+
+use chrono::DateTime;
+
+/// Tipo de contenido con el que se informan las carpetas
+pub const COLLECTION_CONTENT_TYPE: &str = "httpd/unix-directory";
+
+/// Propiedades por las que se puede buscar y ordenar con `DAV:basicsearch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchProperty {
+    DisplayName,
+    ContentType,
+    LastModified,
+    ContentLength,
+}
+
+impl SearchProperty {
+    /// Propiedad por su nombre en el espacio de nombres `DAV:`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "displayname" => Some(Self::DisplayName),
+            "getcontenttype" => Some(Self::ContentType),
+            "getlastmodified" => Some(Self::LastModified),
+            "getcontentlength" => Some(Self::ContentLength),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::DisplayName => "displayname",
+            Self::ContentType => "getcontenttype",
+            Self::LastModified => "getlastmodified",
+            Self::ContentLength => "getcontentlength",
+        }
+    }
+
+    /// Convierte un literal de la consulta al tipo de la propiedad: las
+    /// fechas (RFC 1123 o RFC 3339) a segundos UNIX y los tamaños a bytes
+    pub fn parse_literal(&self, literal: &str) -> Option<SearchValue> {
+        let literal = literal.trim();
+        match self {
+            Self::DisplayName | Self::ContentType => Some(SearchValue::Text(literal.to_string())),
+            Self::ContentLength => literal.parse().ok().map(SearchValue::Number),
+            Self::LastModified => DateTime::parse_from_rfc2822(literal)
+                .or_else(|_| DateTime::parse_from_rfc3339(literal))
+                .ok()
+                .map(|date| SearchValue::Number(date.timestamp().max(0) as u64)),
+        }
+    }
+}
+
+/// Operadores de comparación (RFC 5323, sección 5.5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl Comparison {
+    /// Operador por el nombre de su elemento en el espacio `DAV:`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "eq" => Some(Self::Eq),
+            "lt" => Some(Self::Lt),
+            "lte" => Some(Self::Lte),
+            "gt" => Some(Self::Gt),
+            "gte" => Some(Self::Gte),
+            _ => None,
+        }
+    }
+
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering == Ordering::Equal,
+            Self::Lt => ordering == Ordering::Less,
+            Self::Lte => ordering != Ordering::Greater,
+            Self::Gt => ordering == Ordering::Greater,
+            Self::Gte => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// Valor de una propiedad ya convertido a su tipo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchValue {
+    Text(String),
+    Number(u64),
+}
+
+impl SearchValue {
+    /// Compara dos valores; el texto sin distinguir mayúsculas
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Text(a), Self::Text(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+            (Self::Number(a), Self::Number(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+/// Condición de la cláusula `where` de una búsqueda (RFC 5323, sección 5.4)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchCondition {
+    And(Vec<SearchCondition>),
+    Or(Vec<SearchCondition>),
+    Not(Box<SearchCondition>),
+    Compare {
+        property: SearchProperty,
+        comparison: Comparison,
+        value: SearchValue,
+    },
+    /// Coincidencia con un patrón en el que `%` es cualquier texto y `_`
+    /// un carácter; `\` escapa al siguiente
+    Like {
+        property: SearchProperty,
+        pattern: String,
+    },
+    IsCollection,
+}
+
+/// Datos de un recurso con los que se evalúa una búsqueda
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchedResource<'a> {
+    pub name: &'a str,
+    pub content_type: &'a str,
+    /// Fecha de modificación (segundos UNIX)
+    pub modified_at: u64,
+    pub size: u64,
+    pub is_collection: bool,
+}
+
+impl SearchedResource<'_> {
+    pub fn value(&self, property: SearchProperty) -> SearchValue {
+        match property {
+            SearchProperty::DisplayName => SearchValue::Text(self.name.to_string()),
+            SearchProperty::ContentType => SearchValue::Text(self.content_type.to_string()),
+            SearchProperty::LastModified => SearchValue::Number(self.modified_at),
+            SearchProperty::ContentLength => SearchValue::Number(self.size),
+        }
+    }
+}
+
+impl SearchCondition {
+    /// Indica si el recurso cumple la condición
+    pub fn matches(&self, resource: &SearchedResource) -> bool {
+        match self {
+            Self::And(conditions) => conditions.iter().all(|c| c.matches(resource)),
+            Self::Or(conditions) => conditions.iter().any(|c| c.matches(resource)),
+            Self::Not(condition) => !condition.matches(resource),
+            Self::Compare {
+                property,
+                comparison,
+                value,
+            } => resource
+                .value(*property)
+                .compare(value)
+                .is_some_and(|ordering| comparison.holds(ordering)),
+            Self::Like { property, pattern } => match resource.value(*property) {
+                SearchValue::Text(text) => like(&text, pattern),
+                SearchValue::Number(number) => like(&number.to_string(), pattern),
+            },
+            Self::IsCollection => resource.is_collection,
+        }
+    }
+
+    /// Condiciones que se deben cumplir en cualquier caso: ella misma, o sus
+    /// partes si es una conjunción
+    pub fn conjuncts(&self) -> Vec<&SearchCondition> {
+        match self {
+            Self::And(conditions) => conditions.iter().flat_map(|c| c.conjuncts()).collect(),
+            condition => vec![condition],
+        }
+    }
+}
+
+/// Compara un texto con un patrón de `like` sin distinguir mayúsculas
+pub fn like(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut tokens = Vec::new();
+    let mut chars = pattern
+        .to_lowercase()
+        .chars()
+        .collect::<Vec<_>>()
+        .into_iter();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '%' => LikeToken::Any,
+            '_' => LikeToken::One,
+            '\\' => LikeToken::Char(chars.next().unwrap_or('\\')),
+            c => LikeToken::Char(c),
+        });
+    }
+    like_from(&text, &tokens)
+}
+
+#[derive(Clone, Copy)]
+enum LikeToken {
+    Any,
+    One,
+    Char(char),
+}
+
+fn like_from(text: &[char], tokens: &[LikeToken]) -> bool {
+    match tokens.split_first() {
+        None => text.is_empty(),
+        Some((LikeToken::Any, rest)) => (0..=text.len()).any(|skip| like_from(&text[skip..], rest)),
+        Some((LikeToken::One, rest)) => !text.is_empty() && like_from(&text[1..], rest),
+        Some((LikeToken::Char(c), rest)) => text.first() == Some(c) && like_from(&text[1..], rest),
+    }
+}
+
+/// Literal más largo de un patrón de `like`, que debe aparecer en todo
+/// texto que lo cumpla
+pub fn like_literal(pattern: &str) -> String {
+    let mut longest = String::new();
+    let mut current = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' | '_' => {
+                if current.len() > longest.len() {
+                    longest = std::mem::take(&mut current);
+                }
+                current.clear();
+            }
+            '\\' => current.extend(chars.next()),
+            c => current.push(c),
+        }
+    }
+    if current.len() > longest.len() {
+        longest = current;
+    }
+    longest
+}
+
+/// Criterio de ordenación de los resultados
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOrder {
+    pub property: SearchProperty,
+    pub descending: bool,
+}
+
+/// Compara dos recursos según los criterios de ordenación, en orden
+pub fn compare_resources(
+    a: &SearchedResource,
+    b: &SearchedResource,
+    order: &[SearchOrder],
+) -> Ordering {
+    order
+        .iter()
+        .map(|criterion| {
+            let ordering = a
+                .value(criterion.property)
+                .compare(&b.value(criterion.property))
+                .unwrap_or(Ordering::Equal);
+            if criterion.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(name: &str, size: u64) -> SearchedResource<'_> {
+        SearchedResource {
+            name,
+            content_type: "application/pdf",
+            modified_at: 1_700_000_000,
+            size,
+            is_collection: false,
+        }
+    }
+
+    #[test]
+    fn test_like_patterns() {
+        assert!(like("Report.PDF", "%.pdf"));
+        assert!(like("a_b", "a\\_b"));
+        assert!(!like("axb", "a\\_b"));
+        assert!(like("abc", "a_c"));
+        assert!(!like("report.pdf.txt", "%.pdf"));
+        assert_eq!(like_literal("%2024%report_.pdf"), "report");
+        assert_eq!(like_literal("image/%"), "image/");
+    }
+
+    #[test]
+    fn test_conditions_and_ordering() {
+        let condition = SearchCondition::And(vec![
+            SearchCondition::Like {
+                property: SearchProperty::DisplayName,
+                pattern: "%.pdf".to_string(),
+            },
+            SearchCondition::Not(Box::new(SearchCondition::Compare {
+                property: SearchProperty::ContentLength,
+                comparison: Comparison::Gt,
+                value: SearchProperty::ContentLength.parse_literal("100").unwrap(),
+            })),
+        ]);
+        assert!(condition.matches(&resource("a.pdf", 100)));
+        assert!(!condition.matches(&resource("a.pdf", 101)));
+        assert!(!condition.matches(&resource("a.txt", 1)));
+        assert_eq!(condition.conjuncts().len(), 2);
+
+        let date = SearchProperty::LastModified
+            .parse_literal("Tue, 14 Nov 2023 22:13:20 GMT")
+            .unwrap();
+        assert_eq!(date, SearchValue::Number(1_700_000_000));
+        assert_eq!(
+            SearchProperty::LastModified.parse_literal("2023-11-14T22:13:20Z"),
+            Some(date)
+        );
+        assert!(SearchProperty::ContentLength.parse_literal("big").is_none());
+
+        let order = [
+            SearchOrder {
+                property: SearchProperty::ContentLength,
+                descending: true,
+            },
+            SearchOrder {
+                property: SearchProperty::DisplayName,
+                descending: false,
+            },
+        ];
+        assert_eq!(
+            compare_resources(&resource("b", 5), &resource("a", 5), &order),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_resources(&resource("b", 9), &resource("a", 5), &order),
+            Ordering::Less
+        );
+    }
+}
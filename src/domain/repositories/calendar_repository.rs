@@ -101,4 +101,14 @@ pub trait CalendarRepository: Send + Sync + 'static {
         &self,
         calendar_id: &Uuid,
     ) -> CalendarRepositoryResult<Vec<(String, String)>>;
+
+    /// Gets a user's default time zone for floating event times, if set
+    async fn get_user_timezone(&self, user_id: &str) -> CalendarRepositoryResult<Option<String>>;
+
+    /// Sets or clears a user's default time zone
+    async fn set_user_timezone(
+        &self,
+        user_id: &str,
+        timezone: Option<&str>,
+    ) -> CalendarRepositoryResult<()>;
 }
@@ -0,0 +1,50 @@
+use crate::common::errors::DomainError;
+use crate::domain::entities::calendar_task::CalendarTask;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+pub type CalendarTaskRepositoryResult<T> = Result<T, DomainError>;
+
+/// Repository interface for CalendarTask entity operations
+#[async_trait]
+pub trait CalendarTaskRepository: Send + Sync + 'static {
+    /// Creates a new calendar task
+    async fn create_task(&self, task: CalendarTask) -> CalendarTaskRepositoryResult<CalendarTask>;
+
+    /// Updates an existing calendar task
+    async fn update_task(&self, task: CalendarTask) -> CalendarTaskRepositoryResult<CalendarTask>;
+
+    /// Deletes a calendar task by ID
+    async fn delete_task(&self, id: &Uuid) -> CalendarTaskRepositoryResult<()>;
+
+    /// Finds a calendar task by its ID
+    async fn find_task_by_id(&self, id: &Uuid) -> CalendarTaskRepositoryResult<CalendarTask>;
+
+    /// Finds a task by its iCalendar UID in a specific calendar
+    async fn find_task_by_ical_uid(
+        &self,
+        calendar_id: &Uuid,
+        ical_uid: &str,
+    ) -> CalendarTaskRepositoryResult<Option<CalendarTask>>;
+
+    /// Lists all tasks in a specific calendar
+    async fn list_tasks_by_calendar(
+        &self,
+        calendar_id: &Uuid,
+    ) -> CalendarTaskRepositoryResult<Vec<CalendarTask>>;
+
+    /// Gets the tasks of a calendar that overlap a time range
+    async fn get_tasks_in_time_range(
+        &self,
+        calendar_id: &Uuid,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> CalendarTaskRepositoryResult<Vec<CalendarTask>>;
+
+    /// Deletes all tasks in a calendar
+    async fn delete_all_tasks_in_calendar(
+        &self,
+        calendar_id: &Uuid,
+    ) -> CalendarTaskRepositoryResult<i64>;
+}
@@ -1,6 +1,7 @@
 pub mod address_book_repository;
 pub mod calendar_event_repository;
 pub mod calendar_repository;
+pub mod calendar_task_repository;
 pub mod contact_repository;
 pub mod file_repository;
 pub mod folder_repository;
@@ -0,0 +1,118 @@
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::sync::Mutex;
+
+use crate::application::ports::auth_ports::AppPasswordStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::app_password::AppPassword;
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Almacén de contraseñas de aplicación en un único `.app_passwords.json`
+/// bajo la raíz de almacenamiento, reescrito de forma atómica en cada cambio
+pub struct AppPasswordFsRepository {
+    passwords_path: PathBuf,
+    /// Serializa las escrituras para no perder cambios concurrentes
+    write_lock: Mutex<()>,
+}
+
+impl AppPasswordFsRepository {
+    /// Crea un nuevo almacén de contraseñas bajo la raíz de almacenamiento
+    pub fn new(storage_root: impl AsRef<Path>) -> Self {
+        Self {
+            passwords_path: storage_root.as_ref().join(".app_passwords.json"),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    async fn load(&self) -> Result<Vec<AppPassword>, DomainError> {
+        match fs::read(&self.passwords_path).await {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                DomainError::internal_error(
+                    "AppPassword",
+                    format!("Corrupt app password file: {}", e),
+                )
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(DomainError::internal_error(
+                "AppPassword",
+                format!("Failed to read app passwords: {}", e),
+            )),
+        }
+    }
+
+    async fn save(&self, passwords: &[AppPassword]) -> Result<(), DomainError> {
+        let data = serde_json::to_vec_pretty(passwords).map_err(|e| {
+            DomainError::internal_error(
+                "AppPassword",
+                format!("Failed to serialize app passwords: {}", e),
+            )
+        })?;
+        FileSystemUtils::atomic_write(&self.passwords_path, &data)
+            .await
+            .map_err(|e| {
+                DomainError::internal_error(
+                    "AppPassword",
+                    format!("Failed to save app passwords: {}", e),
+                )
+            })
+    }
+}
+
+#[async_trait]
+impl AppPasswordStoragePort for AppPasswordFsRepository {
+    async fn create_app_password(&self, app_password: AppPassword) -> Result<(), DomainError> {
+        let _guard = self.write_lock.lock().await;
+        let mut passwords = self.load().await?;
+        passwords.push(app_password);
+        self.save(&passwords).await
+    }
+
+    async fn list_app_passwords(&self, user_id: &str) -> Result<Vec<AppPassword>, DomainError> {
+        Ok(self
+            .load()
+            .await?
+            .into_iter()
+            .filter(|password| password.user_id == user_id)
+            .collect())
+    }
+
+    async fn delete_app_password(&self, user_id: &str, id: &str) -> Result<(), DomainError> {
+        let _guard = self.write_lock.lock().await;
+        let mut passwords = self.load().await?;
+        let before = passwords.len();
+        passwords.retain(|password| !(password.user_id == user_id && password.id == id));
+        if passwords.len() == before {
+            return Err(DomainError::not_found("AppPassword", id));
+        }
+        self.save(&passwords).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_app_passwords_are_kept_per_user() {
+        let dir = tempdir().unwrap();
+        let repo = AppPasswordFsRepository::new(dir.path());
+        let (alice, secret) = AppPassword::generate("alice", "Desktop");
+        let (bob, _) = AppPassword::generate("bob", "Android");
+        repo.create_app_password(alice.clone()).await.unwrap();
+        repo.create_app_password(bob.clone()).await.unwrap();
+
+        // Survives a restart
+        let repo = AppPasswordFsRepository::new(dir.path());
+        let stored = repo.list_app_passwords("alice").await.unwrap();
+        assert_eq!(stored, vec![alice.clone()]);
+        assert!(stored[0].verify(&secret));
+
+        // Users can only revoke their own passwords
+        assert!(repo.delete_app_password("alice", &bob.id).await.is_err());
+        repo.delete_app_password("alice", &alice.id).await.unwrap();
+        assert!(repo.list_app_passwords("alice").await.unwrap().is_empty());
+        assert_eq!(repo.list_app_passwords("bob").await.unwrap().len(), 1);
+    }
+}
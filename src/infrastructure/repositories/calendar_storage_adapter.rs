@@ -0,0 +1,736 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::application::dtos::calendar_dto::{
+    CalendarDto, CalendarEventDto, CalendarTaskDto, CreateCalendarDto, CreateEventDto,
+    CreateEventICalDto, CreateTaskDto, UpdateCalendarDto, UpdateEventDto, UpdateTaskDto,
+};
+use crate::application::ports::calendar_ports::CalendarStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::calendar::Calendar;
+use crate::domain::entities::calendar_event::CalendarEvent;
+use crate::domain::entities::calendar_task::{CalendarTask, TaskStatus};
+use crate::domain::entities::icalendar::{escape_text, format_date_time};
+use crate::domain::entities::timezone::CalendarTimeZone;
+use crate::domain::repositories::calendar_event_repository::CalendarEventRepository;
+use crate::domain::repositories::calendar_repository::CalendarRepository;
+use crate::domain::repositories::calendar_task_repository::CalendarTaskRepository;
+
+/// Almacenamiento de calendarios sobre los repositorios de calendarios, de
+/// eventos y de tareas
+pub struct CalendarStorageAdapter {
+    calendars: Arc<dyn CalendarRepository>,
+    events: Arc<dyn CalendarEventRepository>,
+    tasks: Arc<dyn CalendarTaskRepository>,
+}
+
+impl CalendarStorageAdapter {
+    pub fn new(
+        calendars: Arc<dyn CalendarRepository>,
+        events: Arc<dyn CalendarEventRepository>,
+        tasks: Arc<dyn CalendarTaskRepository>,
+    ) -> Self {
+        Self {
+            calendars,
+            events,
+            tasks,
+        }
+    }
+
+    /// Interpreta un identificador; uno que no es UUID no puede existir
+    fn parse_id(id: &str, entity: &'static str) -> Result<Uuid, DomainError> {
+        Uuid::parse_str(id).map_err(|_| DomainError::not_found(entity, id))
+    }
+
+    /// Calendario con sus propiedades personalizadas
+    async fn load_calendar(&self, calendar: Calendar) -> Result<CalendarDto, DomainError> {
+        let properties = self
+            .calendars
+            .get_calendar_properties(calendar.id())
+            .await?;
+        let mut dto = CalendarDto::from(calendar);
+        dto.custom_properties.extend(properties);
+        Ok(dto)
+    }
+
+    async fn load_calendars(
+        &self,
+        calendars: Vec<Calendar>,
+    ) -> Result<Vec<CalendarDto>, DomainError> {
+        let mut dtos = Vec::with_capacity(calendars.len());
+        for calendar in calendars {
+            dtos.push(self.load_calendar(calendar).await?);
+        }
+        Ok(dtos)
+    }
+
+    async fn find_event(&self, event_id: &str) -> Result<CalendarEvent, DomainError> {
+        let id = Self::parse_id(event_id, "Calendar Event")?;
+        self.events.find_event_by_id(&id).await
+    }
+
+    async fn find_task(&self, task_id: &str) -> Result<CalendarTask, DomainError> {
+        let id = Self::parse_id(task_id, "Calendar Task")?;
+        self.tasks.find_task_by_id(&id).await
+    }
+
+    /// Falla si ya hay un evento o una tarea con este UID en el calendario:
+    /// ambos comparten los nombres de recurso de CalDAV
+    async fn ensure_uid_free(&self, calendar_id: &Uuid, ical_uid: &str) -> Result<(), DomainError> {
+        let taken = self
+            .events
+            .find_event_by_ical_uid(calendar_id, ical_uid)
+            .await?
+            .is_some()
+            || self
+                .tasks
+                .find_task_by_ical_uid(calendar_id, ical_uid)
+                .await?
+                .is_some();
+        if taken {
+            return Err(DomainError::already_exists("Calendar Object", ical_uid));
+        }
+        Ok(())
+    }
+
+    /// Comprueba que la tarea padre existe en el calendario y que no es una
+    /// subtarea de `task_uid`, lo que crearía un ciclo
+    async fn check_parent(
+        &self,
+        calendar_id: &Uuid,
+        task_uid: Option<&str>,
+        parent_uid: &str,
+    ) -> Result<(), DomainError> {
+        let mut ancestor = self
+            .tasks
+            .find_task_by_ical_uid(calendar_id, parent_uid)
+            .await?
+            .ok_or_else(|| {
+                DomainError::validation_error(format!("Parent task not found: {}", parent_uid))
+            })?;
+        let mut seen = HashSet::new();
+        loop {
+            if Some(ancestor.ical_uid()) == task_uid {
+                return Err(DomainError::validation_error(
+                    "A task cannot be a subtask of its own subtasks",
+                ));
+            }
+            if !seen.insert(ancestor.ical_uid().to_string()) {
+                return Ok(());
+            }
+            let Some(uid) = ancestor.parent_uid() else {
+                return Ok(());
+            };
+            match self.tasks.find_task_by_ical_uid(calendar_id, uid).await? {
+                Some(parent) => ancestor = parent,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Zona por defecto del propietario de un calendario, para las horas flotantes
+    async fn owner_timezone(&self, calendar_id: &Uuid) -> Result<Option<String>, DomainError> {
+        let calendar = self.calendars.find_calendar_by_id(calendar_id).await?;
+        self.calendars.get_user_timezone(calendar.owner_id()).await
+    }
+}
+
+/// Objeto iCalendar mínimo para un evento creado a partir de sus campos. Con
+/// zona, las horas se escriben en hora local con su `VTIMEZONE`.
+fn event_to_ical(event: &CreateEventDto, uid: &str) -> Result<String, DomainError> {
+    let all_day = event.all_day.unwrap_or(false);
+    let zone = match event.timezone.as_deref().filter(|_| !all_day) {
+        Some(tzid) => Some(CalendarTimeZone::bundled(tzid).ok_or_else(|| {
+            DomainError::validation_error(format!("Unknown time zone: {}", tzid))
+        })?),
+        None => None,
+    };
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//OxiCloud//CalDAV//EN".to_string(),
+    ];
+    if let Some(zone) = &zone {
+        lines.push(zone.to_vtimezone().to_ical().trim_end().to_string());
+    }
+    lines.extend([
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", format_date_time(&Utc::now())),
+    ]);
+    if all_day {
+        lines.push(format!(
+            "DTSTART;VALUE=DATE:{}",
+            event.start_time.format("%Y%m%d")
+        ));
+        lines.push(format!(
+            "DTEND;VALUE=DATE:{}",
+            event.end_time.format("%Y%m%d")
+        ));
+    } else if let Some(zone) = &zone {
+        for (name, time) in [("DTSTART", &event.start_time), ("DTEND", &event.end_time)] {
+            lines.push(format!(
+                "{};TZID={}:{}",
+                name,
+                zone.tzid(),
+                zone.to_local(*time).format("%Y%m%dT%H%M%S")
+            ));
+        }
+    } else {
+        lines.push(format!("DTSTART:{}", format_date_time(&event.start_time)));
+        lines.push(format!("DTEND:{}", format_date_time(&event.end_time)));
+    }
+    lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+    if let Some(description) = &event.description {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    if let Some(location) = &event.location {
+        lines.push(format!("LOCATION:{}", escape_text(location)));
+    }
+    if let Some(rrule) = &event.rrule {
+        lines.push(format!("RRULE:{}", rrule));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines.push("END:VCALENDAR".to_string());
+    Ok(lines.join("\r\n") + "\r\n")
+}
+
+/// Objeto iCalendar mínimo para una tarea creada a partir de sus campos; el
+/// estado, la prioridad, el progreso y la recurrencia se aplican después
+/// sobre la entidad, que los valida
+fn task_to_ical(task: &CreateTaskDto, uid: &str) -> Result<String, DomainError> {
+    if task.summary.trim().is_empty() {
+        return Err(DomainError::validation_error(
+            "Task summary cannot be empty",
+        ));
+    }
+    if let (Some(start), Some(due)) = (task.start_time, task.due_time) {
+        if due < start {
+            return Err(DomainError::validation_error(
+                "Due time cannot be before start time",
+            ));
+        }
+    }
+    let zone = match task.timezone.as_deref() {
+        Some(tzid) => Some(CalendarTimeZone::bundled(tzid).ok_or_else(|| {
+            DomainError::validation_error(format!("Unknown time zone: {}", tzid))
+        })?),
+        None => None,
+    };
+
+    let now = format_date_time(&Utc::now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//OxiCloud//CalDAV//EN".to_string(),
+    ];
+    if let Some(zone) = &zone {
+        lines.push(zone.to_vtimezone().to_ical().trim_end().to_string());
+    }
+    lines.extend([
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", now),
+        format!("CREATED:{}", now),
+    ]);
+    for (name, time) in [("DTSTART", &task.start_time), ("DUE", &task.due_time)] {
+        match (time, &zone) {
+            (Some(time), Some(zone)) => lines.push(format!(
+                "{};TZID={}:{}",
+                name,
+                zone.tzid(),
+                zone.to_local(*time).format("%Y%m%dT%H%M%S")
+            )),
+            (Some(time), None) => lines.push(format!("{}:{}", name, format_date_time(time))),
+            (None, _) => {}
+        }
+    }
+    lines.push(format!("SUMMARY:{}", escape_text(&task.summary)));
+    if let Some(description) = &task.description {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    if let Some(parent_uid) = &task.parent_uid {
+        lines.push(format!("RELATED-TO:{}", parent_uid));
+    }
+    lines.push("END:VTODO".to_string());
+    lines.push("END:VCALENDAR".to_string());
+    Ok(lines.join("\r\n") + "\r\n")
+}
+
+/// Interpreta un estado de tarea enviado por la API
+fn parse_status(status: &str) -> Result<TaskStatus, DomainError> {
+    TaskStatus::parse(status).ok_or_else(|| {
+        DomainError::validation_error(format!(
+            "Invalid task status: {}. Valid values are: NEEDS-ACTION, IN-PROCESS, COMPLETED, CANCELLED",
+            status
+        ))
+    })
+}
+
+#[async_trait]
+impl CalendarStoragePort for CalendarStorageAdapter {
+    async fn create_calendar(
+        &self,
+        calendar: CreateCalendarDto,
+        owner_id: &str,
+    ) -> Result<CalendarDto, DomainError> {
+        let mut created = Calendar::new(
+            calendar.name,
+            owner_id.to_string(),
+            calendar.description,
+            calendar.color,
+        )?;
+        if let Some(id) = calendar.id {
+            let id = Uuid::parse_str(&id).map_err(|_| {
+                DomainError::validation_error(format!("Invalid calendar id: {}", id))
+            })?;
+            created = Calendar::with_id(
+                id,
+                created.name().to_string(),
+                created.owner_id().to_string(),
+                created.description().map(str::to_string),
+                created.color().map(str::to_string),
+                *created.created_at(),
+                *created.updated_at(),
+            )?;
+        }
+
+        let created = self.calendars.create_calendar(created).await?;
+        Ok(CalendarDto::from(created))
+    }
+
+    async fn update_calendar(
+        &self,
+        calendar_id: &str,
+        update: UpdateCalendarDto,
+    ) -> Result<CalendarDto, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        let mut calendar = self.calendars.find_calendar_by_id(&id).await?;
+
+        if let Some(name) = update.name {
+            calendar.update_name(name)?;
+        }
+        if let Some(description) = update.description {
+            calendar.update_description(Some(description).filter(|d| !d.is_empty()));
+        }
+        if let Some(color) = update.color {
+            calendar.update_color(Some(color).filter(|c| !c.is_empty()))?;
+        }
+
+        let updated = self.calendars.update_calendar(calendar).await?;
+        self.load_calendar(updated).await
+    }
+
+    async fn delete_calendar(&self, calendar_id: &str) -> Result<(), DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        self.events.delete_all_events_in_calendar(&id).await?;
+        self.tasks.delete_all_tasks_in_calendar(&id).await?;
+        self.calendars.delete_calendar(&id).await
+    }
+
+    async fn get_calendar(&self, calendar_id: &str) -> Result<CalendarDto, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        let calendar = self.calendars.find_calendar_by_id(&id).await?;
+        self.load_calendar(calendar).await
+    }
+
+    async fn list_calendars_by_owner(
+        &self,
+        owner_id: &str,
+    ) -> Result<Vec<CalendarDto>, DomainError> {
+        let calendars = self.calendars.list_calendars_by_owner(owner_id).await?;
+        self.load_calendars(calendars).await
+    }
+
+    async fn list_calendars_shared_with_user(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<CalendarDto>, DomainError> {
+        let calendars = self
+            .calendars
+            .list_calendars_shared_with_user(user_id)
+            .await?;
+        self.load_calendars(calendars).await
+    }
+
+    async fn list_public_calendars(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CalendarDto>, DomainError> {
+        let calendars = self.calendars.list_public_calendars(limit, offset).await?;
+        self.load_calendars(calendars).await
+    }
+
+    async fn check_calendar_access(
+        &self,
+        calendar_id: &str,
+        user_id: &str,
+    ) -> Result<bool, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        self.calendars.user_has_calendar_access(&id, user_id).await
+    }
+
+    async fn share_calendar(
+        &self,
+        calendar_id: &str,
+        user_id: &str,
+        access_level: &str,
+    ) -> Result<(), DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        self.calendars
+            .share_calendar(&id, user_id, access_level)
+            .await
+    }
+
+    async fn remove_calendar_sharing(
+        &self,
+        calendar_id: &str,
+        user_id: &str,
+    ) -> Result<(), DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        self.calendars.remove_calendar_sharing(&id, user_id).await
+    }
+
+    async fn get_calendar_shares(
+        &self,
+        calendar_id: &str,
+    ) -> Result<Vec<(String, String)>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        self.calendars.get_calendar_shares(&id).await
+    }
+
+    async fn set_calendar_property(
+        &self,
+        calendar_id: &str,
+        property_name: &str,
+        property_value: &str,
+    ) -> Result<(), DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        self.calendars
+            .set_calendar_property(&id, property_name, property_value)
+            .await
+    }
+
+    async fn get_calendar_property(
+        &self,
+        calendar_id: &str,
+        property_name: &str,
+    ) -> Result<Option<String>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        self.calendars
+            .get_calendar_property(&id, property_name)
+            .await
+    }
+
+    async fn get_calendar_properties(
+        &self,
+        calendar_id: &str,
+    ) -> Result<HashMap<String, String>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        self.calendars.get_calendar_properties(&id).await
+    }
+
+    async fn remove_calendar_property(
+        &self,
+        calendar_id: &str,
+        property_name: &str,
+    ) -> Result<(), DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        self.calendars
+            .remove_calendar_property(&id, property_name)
+            .await
+    }
+
+    async fn get_default_timezone(&self, user_id: &str) -> Result<Option<String>, DomainError> {
+        self.calendars.get_user_timezone(user_id).await
+    }
+
+    async fn set_default_timezone(
+        &self,
+        user_id: &str,
+        timezone: Option<&str>,
+    ) -> Result<(), DomainError> {
+        self.calendars.set_user_timezone(user_id, timezone).await
+    }
+
+    async fn create_event(&self, event: CreateEventDto) -> Result<CalendarEventDto, DomainError> {
+        let calendar_id = Self::parse_id(&event.calendar_id, "Calendar")?;
+        let ical_data = event_to_ical(&event, &Uuid::new_v4().to_string())?;
+        let default_timezone = self.owner_timezone(&calendar_id).await?;
+        let created =
+            CalendarEvent::from_ical(calendar_id, ical_data, default_timezone.as_deref())?;
+        let created = self.events.create_event(created).await?;
+        Ok(CalendarEventDto::from(created))
+    }
+
+    async fn create_event_from_ical(
+        &self,
+        event: CreateEventICalDto,
+    ) -> Result<CalendarEventDto, DomainError> {
+        let calendar_id = Self::parse_id(&event.calendar_id, "Calendar")?;
+        let default_timezone = self.owner_timezone(&calendar_id).await?;
+        let created =
+            CalendarEvent::from_ical(calendar_id, event.ical_data, default_timezone.as_deref())?;
+
+        self.ensure_uid_free(&calendar_id, created.ical_uid())
+            .await?;
+
+        let created = self.events.create_event(created).await?;
+        Ok(CalendarEventDto::from(created))
+    }
+
+    async fn update_event(
+        &self,
+        event_id: &str,
+        update: UpdateEventDto,
+    ) -> Result<CalendarEventDto, DomainError> {
+        let mut event = self.find_event(event_id).await?;
+
+        if let Some(summary) = update.summary {
+            event.update_summary(summary)?;
+        }
+        if update.description.is_some() {
+            event.update_description(update.description);
+        }
+        if update.location.is_some() {
+            event.update_location(update.location);
+        }
+        if let Some(all_day) = update.all_day {
+            event.update_all_day(all_day);
+        }
+        if update.start_time.is_some() || update.end_time.is_some() {
+            let start_time = update.start_time.unwrap_or(*event.start_time());
+            let end_time = update.end_time.unwrap_or(*event.end_time());
+            event.update_time_range(start_time, end_time)?;
+        }
+        if update.rrule.is_some() {
+            event.update_rrule(update.rrule)?;
+        }
+
+        let updated = self.events.update_event(event).await?;
+        Ok(CalendarEventDto::from(updated))
+    }
+
+    async fn update_event_from_ical(
+        &self,
+        event_id: &str,
+        ical_data: &str,
+    ) -> Result<CalendarEventDto, DomainError> {
+        let mut event = self.find_event(event_id).await?;
+        let default_timezone = self.owner_timezone(event.calendar_id()).await?;
+        event.update_ical_data(ical_data.to_string(), default_timezone.as_deref())?;
+        let updated = self.events.update_event(event).await?;
+        Ok(CalendarEventDto::from(updated))
+    }
+
+    async fn find_event_by_uid(
+        &self,
+        calendar_id: &str,
+        ical_uid: &str,
+    ) -> Result<Option<CalendarEventDto>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        Ok(self
+            .events
+            .find_event_by_ical_uid(&id, ical_uid)
+            .await?
+            .map(CalendarEventDto::from))
+    }
+
+    async fn delete_event(&self, event_id: &str) -> Result<(), DomainError> {
+        let id = Self::parse_id(event_id, "Calendar Event")?;
+        self.events.delete_event(&id).await
+    }
+
+    async fn get_event(&self, event_id: &str) -> Result<CalendarEventDto, DomainError> {
+        Ok(CalendarEventDto::from(self.find_event(event_id).await?))
+    }
+
+    async fn list_events_by_calendar(
+        &self,
+        calendar_id: &str,
+    ) -> Result<Vec<CalendarEventDto>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        let events = self.events.list_events_by_calendar(&id).await?;
+        Ok(events.into_iter().map(CalendarEventDto::from).collect())
+    }
+
+    async fn list_events_by_calendar_paginated(
+        &self,
+        calendar_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CalendarEventDto>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        let events = self
+            .events
+            .list_events_by_calendar_paginated(&id, limit, offset)
+            .await?;
+        Ok(events.into_iter().map(CalendarEventDto::from).collect())
+    }
+
+    async fn get_events_in_time_range(
+        &self,
+        calendar_id: &str,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Result<Vec<CalendarEventDto>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        let events = self
+            .events
+            .get_events_in_time_range(&id, start, end)
+            .await?;
+        Ok(events.into_iter().map(CalendarEventDto::from).collect())
+    }
+
+    async fn create_task(
+        &self,
+        calendar_id: &str,
+        task: CreateTaskDto,
+    ) -> Result<CalendarTaskDto, DomainError> {
+        let calendar_id = Self::parse_id(calendar_id, "Calendar")?;
+        if let Some(parent_uid) = &task.parent_uid {
+            self.check_parent(&calendar_id, None, parent_uid).await?;
+        }
+        let ical_data = task_to_ical(&task, &Uuid::new_v4().to_string())?;
+        let default_timezone = self.owner_timezone(&calendar_id).await?;
+        let mut created =
+            CalendarTask::from_ical(calendar_id, ical_data, default_timezone.as_deref())?;
+
+        if let Some(status) = &task.status {
+            created.update_status(parse_status(status)?)?;
+        }
+        if task.priority.is_some() {
+            created.update_priority(task.priority)?;
+        }
+        if task.percent_complete.is_some() {
+            created.update_percent_complete(task.percent_complete)?;
+        }
+        if task.rrule.is_some() {
+            created.update_rrule(task.rrule)?;
+        }
+
+        let created = self.tasks.create_task(created).await?;
+        Ok(CalendarTaskDto::from(created))
+    }
+
+    async fn create_task_from_ical(
+        &self,
+        calendar_id: &str,
+        ical_data: &str,
+    ) -> Result<CalendarTaskDto, DomainError> {
+        let calendar_id = Self::parse_id(calendar_id, "Calendar")?;
+        let default_timezone = self.owner_timezone(&calendar_id).await?;
+        let created = CalendarTask::from_ical(
+            calendar_id,
+            ical_data.to_string(),
+            default_timezone.as_deref(),
+        )?;
+        self.ensure_uid_free(&calendar_id, created.ical_uid())
+            .await?;
+
+        let created = self.tasks.create_task(created).await?;
+        Ok(CalendarTaskDto::from(created))
+    }
+
+    async fn update_task(
+        &self,
+        task_id: &str,
+        update: UpdateTaskDto,
+    ) -> Result<CalendarTaskDto, DomainError> {
+        let mut task = self.find_task(task_id).await?;
+
+        if let Some(summary) = update.summary {
+            task.update_summary(summary)?;
+        }
+        if let Some(description) = update.description {
+            task.update_description(Some(description).filter(|d| !d.is_empty()))?;
+        }
+        if let Some(status) = update.status {
+            task.update_status(parse_status(&status)?)?;
+        }
+        if update.priority.is_some() {
+            task.update_priority(update.priority)?;
+        }
+        if update.start_time.is_some() || update.due_time.is_some() {
+            let start_time = update.start_time.or(task.start_time().copied());
+            let due_time = update.due_time.or(task.due_time().copied());
+            task.update_times(start_time, due_time)?;
+        }
+        if update.percent_complete.is_some() {
+            task.update_percent_complete(update.percent_complete)?;
+        }
+        if let Some(parent_uid) = update.parent_uid {
+            let parent_uid = Some(parent_uid).filter(|uid| !uid.is_empty());
+            if let Some(parent_uid) = &parent_uid {
+                self.check_parent(task.calendar_id(), Some(task.ical_uid()), parent_uid)
+                    .await?;
+            }
+            task.update_parent(parent_uid)?;
+        }
+        if let Some(rrule) = update.rrule {
+            task.update_rrule(Some(rrule).filter(|r| !r.is_empty()))?;
+        }
+
+        let updated = self.tasks.update_task(task).await?;
+        Ok(CalendarTaskDto::from(updated))
+    }
+
+    async fn update_task_from_ical(
+        &self,
+        task_id: &str,
+        ical_data: &str,
+    ) -> Result<CalendarTaskDto, DomainError> {
+        let mut task = self.find_task(task_id).await?;
+        let default_timezone = self.owner_timezone(task.calendar_id()).await?;
+        task.update_ical_data(ical_data.to_string(), default_timezone.as_deref())?;
+        let updated = self.tasks.update_task(task).await?;
+        Ok(CalendarTaskDto::from(updated))
+    }
+
+    async fn find_task_by_uid(
+        &self,
+        calendar_id: &str,
+        ical_uid: &str,
+    ) -> Result<Option<CalendarTaskDto>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        Ok(self
+            .tasks
+            .find_task_by_ical_uid(&id, ical_uid)
+            .await?
+            .map(CalendarTaskDto::from))
+    }
+
+    async fn delete_task(&self, task_id: &str) -> Result<(), DomainError> {
+        let id = Self::parse_id(task_id, "Calendar Task")?;
+        self.tasks.delete_task(&id).await
+    }
+
+    async fn get_task(&self, task_id: &str) -> Result<CalendarTaskDto, DomainError> {
+        Ok(CalendarTaskDto::from(self.find_task(task_id).await?))
+    }
+
+    async fn list_tasks_by_calendar(
+        &self,
+        calendar_id: &str,
+    ) -> Result<Vec<CalendarTaskDto>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        let tasks = self.tasks.list_tasks_by_calendar(&id).await?;
+        Ok(tasks.into_iter().map(CalendarTaskDto::from).collect())
+    }
+
+    async fn get_tasks_in_time_range(
+        &self,
+        calendar_id: &str,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Result<Vec<CalendarTaskDto>, DomainError> {
+        let id = Self::parse_id(calendar_id, "Calendar")?;
+        let tasks = self.tasks.get_tasks_in_time_range(&id, start, end).await?;
+        Ok(tasks.into_iter().map(CalendarTaskDto::from).collect())
+    }
+}
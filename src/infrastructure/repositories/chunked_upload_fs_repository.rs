@@ -0,0 +1,241 @@
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::fs;
+use tokio_util::io::ReaderStream;
+
+use crate::application::ports::upload_ports::{
+    ChunkedUploadStoragePort, UploadChunkStream, UploadedChunk,
+};
+use crate::common::errors::DomainError;
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Almacén de las subidas por partes de los clientes de Nextcloud.
+///
+/// Cada transferencia es un directorio `.nextcloud_uploads/<usuario>/<id>`
+/// con un fichero por fragmento. Los fragmentos se escriben de forma atómica,
+/// así que un fragmento cortado a medias nunca aparece como recibido.
+pub struct ChunkedUploadFsRepository {
+    uploads_dir: PathBuf,
+}
+
+impl ChunkedUploadFsRepository {
+    /// Crea un nuevo almacén de subidas bajo la raíz de almacenamiento
+    pub fn new(storage_root: impl AsRef<Path>) -> Self {
+        Self {
+            uploads_dir: storage_root.as_ref().join(".nextcloud_uploads"),
+        }
+    }
+
+    /// Los identificadores se usan como nombres de directorio y de fichero
+    fn is_safe_name(name: &str) -> bool {
+        !name.is_empty()
+            && name.len() <= 255
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && name.chars().any(|c| c != '.')
+    }
+
+    fn transfer_dir(&self, owner: &str, transfer: &str) -> Result<PathBuf, DomainError> {
+        if !Self::is_safe_name(owner) || !Self::is_safe_name(transfer) {
+            return Err(DomainError::not_found("Upload", transfer));
+        }
+        Ok(self.uploads_dir.join(owner).join(transfer))
+    }
+
+    fn chunk_path(&self, owner: &str, transfer: &str, chunk: &str) -> Result<PathBuf, DomainError> {
+        if !Self::is_safe_name(chunk) {
+            return Err(DomainError::not_found("Upload", chunk));
+        }
+        Ok(self.transfer_dir(owner, transfer)?.join(chunk))
+    }
+
+    fn io_error(transfer: &str, action: &str, e: impl std::fmt::Display) -> DomainError {
+        DomainError::internal_error(
+            "Upload",
+            format!("Failed to {} upload {}: {}", action, transfer, e),
+        )
+    }
+
+    async fn require_transfer(&self, owner: &str, transfer: &str) -> Result<PathBuf, DomainError> {
+        let dir = self.transfer_dir(owner, transfer)?;
+        if fs::metadata(&dir)
+            .await
+            .map(|m| m.is_dir())
+            .unwrap_or(false)
+        {
+            Ok(dir)
+        } else {
+            Err(DomainError::not_found("Upload", transfer))
+        }
+    }
+}
+
+#[async_trait]
+impl ChunkedUploadStoragePort for ChunkedUploadFsRepository {
+    async fn create_transfer(&self, owner: &str, transfer: &str) -> Result<(), DomainError> {
+        let dir = self.transfer_dir(owner, transfer)?;
+        fs::create_dir_all(dir.parent().unwrap_or(&self.uploads_dir))
+            .await
+            .map_err(|e| Self::io_error(transfer, "create", e))?;
+        match fs::create_dir(&dir).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                Err(DomainError::already_exists("Upload", transfer))
+            }
+            Err(e) => Err(Self::io_error(transfer, "create", e)),
+        }
+    }
+
+    async fn list_chunks(
+        &self,
+        owner: &str,
+        transfer: &str,
+    ) -> Result<Vec<UploadedChunk>, DomainError> {
+        let dir = self.require_transfer(owner, transfer).await?;
+        let mut entries = fs::read_dir(&dir)
+            .await
+            .map_err(|e| Self::io_error(transfer, "list", e))?;
+
+        let mut chunks = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| Self::io_error(transfer, "list", e))?
+        {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Temporary files of writes in progress
+            if name.starts_with('.') {
+                continue;
+            }
+            let metadata = entry
+                .metadata()
+                .await
+                .map_err(|e| Self::io_error(transfer, "list", e))?;
+            if metadata.is_file() {
+                chunks.push(UploadedChunk {
+                    name,
+                    size: metadata.len(),
+                });
+            }
+        }
+        Ok(chunks)
+    }
+
+    async fn write_chunk(
+        &self,
+        owner: &str,
+        transfer: &str,
+        chunk: &str,
+        content: &[u8],
+    ) -> Result<(), DomainError> {
+        self.require_transfer(owner, transfer).await?;
+        FileSystemUtils::atomic_write(self.chunk_path(owner, transfer, chunk)?, content)
+            .await
+            .map_err(|e| Self::io_error(transfer, "write chunk of", e))
+    }
+
+    async fn read_chunk(
+        &self,
+        owner: &str,
+        transfer: &str,
+        chunk: &str,
+    ) -> Result<UploadChunkStream, DomainError> {
+        match fs::File::open(self.chunk_path(owner, transfer, chunk)?).await {
+            Ok(file) => Ok(Box::pin(ReaderStream::new(file))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(DomainError::not_found("Upload", chunk))
+            }
+            Err(e) => Err(Self::io_error(transfer, "read chunk of", e)),
+        }
+    }
+
+    async fn delete_transfer(&self, owner: &str, transfer: &str) -> Result<(), DomainError> {
+        match fs::remove_dir_all(self.transfer_dir(owner, transfer)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(DomainError::not_found("Upload", transfer))
+            }
+            Err(e) => Err(Self::io_error(transfer, "delete", e)),
+        }
+    }
+
+    async fn delete_stale_transfers(&self, max_age_secs: u64) -> Result<usize, DomainError> {
+        let cutoff = SystemTime::now() - Duration::from_secs(max_age_secs);
+        let mut owners = match fs::read_dir(&self.uploads_dir).await {
+            Ok(owners) => owners,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(Self::io_error("*", "list", e)),
+        };
+
+        let mut removed = 0;
+        while let Ok(Some(owner)) = owners.next_entry().await {
+            let Ok(mut transfers) = fs::read_dir(owner.path()).await else {
+                continue;
+            };
+            while let Ok(Some(transfer)) = transfers.next_entry().await {
+                let modified = transfer
+                    .metadata()
+                    .await
+                    .and_then(|metadata| metadata.modified());
+                if matches!(modified, Ok(modified) if modified < cutoff)
+                    && fs::remove_dir_all(transfer.path()).await.is_ok()
+                {
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::ports::storage_ports::collect_byte_stream;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_chunks_are_kept_per_transfer() {
+        let dir = tempdir().unwrap();
+        let repo = ChunkedUploadFsRepository::new(dir.path());
+
+        assert!(repo.write_chunk("alice", "t1", "1", b"x").await.is_err());
+        repo.create_transfer("alice", "t1").await.unwrap();
+        assert!(repo.create_transfer("alice", "t1").await.is_err());
+
+        repo.write_chunk("alice", "t1", "00002", b"world")
+            .await
+            .unwrap();
+        repo.write_chunk("alice", "t1", "00001", b"hello ")
+            .await
+            .unwrap();
+        let mut chunks = repo.list_chunks("alice", "t1").await.unwrap();
+        chunks.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(
+            chunks,
+            vec![
+                UploadedChunk {
+                    name: "00001".to_string(),
+                    size: 6
+                },
+                UploadedChunk {
+                    name: "00002".to_string(),
+                    size: 5
+                },
+            ]
+        );
+        let chunk = repo.read_chunk("alice", "t1", "00002").await.unwrap();
+        assert_eq!(collect_byte_stream(chunk).await.unwrap(), b"world");
+
+        // Other users do not see the transfer, and names cannot escape it
+        assert!(repo.list_chunks("bob", "t1").await.is_err());
+        assert!(repo.read_chunk("alice", "t1", "../t1").await.is_err());
+        assert!(repo.create_transfer("alice", "..").await.is_err());
+
+        assert_eq!(repo.delete_stale_transfers(3600).await.unwrap(), 0);
+        repo.delete_transfer("alice", "t1").await.unwrap();
+        assert!(repo.list_chunks("alice", "t1").await.is_err());
+    }
+}
@@ -0,0 +1,534 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::sync::{OnceCell, RwLock};
+
+use crate::application::ports::content_index_ports::ContentIndexStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::content_index::{
+    build_snippet, tokenize, ContentMatch, IndexedDocument, Snippet,
+};
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Parámetros de la fórmula de relevancia BM25
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// Longitud máxima, en caracteres, de los fragmentos de resultado
+const SNIPPET_CHARS: usize = 200;
+
+const TEXT_DIR_NAME: &str = "text";
+
+/// Documento de una partición junto con su número de términos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ShardDocument {
+    #[serde(flatten)]
+    document: IndexedDocument,
+    length: u32,
+}
+
+/// Índice invertido de los documentos de un propietario
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexShard {
+    documents: HashMap<String, ShardDocument>,
+    /// Término -> (ID de archivo -> apariciones)
+    postings: HashMap<String, HashMap<String, u32>>,
+}
+
+impl IndexShard {
+    fn remove(&mut self, file_id: &str) {
+        if self.documents.remove(file_id).is_some() {
+            self.postings.retain(|_, files| {
+                files.remove(file_id);
+                !files.is_empty()
+            });
+        }
+    }
+
+    /// Documentos que contienen todos los términos, con su puntuación BM25
+    fn rank(&self, terms: &[String]) -> Vec<(&IndexedDocument, f64)> {
+        let mut postings = Vec::with_capacity(terms.len());
+        for term in terms {
+            match self.postings.get(term) {
+                Some(files) => postings.push(files),
+                None => return Vec::new(),
+            }
+        }
+        // Recorrer la lista más corta y comprobar el resto
+        postings.sort_by_key(|files| files.len());
+
+        let total = self.documents.len() as f64;
+        let average_length = self
+            .documents
+            .values()
+            .map(|d| d.length as f64)
+            .sum::<f64>()
+            / total.max(1.0);
+
+        postings[0]
+            .keys()
+            .filter(|file_id| {
+                postings[1..]
+                    .iter()
+                    .all(|files| files.contains_key(*file_id))
+            })
+            .filter_map(|file_id| {
+                let entry = self.documents.get(file_id)?;
+                let length_norm =
+                    1.0 - BM25_B + BM25_B * entry.length as f64 / average_length.max(1.0);
+                let score = postings
+                    .iter()
+                    .map(|files| {
+                        let frequency = files[file_id] as f64;
+                        let matching = files.len() as f64;
+                        let idf = (1.0 + (total - matching + 0.5) / (matching + 0.5)).ln();
+                        idf * frequency * (BM25_K1 + 1.0) / (frequency + BM25_K1 * length_norm)
+                    })
+                    .sum();
+                Some((&entry.document, score))
+            })
+            .collect()
+    }
+}
+
+/// Estado cargado del índice
+#[derive(Default)]
+struct IndexState {
+    shards: HashMap<String, IndexShard>,
+    /// ID de archivo -> propietario de la partición donde está
+    owners: HashMap<String, String>,
+    /// Particiones modificadas desde la última persistencia
+    dirty: HashSet<String>,
+}
+
+impl IndexState {
+    fn remove(&mut self, file_id: &str) -> bool {
+        let Some(owner) = self.owners.remove(file_id) else {
+            return false;
+        };
+        if let Some(shard) = self.shards.get_mut(&owner) {
+            shard.remove(file_id);
+        }
+        self.dirty.insert(owner);
+        true
+    }
+}
+
+/// Índice invertido del contenido de los archivos sobre el sistema de archivos.
+///
+/// Hay una partición por propietario en `.search_index/<propietario>.json`
+/// (el nombre va en hexadecimal), de modo que una búsqueda solo lee las
+/// particiones que el usuario puede ver. El texto extraído de cada documento
+/// se guarda aparte en `.search_index/text/<id>.txt` para construir los
+/// fragmentos de los resultados. El índice se carga en memoria la primera
+/// vez que se usa y los cambios se escriben al llamar a `flush`.
+pub struct ContentIndexFsRepository {
+    index_dir: PathBuf,
+    state: OnceCell<RwLock<IndexState>>,
+}
+
+impl ContentIndexFsRepository {
+    /// Crea el índice bajo la raíz de almacenamiento
+    pub fn new(storage_root: impl AsRef<Path>) -> Self {
+        Self {
+            index_dir: storage_root.as_ref().join(".search_index"),
+            state: OnceCell::new(),
+        }
+    }
+
+    fn shard_path(&self, owner: &str) -> PathBuf {
+        self.index_dir
+            .join(format!("{}.json", hex::encode(owner.as_bytes())))
+    }
+
+    fn text_path(&self, file_id: &str) -> Result<PathBuf, DomainError> {
+        // Los identificadores se usan como nombres de archivo
+        if file_id.is_empty()
+            || !file_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return Err(DomainError::validation_error(format!(
+                "Invalid content index key: {}",
+                file_id
+            )));
+        }
+        Ok(self
+            .index_dir
+            .join(TEXT_DIR_NAME)
+            .join(format!("{}.txt", file_id)))
+    }
+
+    fn io_error(action: &str, e: impl std::fmt::Display) -> DomainError {
+        DomainError::internal_error("ContentIndex", format!("Failed to {}: {}", action, e))
+    }
+
+    async fn state(&self) -> Result<&RwLock<IndexState>, DomainError> {
+        self.state
+            .get_or_try_init(|| async { self.load().await.map(RwLock::new) })
+            .await
+    }
+
+    async fn load(&self) -> Result<IndexState, DomainError> {
+        let mut state = IndexState::default();
+        let mut entries = match fs::read_dir(&self.index_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(state),
+            Err(e) => return Err(Self::io_error("read content index", e)),
+        };
+
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| Self::io_error("read content index", e))?
+        {
+            let path = entry.path();
+            let Some(owner) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|stem| hex::decode(stem).ok())
+                .and_then(|owner| String::from_utf8(owner).ok())
+            else {
+                continue;
+            };
+
+            let shard: IndexShard = match fs::read(&path).await.map(|data| {
+                serde_json::from_slice(&data).map_err(|e| std::io::Error::other(e.to_string()))
+            }) {
+                Ok(Ok(shard)) => shard,
+                Ok(Err(e)) | Err(e) => {
+                    // Se rellenará al volver a indexar esos archivos
+                    tracing::warn!("Ignoring unreadable content index of {}: {}", owner, e);
+                    continue;
+                }
+            };
+            for file_id in shard.documents.keys() {
+                state.owners.insert(file_id.clone(), owner.clone());
+            }
+            state.shards.insert(owner, shard);
+        }
+
+        tracing::info!(
+            "Content index loaded: {} documents in {} partitions",
+            state.owners.len(),
+            state.shards.len()
+        );
+        Ok(state)
+    }
+
+    async fn delete_text(&self, file_id: &str) {
+        if let Ok(path) = self.text_path(file_id) {
+            if let Err(e) = fs::remove_file(&path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!("Could not delete indexed text of {}: {}", file_id, e);
+                }
+            }
+        }
+    }
+
+    async fn snippet(&self, file_id: &str, terms: &[String]) -> Snippet {
+        let Ok(path) = self.text_path(file_id) else {
+            return Snippet::default();
+        };
+        match fs::read_to_string(&path).await {
+            Ok(text) => build_snippet(&text, terms, SNIPPET_CHARS),
+            Err(_) => Snippet::default(),
+        }
+    }
+}
+
+/// Ruta normalizada para comparar prefijos de carpeta
+fn trimmed_path(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+#[async_trait]
+impl ContentIndexStoragePort for ContentIndexFsRepository {
+    async fn upsert_document(
+        &self,
+        document: IndexedDocument,
+        text: String,
+    ) -> Result<(), DomainError> {
+        let text_path = self.text_path(&document.file_id)?;
+        FileSystemUtils::atomic_write(&text_path, text.as_bytes())
+            .await
+            .map_err(|e| Self::io_error("store indexed text", e))?;
+
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        let mut length = 0u32;
+        for token in tokenize(&text) {
+            *frequencies.entry(token.term).or_default() += 1;
+            length += 1;
+        }
+
+        let mut state = self.state().await?.write().await;
+        state.remove(&document.file_id);
+
+        let file_id = document.file_id.clone();
+        let owner = document.owner.clone();
+        let shard = state.shards.entry(owner.clone()).or_default();
+        for (term, frequency) in frequencies {
+            shard
+                .postings
+                .entry(term)
+                .or_default()
+                .insert(file_id.clone(), frequency);
+        }
+        shard
+            .documents
+            .insert(file_id.clone(), ShardDocument { document, length });
+        state.owners.insert(file_id, owner.clone());
+        state.dirty.insert(owner);
+        Ok(())
+    }
+
+    async fn remove_document(&self, file_id: &str) -> Result<bool, DomainError> {
+        let removed = self.state().await?.write().await.remove(file_id);
+        if removed {
+            self.delete_text(file_id).await;
+        }
+        Ok(removed)
+    }
+
+    async fn remove_under_path(&self, path_prefix: &str) -> Result<usize, DomainError> {
+        let prefix = trimmed_path(path_prefix);
+        let mut state = self.state().await?.write().await;
+        let matching: Vec<String> = state
+            .shards
+            .values()
+            .flat_map(|shard| shard.documents.values())
+            .filter(|entry| {
+                let path = trimmed_path(&entry.document.path);
+                prefix.is_empty()
+                    || path
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .map(|entry| entry.document.file_id.clone())
+            .collect();
+        for file_id in &matching {
+            state.remove(file_id);
+        }
+        drop(state);
+
+        for file_id in &matching {
+            self.delete_text(file_id).await;
+        }
+        Ok(matching.len())
+    }
+
+    async fn search(
+        &self,
+        owners: &[String],
+        terms: &[String],
+        limit: usize,
+    ) -> Result<Vec<ContentMatch>, DomainError> {
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut ranked: Vec<(IndexedDocument, f64)> = {
+            let state = self.state().await?.read().await;
+            owners
+                .iter()
+                .filter_map(|owner| state.shards.get(owner))
+                .flat_map(|shard| shard.rank(terms))
+                .map(|(document, score)| (document.clone(), score))
+                .collect()
+        };
+        ranked.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| b.0.modified_at.cmp(&a.0.modified_at))
+        });
+        ranked.truncate(limit);
+
+        let mut matches = Vec::with_capacity(ranked.len());
+        for (document, score) in ranked {
+            let snippet = self.snippet(&document.file_id, terms).await;
+            matches.push(ContentMatch {
+                document,
+                score,
+                snippet,
+            });
+        }
+        Ok(matches)
+    }
+
+    async fn document_count(&self) -> Result<usize, DomainError> {
+        Ok(self.state().await?.read().await.owners.len())
+    }
+
+    async fn flush(&self) -> Result<(), DomainError> {
+        let mut state = self.state().await?.write().await;
+        let dirty: Vec<String> = state.dirty.drain().collect();
+
+        for owner in dirty {
+            let path = self.shard_path(&owner);
+            let shard = state.shards.get(&owner);
+            let result = match shard {
+                Some(shard) if !shard.documents.is_empty() => {
+                    let data = serde_json::to_vec(shard)
+                        .map_err(|e| Self::io_error("serialize content index", e))?;
+                    FileSystemUtils::atomic_write(&path, &data).await
+                }
+                _ => {
+                    state.shards.remove(&owner);
+                    match fs::remove_file(&path).await {
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                        other => other,
+                    }
+                }
+            };
+            if let Err(e) = result {
+                state.dirty.insert(owner);
+                return Err(Self::io_error("store content index", e));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::content_index::{query_terms, SHARED_OWNER};
+    use tempfile::tempdir;
+
+    fn document(id: &str, path: &str) -> IndexedDocument {
+        IndexedDocument::new(
+            id.to_string(),
+            path.rsplit('/').next().unwrap().to_string(),
+            path.to_string(),
+            "text/plain".to_string(),
+            10,
+            1,
+        )
+    }
+
+    fn owners(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_ranked_search_with_snippets_survives_reload() {
+        let dir = tempdir().unwrap();
+        let repo = ContentIndexFsRepository::new(dir.path());
+
+        repo.upsert_document(
+            document("doc-1", "Mi Carpeta - alice/plan.md"),
+            "Plan de presupuesto: el presupuesto del año y otro presupuesto".to_string(),
+        )
+        .await
+        .unwrap();
+        repo.upsert_document(
+            document("doc-2", "Mi Carpeta - alice/acta.txt"),
+            "Acta de la reunión donde se mencionó el presupuesto una vez, entre muchos otros temas"
+                .to_string(),
+        )
+        .await
+        .unwrap();
+        repo.upsert_document(
+            document("doc-3", "Mi Carpeta - bob/secreto.txt"),
+            "presupuesto privado".to_string(),
+        )
+        .await
+        .unwrap();
+        repo.flush().await.unwrap();
+
+        // Un índice nuevo sobre el mismo directorio ve lo persistido
+        let repo = ContentIndexFsRepository::new(dir.path());
+        assert_eq!(repo.document_count().await.unwrap(), 3);
+
+        let results = repo
+            .search(
+                &owners(&["alice", SHARED_OWNER]),
+                &query_terms("Presupuesto"),
+                10,
+            )
+            .await
+            .unwrap();
+        let ids: Vec<&str> = results
+            .iter()
+            .map(|m| m.document.file_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["doc-1", "doc-2"]);
+        assert!(results[0].score > results[1].score);
+        assert!(results[1].snippet.text.contains("presupuesto"));
+        assert_eq!(results[1].snippet.highlights.len(), 1);
+
+        // Todos los términos deben aparecer
+        let results = repo
+            .search(&owners(&["alice"]), &query_terms("presupuesto reunion"), 10)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.file_id, "doc-2");
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_and_remove_documents() {
+        let dir = tempdir().unwrap();
+        let repo = ContentIndexFsRepository::new(dir.path());
+        let alice = owners(&["alice"]);
+
+        repo.upsert_document(
+            document("doc-1", "Mi Carpeta - alice/a.txt"),
+            "viejo".into(),
+        )
+        .await
+        .unwrap();
+        repo.upsert_document(
+            document("doc-1", "Mi Carpeta - alice/a.txt"),
+            "nuevo".into(),
+        )
+        .await
+        .unwrap();
+        assert!(repo
+            .search(&alice, &query_terms("viejo"), 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            repo.search(&alice, &query_terms("nuevo"), 10)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        repo.upsert_document(
+            document("doc-2", "Mi Carpeta - alice/docs/b.txt"),
+            "nuevo".into(),
+        )
+        .await
+        .unwrap();
+        repo.upsert_document(
+            document("doc-3", "Mi Carpeta - alice/docs-old/c.txt"),
+            "nuevo".into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            repo.remove_under_path("/Mi Carpeta - alice/docs/")
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(repo.remove_document("doc-1").await.unwrap());
+        assert!(!repo.remove_document("doc-1").await.unwrap());
+        assert!(repo.text_path("doc-1").is_ok_and(|path| !path.exists()));
+        repo.flush().await.unwrap();
+
+        let repo = ContentIndexFsRepository::new(dir.path());
+        let remaining = repo
+            .search(&alice, &query_terms("nuevo"), 10)
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].document.file_id, "doc-3");
+        assert!(repo.text_path("../etc").is_err());
+    }
+}
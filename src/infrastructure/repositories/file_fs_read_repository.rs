@@ -15,6 +15,8 @@ use crate::infrastructure::repositories::file_metadata_manager::{
 };
 use crate::infrastructure::repositories::file_path_resolver::FilePathResolver;
 use crate::infrastructure::repositories::parallel_file_processor::ParallelFileProcessor;
+use crate::infrastructure::services::file_encryption_service::FileEncryptionService;
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
 
 /// Implementación de repositorio para operaciones de lectura de archivos
 pub struct FileFsReadRepository {
@@ -23,6 +25,7 @@ pub struct FileFsReadRepository {
     path_resolver: Arc<FilePathResolver>,
     config: AppConfig,
     parallel_processor: Option<Arc<ParallelFileProcessor>>,
+    encryption: Option<Arc<FileEncryptionService>>,
 }
 
 impl FileFsReadRepository {
@@ -40,9 +43,16 @@ impl FileFsReadRepository {
             path_resolver,
             config,
             parallel_processor,
+            encryption: None,
         }
     }
 
+    /// Descifra el contenido de los archivos cifrados en reposo
+    pub fn with_encryption(mut self, encryption: Arc<FileEncryptionService>) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
     /// Crea un stub para pruebas
     pub fn default_stub() -> Self {
         Self {
@@ -51,6 +61,7 @@ impl FileFsReadRepository {
             path_resolver: Arc::new(FilePathResolver::default_stub()),
             config: AppConfig::default(),
             parallel_processor: None,
+            encryption: None,
         }
     }
 
@@ -135,6 +146,12 @@ impl FileFsReadRepository {
                 }
             })?;
 
+        // Para archivos cifrados el tamaño es el del contenido en claro
+        let size = match &self.encryption {
+            Some(encryption) => encryption.plaintext_size(&abs_path).await?.unwrap_or(size),
+            None => size,
+        };
+
         // Obtener nombre del archivo de la ruta
         let name = match storage_path.file_name() {
             Some(name) => name,
@@ -174,7 +191,10 @@ impl FileFsReadRepository {
             )
             .await?;
 
-        Ok(file)
+        Ok(match self.metadata_manager.get_etag(&abs_path).await {
+            Some(etag) => file.with_etag(etag),
+            None => file,
+        })
     }
 }
 
@@ -217,22 +237,68 @@ impl FileReadPort for FileFsReadRepository {
         })?;
 
         // Ruta absoluta del archivo
-        let _abs_path = self.path_resolver.resolve_storage_path(file.storage_path());
+        let abs_path = self.path_resolver.resolve_storage_path(file.storage_path());
 
-        // Implementación real debe leer el contenido del archivo
-        // Por ahora, devolvemos un vector vacío
-        Ok(Vec::new())
+        let content = tokio::fs::read(&abs_path)
+            .await
+            .map_err(|e| DomainError::internal_error("File", e.to_string()))?;
+
+        match &self.encryption {
+            Some(encryption) => encryption.decrypt_bytes(content).await,
+            None => Ok(content),
+        }
     }
 
     async fn get_file_stream(
         &self,
-        _id: &str,
+        id: &str,
     ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
-        // Implementación real debe devolver un stream de bytes del archivo
-        // Por ahora, lanzamos un error
-        Err(DomainError::internal_error(
-            "File stream",
-            "Stream functionality not yet implemented",
-        ))
+        let storage_path = self
+            .path_resolver
+            .get_path_by_id(id)
+            .await
+            .map_err(|e| DomainError::not_found("File", e.to_string()))?;
+        let abs_path = self.path_resolver.resolve_storage_path(&storage_path);
+
+        let file = tokio::fs::File::open(&abs_path)
+            .await
+            .map_err(|e| DomainError::not_found("File", format!("{}: {}", id, e)))?;
+
+        match &self.encryption {
+            Some(encryption) => {
+                let stream = encryption
+                    .decrypt_reader(file)
+                    .await
+                    .map_err(|e| DomainError::internal_error("File stream", e.to_string()))?;
+                Ok(Box::new(stream))
+            }
+            None => Ok(Box::new(tokio_util::io::ReaderStream::new(file))),
+        }
+    }
+
+    async fn get_file_range_stream(
+        &self,
+        id: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
+        let storage_path = self
+            .path_resolver
+            .get_path_by_id(id)
+            .await
+            .map_err(|e| DomainError::not_found("File", e.to_string()))?;
+        let abs_path = self.path_resolver.resolve_storage_path(&storage_path);
+
+        let stream = match &self.encryption {
+            Some(encryption) => {
+                let file = tokio::fs::File::open(&abs_path)
+                    .await
+                    .map_err(|e| DomainError::not_found("File", format!("{}: {}", id, e)))?;
+                encryption.decrypt_range_reader(file, start, end).await
+            }
+            None => FileSystemUtils::read_range_stream(&abs_path, start, end).await,
+        }
+        .map_err(|e| DomainError::internal_error("File stream", e.to_string()))?;
+        Ok(Box::new(stream))
     }
 }
@@ -3,6 +3,7 @@ use bytes::Bytes;
 use futures::{Stream, StreamExt};
 use mime_guess::from_path;
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::fs::File as TokioFile;
@@ -20,11 +21,16 @@ use crate::domain::repositories::file_repository::{
     FileRepository, FileRepositoryError, FileRepositoryResult,
 };
 // use crate::application::ports::outbound::IdMappingPort;
-use crate::application::ports::outbound::FileStoragePort;
+use crate::application::ports::outbound::{FileStoragePort, FileStream};
+use crate::application::ports::storage_ports::{ContentStoragePort, StoredContent};
 use crate::common::config::AppConfig;
 use crate::common::errors::DomainError;
 use crate::domain::services::path_service::{PathService, StoragePath};
 use crate::infrastructure::repositories::parallel_file_processor::ParallelFileProcessor;
+use crate::infrastructure::services::content_reference::ReferenceSigner;
+use crate::infrastructure::services::file_encryption_service::{
+    key_owner_for_path, FileEncryptionService,
+};
 use crate::infrastructure::services::file_metadata_cache::{CacheEntryType, FileMetadataCache};
 use crate::infrastructure::services::id_mapping_service::IdMappingError;
 
@@ -59,6 +65,23 @@ pub struct FileFsRepository {
     metadata_cache: Arc<FileMetadataCache>,
     config: AppConfig,
     parallel_processor: Option<Arc<ParallelFileProcessor>>,
+    encryption: Option<Arc<FileEncryptionService>>,
+    content_store: Option<Arc<dyn ContentStoragePort>>,
+    reference_signer: Option<Arc<ReferenceSigner>>,
+}
+
+/// Size, timestamps and content version of a stored file
+struct StoredFileMetadata {
+    size: u64,
+    created_at: u64,
+    modified_at: u64,
+    etag: Option<String>,
+}
+
+/// Content of a file being created
+enum NewFileContent {
+    Bytes(Vec<u8>),
+    Stream(Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>),
 }
 
 impl FileFsRepository {
@@ -79,6 +102,9 @@ impl FileFsRepository {
             metadata_cache,
             config: AppConfig::default(),
             parallel_processor: None,
+            encryption: None,
+            content_store: None,
+            reference_signer: None,
         }
     }
 
@@ -99,225 +125,1062 @@ impl FileFsRepository {
             metadata_cache,
             config: AppConfig::default(),
             parallel_processor: Some(parallel_processor),
+            encryption: None,
+            content_store: None,
+            reference_signer: None,
         }
     }
 
-    /// Resolves a domain storage path to an absolute filesystem path
-    fn resolve_storage_path(&self, storage_path: &StoragePath) -> PathBuf {
-        self.path_service.resolve_path(storage_path)
-    }
-
-    /// Resolves a legacy PathBuf to an absolute filesystem path
-    #[allow(dead_code)]
-    fn resolve_legacy_path(&self, relative_path: &std::path::Path) -> PathBuf {
-        self.storage_mediator.resolve_path(relative_path)
+    /// Enables at-rest encryption of file contents
+    pub fn with_encryption(mut self, encryption: Arc<FileEncryptionService>) -> Self {
+        self.encryption = Some(encryption);
+        self
     }
 
-    /// Returns a reference to the ID mapping service
-    pub fn id_mapping_service(
-        &self,
-    ) -> &Arc<dyn crate::application::ports::outbound::IdMappingPort> {
-        &self.id_mapping_service
+    /// Keeps file contents in a content store, leaving only a reference to
+    /// them at the path of each file, signed so uploads cannot forge one
+    pub fn with_content_store(
+        mut self,
+        content_store: Arc<dyn ContentStoragePort>,
+        reference_signer: Arc<ReferenceSigner>,
+    ) -> Self {
+        self.content_store = Some(content_store);
+        self.reference_signer = Some(reference_signer);
+        self
     }
 
-    /// Returns a reference to the metadata cache
-    pub fn metadata_cache(&self) -> &Arc<FileMetadataCache> {
-        &self.metadata_cache
+    /// Serializes the signed reference written at the path of a file
+    fn encode_reference(&self, content: &StoredContent) -> FileRepositoryResult<Vec<u8>> {
+        self.reference_signer
+            .as_ref()
+            .map(|signer| signer.encode(content))
+            .ok_or_else(|| FileRepositoryError::Other("No content store configured".to_string()))
     }
 
-    /// Returns a reference to the root path
-    pub fn get_root_path(&self) -> &PathBuf {
-        &self.root_path
+    /// Reads the reference at the path of a file. Without a content store
+    /// every file keeps its content in the tree
+    async fn read_reference(
+        &self,
+        abs_path: &std::path::Path,
+    ) -> std::io::Result<Option<StoredContent>> {
+        match &self.reference_signer {
+            Some(signer) => signer.read(abs_path).await,
+            None => Ok(None),
+        }
     }
 
-    /// Checks if a file exists at a given storage path
-    async fn file_exists_at_storage_path(
+    /// Returns the bytes written at the path of a file: the reference to the
+    /// content when a content store is configured, or the content itself,
+    /// encrypted when encryption is enabled
+    async fn seal_content(
         &self,
         storage_path: &StoragePath,
-    ) -> FileRepositoryResult<bool> {
-        let abs_path = self.resolve_storage_path(storage_path);
-
-        // Try to get from advanced cache first
-        if let Some(is_file) = self.metadata_cache.is_file(&abs_path).await {
-            tracing::debug!(
-                "Metadata cache hit for existence check: {} - path: {}",
-                is_file,
-                abs_path.display()
-            );
-            return Ok(is_file);
+        content: Vec<u8>,
+    ) -> FileRepositoryResult<Vec<u8>> {
+        if let Some(store) = &self.content_store {
+            let stream = futures::stream::once(async move { Ok(Bytes::from(content)) });
+            let stored = store.put_content(Box::pin(stream)).await?;
+            return self.encode_reference(&stored);
         }
 
-        // If not in cache, verify directly and update cache
-        tracing::debug!(
-            "Metadata cache miss for existence check: {}",
-            abs_path.display()
-        );
-
-        // Use timeout to avoid blocking
-        match time::timeout(self.config.timeouts.file_timeout(), fs::metadata(&abs_path)).await {
-            Ok(Ok(metadata)) => {
-                let is_file = metadata.is_file();
-
-                // Update cache with fresh information
-                if let Err(e) = self.metadata_cache.refresh_metadata(&abs_path).await {
-                    tracing::warn!("Failed to update cache for {}: {}", abs_path.display(), e);
-                }
-
-                if is_file {
-                    tracing::debug!("File exists and is accessible: {}", abs_path.display());
-                    Ok(true)
-                } else {
-                    tracing::warn!("Path exists but is not a file: {}", abs_path.display());
-                    Ok(false)
-                }
+        match &self.encryption {
+            Some(encryption) => {
+                let owner = key_owner_for_path(&storage_path.to_string());
+                Ok(encryption.encrypt_bytes(&owner, content).await?)
             }
-            Ok(Err(e)) => {
-                tracing::warn!("File check failed: {} - {}", abs_path.display(), e);
+            None => Ok(content),
+        }
+    }
 
-                // Add to cache as non-existent
-                let entry_type = CacheEntryType::Unknown;
-                let file_metadata =
-                    crate::infrastructure::services::file_metadata_cache::FileMetadata::new(
-                        abs_path.clone(),
-                        false,
-                        entry_type,
-                        None,
-                        None,
-                        None,
-                        None,
-                        Duration::from_millis(self.config.timeouts.file_operation_ms),
-                    );
-                self.metadata_cache.update_cache(file_metadata).await;
+    /// Writes the content of a new file as it arrives: the reference to the
+    /// content when a content store is configured, or the content itself,
+    /// encrypted when encryption is enabled
+    async fn write_content_stream(
+        &self,
+        storage_path: &StoragePath,
+        abs_path: &std::path::Path,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> FileRepositoryResult<()> {
+        if let Some(store) = &self.content_store {
+            let stored = store.put_content(stream).await?;
+            fs::write(abs_path, self.encode_reference(&stored)?).await?;
+            return Ok(());
+        }
 
-                Ok(false)
+        let mut stream = match &self.encryption {
+            Some(encryption) => {
+                let owner = key_owner_for_path(&storage_path.to_string());
+                encryption.encrypt_stream(&owner, stream).await?
             }
-            Err(_) => {
-                tracing::warn!("Timeout checking file metadata: {}", abs_path.display());
-                return Err(FileRepositoryError::Timeout(format!(
-                    "Timeout checking file: {}",
-                    abs_path.display()
-                )));
+            None => stream,
+        };
+        let mut file = TokioFile::create(abs_path).await?;
+        let written: std::io::Result<()> = async {
+            while let Some(chunk) = stream.next().await {
+                file.write_all(&chunk?).await?;
             }
+            file.flush().await
+        }
+        .await;
+        if let Err(e) = written {
+            // A partial file would look like a complete one
+            let _ = fs::remove_file(abs_path).await;
+            return Err(e.into());
         }
+        Ok(())
     }
 
-    /// Legacy method for checking file existence with PathBuf
-    #[allow(dead_code)]
-    pub async fn file_exists(&self, path: &std::path::Path) -> FileRepositoryResult<bool> {
-        let abs_path = self.resolve_legacy_path(path);
+    /// Creates a new file next to any existing one with the same name, which
+    /// gets a numeric suffix instead
+    async fn save_new_file(
+        &self,
+        name: String,
+        folder_id: Option<String>,
+        content_type: String,
+        content: NewFileContent,
+    ) -> FileRepositoryResult<File> {
+        // Get the folder path from the mediator
+        let folder_path = match &folder_id {
+            Some(id) => {
+                match self.storage_mediator.get_folder_path(id).await {
+                    Ok(path) => {
+                        tracing::info!("Using folder path: {:?} for folder_id: {:?}", path, id);
+                        // Convert to StoragePath - use just the folder name to avoid path duplication
+                        // Get just the folder name to avoid path duplication
+                        let lossy = path.to_string_lossy().to_string();
+                        let folder_name = path
+                            .file_name()
+                            .and_then(|f| f.to_str())
+                            .unwrap_or_else(|| &lossy);
+                        tracing::info!("Using folder name: {} for StoragePath", folder_name);
+                        StoragePath::from_string(folder_name)
+                    }
+                    Err(e) => {
+                        tracing::error!("Error getting folder: {}", e);
+                        // Root path
+                        StoragePath::root()
+                    }
+                }
+            }
+            None => StoragePath::root(),
+        };
 
-        // Try to get from advanced cache first
-        if let Some(is_file) = self.metadata_cache.is_file(&abs_path).await {
-            tracing::debug!(
-                "Metadata cache hit for legacy existence check: {} - path: {}",
-                is_file,
-                abs_path.display()
-            );
-            return Ok(is_file);
-        }
+        // Create the storage path for the file
+        let mut file_storage_path = folder_path.join(&name);
+        tracing::info!("Created file path: {:?}", file_storage_path.to_string());
 
-        // If not in cache, verify directly
+        // Check if file already exists and generate a unique name if needed
+        let mut exists = self.file_exists_at_storage_path(&file_storage_path).await?;
         tracing::info!(
-            "Checking if file exists: {} - path: {}",
-            abs_path.exists(),
-            abs_path.display()
+            "File exists check: {} for path: {:?}",
+            exists,
+            file_storage_path.to_string()
         );
 
-        match time::timeout(self.config.timeouts.file_timeout(), fs::metadata(&abs_path)).await {
-            Ok(Ok(metadata)) => {
-                let is_file = metadata.is_file();
+        // If file exists, generate a unique name by adding a suffix
+        let mut original_name = name.clone();
+        let mut counter = 1;
 
-                // Update cache with fresh information
-                if let Err(e) = self.metadata_cache.refresh_metadata(&abs_path).await {
-                    tracing::warn!("Failed to update cache for {}: {}", abs_path.display(), e);
-                }
+        while exists {
+            // Extract filename and extension
+            let file_stem;
+            let extension;
 
-                if is_file {
-                    tracing::info!("File exists and is accessible: {}", abs_path.display());
-                    return Ok(true);
-                } else {
-                    tracing::warn!("Path exists but is not a file: {}", abs_path.display());
-                    return Ok(false);
-                }
+            if let Some(dot_pos) = original_name.rfind('.') {
+                file_stem = original_name[..dot_pos].to_string();
+                extension = original_name[dot_pos..].to_string();
+            } else {
+                file_stem = original_name.clone();
+                extension = "".to_string();
             }
-            Ok(Err(e)) => {
-                tracing::warn!(
-                    "File exists but metadata check failed: {} - {}",
-                    abs_path.display(),
-                    e
+
+            // Create new name with counter
+            let new_name = format!("{}_{}{}", file_stem, counter, extension);
+
+            // Update the storage path with the new name
+            let new_file_storage_path = folder_path.join(&new_name);
+
+            // Check if the new path exists
+            exists = self
+                .file_exists_at_storage_path(&new_file_storage_path)
+                .await?;
+
+            if !exists {
+                // Update variables for the new path
+                tracing::info!(
+                    "Generated unique name for duplicate file: {} -> {}",
+                    original_name,
+                    new_name
                 );
-                return Ok(false);
-            }
-            Err(_) => {
-                tracing::warn!("Timeout checking file metadata: {}", abs_path.display());
-                return Err(FileRepositoryError::Timeout(format!(
-                    "Timeout checking file: {}",
-                    abs_path.display()
-                )));
+                original_name = new_name.clone();
+                file_storage_path = new_file_storage_path;
+            } else {
+                // Try next counter
+                counter += 1;
             }
         }
-    }
 
-    /// Helper method to create a File entity from a storage path and metadata
-    async fn create_file_entity(
-        &self,
-        id: String,
-        name: String,
-        storage_path: StoragePath,
-        size: u64,
-        mime_type: String,
-        folder_id: Option<String>,
-        created_at: Option<u64>,
-        modified_at: Option<u64>,
-    ) -> FileRepositoryResult<File> {
-        // If timestamps are provided, use them; otherwise, let File::new create default timestamps
-        if let (Some(created), Some(modified)) = (created_at, modified_at) {
-            File::with_timestamps(
-                id,
-                name,
-                storage_path,
-                size,
-                mime_type,
-                folder_id,
-                created,
-                modified,
-            )
-            .map_err(|e| FileRepositoryError::Other(e.to_string()))
-        } else {
-            File::new(id, name, storage_path, size, mime_type, folder_id)
-                .map_err(|e| FileRepositoryError::Other(e.to_string()))
-        }
-    }
+        // Create parent directories if they don't exist
+        let abs_path = self.resolve_storage_path(&file_storage_path);
+        self.ensure_parent_directory(&abs_path).await?;
 
-    /// Extracts file metadata from a physical path with timeout and cache
-    async fn get_file_metadata(&self, abs_path: &PathBuf) -> FileRepositoryResult<(u64, u64, u64)> {
-        // Try to get from cache first
-        if let Some(cached_metadata) = self.metadata_cache.get_metadata(abs_path).await {
-            if let (Some(size), Some(created_at), Some(modified_at)) = (
-                cached_metadata.size,
-                cached_metadata.created_at,
-                cached_metadata.modified_at,
-            ) {
-                tracing::debug!("Using cached metadata for: {}", abs_path.display());
-                return Ok((size, created_at, modified_at));
+        match content {
+            NewFileContent::Stream(stream) => {
+                self.write_content_stream(&file_storage_path, &abs_path, stream)
+                    .await?
             }
-        }
+            NewFileContent::Bytes(content) => {
+                let content = self.seal_content(&file_storage_path, content).await?;
+
+                // Calculate file size
+                let content_size = content.len() as u64;
+
+                // Verificar si el archivo es muy grande para el procesamiento paralelo de escritura
+                if self
+                    .config
+                    .resources
+                    .needs_parallel_processing(content_size, &self.config.concurrency)
+                {
+                    // Para archivos muy grandes, usar procesador paralelo
+                    tracing::info!(
+                        "Using parallel file processor for large file write: {} ({} bytes)",
+                        abs_path.display(),
+                        content_size
+                    );
 
-        // If not in cache or incomplete metadata, load from filesystem
-        let metadata =
-            match time::timeout(self.config.timeouts.file_timeout(), fs::metadata(&abs_path)).await
-            {
-                Ok(Ok(metadata)) => metadata,
-                Ok(Err(e)) => return Err(FileRepositoryError::IoError(e)),
-                Err(_) => {
-                    return Err(FileRepositoryError::Timeout(format!(
+                    // Usar el procesador pre-configurado si está disponible o crear uno nuevo
+                    let result = if let Some(processor) = &self.parallel_processor {
+                        tracing::debug!("Using pre-configured parallel processor with buffer pool");
+                        processor.write_file_parallel(&abs_path, &content).await
+                    } else {
+                        tracing::debug!("Creating on-demand parallel processor");
+                        // Importar y crear el procesador paralelo
+                        use crate::infrastructure::repositories::parallel_file_processor::ParallelFileProcessor;
+                        let processor = ParallelFileProcessor::new(self.config.clone());
+
+                        // Escribir archivo en paralelo
+                        processor.write_file_parallel(&abs_path, &content).await
+                    };
+
+                    // Manejar resultado
+                    result?;
+
+                    tracing::info!(
+                        "Successfully wrote {}MB file using parallel chunks",
+                        content_size / (1024 * 1024)
+                    );
+                } else if content_size > self.config.resources.large_file_threshold_mb * 1024 * 1024
+                {
+                    // Para archivos grandes pero no tanto como para paralelizar, usar chunking
+                    let file_creation_result = time::timeout(
+                        self.config.timeouts.file_timeout(),
+                        TokioFile::create(&abs_path),
+                    )
+                    .await
+                    .map_err(|_| {
+                        FileRepositoryError::Timeout(format!(
+                            "Timeout creating file: {}",
+                            abs_path.display()
+                        ))
+                    })?
+                    .map_err(FileRepositoryError::IoError)?;
+
+                    let mut file = file_creation_result;
+
+                    // Define el tamaño del chunk usando la configuración
+                    let chunk_size = self.config.resources.chunk_size_bytes;
+
+                    tracing::info!(
+                        "Using chunked writing with size {} bytes for file: {} ({} bytes)",
+                        chunk_size,
+                        abs_path.display(),
+                        content_size
+                    );
+
+                    // Divide el contenido en chunks y escribe cada uno con timeout
+                    for (i, chunk) in content.chunks(chunk_size).enumerate() {
+                        time::timeout(self.config.timeouts.file_timeout(), file.write_all(chunk))
+                            .await
+                            .map_err(|_| {
+                                FileRepositoryError::Timeout(format!(
+                                    "Timeout writing chunk {} to file: {}",
+                                    i,
+                                    abs_path.display()
+                                ))
+                            })?
+                            .map_err(FileRepositoryError::IoError)?;
+
+                        tracing::debug!(
+                            "Written chunk {} ({} bytes) to file {}",
+                            i,
+                            chunk.len(),
+                            abs_path.display()
+                        );
+                    }
+
+                    // Ensure file is properly flushed and closed
+                    time::timeout(self.config.timeouts.file_timeout(), file.flush())
+                        .await
+                        .map_err(|_| {
+                            FileRepositoryError::Timeout(format!(
+                                "Timeout flushing file: {}",
+                                abs_path.display()
+                            ))
+                        })?
+                        .map_err(FileRepositoryError::IoError)?;
+                } else {
+                    // Para archivos pequeños, escritura simple
+                    let file_creation_result = time::timeout(
+                        self.config.timeouts.file_timeout(),
+                        TokioFile::create(&abs_path),
+                    )
+                    .await
+                    .map_err(|_| {
+                        FileRepositoryError::Timeout(format!(
+                            "Timeout creating file: {}",
+                            abs_path.display()
+                        ))
+                    })?
+                    .map_err(FileRepositoryError::IoError)?;
+
+                    let mut file = file_creation_result;
+
+                    // Para archivos pequeños, escribe todo el contenido de una vez
+                    time::timeout(
+                        self.config.timeouts.file_timeout(),
+                        file.write_all(&content),
+                    )
+                    .await
+                    .map_err(|_| {
+                        FileRepositoryError::Timeout(format!(
+                            "Timeout writing to file: {}",
+                            abs_path.display()
+                        ))
+                    })?
+                    .map_err(FileRepositoryError::IoError)?;
+
+                    // Ensure file is properly flushed and closed
+                    time::timeout(self.config.timeouts.file_timeout(), file.flush())
+                        .await
+                        .map_err(|_| {
+                            FileRepositoryError::Timeout(format!(
+                                "Timeout flushing file: {}",
+                                abs_path.display()
+                            ))
+                        })?
+                        .map_err(FileRepositoryError::IoError)?;
+                }
+            }
+        }
+
+        // Get file metadata
+        let metadata = self.get_file_metadata(&abs_path).await?;
+
+        // Determine the MIME type
+        let mime_type = if content_type.is_empty() {
+            from_path(&abs_path).first_or_octet_stream().to_string()
+        } else {
+            content_type
+        };
+
+        // Create and return the file entity with a persistent ID
+        let id = self
+            .id_mapping_service
+            .get_or_create_id(&file_storage_path)
+            .await?;
+
+        // Keep a string representation of the path for logging
+        let path_string = file_storage_path.to_string();
+
+        let file = self
+            .create_file_entity(
+                id.clone(),    // Clone ID for use in logging
+                original_name, // Use the potentially modified name with counter suffix
+                file_storage_path,
+                mime_type,
+                folder_id,
+                metadata,
+            )
+            .await?;
+
+        // Ensure ID mapping is persisted - this is critical for later retrieval
+        // Ejecutar múltiples intentos de guardado con verificación para garantizar persistencia
+        for attempt in 1..=3 {
+            match self.id_mapping_service.save_changes().await {
+                Ok(_) => {
+                    tracing::info!(
+                        "Successfully saved ID mapping for file ID: {} -> path: {} (attempt {})",
+                        id,
+                        path_string,
+                        attempt
+                    );
+
+                    // Verificar que el mapeo se puede recuperar después de guardado
+                    if let Ok(verified_path) = self.id_mapping_service.get_path_by_id(&id).await {
+                        if verified_path.to_string() == path_string {
+                            tracing::info!(
+                                "Verified ID mapping is retrievable after save: {} -> {}",
+                                id,
+                                path_string
+                            );
+                            break; // Guaradado correcto y verificado, salir del bucle
+                        } else {
+                            tracing::error!(
+                                "Mapping verification failed: expected {} but got {}",
+                                path_string,
+                                verified_path.to_string()
+                            );
+                            if attempt < 3 {
+                                tracing::info!(
+                                    "Will retry saving ID mapping (attempt {}/3)",
+                                    attempt + 1
+                                );
+                                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                                continue;
+                            } else {
+                                return Err(FileRepositoryError::Other(format!(
+                                    "Failed to verify ID mapping for file: {} after 3 attempts",
+                                    id
+                                )));
+                            }
+                        }
+                    } else {
+                        tracing::error!("Cannot verify mapping, ID {} not found after save", id);
+                        if attempt < 3 {
+                            tracing::info!(
+                                "Will retry saving ID mapping (attempt {}/3)",
+                                attempt + 1
+                            );
+                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                            continue;
+                        } else {
+                            return Err(FileRepositoryError::Other(format!(
+                                "Failed to verify ID mapping for file: {} after 3 attempts",
+                                id
+                            )));
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to save ID mapping for file {}: {} (attempt {})",
+                        id,
+                        e,
+                        attempt
+                    );
+                    if attempt < 3 {
+                        tracing::info!("Will retry saving ID mapping (attempt {}/3)", attempt + 1);
+                        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                        continue;
+                    } else {
+                        return Err(FileRepositoryError::Other(format!(
+                            "Failed to save ID mapping for file: {} after 3 attempts - {}",
+                            id, e
+                        )));
+                    }
+                }
+            }
+        }
+
+        // Invalidate any directory cache entries for the parent folders
+        // to ensure directory listings show the new file
+        if let Some(parent_dir) = abs_path.parent() {
+            self.metadata_cache.invalidate_directory(parent_dir).await;
+        }
+
+        tracing::info!("Saved file: {} with ID: {}", path_string, file.id());
+        Ok(file)
+    }
+
+    /// Reads the reference stored at the path of a file, or `None` when the
+    /// file keeps its content in the tree
+    pub(crate) async fn stored_content(
+        &self,
+        abs_path: &std::path::Path,
+    ) -> FileRepositoryResult<Option<StoredContent>> {
+        let Some(content) = self.read_reference(abs_path).await? else {
+            return Ok(None);
+        };
+        match &self.content_store {
+            Some(store) if store.backend_name() == content.backend => Ok(Some(content)),
+            _ => Err(FileRepositoryError::Other(format!(
+                "Content of {} is kept in the '{}' storage backend, which is not configured",
+                abs_path.display(),
+                content.backend
+            ))),
+        }
+    }
+
+    /// Returns the content store holding a content read from the tree
+    fn content_store_for(
+        &self,
+        content: &StoredContent,
+    ) -> FileRepositoryResult<&Arc<dyn ContentStoragePort>> {
+        self.content_store.as_ref().ok_or_else(|| {
+            FileRepositoryError::Other(format!(
+                "No content store configured for the '{}' backend",
+                content.backend
+            ))
+        })
+    }
+
+    /// Releases a content no file refers to anymore. Failures only leave
+    /// unused data behind, so they are logged instead of returned
+    pub(crate) async fn release_stored_content(&self, content: Option<StoredContent>) {
+        let (Some(content), Some(store)) = (content, &self.content_store) else {
+            return;
+        };
+        if let Err(e) = store.release_content(&content).await {
+            tracing::warn!("Could not release stored content: {}", e);
+        }
+    }
+
+    /// Collects a stored content in memory
+    async fn read_stored_content(&self, content: &StoredContent) -> FileRepositoryResult<Vec<u8>> {
+        if !self.config.resources.can_load_in_memory(content.size) {
+            return Err(FileRepositoryError::Other(format!(
+                "File too large to load in memory: {} MB (max: {} MB)",
+                content.size / (1024 * 1024),
+                self.config.resources.max_in_memory_file_size_mb
+            )));
+        }
+
+        let stream = self
+            .content_store_for(content)?
+            .get_content_stream(content)
+            .await?;
+        let mut stream = Box::into_pin(stream);
+        let mut data = Vec::with_capacity(content.size as usize);
+        while let Some(chunk) = stream.next().await {
+            data.extend_from_slice(&chunk?);
+        }
+        Ok(data)
+    }
+
+    /// Refreshes the cached metadata of a file whose content was replaced
+    async fn cache_updated_file(
+        &self,
+        physical_path: &std::path::Path,
+        content_size: u64,
+        mime_type: &str,
+    ) {
+        if let Ok(metadata) = std::fs::metadata(physical_path) {
+            // Create a FileMetadata instance and update the cache
+            use crate::infrastructure::services::file_metadata_cache::FileMetadata;
+            use std::time::UNIX_EPOCH;
+
+            // Get modified and created times
+            let created_at = metadata
+                .created()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
+
+            let modified_at = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
+
+            // Default TTL
+            let ttl = Duration::from_secs(60); // 1 minute
+
+            // Create FileMetadata instance
+            let mut file_metadata = FileMetadata::new(
+                physical_path.to_path_buf(),
+                true, // exists
+                CacheEntryType::File,
+                Some(content_size),
+                Some(mime_type.to_string()),
+                created_at,
+                modified_at,
+                ttl,
+            );
+            file_metadata.etag = FileMetadataCache::content_etag(&metadata);
+
+            // Update the cache
+            self.metadata_cache.update_cache(file_metadata).await;
+        }
+    }
+
+    /// Returns the plaintext size of a file given its on-disk size
+    async fn logical_size(&self, abs_path: &std::path::Path, disk_size: u64) -> u64 {
+        if let Ok(Some(content)) = self.read_reference(abs_path).await {
+            return content.size;
+        }
+
+        match &self.encryption {
+            Some(encryption) => encryption
+                .plaintext_size(abs_path)
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or(disk_size),
+            None => disk_size,
+        }
+    }
+
+    /// Resolves a domain storage path to an absolute filesystem path
+    fn resolve_storage_path(&self, storage_path: &StoragePath) -> PathBuf {
+        self.path_service.resolve_path(storage_path)
+    }
+
+    /// Resolves a legacy PathBuf to an absolute filesystem path
+    #[allow(dead_code)]
+    fn resolve_legacy_path(&self, relative_path: &std::path::Path) -> PathBuf {
+        self.storage_mediator.resolve_path(relative_path)
+    }
+
+    /// Returns a reference to the ID mapping service
+    pub fn id_mapping_service(
+        &self,
+    ) -> &Arc<dyn crate::application::ports::outbound::IdMappingPort> {
+        &self.id_mapping_service
+    }
+
+    /// Returns a reference to the metadata cache
+    pub fn metadata_cache(&self) -> &Arc<FileMetadataCache> {
+        &self.metadata_cache
+    }
+
+    /// Returns a reference to the root path
+    pub fn get_root_path(&self) -> &PathBuf {
+        &self.root_path
+    }
+
+    /// Lists at most `limit` files of a folder, skipping the first `offset`
+    /// in directory order. Skipped entries are never stat'ed or mapped to an
+    /// ID, so walking a large folder page by page stays cheap
+    async fn list_files_in_folder(
+        &self,
+        folder_id: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> FileRepositoryResult<Vec<File>> {
+        let Some((folder_storage_path, abs_folder_path)) = self.folder_location(folder_id).await
+        else {
+            return Ok(Vec::new());
+        };
+
+        // Read directory entries
+        let mut files_result = Vec::new();
+        let mut skipped = 0;
+
+        // Read the directory entries
+        match fs::read_dir(&abs_folder_path).await {
+            Ok(mut entries) => {
+                while let Some(entry) = entries.next_entry().await.unwrap_or(None) {
+                    if !Self::is_listed_file(&entry).await {
+                        continue;
+                    }
+
+                    // Skip the files before the requested page, before any costly lookup
+                    if skipped < offset {
+                        skipped += 1;
+                        continue;
+                    }
+                    if files_result.len() >= limit {
+                        break;
+                    }
+
+                    if let Some(file) = self
+                        .file_of_entry(&entry, &folder_storage_path, folder_id)
+                        .await
+                    {
+                        tracing::info!("Added file to result list: {}", file.name());
+                        files_result.push(file);
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("Error reading directory {:?}: {}", abs_folder_path, e);
+                return Err(FileRepositoryError::IoError(e));
+            }
+        }
+
+        // Persist any new ID mappings that were created
+        if !files_result.is_empty() {
+            if let Err(e) = self.id_mapping_service.save_changes().await {
+                tracing::error!("Error saving ID mappings: {}", e);
+            }
+        }
+
+        tracing::info!(
+            "Found {} files in folder {:?}",
+            files_result.len(),
+            folder_id
+        );
+        Ok(files_result)
+    }
+
+    /// Walks the files of a folder with a single directory read, building
+    /// each file as the stream is consumed
+    fn stream_files_in_folder<'a>(
+        &'a self,
+        folder_id: String,
+        folder_storage_path: StoragePath,
+        mut entries: fs::ReadDir,
+    ) -> FileStream<'a> {
+        Box::pin(async_stream::stream! {
+            let mut listed = false;
+            while let Some(entry) = entries.next_entry().await.unwrap_or(None) {
+                if !Self::is_listed_file(&entry).await {
+                    continue;
+                }
+                if let Some(file) = self
+                    .file_of_entry(&entry, &folder_storage_path, Some(&folder_id))
+                    .await
+                {
+                    listed = true;
+                    yield Ok(file);
+                }
+            }
+
+            // Persist any new ID mappings that were created
+            if listed {
+                if let Err(e) = self.id_mapping_service.save_changes().await {
+                    tracing::error!("Error saving ID mappings: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Resolves the storage path and the directory of a folder, or `None`
+    /// when the folder cannot be listed
+    async fn folder_location(&self, folder_id: Option<&str>) -> Option<(StoragePath, PathBuf)> {
+        // Get the folder storage path
+        let folder_storage_path = match folder_id {
+            Some(id) => {
+                match self.storage_mediator.get_folder_path(id).await {
+                    Ok(path) => {
+                        tracing::info!("Found folder with path: {:?}", path);
+                        // Convert to StoragePath - use just the folder name to avoid path duplication
+                        // Get just the folder name to avoid path duplication
+                        let lossy = path.to_string_lossy().to_string();
+                        let folder_name = path
+                            .file_name()
+                            .and_then(|f| f.to_str())
+                            .unwrap_or_else(|| &lossy);
+                        tracing::info!("Using folder name: {} for StoragePath", folder_name);
+                        StoragePath::from_string(folder_name)
+                    }
+                    Err(e) => {
+                        tracing::error!("Error getting folder by ID: {}: {}", id, e);
+                        return None;
+                    }
+                }
+            }
+            None => StoragePath::root(),
+        };
+
+        // Get the absolute folder path without duplicate ./storage prefix
+        let abs_folder_path = self.path_service.resolve_path(&folder_storage_path);
+        tracing::info!("Absolute folder path: {:?}", abs_folder_path);
+
+        // Check if the directory exists
+        if !abs_folder_path.exists() || !abs_folder_path.is_dir() {
+            tracing::error!(
+                "Directory does not exist or is not a directory: {:?}",
+                abs_folder_path
+            );
+            return None;
+        }
+        Some((folder_storage_path, abs_folder_path))
+    }
+
+    /// Tells if a directory entry is a listed file. The file type comes with
+    /// the directory entry, so this does not stat the file
+    async fn is_listed_file(entry: &fs::DirEntry) -> bool {
+        if !entry.file_type().await.is_ok_and(|t| t.is_file()) {
+            return false;
+        }
+
+        // Skip special files
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        !(file_name.starts_with('.')
+            || file_name == "folder_ids.json"
+            || file_name == "file_ids.json")
+    }
+
+    /// Builds the file of a directory entry, mapping it to an ID
+    async fn file_of_entry(
+        &self,
+        entry: &fs::DirEntry,
+        folder_storage_path: &StoragePath,
+        folder_id: Option<&str>,
+    ) -> Option<File> {
+        let path = entry.path();
+
+        // Get file metadata
+        let metadata = match fs::metadata(&path).await {
+            Ok(m) => m,
+            Err(e) => {
+                tracing::error!("Error getting metadata for {:?}: {}", path, e);
+                return None;
+            }
+        };
+
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let file_storage_path = folder_storage_path.join(&file_name);
+
+        // Get or create an ID for this file
+        let id = match self
+            .id_mapping_service
+            .get_or_create_id(&file_storage_path)
+            .await
+        {
+            Ok(id) => id,
+            Err(e) => {
+                tracing::error!("Error getting ID for file: {}", e);
+                return None;
+            }
+        };
+
+        // Extract metadata
+        let size = self.logical_size(&path, metadata.len()).await;
+
+        // Get creation timestamp
+        let created_at = metadata
+            .created()
+            .map(|time| {
+                time.duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            })
+            .unwrap_or_else(|_| 0);
+
+        // Get modification timestamp
+        let modified_at = metadata
+            .modified()
+            .map(|time| {
+                time.duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            })
+            .unwrap_or_else(|_| 0);
+
+        // Determine MIME type
+        let mime_type = from_path(&path).first_or_octet_stream().to_string();
+
+        // Create file entity
+        match File::with_timestamps(
+            id,
+            file_name.clone(),
+            file_storage_path,
+            size,
+            mime_type,
+            folder_id.map(String::from),
+            created_at,
+            modified_at,
+        ) {
+            Ok(file) => Some(match FileMetadataCache::content_etag(&metadata) {
+                Some(etag) => file.with_etag(etag),
+                None => file,
+            }),
+            Err(e) => {
+                tracing::error!("Error creating file entity for {}: {}", file_name, e);
+                None
+            }
+        }
+    }
+
+    /// Checks if a file exists at a given storage path
+    async fn file_exists_at_storage_path(
+        &self,
+        storage_path: &StoragePath,
+    ) -> FileRepositoryResult<bool> {
+        let abs_path = self.resolve_storage_path(storage_path);
+
+        // Try to get from advanced cache first
+        if let Some(is_file) = self.metadata_cache.is_file(&abs_path).await {
+            tracing::debug!(
+                "Metadata cache hit for existence check: {} - path: {}",
+                is_file,
+                abs_path.display()
+            );
+            return Ok(is_file);
+        }
+
+        // If not in cache, verify directly and update cache
+        tracing::debug!(
+            "Metadata cache miss for existence check: {}",
+            abs_path.display()
+        );
+
+        // Use timeout to avoid blocking
+        match time::timeout(self.config.timeouts.file_timeout(), fs::metadata(&abs_path)).await {
+            Ok(Ok(metadata)) => {
+                let is_file = metadata.is_file();
+
+                // Update cache with fresh information
+                if let Err(e) = self.metadata_cache.refresh_metadata(&abs_path).await {
+                    tracing::warn!("Failed to update cache for {}: {}", abs_path.display(), e);
+                }
+
+                if is_file {
+                    tracing::debug!("File exists and is accessible: {}", abs_path.display());
+                    Ok(true)
+                } else {
+                    tracing::warn!("Path exists but is not a file: {}", abs_path.display());
+                    Ok(false)
+                }
+            }
+            Ok(Err(e)) => {
+                tracing::warn!("File check failed: {} - {}", abs_path.display(), e);
+
+                // Add to cache as non-existent
+                let entry_type = CacheEntryType::Unknown;
+                let file_metadata =
+                    crate::infrastructure::services::file_metadata_cache::FileMetadata::new(
+                        abs_path.clone(),
+                        false,
+                        entry_type,
+                        None,
+                        None,
+                        None,
+                        None,
+                        Duration::from_millis(self.config.timeouts.file_operation_ms),
+                    );
+                self.metadata_cache.update_cache(file_metadata).await;
+
+                Ok(false)
+            }
+            Err(_) => {
+                tracing::warn!("Timeout checking file metadata: {}", abs_path.display());
+                return Err(FileRepositoryError::Timeout(format!(
+                    "Timeout checking file: {}",
+                    abs_path.display()
+                )));
+            }
+        }
+    }
+
+    /// Legacy method for checking file existence with PathBuf
+    #[allow(dead_code)]
+    pub async fn file_exists(&self, path: &std::path::Path) -> FileRepositoryResult<bool> {
+        let abs_path = self.resolve_legacy_path(path);
+
+        // Try to get from advanced cache first
+        if let Some(is_file) = self.metadata_cache.is_file(&abs_path).await {
+            tracing::debug!(
+                "Metadata cache hit for legacy existence check: {} - path: {}",
+                is_file,
+                abs_path.display()
+            );
+            return Ok(is_file);
+        }
+
+        // If not in cache, verify directly
+        tracing::info!(
+            "Checking if file exists: {} - path: {}",
+            abs_path.exists(),
+            abs_path.display()
+        );
+
+        match time::timeout(self.config.timeouts.file_timeout(), fs::metadata(&abs_path)).await {
+            Ok(Ok(metadata)) => {
+                let is_file = metadata.is_file();
+
+                // Update cache with fresh information
+                if let Err(e) = self.metadata_cache.refresh_metadata(&abs_path).await {
+                    tracing::warn!("Failed to update cache for {}: {}", abs_path.display(), e);
+                }
+
+                if is_file {
+                    tracing::info!("File exists and is accessible: {}", abs_path.display());
+                    return Ok(true);
+                } else {
+                    tracing::warn!("Path exists but is not a file: {}", abs_path.display());
+                    return Ok(false);
+                }
+            }
+            Ok(Err(e)) => {
+                tracing::warn!(
+                    "File exists but metadata check failed: {} - {}",
+                    abs_path.display(),
+                    e
+                );
+                return Ok(false);
+            }
+            Err(_) => {
+                tracing::warn!("Timeout checking file metadata: {}", abs_path.display());
+                return Err(FileRepositoryError::Timeout(format!(
+                    "Timeout checking file: {}",
+                    abs_path.display()
+                )));
+            }
+        }
+    }
+
+    /// Helper method to create a File entity from a storage path and metadata
+    async fn create_file_entity(
+        &self,
+        id: String,
+        name: String,
+        storage_path: StoragePath,
+        mime_type: String,
+        folder_id: Option<String>,
+        metadata: StoredFileMetadata,
+    ) -> FileRepositoryResult<File> {
+        let file = File::with_timestamps(
+            id,
+            name,
+            storage_path,
+            metadata.size,
+            mime_type,
+            folder_id,
+            metadata.created_at,
+            metadata.modified_at,
+        )
+        .map_err(|e| FileRepositoryError::Other(e.to_string()))?;
+
+        Ok(match metadata.etag {
+            Some(etag) => file.with_etag(etag),
+            None => file,
+        })
+    }
+
+    /// Extracts file metadata (size, creation and modification timestamps and
+    /// content version) from a physical path with timeout and cache
+    async fn get_file_metadata(
+        &self,
+        abs_path: &PathBuf,
+    ) -> FileRepositoryResult<StoredFileMetadata> {
+        // Try to get from cache first
+        if let Some(cached_metadata) = self.metadata_cache.get_metadata(abs_path).await {
+            if let (Some(size), Some(created_at), Some(modified_at), Some(etag)) = (
+                cached_metadata.size,
+                cached_metadata.created_at,
+                cached_metadata.modified_at,
+                cached_metadata.etag,
+            ) {
+                tracing::debug!("Using cached metadata for: {}", abs_path.display());
+                let size = self.logical_size(abs_path, size).await;
+                return Ok(StoredFileMetadata {
+                    size,
+                    created_at,
+                    modified_at,
+                    etag: Some(etag),
+                });
+            }
+        }
+
+        // If not in cache or incomplete metadata, load from filesystem
+        let metadata =
+            match time::timeout(self.config.timeouts.file_timeout(), fs::metadata(&abs_path)).await
+            {
+                Ok(Ok(metadata)) => metadata,
+                Ok(Err(e)) => return Err(FileRepositoryError::IoError(e)),
+                Err(_) => {
+                    return Err(FileRepositoryError::Timeout(format!(
                         "Timeout getting metadata for: {}",
                         abs_path.display()
                     )))
                 }
             };
 
-        let size = metadata.len();
+        let size = self.logical_size(abs_path, metadata.len()).await;
 
         // Get creation timestamp
         let created_at = metadata
@@ -339,6 +1202,8 @@ impl FileFsRepository {
             })
             .unwrap_or_else(|_| 0);
 
+        let etag = FileMetadataCache::content_etag(&metadata);
+
         // Update cache if possible
         if let Err(e) = self.metadata_cache.refresh_metadata(abs_path).await {
             tracing::warn!(
@@ -348,7 +1213,12 @@ impl FileFsRepository {
             );
         }
 
-        Ok((size, created_at, modified_at))
+        Ok(StoredFileMetadata {
+            size,
+            created_at,
+            modified_at,
+            etag,
+        })
     }
 
     /// Creates parent directories if needed with timeout and fsync
@@ -392,6 +1262,9 @@ impl FileFsRepository {
 
     /// Non-blocking file deletion for large files
     async fn delete_file_non_blocking(&self, abs_path: PathBuf) -> FileRepositoryResult<()> {
+        // A referenced content is released once its reference is gone
+        let stored = self.stored_content(&abs_path).await.ok().flatten();
+
         // Check if file is large enough to warrant spawn_blocking
         let is_large = self.is_large_file(&abs_path).await?;
 
@@ -435,8 +1308,87 @@ impl FileFsRepository {
             .map_err(FileRepositoryError::IoError)?;
         }
 
+        self.release_stored_content(stored).await;
         Ok(())
     }
+
+    /// Copies a file into another folder under the same name. Stored contents
+    /// are shared by taking a new reference instead of writing the bytes again
+    async fn copy_file_into(
+        &self,
+        id: &str,
+        target_folder_id: Option<String>,
+    ) -> FileRepositoryResult<File> {
+        let original_file = self.get_file_by_id(id).await?;
+
+        // Get the target folder path
+        let target_folder_path = match &target_folder_id {
+            Some(folder_id) => self
+                .storage_mediator
+                .get_folder_storage_path(folder_id)
+                .await
+                .map_err(|e| {
+                    FileRepositoryError::Other(format!("Could not get target folder: {}", e))
+                })?,
+            None => StoragePath::root(),
+        };
+
+        let new_storage_path = target_folder_path.join(original_file.name());
+        if self.file_exists_at_storage_path(&new_storage_path).await? {
+            return Err(FileRepositoryError::AlreadyExists(format!(
+                "File already exists at destination: {}",
+                new_storage_path.to_string()
+            )));
+        }
+
+        let old_abs_path = self.resolve_storage_path(original_file.storage_path());
+        let new_abs_path = self.resolve_storage_path(&new_storage_path);
+        self.ensure_parent_directory(&new_abs_path).await?;
+
+        match self.stored_content(&old_abs_path).await? {
+            Some(stored) => {
+                let store = self.content_store_for(&stored)?;
+                let copied = store.copy_content(&stored).await?;
+                let reference = self.encode_reference(&copied)?;
+                if let Err(e) = FileSystemUtils::atomic_write(&new_abs_path, &reference).await {
+                    self.release_stored_content(Some(copied)).await;
+                    return Err(FileRepositoryError::IoError(e));
+                }
+            }
+            None => {
+                let same_key = key_owner_for_path(&original_file.storage_path().to_string())
+                    == key_owner_for_path(&new_storage_path.to_string());
+                if self.encryption.is_none() || same_key {
+                    fs::copy(&old_abs_path, &new_abs_path)
+                        .await
+                        .map_err(FileRepositoryError::IoError)?;
+                } else {
+                    // Files of another home folder are encrypted with its owner's key
+                    let content = FileStoragePort::get_file_content(self, id)
+                        .await
+                        .map_err(|e| FileRepositoryError::Other(e.to_string()))?;
+                    let sealed = self.seal_content(&new_storage_path, content).await?;
+                    FileSystemUtils::atomic_write(&new_abs_path, &sealed)
+                        .await
+                        .map_err(FileRepositoryError::IoError)?;
+                }
+            }
+        }
+
+        let new_id = self
+            .id_mapping_service
+            .get_or_create_id(&new_storage_path)
+            .await
+            .map_err(FileRepositoryError::from)?;
+        self.id_mapping_service.save_changes().await?;
+
+        if let Some(parent_dir) = new_abs_path.parent() {
+            self.metadata_cache.invalidate_directory(parent_dir).await;
+        }
+
+        tracing::info!("File copied from {:?} to {:?}", old_abs_path, new_abs_path);
+        self.get_file_by_id(&new_id).await
+    }
 }
 
 // Convert IdMappingError to FileRepositoryError
@@ -472,6 +1424,9 @@ impl Clone for FileFsRepository {
             metadata_cache: self.metadata_cache.clone(),
             config: self.config.clone(),
             parallel_processor: self.parallel_processor.clone(),
+            encryption: self.encryption.clone(),
+            content_store: self.content_store.clone(),
+            reference_signer: self.reference_signer.clone(),
         }
     }
 }
@@ -492,6 +1447,25 @@ impl FileStoragePort for FileFsRepository {
             })
     }
 
+    async fn save_file_stream(
+        &self,
+        name: String,
+        folder_id: Option<String>,
+        content_type: String,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<File, DomainError> {
+        self.save_new_file(
+            name,
+            folder_id,
+            content_type,
+            NewFileContent::Stream(stream),
+        )
+        .await
+        .map_err(|e| {
+            DomainError::internal_error("FileStorage", format!("Failed to save file: {}", e))
+        })
+    }
+
     async fn get_file(&self, id: &str) -> Result<File, DomainError> {
         self.get_file_by_id(id).await.map_err(|e| {
             DomainError::internal_error(
@@ -512,6 +1486,56 @@ impl FileStoragePort for FileFsRepository {
             })
     }
 
+    async fn list_files_paginated(
+        &self,
+        folder_id: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<File>, DomainError> {
+        let files = match folder_id {
+            // The root listing has its own rules, so it is cut afterwards
+            None => FileRepository::list_files(self, None)
+                .await
+                .map(|files| files.into_iter().skip(offset).take(limit).collect()),
+            Some(_) => self.list_files_in_folder(folder_id, offset, limit).await,
+        };
+        files.map_err(|e| {
+            DomainError::internal_error(
+                "FileStorage",
+                format!("Failed to list files in folder: {:?}: {}", folder_id, e),
+            )
+        })
+    }
+
+    async fn list_files_stream<'a>(
+        &'a self,
+        folder_id: Option<&str>,
+    ) -> Result<FileStream<'a>, DomainError> {
+        let list_error = |e: &dyn std::fmt::Display| {
+            DomainError::internal_error(
+                "FileStorage",
+                format!("Failed to list files in folder: {:?}: {}", folder_id, e),
+            )
+        };
+        let Some(folder_id) = folder_id else {
+            // The root listing has its own rules, so it is loaded whole
+            let files = FileRepository::list_files(self, None)
+                .await
+                .map_err(|e| list_error(&e))?;
+            return Ok(Box::pin(futures::stream::iter(files.into_iter().map(Ok))));
+        };
+
+        let Some((folder_storage_path, abs_folder_path)) =
+            self.folder_location(Some(folder_id)).await
+        else {
+            return Ok(Box::pin(futures::stream::empty()));
+        };
+        let entries = fs::read_dir(&abs_folder_path)
+            .await
+            .map_err(|e| list_error(&e))?;
+        Ok(self.stream_files_in_folder(folder_id.to_string(), folder_storage_path, entries))
+    }
+
     async fn delete_file(&self, id: &str) -> Result<(), DomainError> {
         FileRepository::delete_file(self, id).await.map_err(|e| {
             DomainError::internal_error(
@@ -546,6 +1570,53 @@ impl FileStoragePort for FileFsRepository {
             })
     }
 
+    async fn get_file_range_stream(
+        &self,
+        id: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
+        let file = self
+            .get_file_by_id(id)
+            .await
+            .map_err(|e| DomainError::not_found("File", format!("{}: {}", id, e)))?;
+        let abs_path = self.resolve_storage_path(file.storage_path());
+
+        let range_error = |e: std::io::Error| {
+            DomainError::internal_error(
+                "FileStorage",
+                format!("Failed to read range of file with ID: {}: {}", id, e),
+            )
+        };
+
+        let stored = self.stored_content(&abs_path).await.map_err(|e| {
+            DomainError::internal_error(
+                "FileStorage",
+                format!("Failed to read file with ID: {}: {}", id, e),
+            )
+        })?;
+        if let Some(content) = stored {
+            let store = self
+                .content_store_for(&content)
+                .map_err(|e| DomainError::internal_error("FileStorage", e.to_string()))?;
+            return store.get_content_range_stream(&content, start, end).await;
+        }
+
+        let stream = match &self.encryption {
+            Some(encryption) => {
+                let source = TokioFile::open(&abs_path).await.map_err(range_error)?;
+                encryption
+                    .decrypt_range_reader(source, start, end)
+                    .await
+                    .map_err(range_error)?
+            }
+            None => FileSystemUtils::read_range_stream(&abs_path, start, end)
+                .await
+                .map_err(range_error)?,
+        };
+        Ok(Box::new(stream))
+    }
+
     async fn move_file(
         &self,
         file_id: &str,
@@ -566,6 +1637,24 @@ impl FileStoragePort for FileFsRepository {
         })
     }
 
+    async fn copy_file(
+        &self,
+        file_id: &str,
+        target_folder_id: Option<String>,
+    ) -> Result<File, DomainError> {
+        self.copy_file_into(file_id, target_folder_id)
+            .await
+            .map_err(|e| match e {
+                FileRepositoryError::AlreadyExists(path) => {
+                    DomainError::already_exists("File", path)
+                }
+                e => DomainError::internal_error(
+                    "FileStorage",
+                    format!("Failed to copy file with ID: {}: {}", file_id, e),
+                ),
+            })
+    }
+
     async fn get_file_path(&self, id: &str) -> Result<StoragePath, DomainError> {
         FileRepository::get_file_path(self, id).await.map_err(|e| {
             DomainError::internal_error(
@@ -630,6 +1719,14 @@ impl FileStoragePort for FileFsRepository {
 
         // Resolve to actual filesystem path
         let physical_path = self.storage_mediator.resolve_storage_path(&file_path);
+        let previous = self.stored_content(&physical_path).await.ok().flatten();
+        let content_size = content.len() as u64;
+        let content = self.seal_content(&file_path, content).await.map_err(|e| {
+            DomainError::internal_error(
+                "FileStorage",
+                format!("Failed to encrypt content for file: {}: {}", file_id, e),
+            )
+        })?;
 
         // Write the content to the file with fsync
         FileSystemUtils::atomic_write(&physical_path, &content)
@@ -643,46 +1740,55 @@ impl FileStoragePort for FileFsRepository {
                     ),
                 )
             })?;
+        self.release_stored_content(previous).await;
 
-        // Get the metadata and add it to cache if available
-        if let Some(metadata) = std::fs::metadata(&physical_path).ok() {
-            // Create a FileMetadata instance and update the cache
-            use crate::infrastructure::services::file_metadata_cache::CacheEntryType;
-            use crate::infrastructure::services::file_metadata_cache::FileMetadata;
-            use std::time::Duration;
-            use std::time::UNIX_EPOCH;
-
-            // Get modified and created times
-            let created_at = metadata
-                .created()
-                .ok()
-                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                .map(|d| d.as_secs());
-
-            let modified_at = metadata
-                .modified()
-                .ok()
-                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                .map(|d| d.as_secs());
+        self.cache_updated_file(&physical_path, content_size, file.mime_type())
+            .await;
 
-            // Default TTL
-            let ttl = Duration::from_secs(60); // 1 minute
+        Ok(())
+    }
 
-            // Create FileMetadata instance
-            let file_metadata = FileMetadata::new(
-                physical_path.clone(),
-                true, // exists
-                CacheEntryType::File,
-                Some(metadata.len()),
-                Some(file.mime_type().to_string()),
-                created_at,
-                modified_at,
-                ttl,
-            );
+    async fn update_file_content_stream(
+        &self,
+        file_id: &str,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<(), DomainError> {
+        let update_error = |e: &dyn std::fmt::Display| {
+            DomainError::internal_error(
+                "FileStorage",
+                format!("Failed to update content of file: {}: {}", file_id, e),
+            )
+        };
+        let file = self
+            .get_file_by_id(file_id)
+            .await
+            .map_err(|e| update_error(&e))?;
+        let file_path = FileStoragePort::get_file_path(self, file_id).await?;
+        let physical_path = self.storage_mediator.resolve_storage_path(&file_path);
+        let previous = self.stored_content(&physical_path).await.ok().flatten();
+
+        // The new content is written next to the file and replaces it once complete
+        let dir = physical_path
+            .parent()
+            .ok_or_else(|| update_error(&"file without parent directory"))?;
+        let temp_path = tempfile::NamedTempFile::new_in(dir)
+            .map_err(|e| update_error(&e))?
+            .into_temp_path();
+        self.write_content_stream(&file_path, &temp_path, stream)
+            .await
+            .map_err(|e| update_error(&e))?;
+        temp_path
+            .persist(&physical_path)
+            .map_err(|e| update_error(&e))?;
+        self.release_stored_content(previous).await;
 
-            // Update the cache
-            self.metadata_cache.update_cache(file_metadata).await;
-        }
+        let disk_size = fs::metadata(&physical_path)
+            .await
+            .map_err(|e| update_error(&e))?
+            .len();
+        let content_size = self.logical_size(&physical_path, disk_size).await;
+        self.cache_updated_file(&physical_path, content_size, file.mime_type())
+            .await;
 
         Ok(())
     }
@@ -765,407 +1871,76 @@ impl FileRepository for FileFsRepository {
         let file = self.get_file_by_id(file_id).await?;
 
         // Get the file path
-        let storage_path = FileRepository::get_file_path(self, file_id).await?;
-        let physical_path = self.path_service.resolve_path(&storage_path);
-
-        // Write the content to the file with fsync
-        FileSystemUtils::atomic_write(&physical_path, &content)
-            .await
-            .map_err(|e| FileRepositoryError::IoError(e))?;
-
-        // Get the metadata and add it to cache if available
-        if let Some(metadata) = std::fs::metadata(&physical_path).ok() {
-            // Create a FileMetadata instance and update the cache
-            use crate::infrastructure::services::file_metadata_cache::CacheEntryType;
-            use crate::infrastructure::services::file_metadata_cache::FileMetadata;
-            use std::time::Duration;
-            use std::time::UNIX_EPOCH;
-
-            // Get modified and created times
-            let created_at = metadata
-                .created()
-                .ok()
-                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                .map(|d| d.as_secs());
-
-            let modified_at = metadata
-                .modified()
-                .ok()
-                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                .map(|d| d.as_secs());
-
-            // Default TTL
-            let ttl = Duration::from_secs(60); // 1 minute
-
-            // Create FileMetadata instance
-            let file_metadata = FileMetadata::new(
-                physical_path.clone(),
-                true, // exists
-                CacheEntryType::File,
-                Some(metadata.len()),
-                Some(file.mime_type().to_string()),
-                created_at,
-                modified_at,
-                ttl,
-            );
-
-            // Update the cache
-            self.metadata_cache.update_cache(file_metadata).await;
-        }
-
-        tracing::info!("File content updated successfully: {}", file_id);
-        Ok(())
-    }
-    async fn save_file_from_bytes(
-        &self,
-        name: String,
-        folder_id: Option<String>,
-        content_type: String,
-        content: Vec<u8>,
-    ) -> FileRepositoryResult<File> {
-        // Get the folder path from the mediator
-        let folder_path = match &folder_id {
-            Some(id) => {
-                match self.storage_mediator.get_folder_path(id).await {
-                    Ok(path) => {
-                        tracing::info!("Using folder path: {:?} for folder_id: {:?}", path, id);
-                        // Convert to StoragePath - use just the folder name to avoid path duplication
-                        // Get just the folder name to avoid path duplication
-                        let lossy = path.to_string_lossy().to_string();
-                        let folder_name = path
-                            .file_name()
-                            .and_then(|f| f.to_str())
-                            .unwrap_or_else(|| &lossy);
-                        tracing::info!("Using folder name: {} for StoragePath", folder_name);
-                        StoragePath::from_string(folder_name)
-                    }
-                    Err(e) => {
-                        tracing::error!("Error getting folder: {}", e);
-                        // Root path
-                        StoragePath::root()
-                    }
-                }
-            }
-            None => StoragePath::root(),
-        };
-
-        // Create the storage path for the file
-        let mut file_storage_path = folder_path.join(&name);
-        tracing::info!("Created file path: {:?}", file_storage_path.to_string());
-
-        // Check if file already exists and generate a unique name if needed
-        let mut exists = self.file_exists_at_storage_path(&file_storage_path).await?;
-        tracing::info!(
-            "File exists check: {} for path: {:?}",
-            exists,
-            file_storage_path.to_string()
-        );
-
-        // If file exists, generate a unique name by adding a suffix
-        let mut original_name = name.clone();
-        let mut counter = 1;
-
-        while exists {
-            // Extract filename and extension
-            let file_stem;
-            let extension;
-
-            if let Some(dot_pos) = original_name.rfind('.') {
-                file_stem = original_name[..dot_pos].to_string();
-                extension = original_name[dot_pos..].to_string();
-            } else {
-                file_stem = original_name.clone();
-                extension = "".to_string();
-            }
-
-            // Create new name with counter
-            let new_name = format!("{}_{}{}", file_stem, counter, extension);
-
-            // Update the storage path with the new name
-            let new_file_storage_path = folder_path.join(&new_name);
-
-            // Check if the new path exists
-            exists = self
-                .file_exists_at_storage_path(&new_file_storage_path)
-                .await?;
-
-            if !exists {
-                // Update variables for the new path
-                tracing::info!(
-                    "Generated unique name for duplicate file: {} -> {}",
-                    original_name,
-                    new_name
-                );
-                original_name = new_name.clone();
-                file_storage_path = new_file_storage_path;
-            } else {
-                // Try next counter
-                counter += 1;
-            }
-        }
-
-        // Create parent directories if they don't exist
-        let abs_path = self.resolve_storage_path(&file_storage_path);
-        self.ensure_parent_directory(&abs_path).await?;
-
-        // Calculate file size
-        let content_size = content.len() as u64;
-
-        // Verificar si el archivo es muy grande para el procesamiento paralelo de escritura
-        if self
-            .config
-            .resources
-            .needs_parallel_processing(content_size, &self.config.concurrency)
-        {
-            // Para archivos muy grandes, usar procesador paralelo
-            tracing::info!(
-                "Using parallel file processor for large file write: {} ({} bytes)",
-                abs_path.display(),
-                content_size
-            );
-
-            // Usar el procesador pre-configurado si está disponible o crear uno nuevo
-            let result = if let Some(processor) = &self.parallel_processor {
-                tracing::debug!("Using pre-configured parallel processor with buffer pool");
-                processor.write_file_parallel(&abs_path, &content).await
-            } else {
-                tracing::debug!("Creating on-demand parallel processor");
-                // Importar y crear el procesador paralelo
-                use crate::infrastructure::repositories::parallel_file_processor::ParallelFileProcessor;
-                let processor = ParallelFileProcessor::new(self.config.clone());
-
-                // Escribir archivo en paralelo
-                processor.write_file_parallel(&abs_path, &content).await
-            };
-
-            // Manejar resultado
-            result?;
-
-            tracing::info!(
-                "Successfully wrote {}MB file using parallel chunks",
-                content_size / (1024 * 1024)
-            );
-        } else if content_size > self.config.resources.large_file_threshold_mb * 1024 * 1024 {
-            // Para archivos grandes pero no tanto como para paralelizar, usar chunking
-            let file_creation_result = time::timeout(
-                self.config.timeouts.file_timeout(),
-                TokioFile::create(&abs_path),
-            )
-            .await
-            .map_err(|_| {
-                FileRepositoryError::Timeout(format!(
-                    "Timeout creating file: {}",
-                    abs_path.display()
-                ))
-            })?
-            .map_err(FileRepositoryError::IoError)?;
-
-            let mut file = file_creation_result;
-
-            // Define el tamaño del chunk usando la configuración
-            let chunk_size = self.config.resources.chunk_size_bytes;
-
-            tracing::info!(
-                "Using chunked writing with size {} bytes for file: {} ({} bytes)",
-                chunk_size,
-                abs_path.display(),
-                content_size
-            );
-
-            // Divide el contenido en chunks y escribe cada uno con timeout
-            for (i, chunk) in content.chunks(chunk_size).enumerate() {
-                let _write_result =
-                    time::timeout(self.config.timeouts.file_timeout(), file.write_all(chunk))
-                        .await
-                        .map_err(|_| {
-                            FileRepositoryError::Timeout(format!(
-                                "Timeout writing chunk {} to file: {}",
-                                i,
-                                abs_path.display()
-                            ))
-                        })?
-                        .map_err(FileRepositoryError::IoError)?;
-
-                tracing::debug!(
-                    "Written chunk {} ({} bytes) to file {}",
-                    i,
-                    chunk.len(),
-                    abs_path.display()
-                );
-            }
-
-            // Ensure file is properly flushed and closed
-            let _flush_result = time::timeout(self.config.timeouts.file_timeout(), file.flush())
-                .await
-                .map_err(|_| {
-                    FileRepositoryError::Timeout(format!(
-                        "Timeout flushing file: {}",
-                        abs_path.display()
-                    ))
-                })?
-                .map_err(FileRepositoryError::IoError)?;
-        } else {
-            // Para archivos pequeños, escritura simple
-            let file_creation_result = time::timeout(
-                self.config.timeouts.file_timeout(),
-                TokioFile::create(&abs_path),
-            )
-            .await
-            .map_err(|_| {
-                FileRepositoryError::Timeout(format!(
-                    "Timeout creating file: {}",
-                    abs_path.display()
-                ))
-            })?
-            .map_err(FileRepositoryError::IoError)?;
-
-            let mut file = file_creation_result;
-
-            // Para archivos pequeños, escribe todo el contenido de una vez
-            let _write_result = time::timeout(
-                self.config.timeouts.file_timeout(),
-                file.write_all(&content),
-            )
-            .await
-            .map_err(|_| {
-                FileRepositoryError::Timeout(format!(
-                    "Timeout writing to file: {}",
-                    abs_path.display()
-                ))
-            })?
-            .map_err(FileRepositoryError::IoError)?;
-
-            // Ensure file is properly flushed and closed
-            let _flush_result = time::timeout(self.config.timeouts.file_timeout(), file.flush())
-                .await
-                .map_err(|_| {
-                    FileRepositoryError::Timeout(format!(
-                        "Timeout flushing file: {}",
-                        abs_path.display()
-                    ))
-                })?
-                .map_err(FileRepositoryError::IoError)?;
-        }
-
-        // Get file metadata
-        let (size, created_at, modified_at) = self.get_file_metadata(&abs_path).await?;
+        let storage_path = FileRepository::get_file_path(self, file_id).await?;
+        let physical_path = self.path_service.resolve_path(&storage_path);
+        let previous = self.stored_content(&physical_path).await.ok().flatten();
+        let content_size = content.len() as u64;
+        let content = self.seal_content(&storage_path, content).await?;
 
-        // Determine the MIME type
-        let mime_type = if content_type.is_empty() {
-            from_path(&abs_path).first_or_octet_stream().to_string()
-        } else {
-            content_type
-        };
+        // Write the content to the file with fsync
+        FileSystemUtils::atomic_write(&physical_path, &content)
+            .await
+            .map_err(|e| FileRepositoryError::IoError(e))?;
+        self.release_stored_content(previous).await;
 
-        // Create and return the file entity with a persistent ID
-        let id = self
-            .id_mapping_service
-            .get_or_create_id(&file_storage_path)
-            .await?;
+        // Get the metadata and add it to cache if available
+        if let Some(metadata) = std::fs::metadata(&physical_path).ok() {
+            // Create a FileMetadata instance and update the cache
+            use crate::infrastructure::services::file_metadata_cache::CacheEntryType;
+            use crate::infrastructure::services::file_metadata_cache::FileMetadata;
+            use std::time::Duration;
+            use std::time::UNIX_EPOCH;
 
-        // Keep a string representation of the path for logging
-        let path_string = file_storage_path.to_string();
+            // Get modified and created times
+            let created_at = metadata
+                .created()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
 
-        let file = self
-            .create_file_entity(
-                id.clone(),    // Clone ID for use in logging
-                original_name, // Use the potentially modified name with counter suffix
-                file_storage_path,
-                size,
-                mime_type,
-                folder_id,
-                Some(created_at),
-                Some(modified_at),
-            )
-            .await?;
+            let modified_at = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs());
 
-        // Ensure ID mapping is persisted - this is critical for later retrieval
-        // Ejecutar múltiples intentos de guardado con verificación para garantizar persistencia
-        for attempt in 1..=3 {
-            match self.id_mapping_service.save_changes().await {
-                Ok(_) => {
-                    tracing::info!(
-                        "Successfully saved ID mapping for file ID: {} -> path: {} (attempt {})",
-                        id,
-                        path_string,
-                        attempt
-                    );
+            // Default TTL
+            let ttl = Duration::from_secs(60); // 1 minute
 
-                    // Verificar que el mapeo se puede recuperar después de guardado
-                    if let Ok(verified_path) = self.id_mapping_service.get_path_by_id(&id).await {
-                        if verified_path.to_string() == path_string {
-                            tracing::info!(
-                                "Verified ID mapping is retrievable after save: {} -> {}",
-                                id,
-                                path_string
-                            );
-                            break; // Guaradado correcto y verificado, salir del bucle
-                        } else {
-                            tracing::error!(
-                                "Mapping verification failed: expected {} but got {}",
-                                path_string,
-                                verified_path.to_string()
-                            );
-                            if attempt < 3 {
-                                tracing::info!(
-                                    "Will retry saving ID mapping (attempt {}/3)",
-                                    attempt + 1
-                                );
-                                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                                continue;
-                            } else {
-                                return Err(FileRepositoryError::Other(format!(
-                                    "Failed to verify ID mapping for file: {} after 3 attempts",
-                                    id
-                                )));
-                            }
-                        }
-                    } else {
-                        tracing::error!("Cannot verify mapping, ID {} not found after save", id);
-                        if attempt < 3 {
-                            tracing::info!(
-                                "Will retry saving ID mapping (attempt {}/3)",
-                                attempt + 1
-                            );
-                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                            continue;
-                        } else {
-                            return Err(FileRepositoryError::Other(format!(
-                                "Failed to verify ID mapping for file: {} after 3 attempts",
-                                id
-                            )));
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::error!(
-                        "Failed to save ID mapping for file {}: {} (attempt {})",
-                        id,
-                        e,
-                        attempt
-                    );
-                    if attempt < 3 {
-                        tracing::info!("Will retry saving ID mapping (attempt {}/3)", attempt + 1);
-                        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                        continue;
-                    } else {
-                        return Err(FileRepositoryError::Other(format!(
-                            "Failed to save ID mapping for file: {} after 3 attempts - {}",
-                            id, e
-                        )));
-                    }
-                }
-            }
-        }
+            // Create FileMetadata instance
+            let mut file_metadata = FileMetadata::new(
+                physical_path.clone(),
+                true, // exists
+                CacheEntryType::File,
+                Some(content_size),
+                Some(file.mime_type().to_string()),
+                created_at,
+                modified_at,
+                ttl,
+            );
+            file_metadata.etag = FileMetadataCache::content_etag(&metadata);
 
-        // Invalidate any directory cache entries for the parent folders
-        // to ensure directory listings show the new file
-        if let Some(parent_dir) = abs_path.parent() {
-            self.metadata_cache.invalidate_directory(parent_dir).await;
+            // Update the cache
+            self.metadata_cache.update_cache(file_metadata).await;
         }
 
-        tracing::info!("Saved file: {} with ID: {}", path_string, file.id());
-        Ok(file)
+        tracing::info!("File content updated successfully: {}", file_id);
+        Ok(())
+    }
+    async fn save_file_from_bytes(
+        &self,
+        name: String,
+        folder_id: Option<String>,
+        content_type: String,
+        content: Vec<u8>,
+    ) -> FileRepositoryResult<File> {
+        self.save_new_file(
+            name,
+            folder_id,
+            content_type,
+            NewFileContent::Bytes(content),
+        )
+        .await
     }
 
     async fn save_file_with_id(
@@ -1232,6 +2007,8 @@ impl FileRepository for FileFsRepository {
         // Create parent directories if they don't exist
         self.ensure_parent_directory(&abs_path).await?;
 
+        let content = self.seal_content(&file_storage_path, content).await?;
+
         // Write the file with timeout
         let file_creation_result = time::timeout(
             self.config.timeouts.file_timeout(),
@@ -1245,7 +2022,7 @@ impl FileRepository for FileFsRepository {
 
         let mut file = file_creation_result;
 
-        let _write_result = time::timeout(
+        time::timeout(
             self.config.timeouts.file_timeout(),
             file.write_all(&content),
         )
@@ -1256,7 +2033,7 @@ impl FileRepository for FileFsRepository {
         .map_err(FileRepositoryError::IoError)?;
 
         // Ensure file is properly flushed and closed
-        let _flush_result = time::timeout(self.config.timeouts.file_timeout(), file.flush())
+        time::timeout(self.config.timeouts.file_timeout(), file.flush())
             .await
             .map_err(|_| {
                 FileRepositoryError::Timeout(format!(
@@ -1267,7 +2044,7 @@ impl FileRepository for FileFsRepository {
             .map_err(FileRepositoryError::IoError)?;
 
         // Get file metadata
-        let (size, created_at, modified_at) = self.get_file_metadata(&abs_path).await?;
+        let metadata = self.get_file_metadata(&abs_path).await?;
 
         // Determine the MIME type
         let mime_type = if content_type.is_empty() {
@@ -1305,11 +2082,9 @@ impl FileRepository for FileFsRepository {
                 id.clone(),
                 name,
                 file_storage_path,
-                size,
                 mime_type,
                 folder_id,
-                Some(created_at),
-                Some(modified_at),
+                metadata,
             )
             .await?;
 
@@ -1344,7 +2119,7 @@ impl FileRepository for FileFsRepository {
         }
 
         // Get file metadata
-        let (size, created_at, modified_at) = self.get_file_metadata(&abs_path).await?;
+        let metadata = self.get_file_metadata(&abs_path).await?;
 
         // Get file name from the storage path
         let name = match storage_path.file_name() {
@@ -1375,11 +2150,9 @@ impl FileRepository for FileFsRepository {
                 id.to_string(),
                 name,
                 storage_path,
-                size,
                 mime_type,
                 folder_id,
-                Some(created_at),
-                Some(modified_at),
+                metadata,
             )
             .await?;
 
@@ -1434,7 +2207,7 @@ impl FileRepository for FileFsRepository {
                         let id = Uuid::new_v4().to_string();
 
                         // Extract file properties
-                        let size = metadata.len();
+                        let size = self.logical_size(&path, metadata.len()).await;
                         let created_at = metadata
                             .created()
                             .map(|time| {
@@ -1456,7 +2229,7 @@ impl FileRepository for FileFsRepository {
                         let mime_type = from_path(&path).first_or_octet_stream().to_string();
 
                         // Create file entity
-                        let file = File::with_timestamps(
+                        let mut file = File::with_timestamps(
                             id,
                             file_name,
                             storage_path,
@@ -1467,6 +2240,9 @@ impl FileRepository for FileFsRepository {
                             modified_at,
                         )
                         .unwrap();
+                        if let Some(etag) = FileMetadataCache::content_etag(&metadata) {
+                            file = file.with_etag(etag);
+                        }
 
                         files_result.push(file);
                     }
@@ -1484,159 +2260,7 @@ impl FileRepository for FileFsRepository {
         }
 
         // Si no estamos en modo desarrollo o se especificó un folder_id, seguimos la lógica normal
-        // Get the folder storage path
-        let folder_storage_path = match folder_id {
-            Some(id) => {
-                match self.storage_mediator.get_folder_path(id).await {
-                    Ok(path) => {
-                        tracing::info!("Found folder with path: {:?}", path);
-                        // Convert to StoragePath - use just the folder name to avoid path duplication
-                        // Get just the folder name to avoid path duplication
-                        let lossy = path.to_string_lossy().to_string();
-                        let folder_name = path
-                            .file_name()
-                            .and_then(|f| f.to_str())
-                            .unwrap_or_else(|| &lossy);
-                        tracing::info!("Using folder name: {} for StoragePath", folder_name);
-                        StoragePath::from_string(folder_name)
-                    }
-                    Err(e) => {
-                        tracing::error!("Error getting folder by ID: {}: {}", id, e);
-                        return Ok(Vec::new());
-                    }
-                }
-            }
-            None => StoragePath::root(),
-        };
-
-        // Get the absolute folder path without duplicate ./storage prefix
-        let abs_folder_path = self.path_service.resolve_path(&folder_storage_path);
-        tracing::info!("Absolute folder path: {:?}", abs_folder_path);
-
-        // Check if the directory exists
-        if !abs_folder_path.exists() || !abs_folder_path.is_dir() {
-            tracing::error!(
-                "Directory does not exist or is not a directory: {:?}",
-                abs_folder_path
-            );
-            return Ok(Vec::new());
-        }
-
-        // Read directory entries
-        let mut files_result = Vec::new();
-
-        // Read the directory entries
-        match fs::read_dir(&abs_folder_path).await {
-            Ok(mut entries) => {
-                while let Some(entry) = entries.next_entry().await.unwrap_or(None) {
-                    let path = entry.path();
-
-                    // Skip if not a file
-                    if !path.is_file() {
-                        continue;
-                    }
-
-                    // Skip special files
-                    let file_name_lossy = entry.file_name().to_string_lossy().to_string();
-                    if file_name_lossy.starts_with('.')
-                        || file_name_lossy == "folder_ids.json"
-                        || file_name_lossy == "file_ids.json"
-                    {
-                        continue;
-                    }
-
-                    // Get file metadata
-                    let metadata = match fs::metadata(&path).await {
-                        Ok(m) => m,
-                        Err(e) => {
-                            tracing::error!("Error getting metadata for {:?}: {}", path, e);
-                            continue;
-                        }
-                    };
-
-                    let file_name = file_name_lossy;
-                    let file_storage_path = folder_storage_path.join(&file_name);
-
-                    // Get or create an ID for this file
-                    let id = match self
-                        .id_mapping_service
-                        .get_or_create_id(&file_storage_path)
-                        .await
-                    {
-                        Ok(id) => id,
-                        Err(e) => {
-                            tracing::error!("Error getting ID for file: {}", e);
-                            continue;
-                        }
-                    };
-
-                    // Extract metadata
-                    let size = metadata.len();
-
-                    // Get creation timestamp
-                    let created_at = metadata
-                        .created()
-                        .map(|time| {
-                            time.duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs()
-                        })
-                        .unwrap_or_else(|_| 0);
-
-                    // Get modification timestamp
-                    let modified_at = metadata
-                        .modified()
-                        .map(|time| {
-                            time.duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs()
-                        })
-                        .unwrap_or_else(|_| 0);
-
-                    // Determine MIME type
-                    let mime_type = from_path(&path).first_or_octet_stream().to_string();
-
-                    // Create file entity
-                    match File::with_timestamps(
-                        id,
-                        file_name.clone(),
-                        file_storage_path,
-                        size,
-                        mime_type,
-                        folder_id.map(String::from),
-                        created_at,
-                        modified_at,
-                    ) {
-                        Ok(file) => {
-                            tracing::info!("Added file to result list: {}", file.name());
-                            files_result.push(file);
-                        }
-                        Err(e) => {
-                            tracing::error!("Error creating file entity for {}: {}", file_name, e);
-                            continue;
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                tracing::error!("Error reading directory {:?}: {}", abs_folder_path, e);
-                return Err(FileRepositoryError::IoError(e));
-            }
-        }
-
-        // Persist any new ID mappings that were created
-        if !files_result.is_empty() {
-            if let Err(e) = self.id_mapping_service.save_changes().await {
-                tracing::error!("Error saving ID mappings: {}", e);
-            }
-        }
-
-        tracing::info!(
-            "Found {} files in folder {:?}",
-            files_result.len(),
-            folder_id
-        );
-        Ok(files_result)
+        self.list_files_in_folder(folder_id, 0, usize::MAX).await
     }
 
     async fn delete_file(&self, id: &str) -> FileRepositoryResult<()> {
@@ -1717,6 +2341,11 @@ impl FileRepository for FileFsRepository {
             })?
             .map_err(FileRepositoryError::IoError)?;
 
+        // Referenced contents are read from the content store
+        if let Some(content) = self.stored_content(&abs_path).await? {
+            return self.read_stored_content(&content).await;
+        }
+
         let file_size = metadata.len();
 
         // Check if this can be loaded in memory
@@ -1736,6 +2365,14 @@ impl FileRepository for FileFsRepository {
             )));
         }
 
+        // Encrypted files are read whole and decrypted in one pass
+        if let Some(encryption) = &self.encryption {
+            if encryption.is_encrypted(&abs_path).await? {
+                let content = fs::read(&abs_path).await?;
+                return Ok(encryption.decrypt_bytes(content).await?);
+            }
+        }
+
         // Verificar si el archivo necesita procesamiento paralelo
         if self
             .config
@@ -1833,6 +2470,14 @@ impl FileRepository for FileFsRepository {
         // Open the file for reading with timeout
         let abs_path = self.resolve_storage_path(file.storage_path());
 
+        // Referenced contents are streamed from the content store
+        if let Some(content) = self.stored_content(&abs_path).await? {
+            return Ok(self
+                .content_store_for(&content)?
+                .get_content_stream(&content)
+                .await?);
+        }
+
         // Obtenemos el tamaño del archivo para definir el tamaño óptimo de los chunks
         let metadata = time::timeout(self.config.timeouts.file_timeout(), fs::metadata(&abs_path))
             .await
@@ -1861,6 +2506,10 @@ impl FileRepository for FileFsRepository {
         })?
         .map_err(FileRepositoryError::IoError)?;
 
+        if let Some(encryption) = &self.encryption {
+            return Ok(Box::new(encryption.decrypt_reader(file).await?));
+        }
+
         // Definir tamaño de chunk óptimo según el tamaño del archivo
         let chunk_size = if is_large {
             // Para archivos grandes usamos el tamaño de chunk configurado
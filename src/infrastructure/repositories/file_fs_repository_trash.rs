@@ -414,6 +414,7 @@ impl FileFsRepository {
 
                 if file_exists {
                     debug!("File exists physically, deleting: {}", file_path.display());
+                    let stored = self.stored_content(&file_path).await.unwrap_or_default();
 
                     // Delete the file physically
                     if let Err(e) = fs::remove_file(&file_path).await {
@@ -428,6 +429,7 @@ impl FileFsRepository {
                         }
                     } else {
                         debug!("File physically deleted successfully");
+                        self.release_stored_content(stored).await;
                     }
 
                     // Invalidate cache for this file
@@ -14,6 +14,9 @@ use crate::infrastructure::repositories::file_metadata_manager::{
 };
 use crate::infrastructure::repositories::file_path_resolver::FilePathResolver;
 use crate::infrastructure::repositories::parallel_file_processor::ParallelFileProcessor;
+use crate::infrastructure::services::file_encryption_service::{
+    key_owner_for_path, FileEncryptionService,
+};
 use crate::infrastructure::services::file_system_utils::FileSystemUtils;
 
 /// Implementación de repositorio para operaciones de escritura de archivos
@@ -24,6 +27,7 @@ pub struct FileFsWriteRepository {
     storage_mediator: Arc<dyn StorageMediator>,
     config: AppConfig,
     parallel_processor: Option<Arc<ParallelFileProcessor>>,
+    encryption: Option<Arc<FileEncryptionService>>,
 }
 
 impl FileFsWriteRepository {
@@ -43,9 +47,16 @@ impl FileFsWriteRepository {
             storage_mediator,
             config,
             parallel_processor,
+            encryption: None,
         }
     }
 
+    /// Cifra en reposo el contenido de los archivos escritos
+    pub fn with_encryption(mut self, encryption: Arc<FileEncryptionService>) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
     /// Crea un stub para pruebas
     pub fn default_stub() -> Self {
         Self {
@@ -58,6 +69,7 @@ impl FileFsWriteRepository {
             ),
             config: AppConfig::default(),
             parallel_processor: None,
+            encryption: None,
         }
     }
 
@@ -152,6 +164,16 @@ impl FileWritePort for FileFsWriteRepository {
             .await
             .map_err(|e| DomainError::internal_error("File system", e.to_string()))?;
 
+        // El tamaño del archivo es el del contenido en claro
+        let size = content.len() as u64;
+        let content = match &self.encryption {
+            Some(encryption) => {
+                let owner = key_owner_for_path(&storage_path.to_string());
+                encryption.encrypt_bytes(&owner, content).await?
+            }
+            None => content,
+        };
+
         // Write the file to disk using atomic write with fsync
         tokio::time::timeout(
             self.config.timeouts.file_write_timeout(),
@@ -172,7 +194,6 @@ impl FileWritePort for FileFsWriteRepository {
         })?;
 
         // Create and return a File entity
-        let size = content.len() as u64;
         let file = self
             .create_file_entity(
                 file_id,
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::fs;
@@ -187,6 +187,11 @@ impl FileMetadataManager {
         Ok((size, created_at, modified_at))
     }
 
+    /// Obtiene la versión del contenido de un archivo para usarla como ETag
+    pub async fn get_etag(&self, abs_path: &Path) -> Option<String> {
+        self.metadata_cache.get_etag(abs_path).await
+    }
+
     /// Invalida la entrada de caché para un archivo
     pub async fn invalidate(&self, abs_path: &PathBuf) {
         self.metadata_cache.invalidate(abs_path).await;
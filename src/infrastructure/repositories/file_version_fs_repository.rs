@@ -0,0 +1,387 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio_util::io::ReaderStream;
+
+use crate::application::ports::version_ports::{FileVersionStoragePort, VersionContentStream};
+use crate::common::errors::{DomainError, ErrorKind};
+use crate::domain::entities::file::File;
+use crate::domain::entities::file_version::FileVersion;
+use crate::infrastructure::services::file_encryption_service::{
+    key_owner_for_path, FileEncryptionService,
+};
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Nombre del índice de versiones de cada archivo
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// Almacén de versiones de archivos sobre el sistema de archivos.
+///
+/// Cada archivo versionado tiene un directorio `.versions/<file_id>` con un
+/// `index.json` y un blob por versión. Las escrituras del índice son atómicas y
+/// se serializan con un mutex por archivo.
+pub struct FileVersionFsRepository {
+    versions_dir: PathBuf,
+    locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    encryption: Option<Arc<FileEncryptionService>>,
+}
+
+impl FileVersionFsRepository {
+    /// Crea un nuevo repositorio de versiones bajo la raíz de almacenamiento
+    pub fn new(storage_root: impl AsRef<Path>) -> Self {
+        Self {
+            versions_dir: storage_root.as_ref().join(".versions"),
+            locks: Mutex::new(HashMap::new()),
+            encryption: None,
+        }
+    }
+
+    /// Cifra el contenido de las versiones con el servicio de cifrado en reposo
+    pub fn with_encryption(mut self, encryption: Arc<FileEncryptionService>) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    fn file_dir(&self, file_id: &str) -> Result<PathBuf, DomainError> {
+        // Los identificadores se usan como nombres de directorio
+        if file_id.is_empty() || file_id.contains(['/', '\\']) || file_id.starts_with('.') {
+            return Err(DomainError::validation_error(format!(
+                "Invalid file id for versioning: {}",
+                file_id
+            )));
+        }
+        Ok(self.versions_dir.join(file_id))
+    }
+
+    fn blob_path(&self, file_id: &str, version_id: &str) -> Result<PathBuf, DomainError> {
+        if version_id.is_empty() || version_id.contains(['/', '\\']) || version_id.starts_with('.')
+        {
+            return Err(DomainError::not_found("FileVersion", version_id));
+        }
+        Ok(self.file_dir(file_id)?.join(format!("{}.bin", version_id)))
+    }
+
+    async fn file_lock(&self, file_id: &str) -> Arc<Mutex<()>> {
+        let mut locks = self.locks.lock().await;
+        locks.entry(file_id.to_string()).or_default().clone()
+    }
+
+    async fn read_index(&self, file_id: &str) -> Result<Vec<FileVersion>, DomainError> {
+        let index_path = self.file_dir(file_id)?.join(INDEX_FILE_NAME);
+        match fs::read(&index_path).await {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                DomainError::internal_error(
+                    "FileVersion",
+                    format!("Corrupt version index for {}: {}", file_id, e),
+                )
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(DomainError::internal_error(
+                "FileVersion",
+                format!("Failed to read version index for {}: {}", file_id, e),
+            )),
+        }
+    }
+
+    async fn write_index(
+        &self,
+        file_id: &str,
+        versions: &[FileVersion],
+    ) -> Result<(), DomainError> {
+        let dir = self.file_dir(file_id)?;
+        if versions.is_empty() {
+            return match fs::remove_dir_all(&dir).await {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(DomainError::internal_error(
+                    "FileVersion",
+                    format!(
+                        "Failed to remove version directory {}: {}",
+                        dir.display(),
+                        e
+                    ),
+                )),
+            };
+        }
+
+        let data = serde_json::to_vec_pretty(versions).map_err(|e| {
+            DomainError::internal_error(
+                "FileVersion",
+                format!("Failed to serialize version index: {}", e),
+            )
+        })?;
+        FileSystemUtils::atomic_write(dir.join(INDEX_FILE_NAME), &data)
+            .await
+            .map_err(|e| {
+                DomainError::internal_error(
+                    "FileVersion",
+                    format!("Failed to write version index for {}: {}", file_id, e),
+                )
+            })
+    }
+}
+
+#[async_trait]
+impl FileVersionStoragePort for FileVersionFsRepository {
+    async fn store_version(
+        &self,
+        file: &File,
+        mut content: VersionContentStream,
+    ) -> Result<FileVersion, DomainError> {
+        let lock = self.file_lock(file.id()).await;
+        let _guard = lock.lock().await;
+
+        let mut versions = self.read_index(file.id()).await?;
+        let version_number = versions.iter().map(|v| v.version_number).max().unwrap_or(0) + 1;
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut version = FileVersion::new(
+            file.id().to_string(),
+            file.name().to_string(),
+            version_number,
+            0,
+            file.mime_type().to_string(),
+            file.modified_at(),
+            created_at,
+        );
+
+        let blob_path = self.blob_path(file.id(), &version.id)?;
+        let io_error = |e: std::io::Error| {
+            DomainError::internal_error(
+                "FileVersion",
+                format!(
+                    "Failed to write version blob {}: {}",
+                    blob_path.display(),
+                    e
+                ),
+            )
+        };
+        fs::create_dir_all(self.file_dir(file.id())?)
+            .await
+            .map_err(io_error)?;
+
+        if let Some(encryption) = &self.encryption {
+            let owner = key_owner_for_path(file.path_string());
+            content = encryption.encrypt_stream(&owner, content).await?;
+        }
+
+        let mut blob = fs::File::create(&blob_path).await.map_err(io_error)?;
+        let mut size = 0u64;
+        while let Some(chunk) = content.next().await {
+            let written = match chunk {
+                Ok(chunk) => blob.write_all(&chunk).await.map(|_| chunk.len()),
+                Err(e) => Err(e),
+            };
+            match written {
+                Ok(len) => size += len as u64,
+                Err(e) => {
+                    drop(blob);
+                    let _ = fs::remove_file(&blob_path).await;
+                    return Err(io_error(e));
+                }
+            }
+        }
+        blob.sync_all().await.map_err(io_error)?;
+
+        // El tamaño de la versión es el del contenido en claro
+        if let Some(encryption) = &self.encryption {
+            if let Some(plain) = encryption
+                .plaintext_size(&blob_path)
+                .await
+                .map_err(io_error)?
+            {
+                size = plain;
+            }
+        }
+
+        version.size = size;
+        versions.insert(0, version.clone());
+        if let Err(e) = self.write_index(file.id(), &versions).await {
+            let _ = fs::remove_file(&blob_path).await;
+            return Err(e);
+        }
+
+        Ok(version)
+    }
+
+    async fn list_versions(&self, file_id: &str) -> Result<Vec<FileVersion>, DomainError> {
+        let mut versions = self.read_index(file_id).await?;
+        versions.sort_by_key(|v| std::cmp::Reverse(v.version_number));
+        Ok(versions)
+    }
+
+    async fn get_version(
+        &self,
+        file_id: &str,
+        version_id: &str,
+    ) -> Result<FileVersion, DomainError> {
+        self.read_index(file_id)
+            .await?
+            .into_iter()
+            .find(|v| v.id == version_id)
+            .ok_or_else(|| DomainError::not_found("FileVersion", version_id))
+    }
+
+    async fn get_version_stream(
+        &self,
+        file_id: &str,
+        version_id: &str,
+    ) -> Result<VersionContentStream, DomainError> {
+        self.get_version(file_id, version_id).await?;
+        let blob_path = self.blob_path(file_id, version_id)?;
+        let blob = fs::File::open(&blob_path).await.map_err(|e| {
+            DomainError::new(
+                ErrorKind::NotFound,
+                "FileVersion",
+                format!("Version content missing for {}: {}", version_id, e),
+            )
+        })?;
+        match &self.encryption {
+            Some(encryption) => encryption.decrypt_reader(blob).await.map_err(|e| {
+                DomainError::internal_error(
+                    "FileVersion",
+                    format!("Failed to decrypt version {}: {}", version_id, e),
+                )
+            }),
+            None => Ok(Box::pin(ReaderStream::new(blob))),
+        }
+    }
+
+    async fn get_version_content(
+        &self,
+        file_id: &str,
+        version_id: &str,
+    ) -> Result<Vec<u8>, DomainError> {
+        self.get_version(file_id, version_id).await?;
+        let blob_path = self.blob_path(file_id, version_id)?;
+        let content = fs::read(&blob_path).await.map_err(|e| {
+            DomainError::new(
+                ErrorKind::NotFound,
+                "FileVersion",
+                format!("Version content missing for {}: {}", version_id, e),
+            )
+        })?;
+        match &self.encryption {
+            Some(encryption) => encryption.decrypt_bytes(content).await,
+            None => Ok(content),
+        }
+    }
+
+    async fn delete_version(&self, file_id: &str, version_id: &str) -> Result<(), DomainError> {
+        let lock = self.file_lock(file_id).await;
+        let _guard = lock.lock().await;
+
+        let mut versions = self.read_index(file_id).await?;
+        let before = versions.len();
+        versions.retain(|v| v.id != version_id);
+        if versions.len() == before {
+            return Err(DomainError::not_found("FileVersion", version_id));
+        }
+
+        self.write_index(file_id, &versions).await?;
+        let blob_path = self.blob_path(file_id, version_id)?;
+        match fs::remove_file(&blob_path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(DomainError::internal_error(
+                "FileVersion",
+                format!(
+                    "Failed to delete version blob {}: {}",
+                    blob_path.display(),
+                    e
+                ),
+            )),
+        }
+    }
+
+    async fn delete_all_versions(&self, file_id: &str) -> Result<(), DomainError> {
+        let lock = self.file_lock(file_id).await;
+        let _guard = lock.lock().await;
+        self.write_index(file_id, &[]).await
+    }
+
+    async fn total_version_size(&self, file_id: &str) -> Result<u64, DomainError> {
+        Ok(self.read_index(file_id).await?.iter().map(|v| v.size).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::services::path_service::StoragePath;
+    use bytes::Bytes;
+    use tempfile::tempdir;
+
+    fn sample_file(id: &str) -> File {
+        File::with_timestamps(
+            id.to_string(),
+            "notes.txt".to_string(),
+            StoragePath::from_string("/notes.txt"),
+            5,
+            "text/plain".to_string(),
+            None,
+            1_000,
+            2_000,
+        )
+        .unwrap()
+    }
+
+    fn content(data: &'static [u8]) -> VersionContentStream {
+        Box::pin(futures::stream::iter(vec![Ok(Bytes::from_static(data))]))
+    }
+
+    #[tokio::test]
+    async fn test_store_and_read_versions() {
+        let dir = tempdir().unwrap();
+        let repo = FileVersionFsRepository::new(dir.path());
+        let file = sample_file("file-1");
+
+        let v1 = repo.store_version(&file, content(b"first")).await.unwrap();
+        let v2 = repo.store_version(&file, content(b"second")).await.unwrap();
+
+        assert_eq!(v1.version_number, 1);
+        assert_eq!(v2.version_number, 2);
+        assert_eq!(v2.size, 6);
+
+        let versions = repo.list_versions("file-1").await.unwrap();
+        assert_eq!(
+            versions
+                .iter()
+                .map(|v| v.version_number)
+                .collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        assert_eq!(
+            repo.get_version_content("file-1", &v1.id).await.unwrap(),
+            b"first"
+        );
+        assert_eq!(repo.total_version_size("file-1").await.unwrap(), 11);
+    }
+
+    #[tokio::test]
+    async fn test_delete_versions() {
+        let dir = tempdir().unwrap();
+        let repo = FileVersionFsRepository::new(dir.path());
+        let file = sample_file("file-2");
+
+        let v1 = repo.store_version(&file, content(b"one")).await.unwrap();
+        repo.store_version(&file, content(b"two")).await.unwrap();
+
+        repo.delete_version("file-2", &v1.id).await.unwrap();
+        assert!(repo.get_version("file-2", &v1.id).await.is_err());
+        assert_eq!(repo.list_versions("file-2").await.unwrap().len(), 1);
+
+        repo.delete_all_versions("file-2").await.unwrap();
+        assert!(repo.list_versions("file-2").await.unwrap().is_empty());
+        assert!(!dir.path().join(".versions").join("file-2").exists());
+    }
+}
@@ -13,8 +13,10 @@ use crate::domain::repositories::folder_repository::{
 use crate::domain::services::path_service::{PathService, StoragePath};
 // use crate::application::ports::outbound::IdMappingPort;
 use crate::application::ports::outbound::FolderStoragePort;
+use crate::application::ports::storage_ports::ContentStoragePort;
 use crate::application::services::storage_mediator::StorageMediator;
 use crate::common::errors::DomainError;
+use crate::infrastructure::services::content_reference::{self, ReferenceSigner};
 use crate::infrastructure::services::id_mapping_service::{IdMappingError, IdMappingService};
 
 // To be able to use streams in the list_folders function
@@ -26,6 +28,8 @@ pub struct FolderFsRepository {
     storage_mediator: Arc<dyn StorageMediator>,
     id_mapping_service: Arc<dyn crate::application::ports::outbound::IdMappingPort>,
     path_service: Arc<PathService>,
+    content_store: Option<Arc<dyn ContentStoragePort>>,
+    reference_signer: Option<Arc<ReferenceSigner>>,
 }
 
 impl FolderFsRepository {
@@ -41,6 +45,32 @@ impl FolderFsRepository {
             storage_mediator,
             id_mapping_service,
             path_service,
+            content_store: None,
+            reference_signer: None,
+        }
+    }
+
+    /// Releases the stored content of the files inside a folder before it is deleted
+    pub fn with_content_store(
+        mut self,
+        content_store: Arc<dyn ContentStoragePort>,
+        reference_signer: Arc<ReferenceSigner>,
+    ) -> Self {
+        self.content_store = Some(content_store);
+        self.reference_signer = Some(reference_signer);
+        self
+    }
+
+    /// Releases the content referenced by the files under a directory that is about to be removed
+    pub(crate) async fn release_folder_contents(&self, abs_path: &Path) {
+        if let (Some(store), Some(signer)) = (&self.content_store, &self.reference_signer) {
+            let released =
+                content_reference::release_references_in(abs_path, store.as_ref(), signer).await;
+            tracing::debug!(
+                "Released {} stored contents under {}",
+                released,
+                abs_path.display()
+            );
         }
     }
 
@@ -69,11 +99,13 @@ impl FolderFsRepository {
             storage_mediator: storage_mediator_stub,
             id_mapping_service,
             path_service,
+            content_store: None,
+            reference_signer: None,
         }
     }
 
-    /// Gets the count of items in a directory efficiently
-    async fn count_directory_items(&self, directory_path: &Path) -> FolderRepositoryResult<usize> {
+    /// Gets the count of subdirectories in a directory efficiently
+    async fn count_subdirectories(&self, directory_path: &Path) -> FolderRepositoryResult<usize> {
         use tokio::fs::read_dir;
 
         // Timeout to avoid blocking
@@ -85,9 +117,11 @@ impl FolderFsRepository {
                 let mut entries = result.map_err(FolderRepositoryError::IoError)?;
                 let mut count = 0;
 
-                // Count entries manually
-                while let Ok(Some(_)) = entries.next_entry().await {
-                    count += 1;
+                // Count subdirectories manually; files are not folders
+                while let Ok(Some(entry)) = entries.next_entry().await {
+                    if entry.file_type().await.is_ok_and(|t| t.is_dir()) {
+                        count += 1;
+                    }
                 }
 
                 Ok(count)
@@ -283,6 +317,8 @@ impl Clone for FolderFsRepository {
             storage_mediator: self.storage_mediator.clone(),
             id_mapping_service: self.id_mapping_service.clone(),
             path_service: self.path_service.clone(),
+            content_store: self.content_store.clone(),
+            reference_signer: self.reference_signer.clone(),
         }
     }
 }
@@ -764,7 +800,7 @@ impl FolderRepository for FolderFsRepository {
 
         // Get total count if requested
         let total_count = if include_total {
-            match self.count_directory_items(&abs_parent_path).await {
+            match self.count_subdirectories(&abs_parent_path).await {
                 Ok(count) => Some(count),
                 Err(e) => {
                     tracing::warn!("Error counting directory items: {}", e);
@@ -793,24 +829,13 @@ impl FolderRepository for FolderFsRepository {
         let mut folders = Vec::new();
         let mut current_idx = 0;
 
-        // Loop through entries, applying pagination manually
+        // Loop through entries, applying pagination manually; only
+        // directories count towards the offset and the limit
         while let Some(entry_result) = entries.next().await {
-            // Skip entries before offset
-            if current_idx < offset {
-                current_idx += 1;
-                continue;
-            }
-
-            // Stop after reaching limit
-            if folders.len() >= limit {
-                break;
-            }
-
             let entry = match entry_result {
                 Ok(e) => e,
                 Err(err) => {
                     tracing::error!("Error reading directory entry: {}", err);
-                    current_idx += 1;
                     continue;
                 }
             };
@@ -820,16 +845,25 @@ impl FolderRepository for FolderFsRepository {
                 Ok(ft) => ft,
                 Err(e) => {
                     tracing::error!("Error getting file type: {}", e);
-                    current_idx += 1;
                     continue;
                 }
             };
 
             if !file_type.is_dir() {
+                continue;
+            }
+
+            // Skip folders before offset
+            if current_idx < offset {
                 current_idx += 1;
                 continue;
             }
 
+            // Stop after reaching limit
+            if folders.len() >= limit {
+                break;
+            }
+
             // Get the path and convert to StoragePath
             let path = entry.path();
             let rel_path = match path.strip_prefix(&self.root_path) {
@@ -913,9 +947,9 @@ impl FolderRepository for FolderFsRepository {
             .await
             .map_err(FolderRepositoryError::IoError)?;
 
-        // Update the ID mapping
+        // Update the ID mapping of the folder and everything below it
         self.id_mapping_service
-            .update_path(id, renamed_folder.storage_path())
+            .update_path_with_descendants(id, renamed_folder.storage_path())
             .await
             .map_err(FolderRepositoryError::from)?;
 
@@ -1002,9 +1036,9 @@ impl FolderRepository for FolderFsRepository {
             new_abs_path
         );
 
-        // Update the ID mapping
+        // Update the ID mapping of the folder and everything below it
         self.id_mapping_service
-            .update_path(id, moved_folder.storage_path())
+            .update_path_with_descendants(id, moved_folder.storage_path())
             .await
             .map_err(FolderRepositoryError::from)?;
 
@@ -1032,6 +1066,7 @@ impl FolderRepository for FolderFsRepository {
         // Para carpetas grandes, eliminar puede tomar tiempo
         // Lo manejamos en un task separado para no bloquear
         let abs_path = self.resolve_storage_path(&storage_path);
+        self.release_folder_contents(&abs_path).await;
 
         // Si la carpeta contiene muchos archivos, remove_dir_all puede tardar
         // usamos tokio::spawn para hacerlo en un task separado
@@ -166,6 +166,7 @@ impl FolderFsRepository {
 
         // Eliminar la carpeta recursivamente
         if folder_path.exists() {
+            self.release_folder_contents(&folder_path).await;
             match fs::remove_dir_all(&folder_path).await {
                 Ok(_) => {
                     debug!(
@@ -3,14 +3,24 @@ pub mod folder_fs_repository;
 pub mod parallel_file_processor;
 
 // Nuevos repositorios refactorizados
+pub mod app_password_fs_repository;
+pub mod calendar_storage_adapter;
+pub mod chunked_upload_fs_repository;
+pub mod content_index_fs_repository;
 pub mod file_fs_read_repository;
 pub mod file_fs_repository_trash;
 pub mod file_fs_write_repository;
 pub mod file_metadata_manager;
 pub mod file_path_resolver;
+pub mod file_version_fs_repository;
 pub mod folder_fs_repository_trash;
 pub mod share_fs_repository;
+pub mod sync_change_fs_repository;
+pub mod thumbnail_fs_repository;
 pub mod trash_fs_repository;
+pub mod upload_session_fs_repository;
+pub mod webdav_lock_fs_repository;
+pub mod webdav_property_fs_repository;
 
 // Repositorios PostgreSQL
 pub mod pg;
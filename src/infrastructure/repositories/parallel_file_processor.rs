@@ -501,6 +501,7 @@ impl ParallelFileProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bytes::BufMut;
     use tempfile::tempdir;
 
     #[tokio::test]
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::{types::Uuid, PgPool, Row};
+use sqlx::{postgres::PgRow, types::Uuid, PgPool, Row};
 use std::sync::Arc;
 
 use crate::common::errors::DomainError;
@@ -17,6 +17,28 @@ impl CalendarEventPgRepository {
     pub fn new(pool: Arc<PgPool>) -> Self {
         Self { pool }
     }
+
+    /// Construye un evento a partir de una fila de `caldav.calendar_events`
+    fn row_to_event(row: &PgRow) -> CalendarEventRepositoryResult<CalendarEvent> {
+        CalendarEvent::with_id(
+            row.get("id"),
+            row.get("calendar_id"),
+            row.get("summary"),
+            row.get::<Option<String>, _>("description"),
+            row.get::<Option<String>, _>("location"),
+            row.get("start_time"),
+            row.get("end_time"),
+            row.get("all_day"),
+            row.get::<Option<String>, _>("rrule"),
+            row.get("ical_uid"),
+            row.get::<Option<String>, _>("ical_data")
+                .unwrap_or_default(),
+            row.get("created_at"),
+            row.get("updated_at"),
+        )
+        .map(|event| event.with_timezone(row.get::<Option<String>, _>("timezone")))
+        .map_err(|e| DomainError::database_error(format!("Error creating calendar event: {}", e)))
+    }
 }
 
 #[async_trait]
@@ -33,9 +55,9 @@ impl CalendarEventRepository for CalendarEventPgRepository {
             r#"
             INSERT INTO caldav.calendar_events (
                 id, calendar_id, summary, description, location, start_time, end_time, 
-                all_day, rrule, created_at, updated_at, ical_uid, ical_data
+                all_day, rrule, created_at, updated_at, ical_uid, ical_data, timezone
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
             "#,
         )
         .bind(event.id())
@@ -51,6 +73,7 @@ impl CalendarEventRepository for CalendarEventPgRepository {
         .bind(event.updated_at())
         .bind(event.ical_uid())
         .bind(event.ical_data())
+        .bind(event.timezone())
         .execute(&*self.pool)
         .await
         .map_err(|e| {
@@ -78,8 +101,9 @@ impl CalendarEventRepository for CalendarEventPgRepository {
                 all_day = $6, 
                 rrule = $7,
                 ical_data = $8,
-                updated_at = $9
-            WHERE id = $10
+                timezone = $9,
+                updated_at = $10
+            WHERE id = $11
             "#,
         )
         .bind(event.summary())
@@ -90,6 +114,7 @@ impl CalendarEventRepository for CalendarEventPgRepository {
         .bind(event.all_day())
         .bind(event.rrule())
         .bind(event.ical_data())
+        .bind(event.timezone())
         .bind(now)
         .bind(event.id())
         .execute(&*self.pool)
@@ -126,22 +151,21 @@ impl CalendarEventRepository for CalendarEventPgRepository {
         start: &DateTime<Utc>,
         end: &DateTime<Utc>,
     ) -> CalendarEventRepositoryResult<Vec<CalendarEvent>> {
-        // Para una implementación real, necesitaríamos construir objetos CalendarEvent con un constructor adecuado
-        // Esta es una implementación simplificada para mostrar cómo evitar las macros query_as!
-
-        let _rows = sqlx::query(
+        // Las recurrentes se traen si empiezan antes del final del rango y se
+        // filtran expandiendo sus instancias
+        let rows = sqlx::query(
             r#"
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE calendar_id = $1 
               AND (
                   (start_time >= $2 AND start_time < $3) OR
                   (end_time > $2 AND end_time <= $3) OR
                   (start_time <= $2 AND end_time >= $3) OR
-                  (rrule IS NOT NULL AND end_time >= $2)
+                  ((rrule IS NOT NULL OR ical_data LIKE '%RDATE%') AND start_time < $3)
               )
             ORDER BY start_time
             "#,
@@ -155,16 +179,11 @@ impl CalendarEventRepository for CalendarEventPgRepository {
             DomainError::database_error(format!("Failed to get events in time range: {}", e))
         })?;
 
-        // En un escenario real, construiríamos objetos CalendarEvent para cada fila
-        // Aquí solo devolvemos un vector vacío como ejemplo
-
-        let events = Vec::new();
-        // Código para construir eventos desde rows iría aquí
-        // Por ejemplo:
-        // for row in rows {
-        //     events.push(CalendarEvent::new(...))
-        // }
-
+        let mut events = rows
+            .iter()
+            .map(Self::row_to_event)
+            .collect::<CalendarEventRepositoryResult<Vec<_>>>()?;
+        events.retain(|event| event.occurs_in_range(start, end));
         Ok(events)
     }
 
@@ -174,7 +193,7 @@ impl CalendarEventRepository for CalendarEventPgRepository {
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE id = $1
             "#,
@@ -187,30 +206,7 @@ impl CalendarEventRepository for CalendarEventPgRepository {
         })?
         .ok_or_else(|| DomainError::not_found("Calendar Event", id.to_string()))?;
 
-        // En una implementación real, construiríamos un objeto CalendarEvent completo
-        // Por simplicidad, creamos un objeto con valores predeterminados para
-        // demostrar el enfoque sin macros
-
-        let event = CalendarEvent::with_id(
-            row.get("id"),
-            row.get("calendar_id"),
-            row.get("summary"),
-            row.get::<Option<String>, _>("description"),
-            row.get::<Option<String>, _>("location"),
-            row.get("start_time"),
-            row.get("end_time"),
-            row.get("all_day"),
-            row.get::<Option<String>, _>("rrule"),
-            row.get("ical_uid"),
-            row.get("ical_data"),
-            row.get("created_at"),
-            row.get("updated_at"),
-        )
-        .map_err(|e| {
-            DomainError::database_error(format!("Error creating calendar event: {}", e))
-        })?;
-
-        Ok(event)
+        Self::row_to_event(&row)
     }
 
     async fn list_events_by_calendar(
@@ -218,12 +214,12 @@ impl CalendarEventRepository for CalendarEventPgRepository {
         calendar_id: &Uuid,
     ) -> CalendarEventRepositoryResult<Vec<CalendarEvent>> {
         // Usamos sqlx::query en lugar de query_as para evitar la necesidad de verificar la base de datos en tiempo de compilación
-        let _rows = sqlx::query(
+        let rows = sqlx::query(
             r#"
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE calendar_id = $1
             ORDER BY start_time
@@ -236,22 +232,7 @@ impl CalendarEventRepository for CalendarEventPgRepository {
             DomainError::database_error(format!("Failed to get events by calendar: {}", e))
         })?;
 
-        // En una implementación real, mapearíamos cada fila a un objeto CalendarEvent
-        // Este es un ejemplo simplificado que devuelve una lista vacía
-        let events = Vec::new();
-
-        // Ejemplo de cómo sería el mapeo real:
-        // for row in rows {
-        //     let event = CalendarEvent::new(
-        //         row.get("id"),
-        //         row.get("calendar_id"),
-        //         row.get("summary"),
-        //         // ... otros campos
-        //     );
-        //     events.push(event);
-        // }
-
-        Ok(events)
+        rows.iter().map(Self::row_to_event).collect()
     }
 
     async fn find_events_by_summary(
@@ -261,12 +242,12 @@ impl CalendarEventRepository for CalendarEventPgRepository {
     ) -> CalendarEventRepositoryResult<Vec<CalendarEvent>> {
         let search_pattern = format!("%{}%", summary);
 
-        let _rows = sqlx::query(
+        let rows = sqlx::query(
             r#"
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE calendar_id = $1 AND summary ILIKE $2
             ORDER BY start_time
@@ -280,16 +261,7 @@ impl CalendarEventRepository for CalendarEventPgRepository {
             DomainError::database_error(format!("Failed to find events by summary: {}", e))
         })?;
 
-        // En una implementación real, mapearíamos cada fila a un objeto CalendarEvent
-        // Este es un ejemplo simplificado que devuelve una lista vacía
-        let events = Vec::new();
-
-        // Aquí iría el código para construir eventos desde rows
-        // for row in rows {
-        //     events.push(CalendarEvent::new(...));
-        // }
-
-        Ok(events)
+        rows.iter().map(Self::row_to_event).collect()
     }
 
     async fn find_event_by_ical_uid(
@@ -297,12 +269,12 @@ impl CalendarEventRepository for CalendarEventPgRepository {
         calendar_id: &Uuid,
         ical_uid: &str,
     ) -> CalendarEventRepositoryResult<Option<CalendarEvent>> {
-        let _row_opt = sqlx::query(
+        let row = sqlx::query(
             r#"
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE calendar_id = $1 AND ical_uid = $2
             "#,
@@ -315,9 +287,7 @@ impl CalendarEventRepository for CalendarEventPgRepository {
             DomainError::database_error(format!("Failed to get calendar event by UID: {}", e))
         })?;
 
-        // En una implementación real, crearíamos un objeto CalendarEvent a partir de row_opt
-        // Por simplicidad, devolvemos None como ejemplo
-        Ok(None)
+        row.as_ref().map(Self::row_to_event).transpose()
     }
 
     async fn count_events_in_calendar(
@@ -368,12 +338,12 @@ impl CalendarEventRepository for CalendarEventPgRepository {
         offset: i64,
     ) -> CalendarEventRepositoryResult<Vec<CalendarEvent>> {
         // Usamos sqlx::query en lugar de query_as para evitar la necesidad de verificar la base de datos en tiempo de compilación
-        let _rows = sqlx::query(
+        let rows = sqlx::query(
             r#"
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE calendar_id = $1
             ORDER BY start_time
@@ -392,22 +362,7 @@ impl CalendarEventRepository for CalendarEventPgRepository {
             ))
         })?;
 
-        // En una implementación real, mapearíamos cada fila a un objeto CalendarEvent
-        // Este es un ejemplo simplificado que devuelve una lista vacía
-        let events = Vec::new();
-
-        // Ejemplo de cómo sería el mapeo real:
-        // for row in rows {
-        //     let event = CalendarEvent::new(
-        //         row.get("id"),
-        //         row.get("calendar_id"),
-        //         row.get("summary"),
-        //         // ... otros campos
-        //     );
-        //     events.push(event);
-        // }
-
-        Ok(events)
+        rows.iter().map(Self::row_to_event).collect()
     }
 
     async fn find_recurring_events_in_range(
@@ -416,17 +371,16 @@ impl CalendarEventRepository for CalendarEventPgRepository {
         start: &DateTime<Utc>,
         end: &DateTime<Utc>,
     ) -> CalendarEventRepositoryResult<Vec<CalendarEvent>> {
-        let _rows = sqlx::query(
+        let rows = sqlx::query(
             r#"
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE calendar_id = $1 
               AND rrule IS NOT NULL
-              AND end_time >= $2
-              AND start_time <= $3
+              AND start_time < $3
             ORDER BY start_time
             "#,
         )
@@ -439,29 +393,11 @@ impl CalendarEventRepository for CalendarEventPgRepository {
             DomainError::database_error(format!("Failed to find recurring events in range: {}", e))
         })?;
 
-        // En una implementación real, mapearíamos cada fila a un objeto CalendarEvent
-        // Por simplicidad, devolvemos una lista vacía de eventos
-        let events = Vec::new();
-
-        // Aquí iría el código para construir los objetos CalendarEvent
-        // for row in rows {
-        //     events.push(CalendarEvent::with_id(
-        //         row.get("id"),
-        //         row.get("calendar_id"),
-        //         row.get("summary"),
-        //         row.get::<Option<String>, _>("description"),
-        //         row.get::<Option<String>, _>("location"),
-        //         row.get("start_time"),
-        //         row.get("end_time"),
-        //         row.get("all_day"),
-        //         row.get::<Option<String>, _>("rrule"),
-        //         row.get("ical_uid"),
-        //         row.get("ical_data"),
-        //         row.get("created_at"),
-        //         row.get("updated_at")
-        //     ).unwrap());
-        // }
-
+        let mut events = rows
+            .iter()
+            .map(Self::row_to_event)
+            .collect::<CalendarEventRepositoryResult<Vec<_>>>()?;
+        events.retain(|event| event.occurs_in_range(start, end));
         Ok(events)
     }
 }
@@ -478,7 +414,7 @@ impl CalendarEventPgRepository {
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE id = $1
             "#,
@@ -490,32 +426,7 @@ impl CalendarEventPgRepository {
             DomainError::database_error(format!("Failed to get calendar event by id: {}", e))
         })?;
 
-        if let Some(row) = row_opt {
-            // En una implementación real, construiríamos un objeto CalendarEvent completo
-            // Este es un ejemplo simplificado
-            let event = CalendarEvent::with_id(
-                row.get("id"),
-                row.get("calendar_id"),
-                row.get("summary"),
-                row.get::<Option<String>, _>("description"),
-                row.get::<Option<String>, _>("location"),
-                row.get("start_time"),
-                row.get("end_time"),
-                row.get("all_day"),
-                row.get::<Option<String>, _>("rrule"),
-                row.get("ical_uid"),
-                row.get("ical_data"),
-                row.get("created_at"),
-                row.get("updated_at"),
-            )
-            .map_err(|e| {
-                DomainError::database_error(format!("Error creating calendar event: {}", e))
-            })?;
-
-            return Ok(Some(event));
-        }
-
-        Ok(None)
+        row_opt.as_ref().map(Self::row_to_event).transpose()
     }
 
     // Helper method to get event by UID
@@ -529,7 +440,7 @@ impl CalendarEventPgRepository {
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE calendar_id = $1 AND ical_uid = $2
             "#,
@@ -542,13 +453,7 @@ impl CalendarEventPgRepository {
             DomainError::database_error(format!("Failed to get calendar event by UID: {}", e))
         })?;
 
-        if let Some(_row) = row_opt {
-            // En una implementación real, construiríamos un objeto CalendarEvent a partir de la fila
-            // Por simplicidad, devolvemos None como ejemplo
-            return Ok(None);
-        }
-
-        Ok(None)
+        row_opt.as_ref().map(Self::row_to_event).transpose()
     }
 
     // Helper method to get events by calendar
@@ -556,12 +461,12 @@ impl CalendarEventPgRepository {
         &self,
         calendar_id: &Uuid,
     ) -> CalendarEventRepositoryResult<Vec<CalendarEvent>> {
-        let _rows = sqlx::query(
+        let rows = sqlx::query(
             r#"
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE calendar_id = $1
             ORDER BY start_time
@@ -574,22 +479,7 @@ impl CalendarEventPgRepository {
             DomainError::database_error(format!("Failed to get events by calendar: {}", e))
         })?;
 
-        // En una implementación real, mapearíamos cada fila a un objeto CalendarEvent
-        // Este es un ejemplo simplificado que devuelve una lista vacía
-        let events = Vec::new();
-
-        // Ejemplo de cómo sería el mapeo real:
-        // for row in rows {
-        //     let event = CalendarEvent::with_id(
-        //         row.get("id"),
-        //         row.get("calendar_id"),
-        //         row.get("summary"),
-        //         // ... otros campos
-        //     );
-        //     events.push(event);
-        // }
-
-        Ok(events)
+        rows.iter().map(Self::row_to_event).collect()
     }
 
     // Helper method to get changed events
@@ -598,12 +488,12 @@ impl CalendarEventPgRepository {
         calendar_id: &Uuid,
         since: &DateTime<Utc>,
     ) -> CalendarEventRepositoryResult<Vec<CalendarEvent>> {
-        let _rows = sqlx::query(
+        let rows = sqlx::query(
             r#"
             SELECT 
                 id, calendar_id, summary, description, location, 
                 start_time, end_time, all_day, rrule, 
-                created_at, updated_at, ical_uid, ical_data
+                created_at, updated_at, ical_uid, ical_data, timezone
             FROM caldav.calendar_events
             WHERE calendar_id = $1 AND updated_at > $2
             ORDER BY updated_at
@@ -615,31 +505,7 @@ impl CalendarEventPgRepository {
         .await
         .map_err(|e| DomainError::database_error(format!("Failed to get changed events: {}", e)))?;
 
-        // En una implementación real, mapearíamos cada fila a un objeto CalendarEvent
-        // Este es un ejemplo simplificado que devuelve una lista vacía
-        let events = Vec::new();
-
-        // Ejemplo de cómo sería el mapeo real:
-        // for row in rows {
-        //     let event = CalendarEvent::with_id(
-        //         row.get("id"),
-        //         row.get("calendar_id"),
-        //         row.get("summary"),
-        //         row.get::<Option<String>, _>("description"),
-        //         row.get::<Option<String>, _>("location"),
-        //         row.get("start_time"),
-        //         row.get("end_time"),
-        //         row.get("all_day"),
-        //         row.get::<Option<String>, _>("rrule"),
-        //         row.get("ical_uid"),
-        //         row.get("ical_data"),
-        //         row.get("created_at"),
-        //         row.get("updated_at")
-        //     ).unwrap();
-        //     events.push(event);
-        // }
-
-        Ok(events)
+        rows.iter().map(Self::row_to_event).collect()
     }
 
     // Helper method to add an attendee to an event
@@ -405,6 +405,57 @@ impl CalendarRepository for CalendarPgRepository {
         Ok(shares)
     }
 
+    async fn get_user_timezone(&self, user_id: &str) -> CalendarRepositoryResult<Option<String>> {
+        let row = sqlx::query(
+            r#"
+            SELECT default_timezone
+            FROM caldav.user_settings
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| DomainError::database_error(format!("Failed to get user time zone: {}", e)))?;
+
+        Ok(row.map(|row| row.get("default_timezone")))
+    }
+
+    async fn set_user_timezone(
+        &self,
+        user_id: &str,
+        timezone: Option<&str>,
+    ) -> CalendarRepositoryResult<()> {
+        let result = match timezone {
+            Some(timezone) => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO caldav.user_settings (user_id, default_timezone, updated_at)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (user_id) DO UPDATE
+                    SET default_timezone = $2, updated_at = $3
+                    "#,
+                )
+                .bind(user_id)
+                .bind(timezone)
+                .bind(Utc::now())
+                .execute(&*self.pool)
+                .await
+            }
+            None => {
+                sqlx::query("DELETE FROM caldav.user_settings WHERE user_id = $1")
+                    .bind(user_id)
+                    .execute(&*self.pool)
+                    .await
+            }
+        };
+        result.map_err(|e| {
+            DomainError::database_error(format!("Failed to set user time zone: {}", e))
+        })?;
+
+        Ok(())
+    }
+
     async fn get_calendar_property(
         &self,
         calendar_id: &Uuid,
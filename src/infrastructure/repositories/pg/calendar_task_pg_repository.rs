@@ -0,0 +1,225 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, types::Uuid, PgPool, Row};
+use std::sync::Arc;
+
+use crate::common::errors::DomainError;
+use crate::domain::entities::calendar_task::CalendarTask;
+use crate::domain::repositories::calendar_task_repository::{
+    CalendarTaskRepository, CalendarTaskRepositoryResult,
+};
+
+pub struct CalendarTaskPgRepository {
+    pool: Arc<PgPool>,
+}
+
+impl CalendarTaskPgRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Construye una tarea a partir de una fila de `caldav.calendar_tasks`;
+    /// los datos iCalendar son la fuente de verdad del resto de columnas
+    fn row_to_task(row: &PgRow) -> CalendarTaskRepositoryResult<CalendarTask> {
+        CalendarTask::with_id(
+            row.get("id"),
+            row.get("calendar_id"),
+            row.get("ical_data"),
+            row.get("created_at"),
+            row.get("updated_at"),
+        )
+        .map(|task| task.with_timezone(row.get::<Option<String>, _>("timezone")))
+        .map_err(|e| DomainError::database_error(format!("Error creating calendar task: {}", e)))
+    }
+}
+
+#[async_trait]
+impl CalendarTaskRepository for CalendarTaskPgRepository {
+    async fn create_task(&self, task: CalendarTask) -> CalendarTaskRepositoryResult<CalendarTask> {
+        sqlx::query(
+            r#"
+            INSERT INTO caldav.calendar_tasks (
+                id, calendar_id, summary, description, status, priority, start_time,
+                due_time, completed_at, percent_complete, parent_uid, rrule, timezone,
+                ical_uid, ical_data, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            "#,
+        )
+        .bind(task.id())
+        .bind(task.calendar_id())
+        .bind(task.summary())
+        .bind(task.description())
+        .bind(task.status().as_str())
+        .bind(task.priority().map(i16::from))
+        .bind(task.start_time())
+        .bind(task.due_time())
+        .bind(task.completed_at())
+        .bind(task.percent_complete().map(i16::from))
+        .bind(task.parent_uid())
+        .bind(task.rrule())
+        .bind(task.timezone())
+        .bind(task.ical_uid())
+        .bind(task.ical_data())
+        .bind(task.created_at())
+        .bind(task.updated_at())
+        .execute(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to create calendar task: {}", e))
+        })?;
+
+        Ok(task)
+    }
+
+    async fn update_task(&self, task: CalendarTask) -> CalendarTaskRepositoryResult<CalendarTask> {
+        sqlx::query(
+            r#"
+            UPDATE caldav.calendar_tasks
+            SET summary = $1,
+                description = $2,
+                status = $3,
+                priority = $4,
+                start_time = $5,
+                due_time = $6,
+                completed_at = $7,
+                percent_complete = $8,
+                parent_uid = $9,
+                rrule = $10,
+                timezone = $11,
+                ical_data = $12,
+                updated_at = $13
+            WHERE id = $14
+            "#,
+        )
+        .bind(task.summary())
+        .bind(task.description())
+        .bind(task.status().as_str())
+        .bind(task.priority().map(i16::from))
+        .bind(task.start_time())
+        .bind(task.due_time())
+        .bind(task.completed_at())
+        .bind(task.percent_complete().map(i16::from))
+        .bind(task.parent_uid())
+        .bind(task.rrule())
+        .bind(task.timezone())
+        .bind(task.ical_data())
+        .bind(task.updated_at())
+        .bind(task.id())
+        .execute(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to update calendar task: {}", e))
+        })?;
+
+        Ok(task)
+    }
+
+    async fn delete_task(&self, id: &Uuid) -> CalendarTaskRepositoryResult<()> {
+        sqlx::query("DELETE FROM caldav.calendar_tasks WHERE id = $1")
+            .bind(id)
+            .execute(&*self.pool)
+            .await
+            .map_err(|e| {
+                DomainError::database_error(format!("Failed to delete calendar task: {}", e))
+            })?;
+
+        Ok(())
+    }
+
+    async fn find_task_by_id(&self, id: &Uuid) -> CalendarTaskRepositoryResult<CalendarTask> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, calendar_id, timezone, ical_data, created_at, updated_at
+            FROM caldav.calendar_tasks
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to get calendar task by id: {}", e))
+        })?
+        .ok_or_else(|| DomainError::not_found("Calendar Task", id.to_string()))?;
+
+        Self::row_to_task(&row)
+    }
+
+    async fn find_task_by_ical_uid(
+        &self,
+        calendar_id: &Uuid,
+        ical_uid: &str,
+    ) -> CalendarTaskRepositoryResult<Option<CalendarTask>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, calendar_id, timezone, ical_data, created_at, updated_at
+            FROM caldav.calendar_tasks
+            WHERE calendar_id = $1 AND ical_uid = $2
+            "#,
+        )
+        .bind(calendar_id)
+        .bind(ical_uid)
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to get calendar task by UID: {}", e))
+        })?;
+
+        row.as_ref().map(Self::row_to_task).transpose()
+    }
+
+    async fn list_tasks_by_calendar(
+        &self,
+        calendar_id: &Uuid,
+    ) -> CalendarTaskRepositoryResult<Vec<CalendarTask>> {
+        // Las tareas sin fecha de vencimiento van al final
+        let rows = sqlx::query(
+            r#"
+            SELECT id, calendar_id, timezone, ical_data, created_at, updated_at
+            FROM caldav.calendar_tasks
+            WHERE calendar_id = $1
+            ORDER BY due_time NULLS LAST, created_at
+            "#,
+        )
+        .bind(calendar_id)
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to get tasks by calendar: {}", e))
+        })?;
+
+        rows.iter().map(Self::row_to_task).collect()
+    }
+
+    async fn get_tasks_in_time_range(
+        &self,
+        calendar_id: &Uuid,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> CalendarTaskRepositoryResult<Vec<CalendarTask>> {
+        // Las reglas de RFC 4791 para VTODO dependen de qué propiedades tiene
+        // cada tarea, así que se aplican sobre sus datos iCalendar
+        let mut tasks = self.list_tasks_by_calendar(calendar_id).await?;
+        tasks.retain(|task| task.occurs_in_range(start, end));
+        Ok(tasks)
+    }
+
+    async fn delete_all_tasks_in_calendar(
+        &self,
+        calendar_id: &Uuid,
+    ) -> CalendarTaskRepositoryResult<i64> {
+        let result = sqlx::query("DELETE FROM caldav.calendar_tasks WHERE calendar_id = $1")
+            .bind(calendar_id)
+            .execute(&*self.pool)
+            .await
+            .map_err(|e| {
+                DomainError::database_error(format!(
+                    "Failed to delete all tasks in calendar: {}",
+                    e
+                ))
+            })?;
+
+        Ok(result.rows_affected() as i64)
+    }
+}
@@ -0,0 +1,358 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::application::ports::outbound::IdMappingPort;
+use crate::common::errors::DomainError;
+use crate::domain::services::path_service::StoragePath;
+use crate::infrastructure::repositories::pg::transaction_utils::with_transaction;
+
+/// Filas insertadas por sentencia al importar un mapa JSON
+const IMPORT_BATCH_SIZE: usize = 5_000;
+
+/// Parte del antiguo `folder_ids.json` que se importa
+#[derive(Deserialize)]
+struct LegacyIdMap {
+    #[serde(default)]
+    path_to_id: HashMap<String, String>,
+}
+
+/// Mapeo de IDs sobre PostgreSQL (`storage.id_mappings`).
+///
+/// Cada instancia trabaja sobre un espacio de nombres (`folder`, `file`) que
+/// sustituye a su fichero JSON. Las rutas tienen un índice por prefijo, así que
+/// renombrar o mover una carpeta reescribe en una sola transacción las rutas
+/// de todo lo que cuelga de ella, en todos los espacios de nombres.
+pub struct IdMappingPgRepository {
+    pool: Arc<PgPool>,
+    namespace: String,
+}
+
+impl IdMappingPgRepository {
+    pub fn new(pool: Arc<PgPool>, namespace: impl Into<String>) -> Self {
+        Self {
+            pool,
+            namespace: namespace.into(),
+        }
+    }
+
+    /// Importa una sola vez un mapa JSON del servicio anterior.
+    ///
+    /// Solo importa si el espacio de nombres está vacío; después renombra el
+    /// fichero a `*.migrated` para no volver a leerlo. Devuelve las entradas
+    /// importadas.
+    pub async fn import_json_map(&self, map_path: &Path) -> Result<usize, DomainError> {
+        if !map_path.exists() {
+            return Ok(0);
+        }
+
+        let existing: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM storage.id_mappings WHERE namespace = $1")
+                .bind(&self.namespace)
+                .fetch_one(&*self.pool)
+                .await
+                .map_err(|e| Self::database_error("count ID mappings", e))?;
+
+        let mut imported = 0;
+        if existing == 0 {
+            let content = tokio::fs::read_to_string(map_path).await.map_err(|e| {
+                DomainError::internal_error(
+                    "IdMapping",
+                    format!("Failed to read {}: {}", map_path.display(), e),
+                )
+            })?;
+            let legacy: LegacyIdMap = serde_json::from_str(&content).map_err(|e| {
+                DomainError::internal_error(
+                    "IdMapping",
+                    format!("Failed to parse {}: {}", map_path.display(), e),
+                )
+            })?;
+
+            let entries: Vec<(String, String)> = legacy
+                .path_to_id
+                .into_iter()
+                .map(|(path, id)| (id, StoragePath::from_string(&path).to_string()))
+                .collect();
+            imported = entries.len();
+            let namespace = self.namespace.clone();
+
+            with_transaction(&self.pool, "import_id_mappings", |tx| {
+                Box::pin(async move {
+                    for batch in entries.chunks(IMPORT_BATCH_SIZE) {
+                        let (ids, paths): (Vec<String>, Vec<String>) =
+                            batch.iter().cloned().unzip();
+                        sqlx::query(
+                            r#"
+                            INSERT INTO storage.id_mappings (id, namespace, path)
+                            SELECT id, $1, path FROM UNNEST($2::text[], $3::text[]) AS t(id, path)
+                            ON CONFLICT DO NOTHING
+                            "#,
+                        )
+                        .bind(&namespace)
+                        .bind(&ids)
+                        .bind(&paths)
+                        .execute(&mut **tx)
+                        .await?;
+                    }
+                    Ok::<(), DomainError>(())
+                })
+            })
+            .await?;
+
+            tracing::info!(
+                "Imported {} {} ID mappings from {}",
+                imported,
+                self.namespace,
+                map_path.display()
+            );
+        } else {
+            tracing::warn!(
+                "Skipping import of {}: {} ID mappings already exist in the database",
+                map_path.display(),
+                self.namespace
+            );
+        }
+
+        let migrated_path = map_path.with_extension("json.migrated");
+        tokio::fs::rename(map_path, &migrated_path)
+            .await
+            .map_err(|e| {
+                DomainError::internal_error(
+                    "IdMapping",
+                    format!("Failed to rename {}: {}", map_path.display(), e),
+                )
+            })?;
+
+        Ok(imported)
+    }
+
+    fn database_error(action: &str, e: sqlx::Error) -> DomainError {
+        DomainError::database_error(format!("Failed to {}: {}", action, e))
+    }
+}
+
+/// Patrón LIKE que encaja con todo lo que cuelga de `path`
+fn descendants_pattern(path: &str) -> String {
+    let mut pattern = String::with_capacity(path.len() + 2);
+    for c in path.trim_end_matches('/').chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push_str("/%");
+    pattern
+}
+
+#[async_trait]
+impl IdMappingPort for IdMappingPgRepository {
+    async fn get_or_create_id(&self, path: &StoragePath) -> Result<String, DomainError> {
+        let path_str = path.to_string();
+
+        let existing: Option<String> = sqlx::query_scalar(
+            "SELECT id FROM storage.id_mappings WHERE namespace = $1 AND path = $2",
+        )
+        .bind(&self.namespace)
+        .bind(&path_str)
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| Self::database_error("look up ID mapping", e))?;
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+
+        // Si otra petición la crea a la vez, el ON CONFLICT no devuelve fila
+        let created: Option<String> = sqlx::query_scalar(
+            r#"
+            INSERT INTO storage.id_mappings (id, namespace, path)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (namespace, path) DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&self.namespace)
+        .bind(&path_str)
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| Self::database_error("create ID mapping", e))?;
+
+        match created {
+            Some(id) => Ok(id),
+            None => sqlx::query_scalar(
+                "SELECT id FROM storage.id_mappings WHERE namespace = $1 AND path = $2",
+            )
+            .bind(&self.namespace)
+            .bind(&path_str)
+            .fetch_one(&*self.pool)
+            .await
+            .map_err(|e| Self::database_error("look up ID mapping", e)),
+        }
+    }
+
+    async fn get_path_by_id(&self, id: &str) -> Result<StoragePath, DomainError> {
+        let path: Option<String> = sqlx::query_scalar(
+            "SELECT path FROM storage.id_mappings WHERE id = $1 AND namespace = $2",
+        )
+        .bind(id)
+        .bind(&self.namespace)
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| Self::database_error("look up ID mapping", e))?;
+
+        path.map(|path| StoragePath::from_string(&path))
+            .ok_or_else(|| DomainError::not_found("IdMapping", id))
+    }
+
+    async fn update_path(&self, id: &str, new_path: &StoragePath) -> Result<(), DomainError> {
+        let id = id.to_string();
+        let namespace = self.namespace.clone();
+        let new_path = new_path.to_string();
+
+        with_transaction(&self.pool, "update_id_mapping", |tx| {
+            Box::pin(async move {
+                // Una entrada antigua en la ruta de destino queda huérfana
+                sqlx::query(
+                    "DELETE FROM storage.id_mappings WHERE namespace = $1 AND path = $2 AND id <> $3",
+                )
+                .bind(&namespace)
+                .bind(&new_path)
+                .bind(&id)
+                .execute(&mut **tx)
+                .await?;
+
+                let updated = sqlx::query(
+                    r#"
+                    UPDATE storage.id_mappings
+                    SET path = $3, updated_at = NOW()
+                    WHERE id = $1 AND namespace = $2
+                    "#,
+                )
+                .bind(&id)
+                .bind(&namespace)
+                .bind(&new_path)
+                .execute(&mut **tx)
+                .await?;
+
+                if updated.rows_affected() == 0 {
+                    return Err(DomainError::not_found("IdMapping", id.as_str()));
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    async fn update_path_with_descendants(
+        &self,
+        id: &str,
+        new_path: &StoragePath,
+    ) -> Result<(), DomainError> {
+        let id = id.to_string();
+        let namespace = self.namespace.clone();
+        let new_path = new_path.to_string();
+
+        let moved = with_transaction(&self.pool, "move_id_mapping_subtree", |tx| {
+            Box::pin(async move {
+                let row = sqlx::query(
+                    "SELECT path FROM storage.id_mappings WHERE id = $1 AND namespace = $2 FOR UPDATE",
+                )
+                .bind(&id)
+                .bind(&namespace)
+                .fetch_optional(&mut **tx)
+                .await?
+                .ok_or_else(|| DomainError::not_found("IdMapping", id.as_str()))?;
+                let old_path: String = row.try_get("path")?;
+                if old_path == new_path {
+                    return Ok(0);
+                }
+
+                let old_pattern = descendants_pattern(&old_path);
+                let new_pattern = descendants_pattern(&new_path);
+
+                // Lo que quedara registrado en el destino ya no existe
+                sqlx::query(
+                    r#"
+                    DELETE FROM storage.id_mappings
+                    WHERE (path = $1 OR path LIKE $2 ESCAPE '\')
+                      AND NOT (path = $3 OR path LIKE $4 ESCAPE '\')
+                    "#,
+                )
+                .bind(&new_path)
+                .bind(&new_pattern)
+                .bind(&old_path)
+                .bind(&old_pattern)
+                .execute(&mut **tx)
+                .await?;
+
+                let updated = sqlx::query(
+                    r#"
+                    UPDATE storage.id_mappings
+                    SET path = $1 || substr(path, char_length($2) + 1), updated_at = NOW()
+                    WHERE path = $2 OR path LIKE $3 ESCAPE '\'
+                    "#,
+                )
+                .bind(&new_path)
+                .bind(&old_path)
+                .bind(&old_pattern)
+                .execute(&mut **tx)
+                .await?;
+
+                Ok::<u64, DomainError>(updated.rows_affected())
+            })
+        })
+        .await?;
+
+        tracing::debug!("Rewrote {} ID mappings for moved subtree", moved);
+        Ok(())
+    }
+
+    async fn remove_id(&self, id: &str) -> Result<(), DomainError> {
+        let deleted =
+            sqlx::query("DELETE FROM storage.id_mappings WHERE id = $1 AND namespace = $2")
+                .bind(id)
+                .bind(&self.namespace)
+                .execute(&*self.pool)
+                .await
+                .map_err(|e| Self::database_error("remove ID mapping", e))?;
+
+        if deleted.rows_affected() == 0 {
+            return Err(DomainError::not_found("IdMapping", id));
+        }
+        Ok(())
+    }
+
+    async fn save_changes(&self) -> Result<(), DomainError> {
+        // Cada operación se confirma en la base de datos al ejecutarse
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descendants_pattern_escapes_wildcards() {
+        assert_eq!(descendants_pattern("/docs"), "/docs/%");
+        assert_eq!(descendants_pattern("/docs/"), "/docs/%");
+        assert_eq!(
+            descendants_pattern("/100%_done\\x"),
+            "/100\\%\\_done\\\\x/%"
+        );
+    }
+
+    #[test]
+    fn test_legacy_map_parsing() {
+        let legacy: LegacyIdMap = serde_json::from_str(
+            r#"{"path_to_id": {"/a": "1", "/a/b": "2"}, "id_to_path": {}, "version": 3}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.path_to_id.len(), 2);
+        assert_eq!(legacy.path_to_id["/a/b"], "2");
+    }
+}
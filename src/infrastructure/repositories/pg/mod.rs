@@ -1,8 +1,12 @@
 mod address_book_pg_repository;
 mod calendar_event_pg_repository;
 mod calendar_pg_repository;
+mod calendar_task_pg_repository;
 mod contact_group_pg_repository;
 mod contact_pg_repository;
+mod id_mapping_pg_repository;
+mod reminder_pg_repository;
+mod schedule_inbox_pg_repository;
 mod session_pg_repository;
 mod transaction_utils;
 mod user_pg_repository;
@@ -10,7 +14,11 @@ mod user_pg_repository;
 pub use address_book_pg_repository::AddressBookPgRepository;
 pub use calendar_event_pg_repository::CalendarEventPgRepository;
 pub use calendar_pg_repository::CalendarPgRepository;
+pub use calendar_task_pg_repository::CalendarTaskPgRepository;
 pub use contact_group_pg_repository::ContactGroupPgRepository;
 pub use contact_pg_repository::ContactPgRepository;
+pub use id_mapping_pg_repository::IdMappingPgRepository;
+pub use reminder_pg_repository::ReminderPgRepository;
+pub use schedule_inbox_pg_repository::ScheduleInboxPgRepository;
 pub use session_pg_repository::SessionPgRepository;
 pub use user_pg_repository::UserPgRepository;
@@ -0,0 +1,331 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, types::Uuid, PgPool, Row};
+use std::sync::Arc;
+
+use crate::application::ports::reminder_ports::ReminderStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::reminder::{AlarmAction, AlarmSource, Reminder, ReminderNotification};
+
+/// Columnas de `caldav.reminders` que se leen para construir un recordatorio
+const REMINDER_COLUMNS: &str = "r.id, r.user_id, r.calendar_id, r.object_id, r.component, \
+     r.alarm_key, r.action, r.trigger_at, r.occurrence_start, r.summary, r.description, \
+     r.sent_at";
+
+pub struct ReminderPgRepository {
+    pool: Arc<PgPool>,
+}
+
+impl ReminderPgRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Construye un recordatorio a partir de una fila de `caldav.reminders`
+    fn row_to_reminder(row: &PgRow) -> Result<Reminder, DomainError> {
+        let action: String = row.get("action");
+        Ok(Reminder {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            calendar_id: row.get::<Uuid, _>("calendar_id").to_string(),
+            object_id: row.get::<Uuid, _>("object_id").to_string(),
+            component: row.get("component"),
+            alarm_key: row.get("alarm_key"),
+            action: AlarmAction::parse(&action).ok_or_else(|| {
+                DomainError::database_error(format!("Unknown alarm action: {}", action))
+            })?,
+            trigger_at: row.get("trigger_at"),
+            occurrence_start: row.get("occurrence_start"),
+            summary: row.get("summary"),
+            description: row.get("description"),
+            sent_at: row.get("sent_at"),
+        })
+    }
+
+    /// Construye un aviso a partir de una fila de
+    /// `caldav.reminder_notifications` unida a su recordatorio
+    fn row_to_notification(row: &PgRow) -> Result<ReminderNotification, DomainError> {
+        Ok(ReminderNotification {
+            id: row.get("notification_id"),
+            user_id: row.get("user_id"),
+            reminder: Self::row_to_reminder(row)?,
+            created_at: row.get("notified_at"),
+        })
+    }
+
+    fn row_to_source(row: &PgRow) -> AlarmSource {
+        AlarmSource {
+            object_id: row.get::<Uuid, _>("id").to_string(),
+            calendar_id: row.get::<Uuid, _>("calendar_id").to_string(),
+            owner_id: row.get("owner_id"),
+            timezone: row.get("default_timezone"),
+            ical_data: row.get("ical_data"),
+            updated_at: row.get("updated_at"),
+        }
+    }
+
+    fn parse_id(id: &str, entity: &'static str) -> Result<Uuid, DomainError> {
+        Uuid::parse_str(id)
+            .map_err(|_| DomainError::validation_error(format!("Invalid {} ID: {}", entity, id)))
+    }
+}
+
+#[async_trait]
+impl ReminderStoragePort for ReminderPgRepository {
+    async fn alarm_sources(
+        &self,
+        changed_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<AlarmSource>, DomainError> {
+        // Los objetos que han cambiado se devuelven aunque ya no tengan
+        // alarmas, para que se borren sus recordatorios pendientes
+        let rows = sqlx::query(
+            r#"
+            SELECT o.id, o.calendar_id, c.owner_id, s.default_timezone, o.ical_data, o.updated_at
+            FROM (
+                SELECT id, calendar_id, ical_data, updated_at FROM caldav.calendar_events
+                UNION ALL
+                SELECT id, calendar_id, ical_data, updated_at FROM caldav.calendar_tasks
+            ) o
+            JOIN caldav.calendars c ON c.id = o.calendar_id
+            LEFT JOIN caldav.user_settings s ON s.user_id = c.owner_id
+            WHERE ($1::timestamptz IS NULL AND o.ical_data LIKE '%BEGIN:VALARM%')
+               OR o.updated_at > $1
+            "#,
+        )
+        .bind(changed_since)
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(|e| DomainError::database_error(format!("Failed to list alarms: {}", e)))?;
+
+        Ok(rows.iter().map(Self::row_to_source).collect())
+    }
+
+    async fn find_alarm_source(&self, object_id: &str) -> Result<Option<AlarmSource>, DomainError> {
+        let id = Self::parse_id(object_id, "calendar object")?;
+        let row = sqlx::query(
+            r#"
+            SELECT o.id, o.calendar_id, c.owner_id, s.default_timezone, o.ical_data, o.updated_at
+            FROM (
+                SELECT id, calendar_id, ical_data, updated_at FROM caldav.calendar_events
+                WHERE id = $1
+                UNION ALL
+                SELECT id, calendar_id, ical_data, updated_at FROM caldav.calendar_tasks
+                WHERE id = $1
+            ) o
+            JOIN caldav.calendars c ON c.id = o.calendar_id
+            LEFT JOIN caldav.user_settings s ON s.user_id = c.owner_id
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| DomainError::database_error(format!("Failed to get alarms: {}", e)))?;
+
+        Ok(row.as_ref().map(Self::row_to_source))
+    }
+
+    async fn replace_reminders(
+        &self,
+        object_id: &str,
+        reminders: Vec<Reminder>,
+    ) -> Result<(), DomainError> {
+        let object_id = Self::parse_id(object_id, "calendar object")?;
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            DomainError::database_error(format!("Failed to begin transaction: {}", e))
+        })?;
+
+        sqlx::query("DELETE FROM caldav.reminders WHERE object_id = $1 AND sent_at IS NULL")
+            .bind(object_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                DomainError::database_error(format!("Failed to delete reminders: {}", e))
+            })?;
+
+        // Los disparos ya enviados siguen en la tabla y no se repiten
+        for reminder in reminders {
+            sqlx::query(
+                r#"
+                INSERT INTO caldav.reminders (
+                    id, user_id, calendar_id, object_id, component, alarm_key, action,
+                    trigger_at, occurrence_start, summary, description, sent_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                ON CONFLICT (object_id, alarm_key, trigger_at) DO NOTHING
+                "#,
+            )
+            .bind(reminder.id)
+            .bind(&reminder.user_id)
+            .bind(Self::parse_id(&reminder.calendar_id, "calendar")?)
+            .bind(object_id)
+            .bind(&reminder.component)
+            .bind(&reminder.alarm_key)
+            .bind(reminder.action.as_str())
+            .bind(reminder.trigger_at)
+            .bind(reminder.occurrence_start)
+            .bind(&reminder.summary)
+            .bind(&reminder.description)
+            .bind(reminder.sent_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DomainError::database_error(format!("Failed to save reminder: {}", e)))?;
+        }
+
+        tx.commit().await.map_err(|e| {
+            DomainError::database_error(format!("Failed to commit transaction: {}", e))
+        })?;
+
+        Ok(())
+    }
+
+    async fn prune_reminders(&self, before: DateTime<Utc>) -> Result<u64, DomainError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM caldav.reminders r
+            WHERE (r.sent_at IS NULL
+                   AND NOT EXISTS (SELECT 1 FROM caldav.calendar_events e WHERE e.id = r.object_id)
+                   AND NOT EXISTS (SELECT 1 FROM caldav.calendar_tasks t WHERE t.id = r.object_id))
+               OR (r.sent_at IS NOT NULL
+                   AND r.trigger_at < $1
+                   AND NOT EXISTS (
+                       SELECT 1 FROM caldav.reminder_notifications n WHERE n.reminder_id = r.id
+                   ))
+            "#,
+        )
+        .bind(before)
+        .execute(&*self.pool)
+        .await
+        .map_err(|e| DomainError::database_error(format!("Failed to prune reminders: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn claim_due_reminders(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Reminder>, DomainError> {
+        // SKIP LOCKED deja a cada instancia del servidor sus propias filas
+        let rows = sqlx::query(&format!(
+            r#"
+            UPDATE caldav.reminders r
+            SET sent_at = $1
+            WHERE r.id IN (
+                SELECT id FROM caldav.reminders
+                WHERE sent_at IS NULL AND trigger_at <= $1
+                ORDER BY trigger_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            REMINDER_COLUMNS
+        ))
+        .bind(now)
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(|e| DomainError::database_error(format!("Failed to claim reminders: {}", e)))?;
+
+        rows.iter().map(Self::row_to_reminder).collect()
+    }
+
+    async fn save_notification(
+        &self,
+        notification: ReminderNotification,
+    ) -> Result<ReminderNotification, DomainError> {
+        sqlx::query(
+            r#"
+            INSERT INTO caldav.reminder_notifications (id, user_id, reminder_id, created_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(notification.id)
+        .bind(&notification.user_id)
+        .bind(notification.reminder.id)
+        .bind(notification.created_at)
+        .execute(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to save reminder notification: {}", e))
+        })?;
+
+        Ok(notification)
+    }
+
+    async fn list_notifications(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<ReminderNotification>, DomainError> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT n.id AS notification_id, n.created_at AS notified_at, {}
+            FROM caldav.reminder_notifications n
+            JOIN caldav.reminders r ON r.id = n.reminder_id
+            WHERE n.user_id = $1
+            ORDER BY n.created_at
+            "#,
+            REMINDER_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to list reminder notifications: {}", e))
+        })?;
+
+        rows.iter().map(Self::row_to_notification).collect()
+    }
+
+    async fn find_notification(
+        &self,
+        user_id: &str,
+        notification_id: &str,
+    ) -> Result<Option<ReminderNotification>, DomainError> {
+        // Los identificadores que no son UUID no corresponden a ningún aviso
+        let Ok(id) = Uuid::parse_str(notification_id) else {
+            return Ok(None);
+        };
+        let row = sqlx::query(&format!(
+            r#"
+            SELECT n.id AS notification_id, n.created_at AS notified_at, {}
+            FROM caldav.reminder_notifications n
+            JOIN caldav.reminders r ON r.id = n.reminder_id
+            WHERE n.user_id = $1 AND n.id = $2
+            "#,
+            REMINDER_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to get reminder notification: {}", e))
+        })?;
+
+        row.as_ref().map(Self::row_to_notification).transpose()
+    }
+
+    async fn delete_notification(
+        &self,
+        user_id: &str,
+        notification_id: &str,
+    ) -> Result<(), DomainError> {
+        let Ok(id) = Uuid::parse_str(notification_id) else {
+            return Ok(());
+        };
+        sqlx::query("DELETE FROM caldav.reminder_notifications WHERE user_id = $1 AND id = $2")
+            .bind(user_id)
+            .bind(id)
+            .execute(&*self.pool)
+            .await
+            .map_err(|e| {
+                DomainError::database_error(format!(
+                    "Failed to delete reminder notification: {}",
+                    e
+                ))
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use sqlx::{postgres::PgRow, types::Uuid, PgPool, Row};
+use std::sync::Arc;
+
+use crate::application::ports::scheduling_ports::ScheduleInboxStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::scheduling::{ItipMethod, ScheduleMessage};
+
+pub struct ScheduleInboxPgRepository {
+    pool: Arc<PgPool>,
+}
+
+impl ScheduleInboxPgRepository {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Construye un mensaje a partir de una fila de `caldav.schedule_inbox`
+    fn row_to_message(row: &PgRow) -> Result<ScheduleMessage, DomainError> {
+        let method: String = row.get("method");
+        Ok(ScheduleMessage {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            method: ItipMethod::parse(&method).ok_or_else(|| {
+                DomainError::database_error(format!("Unknown scheduling method: {}", method))
+            })?,
+            ical_uid: row.get("ical_uid"),
+            ical_data: row.get("ical_data"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+#[async_trait]
+impl ScheduleInboxStoragePort for ScheduleInboxPgRepository {
+    async fn save_message(&self, message: ScheduleMessage) -> Result<ScheduleMessage, DomainError> {
+        sqlx::query(
+            r#"
+            INSERT INTO caldav.schedule_inbox (id, user_id, method, ical_uid, ical_data, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(message.id)
+        .bind(&message.user_id)
+        .bind(message.method.as_str())
+        .bind(&message.ical_uid)
+        .bind(&message.ical_data)
+        .bind(message.created_at)
+        .execute(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to save scheduling message: {}", e))
+        })?;
+
+        Ok(message)
+    }
+
+    async fn list_messages(&self, user_id: &str) -> Result<Vec<ScheduleMessage>, DomainError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, user_id, method, ical_uid, ical_data, created_at
+            FROM caldav.schedule_inbox
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to list scheduling messages: {}", e))
+        })?;
+
+        rows.iter().map(Self::row_to_message).collect()
+    }
+
+    async fn find_message(
+        &self,
+        user_id: &str,
+        message_id: &str,
+    ) -> Result<Option<ScheduleMessage>, DomainError> {
+        // Los nombres que no son UUID no corresponden a ningún mensaje
+        let Ok(id) = Uuid::parse_str(message_id) else {
+            return Ok(None);
+        };
+        let row = sqlx::query(
+            r#"
+            SELECT id, user_id, method, ical_uid, ical_data, created_at
+            FROM caldav.schedule_inbox
+            WHERE user_id = $1 AND id = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(&*self.pool)
+        .await
+        .map_err(|e| {
+            DomainError::database_error(format!("Failed to get scheduling message: {}", e))
+        })?;
+
+        row.as_ref().map(Self::row_to_message).transpose()
+    }
+
+    async fn delete_message(&self, user_id: &str, message_id: &str) -> Result<(), DomainError> {
+        let Ok(id) = Uuid::parse_str(message_id) else {
+            return Ok(());
+        };
+        sqlx::query("DELETE FROM caldav.schedule_inbox WHERE user_id = $1 AND id = $2")
+            .bind(user_id)
+            .bind(id)
+            .execute(&*self.pool)
+            .await
+            .map_err(|e| {
+                DomainError::database_error(format!("Failed to delete scheduling message: {}", e))
+            })?;
+
+        Ok(())
+    }
+}
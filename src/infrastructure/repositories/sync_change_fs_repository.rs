@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+use crate::application::ports::sync_ports::ChangeLogStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::sync_change::SyncChange;
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Registro de cambios en `.sync_changes.jsonl` bajo la raíz de
+/// almacenamiento, con una entrada JSON por línea
+pub struct SyncChangeFsRepository {
+    log_path: PathBuf,
+}
+
+impl SyncChangeFsRepository {
+    /// Crea un nuevo registro de cambios bajo la raíz de almacenamiento
+    pub fn new(storage_root: impl AsRef<Path>) -> Self {
+        Self {
+            log_path: storage_root.as_ref().join(".sync_changes.jsonl"),
+        }
+    }
+
+    fn to_line(change: &SyncChange) -> Result<String, DomainError> {
+        let mut line = serde_json::to_string(change).map_err(|e| {
+            DomainError::internal_error("SyncChange", format!("Failed to serialize change: {}", e))
+        })?;
+        line.push('\n');
+        Ok(line)
+    }
+}
+
+#[async_trait]
+impl ChangeLogStoragePort for SyncChangeFsRepository {
+    async fn load_changes(&self) -> Result<Vec<SyncChange>, DomainError> {
+        let data = match fs::read_to_string(&self.log_path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(DomainError::internal_error(
+                    "SyncChange",
+                    format!("Failed to read the change log: {}", e),
+                ));
+            }
+        };
+
+        // Una línea incompleta solo puede venir de una escritura interrumpida
+        let mut changes = Vec::new();
+        for line in data.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str::<SyncChange>(line) {
+                Ok(change) => changes.push(change),
+                Err(e) => warn!("Skipping corrupt change log entry: {}", e),
+            }
+        }
+        Ok(changes)
+    }
+
+    async fn append_change(&self, change: &SyncChange) -> Result<(), DomainError> {
+        let line = Self::to_line(change)?;
+        let write = async {
+            if let Some(parent) = self.log_path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.log_path)
+                .await?;
+            file.write_all(line.as_bytes()).await?;
+            file.flush().await
+        };
+        write.await.map_err(|e| {
+            DomainError::internal_error(
+                "SyncChange",
+                format!("Failed to append to the change log: {}", e),
+            )
+        })
+    }
+
+    async fn replace_changes(&self, changes: &[SyncChange]) -> Result<(), DomainError> {
+        let mut data = String::new();
+        for change in changes {
+            data.push_str(&Self::to_line(change)?);
+        }
+        FileSystemUtils::atomic_write(&self.log_path, data.as_bytes())
+            .await
+            .map_err(|e| {
+                DomainError::internal_error(
+                    "SyncChange",
+                    format!("Failed to rewrite the change log: {}", e),
+                )
+            })
+    }
+}
@@ -0,0 +1,199 @@
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::application::ports::thumbnail_ports::ThumbnailStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::thumbnail::{Thumbnail, ThumbnailFormat, ThumbnailSize};
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Caché de miniaturas sobre el sistema de archivos.
+///
+/// Las miniaturas se guardan en `.thumbnails/<id>/<huella>/<tamaño>.<ext>`.
+/// La huella identifica el contenido original, así que una miniatura de un
+/// contenido anterior nunca se sirve aunque no se haya invalidado; al guardar
+/// una nueva huella se borran las anteriores.
+pub struct ThumbnailFsRepository {
+    thumbnails_dir: PathBuf,
+}
+
+impl ThumbnailFsRepository {
+    /// Crea una nueva caché de miniaturas bajo la raíz de almacenamiento
+    pub fn new(storage_root: impl AsRef<Path>) -> Self {
+        Self {
+            thumbnails_dir: storage_root.as_ref().join(".thumbnails"),
+        }
+    }
+
+    fn file_dir(&self, file_id: &str) -> Result<PathBuf, DomainError> {
+        Ok(self.thumbnails_dir.join(Self::checked_component(file_id)?))
+    }
+
+    fn thumbnail_path(
+        &self,
+        file_id: &str,
+        fingerprint: &str,
+        size: ThumbnailSize,
+        format: ThumbnailFormat,
+    ) -> Result<PathBuf, DomainError> {
+        Ok(self
+            .file_dir(file_id)?
+            .join(Self::checked_component(fingerprint)?)
+            .join(format!("{}.{}", size.name(), format.extension())))
+    }
+
+    fn checked_component(value: &str) -> Result<&str, DomainError> {
+        // Los identificadores y huellas se usan como nombres de directorio
+        if value.is_empty() || !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(DomainError::validation_error(format!(
+                "Invalid thumbnail key: {}",
+                value
+            )));
+        }
+        Ok(value)
+    }
+
+    fn io_error(file_id: &str, action: &str, e: impl std::fmt::Display) -> DomainError {
+        DomainError::internal_error(
+            "Thumbnail",
+            format!("Failed to {} thumbnails of {}: {}", action, file_id, e),
+        )
+    }
+}
+
+#[async_trait]
+impl ThumbnailStoragePort for ThumbnailFsRepository {
+    async fn get_thumbnail(
+        &self,
+        file_id: &str,
+        fingerprint: &str,
+        size: ThumbnailSize,
+        format: ThumbnailFormat,
+    ) -> Result<Option<Vec<u8>>, DomainError> {
+        let path = self.thumbnail_path(file_id, fingerprint, size, format)?;
+        match fs::read(&path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Self::io_error(file_id, "read", e)),
+        }
+    }
+
+    async fn store_thumbnail(&self, thumbnail: &Thumbnail) -> Result<(), DomainError> {
+        let path = self.thumbnail_path(
+            &thumbnail.file_id,
+            &thumbnail.fingerprint,
+            thumbnail.size,
+            thumbnail.format,
+        )?;
+        let file_dir = self.file_dir(&thumbnail.file_id)?;
+
+        // Descartar las miniaturas de contenidos anteriores
+        if let Ok(mut entries) = fs::read_dir(&file_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if entry.file_name() != thumbnail.fingerprint.as_str() {
+                    if let Err(e) = fs::remove_dir_all(entry.path()).await {
+                        tracing::warn!(
+                            "Could not remove stale thumbnails of {}: {}",
+                            thumbnail.file_id,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| Self::io_error(&thumbnail.file_id, "store", e))?;
+        }
+        FileSystemUtils::atomic_write(&path, &thumbnail.content)
+            .await
+            .map_err(|e| Self::io_error(&thumbnail.file_id, "store", e))
+    }
+
+    async fn delete_thumbnails(&self, file_id: &str) -> Result<(), DomainError> {
+        match fs::remove_dir_all(self.file_dir(file_id)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Self::io_error(file_id, "delete", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn thumbnail(fingerprint: &str, content: &[u8]) -> Thumbnail {
+        Thumbnail {
+            file_id: "file-1".to_string(),
+            size: ThumbnailSize::Small,
+            format: ThumbnailFormat::Webp,
+            fingerprint: fingerprint.to_string(),
+            content: content.to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_replaces_previous_content() {
+        let dir = tempdir().unwrap();
+        let repo = ThumbnailFsRepository::new(dir.path());
+
+        repo.store_thumbnail(&thumbnail("a-1", b"old"))
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_thumbnail("file-1", "a-1", ThumbnailSize::Small, ThumbnailFormat::Webp)
+                .await
+                .unwrap(),
+            Some(b"old".to_vec())
+        );
+        assert_eq!(
+            repo.get_thumbnail("file-1", "a-1", ThumbnailSize::Large, ThumbnailFormat::Webp)
+                .await
+                .unwrap(),
+            None
+        );
+
+        repo.store_thumbnail(&thumbnail("b-2", b"new"))
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.get_thumbnail("file-1", "a-1", ThumbnailSize::Small, ThumbnailFormat::Webp)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            repo.get_thumbnail("file-1", "b-2", ThumbnailSize::Small, ThumbnailFormat::Webp)
+                .await
+                .unwrap(),
+            Some(b"new".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_reject_invalid_ids() {
+        let dir = tempdir().unwrap();
+        let repo = ThumbnailFsRepository::new(dir.path());
+
+        repo.store_thumbnail(&thumbnail("a-1", b"data"))
+            .await
+            .unwrap();
+        repo.delete_thumbnails("file-1").await.unwrap();
+        repo.delete_thumbnails("file-1").await.unwrap();
+        assert_eq!(
+            repo.get_thumbnail("file-1", "a-1", ThumbnailSize::Small, ThumbnailFormat::Webp)
+                .await
+                .unwrap(),
+            None
+        );
+
+        assert!(repo
+            .get_thumbnail("../x", "a-1", ThumbnailSize::Small, ThumbnailFormat::Jpeg)
+            .await
+            .is_err());
+    }
+}
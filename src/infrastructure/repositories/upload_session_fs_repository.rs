@@ -0,0 +1,348 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use openssl::hash::{Hasher, MessageDigest};
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+
+use crate::application::ports::upload_ports::{
+    ChunkWrite, UploadChunkStream, UploadSessionStoragePort,
+};
+use crate::common::errors::DomainError;
+use crate::domain::entities::upload_session::{ChecksumAlgorithm, UploadSession};
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Nombre del fichero con el estado de cada subida
+const INFO_FILE_NAME: &str = "info.json";
+/// Nombre del fichero con el contenido recibido
+const DATA_FILE_NAME: &str = "data";
+
+/// Almacén de subidas reanudables sobre el sistema de archivos.
+///
+/// Cada subida tiene un directorio `.uploads/<id>` con un `info.json` y el
+/// contenido recibido en `data`. El desplazamiento confirmado es el de
+/// `info.json`; los bytes de `data` que queden detrás se descartan en la
+/// siguiente escritura, así que sobrevive a reinicios a mitad de fragmento.
+pub struct UploadSessionFsRepository {
+    uploads_dir: PathBuf,
+}
+
+impl UploadSessionFsRepository {
+    /// Crea un nuevo almacén de subidas bajo la raíz de almacenamiento
+    pub fn new(storage_root: impl AsRef<Path>) -> Self {
+        Self {
+            uploads_dir: storage_root.as_ref().join(".uploads"),
+        }
+    }
+
+    fn session_dir(&self, id: &str) -> Result<PathBuf, DomainError> {
+        // Los identificadores se usan como nombres de directorio
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(DomainError::not_found("Upload", id));
+        }
+        Ok(self.uploads_dir.join(id))
+    }
+
+    fn data_path(&self, id: &str) -> Result<PathBuf, DomainError> {
+        Ok(self.session_dir(id)?.join(DATA_FILE_NAME))
+    }
+
+    fn message_digest(algorithm: ChecksumAlgorithm) -> MessageDigest {
+        match algorithm {
+            ChecksumAlgorithm::Sha1 => MessageDigest::sha1(),
+            ChecksumAlgorithm::Sha256 => MessageDigest::sha256(),
+            ChecksumAlgorithm::Md5 => MessageDigest::md5(),
+        }
+    }
+
+    fn io_error(id: &str, action: &str, e: impl std::fmt::Display) -> DomainError {
+        DomainError::internal_error(
+            "Upload",
+            format!("Failed to {} upload {}: {}", action, id, e),
+        )
+    }
+}
+
+#[async_trait]
+impl UploadSessionStoragePort for UploadSessionFsRepository {
+    async fn create_session(&self, session: &UploadSession) -> Result<(), DomainError> {
+        let dir = self.session_dir(&session.id)?;
+        fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| Self::io_error(&session.id, "create", e))?;
+        fs::File::create(dir.join(DATA_FILE_NAME))
+            .await
+            .map_err(|e| Self::io_error(&session.id, "create", e))?;
+        self.save_session(session).await
+    }
+
+    async fn get_session(&self, id: &str) -> Result<UploadSession, DomainError> {
+        let info_path = self.session_dir(id)?.join(INFO_FILE_NAME);
+        match fs::read(&info_path).await {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                DomainError::internal_error(
+                    "Upload",
+                    format!("Corrupt upload info for {}: {}", id, e),
+                )
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(DomainError::not_found("Upload", id))
+            }
+            Err(e) => Err(Self::io_error(id, "read", e)),
+        }
+    }
+
+    async fn save_session(&self, session: &UploadSession) -> Result<(), DomainError> {
+        let info_path = self.session_dir(&session.id)?.join(INFO_FILE_NAME);
+        let data = serde_json::to_vec_pretty(session).map_err(|e| {
+            DomainError::internal_error("Upload", format!("Failed to serialize upload: {}", e))
+        })?;
+        FileSystemUtils::atomic_write(&info_path, &data)
+            .await
+            .map_err(|e| Self::io_error(&session.id, "save", e))
+    }
+
+    async fn list_sessions(&self) -> Result<Vec<UploadSession>, DomainError> {
+        let mut entries = match fs::read_dir(&self.uploads_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Self::io_error("*", "list", e)),
+        };
+
+        let mut sessions = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| Self::io_error("*", "list", e))?
+        {
+            let id = entry.file_name().to_string_lossy().to_string();
+            match self.get_session(&id).await {
+                Ok(session) => sessions.push(session),
+                Err(e) => tracing::warn!("Skipping unreadable upload {}: {}", id, e),
+            }
+        }
+        Ok(sessions)
+    }
+
+    async fn append_chunk(
+        &self,
+        id: &str,
+        offset: u64,
+        mut chunk: UploadChunkStream,
+        limit: u64,
+        checksum: Option<ChecksumAlgorithm>,
+    ) -> Result<ChunkWrite, DomainError> {
+        let data_path = self.data_path(id)?;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(&data_path)
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => DomainError::not_found("Upload", id),
+                _ => Self::io_error(id, "open", e),
+            })?;
+
+        // Descartar lo escrito tras el último desplazamiento confirmado
+        file.set_len(offset)
+            .await
+            .map_err(|e| Self::io_error(id, "truncate", e))?;
+        file.seek(std::io::SeekFrom::Start(offset))
+            .await
+            .map_err(|e| Self::io_error(id, "seek", e))?;
+
+        let mut hasher = checksum
+            .map(|algorithm| Hasher::new(Self::message_digest(algorithm)))
+            .transpose()
+            .map_err(|e| Self::io_error(id, "hash", e))?;
+
+        let mut written = 0u64;
+        let mut complete = true;
+        while let Some(next) = chunk.next().await {
+            let bytes = match next {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::debug!("Upload {} interrupted after {} bytes: {}", id, written, e);
+                    complete = false;
+                    break;
+                }
+            };
+
+            let remaining = limit - written;
+            if bytes.len() as u64 > remaining {
+                return Err(DomainError::validation_error(format!(
+                    "Chunk exceeds the declared upload length of upload {}",
+                    id
+                )));
+            }
+
+            file.write_all(&bytes)
+                .await
+                .map_err(|e| Self::io_error(id, "write", e))?;
+            if let Some(hasher) = hasher.as_mut() {
+                hasher
+                    .update(&bytes)
+                    .map_err(|e| Self::io_error(id, "hash", e))?;
+            }
+            written += bytes.len() as u64;
+        }
+
+        file.sync_all()
+            .await
+            .map_err(|e| Self::io_error(id, "sync", e))?;
+
+        let digest = hasher
+            .map(|mut hasher| hasher.finish().map(|digest| digest.to_vec()))
+            .transpose()
+            .map_err(|e| Self::io_error(id, "hash", e))?;
+
+        Ok(ChunkWrite {
+            written,
+            digest,
+            complete,
+        })
+    }
+
+    async fn truncate(&self, id: &str, length: u64) -> Result<(), DomainError> {
+        let file = OpenOptions::new()
+            .write(true)
+            .open(self.data_path(id)?)
+            .await
+            .map_err(|e| Self::io_error(id, "open", e))?;
+        file.set_len(length)
+            .await
+            .map_err(|e| Self::io_error(id, "truncate", e))
+    }
+
+    async fn read_content_stream(&self, id: &str) -> Result<UploadChunkStream, DomainError> {
+        let file = fs::File::open(self.data_path(id)?)
+            .await
+            .map_err(|e| Self::io_error(id, "read", e))?;
+        Ok(Box::pin(ReaderStream::new(file)))
+    }
+
+    async fn delete_session(&self, id: &str) -> Result<(), DomainError> {
+        match fs::remove_dir_all(self.session_dir(id)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Self::io_error(id, "delete", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn sample_session(length: u64) -> UploadSession {
+        UploadSession::new(
+            length,
+            "report.pdf".to_string(),
+            "application/pdf".to_string(),
+            None,
+            HashMap::new(),
+            1_000,
+            2_000,
+        )
+        .with_owner("user-1")
+    }
+
+    async fn read_content(repo: &UploadSessionFsRepository, id: &str) -> Vec<u8> {
+        let mut stream = repo.read_content_stream(id).await.unwrap();
+        let mut content = Vec::new();
+        while let Some(part) = stream.next().await {
+            content.extend_from_slice(&part.unwrap());
+        }
+        content
+    }
+
+    fn chunk(parts: Vec<std::io::Result<&'static [u8]>>) -> UploadChunkStream {
+        Box::pin(futures::stream::iter(
+            parts.into_iter().map(|part| part.map(Bytes::from_static)),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_append_and_resume_after_restart() {
+        let dir = tempdir().unwrap();
+        let repo = UploadSessionFsRepository::new(dir.path());
+        let mut session = sample_session(11);
+        repo.create_session(&session).await.unwrap();
+
+        let first = repo
+            .append_chunk(&session.id, 0, chunk(vec![Ok(b"hello")]), 11, None)
+            .await
+            .unwrap();
+        assert_eq!(first.written, 5);
+        session.offset = 5;
+        repo.save_session(&session).await.unwrap();
+
+        // A new instance sees the persisted state
+        let repo = UploadSessionFsRepository::new(dir.path());
+        let stored = repo.get_session(&session.id).await.unwrap();
+        assert_eq!(stored.offset, 5);
+        assert_eq!(repo.list_sessions().await.unwrap().len(), 1);
+
+        repo.append_chunk(&session.id, 5, chunk(vec![Ok(b" world")]), 6, None)
+            .await
+            .unwrap();
+        assert_eq!(read_content(&repo, &session.id).await, b"hello world");
+
+        repo.delete_session(&session.id).await.unwrap();
+        assert!(repo.get_session(&session.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_chunk_keeps_received_bytes() {
+        let dir = tempdir().unwrap();
+        let repo = UploadSessionFsRepository::new(dir.path());
+        let session = sample_session(10);
+        repo.create_session(&session).await.unwrap();
+
+        let result = repo
+            .append_chunk(
+                &session.id,
+                0,
+                chunk(vec![
+                    Ok(b"abc"),
+                    Err(std::io::Error::new(
+                        std::io::ErrorKind::ConnectionReset,
+                        "reset",
+                    )),
+                ]),
+                10,
+                Some(ChecksumAlgorithm::Sha1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.written, 3);
+        assert!(!result.complete);
+        assert_eq!(
+            hex::encode(result.digest.unwrap()),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+
+        // Writing again from offset 1 discards whatever followed it
+        repo.append_chunk(&session.id, 1, chunk(vec![Ok(b"x")]), 9, None)
+            .await
+            .unwrap();
+        assert_eq!(read_content(&repo, &session.id).await, b"ax");
+    }
+
+    #[tokio::test]
+    async fn test_chunk_longer_than_limit_is_rejected() {
+        let dir = tempdir().unwrap();
+        let repo = UploadSessionFsRepository::new(dir.path());
+        let session = sample_session(2);
+        repo.create_session(&session).await.unwrap();
+
+        assert!(repo
+            .append_chunk(&session.id, 0, chunk(vec![Ok(b"abc")]), 2, None)
+            .await
+            .is_err());
+        assert!(repo.get_session("../etc").await.is_err());
+    }
+}
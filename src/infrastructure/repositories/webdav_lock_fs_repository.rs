@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::application::ports::webdav_lock_ports::WebDavLockStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::webdav_lock::WebDavLock;
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Almacén de bloqueos WebDAV en un único `.webdav_locks.json` bajo la raíz
+/// de almacenamiento, reescrito de forma atómica en cada cambio
+pub struct WebDavLockFsRepository {
+    locks_path: PathBuf,
+}
+
+impl WebDavLockFsRepository {
+    /// Crea un nuevo almacén de bloqueos bajo la raíz de almacenamiento
+    pub fn new(storage_root: impl AsRef<Path>) -> Self {
+        Self {
+            locks_path: storage_root.as_ref().join(".webdav_locks.json"),
+        }
+    }
+}
+
+#[async_trait]
+impl WebDavLockStoragePort for WebDavLockFsRepository {
+    async fn load_locks(&self) -> Result<Vec<WebDavLock>, DomainError> {
+        match fs::read(&self.locks_path).await {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                DomainError::internal_error("Lock", format!("Corrupt WebDAV lock file: {}", e))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(DomainError::internal_error(
+                "Lock",
+                format!("Failed to read WebDAV locks: {}", e),
+            )),
+        }
+    }
+
+    async fn save_locks(&self, locks: &[WebDavLock]) -> Result<(), DomainError> {
+        let data = serde_json::to_vec_pretty(locks).map_err(|e| {
+            DomainError::internal_error("Lock", format!("Failed to serialize locks: {}", e))
+        })?;
+        FileSystemUtils::atomic_write(&self.locks_path, &data)
+            .await
+            .map_err(|e| {
+                DomainError::internal_error("Lock", format!("Failed to save WebDAV locks: {}", e))
+            })
+    }
+}
@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::application::ports::webdav_property_ports::DeadPropertyStoragePort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::webdav_property::DeadProperty;
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Almacén de propiedades muertas WebDAV con un JSON por recurso en
+/// `.webdav_props/` bajo la raíz de almacenamiento
+pub struct WebDavPropertyFsRepository {
+    properties_dir: PathBuf,
+}
+
+impl WebDavPropertyFsRepository {
+    /// Crea un nuevo almacén de propiedades bajo la raíz de almacenamiento
+    pub fn new(storage_root: impl AsRef<Path>) -> Self {
+        Self {
+            properties_dir: storage_root.as_ref().join(".webdav_props"),
+        }
+    }
+
+    fn properties_path(&self, resource_id: &str) -> Result<PathBuf, DomainError> {
+        // Los IDs forman parte del nombre del archivo
+        if resource_id.is_empty()
+            || !resource_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(DomainError::validation_error(format!(
+                "Invalid resource ID for WebDAV properties: {}",
+                resource_id
+            )));
+        }
+        Ok(self.properties_dir.join(format!("{}.json", resource_id)))
+    }
+}
+
+#[async_trait]
+impl DeadPropertyStoragePort for WebDavPropertyFsRepository {
+    async fn load_properties(&self, resource_id: &str) -> Result<Vec<DeadProperty>, DomainError> {
+        let path = self.properties_path(resource_id)?;
+        match fs::read(&path).await {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                DomainError::internal_error(
+                    "Property",
+                    format!("Corrupt WebDAV properties for {}: {}", resource_id, e),
+                )
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(DomainError::internal_error(
+                "Property",
+                format!("Failed to read WebDAV properties: {}", e),
+            )),
+        }
+    }
+
+    async fn save_properties(
+        &self,
+        resource_id: &str,
+        properties: &[DeadProperty],
+    ) -> Result<(), DomainError> {
+        let path = self.properties_path(resource_id)?;
+
+        if properties.is_empty() {
+            return match fs::remove_file(&path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(DomainError::internal_error(
+                        "Property",
+                        format!("Failed to delete WebDAV properties: {}", e),
+                    ))
+                }
+                _ => Ok(()),
+            };
+        }
+
+        let data = serde_json::to_vec_pretty(properties).map_err(|e| {
+            DomainError::internal_error(
+                "Property",
+                format!("Failed to serialize properties: {}", e),
+            )
+        })?;
+        FileSystemUtils::atomic_write(&path, &data)
+            .await
+            .map_err(|e| {
+                DomainError::internal_error(
+                    "Property",
+                    format!("Failed to save WebDAV properties: {}", e),
+                )
+            })
+    }
+}
@@ -0,0 +1,688 @@
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::pin::Pin;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::application::ports::storage_ports::{
+    slice_byte_stream, BlockGarbageCollectorPort, ContentStoragePort, StoredContent,
+};
+use crate::common::errors::DomainError;
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Nombre del fichero de índice de referencias dentro del almacén
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// Diario con los cambios del índice posteriores a `index.json`
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+
+/// Registros del diario a partir de los que se reescribe `index.json`,
+/// si además superan el número de bloques del índice
+const JOURNAL_COMPACTION_MIN: usize = 10_000;
+
+/// Nombre de este backend en las referencias de contenido
+const BACKEND_NAME: &str = "dedup";
+
+/// Referencia a un bloque dentro del manifiesto de un archivo
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkRef {
+    /// Hash SHA-256 del contenido del bloque (hex)
+    pub hash: String,
+    /// Tamaño del bloque en bytes
+    pub size: u64,
+}
+
+/// Lista ordenada de bloques que componen el contenido de un archivo
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    pub chunks: Vec<ChunkRef>,
+    pub size: u64,
+}
+
+/// Entrada del índice: tamaño del bloque y número de manifiestos que lo referencian
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChunkEntry {
+    size: u64,
+    refs: u64,
+}
+
+/// Índice persistente de bloques
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChunkIndex {
+    chunks: HashMap<String, ChunkEntry>,
+}
+
+/// Cambio del índice guardado en el diario: el estado nuevo de un bloque,
+/// o `None` si se eliminó. Repetir un registro deja el mismo estado.
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalRecord {
+    hash: String,
+    entry: Option<ChunkEntry>,
+}
+
+/// Índice en memoria junto con el diario donde se anotan sus cambios
+struct IndexState {
+    index: ChunkIndex,
+    journal: fs::File,
+    journal_records: usize,
+}
+
+/// Resultado de una pasada de recolección de basura
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GarbageCollectionStats {
+    pub chunks_removed: usize,
+    pub bytes_freed: u64,
+}
+
+/// Almacén de bloques direccionado por contenido con conteo de referencias.
+///
+/// El contenido se divide en bloques de tamaño fijo; cada bloque se guarda una
+/// sola vez bajo `objects/<aa>/<hash>` y el índice lleva la cuenta de cuántos
+/// manifiestos lo usan. Los bloques que quedan sin referencias no se borran en
+/// el momento, sino en la siguiente llamada a `collect_garbage`.
+///
+/// Los bloques se escriben fuera del cerrojo del índice, que solo protege el
+/// conteo de referencias. Cada cambio se añade a un diario y `index.json` solo
+/// se reescribe de vez en cuando para vaciarlo.
+pub struct ChunkStore {
+    root: PathBuf,
+    chunk_size: usize,
+    state: Mutex<IndexState>,
+}
+
+impl ChunkStore {
+    /// Abre (o crea) un almacén de bloques en el directorio indicado
+    pub async fn new(root: PathBuf, chunk_size: usize) -> Result<Self, DomainError> {
+        FileSystemUtils::create_dir_with_sync(root.join("objects"))
+            .await
+            .map_err(Self::io_error)?;
+
+        let index_path = root.join(INDEX_FILE_NAME);
+        let mut index = if index_path.exists() {
+            let raw = fs::read(&index_path).await.map_err(Self::io_error)?;
+            serde_json::from_slice(&raw).map_err(|e| {
+                DomainError::internal_error(
+                    "ChunkStore",
+                    format!("Corrupted chunk index {}: {}", index_path.display(), e),
+                )
+            })?
+        } else {
+            ChunkIndex::default()
+        };
+
+        // Aplicar los cambios anotados después de la última reescritura del índice.
+        // Solo cuentan las líneas completas: tras una caída la última puede
+        // quedar a medias y se descarta antes de seguir escribiendo detrás
+        let journal_path = root.join(JOURNAL_FILE_NAME);
+        let mut journal_records = 0;
+        let mut complete_len = 0;
+        if journal_path.exists() {
+            let raw = fs::read(&journal_path).await.map_err(Self::io_error)?;
+            complete_len = raw
+                .iter()
+                .rposition(|b| *b == b'\n')
+                .map_or(0, |pos| pos + 1);
+            for line in raw[..complete_len]
+                .split(|b| *b == b'\n')
+                .filter(|l| !l.is_empty())
+            {
+                match serde_json::from_slice::<JournalRecord>(line) {
+                    Ok(record) => {
+                        Self::apply(&mut index, record);
+                        journal_records += 1;
+                    }
+                    Err(e) => tracing::warn!("Ignoring corrupted chunk journal record: {}", e),
+                }
+            }
+            if complete_len < raw.len() {
+                tracing::warn!(
+                    "Discarding {} bytes of a truncated chunk journal record",
+                    raw.len() - complete_len
+                );
+            }
+        }
+        let journal = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&journal_path)
+            .await
+            .map_err(Self::io_error)?;
+        journal
+            .set_len(complete_len as u64)
+            .await
+            .map_err(Self::io_error)?;
+
+        tracing::info!(
+            "Chunk store opened at {} with {} chunks",
+            root.display(),
+            index.chunks.len()
+        );
+
+        Ok(Self {
+            root,
+            chunk_size: chunk_size.max(4096),
+            state: Mutex::new(IndexState {
+                index,
+                journal,
+                journal_records,
+            }),
+        })
+    }
+
+    fn io_error(e: std::io::Error) -> DomainError {
+        DomainError::internal_error("ChunkStore", e.to_string())
+    }
+
+    fn apply(index: &mut ChunkIndex, record: JournalRecord) {
+        match record.entry {
+            Some(entry) => index.chunks.insert(record.hash, entry),
+            None => index.chunks.remove(&record.hash),
+        };
+    }
+
+    /// Ruta física de un bloque a partir de su hash
+    fn chunk_path(&self, hash: &str) -> PathBuf {
+        self.root.join("objects").join(&hash[..2]).join(hash)
+    }
+
+    /// Calcula el hash SHA-256 de un bloque
+    fn hash_chunk(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    /// Anota en el diario el estado actual de los bloques indicados y, si el
+    /// diario ha crecido demasiado, reescribe el índice completo
+    async fn record(&self, state: &mut IndexState, hashes: &[&str]) -> Result<(), DomainError> {
+        let mut raw = Vec::new();
+        for hash in hashes {
+            let record = JournalRecord {
+                hash: hash.to_string(),
+                entry: state.index.chunks.get(*hash).cloned(),
+            };
+            serde_json::to_writer(&mut raw, &record)
+                .map_err(|e| DomainError::internal_error("ChunkStore", e.to_string()))?;
+            raw.push(b'\n');
+        }
+        state
+            .journal
+            .write_all(&raw)
+            .await
+            .map_err(Self::io_error)?;
+        // Perder un incremento haría que la recolección borrase bloques en uso
+        state.journal.sync_data().await.map_err(Self::io_error)?;
+        state.journal_records += hashes.len();
+
+        if state.journal_records > JOURNAL_COMPACTION_MIN.max(state.index.chunks.len()) {
+            self.compact(state).await?;
+        }
+        Ok(())
+    }
+
+    /// Reescribe el índice de forma atómica y vacía el diario. Si se
+    /// interrumpe entre ambos pasos, volver a aplicar el diario no cambia nada
+    async fn compact(&self, state: &mut IndexState) -> Result<(), DomainError> {
+        let raw = serde_json::to_vec(&state.index)
+            .map_err(|e| DomainError::internal_error("ChunkStore", e.to_string()))?;
+        FileSystemUtils::atomic_write(self.root.join(INDEX_FILE_NAME), &raw)
+            .await
+            .map_err(Self::io_error)?;
+        state.journal.set_len(0).await.map_err(Self::io_error)?;
+        state.journal_records = 0;
+        Ok(())
+    }
+
+    /// Escribe un bloque en su ruta
+    async fn write_chunk(&self, hash: &str, data: &[u8]) -> Result<(), DomainError> {
+        let path = self.chunk_path(hash);
+        if let Some(parent) = path.parent() {
+            FileSystemUtils::create_dir_with_sync(parent)
+                .await
+                .map_err(Self::io_error)?;
+        }
+        FileSystemUtils::atomic_write(&path, data)
+            .await
+            .map_err(Self::io_error)
+    }
+
+    /// Añade una referencia a un bloque, escribiéndolo si todavía no existe
+    async fn put_chunk(&self, data: &[u8]) -> Result<ChunkRef, DomainError> {
+        let hash = Self::hash_chunk(data);
+        let size = data.len() as u64;
+
+        // Un bloque del índice siempre está en disco: la recolección quita
+        // ambos a la vez
+        {
+            let mut state = self.state.lock().await;
+            if let Some(entry) = state.index.chunks.get_mut(&hash) {
+                entry.refs += 1;
+                self.record(&mut state, &[&hash]).await?;
+                return Ok(ChunkRef { hash, size });
+            }
+        }
+
+        self.write_chunk(&hash, data).await?;
+
+        let mut state = self.state.lock().await;
+        match state.index.chunks.get_mut(&hash) {
+            // Otra escritura del mismo bloque llegó antes al índice
+            Some(entry) => entry.refs += 1,
+            None => {
+                // Una recolección pudo borrar el bloque que otra escritura
+                // registró y liberó mientras se escribía este
+                if !fs::try_exists(self.chunk_path(&hash))
+                    .await
+                    .map_err(Self::io_error)?
+                {
+                    self.write_chunk(&hash, data).await?;
+                }
+                state
+                    .index
+                    .chunks
+                    .insert(hash.clone(), ChunkEntry { size, refs: 1 });
+            }
+        }
+        self.record(&mut state, &[&hash]).await?;
+
+        Ok(ChunkRef { hash, size })
+    }
+
+    /// Almacena un stream sin cargarlo entero en memoria
+    pub async fn put_stream<S>(&self, mut stream: S) -> Result<ChunkManifest, DomainError>
+    where
+        S: Stream<Item = Result<Bytes, std::io::Error>> + Unpin + Send,
+    {
+        let mut manifest = ChunkManifest::default();
+        let mut buffer = BytesMut::with_capacity(self.chunk_size);
+
+        let result = async {
+            while let Some(item) = stream.next().await {
+                let data =
+                    item.map_err(|e| DomainError::internal_error("ChunkStore", e.to_string()))?;
+                buffer.extend_from_slice(&data);
+
+                while buffer.len() >= self.chunk_size {
+                    let piece = buffer.split_to(self.chunk_size);
+                    manifest.chunks.push(self.put_chunk(&piece).await?);
+                    manifest.size += piece.len() as u64;
+                }
+            }
+
+            if !buffer.is_empty() {
+                manifest.chunks.push(self.put_chunk(&buffer).await?);
+                manifest.size += buffer.len() as u64;
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            // Deshacer las referencias añadidas antes del fallo
+            self.release(&manifest).await?;
+            return Err(e);
+        }
+
+        Ok(manifest)
+    }
+
+    /// Añade una referencia a todos los bloques de un manifiesto (copia sin duplicar datos)
+    pub async fn retain(&self, manifest: &ChunkManifest) -> Result<(), DomainError> {
+        let mut state = self.state.lock().await;
+        if let Some(missing) = manifest
+            .chunks
+            .iter()
+            .find(|chunk| !state.index.chunks.contains_key(&chunk.hash))
+        {
+            return Err(DomainError::not_found("Chunk", missing.hash.clone()));
+        }
+
+        for chunk in &manifest.chunks {
+            if let Some(entry) = state.index.chunks.get_mut(&chunk.hash) {
+                entry.refs += 1;
+            }
+        }
+        let hashes: Vec<&str> = manifest.chunks.iter().map(|c| c.hash.as_str()).collect();
+        self.record(&mut state, &hashes).await
+    }
+
+    /// Quita una referencia a todos los bloques de un manifiesto
+    pub async fn release(&self, manifest: &ChunkManifest) -> Result<(), DomainError> {
+        let mut state = self.state.lock().await;
+        for chunk in &manifest.chunks {
+            if let Some(entry) = state.index.chunks.get_mut(&chunk.hash) {
+                entry.refs = entry.refs.saturating_sub(1);
+            }
+        }
+        let hashes: Vec<&str> = manifest.chunks.iter().map(|c| c.hash.as_str()).collect();
+        self.record(&mut state, &hashes).await
+    }
+
+    /// Reconstruye el contenido de un manifiesto como stream, verificando cada bloque
+    pub fn read_stream(
+        &self,
+        manifest: &ChunkManifest,
+    ) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static {
+        let paths: Vec<(String, PathBuf)> = manifest
+            .chunks
+            .iter()
+            .map(|c| (c.hash.clone(), self.chunk_path(&c.hash)))
+            .collect();
+
+        async_stream::try_stream! {
+            for (hash, path) in paths {
+                let data = fs::read(&path).await?;
+                if Self::hash_chunk(&data) != hash {
+                    Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Chunk {} failed integrity check", hash),
+                    ))?;
+                }
+                yield Bytes::from(data);
+            }
+        }
+    }
+
+    /// Lee el rango `[start, end)` del contenido saltando los bloques que no lo contienen
+    pub fn read_range_stream(
+        &self,
+        manifest: &ChunkManifest,
+        start: u64,
+        end: u64,
+    ) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static {
+        let mut offset = 0u64;
+        let mut base = None;
+        let mut chunks = Vec::new();
+        for chunk in &manifest.chunks {
+            let chunk_end = offset + chunk.size;
+            if chunk_end > start && offset < end {
+                base.get_or_insert(offset);
+                chunks.push(chunk.clone());
+            }
+            offset = chunk_end;
+        }
+
+        let base = base.unwrap_or(0);
+        let partial = ChunkManifest {
+            size: chunks.iter().map(|c| c.size).sum(),
+            chunks,
+        };
+        slice_byte_stream(
+            Box::pin(self.read_stream(&partial)),
+            start.saturating_sub(base),
+            end.saturating_sub(base),
+        )
+    }
+
+    /// Elimina del disco los bloques que ya no tienen referencias
+    pub async fn collect_garbage(&self) -> Result<GarbageCollectionStats, DomainError> {
+        let orphans: Vec<String> = {
+            let state = self.state.lock().await;
+            state
+                .index
+                .chunks
+                .iter()
+                .filter(|(_, entry)| entry.refs == 0)
+                .map(|(hash, _)| hash.clone())
+                .collect()
+        };
+
+        let mut stats = GarbageCollectionStats::default();
+        for hash in orphans {
+            // El bloque y su entrada se quitan juntos, y solo si sigue sin
+            // referencias: entre medias otra escritura puede haberlo reutilizado
+            let mut state = self.state.lock().await;
+            let size = match state.index.chunks.get(&hash) {
+                Some(entry) if entry.refs == 0 => entry.size,
+                _ => continue,
+            };
+            match fs::remove_file(self.chunk_path(&hash)).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    tracing::warn!("Could not remove orphan chunk {}: {}", hash, e);
+                    continue;
+                }
+            }
+            state.index.chunks.remove(&hash);
+            self.record(&mut state, &[&hash]).await?;
+            stats.chunks_removed += 1;
+            stats.bytes_freed += size;
+        }
+
+        if stats.chunks_removed > 0 {
+            tracing::info!(
+                "Chunk GC removed {} chunks ({} bytes)",
+                stats.chunks_removed,
+                stats.bytes_freed
+            );
+        }
+
+        Ok(stats)
+    }
+
+    /// Manifiesto de un contenido guardado en este almacén
+    fn manifest_of(content: &StoredContent) -> Result<ChunkManifest, DomainError> {
+        if content.backend != BACKEND_NAME {
+            return Err(DomainError::internal_error(
+                "ChunkStore",
+                format!("Content stored in the '{}' backend", content.backend),
+            ));
+        }
+        let manifest: ChunkManifest =
+            serde_json::from_value(content.locator.clone()).map_err(|e| {
+                DomainError::internal_error("ChunkStore", format!("Invalid chunk manifest: {}", e))
+            })?;
+        // Los hashes forman la ruta de cada bloque
+        if let Some(chunk) = manifest.chunks.iter().find(|chunk| {
+            chunk.hash.len() != 64 || !chunk.hash.bytes().all(|b| b.is_ascii_hexdigit())
+        }) {
+            return Err(DomainError::internal_error(
+                "ChunkStore",
+                format!("Invalid chunk hash in manifest: {}", chunk.hash),
+            ));
+        }
+        Ok(manifest)
+    }
+}
+
+#[async_trait]
+impl ContentStoragePort for ChunkStore {
+    fn backend_name(&self) -> &'static str {
+        BACKEND_NAME
+    }
+
+    async fn put_content(
+        &self,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<StoredContent, DomainError> {
+        let manifest = self.put_stream(stream).await?;
+        Ok(StoredContent {
+            backend: BACKEND_NAME.to_string(),
+            size: manifest.size,
+            locator: serde_json::to_value(&manifest)
+                .map_err(|e| DomainError::internal_error("ChunkStore", e.to_string()))?,
+        })
+    }
+
+    async fn get_content_stream(
+        &self,
+        content: &StoredContent,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
+        let manifest = Self::manifest_of(content)?;
+        Ok(Box::new(Box::pin(self.read_stream(&manifest))))
+    }
+
+    async fn get_content_range_stream(
+        &self,
+        content: &StoredContent,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
+        let manifest = Self::manifest_of(content)?;
+        Ok(Box::new(Box::pin(
+            self.read_range_stream(&manifest, start, end),
+        )))
+    }
+
+    async fn copy_content(&self, content: &StoredContent) -> Result<StoredContent, DomainError> {
+        // La copia comparte los bloques: solo ganan una referencia más
+        self.retain(&Self::manifest_of(content)?).await?;
+        Ok(content.clone())
+    }
+
+    async fn release_content(&self, content: &StoredContent) -> Result<(), DomainError> {
+        // Los bloques sin referencias se eliminan en la siguiente recolección
+        self.release(&Self::manifest_of(content)?).await
+    }
+}
+
+#[async_trait]
+impl BlockGarbageCollectorPort for ChunkStore {
+    async fn collect_garbage(&self) -> Result<(usize, u64), DomainError> {
+        let stats = ChunkStore::collect_garbage(self).await?;
+        Ok((stats.chunks_removed, stats.bytes_freed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    async fn put_bytes(store: &ChunkStore, content: &[u8]) -> ChunkManifest {
+        let stream = futures::stream::iter(vec![Ok(Bytes::copy_from_slice(content))]);
+        store.put_stream(stream).await.unwrap()
+    }
+
+    async fn read_all(store: &ChunkStore, manifest: &ChunkManifest) -> Vec<u8> {
+        let mut content = Vec::new();
+        let mut stream = Box::pin(store.read_stream(manifest));
+        while let Some(chunk) = stream.next().await {
+            content.extend_from_slice(&chunk.unwrap());
+        }
+        content
+    }
+
+    /// Número de bloques almacenados y bytes que ocupan físicamente
+    async fn physical_usage(store: &ChunkStore) -> (usize, u64) {
+        let state = store.state.lock().await;
+        (
+            state.index.chunks.len(),
+            state.index.chunks.values().map(|e| e.size).sum(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_identical_content_is_stored_once() {
+        let temp_dir = tempdir().unwrap();
+        let store = ChunkStore::new(temp_dir.path().join("blocks"), 4096)
+            .await
+            .unwrap();
+
+        let content = vec![7u8; 10_000];
+        let first = put_bytes(&store, &content).await;
+        let second = put_bytes(&store, &content).await;
+
+        assert_eq!(first, second);
+        // 10_000 bytes en bloques de 4096: dos bloques iguales y un resto distinto
+        let (chunks, bytes) = physical_usage(&store).await;
+        assert_eq!(chunks, 2);
+        assert_eq!(bytes, 4096 + (10_000 - 8192));
+
+        assert_eq!(read_all(&store, &second).await, content);
+
+        let other = put_bytes(&store, b"other").await;
+        assert_ne!(first, other);
+    }
+
+    #[tokio::test]
+    async fn test_garbage_collection_only_removes_unreferenced_chunks() {
+        let temp_dir = tempdir().unwrap();
+        let store = ChunkStore::new(temp_dir.path().join("blocks"), 4096)
+            .await
+            .unwrap();
+
+        let shared = put_bytes(&store, b"shared content").await;
+        store.retain(&shared).await.unwrap();
+        let unique = put_bytes(&store, b"unique content").await;
+
+        store.release(&shared).await.unwrap();
+        store.release(&unique).await.unwrap();
+
+        let stats = store.collect_garbage().await.unwrap();
+        assert_eq!(stats.chunks_removed, 1);
+        assert_eq!(stats.bytes_freed, 14);
+        assert_eq!(read_all(&store, &shared).await, b"shared content");
+    }
+
+    #[tokio::test]
+    async fn test_index_survives_reopen() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("blocks");
+
+        let manifest = {
+            let store = ChunkStore::new(root.clone(), 4096).await.unwrap();
+            let stream = futures::stream::iter(vec![
+                Ok(Bytes::from_static(b"hello ")),
+                Ok(Bytes::from_static(b"world")),
+            ]);
+            store.put_stream(stream).await.unwrap()
+        };
+
+        let store = ChunkStore::new(root, 4096).await.unwrap();
+        store.release(&manifest).await.unwrap();
+        let stats = store.collect_garbage().await.unwrap();
+        assert_eq!(stats.chunks_removed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_journal_replays_over_compacted_index() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("blocks");
+
+        let (kept, dropped) = {
+            let store = ChunkStore::new(root.clone(), 4096).await.unwrap();
+            let kept = put_bytes(&store, b"kept").await;
+            store.compact(&mut *store.state.lock().await).await.unwrap();
+            let dropped = put_bytes(&store, b"dropped").await;
+            store.retain(&kept).await.unwrap();
+            store.release(&dropped).await.unwrap();
+            (kept, dropped)
+        };
+
+        // A write cut short by a crash leaves a partial last line
+        let mut journal = fs::OpenOptions::new()
+            .append(true)
+            .open(root.join(JOURNAL_FILE_NAME))
+            .await
+            .unwrap();
+        journal.write_all(b"{\"hash\":\"ab").await.unwrap();
+
+        {
+            let store = ChunkStore::new(root.clone(), 4096).await.unwrap();
+            let state = store.state.lock().await;
+            assert_eq!(state.index.chunks[&kept.chunks[0].hash].refs, 2);
+            assert_eq!(state.index.chunks[&dropped.chunks[0].hash].refs, 0);
+        }
+
+        // Records written after recovery must survive the next replay
+        {
+            let store = ChunkStore::new(root.clone(), 4096).await.unwrap();
+            store.retain(&kept).await.unwrap();
+        }
+
+        let store = ChunkStore::new(root, 4096).await.unwrap();
+        assert_eq!(
+            store.state.lock().await.index.chunks[&kept.chunks[0].hash].refs,
+            3
+        );
+        let stats = store.collect_garbage().await.unwrap();
+        assert_eq!(stats.chunks_removed, 1);
+        assert_eq!(read_all(&store, &kept).await, b"kept");
+    }
+}
@@ -0,0 +1,287 @@
+use hmac::{Hmac, Mac};
+use openssl::rand::rand_bytes;
+use sha2::Sha256;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+
+use crate::application::ports::storage_ports::{ContentStoragePort, StoredContent};
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Cabecera de los archivos del árbol que solo guardan la referencia a su
+/// contenido
+const REFERENCE_MAGIC: &[u8] = b"OXICLOUD-CONTENT-REF\n";
+
+/// Tamaño de la clave con la que se firman las referencias
+const REFERENCE_KEY_LEN: usize = 32;
+
+/// Longitud de la firma en hex, seguida de un salto de línea
+const SIGNATURE_LEN: usize = 64;
+
+/// Firma las referencias que se escriben en el árbol y solo acepta las que
+/// llevan una firma válida.
+///
+/// El contenido de un archivo lo decide su usuario, así que un archivo que
+/// empiece por la cabecera no basta para tratarlo como referencia: sin la
+/// firma podría apuntar a bloques u objetos de otros usuarios.
+pub struct ReferenceSigner {
+    key: Vec<u8>,
+}
+
+impl ReferenceSigner {
+    pub fn new(key: Vec<u8>) -> Self {
+        Self { key }
+    }
+
+    /// Lee la clave de firma del fichero indicado, o la genera la primera vez.
+    /// Perder el fichero deja ilegibles todas las referencias existentes
+    pub async fn load_or_create(path: &Path) -> std::io::Result<Self> {
+        match fs::read_to_string(path).await {
+            Ok(value) => hex::decode(value.trim())
+                .ok()
+                .filter(|key| key.len() == REFERENCE_KEY_LEN)
+                .map(Self::new)
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Invalid content reference key {}", path.display()),
+                    )
+                }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut key = vec![0u8; REFERENCE_KEY_LEN];
+                rand_bytes(&mut key).map_err(std::io::Error::other)?;
+                FileSystemUtils::atomic_write(path, hex::encode(&key).as_bytes()).await?;
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let _ = fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await;
+                }
+                tracing::info!("Created content reference key at {}", path.display());
+                Ok(Self::new(key))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn mac(&self, payload: &[u8]) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(payload);
+        mac
+    }
+
+    /// Serializa la referencia que se escribe en la ruta de un archivo
+    pub fn encode(&self, content: &StoredContent) -> Vec<u8> {
+        // Serializar una estructura con campos simples no puede fallar
+        let payload = serde_json::to_vec(content).unwrap_or_default();
+        let mut raw = REFERENCE_MAGIC.to_vec();
+        raw.extend(hex::encode(self.mac(&payload).finalize().into_bytes()).into_bytes());
+        raw.push(b'\n');
+        raw.extend(payload);
+        raw
+    }
+
+    /// Lee la referencia guardada en la ruta de un archivo, o `None` si el
+    /// archivo tiene su contenido en el propio árbol. Lo que empieza por la
+    /// cabecera sin una firma válida es contenido normal
+    pub async fn read(&self, path: &Path) -> std::io::Result<Option<StoredContent>> {
+        if !has_reference_header(path).await? {
+            return Ok(None);
+        }
+
+        let raw = fs::read(path).await?;
+        let signed = &raw[REFERENCE_MAGIC.len()..];
+        if signed.len() <= SIGNATURE_LEN || signed[SIGNATURE_LEN] != b'\n' {
+            return Ok(None);
+        }
+        let (signature, payload) = (&signed[..SIGNATURE_LEN], &signed[SIGNATURE_LEN + 1..]);
+        let valid = hex::decode(signature)
+            .map(|signature| self.mac(payload).verify_slice(&signature).is_ok())
+            .unwrap_or(false);
+        if !valid {
+            tracing::debug!(
+                "Ignoring unsigned content reference header in {}",
+                path.display()
+            );
+            return Ok(None);
+        }
+
+        serde_json::from_slice(payload).map(Some).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Corrupted content reference {}: {}", path.display(), e),
+            )
+        })
+    }
+}
+
+/// Indica si un archivo empieza por la cabecera de las referencias, sin
+/// comprobar su firma
+pub async fn has_reference_header(path: &Path) -> std::io::Result<bool> {
+    let mut file = fs::File::open(path).await?;
+    let mut magic = vec![0u8; REFERENCE_MAGIC.len()];
+    let mut read = 0;
+    while read < magic.len() {
+        match file.read(&mut magic[read..]).await? {
+            0 => return Ok(false),
+            n => read += n,
+        }
+    }
+    Ok(magic == REFERENCE_MAGIC)
+}
+
+/// Libera los contenidos referenciados por los archivos de un directorio y
+/// sus subdirectorios, antes de borrarlo entero. Devuelve cuántos se liberaron
+pub async fn release_references_in(
+    dir: &Path,
+    store: &dyn ContentStoragePort,
+    signer: &ReferenceSigner,
+) -> usize {
+    let mut pending: Vec<PathBuf> = vec![dir.to_path_buf()];
+    let mut released = 0;
+
+    while let Some(current) = pending.pop() {
+        let mut entries = match fs::read_dir(&current).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Could not read directory {}: {}", current.display(), e);
+                continue;
+            }
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            match entry.file_type().await {
+                Ok(file_type) if file_type.is_dir() => pending.push(path),
+                Ok(file_type) if file_type.is_file() => {
+                    let content = match signer.read(&path).await {
+                        Ok(Some(content)) if content.backend == store.backend_name() => content,
+                        Ok(_) => continue,
+                        Err(e) => {
+                            tracing::warn!("Could not read {}: {}", path.display(), e);
+                            continue;
+                        }
+                    };
+                    match store.release_content(&content).await {
+                        Ok(()) => released += 1,
+                        Err(e) => {
+                            tracing::warn!(
+                                "Could not release the content of {}: {}",
+                                path.display(),
+                                e
+                            )
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    released
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_reference_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let signer = ReferenceSigner::new(vec![1; REFERENCE_KEY_LEN]);
+        let content = StoredContent {
+            backend: "dedup".to_string(),
+            locator: serde_json::json!({ "chunks": [] }),
+            size: 42,
+        };
+
+        let reference = temp_dir.path().join("reference.bin");
+        fs::write(&reference, signer.encode(&content))
+            .await
+            .unwrap();
+        assert_eq!(signer.read(&reference).await.unwrap(), Some(content));
+
+        // Los archivos normales, también los más cortos que la cabecera, no
+        // son referencias
+        let plain = temp_dir.path().join("plain.txt");
+        fs::write(&plain, b"OXI").await.unwrap();
+        assert_eq!(signer.read(&plain).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_forged_reference_is_plain_content() {
+        let temp_dir = tempdir().unwrap();
+        let signer = ReferenceSigner::new(vec![1; REFERENCE_KEY_LEN]);
+        let content = StoredContent {
+            backend: "s3".to_string(),
+            locator: serde_json::json!({ "key": "files/someone-else" }),
+            size: 42,
+        };
+
+        // Un archivo subido con la cabecera y sin firma
+        let mut unsigned = REFERENCE_MAGIC.to_vec();
+        unsigned.extend(serde_json::to_vec(&content).unwrap());
+        let path = temp_dir.path().join("unsigned.bin");
+        fs::write(&path, &unsigned).await.unwrap();
+        assert!(has_reference_header(&path).await.unwrap());
+        assert_eq!(signer.read(&path).await.unwrap(), None);
+
+        // Firmado con otra clave
+        let other = ReferenceSigner::new(vec![2; REFERENCE_KEY_LEN]);
+        fs::write(&path, other.encode(&content)).await.unwrap();
+        assert_eq!(signer.read(&path).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_signing_key_is_kept_across_restarts() {
+        let temp_dir = tempdir().unwrap();
+        let key_path = temp_dir.path().join("reference.key");
+        let content = StoredContent {
+            backend: "dedup".to_string(),
+            locator: serde_json::json!({ "chunks": [] }),
+            size: 0,
+        };
+
+        let reference = temp_dir.path().join("reference.bin");
+        let signer = ReferenceSigner::load_or_create(&key_path).await.unwrap();
+        fs::write(&reference, signer.encode(&content))
+            .await
+            .unwrap();
+
+        let reopened = ReferenceSigner::load_or_create(&key_path).await.unwrap();
+        assert_eq!(reopened.read(&reference).await.unwrap(), Some(content));
+    }
+
+    #[tokio::test]
+    async fn test_release_references_in_frees_copied_content() {
+        use crate::infrastructure::services::chunk_store::ChunkStore;
+        use bytes::Bytes;
+
+        let temp_dir = tempdir().unwrap();
+        let signer = ReferenceSigner::new(vec![1; REFERENCE_KEY_LEN]);
+        let store = ChunkStore::new(temp_dir.path().join("blocks"), 4096)
+            .await
+            .unwrap();
+
+        let stream = futures::stream::once(async { Ok(Bytes::from_static(b"shared")) });
+        let original = store.put_content(Box::pin(stream)).await.unwrap();
+        let copy = store.copy_content(&original).await.unwrap();
+
+        // El original queda fuera de la carpeta que se borra
+        let folder = temp_dir.path().join("folder").join("nested");
+        fs::create_dir_all(&folder).await.unwrap();
+        fs::write(folder.join("copy.bin"), signer.encode(&copy))
+            .await
+            .unwrap();
+        fs::write(folder.join("plain.txt"), b"plain").await.unwrap();
+
+        let released =
+            release_references_in(&temp_dir.path().join("folder"), &store, &signer).await;
+        assert_eq!(released, 1);
+        assert_eq!(store.collect_garbage().await.unwrap().chunks_removed, 0);
+
+        store.release_content(&original).await.unwrap();
+        assert_eq!(store.collect_garbage().await.unwrap().chunks_removed, 1);
+    }
+}
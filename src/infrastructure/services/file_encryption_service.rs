@@ -0,0 +1,1243 @@
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{self, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::RwLock;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::application::ports::storage_ports::EncryptionKeyPort;
+use crate::common::config::EncryptionConfig;
+use crate::common::errors::DomainError;
+use crate::infrastructure::services::content_reference::has_reference_header;
+use crate::infrastructure::services::file_system_utils::FileSystemUtils;
+
+/// Cabecera que identifica un archivo cifrado
+const MAGIC: &[u8; 8] = b"OXCENC01";
+/// magic (8) + id de clave (16) + prefijo de nonce (8) + tamaño de segmento (4)
+pub const HEADER_LEN: usize = 36;
+const TAG_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const WRAP_NONCE_LEN: usize = 12;
+/// Límite de seguridad para el tamaño de segmento leído de una cabecera
+const MAX_SEGMENT_SIZE: u32 = 16 * 1024 * 1024;
+
+const KEYSTORE_FILE: &str = "keystore.json";
+const TEMP_SUFFIX: &str = ".oxenc-tmp";
+
+/// Propietario de la clave usada para archivos fuera de una carpeta personal
+pub const SHARED_KEY_OWNER: &str = "_shared";
+const HOME_FOLDER_PREFIX: &str = "Mi Carpeta - ";
+
+/// Archivos de metadatos en la raíz del almacenamiento que nunca se cifran,
+/// además de los que empiezan por '.'
+const ROOT_METADATA_FILES: &[&str] = &["folder_ids.json", "file_ids.json"];
+
+/// Índices que se leen en claro dentro de la papelera y de las versiones,
+/// por ruta relativa a la raíz (`*` es el identificador de un archivo)
+const INTERNAL_INDEX_FILES: &[&[&str]] = &[
+    &[".trash", "trash_index.json"],
+    &[".versions", "*", "index.json"],
+];
+
+/// Directorios de la raíz que la migración deja en claro porque el servidor
+/// lee su contenido tal cual:
+/// - `.keys`: las propias claves de datos
+/// - `.uploads` y `.nextcloud_uploads`: subidas en curso a las que se sigue
+///   añadiendo contenido; se cifran al guardarse y se borran al caducar
+/// - `.thumbnails`: miniaturas de antes de activar el cifrado, que ya no se sirven
+/// - `.search_index`: el texto extraído para la búsqueda
+/// - `.webdav_props`: propiedades WebDAV de los recursos
+/// - `.dedup`: bloques del backend deduplicado, incompatible con el cifrado
+///
+/// El resto, incluidas la papelera y las versiones, se cifra con la clave común.
+const UNENCRYPTED_ROOT_DIRS: &[&str] = &[
+    ".keys",
+    ".uploads",
+    ".nextcloud_uploads",
+    ".thumbnails",
+    ".search_index",
+    ".webdav_props",
+    ".dedup",
+];
+
+type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, io::Error>> + Send>>;
+
+/// Determina el propietario de la clave de datos para una ruta de almacenamiento.
+///
+/// Los archivos bajo "Mi Carpeta - {usuario}" usan la clave de ese usuario; el
+/// resto comparte una clave común.
+pub fn key_owner_for_path(storage_path: &str) -> String {
+    storage_path
+        .trim_start_matches('/')
+        .split('/')
+        .next()
+        .and_then(|first| first.strip_prefix(HOME_FOLDER_PREFIX))
+        .filter(|user| !user.is_empty())
+        .unwrap_or(SHARED_KEY_OWNER)
+        .to_string()
+}
+
+/// Clave de usuario envuelta con la clave maestra
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WrappedKey {
+    owner: String,
+    /// nonce || clave cifrada || tag, en hexadecimal
+    wrapped: String,
+    created_at: u64,
+}
+
+/// Contenido persistido de `.keys/keystore.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct KeyStore {
+    master_key_id: String,
+    keys: HashMap<String, WrappedKey>,
+}
+
+struct MasterKey {
+    id: String,
+    key: [u8; KEY_LEN],
+}
+
+impl MasterKey {
+    fn new(key: [u8; KEY_LEN]) -> Self {
+        // Huella corta de la clave, sirve para detectar claves incorrectas
+        let id = hex::encode(&Sha256::digest(key)[..8]);
+        Self { id, key }
+    }
+
+    fn parse(value: &str) -> Result<Self, DomainError> {
+        let bytes = hex::decode(value.trim()).map_err(|_| {
+            DomainError::validation_error("Encryption master key must be hex encoded")
+        })?;
+        Self::from_bytes(bytes)
+    }
+
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, DomainError> {
+        let key: [u8; KEY_LEN] = bytes.try_into().map_err(|_| {
+            DomainError::validation_error("Encryption master key must be 32 bytes long")
+        })?;
+        Ok(Self::new(key))
+    }
+
+    fn generate() -> Result<Self, DomainError> {
+        Ok(Self::new(random_bytes()?))
+    }
+}
+
+/// Estado de claves en memoria: las claves de usuario se desenvuelven al cargar
+struct KeyState {
+    master: MasterKey,
+    store: KeyStore,
+    unwrapped: HashMap<String, [u8; KEY_LEN]>,
+    owners: HashMap<String, String>,
+}
+
+/// Cabecera de un archivo cifrado
+#[derive(Debug, Clone, Copy)]
+struct SegmentHeader {
+    key_id: [u8; 16],
+    nonce_prefix: [u8; 8],
+    segment_size: u32,
+}
+
+impl SegmentHeader {
+    fn generate(key_id: [u8; 16], segment_size: u32) -> io::Result<Self> {
+        Ok(Self {
+            key_id,
+            nonce_prefix: random_bytes().map_err(io::Error::other)?,
+            segment_size,
+        })
+    }
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+            return None;
+        }
+        let segment_size = u32::from_be_bytes(data[32..36].try_into().ok()?);
+        if segment_size == 0 || segment_size > MAX_SEGMENT_SIZE {
+            return None;
+        }
+        Some(Self {
+            key_id: data[8..24].try_into().ok()?,
+            nonce_prefix: data[24..32].try_into().ok()?,
+            segment_size,
+        })
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_LEN] {
+        let mut out = [0u8; HEADER_LEN];
+        out[..8].copy_from_slice(MAGIC);
+        out[8..24].copy_from_slice(&self.key_id);
+        out[24..32].copy_from_slice(&self.nonce_prefix);
+        out[32..36].copy_from_slice(&self.segment_size.to_be_bytes());
+        out
+    }
+
+    fn key_id(&self) -> String {
+        Uuid::from_bytes(self.key_id).to_string()
+    }
+
+    /// Tamaño en claro de un archivo cifrado de `disk_len` bytes
+    fn plaintext_len(&self, disk_len: u64) -> u64 {
+        let segment = u64::from(self.segment_size);
+        let body = disk_len.saturating_sub(HEADER_LEN as u64);
+        let full = body / (segment + TAG_LEN as u64);
+        let rest = body % (segment + TAG_LEN as u64);
+        full * segment + rest.saturating_sub(TAG_LEN as u64)
+    }
+
+    /// Nonce y datos asociados del segmento `index`. El último segmento se marca
+    /// en los datos asociados para detectar truncamientos.
+    fn segment_params(&self, index: u64, last: bool) -> io::Result<([u8; 12], Vec<u8>)> {
+        let counter = u32::try_from(index)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Too many segments"))?;
+        let mut nonce = [0u8; 12];
+        nonce[..8].copy_from_slice(&self.nonce_prefix);
+        nonce[8..].copy_from_slice(&counter.to_be_bytes());
+
+        let mut aad = self.to_bytes().to_vec();
+        aad.extend_from_slice(&index.to_be_bytes());
+        aad.push(last as u8);
+        Ok((nonce, aad))
+    }
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], DomainError> {
+    let mut buf = [0u8; N];
+    rand_bytes(&mut buf)
+        .map_err(|e| DomainError::internal_error("Encryption", format!("RNG failure: {}", e)))?;
+    Ok(buf)
+}
+
+fn seal_segment(
+    key: &[u8; KEY_LEN],
+    header: &SegmentHeader,
+    index: u64,
+    last: bool,
+    plaintext: &[u8],
+) -> io::Result<Vec<u8>> {
+    let (nonce, aad) = header.segment_params(index, last)?;
+    let mut tag = [0u8; TAG_LEN];
+    let mut out = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(&nonce),
+        &aad,
+        plaintext,
+        &mut tag,
+    )
+    .map_err(io::Error::other)?;
+    out.extend_from_slice(&tag);
+    Ok(out)
+}
+
+fn open_segment(
+    key: &[u8; KEY_LEN],
+    header: &SegmentHeader,
+    index: u64,
+    last: bool,
+    data: &[u8],
+) -> io::Result<Vec<u8>> {
+    if data.len() < TAG_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Encrypted file is truncated",
+        ));
+    }
+    let (nonce, aad) = header.segment_params(index, last)?;
+    let (ciphertext, tag) = data.split_at(data.len() - TAG_LEN);
+    decrypt_aead(
+        Cipher::aes_256_gcm(),
+        key,
+        Some(&nonce),
+        &aad,
+        ciphertext,
+        tag,
+    )
+    .map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Authentication failed for encrypted segment {}", index),
+        )
+    })
+}
+
+fn seal_all(key: &[u8; KEY_LEN], header: SegmentHeader, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+    let segment = header.segment_size as usize;
+    let full = plaintext.len() / segment;
+    let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + (full + 1) * TAG_LEN);
+    out.extend_from_slice(&header.to_bytes());
+    for index in 0..full {
+        let chunk = &plaintext[index * segment..(index + 1) * segment];
+        out.extend(seal_segment(key, &header, index as u64, false, chunk)?);
+    }
+    // El último segmento siempre es más corto que un segmento completo (puede estar vacío)
+    out.extend(seal_segment(
+        key,
+        &header,
+        full as u64,
+        true,
+        &plaintext[full * segment..],
+    )?);
+    Ok(out)
+}
+
+fn open_all(key: &[u8; KEY_LEN], header: SegmentHeader, data: &[u8]) -> io::Result<Vec<u8>> {
+    let segment = header.segment_size as usize + TAG_LEN;
+    let body = &data[HEADER_LEN..];
+    let mut out = Vec::with_capacity(header.plaintext_len(data.len() as u64) as usize);
+    let mut offset = 0;
+    let mut index = 0u64;
+    loop {
+        let end = (offset + segment).min(body.len());
+        let last = end - offset < segment;
+        out.extend(open_segment(key, &header, index, last, &body[offset..end])?);
+        if last {
+            return Ok(out);
+        }
+        offset = end;
+        index += 1;
+    }
+}
+
+/// Lee hasta llenar `buf` o llegar al final; devuelve los bytes leídos
+async fn read_full<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = reader.read(&mut buf[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+/// Servicio de cifrado en reposo del contenido de los archivos.
+///
+/// Cada archivo se cifra con AES-256-GCM en segmentos independientes usando la
+/// clave de datos de su propietario. Las claves de datos se guardan envueltas con
+/// la clave maestra en `.keys/keystore.json`. Los archivos sin cabecera de cifrado
+/// se leen tal cual, lo que permite migrar el contenido existente poco a poco.
+pub struct FileEncryptionService {
+    storage_root: PathBuf,
+    keys_dir: PathBuf,
+    /// Fichero de la clave maestra; `None` cuando la clave llega por configuración
+    master_key_file: Option<PathBuf>,
+    segment_size: usize,
+    state: RwLock<KeyState>,
+}
+
+impl FileEncryptionService {
+    /// Carga (o inicializa) las claves de cifrado bajo la raíz de almacenamiento
+    pub async fn new(
+        storage_root: impl Into<PathBuf>,
+        config: &EncryptionConfig,
+    ) -> Result<Self, DomainError> {
+        let storage_root = storage_root.into();
+        let keys_dir = storage_root.join(".keys");
+        fs::create_dir_all(&keys_dir).await.map_err(|e| {
+            DomainError::internal_error(
+                "Encryption",
+                format!("Failed to create key directory: {}", e),
+            )
+        })?;
+
+        let mut store = Self::read_store(&keys_dir).await?;
+
+        let (master, master_key_file) = match &config.master_key {
+            Some(value) => (MasterKey::parse(value)?, None),
+            None => {
+                let path = config.master_key_file.clone().ok_or_else(|| {
+                    DomainError::validation_error(
+                        "Encryption at rest needs OXICLOUD_ENCRYPTION_MASTER_KEY or \
+                         OXICLOUD_ENCRYPTION_MASTER_KEY_FILE",
+                    )
+                })?;
+                Self::check_key_outside_storage(&path, &storage_root).await?;
+                (Self::load_master_key_file(&path, &store).await?, Some(path))
+            }
+        };
+
+        if store.master_key_id.is_empty() {
+            store.master_key_id = master.id.clone();
+            Self::write_store(&keys_dir, &store).await?;
+        } else if store.master_key_id != master.id {
+            return Err(DomainError::validation_error(format!(
+                "Encryption master key {} does not match key store (expected {})",
+                master.id, store.master_key_id
+            )));
+        }
+
+        let mut unwrapped = HashMap::new();
+        let mut owners = HashMap::new();
+        for (key_id, entry) in &store.keys {
+            unwrapped.insert(key_id.clone(), Self::unwrap_key(&master, key_id, entry)?);
+            owners.insert(entry.owner.clone(), key_id.clone());
+        }
+
+        tracing::info!(
+            "File encryption enabled (master key {}, {} data keys)",
+            master.id,
+            unwrapped.len()
+        );
+
+        Ok(Self {
+            storage_root,
+            keys_dir,
+            master_key_file,
+            segment_size: config.segment_size.clamp(1024, MAX_SEGMENT_SIZE as usize),
+            state: RwLock::new(KeyState {
+                master,
+                store,
+                unwrapped,
+                owners,
+            }),
+        })
+    }
+
+    /// Rechaza un fichero de clave maestra dentro del almacenamiento: una copia
+    /// de seguridad o un disco robado tendrían la clave junto a lo que protege
+    async fn check_key_outside_storage(
+        path: &Path,
+        storage_root: &Path,
+    ) -> Result<(), DomainError> {
+        let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+        let (parent, root) = match (
+            fs::canonicalize(parent.unwrap_or(Path::new("."))).await,
+            fs::canonicalize(storage_root).await,
+        ) {
+            (Ok(parent), Ok(root)) => (parent, root),
+            (Err(e), _) | (_, Err(e)) => {
+                return Err(DomainError::internal_error(
+                    "Encryption",
+                    format!(
+                        "Failed to resolve master key path {}: {}",
+                        path.display(),
+                        e
+                    ),
+                ))
+            }
+        };
+        if parent.starts_with(&root) {
+            return Err(DomainError::validation_error(format!(
+                "Encryption master key file {} must be outside the storage directory {}",
+                path.display(),
+                storage_root.display()
+            )));
+        }
+        Ok(())
+    }
+
+    fn pending_key_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".new");
+        path.with_file_name(name)
+    }
+
+    async fn read_master_key(path: &Path) -> Result<MasterKey, DomainError> {
+        let value = fs::read_to_string(path).await.map_err(|e| {
+            DomainError::internal_error(
+                "Encryption",
+                format!("Failed to read master key {}: {}", path.display(), e),
+            )
+        })?;
+        MasterKey::parse(&value)
+    }
+
+    async fn write_master_key(path: &Path, master: &MasterKey) -> Result<(), DomainError> {
+        FileSystemUtils::atomic_write(path, hex::encode(master.key).as_bytes())
+            .await
+            .map_err(|e| {
+                DomainError::internal_error(
+                    "Encryption",
+                    format!("Failed to write master key {}: {}", path.display(), e),
+                )
+            })?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await;
+        }
+        Ok(())
+    }
+
+    /// Lee la clave maestra del fichero, completando una rotación interrumpida
+    /// o generando una clave nueva si todavía no hay claves de usuario
+    async fn load_master_key_file(path: &Path, store: &KeyStore) -> Result<MasterKey, DomainError> {
+        let pending = Self::pending_key_path(path);
+        if !store.master_key_id.is_empty() && pending.exists() {
+            let candidate = Self::read_master_key(&pending).await?;
+            if candidate.id == store.master_key_id {
+                tracing::warn!("Completing interrupted master key rotation");
+                fs::rename(&pending, path).await.map_err(|e| {
+                    DomainError::internal_error(
+                        "Encryption",
+                        format!("Failed to install rotated master key: {}", e),
+                    )
+                })?;
+                return Ok(candidate);
+            }
+            let _ = fs::remove_file(&pending).await;
+        }
+
+        if path.exists() {
+            return Self::read_master_key(path).await;
+        }
+
+        if !store.keys.is_empty() {
+            return Err(DomainError::not_found(
+                "Encryption master key",
+                path.display().to_string(),
+            ));
+        }
+
+        let master = MasterKey::generate()?;
+        Self::write_master_key(path, &master).await?;
+        tracing::info!("Generated new encryption master key at {}", path.display());
+        Ok(master)
+    }
+
+    async fn read_store(keys_dir: &Path) -> Result<KeyStore, DomainError> {
+        match fs::read(keys_dir.join(KEYSTORE_FILE)).await {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                DomainError::internal_error("Encryption", format!("Corrupt key store: {}", e))
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(KeyStore::default()),
+            Err(e) => Err(DomainError::internal_error(
+                "Encryption",
+                format!("Failed to read key store: {}", e),
+            )),
+        }
+    }
+
+    async fn write_store(keys_dir: &Path, store: &KeyStore) -> Result<(), DomainError> {
+        let data = serde_json::to_vec_pretty(store).map_err(|e| {
+            DomainError::internal_error(
+                "Encryption",
+                format!("Failed to serialize key store: {}", e),
+            )
+        })?;
+        FileSystemUtils::atomic_write(keys_dir.join(KEYSTORE_FILE), &data)
+            .await
+            .map_err(|e| {
+                DomainError::internal_error(
+                    "Encryption",
+                    format!("Failed to write key store: {}", e),
+                )
+            })
+    }
+
+    fn wrap_key(
+        master: &MasterKey,
+        key_id: &str,
+        owner: &str,
+        key: &[u8; KEY_LEN],
+    ) -> Result<String, DomainError> {
+        let nonce: [u8; WRAP_NONCE_LEN] = random_bytes()?;
+        let aad = format!("{}:{}", key_id, owner);
+        let mut tag = [0u8; TAG_LEN];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &master.key,
+            Some(&nonce),
+            aad.as_bytes(),
+            key,
+            &mut tag,
+        )
+        .map_err(|e| DomainError::internal_error("Encryption", e.to_string()))?;
+
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        out.extend_from_slice(&tag);
+        Ok(hex::encode(out))
+    }
+
+    fn unwrap_key(
+        master: &MasterKey,
+        key_id: &str,
+        entry: &WrappedKey,
+    ) -> Result<[u8; KEY_LEN], DomainError> {
+        let data = hex::decode(&entry.wrapped).unwrap_or_default();
+        if data.len() != WRAP_NONCE_LEN + KEY_LEN + TAG_LEN {
+            return Err(DomainError::internal_error(
+                "Encryption",
+                format!("Malformed wrapped key {}", key_id),
+            ));
+        }
+        let (nonce, rest) = data.split_at(WRAP_NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(KEY_LEN);
+        let aad = format!("{}:{}", key_id, entry.owner);
+        let key = decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &master.key,
+            Some(nonce),
+            aad.as_bytes(),
+            ciphertext,
+            tag,
+        )
+        .map_err(|_| {
+            DomainError::internal_error(
+                "Encryption",
+                format!("Failed to unwrap data key {}: wrong master key?", key_id),
+            )
+        })?;
+        key.try_into().map_err(|_| {
+            DomainError::internal_error("Encryption", format!("Invalid data key {}", key_id))
+        })
+    }
+
+    /// Devuelve la clave de datos de un propietario, creándola si no existe
+    async fn data_key(&self, owner: &str) -> Result<([u8; 16], [u8; KEY_LEN]), DomainError> {
+        {
+            let state = self.state.read().await;
+            if let Some(key_id) = state.owners.get(owner) {
+                return Ok((Self::key_id_bytes(key_id)?, state.unwrapped[key_id]));
+            }
+        }
+
+        let mut state = self.state.write().await;
+        if let Some(key_id) = state.owners.get(owner) {
+            return Ok((Self::key_id_bytes(key_id)?, state.unwrapped[key_id]));
+        }
+
+        let key_uuid = Uuid::new_v4();
+        let key_id = key_uuid.to_string();
+        let key: [u8; KEY_LEN] = random_bytes()?;
+        let entry = WrappedKey {
+            owner: owner.to_string(),
+            wrapped: Self::wrap_key(&state.master, &key_id, owner, &key)?,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+
+        let mut store = state.store.clone();
+        store.keys.insert(key_id.clone(), entry);
+        Self::write_store(&self.keys_dir, &store).await?;
+
+        state.store = store;
+        state.unwrapped.insert(key_id.clone(), key);
+        state.owners.insert(owner.to_string(), key_id.clone());
+        tracing::info!("Created data key {} for {}", key_id, owner);
+
+        Ok((*key_uuid.as_bytes(), key))
+    }
+
+    fn key_id_bytes(key_id: &str) -> Result<[u8; 16], DomainError> {
+        Uuid::parse_str(key_id)
+            .map(|id| *id.as_bytes())
+            .map_err(|e| DomainError::internal_error("Encryption", e.to_string()))
+    }
+
+    async fn key_by_id(&self, key_id: &str) -> io::Result<[u8; KEY_LEN]> {
+        self.state
+            .read()
+            .await
+            .unwrapped
+            .get(key_id)
+            .copied()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Unknown data key {}", key_id),
+                )
+            })
+    }
+
+    /// Cifra un contenido completo con la clave de `owner`
+    pub async fn encrypt_bytes(
+        &self,
+        owner: &str,
+        plaintext: Vec<u8>,
+    ) -> Result<Vec<u8>, DomainError> {
+        let (key_id, key) = self.data_key(owner).await?;
+        let segment_size = self.segment_size as u32;
+        tokio::task::spawn_blocking(move || {
+            let header = SegmentHeader::generate(key_id, segment_size)?;
+            seal_all(&key, header, &plaintext)
+        })
+        .await
+        .map_err(|e| DomainError::internal_error("Encryption", e.to_string()))?
+        .map_err(|e| DomainError::internal_error("Encryption", e.to_string()))
+    }
+
+    /// Descifra un contenido completo; el contenido en claro se devuelve tal cual
+    pub async fn decrypt_bytes(&self, data: Vec<u8>) -> Result<Vec<u8>, DomainError> {
+        let Some(header) = SegmentHeader::parse(&data) else {
+            return Ok(data);
+        };
+        let key = self
+            .key_by_id(&header.key_id())
+            .await
+            .map_err(|e| DomainError::internal_error("Encryption", e.to_string()))?;
+        tokio::task::spawn_blocking(move || open_all(&key, header, &data))
+            .await
+            .map_err(|e| DomainError::internal_error("Encryption", e.to_string()))?
+            .map_err(|e| DomainError::internal_error("Encryption", e.to_string()))
+    }
+
+    /// Cifra un stream en segmentos sin acumular el contenido completo
+    pub async fn encrypt_stream(
+        &self,
+        owner: &str,
+        mut input: ByteStream,
+    ) -> Result<ByteStream, DomainError> {
+        let (key_id, key) = self.data_key(owner).await?;
+        let segment_size = self.segment_size;
+        let header = SegmentHeader::generate(key_id, segment_size as u32)
+            .map_err(|e| DomainError::internal_error("Encryption", e.to_string()))?;
+
+        Ok(Box::pin(async_stream::try_stream! {
+            yield Bytes::copy_from_slice(&header.to_bytes());
+
+            let mut buffer = BytesMut::new();
+            let mut index = 0u64;
+            while let Some(chunk) = input.next().await {
+                buffer.extend_from_slice(&chunk?);
+                while buffer.len() >= segment_size {
+                    let segment = buffer.split_to(segment_size);
+                    yield Bytes::from(seal_segment(&key, &header, index, false, &segment)?);
+                    index += 1;
+                }
+            }
+            yield Bytes::from(seal_segment(&key, &header, index, true, &buffer)?);
+        }))
+    }
+
+    /// Devuelve un stream con el contenido en claro de `reader`.
+    ///
+    /// Si el contenido no empieza por la cabecera de cifrado se devuelve sin cambios.
+    pub async fn decrypt_reader<R>(&self, mut reader: R) -> io::Result<ByteStream>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let mut head = vec![0u8; HEADER_LEN];
+        let read = read_full(&mut reader, &mut head).await?;
+        head.truncate(read);
+
+        let Some(header) = SegmentHeader::parse(&head) else {
+            let prefix = futures::stream::iter(Some(Ok(Bytes::from(head))));
+            return Ok(Box::pin(prefix.chain(ReaderStream::new(reader))));
+        };
+
+        let key = self.key_by_id(&header.key_id()).await?;
+        let segment = header.segment_size as usize + TAG_LEN;
+
+        Ok(Box::pin(async_stream::try_stream! {
+            let mut index = 0u64;
+            loop {
+                let mut buf = vec![0u8; segment];
+                let read = read_full(&mut reader, &mut buf).await?;
+                buf.truncate(read);
+                let last = read < segment;
+                let plaintext = open_segment(&key, &header, index, last, &buf)?;
+                if !plaintext.is_empty() {
+                    yield Bytes::from(plaintext);
+                }
+                if last {
+                    break;
+                }
+                index += 1;
+            }
+        }))
+    }
+
+    /// Devuelve el rango `[start, end)` del contenido en claro de `reader`,
+    /// descifrando solo los segmentos que lo contienen
+    pub async fn decrypt_range_reader<R>(
+        &self,
+        mut reader: R,
+        start: u64,
+        end: u64,
+    ) -> io::Result<ByteStream>
+    where
+        R: AsyncRead + AsyncSeek + Unpin + Send + 'static,
+    {
+        let mut head = [0u8; HEADER_LEN];
+        let read = read_full(&mut reader, &mut head).await?;
+
+        let Some(header) = SegmentHeader::parse(&head[..read]) else {
+            reader.seek(SeekFrom::Start(start)).await?;
+            let limited = reader.take(end.saturating_sub(start));
+            return Ok(Box::pin(ReaderStream::new(limited)));
+        };
+
+        let key = self.key_by_id(&header.key_id()).await?;
+        let plain_segment = u64::from(header.segment_size);
+        let segment = plain_segment as usize + TAG_LEN;
+        let first = start / plain_segment;
+        reader
+            .seek(SeekFrom::Start(HEADER_LEN as u64 + first * segment as u64))
+            .await?;
+
+        Ok(Box::pin(async_stream::try_stream! {
+            let mut index = first;
+            let mut position = first * plain_segment;
+            while position < end {
+                let mut buf = vec![0u8; segment];
+                let read = read_full(&mut reader, &mut buf).await?;
+                buf.truncate(read);
+                let last = read < segment;
+                let plaintext = open_segment(&key, &header, index, last, &buf)?;
+
+                let from = start.saturating_sub(position).min(plaintext.len() as u64) as usize;
+                let to = (end - position).min(plaintext.len() as u64) as usize;
+                if from < to {
+                    yield Bytes::copy_from_slice(&plaintext[from..to]);
+                }
+                if last {
+                    break;
+                }
+                position += plain_segment;
+                index += 1;
+            }
+        }))
+    }
+
+    async fn read_header(path: &Path) -> io::Result<Option<SegmentHeader>> {
+        let mut file = fs::File::open(path).await?;
+        let mut head = [0u8; HEADER_LEN];
+        let read = read_full(&mut file, &mut head).await?;
+        Ok(SegmentHeader::parse(&head[..read]))
+    }
+
+    /// Indica si el archivo en disco está cifrado
+    pub async fn is_encrypted(&self, path: &Path) -> io::Result<bool> {
+        Ok(Self::read_header(path).await?.is_some())
+    }
+
+    /// Tamaño en claro de un archivo cifrado, o `None` si está en claro
+    pub async fn plaintext_size(&self, path: &Path) -> io::Result<Option<u64>> {
+        let Some(header) = Self::read_header(path).await? else {
+            return Ok(None);
+        };
+        let disk_len = fs::metadata(path).await?.len();
+        Ok(Some(header.plaintext_len(disk_len)))
+    }
+
+    /// Cifra en el sitio un archivo en claro, conservando su fecha de modificación.
+    /// Devuelve `false` si ya estaba cifrado.
+    pub async fn encrypt_file_in_place(
+        &self,
+        path: &Path,
+        owner: &str,
+    ) -> Result<bool, DomainError> {
+        let io_error = |e: io::Error| {
+            DomainError::internal_error(
+                "Encryption",
+                format!("Failed to encrypt {}: {}", path.display(), e),
+            )
+        };
+
+        if self.is_encrypted(path).await.map_err(io_error)? {
+            return Ok(false);
+        }
+
+        let before = fs::metadata(path).await.map_err(io_error)?;
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(TEMP_SUFFIX);
+        let temp_path = path.with_file_name(temp_name);
+
+        let result = async {
+            let source = fs::File::open(path).await?;
+            let mut encrypted = self
+                .encrypt_stream(owner, Box::pin(ReaderStream::new(source)))
+                .await
+                .map_err(io::Error::other)?;
+
+            let mut target = fs::File::create(&temp_path).await?;
+            while let Some(chunk) = encrypted.next().await {
+                target.write_all(&chunk?).await?;
+            }
+            target.flush().await?;
+            target.sync_all().await?;
+
+            let target = target.into_std().await;
+            if let Ok(modified) = before.modified() {
+                target.set_modified(modified)?;
+            }
+
+            // Si el archivo cambió mientras se cifraba, se deja para la próxima pasada
+            let after = fs::metadata(path).await?;
+            if after.len() != before.len() || after.modified().ok() != before.modified().ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "file modified during encryption",
+                ));
+            }
+            fs::rename(&temp_path, path).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(io_error(e));
+        }
+        Ok(true)
+    }
+
+    /// Indica si un archivo es uno de los índices de `INTERNAL_INDEX_FILES`
+    fn is_internal_index(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.storage_root) else {
+            return false;
+        };
+        let parts: Vec<_> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        INTERNAL_INDEX_FILES.iter().any(|pattern| {
+            pattern.len() == parts.len()
+                && pattern
+                    .iter()
+                    .zip(&parts)
+                    .all(|(expected, part)| *expected == "*" || expected == part)
+        })
+    }
+
+    /// Archivos de contenido bajo la raíz susceptibles de cifrarse, con su propietario
+    async fn migration_candidates(&self) -> io::Result<Vec<(PathBuf, String)>> {
+        let mut candidates = Vec::new();
+        let mut pending = vec![(self.storage_root.clone(), None::<String>)];
+
+        while let Some((dir, owner)) = pending.pop() {
+            let at_root = dir == self.storage_root;
+            let mut entries = fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let file_type = entry.file_type().await?;
+
+                if file_type.is_dir() {
+                    if at_root && UNENCRYPTED_ROOT_DIRS.contains(&name.as_str()) {
+                        continue;
+                    }
+                    // Fuera de las carpetas personales la ruta no indica el propietario
+                    let owner = owner
+                        .clone()
+                        .or_else(|| at_root.then(|| key_owner_for_path(&name)));
+                    pending.push((entry.path(), owner));
+                } else if file_type.is_file() {
+                    let skip = name.ends_with(TEMP_SUFFIX)
+                        || (at_root
+                            && (name.starts_with('.')
+                                || ROOT_METADATA_FILES.contains(&name.as_str())))
+                        || self.is_internal_index(&entry.path())
+                        // Las referencias a contenido guardado fuera del árbol se leen en claro
+                        || !matches!(has_reference_header(&entry.path()).await, Ok(false));
+                    if !skip {
+                        let owner = owner
+                            .clone()
+                            .unwrap_or_else(|| SHARED_KEY_OWNER.to_string());
+                        candidates.push((entry.path(), owner));
+                    }
+                }
+            }
+        }
+
+        Ok(candidates)
+    }
+}
+
+#[async_trait]
+impl EncryptionKeyPort for FileEncryptionService {
+    async fn rotate_master_key(
+        &self,
+        new_master_key: Option<Vec<u8>>,
+    ) -> Result<(String, usize), DomainError> {
+        let mut state = self.state.write().await;
+
+        // Una clave elegida por el llamante solo se admite cuando el servidor
+        // no puede guardar una generada, porque la clave viene de la configuración
+        let new_master =
+            match (new_master_key, self.master_key_file.is_some()) {
+                (None, true) => MasterKey::generate()?,
+                (Some(bytes), false) => MasterKey::from_bytes(bytes)?,
+                (Some(_), true) => {
+                    return Err(DomainError::validation_error(
+                        "The master key file is managed by the server; omit the new key",
+                    ))
+                }
+                (None, false) => return Err(DomainError::validation_error(
+                    "The master key is provided by configuration; supply the new key explicitly",
+                )),
+            };
+        if new_master.id == state.master.id {
+            return Err(DomainError::validation_error(
+                "The new master key must differ from the current one",
+            ));
+        }
+
+        let mut store = KeyStore {
+            master_key_id: new_master.id.clone(),
+            keys: HashMap::new(),
+        };
+        for (key_id, entry) in &state.store.keys {
+            let key = state.unwrapped[key_id];
+            store.keys.insert(
+                key_id.clone(),
+                WrappedKey {
+                    owner: entry.owner.clone(),
+                    wrapped: Self::wrap_key(&new_master, key_id, &entry.owner, &key)?,
+                    created_at: entry.created_at,
+                },
+            );
+        }
+
+        // La clave nueva se escribe antes que el almacén para poder completar
+        // la rotación si el proceso se interrumpe
+        let pending = self.master_key_file.as_deref().map(Self::pending_key_path);
+        if let Some(pending) = &pending {
+            Self::write_master_key(pending, &new_master).await?;
+        }
+        Self::write_store(&self.keys_dir, &store).await?;
+        if let (Some(pending), Some(path)) = (&pending, &self.master_key_file) {
+            fs::rename(pending, path).await.map_err(|e| {
+                DomainError::internal_error(
+                    "Encryption",
+                    format!("Failed to install rotated master key: {}", e),
+                )
+            })?;
+        } else {
+            tracing::warn!(
+                "Master key rotated to {}; update OXICLOUD_ENCRYPTION_MASTER_KEY before restarting",
+                new_master.id
+            );
+        }
+
+        let rewrapped = store.keys.len();
+        let master_key_id = new_master.id.clone();
+        state.master = new_master;
+        state.store = store;
+
+        tracing::info!(
+            "Rotated encryption master key to {} ({} data keys re-wrapped)",
+            master_key_id,
+            rewrapped
+        );
+        Ok((master_key_id, rewrapped))
+    }
+
+    async fn migrate_plaintext_files(&self) -> Result<(usize, usize), DomainError> {
+        let candidates = self.migration_candidates().await.map_err(|e| {
+            DomainError::internal_error("Encryption", format!("Failed to scan storage: {}", e))
+        })?;
+
+        let mut encrypted = 0;
+        let mut failed = 0;
+        for (path, owner) in candidates {
+            match self.encrypt_file_in_place(&path, &owner).await {
+                Ok(true) => encrypted += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    failed += 1;
+                }
+            }
+        }
+
+        tracing::info!(
+            "Encryption migration finished: {} files encrypted, {} skipped",
+            encrypted,
+            failed
+        );
+        Ok((encrypted, failed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn config(keys: &Path, segment_size: usize) -> EncryptionConfig {
+        EncryptionConfig {
+            enabled: true,
+            master_key_file: Some(keys.join("master.key")),
+            segment_size,
+            ..EncryptionConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip_and_plaintext_size() {
+        let dir = tempdir().unwrap();
+        let keys = tempdir().unwrap();
+        let service = FileEncryptionService::new(dir.path(), &config(keys.path(), 1024))
+            .await
+            .unwrap();
+
+        for len in [0usize, 10, 1024, 4096, 5000] {
+            let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let encrypted = service
+                .encrypt_bytes("alice", plaintext.clone())
+                .await
+                .unwrap();
+            assert_ne!(encrypted, plaintext);
+
+            let path = dir.path().join(format!("file-{}", len));
+            fs::write(&path, &encrypted).await.unwrap();
+            assert_eq!(
+                service.plaintext_size(&path).await.unwrap(),
+                Some(len as u64)
+            );
+
+            let file = fs::File::open(&path).await.unwrap();
+            let mut stream = service.decrypt_reader(file).await.unwrap();
+            let mut streamed = Vec::new();
+            while let Some(chunk) = stream.next().await {
+                streamed.extend_from_slice(&chunk.unwrap());
+            }
+            assert_eq!(streamed, plaintext);
+            assert_eq!(service.decrypt_bytes(encrypted).await.unwrap(), plaintext);
+
+            // Ranges only decrypt the segments they touch
+            for (start, end) in [(0, 10), (1000, 2100), (len.saturating_sub(7), len)] {
+                let (start, end) = (start.min(len), end.min(len));
+                let file = fs::File::open(&path).await.unwrap();
+                let mut stream = service
+                    .decrypt_range_reader(file, start as u64, end as u64)
+                    .await
+                    .unwrap();
+                let mut ranged = Vec::new();
+                while let Some(chunk) = stream.next().await {
+                    ranged.extend_from_slice(&chunk.unwrap());
+                }
+                assert_eq!(ranged, &plaintext[start..end]);
+            }
+        }
+
+        // Legacy plaintext passes through untouched
+        assert_eq!(
+            service.decrypt_bytes(b"plain".to_vec()).await.unwrap(),
+            b"plain"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tampering_and_truncation_are_detected() {
+        let dir = tempdir().unwrap();
+        let keys = tempdir().unwrap();
+        let service = FileEncryptionService::new(dir.path(), &config(keys.path(), 1024))
+            .await
+            .unwrap();
+        let encrypted = service.encrypt_bytes("bob", vec![7u8; 3000]).await.unwrap();
+
+        let mut tampered = encrypted.clone();
+        tampered[HEADER_LEN + 5] ^= 1;
+        assert!(service.decrypt_bytes(tampered).await.is_err());
+
+        // Dropping the final segment must not go unnoticed
+        let truncated = encrypted[..HEADER_LEN + 2 * (1024 + TAG_LEN)].to_vec();
+        assert!(service.decrypt_bytes(truncated).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_master_key_rotation_and_migration() {
+        let dir = tempdir().unwrap();
+        let keys = tempdir().unwrap();
+        let home = dir.path().join("Mi Carpeta - carol");
+        fs::create_dir_all(&home).await.unwrap();
+        fs::write(home.join("notes.txt"), b"secret notes")
+            .await
+            .unwrap();
+        fs::write(dir.path().join("folder_ids.json"), b"{}")
+            .await
+            .unwrap();
+        fs::write(dir.path().join(".webdav_locks.json"), b"{}")
+            .await
+            .unwrap();
+        let signer =
+            crate::infrastructure::services::content_reference::ReferenceSigner::new(vec![0; 32]);
+        let reference = signer.encode(&crate::application::ports::storage_ports::StoredContent {
+            backend: "s3".to_string(),
+            locator: serde_json::json!({ "key": "files/1" }),
+            size: 5,
+        });
+        fs::write(home.join("remote.bin"), &reference)
+            .await
+            .unwrap();
+        // Trash, versions and shared folders hold content; only their indexes stay plain
+        for (path, content) in [
+            ("Shared/data.json", &b"shared data"[..]),
+            (".trash/files/u1/f1", b"trashed"),
+            (".trash/trash_index.json", b"[]"),
+            (".versions/f1/v1.bin", b"old version"),
+            (".versions/f1/index.json", b"[]"),
+            (".uploads/u1/data", b"partial upload"),
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+            fs::write(path, content).await.unwrap();
+        }
+
+        let service = FileEncryptionService::new(dir.path(), &config(keys.path(), 1024))
+            .await
+            .unwrap();
+        assert_eq!(service.migrate_plaintext_files().await.unwrap(), (4, 0));
+        for encrypted in [
+            home.join("notes.txt"),
+            dir.path().join("Shared/data.json"),
+            dir.path().join(".trash/files/u1/f1"),
+            dir.path().join(".versions/f1/v1.bin"),
+        ] {
+            assert!(service.is_encrypted(&encrypted).await.unwrap());
+        }
+        for untouched in [
+            dir.path().join("folder_ids.json"),
+            dir.path().join(".webdav_locks.json"),
+            dir.path().join(".trash/trash_index.json"),
+            dir.path().join(".versions/f1/index.json"),
+            dir.path().join(".uploads/u1/data"),
+            home.join("remote.bin"),
+        ] {
+            assert!(!service.is_encrypted(&untouched).await.unwrap());
+        }
+        assert_eq!(fs::read(home.join("remote.bin")).await.unwrap(), reference);
+
+        // The key file is managed by the server, so callers cannot pick the key
+        assert!(service.rotate_master_key(Some(vec![7; 32])).await.is_err());
+        let (new_id, rewrapped) = service.rotate_master_key(None).await.unwrap();
+        assert_eq!(rewrapped, 2);
+        drop(service);
+
+        // A fresh instance picks up the rotated master key from disk
+        let reopened = FileEncryptionService::new(dir.path(), &config(keys.path(), 1024))
+            .await
+            .unwrap();
+        assert_eq!(reopened.state.read().await.master.id, new_id);
+        let data = fs::read(home.join("notes.txt")).await.unwrap();
+        assert_eq!(reopened.decrypt_bytes(data).await.unwrap(), b"secret notes");
+    }
+
+    #[tokio::test]
+    async fn test_master_key_must_live_outside_storage() {
+        let dir = tempdir().unwrap();
+
+        let missing = EncryptionConfig {
+            enabled: true,
+            ..EncryptionConfig::default()
+        };
+        assert!(FileEncryptionService::new(dir.path(), &missing)
+            .await
+            .is_err());
+
+        let inside = config(dir.path(), 1024);
+        assert!(FileEncryptionService::new(dir.path(), &inside)
+            .await
+            .is_err());
+        assert!(!dir.path().join("master.key").exists());
+    }
+}
@@ -60,6 +60,8 @@ pub struct FileMetadata {
     pub created_at: Option<u64>,
     /// Timestamp de modificación (UNIX epoch seconds)
     pub modified_at: Option<u64>,
+    /// Versión del contenido usada como ETag (para archivos)
+    pub etag: Option<String>,
     /// Acceso previo (usado para LRU)
     pub last_access: Instant,
     /// Tiempo de expiración de la caché
@@ -90,6 +92,7 @@ impl FileMetadata {
             mime_type,
             created_at,
             modified_at,
+            etag: None,
             last_access: now,
             expires_at: now + ttl,
             access_count: 1,
@@ -168,6 +171,26 @@ impl FileMetadataCache {
         )
     }
 
+    /// Calcula la versión del contenido de un archivo a partir de su tamaño y
+    /// de la fecha de modificación con resolución de nanosegundos, de modo que
+    /// dos escrituras en el mismo segundo dan ETags distintos
+    pub fn content_etag(metadata: &std::fs::Metadata) -> Option<String> {
+        if !metadata.is_file() {
+            return None;
+        }
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Some(format!(
+            "{:x}{:08x}-{:x}",
+            modified.as_secs(),
+            modified.subsec_nanos(),
+            metadata.len()
+        ))
+    }
+
     /// Crea una instancia por defecto
     pub fn default() -> Self {
         Self::new(AppConfig::default(), 10_000)
@@ -308,6 +331,15 @@ impl FileMetadataCache {
         None
     }
 
+    /// Obtiene el ETag de un archivo, leyéndolo del disco si no está en caché
+    pub async fn get_etag(&self, path: &Path) -> Option<String> {
+        if let Some(etag) = self.get_metadata(path).await.and_then(|m| m.etag) {
+            return Some(etag);
+        }
+
+        self.refresh_metadata(path).await.ok()?.etag
+    }
+
     /// Refresca los metadatos de un path
     pub async fn refresh_metadata(&self, path: &Path) -> Result<FileMetadata, std::io::Error> {
         // Realizar lectura real del sistema de archivos
@@ -363,7 +395,7 @@ impl FileMetadataCache {
         };
 
         // Crear entrada de metadatos
-        let file_metadata = FileMetadata::new(
+        let mut file_metadata = FileMetadata::new(
             path.to_path_buf(),
             true,
             entry_type,
@@ -373,6 +405,7 @@ impl FileMetadataCache {
             modified_at,
             ttl,
         );
+        file_metadata.etag = Self::content_etag(&metadata);
 
         // Actualizar caché
         self.update_cache(file_metadata.clone()).await;
@@ -665,6 +698,35 @@ mod tests {
         assert!(stats.hits > 0);
     }
 
+    #[tokio::test]
+    async fn test_etag_changes_within_the_same_second() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("etag.txt");
+        fs::write(&file_path, b"first").await.unwrap();
+
+        let set_mtime = |nanos: u32| {
+            let file = std::fs::File::options()
+                .write(true)
+                .open(&file_path)
+                .unwrap();
+            file.set_modified(UNIX_EPOCH + Duration::new(1_700_000_000, nanos))
+                .unwrap();
+        };
+        set_mtime(100);
+
+        let cache = FileMetadataCache::new(AppConfig::default(), 1000);
+        let first = cache.get_etag(&file_path).await.unwrap();
+        assert_eq!(cache.get_etag(&file_path).await, Some(first.clone()));
+
+        // Mismo tamaño y mismo segundo: solo cambian los nanosegundos
+        fs::write(&file_path, b"other").await.unwrap();
+        set_mtime(200);
+        cache.invalidate(&file_path).await;
+        let second = cache.get_etag(&file_path).await.unwrap();
+        assert_ne!(first, second);
+        assert!(cache.get_etag(temp_dir.path()).await.is_none());
+    }
+
     #[tokio::test]
     async fn test_directory_operations() {
         // Crear estructura de directorios para pruebas
@@ -1,8 +1,12 @@
-use std::io::Error as IoError;
+use bytes::Bytes;
+use futures::Stream;
+use std::io::{Error as IoError, SeekFrom};
 use std::path::Path;
+use std::pin::Pin;
 use tempfile::NamedTempFile;
 use tokio::fs::{self, File, OpenOptions};
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
 use tracing::{error, warn};
 
 /// Utility functions for file system operations with proper synchronization
@@ -181,6 +185,19 @@ impl FileSystemUtils {
         Ok(())
     }
 
+    /// Opens a file and streams the byte range `[start, end)` without reading
+    /// the bytes before `start`
+    pub async fn read_range_stream<P: AsRef<Path>>(
+        path: P,
+        start: u64,
+        end: u64,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Bytes, IoError>> + Send>>, IoError> {
+        let mut file = File::open(path).await?;
+        file.seek(SeekFrom::Start(start)).await?;
+        let limited = file.take(end.saturating_sub(start));
+        Ok(Box::pin(ReaderStream::new(limited)))
+    }
+
     /// Removes a directory with parent directory syncing
     pub async fn remove_dir_with_sync<P: AsRef<Path>>(
         path: P,
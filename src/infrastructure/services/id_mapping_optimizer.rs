@@ -8,7 +8,7 @@ use tracing::{debug, error, info, warn};
 use crate::application::ports::outbound::IdMappingPort;
 use crate::common::errors::DomainError;
 use crate::domain::services::path_service::StoragePath;
-use crate::infrastructure::services::id_mapping_service::{IdMappingError, IdMappingService};
+use crate::infrastructure::services::id_mapping_service::IdMappingError;
 
 /// Maximum number of entries in the cache
 const MAX_CACHE_SIZE: usize = 10_000;
@@ -19,7 +19,7 @@ const CACHE_TTL_SECONDS: u64 = 60 * 5; // 5 minutes
 /// Optimizer for batch ID mapping operations
 pub struct IdMappingOptimizer {
     /// Base ID mapping service
-    base_service: Arc<IdMappingService>,
+    base_service: Arc<dyn IdMappingPort>,
 
     /// Path to ID cache (path -> id)
     path_to_id_cache: RwLock<HashMap<String, (String, Instant)>>,
@@ -86,7 +86,7 @@ struct BatchResult {
 
 impl IdMappingOptimizer {
     /// Creates a new optimizer for the ID mapping service
-    pub fn new(base_service: Arc<IdMappingService>) -> Self {
+    pub fn new(base_service: Arc<dyn IdMappingPort>) -> Self {
         Self {
             base_service,
             path_to_id_cache: RwLock::new(HashMap::with_capacity(1000)),
@@ -275,7 +275,7 @@ impl IdMappingOptimizer {
         // Guardar los cambios al disco en segundo plano
         let service_clone = self.base_service.clone();
         tokio::spawn(async move {
-            if let Err(e) = service_clone.save_pending_changes().await {
+            if let Err(e) = service_clone.save_changes().await {
                 error!("Error saving ID mapping changes: {}", e);
             }
         });
@@ -536,6 +536,20 @@ impl IdMappingPort for IdMappingOptimizer {
         Ok(result)
     }
 
+    async fn update_path_with_descendants(
+        &self,
+        id: &str,
+        new_path: &StoragePath,
+    ) -> Result<(), DomainError> {
+        // Cualquier entrada en caché puede colgar de la ruta antigua
+        self.path_to_id_cache.write().await.clear();
+        self.id_to_path_cache.write().await.clear();
+
+        self.base_service
+            .update_path_with_descendants(id, new_path)
+            .await
+    }
+
     async fn remove_id(&self, id: &str) -> Result<(), DomainError> {
         // Invalidar caché para este ID
         {
@@ -565,6 +579,7 @@ impl IdMappingPort for IdMappingOptimizer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infrastructure::services::id_mapping_service::IdMappingService;
     use tempfile::tempdir;
 
     async fn create_test_service() -> (Arc<IdMappingService>, Arc<IdMappingOptimizer>) {
@@ -413,6 +413,62 @@ impl IdMappingService {
         }
     }
 
+    /// Actualiza la ruta de un ID y reescribe el prefijo de todas las rutas
+    /// que cuelgan de ella bajo un único lock de escritura
+    pub async fn update_path_with_descendants(
+        &self,
+        id: &str,
+        new_path: &StoragePath,
+    ) -> Result<(), IdMappingError> {
+        let mut map = time::timeout(self.timeouts.lock_timeout(), self.id_map.write())
+            .await
+            .map_err(|_| {
+                IdMappingError::Timeout("Timeout acquiring write lock for ID update".to_string())
+            })?;
+
+        let old_path = map
+            .id_to_path
+            .get(id)
+            .cloned()
+            .ok_or_else(|| IdMappingError::NotFound(id.to_string()))?;
+        let new_path_str = new_path.to_string();
+        if old_path == new_path_str {
+            return Ok(());
+        }
+
+        let old_prefix = format!("{}/", old_path.trim_end_matches('/'));
+        let moved: Vec<(String, String)> = map
+            .path_to_id
+            .iter()
+            .filter(|(path, _)| **path == old_path || path.starts_with(&old_prefix))
+            .map(|(path, id)| (path.clone(), id.clone()))
+            .collect();
+
+        for (path, _) in &moved {
+            map.path_to_id.remove(path);
+        }
+        for (path, moved_id) in &moved {
+            let rewritten = format!("{}{}", new_path_str, &path[old_path.len()..]);
+            // Una entrada antigua en el destino queda huérfana
+            if let Some(stale_id) = map.path_to_id.insert(rewritten.clone(), moved_id.clone()) {
+                map.id_to_path.remove(&stale_id);
+            }
+            map.id_to_path.insert(moved_id.clone(), rewritten);
+        }
+
+        drop(map);
+        self.mark_pending().await;
+
+        tracing::debug!(
+            "Updated {} path mappings: {} -> {}",
+            moved.len(),
+            old_path,
+            new_path_str
+        );
+
+        Ok(())
+    }
+
     /// Elimina un ID del mapa
     pub async fn remove_id(&self, id: &str) -> Result<(), IdMappingError> {
         let write_result =
@@ -557,6 +613,27 @@ impl IdMappingPort for IdMappingService {
         })
     }
 
+    /// Actualiza la ruta de un ID y la de todos sus descendientes
+    async fn update_path_with_descendants(
+        &self,
+        id: &str,
+        new_path: &StoragePath,
+    ) -> Result<(), DomainError> {
+        self.update_path_with_descendants(id, new_path)
+            .await
+            .map_err(|e| {
+                DomainError::internal_error(
+                    "IdMapping",
+                    format!(
+                        "Failed to update subtree for ID: {} to {}: {}",
+                        id,
+                        new_path.to_string(),
+                        e
+                    ),
+                )
+            })
+    }
+
     /// Elimina un ID del mapa
     async fn remove_id(&self, id: &str) -> Result<(), DomainError> {
         self.remove_id(id).await.map_err(|e| {
@@ -648,6 +725,48 @@ mod tests {
         assert_eq!(retrieved_path, new_path, "Path should be updated");
     }
 
+    #[tokio::test]
+    async fn test_update_path_with_descendants() {
+        let service = IdMappingService::new_in_memory();
+
+        let folder = service
+            .get_or_create_id(&StoragePath::from_string("/docs"))
+            .await
+            .unwrap();
+        let child = service
+            .get_or_create_id(&StoragePath::from_string("/docs/2024/report"))
+            .await
+            .unwrap();
+        let sibling = service
+            .get_or_create_id(&StoragePath::from_string("/docs-old"))
+            .await
+            .unwrap();
+
+        let new_path = StoragePath::from_string("/archive/docs");
+        service
+            .update_path_with_descendants(&folder, &new_path)
+            .await
+            .unwrap();
+
+        assert_eq!(service.get_path_by_id(&folder).await.unwrap(), new_path);
+        assert_eq!(
+            service.get_path_by_id(&child).await.unwrap(),
+            StoragePath::from_string("/archive/docs/2024/report")
+        );
+        // Una ruta que solo comparte el prefijo textual no se toca
+        assert_eq!(
+            service.get_path_by_id(&sibling).await.unwrap(),
+            StoragePath::from_string("/docs-old")
+        );
+        assert_eq!(
+            service
+                .get_or_create_id(&StoragePath::from_string("/archive/docs/2024/report"))
+                .await
+                .unwrap(),
+            child
+        );
+    }
+
     #[tokio::test]
     async fn test_save_and_load() {
         let temp_dir = tempdir().unwrap();
@@ -0,0 +1,175 @@
+use async_trait::async_trait;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::application::ports::thumbnail_ports::ThumbnailRendererPort;
+use crate::common::errors::DomainError;
+use crate::domain::entities::thumbnail::{ThumbnailFormat, ThumbnailSize};
+
+/// Calidad de compresión de las miniaturas
+const OUTPUT_QUALITY: &str = "82";
+
+/// Genera miniaturas con ImageMagick.
+///
+/// La imagen se pasa por stdin y la miniatura se lee de stdout, sin ficheros
+/// temporales. El decodificador se fija a partir del tipo MIME para que
+/// ImageMagick nunca deduzca el formato del contenido, y los recursos de cada
+/// proceso están limitados para contener imágenes maliciosas.
+pub struct ImageMagickRenderer {
+    converter: String,
+    timeout: Duration,
+}
+
+impl ImageMagickRenderer {
+    pub fn new(converter: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            converter: converter.into(),
+            timeout,
+        }
+    }
+
+    /// Comprueba que el ejecutable configurado existe y responde
+    pub async fn is_available(&self) -> bool {
+        Command::new(&self.converter)
+            .arg("-version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+
+    /// Decodificador de ImageMagick para un tipo MIME admitido
+    fn input_coder(mime_type: &str) -> Option<&'static str> {
+        match mime_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "image/jpeg" => Some("jpeg"),
+            "image/png" => Some("png"),
+            "image/gif" => Some("gif"),
+            "image/webp" => Some("webp"),
+            "image/bmp" => Some("bmp"),
+            "image/tiff" => Some("tiff"),
+            _ => None,
+        }
+    }
+
+    fn arguments(coder: &str, size: ThumbnailSize, format: ThumbnailFormat) -> Vec<String> {
+        let dimension = size.max_dimension();
+        let mut args: Vec<String> = [
+            "-limit", "memory", "256MiB", "-limit", "map", "512MiB", "-limit", "area", "128MP",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+
+        // Solo el primer fotograma de GIF y TIFF multipágina
+        args.push(format!("{}:-[0]", coder));
+        args.push("-auto-orient".to_string());
+        args.push("-thumbnail".to_string());
+        args.push(format!("{}x{}>", dimension, dimension));
+        if format == ThumbnailFormat::Jpeg {
+            // JPEG no tiene transparencia
+            args.extend(["-background", "white", "-flatten"].map(String::from));
+        }
+        args.extend(["-strip", "-quality", OUTPUT_QUALITY].map(String::from));
+        args.push(
+            match format {
+                ThumbnailFormat::Jpeg => "jpeg:-",
+                ThumbnailFormat::Webp => "webp:-",
+            }
+            .to_string(),
+        );
+        args
+    }
+
+    fn render_error(message: impl std::fmt::Display) -> DomainError {
+        DomainError::internal_error(
+            "Thumbnail",
+            format!("Failed to render thumbnail: {}", message),
+        )
+    }
+}
+
+#[async_trait]
+impl ThumbnailRendererPort for ImageMagickRenderer {
+    async fn render(
+        &self,
+        source: &[u8],
+        mime_type: &str,
+        size: ThumbnailSize,
+        format: ThumbnailFormat,
+    ) -> Result<Vec<u8>, DomainError> {
+        let coder = Self::input_coder(mime_type).ok_or_else(|| {
+            DomainError::validation_error(format!("Unsupported image type: {}", mime_type))
+        })?;
+
+        let mut child = Command::new(&self.converter)
+            .args(Self::arguments(coder, size, format))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(Self::render_error)?;
+
+        // Escribir en paralelo con la lectura para no bloquear la tubería
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| Self::render_error("stdin unavailable"))?;
+        let input = source.to_vec();
+        let writer = tokio::spawn(async move {
+            let _ = stdin.write_all(&input).await;
+        });
+
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| DomainError::timeout("Thumbnail", "Thumbnail rendering timed out"))?
+            .map_err(Self::render_error)?;
+        let _ = writer.await;
+
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(Self::render_error(
+                String::from_utf8_lossy(&output.stderr).trim(),
+            ));
+        }
+        Ok(output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arguments_pin_decoder_and_orientation() {
+        let args =
+            ImageMagickRenderer::arguments("png", ThumbnailSize::Small, ThumbnailFormat::Jpeg);
+        assert!(args.contains(&"png:-[0]".to_string()));
+        assert!(args.contains(&"-auto-orient".to_string()));
+        assert!(args.contains(&"128x128>".to_string()));
+        assert!(args.contains(&"-flatten".to_string()));
+        assert_eq!(args.last().unwrap(), "jpeg:-");
+
+        let args =
+            ImageMagickRenderer::arguments("jpeg", ThumbnailSize::Large, ThumbnailFormat::Webp);
+        assert!(!args.contains(&"-flatten".to_string()));
+        assert_eq!(args.last().unwrap(), "webp:-");
+    }
+
+    #[test]
+    fn test_input_coder_rejects_unknown_types() {
+        assert_eq!(ImageMagickRenderer::input_coder("image/JPEG"), Some("jpeg"));
+        assert_eq!(ImageMagickRenderer::input_coder("image/svg+xml"), None);
+        assert_eq!(ImageMagickRenderer::input_coder("text/plain"), None);
+    }
+}
@@ -1,10 +1,19 @@
 pub mod buffer_pool;
 pub mod cache_manager;
+pub mod chunk_store;
 pub mod compression_service;
+pub mod content_reference;
+pub mod file_encryption_service;
 pub mod file_metadata_cache;
 pub mod file_system_i18n_service;
 pub mod file_system_utils;
 pub mod id_mapping_optimizer;
 pub mod id_mapping_service;
+pub mod image_magick_renderer;
+pub mod object_content_store;
+pub mod reminder_channels;
+pub mod s3_object_storage;
+pub mod smtp_mailer;
+pub mod text_extractor;
 pub mod trash_cleanup_service;
 pub mod zip_service;
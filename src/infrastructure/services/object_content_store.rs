@@ -0,0 +1,265 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::Stream;
+use serde_json::json;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::application::ports::storage_ports::{
+    ContentStoragePort, ObjectStoragePort, StoredContent,
+};
+use crate::common::errors::DomainError;
+
+/// Nombre de este backend en las referencias de contenido
+const BACKEND_NAME: &str = "s3";
+
+/// Prefijo de las claves de los objetos con contenido de archivos
+const KEY_PREFIX: &str = "files/";
+
+/// Contenido de los archivos en un almacén de objetos (S3 o compatible).
+///
+/// Cada contenido es el objeto `files/<uuid>`. Copiar un archivo usa la copia
+/// del propio almacén, así que el contenido no pasa por el servidor.
+pub struct ObjectContentStore {
+    objects: Arc<dyn ObjectStoragePort>,
+}
+
+impl ObjectContentStore {
+    pub fn new(objects: Arc<dyn ObjectStoragePort>) -> Self {
+        Self { objects }
+    }
+
+    /// Clave de un objeto nuevo
+    fn new_key() -> String {
+        format!("{}{}", KEY_PREFIX, uuid::Uuid::new_v4())
+    }
+
+    /// Clave del objeto de un contenido guardado. Solo se aceptan claves
+    /// creadas por `new_key`, nunca otros objetos del bucket
+    fn key_of(content: &StoredContent) -> Result<&str, DomainError> {
+        let key = content
+            .locator
+            .get("key")
+            .and_then(|key| key.as_str())
+            .ok_or_else(|| {
+                DomainError::internal_error("ObjectStorage", "Content reference without object key")
+            })?;
+        match key.strip_prefix(KEY_PREFIX).map(uuid::Uuid::parse_str) {
+            Some(Ok(_)) => Ok(key),
+            _ => Err(DomainError::internal_error(
+                "ObjectStorage",
+                format!("Content reference to an unexpected object key: {}", key),
+            )),
+        }
+    }
+
+    fn stored(key: String, size: u64) -> StoredContent {
+        StoredContent {
+            backend: BACKEND_NAME.to_string(),
+            locator: json!({ "key": key }),
+            size,
+        }
+    }
+}
+
+#[async_trait]
+impl ContentStoragePort for ObjectContentStore {
+    fn backend_name(&self) -> &'static str {
+        BACKEND_NAME
+    }
+
+    async fn put_content(
+        &self,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+    ) -> Result<StoredContent, DomainError> {
+        let key = Self::new_key();
+        let size = self.objects.put_object_stream(&key, stream).await?;
+        Ok(Self::stored(key, size))
+    }
+
+    async fn get_content_stream(
+        &self,
+        content: &StoredContent,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
+        self.objects.get_object_stream(Self::key_of(content)?).await
+    }
+
+    async fn get_content_range_stream(
+        &self,
+        content: &StoredContent,
+        start: u64,
+        end: u64,
+    ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError> {
+        self.objects
+            .get_object_range_stream(Self::key_of(content)?, start, end.min(content.size))
+            .await
+    }
+
+    async fn copy_content(&self, content: &StoredContent) -> Result<StoredContent, DomainError> {
+        // La copia la hace el propio almacén, sin descargar el contenido
+        let key = Self::new_key();
+        self.objects
+            .copy_object(Self::key_of(content)?, &key, content.size)
+            .await?;
+        Ok(Self::stored(key, content.size))
+    }
+
+    async fn release_content(&self, content: &StoredContent) -> Result<(), DomainError> {
+        self.objects.delete_object(Self::key_of(content)?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Almacén de objetos en memoria que cuenta las copias
+    #[derive(Default)]
+    struct MemoryObjects {
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+        copies: AtomicUsize,
+    }
+
+    impl MemoryObjects {
+        fn stream(
+            content: Vec<u8>,
+        ) -> Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send> {
+            Box::new(futures::stream::iter(vec![Ok(Bytes::from(content))]))
+        }
+
+        fn get(&self, key: &str) -> Result<Vec<u8>, DomainError> {
+            self.objects
+                .lock()
+                .unwrap()
+                .get(key)
+                .cloned()
+                .ok_or_else(|| DomainError::not_found("Object", key.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStoragePort for MemoryObjects {
+        async fn put_object_stream(
+            &self,
+            key: &str,
+            mut stream: Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>,
+        ) -> Result<u64, DomainError> {
+            let mut content = Vec::new();
+            while let Some(chunk) = stream.next().await {
+                content.extend_from_slice(&chunk.unwrap());
+            }
+            let size = content.len() as u64;
+            self.objects
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), content);
+            Ok(size)
+        }
+
+        async fn get_object_stream(
+            &self,
+            key: &str,
+        ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>
+        {
+            Ok(Self::stream(self.get(key)?))
+        }
+
+        async fn get_object_range_stream(
+            &self,
+            key: &str,
+            start: u64,
+            end: u64,
+        ) -> Result<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>, DomainError>
+        {
+            let content = self.get(key)?;
+            Ok(Self::stream(content[start as usize..end as usize].to_vec()))
+        }
+
+        async fn copy_object(
+            &self,
+            source_key: &str,
+            target_key: &str,
+            _size: u64,
+        ) -> Result<(), DomainError> {
+            let content = self.get(source_key)?;
+            self.copies.fetch_add(1, Ordering::SeqCst);
+            self.objects
+                .lock()
+                .unwrap()
+                .insert(target_key.to_string(), content);
+            Ok(())
+        }
+
+        async fn delete_object(&self, key: &str) -> Result<(), DomainError> {
+            self.objects.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    async fn read_all(
+        stream: Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>,
+    ) -> Vec<u8> {
+        let mut stream = Box::into_pin(stream);
+        let mut content = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            content.extend_from_slice(&chunk.unwrap());
+        }
+        content
+    }
+
+    #[tokio::test]
+    async fn test_content_lives_in_object_storage() {
+        let objects = Arc::new(MemoryObjects::default());
+        let store = ObjectContentStore::new(objects.clone());
+
+        let stream = futures::stream::iter(vec![
+            Ok(Bytes::from_static(b"hello ")),
+            Ok(Bytes::from_static(b"object storage")),
+        ]);
+        let content = store.put_content(Box::pin(stream)).await.unwrap();
+        assert_eq!(content.size, 20);
+        let key = ObjectContentStore::key_of(&content).unwrap().to_string();
+        assert_eq!(objects.get(&key).unwrap(), b"hello object storage");
+
+        let range = read_all(
+            store
+                .get_content_range_stream(&content, 6, 50)
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(range, b"object storage");
+
+        // Copying is done by the store and gets a new object
+        let copy = store.copy_content(&content).await.unwrap();
+        assert_eq!(objects.copies.load(Ordering::SeqCst), 1);
+        assert_ne!(copy, content);
+        assert_eq!(
+            read_all(store.get_content_stream(&copy).await.unwrap()).await,
+            b"hello object storage"
+        );
+
+        store.release_content(&content).await.unwrap();
+        assert!(objects.get(&key).is_err());
+        assert!(store.get_content_stream(&copy).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_keys_outside_the_content_prefix() {
+        let objects = Arc::new(MemoryObjects::default());
+        let store = ObjectContentStore::new(objects.clone());
+        for key in ["config/secrets.json", "files/../other", "files/"] {
+            let content = StoredContent {
+                backend: BACKEND_NAME.to_string(),
+                locator: json!({ "key": key }),
+                size: 1,
+            };
+            assert!(store.get_content_stream(&content).await.is_err());
+            assert!(store.release_content(&content).await.is_err());
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+
+use crate::application::ports::reminder_ports::ReminderChannel;
+use crate::common::errors::DomainError;
+use crate::domain::entities::reminder::{AlarmAction, Reminder};
+use crate::domain::entities::user::User;
+use crate::infrastructure::services::smtp_mailer::SmtpMailer;
+
+/// Cabecera con la firma HMAC-SHA256 del cuerpo de cada aviso del webhook
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-OxiCloud-Signature";
+
+/// Tiempo máximo de cada llamada al webhook
+const WEBHOOK_TIMEOUT_SECS: u64 = 30;
+
+/// Texto de un aviso: el resumen, el inicio de la instancia y la
+/// descripción de la alarma
+fn reminder_text(reminder: &Reminder) -> String {
+    let mut text = format!(
+        "{}\n\nStarts: {}\n",
+        reminder.summary,
+        reminder.occurrence_start.format("%Y-%m-%d %H:%M UTC")
+    );
+    if let Some(description) = reminder
+        .description
+        .as_deref()
+        .filter(|description| !description.trim().is_empty())
+    {
+        text.push('\n');
+        text.push_str(description);
+        text.push('\n');
+    }
+    text
+}
+
+/// Canal de las alarmas de correo (`ACTION:EMAIL`). Se envían solo al
+/// propietario del calendario: los `ATTENDEE` de la alarma se ignoran, para
+/// que nadie pueda usar el servidor de correo para escribir a direcciones
+/// ajenas (las alarmas del organizador llegan además a las copias de los
+/// invitados)
+pub struct EmailReminderChannel {
+    mailer: SmtpMailer,
+}
+
+impl EmailReminderChannel {
+    pub fn new(mailer: SmtpMailer) -> Self {
+        Self { mailer }
+    }
+}
+
+#[async_trait]
+impl ReminderChannel for EmailReminderChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn accepts(&self, action: AlarmAction) -> bool {
+        action == AlarmAction::Email
+    }
+
+    async fn deliver(&self, reminder: &Reminder, user: &User) -> Result<(), DomainError> {
+        self.mailer
+            .send(
+                &[user.email().to_string()],
+                &format!("Reminder: {}", reminder.summary),
+                &reminder_text(reminder),
+            )
+            .await
+    }
+}
+
+/// Canal que envía cada aviso, de cualquier alarma, como JSON a una URL.
+/// Con una clave, el cuerpo se firma con HMAC-SHA256 en la cabecera
+/// `X-OxiCloud-Signature: sha256=<hex>`.
+pub struct WebhookReminderChannel {
+    client: reqwest::Client,
+    url: String,
+    secret: Option<String>,
+}
+
+impl WebhookReminderChannel {
+    pub fn new(url: String, secret: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+                .build()
+                .unwrap_or_default(),
+            url,
+            secret,
+        }
+    }
+
+    fn signature(secret: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+}
+
+#[async_trait]
+impl ReminderChannel for WebhookReminderChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn accepts(&self, _action: AlarmAction) -> bool {
+        true
+    }
+
+    async fn deliver(&self, reminder: &Reminder, user: &User) -> Result<(), DomainError> {
+        let body = serde_json::to_vec(&serde_json::json!({
+            "type": "reminder",
+            "id": reminder.id.to_string(),
+            "user_id": user.id(),
+            "username": user.username(),
+            "calendar_id": reminder.calendar_id,
+            "object_id": reminder.object_id,
+            "component": reminder.component,
+            "action": reminder.action.as_str(),
+            "summary": reminder.summary,
+            "description": reminder.description,
+            "trigger_at": reminder.trigger_at,
+            "occurrence_start": reminder.occurrence_start,
+        }))
+        .map_err(|e| DomainError::internal_error("Webhook", e.to_string()))?;
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = &self.secret {
+            request = request.header(WEBHOOK_SIGNATURE_HEADER, Self::signature(secret, &body));
+        }
+        let response = request.body(body).send().await.map_err(|e| {
+            DomainError::internal_error("Webhook", format!("Webhook request failed: {}", e))
+        })?;
+
+        if !response.status().is_success() {
+            return Err(DomainError::internal_error(
+                "Webhook",
+                format!("Webhook answered {}", response.status()),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_signature() {
+        // Caso de prueba 2 de la RFC 4231
+        assert_eq!(
+            WebhookReminderChannel::signature("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
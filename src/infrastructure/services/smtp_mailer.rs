@@ -0,0 +1,333 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_native_tls::{native_tls, TlsConnector};
+use uuid::Uuid;
+
+use crate::common::config::{SmtpConfig, SmtpTls};
+use crate::common::errors::DomainError;
+
+/// Comprueba que una dirección no pueda inyectar cabeceras ni órdenes SMTP:
+/// sin caracteres de control ni `<`/`>`, que cerrarían `RCPT TO:<...>`
+fn check_address(address: &str) -> Result<&str, String> {
+    if address.is_empty()
+        || address
+            .chars()
+            .any(|c| c.is_control() || c == '<' || c == '>')
+    {
+        return Err(format!("invalid mail address: {:?}", address));
+    }
+    Ok(address)
+}
+
+/// Asunto de un correo: sin saltos de línea y, si no es ASCII, como palabra
+/// codificada (RFC 2047)
+fn encode_subject(subject: &str) -> String {
+    let subject: String = subject.chars().filter(|c| !c.is_control()).collect();
+    if subject.is_ascii() {
+        subject
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(subject.as_bytes()))
+    }
+}
+
+/// Mensaje de texto plano con el cuerpo en base64 en líneas de 76
+/// caracteres, que no necesita `8BITMIME` ni duplicar puntos
+fn format_message(from: &str, to: &[String], subject: &str, body: &str) -> Result<String, String> {
+    check_address(from)?;
+    let to = to
+        .iter()
+        .map(|address| check_address(address))
+        .collect::<Result<Vec<_>, _>>()?;
+    let domain = from.rsplit('@').next().unwrap_or("localhost");
+    let encoded = STANDARD.encode(body.replace("\r\n", "\n").replace('\n', "\r\n").as_bytes());
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\n\
+         MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\
+         Content-Transfer-Encoding: base64\r\n\r\n",
+        from,
+        to.join(", "),
+        encode_subject(subject),
+        Utc::now().to_rfc2822(),
+        Uuid::new_v4(),
+        domain
+    );
+    for line in encoded.as_bytes().chunks(76) {
+        message.push_str(std::str::from_utf8(line).unwrap_or_default());
+        message.push_str("\r\n");
+    }
+    Ok(message)
+}
+
+trait SmtpStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> SmtpStream for T {}
+
+/// Conexión con un servidor SMTP, a la espera de órdenes
+struct SmtpConnection {
+    stream: BufReader<Box<dyn SmtpStream>>,
+}
+
+impl SmtpConnection {
+    /// Lee una respuesta, con todas sus líneas, y comprueba su código
+    async fn expect(&mut self, code: u16) -> Result<String, String> {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            let read = self
+                .stream
+                .read_line(&mut line)
+                .await
+                .map_err(|e| e.to_string())?;
+            if read == 0 {
+                return Err("connection closed by the server".to_string());
+            }
+            let line = line.trim_end();
+            reply.push_str(line.get(4..).unwrap_or_default());
+            reply.push('\n');
+            // Las líneas `250-...` continúan la respuesta y `250 ...` la termina
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return match line.get(..3).and_then(|status| status.parse::<u16>().ok()) {
+                    Some(status) if status == code => Ok(reply),
+                    _ => Err(format!("unexpected reply: {}", line)),
+                };
+            }
+        }
+    }
+
+    /// Envía una orden y comprueba el código de la respuesta
+    async fn command(&mut self, command: &str, code: u16) -> Result<String, String> {
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{}\r\n", command).as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+        stream.flush().await.map_err(|e| e.to_string())?;
+        self.expect(code).await
+    }
+}
+
+/// Cliente SMTP mínimo para enviar correos de texto a través de un
+/// servidor de correo, con `STARTTLS` o TLS directo y `AUTH PLAIN`
+pub struct SmtpMailer {
+    config: SmtpConfig,
+    host: String,
+}
+
+impl SmtpMailer {
+    /// Cliente para el servidor configurado, o `None` si no hay ninguno
+    pub fn new(config: &SmtpConfig) -> Option<Self> {
+        let host = config.host.clone().filter(|host| !host.trim().is_empty())?;
+        Some(Self {
+            config: config.clone(),
+            host,
+        })
+    }
+
+    /// Envía un correo de texto a los destinatarios
+    pub async fn send(&self, to: &[String], subject: &str, body: &str) -> Result<(), DomainError> {
+        if to.is_empty() {
+            return Ok(());
+        }
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        match tokio::time::timeout(timeout, self.transaction(to, subject, body)).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(DomainError::internal_error(
+                "Mail",
+                format!("SMTP delivery through {} failed: {}", self.host, e),
+            )),
+            Err(_) => Err(DomainError::internal_error(
+                "Mail",
+                format!("SMTP delivery through {} timed out", self.host),
+            )),
+        }
+    }
+
+    async fn tls(&self, stream: Box<dyn SmtpStream>) -> Result<Box<dyn SmtpStream>, String> {
+        let connector = native_tls::TlsConnector::new().map_err(|e| e.to_string())?;
+        let stream = TlsConnector::from(connector)
+            .connect(&self.host, stream)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(Box::new(stream))
+    }
+
+    async fn transaction(&self, to: &[String], subject: &str, body: &str) -> Result<(), String> {
+        // Las direcciones se comprueban antes de conectar para no dejar una
+        // transacción a medias
+        let from = check_address(&self.config.from)?;
+        let message = format_message(from, to, subject, body)?;
+
+        let tcp = TcpStream::connect((self.host.as_str(), self.config.port))
+            .await
+            .map_err(|e| e.to_string())?;
+        let stream: Box<dyn SmtpStream> = match self.config.tls {
+            SmtpTls::Tls => self.tls(Box::new(tcp)).await?,
+            _ => Box::new(tcp),
+        };
+        let mut connection = SmtpConnection {
+            stream: BufReader::new(stream),
+        };
+        let client = from.rsplit('@').next().unwrap_or("localhost");
+
+        connection.expect(220).await?;
+        connection.command(&format!("EHLO {}", client), 250).await?;
+        if self.config.tls == SmtpTls::StartTls {
+            connection.command("STARTTLS", 220).await?;
+            // El servidor no envía nada más antes de negociar TLS
+            connection = SmtpConnection {
+                stream: BufReader::new(self.tls(connection.stream.into_inner()).await?),
+            };
+            connection.command(&format!("EHLO {}", client), 250).await?;
+        }
+        if let (Some(username), Some(password)) = (&self.config.username, &self.config.password) {
+            let credentials = format!("\0{}\0{}", username, password);
+            connection
+                .command(
+                    &format!("AUTH PLAIN {}", STANDARD.encode(credentials.as_bytes())),
+                    235,
+                )
+                .await?;
+        }
+
+        connection
+            .command(&format!("MAIL FROM:<{}>", from), 250)
+            .await?;
+        for recipient in to {
+            connection
+                .command(&format!("RCPT TO:<{}>", check_address(recipient)?), 250)
+                .await?;
+        }
+        connection.command("DATA", 354).await?;
+        connection.command(&format!("{}.", message), 250).await?;
+        connection.command("QUIT", 221).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Servidor SMTP de prueba: acepta una conexión, responde a cada orden
+    /// y devuelve las órdenes recibidas y el mensaje de `DATA`
+    async fn fake_server(listener: TcpListener) -> (Vec<String>, String) {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut socket = BufReader::new(socket);
+        let mut commands = Vec::new();
+        let mut message = String::new();
+        socket
+            .get_mut()
+            .write_all(b"220 mx ready\r\n")
+            .await
+            .unwrap();
+        loop {
+            let mut line = String::new();
+            if socket.read_line(&mut line).await.unwrap() == 0 {
+                break;
+            }
+            let command = line.trim_end().to_string();
+            let reply: &[u8] = if command.starts_with("EHLO") {
+                b"250-mx\r\n250 AUTH PLAIN\r\n"
+            } else if command.starts_with("AUTH") {
+                b"235 authenticated\r\n"
+            } else if command == "DATA" {
+                socket.get_mut().write_all(b"354 go on\r\n").await.unwrap();
+                loop {
+                    let mut line = String::new();
+                    socket.read_line(&mut line).await.unwrap();
+                    if line == ".\r\n" {
+                        break;
+                    }
+                    message.push_str(&line);
+                }
+                b"250 queued\r\n"
+            } else if command == "QUIT" {
+                socket.get_mut().write_all(b"221 bye\r\n").await.unwrap();
+                commands.push(command);
+                break;
+            } else {
+                b"250 ok\r\n"
+            };
+            socket.get_mut().write_all(reply).await.unwrap();
+            commands.push(command);
+        }
+        (commands, message)
+    }
+
+    #[test]
+    fn test_encode_subject() {
+        assert_eq!(encode_subject("Standup"), "Standup");
+        assert_eq!(encode_subject("Café\r\n"), "=?UTF-8?B?Q2Fmw6k=?=");
+    }
+
+    #[test]
+    fn test_rejects_injected_addresses() {
+        let from = "oxicloud@example.com";
+        for address in [
+            "ana@example.com>\r\nRCPT TO:<eve@example.com",
+            "ana@example.com\r\nBcc: eve@example.com",
+            "<ana@example.com>",
+            "",
+        ] {
+            assert!(check_address(address).is_err());
+            assert!(format_message(from, &[address.to_string()], "Hi", "Hi").is_err());
+        }
+        assert!(format_message(from, &["ana@example.com".to_string()], "Hi", "Hi").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_conversation() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(fake_server(listener));
+
+        assert!(SmtpMailer::new(&SmtpConfig::default()).is_none());
+        let mailer = SmtpMailer::new(&SmtpConfig {
+            host: Some("127.0.0.1".to_string()),
+            port,
+            tls: SmtpTls::None,
+            username: Some("oxi".to_string()),
+            password: Some("secret".to_string()),
+            from: "oxicloud@example.com".to_string(),
+            timeout_secs: 5,
+        })
+        .unwrap();
+        mailer
+            .send(
+                &[
+                    "ana@example.com".to_string(),
+                    "luis@example.com".to_string(),
+                ],
+                "Reminder: Standup",
+                "Standup\n.\nStarts soon",
+            )
+            .await
+            .unwrap();
+
+        let (commands, message) = server.await.unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                "EHLO example.com".to_string(),
+                format!("AUTH PLAIN {}", STANDARD.encode(b"\0oxi\0secret")),
+                "MAIL FROM:<oxicloud@example.com>".to_string(),
+                "RCPT TO:<ana@example.com>".to_string(),
+                "RCPT TO:<luis@example.com>".to_string(),
+                "DATA".to_string(),
+                "QUIT".to_string(),
+            ]
+        );
+
+        let (headers, body) = message.split_once("\r\n\r\n").unwrap();
+        assert!(headers.contains("To: ana@example.com, luis@example.com\r\n"));
+        assert!(headers.contains("Subject: Reminder: Standup\r\n"));
+        assert!(headers.contains("Content-Transfer-Encoding: base64"));
+        // El punto suelto del cuerpo no termina el mensaje al ir codificado
+        let body = STANDARD.decode(body.replace("\r\n", "")).unwrap();
+        assert_eq!(body, b"Standup\r\n.\r\nStarts soon");
+    }
+}
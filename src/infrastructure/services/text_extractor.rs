@@ -0,0 +1,672 @@
+use flate2::read::ZlibDecoder;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Cursor, Read};
+
+use crate::application::ports::content_index_ports::TextExtractorPort;
+use crate::domain::entities::content_index::normalize_text;
+
+/// Límite de bytes descomprimidos por parte de un documento, para que un
+/// archivo malicioso no agote la memoria
+const MAX_INFLATED_PART: u64 = 64 * 1024 * 1024;
+
+/// Bytes iniciales que se inspeccionan para descartar archivos binarios
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// Extensiones de texto plano, Markdown y código fuente
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt",
+    "text",
+    "md",
+    "markdown",
+    "rst",
+    "adoc",
+    "org",
+    "tex",
+    "csv",
+    "tsv",
+    "log",
+    "ini",
+    "cfg",
+    "conf",
+    "toml",
+    "yaml",
+    "yml",
+    "json",
+    "xml",
+    "html",
+    "htm",
+    "css",
+    "scss",
+    "svg",
+    "rs",
+    "py",
+    "js",
+    "mjs",
+    "ts",
+    "tsx",
+    "jsx",
+    "go",
+    "java",
+    "kt",
+    "kts",
+    "scala",
+    "c",
+    "h",
+    "cc",
+    "cpp",
+    "hpp",
+    "cs",
+    "rb",
+    "php",
+    "pl",
+    "lua",
+    "swift",
+    "m",
+    "r",
+    "sql",
+    "sh",
+    "bash",
+    "zsh",
+    "fish",
+    "ps1",
+    "bat",
+    "vue",
+    "svelte",
+    "dart",
+    "ex",
+    "exs",
+    "erl",
+    "hs",
+    "clj",
+    "el",
+    "vim",
+    "gradle",
+    "properties",
+    "env",
+    "dockerfile",
+    "makefile",
+    "cmake",
+];
+
+/// Tipos MIME de texto que no empiezan por `text/`
+const TEXT_MIME_TYPES: &[&str] = &[
+    "application/json",
+    "application/xml",
+    "application/javascript",
+    "application/x-javascript",
+    "application/x-sh",
+    "application/x-yaml",
+    "application/yaml",
+    "application/toml",
+    "application/sql",
+    "application/x-httpd-php",
+    "image/svg+xml",
+];
+
+/// Formato del que se sabe extraer texto
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DocumentKind {
+    PlainText,
+    Pdf,
+    Word,
+    Spreadsheet,
+    Presentation,
+}
+
+/// Extractor de texto para texto plano, Markdown, código, PDF y documentos
+/// de Office Open XML (docx, xlsx, pptx).
+///
+/// La extracción de PDF lee los operadores de texto de los flujos de
+/// contenido sin comprimir o comprimidos con Flate; el texto de fuentes con
+/// codificaciones propias (CID) no se puede recuperar sin sus tablas
+/// ToUnicode y se descarta.
+#[derive(Debug, Default, Clone)]
+pub struct DocumentTextExtractor;
+
+impl DocumentTextExtractor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn kind(name: &str, mime_type: &str) -> Option<DocumentKind> {
+        let mime_type = mime_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        let lower_name = name.to_ascii_lowercase();
+        let extension = lower_name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext)
+            .unwrap_or(lower_name.as_str());
+
+        match extension {
+            "pdf" => return Some(DocumentKind::Pdf),
+            "docx" | "docm" | "dotx" => return Some(DocumentKind::Word),
+            "xlsx" | "xlsm" => return Some(DocumentKind::Spreadsheet),
+            "pptx" | "pptm" | "ppsx" => return Some(DocumentKind::Presentation),
+            ext if TEXT_EXTENSIONS.contains(&ext) => return Some(DocumentKind::PlainText),
+            _ => {}
+        }
+
+        match mime_type.as_str() {
+            "application/pdf" => Some(DocumentKind::Pdf),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document" => {
+                Some(DocumentKind::Word)
+            }
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet" => {
+                Some(DocumentKind::Spreadsheet)
+            }
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation" => {
+                Some(DocumentKind::Presentation)
+            }
+            mime if mime.starts_with("text/") || TEXT_MIME_TYPES.contains(&mime) => {
+                Some(DocumentKind::PlainText)
+            }
+            _ => None,
+        }
+    }
+
+    fn plain_text(content: &[u8]) -> Option<String> {
+        let sniff = &content[..content.len().min(BINARY_SNIFF_LEN)];
+        if sniff.contains(&0) {
+            return None;
+        }
+        let content = content.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(content);
+        Some(String::from_utf8_lossy(content).into_owned())
+    }
+
+    /// Texto de las partes XML de un paquete OOXML cuyo nombre cumple `wanted`
+    fn ooxml_text(content: &[u8], wanted: impl Fn(&str) -> bool) -> Option<String> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(content)).ok()?;
+        let mut parts: Vec<String> = archive
+            .file_names()
+            .filter(|name| wanted(name))
+            .map(String::from)
+            .collect();
+        // slide2.xml antes que slide10.xml
+        parts.sort_by_key(|name| {
+            let (digits, rest): (String, String) = name.chars().partition(|c| c.is_ascii_digit());
+            (rest, digits.parse::<u64>().unwrap_or(0))
+        });
+
+        let mut text = String::new();
+        for part in parts {
+            let Ok(entry) = archive.by_name(&part) else {
+                continue;
+            };
+            let mut xml = Vec::new();
+            if entry.take(MAX_INFLATED_PART).read_to_end(&mut xml).is_ok() {
+                Self::xml_text_runs(&xml, &mut text);
+                text.push('\n');
+            }
+        }
+        Some(text)
+    }
+
+    /// Añade a `out` el texto de los elementos `t` (`w:t`, `a:t`, o las
+    /// cadenas de una hoja), separando párrafos y celdas
+    fn xml_text_runs(xml: &[u8], out: &mut String) {
+        let mut reader = Reader::from_reader(xml);
+        let mut buffer = Vec::new();
+        let mut in_text = false;
+
+        loop {
+            match reader.read_event_into(&mut buffer) {
+                Ok(Event::Start(e)) => in_text = e.local_name().as_ref() == b"t",
+                Ok(Event::Empty(e)) => {
+                    if matches!(e.local_name().as_ref(), b"tab" | b"br" | b"cr") {
+                        out.push(' ');
+                    }
+                }
+                Ok(Event::Text(e)) if in_text => {
+                    if let Ok(text) = e.unescape() {
+                        out.push_str(&text);
+                    }
+                }
+                Ok(Event::End(e)) => {
+                    in_text = false;
+                    if matches!(e.local_name().as_ref(), b"p" | b"si" | b"c") {
+                        out.push('\n');
+                    }
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+            buffer.clear();
+        }
+    }
+
+    fn pdf_text(content: &[u8]) -> Option<String> {
+        if !content.starts_with(b"%PDF") {
+            return None;
+        }
+
+        let mut text = String::new();
+        let mut position = 0;
+        while let Some(found) = find(&content[position..], b"stream") {
+            let keyword = position + found;
+            position = keyword + b"stream".len();
+
+            // Saltar "endstream" y palabras que solo contienen "stream"
+            if keyword >= 3 && &content[keyword - 3..keyword] == b"end" {
+                continue;
+            }
+            let mut data_start = position;
+            match content.get(data_start) {
+                Some(b'\r') if content.get(data_start + 1) == Some(&b'\n') => data_start += 2,
+                Some(b'\n') | Some(b'\r') => data_start += 1,
+                _ => continue,
+            }
+            let Some(length) = find(&content[data_start..], b"endstream") else {
+                break;
+            };
+            let data = &content[data_start..data_start + length];
+            position = data_start + length;
+
+            // Diccionario del flujo: desde el último "obj" hasta "stream"
+            let dict_start = rfind(&content[..keyword], b"obj").unwrap_or(0);
+            let dict = &content[dict_start..keyword];
+            if find(dict, b"/Image").is_some() || find(dict, b"/Length1").is_some() {
+                continue;
+            }
+
+            let decoded;
+            let stream = if find(dict, b"/Filter").is_none() {
+                data
+            } else if find(dict, b"/FlateDecode").is_some() && count(dict, b"/Filter") == 1 {
+                let mut inflated = Vec::new();
+                let decoder = ZlibDecoder::new(data);
+                if decoder
+                    .take(MAX_INFLATED_PART)
+                    .read_to_end(&mut inflated)
+                    .is_err()
+                    && inflated.is_empty()
+                {
+                    continue;
+                }
+                decoded = inflated;
+                &decoded
+            } else {
+                continue;
+            };
+
+            pdf_content_text(stream, &mut text);
+        }
+
+        Some(text)
+    }
+}
+
+impl TextExtractorPort for DocumentTextExtractor {
+    fn supports(&self, name: &str, mime_type: &str) -> bool {
+        Self::kind(name, mime_type).is_some()
+    }
+
+    fn extract(&self, name: &str, mime_type: &str, content: &[u8]) -> Option<String> {
+        let text = match Self::kind(name, mime_type)? {
+            DocumentKind::PlainText => Self::plain_text(content),
+            DocumentKind::Pdf => Self::pdf_text(content),
+            DocumentKind::Word => Self::ooxml_text(content, |part| {
+                part == "word/document.xml"
+                    || part == "word/footnotes.xml"
+                    || ((part.starts_with("word/header") || part.starts_with("word/footer"))
+                        && part.ends_with(".xml"))
+            }),
+            DocumentKind::Spreadsheet => Self::ooxml_text(content, |part| {
+                part == "xl/sharedStrings.xml"
+                    || (part.starts_with("xl/worksheets/sheet") && part.ends_with(".xml"))
+            }),
+            DocumentKind::Presentation => Self::ooxml_text(content, |part| {
+                (part.starts_with("ppt/slides/slide") || part.starts_with("ppt/notesSlides/"))
+                    && part.ends_with(".xml")
+            }),
+        }?;
+
+        let text = normalize_text(&text);
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+}
+
+fn count(haystack: &[u8], needle: &[u8]) -> usize {
+    haystack
+        .windows(needle.len())
+        .filter(|window| *window == needle)
+        .count()
+}
+
+/// Operando pendiente de un operador de texto
+enum PdfOperand {
+    Text(Vec<u8>),
+    Array(Vec<(Vec<u8>, f64)>),
+    Other,
+}
+
+/// Lee los operadores de texto (`Tj`, `TJ`, `'`, `"`) de un flujo de
+/// contenido y añade su texto a `out`
+fn pdf_content_text(stream: &[u8], out: &mut String) {
+    let mut operands: Vec<PdfOperand> = Vec::new();
+    let mut in_text_object = false;
+    let mut i = 0;
+
+    while i < stream.len() {
+        match stream[i] {
+            b'(' => {
+                let (bytes, next) = pdf_literal_string(stream, i + 1);
+                operands.push(PdfOperand::Text(bytes));
+                i = next;
+            }
+            b'<' if stream.get(i + 1) == Some(&b'<') => {
+                operands.push(PdfOperand::Other);
+                i += 2;
+            }
+            b'<' => {
+                let (bytes, next) = pdf_hex_string(stream, i + 1);
+                operands.push(PdfOperand::Text(bytes));
+                i = next;
+            }
+            b'[' => {
+                let (items, next) = pdf_text_array(stream, i + 1);
+                operands.push(PdfOperand::Array(items));
+                i = next;
+            }
+            b'%' => {
+                while i < stream.len() && stream[i] != b'\n' && stream[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == b'\'' || c == b'"' || c == b'*' => {
+                let start = i;
+                i += 1;
+                while i < stream.len() && (stream[i].is_ascii_alphanumeric() || stream[i] == b'*') {
+                    i += 1;
+                }
+                match &stream[start..i] {
+                    b"BT" => in_text_object = true,
+                    b"ET" => {
+                        in_text_object = false;
+                        out.push('\n');
+                    }
+                    b"Tj" | b"'" | b"\"" if in_text_object => {
+                        if let Some(PdfOperand::Text(bytes)) = operands.last() {
+                            if stream[start] != b'T' {
+                                out.push('\n');
+                            }
+                            push_pdf_string(bytes, out);
+                        }
+                    }
+                    b"TJ" if in_text_object => {
+                        if let Some(PdfOperand::Array(items)) = operands.last() {
+                            for (bytes, adjustment) in items {
+                                // Un desplazamiento grande separa palabras
+                                if *adjustment < -200.0 {
+                                    out.push(' ');
+                                }
+                                push_pdf_string(bytes, out);
+                            }
+                        }
+                    }
+                    b"Td" | b"TD" | b"T*" | b"Tm" if in_text_object => out.push(' '),
+                    _ => {}
+                }
+                operands.clear();
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// Cadena literal `( ... )` que empieza en `start`; devuelve sus bytes y la
+/// posición siguiente al paréntesis de cierre
+fn pdf_literal_string(stream: &[u8], start: usize) -> (Vec<u8>, usize) {
+    let mut bytes = Vec::new();
+    let mut depth = 1;
+    let mut i = start;
+
+    while i < stream.len() {
+        let c = stream[i];
+        i += 1;
+        match c {
+            b'\\' => {
+                let Some(&escaped) = stream.get(i) else { break };
+                i += 1;
+                match escaped {
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'b' | b'f' => {}
+                    b'\r' | b'\n' => {
+                        // Continuación de línea
+                        if escaped == b'\r' && stream.get(i) == Some(&b'\n') {
+                            i += 1;
+                        }
+                    }
+                    b'0'..=b'7' => {
+                        let mut value = (escaped - b'0') as u32;
+                        for _ in 0..2 {
+                            match stream.get(i) {
+                                Some(&digit @ b'0'..=b'7') => {
+                                    value = value * 8 + (digit - b'0') as u32;
+                                    i += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        bytes.push(value as u8);
+                    }
+                    other => bytes.push(other),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                bytes.push(c);
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+                bytes.push(c);
+            }
+            _ => bytes.push(c),
+        }
+    }
+
+    (bytes, i)
+}
+
+/// Cadena hexadecimal `< ... >` que empieza en `start`
+fn pdf_hex_string(stream: &[u8], start: usize) -> (Vec<u8>, usize) {
+    let mut digits = Vec::new();
+    let mut i = start;
+    while i < stream.len() && stream[i] != b'>' {
+        if let Some(value) = (stream[i] as char).to_digit(16) {
+            digits.push(value as u8);
+        }
+        i += 1;
+    }
+    if digits.len() % 2 == 1 {
+        digits.push(0);
+    }
+    let bytes = digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect();
+    (bytes, (i + 1).min(stream.len()))
+}
+
+/// Array de un operador `TJ`: cadenas con el ajuste de espaciado que las precede
+fn pdf_text_array(stream: &[u8], start: usize) -> (Vec<(Vec<u8>, f64)>, usize) {
+    let mut items = Vec::new();
+    let mut adjustment = 0.0;
+    let mut i = start;
+
+    while i < stream.len() {
+        match stream[i] {
+            b']' => return (items, i + 1),
+            b'(' => {
+                let (bytes, next) = pdf_literal_string(stream, i + 1);
+                items.push((bytes, adjustment));
+                adjustment = 0.0;
+                i = next;
+            }
+            b'<' => {
+                let (bytes, next) = pdf_hex_string(stream, i + 1);
+                items.push((bytes, adjustment));
+                adjustment = 0.0;
+                i = next;
+            }
+            c if c == b'-' || c == b'.' || c.is_ascii_digit() => {
+                let number_start = i;
+                i += 1;
+                while i < stream.len() && (stream[i] == b'.' || stream[i].is_ascii_digit()) {
+                    i += 1;
+                }
+                adjustment = std::str::from_utf8(&stream[number_start..i])
+                    .ok()
+                    .and_then(|number| number.parse().ok())
+                    .unwrap_or(0.0);
+            }
+            _ => i += 1,
+        }
+    }
+
+    (items, i)
+}
+
+/// Decodifica una cadena de texto de PDF (UTF-16BE con BOM, o una codificación
+/// de un byte tratada como Latin-1) descartando los caracteres de control
+fn push_pdf_string(bytes: &[u8], out: &mut String) {
+    if let Some(utf16) = bytes.strip_prefix(b"\xFE\xFF") {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        out.extend(
+            char::decode_utf16(units)
+                .filter_map(|c| c.ok())
+                .filter(|c| !c.is_control() || c.is_whitespace()),
+        );
+    } else {
+        out.extend(
+            bytes
+                .iter()
+                .map(|&b| b as char)
+                .filter(|c| !c.is_control() || c.is_whitespace()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn ooxml(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, xml) in parts {
+            writer
+                .start_file(name.to_string(), SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(xml.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_plain_text_and_code() {
+        let extractor = DocumentTextExtractor::new();
+        assert!(extractor.supports("README.md", "application/octet-stream"));
+        assert!(extractor.supports("main.rs", ""));
+        assert!(extractor.supports("notes", "text/plain; charset=utf-8"));
+        assert!(!extractor.supports("photo.jpg", "image/jpeg"));
+
+        let text = extractor.extract("lib.rs", "", b"\xEF\xBB\xBFfn  main() {\n\n}\n");
+        assert_eq!(text.as_deref(), Some("fn main() { }"));
+        assert_eq!(extractor.extract("data.txt", "", b"ab\0cd"), None);
+    }
+
+    #[test]
+    fn test_docx_and_xlsx() {
+        let extractor = DocumentTextExtractor::new();
+        let docx = ooxml(&[
+            (
+                "word/document.xml",
+                r#"<w:document xmlns:w="w"><w:body><w:p><w:r><w:t>Informe</w:t></w:r><w:r><w:tab/><w:t xml:space="preserve">de ventas &amp; costes</w:t></w:r></w:p><w:p><w:r><w:t>Segundo</w:t></w:r></w:p></w:body></w:document>"#,
+            ),
+            (
+                "word/styles.xml",
+                r#"<w:styles><w:t>ignorado</w:t></w:styles>"#,
+            ),
+        ]);
+        assert_eq!(
+            extractor.extract("a.docx", "", &docx).as_deref(),
+            Some("Informe de ventas & costes Segundo")
+        );
+
+        let xlsx = ooxml(&[(
+            "xl/sharedStrings.xml",
+            r#"<sst><si><t>Región</t></si><si><r><t>Total</t></r></si></sst>"#,
+        )]);
+        assert_eq!(
+            extractor.extract("b.xlsx", "", &xlsx).as_deref(),
+            Some("Región Total")
+        );
+    }
+
+    #[test]
+    fn test_pptx_slides_in_order() {
+        let extractor = DocumentTextExtractor::new();
+        let slide = |text: &str| {
+            format!(
+                r#"<p:sld><a:p><a:r><a:t>{}</a:t></a:r></a:p></p:sld>"#,
+                text
+            )
+        };
+        let pptx = ooxml(&[
+            ("ppt/slides/slide10.xml", &slide("diez")),
+            ("ppt/slides/slide2.xml", &slide("dos")),
+        ]);
+        assert_eq!(
+            extractor.extract("c.pptx", "", &pptx).as_deref(),
+            Some("dos diez")
+        );
+    }
+
+    #[test]
+    fn test_pdf_text_operators() {
+        let content =
+            b"BT /F1 12 Tf 72 712 Td (Hola \\(mundo\\)) Tj T* [(Fac)-30(tura)-500(anual)] TJ ET";
+        let mut compressed = ZlibEncoder::new(Vec::new(), Compression::default());
+        compressed.write_all(content).unwrap();
+        let compressed = compressed.finish().unwrap();
+
+        let mut pdf = b"%PDF-1.4\n1 0 obj\n<< /Length 10 >>\nstream\nBT (sin comprimir) Tj ET\nendstream\nendobj\n".to_vec();
+        pdf.extend_from_slice(b"2 0 obj\n<< /Filter /FlateDecode >>\nstream\n");
+        pdf.extend_from_slice(&compressed);
+        pdf.extend_from_slice(b"\nendstream\nendobj\n");
+        pdf.extend_from_slice(b"3 0 obj\n<< /Subtype /Image /Length 3 >>\nstream\nBT (imagen) Tj ET\nendstream\nendobj\n%%EOF");
+
+        let text = DocumentTextExtractor::new()
+            .extract("d.pdf", "application/pdf", &pdf)
+            .unwrap();
+        assert_eq!(text, "sin comprimir Hola (mundo) Factura anual");
+    }
+}
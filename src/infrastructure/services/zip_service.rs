@@ -4,12 +4,27 @@ use crate::{
     application::ports::inbound::{FileUseCase, FolderUseCase},
     common::errors::{DomainError, ErrorKind, Result},
 };
-use std::io::{Cursor, Read, Write};
-use std::sync::Arc;
+use bytes::Bytes;
+use futures::StreamExt;
+use std::collections::HashSet;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::*;
 use zip::{write::SimpleFileOptions, ZipWriter};
 
+/// Tamaño mínimo de los bloques que se envían al cuerpo HTTP
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Bloques que pueden esperar a que el cliente los lea; cuando el canal está
+/// lleno la escritura del ZIP se detiene hasta que el cliente consume datos
+const CHANNEL_CAPACITY: usize = 4;
+
+/// A partir de este tamaño las entradas necesitan ZIP64
+const ZIP64_THRESHOLD: u64 = u32::MAX as u64;
+
 /// Error relacionado con la creación de archivos ZIP
 #[derive(Debug, Error)]
 pub enum ZipError {
@@ -27,12 +42,19 @@ pub enum ZipError {
 
     #[error("Carpeta no encontrada: {0}")]
     FolderNotFound(String),
+
+    #[error("Descarga cancelada por el cliente")]
+    Cancelled,
 }
 
 // Implementar From<ZipError> para DomainError para permitir el uso de ?
 impl From<ZipError> for DomainError {
     fn from(err: ZipError) -> Self {
-        DomainError::new(ErrorKind::InternalError, "zip_service", err.to_string())
+        let kind = match err {
+            ZipError::FolderNotFound(_) => ErrorKind::NotFound,
+            _ => ErrorKind::InternalError,
+        };
+        DomainError::new(kind, "zip_service", err.to_string())
     }
 }
 
@@ -43,7 +65,67 @@ impl From<zip::result::ZipError> for DomainError {
     }
 }
 
-/// Servicio para crear archivos ZIP
+/// Flujo de bytes de un ZIP en construcción, listo para el cuerpo HTTP
+pub type ZipStream = ReceiverStream<std::io::Result<Bytes>>;
+
+/// Entrada del ZIP, resuelta antes de empezar a enviar bytes
+#[derive(Debug, Clone)]
+enum ZipEntry {
+    Directory(String),
+    File { path: String, file: FileDto },
+}
+
+/// Búfer compartido entre el `ZipWriter` y la tarea que envía los bloques
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Extrae el contenido acumulado si alcanza `min_len` bytes
+    fn take(&self, min_len: usize) -> Option<Vec<u8>> {
+        let mut buffer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if buffer.is_empty() || buffer.len() < min_len {
+            return None;
+        }
+        Some(std::mem::take(&mut *buffer))
+    }
+
+    /// Envía el contenido acumulado, esperando si el cliente va retrasado
+    async fn send_pending(
+        &self,
+        sender: &mpsc::Sender<std::io::Result<Bytes>>,
+        min_len: usize,
+    ) -> std::result::Result<(), ZipError> {
+        match self.take(min_len) {
+            Some(chunk) => sender
+                .send(Ok(Bytes::from(chunk)))
+                .await
+                .map_err(|_| ZipError::Cancelled),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Servicio para crear archivos ZIP.
+///
+/// Los ZIP se generan en streaming: primero se recorre la estructura de
+/// carpetas (solo metadatos) y después una tarea lee cada archivo con
+/// `get_file_stream` y emite los bytes comprimidos a medida que se producen.
+/// El canal hacia el cuerpo HTTP está acotado, por lo que la memoria usada no
+/// depende del tamaño de la descarga.
 pub struct ZipService {
     file_service: Arc<dyn FileUseCase>,
     folder_service: Arc<dyn FolderUseCase>,
@@ -58,9 +140,9 @@ impl ZipService {
         }
     }
 
-    /// Crea un archivo ZIP con el contenido de una carpeta y todas sus subcarpetas
-    /// Retorna los bytes del ZIP
-    pub async fn create_folder_zip(&self, folder_id: &str, folder_name: &str) -> Result<Vec<u8>> {
+    /// Crea un ZIP con el contenido de una carpeta y todas sus subcarpetas
+    /// Retorna el flujo de bytes del ZIP
+    pub async fn stream_folder_zip(&self, folder_id: &str, folder_name: &str) -> Result<ZipStream> {
         info!(
             "Creando ZIP para carpeta: {} (ID: {})",
             folder_name, folder_id
@@ -75,49 +157,62 @@ impl ZipService {
             }
         };
 
-        // Crear un buffer en memoria para el ZIP
-        let buf = Cursor::new(Vec::new());
-        let mut zip = ZipWriter::new(buf);
+        let mut entries = Vec::new();
+        let mut processed_folders = HashSet::new();
+        self.collect_folder_entries(&folder, folder_name, &mut entries, &mut processed_folders)
+            .await?;
 
-        // Establecer opciones de compresión
-        let options = SimpleFileOptions::default()
-            .compression_method(zip::CompressionMethod::Deflated)
-            .unix_permissions(0o755);
+        Ok(self.spawn_writer(entries))
+    }
 
-        // Objeto para seguir las carpetas procesadas y evitar ciclos
-        let mut processed_folders = std::collections::HashSet::new();
+    /// Crea un ZIP con una selección de archivos y carpetas.
+    ///
+    /// Cada elemento aparece en la raíz del ZIP; los nombres repetidos se
+    /// numeran para que ninguna entrada sobrescriba a otra.
+    pub async fn stream_selection_zip(
+        &self,
+        file_ids: &[String],
+        folder_ids: &[String],
+    ) -> Result<ZipStream> {
+        info!(
+            "Creando ZIP para selección: {} archivos, {} carpetas",
+            file_ids.len(),
+            folder_ids.len()
+        );
 
-        // Procesamos la carpeta raíz y construimos el ZIP
-        self.process_folder_recursively(
-            &mut zip,
-            &folder,
-            folder_name,
-            &options,
-            &mut processed_folders,
-        )
-        .await?;
+        let mut entries = Vec::new();
+        let mut root_names = HashSet::new();
+        let mut processed_folders = HashSet::new();
 
-        // Finalizar el ZIP y obtener los bytes
-        let mut zip_buf = zip.finish()?;
+        for folder_id in folder_ids {
+            let folder = match self.folder_service.get_folder(folder_id).await {
+                Ok(folder) => folder,
+                Err(e) => {
+                    error!("Error al obtener carpeta {}: {}", folder_id, e);
+                    return Err(ZipError::FolderNotFound(folder_id.to_string()).into());
+                }
+            };
+            let name = unique_name(&mut root_names, &folder.name);
+            self.collect_folder_entries(&folder, &name, &mut entries, &mut processed_folders)
+                .await?;
+        }
 
-        let mut bytes = Vec::new();
-        match zip_buf.read_to_end(&mut bytes) {
-            Ok(_) => Ok(bytes),
-            Err(e) => {
-                error!("Error al leer ZIP finalizado: {}", e);
-                Err(ZipError::IoError(e).into())
-            }
+        for file_id in file_ids {
+            let file = self.file_service.get_file(file_id).await?;
+            let path = unique_name(&mut root_names, &file.name);
+            entries.push(ZipEntry::File { path, file });
         }
+
+        Ok(self.spawn_writer(entries))
     }
 
-    // Implementación alternativa para evitar recursión en async
-    async fn process_folder_recursively(
+    // Implementación iterativa para evitar recursión en async
+    async fn collect_folder_entries(
         &self,
-        zip: &mut ZipWriter<Cursor<Vec<u8>>>,
         folder: &FolderDto,
         path: &str,
-        options: &SimpleFileOptions,
-        processed_folders: &mut std::collections::HashSet<String>,
+        entries: &mut Vec<ZipEntry>,
+        processed_folders: &mut HashSet<String>,
     ) -> Result<()> {
         // Estructura para representar el trabajo pendiente
         struct PendingFolder {
@@ -136,24 +231,13 @@ impl ZipService {
             let folder_id = current.folder.id.to_string();
 
             // Evitar ciclos
-            if processed_folders.contains(&folder_id) {
+            if !processed_folders.insert(folder_id.clone()) {
                 continue;
             }
 
-            processed_folders.insert(folder_id.clone());
-
             // Crear la entrada de directorio en el ZIP
             let folder_path = format!("{}/", current.path);
-            match zip.add_directory(&folder_path, *options) {
-                Ok(_) => debug!("Carpeta agregada al ZIP: {}", folder_path),
-                Err(e) => {
-                    warn!(
-                        "No se pudo agregar carpeta al ZIP (puede que ya exista): {}",
-                        e
-                    );
-                    // Continuamos aunque falle crear el directorio (podría estar duplicado)
-                }
-            }
+            entries.push(ZipEntry::Directory(folder_path.clone()));
 
             // Agregar archivos de la carpeta al ZIP
             let files = match self.file_service.list_files(Some(&folder_id)).await {
@@ -168,10 +252,11 @@ impl ZipService {
                 }
             };
 
-            // Agregar cada archivo al ZIP
             for file in files {
-                self.add_file_to_zip(zip, &file, &folder_path, options)
-                    .await?;
+                entries.push(ZipEntry::File {
+                    path: format!("{}{}", folder_path, file.name),
+                    file,
+                });
             }
 
             // Procesar subcarpetas
@@ -200,50 +285,183 @@ impl ZipService {
         Ok(())
     }
 
-    // Agrega un archivo al ZIP
-    async fn add_file_to_zip(
-        &self,
-        zip: &mut ZipWriter<Cursor<Vec<u8>>>,
-        file: &FileDto,
-        folder_path: &str,
-        options: &SimpleFileOptions,
-    ) -> Result<()> {
-        let file_path = format!("{}{}", folder_path, file.name);
-        info!("Agregando archivo al ZIP: {}", file_path);
+    /// Lanza la tarea que escribe el ZIP y devuelve el extremo de lectura
+    fn spawn_writer(&self, entries: Vec<ZipEntry>) -> ZipStream {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let file_service = self.file_service.clone();
 
-        // Obtener el contenido del archivo
-        let file_id = file.id.to_string();
-        let content = match self.file_service.get_file_content(&file_id).await {
-            Ok(content) => content,
-            Err(e) => {
-                error!("Error al leer contenido del archivo {}: {}", file_id, e);
-                return Err(ZipError::FileReadError(format!(
-                    "Error al leer archivo {}: {}",
-                    file_id, e
-                ))
-                .into());
+        tokio::spawn(async move {
+            match write_entries(file_service, entries, &sender).await {
+                Ok(()) => debug!("ZIP enviado por completo"),
+                Err(ZipError::Cancelled) => info!("Descarga de ZIP cancelada por el cliente"),
+                Err(e) => {
+                    error!("Error al generar ZIP: {}", e);
+                    // Abortar el cuerpo para que el cliente no reciba un ZIP truncado como válido
+                    let _ = sender.send(Err(std::io::Error::other(e.to_string()))).await;
+                }
             }
-        };
+        });
+
+        ReceiverStream::new(receiver)
+    }
+}
 
-        // Escribir archivo al ZIP
-        match zip.start_file_from_path(std::path::Path::new(&file_path), *options) {
-            Ok(_) => match zip.write_all(&content) {
-                Ok(_) => {
-                    debug!("Archivo agregado al ZIP: {}", file_path);
-                    Ok(())
+/// Escribe las entradas en un ZIP de streaming, enviando los bytes por `sender`
+async fn write_entries(
+    file_service: Arc<dyn FileUseCase>,
+    entries: Vec<ZipEntry>,
+    sender: &mpsc::Sender<std::io::Result<Bytes>>,
+) -> std::result::Result<(), ZipError> {
+    let buffer = SharedBuffer::default();
+    let mut zip = ZipWriter::new_stream(buffer.clone());
+
+    // Establecer opciones de compresión
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o755);
+
+    for entry in entries {
+        match entry {
+            ZipEntry::Directory(path) => {
+                if let Err(e) = zip.add_directory(&path, options) {
+                    // Continuamos aunque falle crear el directorio (podría estar duplicado)
+                    warn!("No se pudo agregar carpeta al ZIP: {}", e);
                 }
-                Err(e) => {
-                    error!(
-                        "Error al escribir contenido del archivo {}: {}",
-                        file_path, e
-                    );
-                    Err(ZipError::IoError(e).into())
+            }
+            ZipEntry::File { path, file } => {
+                debug!("Agregando archivo al ZIP: {}", path);
+                let mut file_options = options.large_file(file.size >= ZIP64_THRESHOLD);
+                if let Some(modified) = zip_timestamp(file.modified_at) {
+                    file_options = file_options.last_modified_time(modified);
+                }
+
+                let stream = file_service.get_file_stream(&file.id).await.map_err(|e| {
+                    ZipError::FileReadError(format!("Error al leer archivo {}: {}", file.id, e))
+                })?;
+                zip.start_file(path.as_str(), file_options)?;
+
+                let mut stream = Box::into_pin(stream);
+                while let Some(chunk) = stream.next().await {
+                    zip.write_all(&chunk?)?;
+                    buffer.send_pending(sender, CHUNK_SIZE).await?;
                 }
-            },
-            Err(e) => {
-                error!("Error al iniciar archivo en ZIP {}: {}", file_path, e);
-                Err(ZipError::ZipError(e).into())
             }
         }
+        buffer.send_pending(sender, CHUNK_SIZE).await?;
+    }
+
+    zip.finish()?;
+    buffer.send_pending(sender, 0).await
+}
+
+/// Fecha de modificación en el formato de ZIP, si es representable
+fn zip_timestamp(modified_at: u64) -> Option<zip::DateTime> {
+    let timestamp = time::OffsetDateTime::from_unix_timestamp(modified_at as i64).ok()?;
+    zip::DateTime::try_from(timestamp).ok()
+}
+
+/// Devuelve `name`, o `name (n)` si ya se usó en la raíz del ZIP
+fn unique_name(used: &mut HashSet<String>, name: &str) -> String {
+    if used.insert(name.to_string()) {
+        return name.to_string();
+    }
+
+    let (stem, extension) = match name.rfind('.') {
+        Some(idx) if idx > 0 => (&name[..idx], &name[idx..]),
+        _ => (name, ""),
+    };
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{} ({}){}", stem, counter, extension);
+        if used.insert(candidate.clone()) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::ports::test_doubles::FakeFiles;
+    use std::io::{Cursor, Read};
+
+    /// Archivos cuyo contenido es su ID repetido, en varios bloques
+    fn files_with(ids: &[&str]) -> Arc<FakeFiles> {
+        let files = FakeFiles::new();
+        for id in ids {
+            files.add(
+                id,
+                &format!("/{}", id),
+                "text/plain",
+                id.repeat(3000).as_bytes(),
+                1,
+            );
+        }
+        files
+    }
+
+    fn file_entry(path: &str, id: &str) -> ZipEntry {
+        let mut file = FileDto::empty();
+        file.id = id.to_string();
+        file.size = (id.len() * 3000) as u64;
+        file.modified_at = 1_700_000_000;
+        ZipEntry::File {
+            path: path.to_string(),
+            file,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_zip_is_readable() {
+        let entries = vec![
+            ZipEntry::Directory("docs/".to_string()),
+            file_entry("docs/a.txt", "alpha"),
+            file_entry("b.txt", "beta"),
+        ];
+        let (sender, mut receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let files = files_with(&["alpha", "beta"]);
+        let writer = tokio::spawn(async move { write_entries(files, entries, &sender).await });
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = receiver.recv().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        writer.await.unwrap().unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.len(), 3);
+        let mut content = String::new();
+        archive
+            .by_name("docs/a.txt")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "alpha".repeat(3000));
+        assert!(archive.by_name("docs/").unwrap().is_dir());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_receiver_cancels_writer() {
+        let entries = (0..64)
+            .map(|i| file_entry(&format!("f{}.bin", i), "payload"))
+            .collect();
+        let (sender, receiver) = mpsc::channel(1);
+        drop(receiver);
+
+        let result = write_entries(files_with(&["payload"]), entries, &sender).await;
+        assert!(matches!(result, Err(ZipError::Cancelled)));
+    }
+
+    #[test]
+    fn test_unique_name_numbers_duplicates() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_name(&mut used, "report.pdf"), "report.pdf");
+        assert_eq!(unique_name(&mut used, "report.pdf"), "report (2).pdf");
+        assert_eq!(unique_name(&mut used, "report.pdf"), "report (3).pdf");
+        assert_eq!(unique_name(&mut used, "photos"), "photos");
+        assert_eq!(unique_name(&mut used, "photos"), "photos (2)");
+        assert_eq!(unique_name(&mut used, ".env"), ".env");
+        assert_eq!(unique_name(&mut used, ".env"), ".env (2)");
     }
 }
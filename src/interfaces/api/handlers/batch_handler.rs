@@ -11,6 +11,10 @@ use crate::application::dtos::folder_dto::FolderDto;
 use crate::application::services::batch_operations::{
     BatchOperationService, BatchResult, BatchStats,
 };
+use crate::common::di::AppState as GlobalAppState;
+use crate::common::errors::ErrorKind;
+use crate::infrastructure::services::zip_service::ZipService;
+use crate::interfaces::api::handlers::folder_handler::zip_response;
 use crate::interfaces::api::handlers::ApiResult;
 
 /// Estado compartido para el handler de batch
@@ -60,6 +64,20 @@ pub struct CreateFolderDetail {
     pub parent_id: Option<String>,
 }
 
+/// DTO para descargar una selección de archivos y carpetas como ZIP
+#[derive(Debug, Deserialize)]
+pub struct BatchDownloadRequest {
+    /// IDs de los archivos a incluir
+    #[serde(default)]
+    pub file_ids: Vec<String>,
+    /// IDs de las carpetas a incluir con todo su contenido
+    #[serde(default)]
+    pub folder_ids: Vec<String>,
+    /// Nombre del archivo ZIP, sin extensión (opcional)
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
 /// DTO para los resultados de operaciones en lote
 #[derive(Debug, Serialize)]
 pub struct BatchOperationResponse<T> {
@@ -429,3 +447,45 @@ pub async fn get_folders_batch(
 
     Ok((status_code, Json(response)).into_response())
 }
+
+/// Handler para descargar una selección de archivos y carpetas en un solo ZIP
+pub async fn download_selection_zip(
+    State(state): State<GlobalAppState>,
+    Json(request): Json<BatchDownloadRequest>,
+) -> ApiResult<impl IntoResponse> {
+    // Verificar que hay elementos para descargar
+    if request.file_ids.is_empty() && request.folder_ids.is_empty() {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "No file or folder IDs provided"
+            })),
+        )
+            .into_response());
+    }
+
+    let zip_service = ZipService::new(
+        state.applications.file_service.clone(),
+        state.applications.folder_service.clone(),
+    );
+
+    // Los errores al resolver la selección se devuelven antes de enviar bytes
+    let zip_stream = zip_service
+        .stream_selection_zip(&request.file_ids, &request.folder_ids)
+        .await
+        .map_err(|e| {
+            let status = match e.kind {
+                ErrorKind::NotFound => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, e.to_string())
+        })?;
+
+    let name = request
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("download");
+    Ok(zip_response(zip_stream, &format!("{}.zip", name)))
+}
@@ -1,19 +1,2734 @@
-use axum::{http::StatusCode, response::IntoResponse, routing::get, Json, Router};
-use serde_json::json;
+/**
+ * CalDAV Handler Module
+ *
+ * This module serves calendars over CalDAV (RFC 4791). Each user has a
+ * calendar home at `/caldav/calendars/{name}/` holding one collection per
+ * calendar, named by its id, and each event or task is a `.ics` resource
+ * in it.
+ * Clients find the home from `/.well-known/caldav` (RFC 6764) or from the
+ * `calendar-home-set` of their principal, then synchronize through PROPFIND
+ * and the calendar-query and calendar-multiget reports.
+ * The home also holds the scheduling inbox and outbox of the user
+ * (RFC 6638): events they organize are sent to their attendees when
+ * stored, answers go back to the organizer, and free-busy lookups are
+ * POSTed to the outbox.
+ */
+use axum::{
+    body::{self, Body},
+    extract::State,
+    http::{header, HeaderMap, HeaderName, Request, StatusCode},
+    response::Response,
+    routing::any,
+    Router,
+};
+use bytes::{Buf, Bytes};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use uuid::Uuid;
 
+use crate::application::adapters::caldav_adapter::{
+    CalDavAdapter, CalDavReportType, CalDavResource, APPLE_ICAL_NS, CALENDAR_CONTENT_TYPE,
+};
+use crate::application::adapters::webdav_adapter::{
+    PropFindRequest, PropFindType, PropPatchStatus, QualifiedName, WebDavAdapter, CALDAV_NS,
+};
+use crate::application::dtos::calendar_dto::{
+    CalendarDto, CalendarObjectDto, CreateCalendarDto, CreateEventICalDto, UpdateCalendarDto,
+    SUPPORTED_COMPONENTS_PROPERTY,
+};
+use crate::application::ports::calendar_ports::CalendarUseCase;
+use crate::application::ports::scheduling_ports::SchedulingUseCase;
 use crate::common::di::AppState;
+use crate::common::errors::{AppError, ErrorKind};
+use crate::domain::entities::icalendar::ICalComponent;
+use crate::domain::entities::scheduling::{self, ScheduleMessage};
+use crate::domain::entities::timezone::CalendarTimeZone;
+use crate::domain::entities::webdav_acl::{Principal, PrivilegeSet};
+use crate::interfaces::api::http_range::{check_write_preconditions, etag_matches};
+use crate::interfaces::middleware::auth::CurrentUser;
 
-// Temporary placeholder implementation
+const HEADER_DAV: HeaderName = HeaderName::from_static("dav");
+
+/// Root of the CalDAV tree
+const CALDAV_PATH: &str = "/caldav/";
+
+/// Prefix of the calendar properties that map a resource name to the UID of
+/// its object, for clients that do not name resources after the UID
+const RESOURCE_PROPERTY_PREFIX: &str = "_resource:";
+
+/**
+ * Creates the router for the CalDAV tree.
+ *
+ * @return Router configured with the CalDAV endpoints
+ */
 pub fn caldav_routes() -> Router<AppState> {
-    Router::new().route("/placeholder", get(placeholder_handler))
+    Router::new()
+        .route("/caldav", any(handle_caldav_methods))
+        .route("/caldav/", any(handle_caldav_methods))
+        .route("/caldav/{*path}", any(handle_caldav_methods))
+}
+
+/**
+ * Creates the router for the CalDAV service discovery URL, which must be
+ * served from the root of the host.
+ */
+pub fn well_known_routes() -> Router<AppState> {
+    Router::new().route("/.well-known/caldav", any(well_known_caldav))
+}
+
+async fn well_known_caldav() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::MOVED_PERMANENTLY)
+        .header(header::LOCATION, CALDAV_PATH)
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn handle_caldav_methods(
+    State(state): State<AppState>,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    dispatch(Arc::new(state), req).await
+}
+
+/// Resource addressed by a path below `/caldav/`
+enum CalDavPath {
+    Root,
+    Home,
+    Calendar(String),
+    Object(String, String),
+    Inbox,
+    Outbox,
+    /// Scheduling message in the inbox, by resource name
+    InboxMessage(String),
+}
+
+/**
+ * Runs a request whose URI path starts with `/caldav`.
+ *
+ * @param state The application state containing service dependencies
+ * @param req The request, carrying the authenticated user as an extension
+ * @return The CalDAV response
+ */
+async fn dispatch(state: Arc<AppState>, req: Request<Body>) -> Result<Response<Body>, AppError> {
+    let user = req
+        .extensions()
+        .get::<CurrentUser>()
+        .cloned()
+        .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
+    let service = state
+        .calendar_service
+        .clone()
+        .ok_or_else(|| AppError::method_not_allowed("Calendar service is not available"))?;
+    let path = req
+        .uri()
+        .path()
+        .strip_prefix("/caldav")
+        .unwrap_or("")
+        .trim_matches('/')
+        .to_string();
+
+    if req.method() == "OPTIONS" {
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(HEADER_DAV, "1, 3, calendar-access, calendar-auto-schedule")
+            .header(
+                header::ALLOW,
+                "OPTIONS, GET, HEAD, PUT, DELETE, POST, PROPFIND, PROPPATCH, MKCALENDAR, REPORT",
+            )
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let target = parse_path(&path, &user)?;
+    let ctx = CalDavContext {
+        service: service.as_ref(),
+        scheduling: state.scheduling_service.as_deref(),
+        user: &user,
+    };
+    match (req.method().as_str(), target) {
+        ("PROPFIND", target) => handle_propfind(&ctx, target, req).await,
+        ("PROPPATCH", CalDavPath::Home) => handle_home_proppatch(&ctx, req).await,
+        ("PROPPATCH", CalDavPath::Calendar(calendar_id)) => {
+            handle_proppatch(&ctx, &calendar_id, req).await
+        }
+        ("MKCALENDAR", CalDavPath::Calendar(calendar_id)) => {
+            handle_mkcalendar(&ctx, &calendar_id, req).await
+        }
+        ("REPORT", CalDavPath::Home) => handle_home_report(&ctx, req).await,
+        ("REPORT", CalDavPath::Calendar(calendar_id)) => {
+            handle_report(&ctx, &calendar_id, req).await
+        }
+        ("GET" | "HEAD", CalDavPath::Object(calendar_id, name)) => {
+            handle_get(&ctx, &calendar_id, &name, req).await
+        }
+        ("PUT", CalDavPath::Object(calendar_id, name)) => {
+            handle_put(&ctx, &calendar_id, &name, req).await
+        }
+        ("DELETE", CalDavPath::Object(calendar_id, name)) => {
+            handle_delete_object(&ctx, &calendar_id, &name, req.headers()).await
+        }
+        ("DELETE", CalDavPath::Calendar(calendar_id)) => {
+            service.delete_calendar(&calendar_id, &user.id).await?;
+            Ok(Response::builder()
+                .status(StatusCode::NO_CONTENT)
+                .body(Body::empty())
+                .unwrap())
+        }
+        ("POST", CalDavPath::Outbox) => handle_outbox_post(&ctx, req).await,
+        ("GET" | "HEAD", CalDavPath::InboxMessage(name)) => {
+            handle_get_message(&ctx, &name, req).await
+        }
+        ("DELETE", CalDavPath::InboxMessage(name)) => {
+            ctx.scheduling()?
+                .delete_inbox_message(message_id(&name), &user.id)
+                .await?;
+            Ok(Response::builder()
+                .status(StatusCode::NO_CONTENT)
+                .body(Body::empty())
+                .unwrap())
+        }
+        (method, _) => Err(AppError::method_not_allowed(format!(
+            "Method not allowed: {}",
+            method
+        ))),
+    }
+}
+
+/// Services and user a CalDAV request runs with
+struct CalDavContext<'a> {
+    service: &'a dyn CalendarUseCase,
+    /// Scheduling between users, when the server has it
+    scheduling: Option<&'a dyn SchedulingUseCase>,
+    user: &'a CurrentUser,
+}
+
+impl CalDavContext<'_> {
+    fn home_href(&self) -> String {
+        Principal::calendar_home_for(&self.user.username)
+    }
+
+    fn message_href(&self, message: &ScheduleMessage) -> String {
+        format!(
+            "{}{}.ics",
+            Principal::schedule_inbox_for(&self.user.username),
+            message.id
+        )
+    }
+
+    /// The scheduling service; the inbox and outbox don't exist without it
+    fn scheduling(&self) -> Result<&dyn SchedulingUseCase, AppError> {
+        self.scheduling
+            .ok_or_else(|| AppError::not_found("Scheduling is not available"))
+    }
+
+    /// Sends the scheduling messages an event change calls for; a failure
+    /// doesn't undo the change, which is already stored
+    async fn schedule_change(&self, previous: Option<&str>, current: Option<&str>) {
+        if let Some(scheduling) = self.scheduling {
+            if let Err(e) = scheduling
+                .schedule_change(&self.user.id, previous, current)
+                .await
+            {
+                tracing::warn!("Could not schedule calendar change: {}", e);
+            }
+        }
+    }
+
+    fn calendar_href(&self, calendar_id: &str) -> String {
+        format!("{}{}/", self.home_href(), calendar_id)
+    }
+
+    fn principal_href(&self) -> String {
+        Principal::href_for(&self.user.username)
+    }
+
+    /// Objects of a calendar, each with the href it is served at
+    async fn calendar_objects(
+        &self,
+        calendar: &CalendarDto,
+        objects: Vec<CalendarObjectDto>,
+    ) -> Vec<(String, CalendarObjectDto)> {
+        let names = resource_names(calendar);
+        let href = self.calendar_href(&calendar.id);
+        objects
+            .into_iter()
+            .map(|object| {
+                let name = names
+                    .get(object.ical_uid())
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| format!("{}.ics", object.ical_uid()));
+                (format!("{}{}", href, name), object)
+            })
+            .collect()
+    }
+
+    /// Events and tasks of a calendar
+    async fn list_objects(&self, calendar_id: &str) -> Result<Vec<CalendarObjectDto>, AppError> {
+        let events = self
+            .service
+            .list_events(calendar_id, None, None, &self.user.id)
+            .await?;
+        let tasks = self.service.list_tasks(calendar_id, &self.user.id).await?;
+        Ok(events
+            .into_iter()
+            .map(CalendarObjectDto::Event)
+            .chain(tasks.into_iter().map(CalendarObjectDto::Task))
+            .collect())
+    }
+
+    /// Event or task served under a resource name of a calendar, if any
+    async fn find_object(
+        &self,
+        calendar: &CalendarDto,
+        name: &str,
+    ) -> Result<Option<CalendarObjectDto>, AppError> {
+        self.find_object_by_uid(&calendar.id, &resource_uid(calendar, name))
+            .await
+    }
+
+    /// Event or task of a calendar with a UID, if any
+    async fn find_object_by_uid(
+        &self,
+        calendar_id: &str,
+        uid: &str,
+    ) -> Result<Option<CalendarObjectDto>, AppError> {
+        if let Some(event) = self
+            .service
+            .get_event_by_uid(calendar_id, uid, &self.user.id)
+            .await?
+        {
+            return Ok(Some(CalendarObjectDto::Event(event)));
+        }
+        Ok(self
+            .service
+            .get_task_by_uid(calendar_id, uid, &self.user.id)
+            .await?
+            .map(CalendarObjectDto::Task))
+    }
+}
+
+/**
+ * Maps a path below `/caldav/` to the resource it names. Only the home of
+ * the current user can be browsed.
+ */
+fn parse_path(path: &str, user: &CurrentUser) -> Result<CalDavPath, AppError> {
+    let segments: Vec<&str> = path.split('/').collect();
+    let (owner, rest) = match segments.as_slice() {
+        [""] => return Ok(CalDavPath::Root),
+        ["calendars", owner, rest @ ..] => (owner, rest),
+        _ => return Err(AppError::not_found(format!("Resource not found: {}", path))),
+    };
+    if *owner != user.username {
+        return Err(AppError::forbidden(
+            "You can only access your own calendars",
+        ));
+    }
+    match rest {
+        [] => Ok(CalDavPath::Home),
+        ["inbox"] => Ok(CalDavPath::Inbox),
+        ["outbox"] => Ok(CalDavPath::Outbox),
+        ["inbox", name] => Ok(CalDavPath::InboxMessage(name.to_string())),
+        [calendar] => Ok(CalDavPath::Calendar(calendar.to_string())),
+        [calendar, name] => Ok(CalDavPath::Object(calendar.to_string(), name.to_string())),
+        _ => Err(AppError::not_found(format!("Resource not found: {}", path))),
+    }
+}
+
+/// UID of the object served under a resource name of a calendar
+fn resource_uid(calendar: &CalendarDto, name: &str) -> String {
+    calendar
+        .custom_properties
+        .get(&format!("{}{}", RESOURCE_PROPERTY_PREFIX, name))
+        .cloned()
+        .unwrap_or_else(|| name.strip_suffix(".ics").unwrap_or(name).to_string())
+}
+
+/// Id of the scheduling message served under a resource name of the inbox
+fn message_id(name: &str) -> &str {
+    name.strip_suffix(".ics").unwrap_or(name)
+}
+
+/// Resource names of the objects not named after their UID, by UID
+fn resource_names(calendar: &CalendarDto) -> HashMap<&str, &str> {
+    calendar
+        .custom_properties
+        .iter()
+        .filter_map(|(key, uid)| {
+            key.strip_prefix(RESOURCE_PROPERTY_PREFIX)
+                .map(|name| (uid.as_str(), name))
+        })
+        .collect()
+}
+
+async fn read_body(body: Body) -> Result<Bytes, AppError> {
+    body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))
+}
+
+fn multistatus(body: Vec<u8>) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/**
+ * Answers a request that breaks a CalDAV precondition with the `DAV:error`
+ * body naming it, so clients can tell why it failed.
+ */
+fn precondition_failed(status: StatusCode, condition: &str) -> Result<Response<Body>, AppError> {
+    let mut response_body = Vec::new();
+    CalDavAdapter::generate_error_response(&mut response_body, condition)
+        .map_err(|e| AppError::internal_error(format!("Failed to generate response: {}", e)))?;
+
+    Ok(Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(response_body))
+        .unwrap())
+}
+
+/**
+ * Handles PROPFIND on the CalDAV tree.
+ *
+ * The root points to the home of the current user, the home lists the
+ * user's calendars and scheduling collections, a calendar lists its events
+ * and tasks and the inbox its messages; members are included unless the
+ * request has `Depth: 0`.
+ *
+ * @param ctx The calendar service and the authenticated user
+ * @param target The resource the request was sent to
+ * @param req The HTTP request containing the PROPFIND XML body
+ * @return XML response with the properties of the resources
+ */
+async fn handle_propfind(
+    ctx: &CalDavContext<'_>,
+    target: CalDavPath,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let members = req
+        .headers()
+        .get("Depth")
+        .and_then(|v| v.to_str().ok())
+        .is_none_or(|depth| depth != "0");
+    let body_bytes = read_body(req.into_body()).await?;
+    let request = if body_bytes.is_empty() {
+        // Empty body means get all properties
+        PropFindRequest {
+            prop_find_type: PropFindType::AllProp,
+        }
+    } else {
+        WebDavAdapter::parse_propfind(body_bytes.reader()).map_err(|e| {
+            AppError::bad_request(format!("Failed to parse PROPFIND request: {}", e))
+        })?
+    };
+
+    let owner = ctx.principal_href();
+    let home = ctx.home_href();
+    let mut calendars = Vec::new();
+    let mut objects = Vec::new();
+    let mut collections = Vec::new();
+    let mut boxes = Vec::new();
+    let mut messages = Vec::new();
+    match target {
+        CalDavPath::Root => {
+            collections.push((CALDAV_PATH.to_string(), home.clone()));
+            if members {
+                collections.push((home.clone(), home.clone()));
+            }
+        }
+        CalDavPath::Home => {
+            collections.push((home.clone(), home.clone()));
+            if members && ctx.scheduling.is_some() {
+                boxes.extend([true, false]);
+            }
+            if members {
+                for calendar in ctx.service.list_my_calendars(&ctx.user.id).await? {
+                    let calendar_objects = ctx.list_objects(&calendar.id).await?;
+                    calendars.push((calendar, calendar_objects));
+                }
+            }
+        }
+        CalDavPath::Calendar(calendar_id) => {
+            let calendar = ctx.service.get_calendar(&calendar_id, &ctx.user.id).await?;
+            let calendar_objects = ctx.list_objects(&calendar_id).await?;
+            if members {
+                objects = ctx
+                    .calendar_objects(&calendar, calendar_objects.clone())
+                    .await;
+            }
+            calendars.push((calendar, calendar_objects));
+        }
+        CalDavPath::Object(calendar_id, name) => {
+            let calendar = ctx.service.get_calendar(&calendar_id, &ctx.user.id).await?;
+            let object = ctx.find_object(&calendar, &name).await?.ok_or_else(|| {
+                AppError::not_found(format!("Calendar object not found: {}", name))
+            })?;
+            objects.push((
+                format!("{}{}", ctx.calendar_href(&calendar_id), name),
+                object,
+            ));
+        }
+        CalDavPath::Inbox => {
+            let scheduling = ctx.scheduling()?;
+            boxes.push(true);
+            if members {
+                messages = scheduling.list_inbox(&ctx.user.id).await?;
+            }
+        }
+        CalDavPath::Outbox => {
+            ctx.scheduling()?;
+            boxes.push(false);
+        }
+        CalDavPath::InboxMessage(name) => messages.push(
+            ctx.scheduling()?
+                .get_inbox_message(message_id(&name), &ctx.user.id)
+                .await?,
+        ),
+    }
+
+    let timezone = match collections.is_empty() {
+        true => None,
+        false => CalendarTimeZone::bundled(&ctx.service.get_default_timezone(&ctx.user.id).await?),
+    };
+    let mut resources: Vec<CalDavResource> = collections
+        .into_iter()
+        .map(|(href, home)| CalDavResource::Home {
+            href,
+            owner: owner.clone(),
+            home,
+            timezone: timezone.clone(),
+        })
+        .collect();
+    resources.extend(calendars.iter().map(|(calendar, calendar_objects)| {
+        CalDavResource::Calendar {
+            calendar,
+            href: ctx.calendar_href(&calendar.id),
+            owner: owner.clone(),
+            ctag: CalDavAdapter::calendar_ctag(calendar, calendar_objects),
+            privileges: if calendar.owner_id == ctx.user.id {
+                PrivilegeSet::full()
+            } else {
+                PrivilegeSet::read_write()
+            },
+        }
+    }));
+    resources.extend(boxes.into_iter().map(|inbox| CalDavResource::ScheduleBox {
+        href: if inbox {
+            Principal::schedule_inbox_for(&ctx.user.username)
+        } else {
+            Principal::schedule_outbox_for(&ctx.user.username)
+        },
+        owner: owner.clone(),
+        inbox,
+    }));
+    resources.extend(objects.iter().map(|(href, object)| CalDavResource::Object {
+        object,
+        href: href.clone(),
+        expansion: None,
+    }));
+    resources.extend(messages.iter().map(|message| CalDavResource::Message {
+        message,
+        href: ctx.message_href(message),
+    }));
+
+    let mut response_body = Vec::new();
+    CalDavAdapter::generate_propfind_response(&mut response_body, &resources, &request, &owner)
+        .map_err(|e| AppError::internal_error(format!("Failed to generate response: {}", e)))?;
+    Ok(multistatus(response_body))
+}
+
+/**
+ * Handles PROPPATCH on a calendar.
+ *
+ * The display name, description and color change the calendar itself;
+ * other properties, such as the order clients sort calendars by, are stored
+ * with the calendar. As in WebDAV, a single protected property fails the
+ * whole request.
+ */
+async fn handle_proppatch(
+    ctx: &CalDavContext<'_>,
+    calendar_id: &str,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    ctx.service.get_calendar(calendar_id, &ctx.user.id).await?;
+    let body_bytes = read_body(req.into_body()).await?;
+    let (props_to_set, props_to_remove) = WebDavAdapter::parse_proppatch(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse PROPPATCH request: {}", e)))?;
+
+    let rejected = props_to_set
+        .iter()
+        .map(|prop| &prop.name)
+        .chain(&props_to_remove)
+        .any(CalDavAdapter::is_protected_calendar_property);
+
+    if !rejected {
+        let mut update = UpdateCalendarDto {
+            name: None,
+            description: None,
+            color: None,
+            is_public: None,
+        };
+        let changes = props_to_set
+            .iter()
+            .map(|prop| (&prop.name, prop.value.clone().unwrap_or_default()))
+            .chain(props_to_remove.iter().map(|prop| (prop, String::new())));
+        for (prop, value) in changes {
+            match (prop.namespace.as_str(), prop.name.as_str()) {
+                // A calendar always has a name, so removing it changes nothing
+                ("DAV:", "displayname") => {
+                    update.name = Some(value).filter(|name| !name.is_empty())
+                }
+                (CALDAV_NS, "calendar-description") => update.description = Some(value),
+                (APPLE_ICAL_NS, "calendar-color") => {
+                    update.color = Some(CalDavAdapter::normalize_color(&value).unwrap_or(value))
+                }
+                _ => {
+                    let key = CalDavAdapter::dead_property_key(prop);
+                    if props_to_remove.contains(prop) {
+                        ctx.service
+                            .remove_calendar_property(calendar_id, &key, &ctx.user.id)
+                            .await?;
+                    } else {
+                        ctx.service
+                            .set_calendar_property(calendar_id, &key, &value, &ctx.user.id)
+                            .await?;
+                    }
+                }
+            }
+        }
+        if update.name.is_some() || update.description.is_some() || update.color.is_some() {
+            ctx.service
+                .update_calendar(calendar_id, update, &ctx.user.id)
+                .await?;
+        }
+    }
+
+    let results: Vec<_> = props_to_set
+        .iter()
+        .map(|prop| &prop.name)
+        .chain(&props_to_remove)
+        .map(|prop| {
+            let status = if !rejected {
+                PropPatchStatus::Ok
+            } else if CalDavAdapter::is_protected_calendar_property(prop) {
+                PropPatchStatus::Forbidden
+            } else {
+                PropPatchStatus::FailedDependency
+            };
+            (prop, status)
+        })
+        .collect();
+
+    let mut response_body = Vec::new();
+    WebDavAdapter::generate_proppatch_response(
+        &mut response_body,
+        &ctx.calendar_href(calendar_id),
+        &results,
+    )
+    .map_err(|e| {
+        AppError::internal_error(format!("Failed to generate PROPPATCH response: {}", e))
+    })?;
+    Ok(multistatus(response_body))
+}
+
+/**
+ * Handles PROPPATCH on the calendar home.
+ *
+ * Only `calendar-timezone` can be changed: it sets the user's default time
+ * zone, used for floating times, and removing it resets the zone to UTC.
+ * Zones the server doesn't know are rejected.
+ */
+async fn handle_home_proppatch(
+    ctx: &CalDavContext<'_>,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let body_bytes = read_body(req.into_body()).await?;
+    let (props_to_set, props_to_remove) = WebDavAdapter::parse_proppatch(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse PROPPATCH request: {}", e)))?;
+
+    let is_timezone =
+        |prop: &QualifiedName| prop.namespace == CALDAV_NS && prop.name == "calendar-timezone";
+    let changes: Vec<(&QualifiedName, Option<Option<CalendarTimeZone>>)> = props_to_set
+        .iter()
+        .map(|prop| {
+            let zone = is_timezone(&prop.name)
+                .then(|| {
+                    prop.value
+                        .as_deref()
+                        .and_then(CalDavAdapter::parse_calendar_timezone)
+                })
+                .flatten();
+            (&prop.name, zone.map(Some))
+        })
+        .chain(
+            props_to_remove
+                .iter()
+                .map(|prop| (prop, is_timezone(prop).then_some(None))),
+        )
+        .collect();
+    let rejected = changes.iter().any(|(_, zone)| zone.is_none());
+
+    if !rejected {
+        if let Some((_, Some(zone))) = changes.last() {
+            ctx.service
+                .set_default_timezone(&ctx.user.id, zone.as_ref().map(CalendarTimeZone::tzid))
+                .await?;
+        }
+    }
+
+    let results: Vec<_> = changes
+        .iter()
+        .map(|(prop, zone)| {
+            let status = match (rejected, zone) {
+                (false, _) => PropPatchStatus::Ok,
+                (true, None) => PropPatchStatus::Forbidden,
+                (true, Some(_)) => PropPatchStatus::FailedDependency,
+            };
+            (*prop, status)
+        })
+        .collect();
+
+    let mut response_body = Vec::new();
+    WebDavAdapter::generate_proppatch_response(&mut response_body, &ctx.home_href(), &results)
+        .map_err(|e| {
+            AppError::internal_error(format!("Failed to generate PROPPATCH response: {}", e))
+        })?;
+    Ok(multistatus(response_body))
+}
+
+/**
+ * Handles MKCALENDAR: creates a calendar whose id is the last segment of
+ * the URL, which clients generate as a UUID. A
+ * `supported-calendar-component-set` restricts the calendar to events or
+ * to tasks.
+ */
+async fn handle_mkcalendar(
+    ctx: &CalDavContext<'_>,
+    calendar_id: &str,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    if Uuid::parse_str(calendar_id).is_err() {
+        return precondition_failed(StatusCode::FORBIDDEN, "calendar-collection-location-ok");
+    }
+    if ctx
+        .service
+        .get_calendar(calendar_id, &ctx.user.id)
+        .await
+        .is_ok()
+    {
+        return Err(AppError::method_not_allowed(format!(
+            "Calendar already exists: {}",
+            calendar_id
+        )));
+    }
+
+    let body_bytes = read_body(req.into_body()).await?;
+    let request = CalDavAdapter::parse_mkcalendar(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse MKCALENDAR request: {}", e)))?;
+    if request
+        .components
+        .iter()
+        .any(|component| !matches!(component.as_str(), "VEVENT" | "VTODO"))
+    {
+        return precondition_failed(StatusCode::FORBIDDEN, "supported-calendar-component");
+    }
+
+    ctx.service
+        .create_calendar(
+            CreateCalendarDto {
+                id: Some(calendar_id.to_string()),
+                name: request.name,
+                description: request.description,
+                color: request
+                    .color
+                    .map(|color| CalDavAdapter::normalize_color(&color).unwrap_or(color)),
+                is_public: None,
+            },
+            &ctx.user.id,
+        )
+        .await?;
+    if !request.components.is_empty() {
+        ctx.service
+            .set_calendar_property(
+                calendar_id,
+                SUPPORTED_COMPONENTS_PROPERTY,
+                &request.components.join(","),
+                &ctx.user.id,
+            )
+            .await?;
+    }
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(Body::empty())
+        .unwrap())
 }
 
-async fn placeholder_handler() -> impl IntoResponse {
-    (
-        StatusCode::OK,
-        Json(json!({
-            "message": "CalDAV functionality is not yet implemented"
-        })),
+/**
+ * Handles the calendar-query and calendar-multiget REPORTs on a calendar.
+ *
+ * A query returns the events and tasks of the component it filters on that
+ * overlap its time range, or all of them without one, keeping those that
+ * meet its property filters; a multiget returns the objects at the given
+ * hrefs and a 404 entry for each href that names none. A free-busy-query
+ * returns the busy time of the calendar.
+ */
+async fn handle_report(
+    ctx: &CalDavContext<'_>,
+    calendar_id: &str,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let calendar = ctx.service.get_calendar(calendar_id, &ctx.user.id).await?;
+    let body_bytes = read_body(req.into_body()).await?;
+    let report = CalDavAdapter::parse_report(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse REPORT request: {}", e)))?;
+
+    let calendar_href = ctx.calendar_href(calendar_id);
+    let (objects, missing, props, expansion) = match report {
+        Some(CalDavReportType::CalendarQuery {
+            time_range,
+            component,
+            prop_filters,
+            props,
+            expansion,
+        }) => {
+            let wants = |kind: &str| component.as_deref().is_none_or(|c| c == kind);
+            let mut objects = Vec::new();
+            if wants("VEVENT") {
+                let events = match time_range {
+                    Some((start, end)) => {
+                        ctx.service
+                            .get_events_in_range(calendar_id, start, end, &ctx.user.id)
+                            .await?
+                    }
+                    None => {
+                        ctx.service
+                            .list_events(calendar_id, None, None, &ctx.user.id)
+                            .await?
+                    }
+                };
+                objects.extend(events.into_iter().map(CalendarObjectDto::Event));
+            }
+            if wants("VTODO") {
+                let tasks = match time_range {
+                    Some((start, end)) => {
+                        ctx.service
+                            .get_tasks_in_range(calendar_id, start, end, &ctx.user.id)
+                            .await?
+                    }
+                    None => ctx.service.list_tasks(calendar_id, &ctx.user.id).await?,
+                };
+                objects.extend(tasks.into_iter().map(CalendarObjectDto::Task));
+            }
+            // A range query returns each instance of a recurring event
+            let mut seen = HashSet::new();
+            objects.retain(|object| {
+                seen.insert(object.id().to_string())
+                    && CalDavAdapter::matches_prop_filters(object, &prop_filters)
+            });
+            (
+                ctx.calendar_objects(&calendar, objects).await,
+                Vec::new(),
+                props,
+                expansion,
+            )
+        }
+        Some(CalDavReportType::CalendarMultiget {
+            hrefs,
+            props,
+            expansion,
+        }) => {
+            let mut objects = Vec::new();
+            let mut missing = Vec::new();
+            for href in hrefs {
+                let object = match href
+                    .find(&calendar_href)
+                    .map(|idx| &href[idx + calendar_href.len()..])
+                    .filter(|name| !name.is_empty() && !name.contains('/'))
+                {
+                    Some(name) => ctx.find_object(&calendar, name).await?,
+                    None => None,
+                };
+                match object {
+                    Some(object) => objects.push((href, object)),
+                    None => missing.push(href),
+                }
+            }
+            (objects, missing, props, expansion)
+        }
+        Some(CalDavReportType::FreeBusyQuery { start, end }) => {
+            return free_busy_response(ctx, std::slice::from_ref(&calendar), start, end).await
+        }
+        _ => return Err(AppError::forbidden("Unsupported REPORT type")),
+    };
+
+    let objects: Vec<_> = objects
+        .iter()
+        .map(|(href, object)| (href.clone(), object))
+        .collect();
+    let mut response_body = Vec::new();
+    CalDavAdapter::generate_report_response(
+        &mut response_body,
+        &objects,
+        &missing,
+        &props,
+        expansion,
     )
+    .map_err(|e| AppError::internal_error(format!("Failed to generate response: {}", e)))?;
+    Ok(multistatus(response_body))
+}
+
+/**
+ * Handles REPORT on the calendar home: only free-busy-query, over all the
+ * calendars of the user.
+ */
+async fn handle_home_report(
+    ctx: &CalDavContext<'_>,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let body_bytes = read_body(req.into_body()).await?;
+    let report = CalDavAdapter::parse_report(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse REPORT request: {}", e)))?;
+    match report {
+        Some(CalDavReportType::FreeBusyQuery { start, end }) => {
+            let calendars = ctx.service.list_my_calendars(&ctx.user.id).await?;
+            free_busy_response(ctx, &calendars, start, end).await
+        }
+        _ => Err(AppError::forbidden("Unsupported REPORT type")),
+    }
+}
+
+/**
+ * Answers a free-busy-query (RFC 4791, section 7.10) with a VFREEBUSY
+ * component holding the busy periods of the events of some calendars.
+ */
+async fn free_busy_response(
+    ctx: &CalDavContext<'_>,
+    calendars: &[CalendarDto],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Response<Body>, AppError> {
+    let mut periods = Vec::new();
+    for calendar in calendars
+        .iter()
+        .filter(|calendar| calendar.supports_component("VEVENT"))
+    {
+        periods.extend(
+            ctx.service
+                .get_free_busy(&calendar.id, start, end, &ctx.user.id)
+                .await?,
+        );
+    }
+    let free_busy =
+        scheduling::free_busy_component(&scheduling::merge_periods(periods), start, end);
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, CALENDAR_CONTENT_TYPE)
+        .body(Body::from(
+            scheduling::vcalendar(None, vec![free_busy]).to_ical(),
+        ))
+        .unwrap())
+}
+
+/**
+ * Handles POST to the scheduling outbox (RFC 6638, section 5): a free-busy
+ * lookup, or an iTIP message the client schedules itself.
+ *
+ * @return A `schedule-response` with the request status for each recipient
+ */
+async fn handle_outbox_post(
+    ctx: &CalDavContext<'_>,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let scheduling = ctx.scheduling()?;
+    let body_bytes = read_body(req.into_body()).await?;
+    let ical_data = String::from_utf8(body_bytes.to_vec())
+        .map_err(|_| AppError::bad_request("Calendar data must be UTF-8"))?;
+    let responses = scheduling.send_message(&ctx.user.id, &ical_data).await?;
+
+    let mut response_body = Vec::new();
+    CalDavAdapter::generate_schedule_response(&mut response_body, &responses)
+        .map_err(|e| AppError::internal_error(format!("Failed to generate response: {}", e)))?;
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(response_body))
+        .unwrap())
+}
+
+/**
+ * Handles GET and HEAD on a scheduling message of the inbox.
+ */
+async fn handle_get_message(
+    ctx: &CalDavContext<'_>,
+    name: &str,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let message = ctx
+        .scheduling()?
+        .get_inbox_message(message_id(name), &ctx.user.id)
+        .await?;
+    let body = if req.method() == "HEAD" {
+        Body::empty()
+    } else {
+        Body::from(message.ical_data.clone())
+    };
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::ETAG, CalDavAdapter::message_etag(&message))
+        .header(header::LAST_MODIFIED, message.created_at.to_rfc2822())
+        .header(header::CONTENT_TYPE, CALENDAR_CONTENT_TYPE)
+        .header(header::CONTENT_LENGTH, message.ical_data.len())
+        .body(body)
+        .unwrap())
+}
+
+/**
+ * Handles GET and HEAD on a calendar object: its iCalendar data, as last
+ * stored by a client.
+ */
+async fn handle_get(
+    ctx: &CalDavContext<'_>,
+    calendar_id: &str,
+    name: &str,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let calendar = ctx.service.get_calendar(calendar_id, &ctx.user.id).await?;
+    let object = ctx
+        .find_object(&calendar, name)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Calendar object not found: {}", name)))?;
+    let etag = CalDavAdapter::object_etag(&object);
+
+    let not_modified = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|list| etag_matches(list, &etag, true));
+    let builder = Response::builder()
+        .header(header::ETAG, &etag)
+        .header(header::LAST_MODIFIED, object.updated_at().to_rfc2822());
+    if not_modified {
+        return Ok(builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let body = if req.method() == "HEAD" {
+        Body::empty()
+    } else {
+        Body::from(object.ical_data().to_string())
+    };
+    Ok(builder
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, CALENDAR_CONTENT_TYPE)
+        .header(header::CONTENT_LENGTH, object.ical_data().len())
+        .body(body)
+        .unwrap())
+}
+
+/**
+ * Handles PUT of a calendar object.
+ *
+ * The object must hold one event or one task of a component the calendar
+ * supports; its UID identifies it within the calendar, so an update cannot
+ * change it or the component, and no two resources can share it.
+ * `If-Match` and `If-None-Match: *` let clients avoid overwriting changes
+ * made elsewhere. Stored events are then scheduled with their attendees or
+ * organizer.
+ *
+ * @return 201 Created or 204 No Content, with the ETag of the stored object
+ */
+async fn handle_put(
+    ctx: &CalDavContext<'_>,
+    calendar_id: &str,
+    name: &str,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let calendar = ctx.service.get_calendar(calendar_id, &ctx.user.id).await?;
+    let headers = req.headers().clone();
+    let body_bytes = read_body(req.into_body()).await?;
+    let ical_data = String::from_utf8(body_bytes.to_vec())
+        .map_err(|_| AppError::bad_request("Calendar data must be UTF-8"))?;
+
+    let Ok(object) = ICalComponent::parse(&ical_data) else {
+        return precondition_failed(StatusCode::FORBIDDEN, "valid-calendar-data");
+    };
+    let kind = match object
+        .calendar_components()
+        .map(|component| component.name.as_str())
+        .find(|name| matches!(*name, "VEVENT" | "VTODO"))
+    {
+        Some(kind) if calendar.supports_component(kind) => kind.to_string(),
+        _ => return precondition_failed(StatusCode::FORBIDDEN, "supported-calendar-component"),
+    };
+    let uid = match object
+        .calendar_components()
+        .find_map(|component| component.property("UID"))
+    {
+        Some(uid) => uid.text(),
+        None => {
+            return precondition_failed(StatusCode::FORBIDDEN, "valid-calendar-object-resource")
+        }
+    };
+
+    let existing = ctx.find_object(&calendar, name).await?;
+    check_write_preconditions(
+        &headers,
+        existing.as_ref().map(CalDavAdapter::object_etag).as_deref(),
+    )?;
+
+    let previous = existing
+        .as_ref()
+        .map(|existing| existing.ical_data().to_string());
+    let (status, object) = match existing {
+        Some(existing) => {
+            if existing.ical_uid() != uid {
+                return precondition_failed(StatusCode::FORBIDDEN, "no-uid-conflict");
+            }
+            if existing.component() != kind {
+                return precondition_failed(
+                    StatusCode::FORBIDDEN,
+                    "valid-calendar-object-resource",
+                );
+            }
+            let updated = match existing {
+                CalendarObjectDto::Event(event) => ctx
+                    .service
+                    .update_event_from_ical(&event.id, &ical_data, &ctx.user.id)
+                    .await
+                    .map(CalendarObjectDto::Event),
+                CalendarObjectDto::Task(task) => ctx
+                    .service
+                    .update_task_from_ical(&task.id, &ical_data, &ctx.user.id)
+                    .await
+                    .map(CalendarObjectDto::Task),
+            };
+            (StatusCode::NO_CONTENT, updated)
+        }
+        None => {
+            if ctx.find_object_by_uid(calendar_id, &uid).await?.is_some() {
+                return precondition_failed(StatusCode::FORBIDDEN, "no-uid-conflict");
+            }
+            let created = if kind == "VTODO" {
+                ctx.service
+                    .create_task_from_ical(calendar_id, &ical_data, &ctx.user.id)
+                    .await
+                    .map(CalendarObjectDto::Task)
+            } else {
+                ctx.service
+                    .create_event_from_ical(
+                        CreateEventICalDto {
+                            calendar_id: calendar_id.to_string(),
+                            ical_data: ical_data.clone(),
+                        },
+                        &ctx.user.id,
+                    )
+                    .await
+                    .map(CalendarObjectDto::Event)
+            };
+            if created.is_ok() && name != format!("{}.ics", uid) {
+                ctx.service
+                    .set_calendar_property(
+                        calendar_id,
+                        &format!("{}{}", RESOURCE_PROPERTY_PREFIX, name),
+                        &uid,
+                        &ctx.user.id,
+                    )
+                    .await?;
+            }
+            (StatusCode::CREATED, created)
+        }
+    };
+
+    let object = match object {
+        Ok(object) => object,
+        Err(e) if e.kind == ErrorKind::InvalidInput => {
+            return precondition_failed(StatusCode::FORBIDDEN, "valid-calendar-object-resource")
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if let CalendarObjectDto::Event(event) = &object {
+        ctx.schedule_change(previous.as_deref(), Some(&event.ical_data))
+            .await;
+    }
+    Ok(Response::builder()
+        .status(status)
+        .header(header::ETAG, CalDavAdapter::object_etag(&object))
+        .body(Body::empty())
+        .unwrap())
+}
+
+/**
+ * Handles DELETE of a calendar object, honouring `If-Match`. Deleting an
+ * event cancels it for its attendees or, for an attendee, declines it,
+ * unless the client sends `Schedule-Reply: F` (RFC 6638, section 8.1).
+ */
+async fn handle_delete_object(
+    ctx: &CalDavContext<'_>,
+    calendar_id: &str,
+    name: &str,
+    headers: &HeaderMap,
+) -> Result<Response<Body>, AppError> {
+    let calendar = ctx.service.get_calendar(calendar_id, &ctx.user.id).await?;
+    let object = ctx
+        .find_object(&calendar, name)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Calendar object not found: {}", name)))?;
+    check_write_preconditions(headers, Some(&CalDavAdapter::object_etag(&object)))?;
+
+    match &object {
+        CalendarObjectDto::Event(event) => {
+            ctx.service.delete_event(&event.id, &ctx.user.id).await?;
+            let silent = headers
+                .get("Schedule-Reply")
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|reply| reply.trim().eq_ignore_ascii_case("F"));
+            if !silent {
+                ctx.schedule_change(Some(&event.ical_data), None).await;
+            }
+        }
+        CalendarObjectDto::Task(task) => ctx.service.delete_task(&task.id, &ctx.user.id).await?,
+    }
+    let key = format!("{}{}", RESOURCE_PROPERTY_PREFIX, name);
+    if calendar.custom_properties.contains_key(&key) {
+        ctx.service
+            .remove_calendar_property(calendar_id, &key, &ctx.user.id)
+            .await?;
+    }
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::ports::auth_ports::UserStoragePort;
+    use crate::application::ports::scheduling_ports::ScheduleInboxStoragePort;
+    use crate::application::services::calendar_service::CalendarService;
+    use crate::application::services::scheduling_service::SchedulingService;
+    use crate::common::errors::DomainError;
+    use crate::domain::entities::calendar::Calendar;
+    use crate::domain::entities::calendar_event::CalendarEvent;
+    use crate::domain::entities::calendar_task::CalendarTask;
+    use crate::domain::entities::user::{User, UserRole};
+    use crate::domain::repositories::calendar_event_repository::CalendarEventRepository;
+    use crate::domain::repositories::calendar_repository::CalendarRepository;
+    use crate::domain::repositories::calendar_task_repository::CalendarTaskRepository;
+    use crate::infrastructure::repositories::calendar_storage_adapter::CalendarStorageAdapter;
+    use async_trait::async_trait;
+    use chrono::{DateTime, Datelike, TimeZone, Utc};
+    use std::sync::Mutex;
+
+    /// Calendars, events and tasks kept in memory
+    #[derive(Default)]
+    struct MemoryCalendars {
+        calendars: Mutex<HashMap<Uuid, Calendar>>,
+        properties: Mutex<HashMap<Uuid, HashMap<String, String>>>,
+        events: Mutex<HashMap<Uuid, CalendarEvent>>,
+        tasks: Mutex<HashMap<Uuid, CalendarTask>>,
+        timezones: Mutex<HashMap<String, String>>,
+    }
+
+    impl MemoryCalendars {
+        fn events_where(&self, filter: impl Fn(&CalendarEvent) -> bool) -> Vec<CalendarEvent> {
+            let mut events: Vec<_> = self
+                .events
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|event| filter(event))
+                .cloned()
+                .collect();
+            events.sort_by_key(|event| *event.start_time());
+            events
+        }
+
+        fn tasks_where(&self, filter: impl Fn(&CalendarTask) -> bool) -> Vec<CalendarTask> {
+            let mut tasks: Vec<_> = self
+                .tasks
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|task| filter(task))
+                .cloned()
+                .collect();
+            tasks.sort_by_key(|task| *task.created_at());
+            tasks
+        }
+    }
+
+    #[async_trait]
+    impl CalendarRepository for MemoryCalendars {
+        async fn create_calendar(&self, calendar: Calendar) -> Result<Calendar, DomainError> {
+            let mut calendars = self.calendars.lock().unwrap();
+            if calendars.contains_key(calendar.id()) {
+                return Err(DomainError::already_exists(
+                    "Calendar",
+                    calendar.id().to_string(),
+                ));
+            }
+            calendars.insert(*calendar.id(), calendar.clone());
+            Ok(calendar)
+        }
+
+        async fn update_calendar(&self, calendar: Calendar) -> Result<Calendar, DomainError> {
+            self.calendars
+                .lock()
+                .unwrap()
+                .insert(*calendar.id(), calendar.clone());
+            Ok(calendar)
+        }
+
+        async fn delete_calendar(&self, id: &Uuid) -> Result<(), DomainError> {
+            self.calendars.lock().unwrap().remove(id);
+            Ok(())
+        }
+
+        async fn find_calendar_by_id(&self, id: &Uuid) -> Result<Calendar, DomainError> {
+            self.calendars
+                .lock()
+                .unwrap()
+                .get(id)
+                .cloned()
+                .ok_or_else(|| DomainError::not_found("Calendar", id.to_string()))
+        }
+
+        async fn list_calendars_by_owner(
+            &self,
+            owner_id: &str,
+        ) -> Result<Vec<Calendar>, DomainError> {
+            Ok(self
+                .calendars
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|calendar| calendar.owner_id() == owner_id)
+                .cloned()
+                .collect())
+        }
+
+        async fn find_calendar_by_name_and_owner(
+            &self,
+            name: &str,
+            owner_id: &str,
+        ) -> Result<Calendar, DomainError> {
+            self.list_calendars_by_owner(owner_id)
+                .await?
+                .into_iter()
+                .find(|calendar| calendar.name() == name)
+                .ok_or_else(|| DomainError::not_found("Calendar", name))
+        }
+
+        async fn list_calendars_shared_with_user(
+            &self,
+            _user_id: &str,
+        ) -> Result<Vec<Calendar>, DomainError> {
+            Ok(Vec::new())
+        }
+
+        async fn list_public_calendars(
+            &self,
+            _limit: i64,
+            _offset: i64,
+        ) -> Result<Vec<Calendar>, DomainError> {
+            Ok(Vec::new())
+        }
+
+        async fn user_has_calendar_access(
+            &self,
+            calendar_id: &Uuid,
+            user_id: &str,
+        ) -> Result<bool, DomainError> {
+            Ok(self.find_calendar_by_id(calendar_id).await?.owner_id() == user_id)
+        }
+
+        async fn get_calendar_property(
+            &self,
+            calendar_id: &Uuid,
+            property_name: &str,
+        ) -> Result<Option<String>, DomainError> {
+            Ok(self
+                .get_calendar_properties(calendar_id)
+                .await?
+                .remove(property_name))
+        }
+
+        async fn set_calendar_property(
+            &self,
+            calendar_id: &Uuid,
+            property_name: &str,
+            property_value: &str,
+        ) -> Result<(), DomainError> {
+            self.properties
+                .lock()
+                .unwrap()
+                .entry(*calendar_id)
+                .or_default()
+                .insert(property_name.to_string(), property_value.to_string());
+            Ok(())
+        }
+
+        async fn remove_calendar_property(
+            &self,
+            calendar_id: &Uuid,
+            property_name: &str,
+        ) -> Result<(), DomainError> {
+            if let Some(properties) = self.properties.lock().unwrap().get_mut(calendar_id) {
+                properties.remove(property_name);
+            }
+            Ok(())
+        }
+
+        async fn get_calendar_properties(
+            &self,
+            calendar_id: &Uuid,
+        ) -> Result<HashMap<String, String>, DomainError> {
+            Ok(self
+                .properties
+                .lock()
+                .unwrap()
+                .get(calendar_id)
+                .cloned()
+                .unwrap_or_default())
+        }
+
+        async fn share_calendar(
+            &self,
+            _calendar_id: &Uuid,
+            _user_id: &str,
+            _access_level: &str,
+        ) -> Result<(), DomainError> {
+            Ok(())
+        }
+
+        async fn remove_calendar_sharing(
+            &self,
+            _calendar_id: &Uuid,
+            _user_id: &str,
+        ) -> Result<(), DomainError> {
+            Ok(())
+        }
+
+        async fn get_calendar_shares(
+            &self,
+            _calendar_id: &Uuid,
+        ) -> Result<Vec<(String, String)>, DomainError> {
+            Ok(Vec::new())
+        }
+
+        async fn get_user_timezone(&self, user_id: &str) -> Result<Option<String>, DomainError> {
+            Ok(self.timezones.lock().unwrap().get(user_id).cloned())
+        }
+
+        async fn set_user_timezone(
+            &self,
+            user_id: &str,
+            timezone: Option<&str>,
+        ) -> Result<(), DomainError> {
+            let mut timezones = self.timezones.lock().unwrap();
+            match timezone {
+                Some(timezone) => timezones.insert(user_id.to_string(), timezone.to_string()),
+                None => timezones.remove(user_id),
+            };
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl CalendarEventRepository for MemoryCalendars {
+        async fn create_event(&self, event: CalendarEvent) -> Result<CalendarEvent, DomainError> {
+            self.events
+                .lock()
+                .unwrap()
+                .insert(*event.id(), event.clone());
+            Ok(event)
+        }
+
+        async fn update_event(&self, event: CalendarEvent) -> Result<CalendarEvent, DomainError> {
+            self.create_event(event).await
+        }
+
+        async fn delete_event(&self, id: &Uuid) -> Result<(), DomainError> {
+            self.events.lock().unwrap().remove(id);
+            Ok(())
+        }
+
+        async fn find_event_by_id(&self, id: &Uuid) -> Result<CalendarEvent, DomainError> {
+            self.events
+                .lock()
+                .unwrap()
+                .get(id)
+                .cloned()
+                .ok_or_else(|| DomainError::not_found("Calendar Event", id.to_string()))
+        }
+
+        async fn list_events_by_calendar(
+            &self,
+            calendar_id: &Uuid,
+        ) -> Result<Vec<CalendarEvent>, DomainError> {
+            Ok(self.events_where(|event| event.belongs_to_calendar(calendar_id)))
+        }
+
+        async fn find_events_by_summary(
+            &self,
+            calendar_id: &Uuid,
+            summary: &str,
+        ) -> Result<Vec<CalendarEvent>, DomainError> {
+            Ok(self.events_where(|event| {
+                event.belongs_to_calendar(calendar_id) && event.summary().contains(summary)
+            }))
+        }
+
+        async fn get_events_in_time_range(
+            &self,
+            calendar_id: &Uuid,
+            start: &DateTime<Utc>,
+            end: &DateTime<Utc>,
+        ) -> Result<Vec<CalendarEvent>, DomainError> {
+            Ok(self.events_where(|event| {
+                event.belongs_to_calendar(calendar_id) && event.occurs_in_range(start, end)
+            }))
+        }
+
+        async fn find_event_by_ical_uid(
+            &self,
+            calendar_id: &Uuid,
+            ical_uid: &str,
+        ) -> Result<Option<CalendarEvent>, DomainError> {
+            Ok(self
+                .events_where(|event| {
+                    event.belongs_to_calendar(calendar_id) && event.ical_uid() == ical_uid
+                })
+                .pop())
+        }
+
+        async fn count_events_in_calendar(&self, calendar_id: &Uuid) -> Result<i64, DomainError> {
+            Ok(self.list_events_by_calendar(calendar_id).await?.len() as i64)
+        }
+
+        async fn delete_all_events_in_calendar(
+            &self,
+            calendar_id: &Uuid,
+        ) -> Result<i64, DomainError> {
+            let mut events = self.events.lock().unwrap();
+            let before = events.len();
+            events.retain(|_, event| !event.belongs_to_calendar(calendar_id));
+            Ok((before - events.len()) as i64)
+        }
+
+        async fn list_events_by_calendar_paginated(
+            &self,
+            calendar_id: &Uuid,
+            limit: i64,
+            offset: i64,
+        ) -> Result<Vec<CalendarEvent>, DomainError> {
+            Ok(self
+                .list_events_by_calendar(calendar_id)
+                .await?
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect())
+        }
+
+        async fn find_recurring_events_in_range(
+            &self,
+            calendar_id: &Uuid,
+            start: &DateTime<Utc>,
+            end: &DateTime<Utc>,
+        ) -> Result<Vec<CalendarEvent>, DomainError> {
+            Ok(self
+                .get_events_in_time_range(calendar_id, start, end)
+                .await?
+                .into_iter()
+                .filter(|event| event.rrule().is_some())
+                .collect())
+        }
+    }
+
+    #[async_trait]
+    impl CalendarTaskRepository for MemoryCalendars {
+        async fn create_task(&self, task: CalendarTask) -> Result<CalendarTask, DomainError> {
+            self.tasks.lock().unwrap().insert(*task.id(), task.clone());
+            Ok(task)
+        }
+
+        async fn update_task(&self, task: CalendarTask) -> Result<CalendarTask, DomainError> {
+            CalendarTaskRepository::create_task(self, task).await
+        }
+
+        async fn delete_task(&self, id: &Uuid) -> Result<(), DomainError> {
+            self.tasks.lock().unwrap().remove(id);
+            Ok(())
+        }
+
+        async fn find_task_by_id(&self, id: &Uuid) -> Result<CalendarTask, DomainError> {
+            self.tasks
+                .lock()
+                .unwrap()
+                .get(id)
+                .cloned()
+                .ok_or_else(|| DomainError::not_found("Calendar Task", id.to_string()))
+        }
+
+        async fn find_task_by_ical_uid(
+            &self,
+            calendar_id: &Uuid,
+            ical_uid: &str,
+        ) -> Result<Option<CalendarTask>, DomainError> {
+            Ok(self
+                .tasks_where(|task| {
+                    task.calendar_id() == calendar_id && task.ical_uid() == ical_uid
+                })
+                .pop())
+        }
+
+        async fn list_tasks_by_calendar(
+            &self,
+            calendar_id: &Uuid,
+        ) -> Result<Vec<CalendarTask>, DomainError> {
+            Ok(self.tasks_where(|task| task.calendar_id() == calendar_id))
+        }
+
+        async fn get_tasks_in_time_range(
+            &self,
+            calendar_id: &Uuid,
+            start: &DateTime<Utc>,
+            end: &DateTime<Utc>,
+        ) -> Result<Vec<CalendarTask>, DomainError> {
+            Ok(self.tasks_where(|task| {
+                task.calendar_id() == calendar_id && task.occurs_in_range(start, end)
+            }))
+        }
+
+        async fn delete_all_tasks_in_calendar(
+            &self,
+            calendar_id: &Uuid,
+        ) -> Result<i64, DomainError> {
+            let mut tasks = self.tasks.lock().unwrap();
+            let before = tasks.len();
+            tasks.retain(|_, task| task.calendar_id() != calendar_id);
+            Ok((before - tasks.len()) as i64)
+        }
+    }
+
+    /// Alice and Bob, the local calendar users
+    struct MemoryUsers {
+        users: Vec<User>,
+    }
+
+    impl MemoryUsers {
+        fn new() -> Self {
+            let user = |id: &str, name: &str| {
+                User::from_data(
+                    id.to_string(),
+                    name.to_string(),
+                    format!("{}@example.com", name),
+                    String::new(),
+                    UserRole::User,
+                    0,
+                    0,
+                    Utc::now(),
+                    Utc::now(),
+                    None,
+                    true,
+                )
+            };
+            Self {
+                users: vec![user("user-alice", "alice"), user("user-bob", "bob")],
+            }
+        }
+
+        fn find(&self, matches: impl Fn(&User) -> bool, key: &str) -> Result<User, DomainError> {
+            self.users
+                .iter()
+                .find(|user| matches(user))
+                .cloned()
+                .ok_or_else(|| DomainError::not_found("User", key))
+        }
+    }
+
+    #[async_trait]
+    impl UserStoragePort for MemoryUsers {
+        async fn create_user(&self, _user: User) -> Result<User, DomainError> {
+            unimplemented!()
+        }
+        async fn get_user_by_id(&self, id: &str) -> Result<User, DomainError> {
+            self.find(|user| user.id() == id, id)
+        }
+        async fn get_user_by_username(&self, username: &str) -> Result<User, DomainError> {
+            self.find(|user| user.username() == username, username)
+        }
+        async fn get_user_by_email(&self, email: &str) -> Result<User, DomainError> {
+            self.find(|user| user.email() == email, email)
+        }
+        async fn update_user(&self, _user: User) -> Result<User, DomainError> {
+            unimplemented!()
+        }
+        async fn update_storage_usage(
+            &self,
+            _user_id: &str,
+            _usage_bytes: i64,
+        ) -> Result<(), DomainError> {
+            unimplemented!()
+        }
+        async fn list_users(&self, _limit: i64, _offset: i64) -> Result<Vec<User>, DomainError> {
+            unimplemented!()
+        }
+        async fn list_users_by_role(&self, _role: &str) -> Result<Vec<User>, DomainError> {
+            unimplemented!()
+        }
+        async fn delete_user(&self, _user_id: &str) -> Result<(), DomainError> {
+            unimplemented!()
+        }
+        async fn change_password(
+            &self,
+            _user_id: &str,
+            _password_hash: &str,
+        ) -> Result<(), DomainError> {
+            unimplemented!()
+        }
+    }
+
+    /// Scheduling inboxes kept in memory
+    #[derive(Default)]
+    struct MemoryInbox {
+        messages: Mutex<Vec<ScheduleMessage>>,
+    }
+
+    #[async_trait]
+    impl ScheduleInboxStoragePort for MemoryInbox {
+        async fn save_message(
+            &self,
+            message: ScheduleMessage,
+        ) -> Result<ScheduleMessage, DomainError> {
+            self.messages.lock().unwrap().push(message.clone());
+            Ok(message)
+        }
+
+        async fn list_messages(&self, user_id: &str) -> Result<Vec<ScheduleMessage>, DomainError> {
+            Ok(self
+                .messages
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|message| message.user_id == user_id)
+                .cloned()
+                .collect())
+        }
+
+        async fn find_message(
+            &self,
+            user_id: &str,
+            message_id: &str,
+        ) -> Result<Option<ScheduleMessage>, DomainError> {
+            Ok(self
+                .list_messages(user_id)
+                .await?
+                .into_iter()
+                .find(|message| message.id.to_string() == message_id))
+        }
+
+        async fn delete_message(&self, user_id: &str, message_id: &str) -> Result<(), DomainError> {
+            self.messages.lock().unwrap().retain(|message| {
+                message.user_id != user_id || message.id.to_string() != message_id
+            });
+            Ok(())
+        }
+    }
+
+    fn caldav_state() -> Arc<AppState> {
+        let store = Arc::new(MemoryCalendars::default());
+        let storage = CalendarStorageAdapter::new(store.clone(), store.clone(), store);
+        let calendars: Arc<dyn CalendarUseCase> = Arc::new(CalendarService::new(Arc::new(storage)));
+        let scheduling = SchedulingService::new(
+            calendars.clone(),
+            Arc::new(MemoryUsers::new()),
+            Arc::new(MemoryInbox::default()),
+        );
+        Arc::new(
+            AppState::default()
+                .with_calendar_service(calendars)
+                .with_scheduling_service(Arc::new(scheduling)),
+        )
+    }
+
+    struct Reply {
+        status: StatusCode,
+        headers: HeaderMap,
+        body: String,
+    }
+
+    impl Reply {
+        fn etag(&self) -> String {
+            self.headers[header::ETAG].to_str().unwrap().to_string()
+        }
+    }
+
+    /// Sends a request as alice, the way a client would
+    async fn send(
+        state: &Arc<AppState>,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Reply {
+        send_as(state, "alice", method, path, headers, body).await
+    }
+
+    /// Sends a request as one of the users of `MemoryUsers`
+    async fn send_as(
+        state: &Arc<AppState>,
+        username: &str,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Reply {
+        let mut builder = Request::builder().method(method).uri(path);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let mut req = builder.body(Body::from(body.to_string())).unwrap();
+        req.extensions_mut().insert(CurrentUser {
+            id: format!("user-{}", username),
+            username: username.to_string(),
+            email: format!("{}@example.com", username),
+            role: "user".to_string(),
+        });
+
+        let response = match dispatch(state.clone(), req).await {
+            Ok(response) => response,
+            Err(e) => Response::builder()
+                .status(e.status_code)
+                .body(Body::from(e.message))
+                .unwrap(),
+        };
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        Reply {
+            status,
+            headers,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        }
+    }
+
+    fn event(uid: &str, summary: &str, start: &str, end: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nBEGIN:VEVENT\r\n\
+             UID:{}\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:{}\r\nDTEND:{}\r\n\
+             SUMMARY:{}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            uid, start, end, summary
+        )
+    }
+
+    fn task(uid: &str, summary: &str, due: &str, extra: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nBEGIN:VTODO\r\n\
+             UID:{}\r\nDTSTAMP:20240101T000000Z\r\nDUE:{}\r\nSUMMARY:{}\r\n{}\
+             END:VTODO\r\nEND:VCALENDAR\r\n",
+            uid, due, summary, extra
+        )
+    }
+
+    /// Replays the discovery and synchronization of Thunderbird
+    #[tokio::test]
+    async fn test_thunderbird_replay() {
+        let state = caldav_state();
+        let service = state.calendar_service.clone().unwrap();
+        let calendar = service
+            .create_calendar(
+                CreateCalendarDto {
+                    id: None,
+                    name: "Work".to_string(),
+                    description: None,
+                    color: Some("#3366CC".to_string()),
+                    is_public: None,
+                },
+                "user-alice",
+            )
+            .await
+            .unwrap();
+        let calendar_href = format!("/caldav/calendars/alice/{}/", calendar.id);
+
+        let redirect = well_known_caldav().await;
+        assert_eq!(redirect.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(redirect.headers()[header::LOCATION], "/caldav/");
+
+        let root = send(
+            &state,
+            "PROPFIND",
+            "/caldav/",
+            &[("Depth", "0")],
+            r#"<D:propfind xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:prop><D:current-user-principal/><C:calendar-home-set/></D:prop>
+               </D:propfind>"#,
+        )
+        .await;
+        assert_eq!(root.status, StatusCode::MULTI_STATUS);
+        assert!(root.body.contains("/principals/users/alice/"));
+        assert!(root
+            .body
+            .contains("<C:calendar-home-set><D:href>/caldav/calendars/alice/</D:href>"));
+
+        let home = send(
+            &state,
+            "PROPFIND",
+            "/caldav/calendars/alice/",
+            &[("Depth", "1")],
+            r#"<D:propfind xmlns:D="DAV:" xmlns:CS="http://calendarserver.org/ns/"
+                           xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:prop>
+                   <D:resourcetype/><D:displayname/><CS:getctag/>
+                   <C:supported-calendar-component-set/><D:current-user-privilege-set/>
+                 </D:prop>
+               </D:propfind>"#,
+        )
+        .await;
+        assert!(home.body.contains(&calendar_href));
+        assert!(home.body.contains("<D:displayname>Work</D:displayname>"));
+        assert!(home.body.contains("<C:comp name=\"VEVENT\"/>"));
+        let ctag = |body: &str| {
+            let start = body.find("<CS:getctag>").unwrap() + "<CS:getctag>".len();
+            body[start..start + 32].to_string()
+        };
+        let empty_ctag = ctag(&home.body);
+
+        // New events are only created if nothing is there yet
+        let object = format!("{}meeting-1.ics", calendar_href);
+        let created = send(
+            &state,
+            "PUT",
+            &object,
+            &[("If-None-Match", "*")],
+            &event(
+                "meeting-1",
+                "Planning",
+                "20240110T090000Z",
+                "20240110T100000Z",
+            ),
+        )
+        .await;
+        assert_eq!(created.status, StatusCode::CREATED);
+        let etag = created.etag();
+        let again = send(
+            &state,
+            "PUT",
+            &object,
+            &[("If-None-Match", "*")],
+            &event(
+                "meeting-1",
+                "Planning",
+                "20240110T090000Z",
+                "20240110T100000Z",
+            ),
+        )
+        .await;
+        assert_eq!(again.status, StatusCode::PRECONDITION_FAILED);
+
+        let listing = send(
+            &state,
+            "PROPFIND",
+            &calendar_href,
+            &[("Depth", "1")],
+            r#"<D:propfind xmlns:D="DAV:" xmlns:CS="http://calendarserver.org/ns/">
+                 <D:prop><D:getetag/><D:getcontenttype/><CS:getctag/></D:prop>
+               </D:propfind>"#,
+        )
+        .await;
+        assert!(listing
+            .body
+            .contains(&format!("<D:href>{}</D:href>", object)));
+        assert!(listing.body.contains(&format!(
+            "<D:getetag>{}</D:getetag>",
+            etag.replace('"', "&quot;")
+        )));
+        assert_ne!(ctag(&listing.body), empty_ctag);
+
+        let multiget = send(
+            &state,
+            "REPORT",
+            &calendar_href,
+            &[("Depth", "1")],
+            &format!(
+                r#"<C:calendar-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                     <D:prop><D:getetag/><C:calendar-data/></D:prop>
+                     <D:href>{}</D:href>
+                     <D:href>{}missing.ics</D:href>
+                   </C:calendar-multiget>"#,
+                object, calendar_href
+            ),
+        )
+        .await;
+        assert_eq!(multiget.status, StatusCode::MULTI_STATUS);
+        assert!(multiget.body.contains("SUMMARY:Planning"));
+        assert!(multiget
+            .body
+            .contains("missing.ics</D:href><D:status>HTTP/1.1 404"));
+
+        // Updates must match the ETag the client last saw
+        let changed = event(
+            "meeting-1",
+            "Planning v2",
+            "20240110T090000Z",
+            "20240110T110000Z",
+        );
+        let stale = send(
+            &state,
+            "PUT",
+            &object,
+            &[("If-Match", "\"stale\"")],
+            &changed,
+        )
+        .await;
+        assert_eq!(stale.status, StatusCode::PRECONDITION_FAILED);
+        let updated = send(&state, "PUT", &object, &[("If-Match", &etag)], &changed).await;
+        assert_eq!(updated.status, StatusCode::NO_CONTENT);
+        assert_ne!(updated.etag(), etag);
+
+        let fetched = send(&state, "GET", &object, &[], "").await;
+        assert_eq!(fetched.body, changed);
+        assert_eq!(fetched.etag(), updated.etag());
+        assert_eq!(
+            fetched.headers[header::CONTENT_TYPE],
+            "text/calendar; charset=utf-8"
+        );
+    }
+
+    /// Replays the time-range synchronization and deletes of DAVx5
+    #[tokio::test]
+    async fn test_davx5_replay() {
+        let state = caldav_state();
+        let calendar_id = Uuid::new_v4().to_string();
+        let calendar_href = format!("/caldav/calendars/alice/{}/", calendar_id);
+        let made = send(&state, "MKCALENDAR", &calendar_href, &[], "").await;
+        assert_eq!(made.status, StatusCode::CREATED);
+
+        for (uid, start, end) in [
+            ("jan", "20240115T120000Z", "20240115T130000Z"),
+            ("mar", "20240315T120000Z", "20240315T130000Z"),
+        ] {
+            let put = send(
+                &state,
+                "PUT",
+                &format!("{}{}.ics", calendar_href, uid),
+                &[("If-None-Match", "*")],
+                &event(uid, uid, start, end),
+            )
+            .await;
+            assert_eq!(put.status, StatusCode::CREATED);
+        }
+
+        let query = send(
+            &state,
+            "REPORT",
+            &calendar_href,
+            &[("Depth", "1")],
+            r#"<CAL:calendar-query xmlns="DAV:" xmlns:CAL="urn:ietf:params:xml:ns:caldav">
+                 <prop><getetag/></prop>
+                 <CAL:filter>
+                   <CAL:comp-filter name="VCALENDAR">
+                     <CAL:comp-filter name="VEVENT">
+                       <CAL:time-range start="20240101T000000Z" end="20240201T000000Z"/>
+                     </CAL:comp-filter>
+                   </CAL:comp-filter>
+                 </CAL:filter>
+               </CAL:calendar-query>"#,
+        )
+        .await;
+        assert!(query.body.contains("jan.ics"));
+        assert!(!query.body.contains("mar.ics"));
+        assert!(query.body.contains("<D:getetag>"));
+
+        let todos = send(
+            &state,
+            "REPORT",
+            &calendar_href,
+            &[("Depth", "1")],
+            r#"<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:prop><D:getetag/></D:prop>
+                 <C:filter><C:comp-filter name="VCALENDAR">
+                   <C:comp-filter name="VTODO"/>
+                 </C:comp-filter></C:filter>
+               </C:calendar-query>"#,
+        )
+        .await;
+        assert!(!todos.body.contains("<D:response>"));
+
+        let object = format!("{}jan.ics", calendar_href);
+        let etag = send(&state, "HEAD", &object, &[], "").await.etag();
+        let refused = send(&state, "DELETE", &object, &[("If-Match", "\"old\"")], "").await;
+        assert_eq!(refused.status, StatusCode::PRECONDITION_FAILED);
+        let deleted = send(&state, "DELETE", &object, &[("If-Match", &etag)], "").await;
+        assert_eq!(deleted.status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            send(&state, "GET", &object, &[], "").await.status,
+            StatusCode::NOT_FOUND
+        );
+
+        // Other users' homes are off limits
+        let other = send(&state, "PROPFIND", "/caldav/calendars/bob/", &[], "").await;
+        assert_eq!(other.status, StatusCode::FORBIDDEN);
+    }
+
+    /// A weekly meeting with a cancelled and a moved instance, read back as
+    /// expanded instances both by the service and by a CalDAV `expand`
+    #[tokio::test]
+    async fn test_recurring_event_expansion() {
+        let state = caldav_state();
+        let calendar_id = Uuid::new_v4().to_string();
+        let calendar_href = format!("/caldav/calendars/alice/{}/", calendar_id);
+        send(&state, "MKCALENDAR", &calendar_href, &[], "").await;
+
+        let weekly = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\n\
+             BEGIN:VEVENT\r\nUID:weekly\r\nSUMMARY:Weekly sync\r\n\
+             DTSTART:20240101T100000Z\r\nDTEND:20240101T110000Z\r\n\
+             RRULE:FREQ=WEEKLY;BYDAY=MO\r\nEXDATE:20240115T100000Z\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nUID:weekly\r\nRECURRENCE-ID:20240122T100000Z\r\n\
+             SUMMARY:Weekly sync (moved)\r\nDTSTART:20240123T150000Z\r\n\
+             DTEND:20240123T160000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let put = send(
+            &state,
+            "PUT",
+            &format!("{}weekly.ics", calendar_href),
+            &[],
+            weekly,
+        )
+        .await;
+        assert_eq!(put.status, StatusCode::CREATED);
+
+        let service = state.calendar_service.as_ref().unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 8, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let instances = service
+            .get_events_in_range(&calendar_id, start, end, "user-alice")
+            .await
+            .unwrap();
+        let summaries: Vec<_> = instances
+            .iter()
+            .map(|instance| (instance.start_time.day(), instance.summary.as_str()))
+            .collect();
+        assert_eq!(
+            summaries,
+            [
+                (8, "Weekly sync"),
+                (23, "Weekly sync (moved)"),
+                (29, "Weekly sync")
+            ]
+        );
+        assert_eq!(
+            instances[1].recurrence_id,
+            Utc.with_ymd_and_hms(2024, 1, 22, 10, 0, 0).single()
+        );
+
+        let query = send(
+            &state,
+            "REPORT",
+            &calendar_href,
+            &[("Depth", "1")],
+            r#"<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:prop>
+                   <D:getetag/>
+                   <C:calendar-data>
+                     <C:expand start="20240108T000000Z" end="20240201T000000Z"/>
+                   </C:calendar-data>
+                 </D:prop>
+                 <C:filter><C:comp-filter name="VCALENDAR">
+                   <C:comp-filter name="VEVENT">
+                     <C:time-range start="20240108T000000Z" end="20240201T000000Z"/>
+                   </C:comp-filter>
+                 </C:comp-filter></C:filter>
+               </C:calendar-query>"#,
+        )
+        .await;
+        assert_eq!(query.body.matches("<D:response>").count(), 1);
+        assert_eq!(query.body.matches("BEGIN:VEVENT").count(), 3);
+        assert!(query.body.contains("RECURRENCE-ID:20240122T100000Z"));
+        assert!(!query.body.contains("RRULE"));
+
+        // Months after the first instance, the event is still found
+        let later = send(
+            &state,
+            "REPORT",
+            &calendar_href,
+            &[("Depth", "1")],
+            r#"<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:prop>
+                   <C:calendar-data>
+                     <C:limit-recurrence-set start="20240601T000000Z" end="20240701T000000Z"/>
+                   </C:calendar-data>
+                 </D:prop>
+                 <C:filter><C:comp-filter name="VCALENDAR">
+                   <C:comp-filter name="VEVENT">
+                     <C:time-range start="20240603T000000Z" end="20240604T000000Z"/>
+                   </C:comp-filter>
+                 </C:comp-filter></C:filter>
+               </C:calendar-query>"#,
+        )
+        .await;
+        assert!(later.body.contains("RRULE:FREQ=WEEKLY;BYDAY=MO"));
+        assert!(!later.body.contains("(moved)"));
+    }
+
+    /// The home's calendar-timezone sets the zone floating times are read
+    /// in, so a weekly meeting keeps its local time across the DST change
+    #[tokio::test]
+    async fn test_default_timezone_and_floating_events() {
+        let state = caldav_state();
+        let timezone_prop = r#"<D:propfind xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:prop><C:calendar-timezone/></D:prop>
+               </D:propfind>"#;
+        let home = send(
+            &state,
+            "PROPFIND",
+            "/caldav/calendars/alice/",
+            &[("Depth", "0")],
+            timezone_prop,
+        )
+        .await;
+        assert!(home.body.contains("TZID:UTC"));
+
+        let proppatch = |zone: &str| {
+            format!(
+                r#"<D:propertyupdate xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                     <D:set><D:prop><C:calendar-timezone>BEGIN:VCALENDAR
+BEGIN:VTIMEZONE
+TZID:{}
+END:VTIMEZONE
+END:VCALENDAR
+</C:calendar-timezone></D:prop></D:set>
+                   </D:propertyupdate>"#,
+                zone
+            )
+        };
+        let unknown = send(
+            &state,
+            "PROPPATCH",
+            "/caldav/calendars/alice/",
+            &[],
+            &proppatch("Mars/Olympus_Mons"),
+        )
+        .await;
+        assert!(unknown.body.contains("403"));
+        let patched = send(
+            &state,
+            "PROPPATCH",
+            "/caldav/calendars/alice/",
+            &[],
+            &proppatch("Europe/Berlin"),
+        )
+        .await;
+        assert_eq!(patched.status, StatusCode::MULTI_STATUS);
+        assert!(patched.body.contains("200 OK"));
+
+        let home = send(
+            &state,
+            "PROPFIND",
+            "/caldav/calendars/alice/",
+            &[("Depth", "0")],
+            timezone_prop,
+        )
+        .await;
+        assert!(home.body.contains("TZID:Europe/Berlin"));
+        assert!(home.body.contains("BEGIN:DAYLIGHT"));
+
+        let calendar_id = Uuid::new_v4().to_string();
+        let calendar_href = format!("/caldav/calendars/alice/{}/", calendar_id);
+        send(&state, "MKCALENDAR", &calendar_href, &[], "").await;
+        let floating = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\n\
+             BEGIN:VEVENT\r\nUID:floating\r\nSUMMARY:Breakfast\r\n\
+             DTSTART:20240321T090000\r\nDTEND:20240321T100000\r\n\
+             RRULE:FREQ=WEEKLY;COUNT=3\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let put = send(
+            &state,
+            "PUT",
+            &format!("{}floating.ics", calendar_href),
+            &[],
+            floating,
+        )
+        .await;
+        assert_eq!(put.status, StatusCode::CREATED);
+
+        let service = state.calendar_service.as_ref().unwrap();
+        let instances = service
+            .get_events_in_range(
+                &calendar_id,
+                Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap(),
+                "user-alice",
+            )
+            .await
+            .unwrap();
+        let starts: Vec<_> = instances
+            .iter()
+            .map(|instance| instance.start_time)
+            .collect();
+        assert_eq!(
+            starts,
+            [
+                Utc.with_ymd_and_hms(2024, 3, 21, 8, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 3, 28, 8, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 4, 4, 7, 0, 0).unwrap()
+            ]
+        );
+        assert_eq!(instances[0].timezone.as_deref(), Some("Europe/Berlin"));
+    }
+
+    /// Replays calendar creation and editing by Apple Calendar, which names
+    /// resources freely and sends colors with an alpha channel
+    #[tokio::test]
+    async fn test_apple_calendar_replay() {
+        let state = caldav_state();
+        let refused = send(
+            &state,
+            "MKCALENDAR",
+            "/caldav/calendars/alice/personal/",
+            &[],
+            "",
+        )
+        .await;
+        assert_eq!(refused.status, StatusCode::FORBIDDEN);
+        assert!(refused.body.contains("calendar-collection-location-ok"));
+
+        let calendar_href = format!("/caldav/calendars/alice/{}/", Uuid::new_v4());
+        let made = send(
+            &state,
+            "MKCALENDAR",
+            &calendar_href,
+            &[],
+            r#"<C:mkcalendar xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav"
+                             xmlns:A="http://apple.com/ns/ical/">
+                 <D:set><D:prop>
+                   <D:displayname>Home</D:displayname>
+                   <A:calendar-color>#FF2968FF</A:calendar-color>
+                 </D:prop></D:set>
+               </C:mkcalendar>"#,
+        )
+        .await;
+        assert_eq!(made.status, StatusCode::CREATED);
+
+        let patched = send(
+            &state,
+            "PROPPATCH",
+            &calendar_href,
+            &[],
+            r#"<D:propertyupdate xmlns:D="DAV:" xmlns:A="http://apple.com/ns/ical/">
+                 <D:set><D:prop>
+                   <A:calendar-order>2</A:calendar-order>
+                   <A:calendar-color>#1BADF8FF</A:calendar-color>
+                 </D:prop></D:set>
+               </D:propertyupdate>"#,
+        )
+        .await;
+        assert_eq!(patched.status, StatusCode::MULTI_STATUS);
+        assert!(!patched.body.contains("403"));
+        let protected = send(
+            &state,
+            "PROPPATCH",
+            &calendar_href,
+            &[],
+            r#"<D:propertyupdate xmlns:D="DAV:" xmlns:CS="http://calendarserver.org/ns/">
+                 <D:set><D:prop><CS:getctag>1</CS:getctag></D:prop></D:set>
+               </D:propertyupdate>"#,
+        )
+        .await;
+        assert!(protected.body.contains("HTTP/1.1 403 Forbidden"));
+
+        let props = send(&state, "PROPFIND", &calendar_href, &[("Depth", "0")], "").await;
+        assert!(props
+            .body
+            .contains("<ICAL:calendar-color>#1BADF8</ICAL:calendar-color>"));
+        assert!(props.body.contains(">2</x:calendar-order>"));
+
+        // The resource name need not be the UID
+        let object = format!("{}1A2B3C.ics", calendar_href);
+        let put = send(
+            &state,
+            "PUT",
+            &object,
+            &[("If-None-Match", "*")],
+            &event(
+                "apple-uid-1",
+                "Dentist",
+                "20240120T150000Z",
+                "20240120T160000Z",
+            ),
+        )
+        .await;
+        assert_eq!(put.status, StatusCode::CREATED);
+        let listing = send(&state, "PROPFIND", &calendar_href, &[("Depth", "1")], "").await;
+        assert!(listing
+            .body
+            .contains(&format!("<D:href>{}</D:href>", object)));
+        assert!(!listing.body.contains("apple-uid-1.ics"));
+        assert!(send(&state, "GET", &object, &[], "")
+            .await
+            .body
+            .contains("SUMMARY:Dentist"));
+
+        // A second resource cannot reuse the UID
+        let duplicate = send(
+            &state,
+            "PUT",
+            &format!("{}other.ics", calendar_href),
+            &[],
+            &event(
+                "apple-uid-1",
+                "Dentist",
+                "20240120T150000Z",
+                "20240120T160000Z",
+            ),
+        )
+        .await;
+        assert_eq!(duplicate.status, StatusCode::FORBIDDEN);
+        assert!(duplicate.body.contains("no-uid-conflict"));
+
+        let deleted = send(&state, "DELETE", &calendar_href, &[], "").await;
+        assert_eq!(deleted.status, StatusCode::NO_CONTENT);
+        let home = send(
+            &state,
+            "PROPFIND",
+            "/caldav/calendars/alice/",
+            &[("Depth", "1")],
+            "",
+        )
+        .await;
+        assert!(!home.body.contains(&calendar_href));
+    }
+
+    /// A task list the way Tasks.org creates and syncs it through DAVx5
+    #[tokio::test]
+    async fn test_task_list_replay() {
+        let state = caldav_state();
+        let calendar_id = Uuid::new_v4().to_string();
+        let calendar_href = format!("/caldav/calendars/alice/{}/", calendar_id);
+        let made = send(
+            &state,
+            "MKCALENDAR",
+            &calendar_href,
+            &[],
+            r#"<C:mkcalendar xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:set><D:prop>
+                   <D:displayname>Chores</D:displayname>
+                   <C:supported-calendar-component-set>
+                     <C:comp name="VTODO"/>
+                   </C:supported-calendar-component-set>
+                 </D:prop></D:set>
+               </C:mkcalendar>"#,
+        )
+        .await;
+        assert_eq!(made.status, StatusCode::CREATED);
+
+        let props = send(
+            &state,
+            "PROPFIND",
+            &calendar_href,
+            &[("Depth", "0")],
+            r#"<D:propfind xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:prop><C:supported-calendar-component-set/></D:prop>
+               </D:propfind>"#,
+        )
+        .await;
+        assert!(props.body.contains(r#"<C:comp name="VTODO"/>"#));
+        assert!(!props.body.contains(r#"<C:comp name="VEVENT"/>"#));
+
+        // The list only takes tasks
+        let meeting = send(
+            &state,
+            "PUT",
+            &format!("{}meeting.ics", calendar_href),
+            &[],
+            &event("meeting", "Meeting", "20240110T090000Z", "20240110T100000Z"),
+        )
+        .await;
+        assert_eq!(meeting.status, StatusCode::FORBIDDEN);
+        assert!(meeting.body.contains("supported-calendar-component"));
+
+        for (uid, summary, due, extra) in [
+            ("milk", "Buy milk", "20240110T180000Z", ""),
+            (
+                "report",
+                "Send report",
+                "20240112T170000Z",
+                "STATUS:COMPLETED\r\nCOMPLETED:20240111T090000Z\r\n",
+            ),
+            (
+                "slides",
+                "Prepare slides",
+                "20240111T170000Z",
+                "RELATED-TO:report\r\nPRIORITY:1\r\n",
+            ),
+            ("taxes", "File taxes", "20240415T170000Z", ""),
+        ] {
+            let put = send(
+                &state,
+                "PUT",
+                &format!("{}{}.ics", calendar_href, uid),
+                &[("If-None-Match", "*")],
+                &task(uid, summary, due, extra),
+            )
+            .await;
+            assert_eq!(put.status, StatusCode::CREATED, "{}", put.body);
+        }
+
+        // Open tasks due in January
+        let open = send(
+            &state,
+            "REPORT",
+            &calendar_href,
+            &[("Depth", "1")],
+            r#"<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:prop><D:getetag/><D:getcontenttype/></D:prop>
+                 <C:filter><C:comp-filter name="VCALENDAR">
+                   <C:comp-filter name="VTODO">
+                     <C:time-range start="20240101T000000Z" end="20240201T000000Z"/>
+                     <C:prop-filter name="COMPLETED"><C:is-not-defined/></C:prop-filter>
+                   </C:comp-filter>
+                 </C:comp-filter></C:filter>
+               </C:calendar-query>"#,
+        )
+        .await;
+        assert!(open.body.contains("milk.ics"));
+        assert!(open.body.contains("slides.ics"));
+        assert!(!open.body.contains("report.ics"));
+        assert!(!open.body.contains("taxes.ics"));
+        assert!(open.body.contains("component=VTODO"));
+
+        let service = state.calendar_service.as_ref().unwrap();
+        let tasks = service
+            .list_tasks(&calendar_id, "user-alice")
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 4);
+        let slides = tasks.iter().find(|task| task.ical_uid == "slides").unwrap();
+        assert_eq!(slides.parent_uid.as_deref(), Some("report"));
+        assert_eq!(slides.priority, Some(1));
+        let report = tasks.iter().find(|task| task.ical_uid == "report").unwrap();
+        assert_eq!(report.status, "COMPLETED");
+        assert!(report.completed_at.is_some());
+
+        // A task cannot be deleted with a stale ETag, and goes away for good
+        let object = format!("{}milk.ics", calendar_href);
+        let etag = send(&state, "HEAD", &object, &[], "").await.etag();
+        let refused = send(&state, "DELETE", &object, &[("If-Match", "\"old\"")], "").await;
+        assert_eq!(refused.status, StatusCode::PRECONDITION_FAILED);
+        let deleted = send(&state, "DELETE", &object, &[("If-Match", &etag)], "").await;
+        assert_eq!(deleted.status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            send(&state, "GET", &object, &[], "").await.status,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    fn meeting(bob_partstat: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nBEGIN:VEVENT\r\n\
+             UID:planning\r\nDTSTAMP:20240101T000000Z\r\nDTSTART:20240115T100000Z\r\n\
+             DTEND:20240115T110000Z\r\nSUMMARY:Planning\r\n\
+             ORGANIZER;CN=Alice:mailto:alice@example.com\r\n\
+             ATTENDEE;PARTSTAT=ACCEPTED:mailto:alice@example.com\r\n\
+             ATTENDEE;PARTSTAT={};RSVP=TRUE:mailto:bob@example.com\r\n\
+             ATTENDEE;PARTSTAT=NEEDS-ACTION:mailto:zoe@elsewhere.org\r\n\
+             BEGIN:VALARM\r\nTRIGGER:-PT10M\r\nACTION:DISPLAY\r\nDESCRIPTION:Planning\r\n\
+             END:VALARM\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            bob_partstat
+        )
+    }
+
+    /// Alice invites Bob, Bob accepts and Alice cancels, each through
+    /// their own CalDAV client
+    #[tokio::test]
+    async fn test_scheduling_between_users() {
+        let state = caldav_state();
+        let service = state.calendar_service.clone().unwrap();
+        let scheduling = state.scheduling_service.clone().unwrap();
+
+        let options = send(&state, "OPTIONS", "/caldav/", &[], "").await;
+        assert!(options.headers[HEADER_DAV]
+            .to_str()
+            .unwrap()
+            .contains("calendar-auto-schedule"));
+
+        let alice_calendar = format!("/caldav/calendars/alice/{}/", Uuid::new_v4());
+        let made = send(
+            &state,
+            "MKCALENDAR",
+            &alice_calendar,
+            &[],
+            r#"<C:mkcalendar xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <D:set><D:prop><D:displayname>Work</D:displayname></D:prop></D:set>
+               </C:mkcalendar>"#,
+        )
+        .await;
+        assert_eq!(made.status, StatusCode::CREATED);
+        let alice_event = format!("{}planning.ics", alice_calendar);
+        let created = send(&state, "PUT", &alice_event, &[], &meeting("NEEDS-ACTION")).await;
+        assert_eq!(created.status, StatusCode::CREATED);
+
+        // Bob had no calendar: the invitation lands in a new one
+        let bob_calendars = service.list_my_calendars("user-bob").await.unwrap();
+        assert_eq!(bob_calendars.len(), 1);
+        let bob_event = format!("/caldav/calendars/bob/{}/planning.ics", bob_calendars[0].id);
+        let copy = send_as(&state, "bob", "GET", &bob_event, &[], "").await;
+        assert_eq!(copy.status, StatusCode::OK);
+        assert!(copy.body.contains("SUMMARY:Planning"));
+        assert!(!copy.body.contains("METHOD:"));
+        assert!(!copy.body.contains("VALARM"));
+
+        let home = send_as(
+            &state,
+            "bob",
+            "PROPFIND",
+            "/caldav/calendars/bob/",
+            &[("Depth", "1")],
+            r#"<D:propfind xmlns:D="DAV:"><D:prop><D:resourcetype/></D:prop></D:propfind>"#,
+        )
+        .await;
+        assert!(home.body.contains("<C:schedule-inbox/>"));
+        assert!(home.body.contains("<C:schedule-outbox/>"));
+
+        let inbox = scheduling.list_inbox("user-bob").await.unwrap();
+        assert_eq!(inbox.len(), 1);
+        let message_href = format!("/caldav/calendars/bob/inbox/{}.ics", inbox[0].id);
+        let listing = send_as(
+            &state,
+            "bob",
+            "PROPFIND",
+            "/caldav/calendars/bob/inbox/",
+            &[("Depth", "1")],
+            r#"<D:propfind xmlns:D="DAV:"><D:prop><D:getetag/></D:prop></D:propfind>"#,
+        )
+        .await;
+        assert_eq!(listing.status, StatusCode::MULTI_STATUS);
+        assert!(listing.body.contains(&message_href));
+        let message = send_as(&state, "bob", "GET", &message_href, &[], "").await;
+        assert!(message.body.contains("METHOD:REQUEST"));
+
+        // Bob accepts: the answer reaches Alice's copy and inbox
+        let accepted = send_as(&state, "bob", "PUT", &bob_event, &[], &meeting("ACCEPTED")).await;
+        assert_eq!(accepted.status, StatusCode::NO_CONTENT);
+        let organizer_copy = send(&state, "GET", &alice_event, &[], "").await;
+        assert!(organizer_copy
+            .body
+            .contains("ATTENDEE;PARTSTAT=ACCEPTED;RSVP=TRUE:mailto:bob@example.com"));
+        let alice_inbox = scheduling.list_inbox("user-alice").await.unwrap();
+        assert_eq!(alice_inbox.len(), 1);
+        assert_eq!(alice_inbox[0].method, scheduling::ItipMethod::Reply);
+
+        // Bob cannot answer for Alice, nor cancel her event by posing as
+        // the organizer of an object with the same UID
+        let forged_reply = send_as(
+            &state,
+            "bob",
+            "POST",
+            "/caldav/calendars/bob/outbox/",
+            &[("Content-Type", "text/calendar")],
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nMETHOD:REPLY\r\n\
+             BEGIN:VEVENT\r\nUID:planning\r\nDTSTAMP:20240102T000000Z\r\n\
+             ORGANIZER:mailto:alice@example.com\r\n\
+             ATTENDEE;PARTSTAT=DECLINED:mailto:alice@example.com\r\n\
+             ATTENDEE;PARTSTAT=ACCEPTED:mailto:bob@example.com\r\n\
+             END:VEVENT\r\nEND:VCALENDAR\r\n",
+        )
+        .await;
+        assert_eq!(forged_reply.status, StatusCode::OK);
+        let forged_cancel = send_as(
+            &state,
+            "bob",
+            "POST",
+            "/caldav/calendars/bob/outbox/",
+            &[("Content-Type", "text/calendar")],
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nMETHOD:CANCEL\r\n\
+             BEGIN:VEVENT\r\nUID:planning\r\nDTSTAMP:20240102T000000Z\r\n\
+             ORGANIZER:mailto:bob@example.com\r\nATTENDEE:mailto:alice@example.com\r\n\
+             STATUS:CANCELLED\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+        )
+        .await;
+        assert_eq!(forged_cancel.status, StatusCode::OK);
+        let organizer_copy = send(&state, "GET", &alice_event, &[], "").await;
+        assert_eq!(organizer_copy.status, StatusCode::OK);
+        assert!(organizer_copy
+            .body
+            .contains("ATTENDEE;PARTSTAT=ACCEPTED:mailto:alice@example.com"));
+        assert_eq!(scheduling.list_inbox("user-alice").await.unwrap().len(), 3);
+
+        // Free-busy lookups, through the outbox and as a REPORT
+        let lookup = send(
+            &state,
+            "POST",
+            "/caldav/calendars/alice/outbox/",
+            &[("Content-Type", "text/calendar")],
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nMETHOD:REQUEST\r\n\
+             BEGIN:VFREEBUSY\r\nUID:lookup\r\nDTSTAMP:20240101T000000Z\r\n\
+             DTSTART:20240115T000000Z\r\nDTEND:20240116T000000Z\r\n\
+             ORGANIZER:mailto:alice@example.com\r\nATTENDEE:mailto:bob@example.com\r\n\
+             ATTENDEE:mailto:zoe@elsewhere.org\r\nEND:VFREEBUSY\r\nEND:VCALENDAR\r\n",
+        )
+        .await;
+        assert_eq!(lookup.status, StatusCode::OK);
+        assert!(lookup
+            .body
+            .contains("<C:request-status>2.0;Success</C:request-status>"));
+        assert!(lookup
+            .body
+            .contains("<C:request-status>3.7;Invalid calendar user</C:request-status>"));
+        assert!(lookup
+            .body
+            .contains("FREEBUSY;FBTYPE=BUSY:20240115T100000Z/20240115T110000Z"));
+
+        let report = send_as(
+            &state,
+            "bob",
+            "REPORT",
+            "/caldav/calendars/bob/",
+            &[("Depth", "1")],
+            r#"<C:free-busy-query xmlns:C="urn:ietf:params:xml:ns:caldav">
+                 <C:time-range start="20240115T000000Z" end="20240116T000000Z"/>
+               </C:free-busy-query>"#,
+        )
+        .await;
+        assert_eq!(report.status, StatusCode::OK);
+        assert!(report.body.contains("BEGIN:VFREEBUSY"));
+        assert!(report
+            .body
+            .contains("FREEBUSY;FBTYPE=BUSY:20240115T100000Z/20240115T110000Z"));
+
+        // Alice cancels: Bob's copy goes away and the cancellation stays
+        assert_eq!(
+            send(&state, "DELETE", &alice_event, &[], "").await.status,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            send_as(&state, "bob", "GET", &bob_event, &[], "")
+                .await
+                .status,
+            StatusCode::NOT_FOUND
+        );
+        let inbox = scheduling.list_inbox("user-bob").await.unwrap();
+        assert_eq!(inbox.len(), 2);
+        assert_eq!(inbox[1].method, scheduling::ItipMethod::Cancel);
+
+        assert_eq!(
+            send_as(&state, "bob", "DELETE", &message_href, &[], "")
+                .await
+                .status,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(scheduling.list_inbox("user-bob").await.unwrap().len(), 1);
+    }
 }
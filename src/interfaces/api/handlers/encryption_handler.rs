@@ -0,0 +1,74 @@
+use axum::{
+    extract::State, http::StatusCode, middleware, response::IntoResponse, routing::post, Json,
+    Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::application::ports::storage_ports::EncryptionKeyPort;
+use crate::common::di::AuthServices;
+use crate::common::errors::{AppError, DomainError};
+use crate::interfaces::middleware::auth::{require_access_token, require_admin};
+
+/// Optional body for a master key rotation
+#[derive(Debug, Default, Deserialize)]
+pub struct RotateKeyRequest {
+    /// New master key, hex encoded. Only accepted, and then required, when
+    /// the master key comes from configuration; otherwise one is generated
+    pub new_master_key: Option<String>,
+}
+
+/// Admin routes for at-rest encryption key management.
+///
+/// Callers need a valid access token of a user with the admin role.
+pub fn encryption_routes<S>(service: Arc<dyn EncryptionKeyPort>, auth: AuthServices) -> Router<S> {
+    Router::new()
+        .route("/rotate-key", post(rotate_master_key))
+        .route("/migrate", post(migrate_plaintext_files))
+        .route_layer(middleware::from_fn(require_admin))
+        .route_layer(middleware::from_fn_with_state(auth, require_access_token))
+        .with_state(service)
+}
+
+/// Rotates the master key and re-wraps every data key
+pub async fn rotate_master_key(
+    State(service): State<Arc<dyn EncryptionKeyPort>>,
+    payload: Option<Json<RotateKeyRequest>>,
+) -> Result<impl IntoResponse, AppError> {
+    let request = payload.map(|Json(request)| request).unwrap_or_default();
+    let new_master_key = request
+        .new_master_key
+        .map(|value| {
+            hex::decode(value.trim())
+                .map_err(|_| DomainError::validation_error("new_master_key must be hex encoded"))
+        })
+        .transpose()?;
+
+    let (master_key_id, keys_rewrapped) = service.rotate_master_key(new_master_key).await?;
+    info!("Encryption master key rotated to {}", master_key_id);
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "master_key_id": master_key_id,
+            "keys_rewrapped": keys_rewrapped,
+        })),
+    ))
+}
+
+/// Encrypts any file content still stored in plaintext
+pub async fn migrate_plaintext_files(
+    State(service): State<Arc<dyn EncryptionKeyPort>>,
+) -> Result<impl IntoResponse, AppError> {
+    let (encrypted, failed) = service.migrate_plaintext_files().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "encrypted": encrypted,
+            "failed": failed,
+        })),
+    ))
+}
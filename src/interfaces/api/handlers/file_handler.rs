@@ -1,6 +1,6 @@
 use axum::{
     extract::{Multipart, Path, Query, State},
-    http::{header, HeaderName, HeaderValue, Response, StatusCode},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Response, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -12,11 +12,17 @@ use std::sync::Arc;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use crate::application::dtos::file_dto::FileDto;
+use crate::application::ports::inbound::FileUseCase;
 use crate::application::services::file_service::{FileService, FileServiceError};
 use crate::common::di::AppState;
+use crate::common::errors::{AppError, DomainError};
 use crate::infrastructure::services::compression_service::{
     CompressionLevel, CompressionService, GzipCompressionService,
 };
+use crate::interfaces::api::http_range::{
+    check_write_preconditions, file_etag, http_date, is_not_modified, serve_file, FileResource,
+};
 
 /**
  * Type aliases for dependency injection state.
@@ -36,7 +42,8 @@ type GlobalState = AppState;
  * 1. File uploads through multipart form data
  * 2. File downloads with optional compression
  * 3. Listing files in folders
- * 4. Moving files between folders
+ * 4. Replacing file contents and moving files between folders, with
+ *    `If-Match`/`If-None-Match` preconditions
  * 5. Deleting files (with trash integration)
  *
  * This component acts as an adapter in the hexagonal architecture, translating
@@ -217,11 +224,15 @@ impl FileHandler {
         }
     }
 
-    /// Downloads a file with optional compression
+    /// Downloads a file with optional compression.
+    ///
+    /// Uncompressed downloads are streamed and honour `Range`, `If-Range`,
+    /// `If-None-Match` and `If-Modified-Since`; gzip responses are always complete.
     pub async fn download_file(
         State(service): State<FileServiceState>,
         Path(id): Path<String>,
         Query(params): Query<HashMap<String, String>>,
+        headers: HeaderMap,
     ) -> impl IntoResponse {
         // Initialize compression service
         let compression_service = GzipCompressionService::new();
@@ -243,13 +254,15 @@ impl FileHandler {
         // Get file info first to check it exists and get metadata
         match service.get_file(&id).await {
             Ok(file) => {
-                // Determine if we should compress based on file type and size
+                // Determine if we should compress based on file type and size.
+                // Range requests are only compressed when explicitly forced.
                 let should_compress = if force_no_compress {
                     false
                 } else if force_compress {
                     true
                 } else {
-                    compression_service.should_compress(&file.mime_type, file.size)
+                    !headers.contains_key(header::RANGE)
+                        && compression_service.should_compress(&file.mime_type, file.size)
                 };
 
                 // Log compression decision for debugging
@@ -261,270 +274,128 @@ impl FileHandler {
                     should_compress
                 );
 
-                // For large files, use streaming response with potential compression
-                if file.size > 10 * 1024 * 1024 {
-                    // 10MB threshold for streaming
-                    match service.get_file_content(&id).await {
-                        Ok(content) => {
-                            // Create base headers
-                            let mut headers = HashMap::new();
-
-                            // Determine if the file should be displayed inline or downloaded
-                            // Images and PDFs should be displayed inline by default, or if inline param is present
-                            let force_inline = params
-                                .get("inline")
-                                .map_or(false, |v| v == "true" || v == "1");
-
-                            let disposition = if force_inline
-                                || file.mime_type.starts_with("image/")
-                                || file.mime_type == "application/pdf"
-                            {
-                                format!("inline; filename=\"{}\"", file.name)
-                            } else {
-                                format!("attachment; filename=\"{}\"", file.name)
-                            };
-
-                            headers.insert(header::CONTENT_DISPOSITION.to_string(), disposition);
-
-                            if should_compress {
-                                // Add content-encoding header for compressed response
-                                headers.insert(
-                                    header::CONTENT_ENCODING.to_string(),
-                                    "gzip".to_string(),
-                                );
-                                headers.insert(
-                                    header::CONTENT_TYPE.to_string(),
-                                    file.mime_type.clone(),
-                                );
-                                headers.insert(
-                                    header::VARY.to_string(),
-                                    "Accept-Encoding".to_string(),
-                                );
+                // Determine if the file should be displayed inline or downloaded
+                // Images and PDFs should be displayed inline by default, or if inline param is present
+                let force_inline = params
+                    .get("inline")
+                    .is_some_and(|v| v == "true" || v == "1");
+
+                let disposition = if force_inline
+                    || file.mime_type.starts_with("image/")
+                    || file.mime_type == "application/pdf"
+                {
+                    format!("inline; filename=\"{}\"", file.name)
+                } else {
+                    format!("attachment; filename=\"{}\"", file.name)
+                };
 
-                                // Compress the content
-                                match compression_service
-                                    .compress_data(&content, compression_level)
-                                    .await
-                                {
-                                    Ok(compressed_content) => {
-                                        tracing::debug!(
-                                            "Compressed file: {} from {}KB to {}KB (ratio: {:.2})",
-                                            file.name,
-                                            content.len() / 1024,
-                                            compressed_content.len() / 1024,
-                                            content.len() as f64 / compressed_content.len() as f64
-                                        );
-
-                                        // Build a custom response with headers and body
-                                        let mut response = Response::builder()
-                                            .status(StatusCode::OK)
-                                            .body(axum::body::Body::from(compressed_content))
-                                            .unwrap();
-
-                                        // Add headers to response
-                                        for (name, value) in headers {
-                                            response.headers_mut().insert(
-                                                HeaderName::from_bytes(name.as_bytes()).unwrap(),
-                                                HeaderValue::from_str(&value).unwrap(),
-                                            );
-                                        }
-
-                                        response
-                                    }
-                                    Err(e) => {
-                                        tracing::warn!(
-                                            "Compression failed, sending uncompressed: {}",
-                                            e
-                                        );
-                                        // Fall back to uncompressed
-                                        headers.insert(
-                                            header::CONTENT_TYPE.to_string(),
-                                            file.mime_type.clone(),
-                                        );
-
-                                        // Build a custom response with headers and body
-                                        let mut response = Response::builder()
-                                            .status(StatusCode::OK)
-                                            .body(axum::body::Body::from(content))
-                                            .unwrap();
-
-                                        // Add headers to response
-                                        for (name, value) in headers {
-                                            response.headers_mut().insert(
-                                                HeaderName::from_bytes(name.as_bytes()).unwrap(),
-                                                HeaderValue::from_str(&value).unwrap(),
-                                            );
-                                        }
-
-                                        response
-                                    }
-                                }
-                            } else {
-                                // No compression, return as-is
-                                headers.insert(
-                                    header::CONTENT_TYPE.to_string(),
-                                    file.mime_type.clone(),
-                                );
+                let etag = file_etag(&file.etag);
 
-                                // Build a custom response with headers and body
-                                let mut response = Response::builder()
-                                    .status(StatusCode::OK)
-                                    .body(axum::body::Body::from(content))
-                                    .unwrap();
-
-                                // Add headers to response
-                                for (name, value) in headers {
-                                    response.headers_mut().insert(
-                                        HeaderName::from_bytes(name.as_bytes()).unwrap(),
-                                        HeaderValue::from_str(&value).unwrap(),
-                                    );
-                                }
-
-                                response
-                            }
-                        }
-                        Err(err) => {
-                            tracing::error!("Error getting file content: {}", err);
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({
-                                    "error": format!("Error reading file: {}", err)
-                                })),
-                            )
-                                .into_response()
+                if !should_compress {
+                    // Stream the content, reading only the requested ranges
+                    let resource = FileResource {
+                        size: file.size,
+                        content_type: file.mime_type.clone(),
+                        etag,
+                        last_modified: file.modified_at,
+                        content_disposition: Some(disposition),
+                    };
+                    let file_id = file.id.clone();
+                    return serve_file(&headers, resource, move |start, end| {
+                        let service = service.clone();
+                        let file_id = file_id.clone();
+                        async move {
+                            service
+                                .get_file_range_stream(&file_id, start, end)
+                                .await
+                                .map_err(DomainError::from)
                         }
-                    }
-                } else {
-                    // For smaller files, load entirely but still potentially compress
-                    match service.get_file_content(&id).await {
-                        Ok(content) => {
-                            // Create base headers
-                            let mut headers = HashMap::new();
-
-                            // Determine if the file should be displayed inline or downloaded
-                            // Images and PDFs should be displayed inline by default, or if inline param is present
-                            let force_inline = params
-                                .get("inline")
-                                .map_or(false, |v| v == "true" || v == "1");
-
-                            let disposition = if force_inline
-                                || file.mime_type.starts_with("image/")
-                                || file.mime_type == "application/pdf"
-                            {
-                                format!("inline; filename=\"{}\"", file.name)
-                            } else {
-                                format!("attachment; filename=\"{}\"", file.name)
-                            };
+                    })
+                    .await
+                    .into_response();
+                }
 
-                            headers.insert(header::CONTENT_DISPOSITION.to_string(), disposition);
+                // The gzip representation gets a weak validator of its own
+                let etag = format!("W/{}", etag);
+                if is_not_modified(&headers, &etag, file.modified_at) {
+                    return Response::builder()
+                        .status(StatusCode::NOT_MODIFIED)
+                        .header(header::ETAG, etag)
+                        .header(header::LAST_MODIFIED, http_date(file.modified_at))
+                        .body(axum::body::Body::empty())
+                        .unwrap();
+                }
+
+                match service.get_file_content(&id).await {
+                    Ok(content) => {
+                        // Create base headers
+                        let mut headers = HashMap::new();
+                        headers.insert(header::CONTENT_DISPOSITION.to_string(), disposition);
+                        headers.insert(header::CONTENT_TYPE.to_string(), file.mime_type.clone());
+                        headers.insert(header::ETAG.to_string(), etag);
+                        headers.insert(
+                            header::LAST_MODIFIED.to_string(),
+                            http_date(file.modified_at),
+                        );
+
+                        // Compress the content
+                        let body = match compression_service
+                            .compress_data(&content, compression_level)
+                            .await
+                        {
+                            Ok(compressed_content) => {
+                                tracing::debug!(
+                                    "Compressed file: {} from {}KB to {}KB (ratio: {:.2})",
+                                    file.name,
+                                    content.len() / 1024,
+                                    compressed_content.len() / 1024,
+                                    content.len() as f64 / compressed_content.len() as f64
+                                );
 
-                            if should_compress {
                                 // Add content-encoding header for compressed response
                                 headers.insert(
                                     header::CONTENT_ENCODING.to_string(),
                                     "gzip".to_string(),
                                 );
-                                headers.insert(
-                                    header::CONTENT_TYPE.to_string(),
-                                    file.mime_type.clone(),
-                                );
                                 headers.insert(
                                     header::VARY.to_string(),
                                     "Accept-Encoding".to_string(),
                                 );
-
-                                // Compress the content
-                                match compression_service
-                                    .compress_data(&content, compression_level)
-                                    .await
-                                {
-                                    Ok(compressed_content) => {
-                                        tracing::debug!(
-                                            "Compressed file: {} from {}KB to {}KB (ratio: {:.2})",
-                                            file.name,
-                                            content.len() / 1024,
-                                            compressed_content.len() / 1024,
-                                            content.len() as f64 / compressed_content.len() as f64
-                                        );
-
-                                        // Build a custom response with headers and body
-                                        let mut response = Response::builder()
-                                            .status(StatusCode::OK)
-                                            .body(axum::body::Body::from(compressed_content))
-                                            .unwrap();
-
-                                        // Add headers to response
-                                        for (name, value) in headers {
-                                            response.headers_mut().insert(
-                                                HeaderName::from_bytes(name.as_bytes()).unwrap(),
-                                                HeaderValue::from_str(&value).unwrap(),
-                                            );
-                                        }
-
-                                        response
-                                    }
-                                    Err(e) => {
-                                        tracing::warn!(
-                                            "Compression failed, sending uncompressed: {}",
-                                            e
-                                        );
-                                        // Fall back to uncompressed
-                                        headers.insert(
-                                            header::CONTENT_TYPE.to_string(),
-                                            file.mime_type.clone(),
-                                        );
-
-                                        // Build a custom response with headers and body
-                                        let mut response = Response::builder()
-                                            .status(StatusCode::OK)
-                                            .body(axum::body::Body::from(content))
-                                            .unwrap();
-
-                                        // Add headers to response
-                                        for (name, value) in headers {
-                                            response.headers_mut().insert(
-                                                HeaderName::from_bytes(name.as_bytes()).unwrap(),
-                                                HeaderValue::from_str(&value).unwrap(),
-                                            );
-                                        }
-
-                                        response
-                                    }
-                                }
-                            } else {
-                                // No compression, return as-is
-                                headers.insert(
-                                    header::CONTENT_TYPE.to_string(),
-                                    file.mime_type.clone(),
+                                compressed_content
+                            }
+                            Err(e) => {
+                                tracing::warn!("Compression failed, sending uncompressed: {}", e);
+                                content
+                            }
+                        };
+
+                        // Build a custom response with headers and body
+                        let mut response = Response::builder()
+                            .status(StatusCode::OK)
+                            .body(axum::body::Body::from(body))
+                            .unwrap();
+
+                        // Add headers to response
+                        for (name, value) in headers {
+                            if let Ok(value) = HeaderValue::from_str(&value) {
+                                response.headers_mut().insert(
+                                    HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                                    value,
                                 );
-
-                                // Build a custom response with headers and body
-                                let mut response = Response::builder()
-                                    .status(StatusCode::OK)
-                                    .body(axum::body::Body::from(content))
-                                    .unwrap();
-
-                                // Add headers to response
-                                for (name, value) in headers {
-                                    response.headers_mut().insert(
-                                        HeaderName::from_bytes(name.as_bytes()).unwrap(),
-                                        HeaderValue::from_str(&value).unwrap(),
-                                    );
-                                }
-
-                                response
                             }
                         }
-                        Err(err) => {
-                            tracing::error!("Error getting file content: {}", err);
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(serde_json::json!({
-                                    "error": format!("Error reading file: {}", err)
-                                })),
-                            )
-                                .into_response()
-                        }
+
+                        response
+                    }
+                    Err(err) => {
+                        tracing::error!("Error getting file content: {}", err);
+                        (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(serde_json::json!({
+                                "error": format!("Error reading file: {}", err)
+                            })),
+                        )
+                            .into_response()
                     }
                 }
             }
@@ -673,10 +544,51 @@ impl FileHandler {
         }
     }
 
+    /// Fetches a file before a write and checks the request's `If-Match` and
+    /// `If-None-Match` headers against its current ETag
+    pub async fn check_preconditions(
+        service: &dyn FileUseCase,
+        id: &str,
+        headers: &HeaderMap,
+    ) -> Result<FileDto, AppError> {
+        let file = service.get_file(id).await?;
+        check_write_preconditions(headers, Some(&file_etag(&file.etag)))?;
+        Ok(file)
+    }
+
+    /// Replaces the content of a file with the request body.
+    ///
+    /// Clients send the ETag they last saw in `If-Match` so that concurrent
+    /// edits fail with 412 instead of overwriting each other.
+    pub async fn update_file(
+        State(service): State<FileServiceState>,
+        Path(id): Path<String>,
+        headers: HeaderMap,
+        body: axum::body::Bytes,
+    ) -> Result<Response<axum::body::Body>, AppError> {
+        Self::check_preconditions(service.as_ref(), &id, &headers).await?;
+
+        let file = service
+            .update_file_content(&id, &body)
+            .await
+            .map_err(|e| AppError::from(DomainError::from(e)))?;
+        tracing::info!("File content updated: {} (ID: {})", file.name, file.id);
+
+        let json = serde_json::to_string(&file)
+            .map_err(|e| AppError::internal_error(format!("Failed to serialize file: {}", e)))?;
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::ETAG, file_etag(&file.etag))
+            .body(axum::body::Body::from(json))
+            .map_err(|e| AppError::internal_error(format!("Failed to build response: {}", e)))
+    }
+
     /// Moves a file to a different folder
     pub async fn move_file(
         State(service): State<FileServiceState>,
         Path(id): Path<String>,
+        headers: HeaderMap,
         Json(payload): Json<MoveFilePayload>,
     ) -> impl IntoResponse {
         tracing::info!(
@@ -685,6 +597,18 @@ impl FileHandler {
             payload.folder_id
         );
 
+        if let Err(err) = check_write_preconditions(
+            &headers,
+            service
+                .get_file(&id)
+                .await
+                .ok()
+                .map(|file| file_etag(&file.etag))
+                .as_deref(),
+        ) {
+            return err.into_response();
+        }
+
         // First verify if the file exists
         match service.get_file(&id).await {
             Ok(file) => {
@@ -1,6 +1,7 @@
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::{header, HeaderName, HeaderValue, Response, StatusCode},
+    http::{header, Response, StatusCode},
     response::IntoResponse,
     Json,
 };
@@ -13,7 +14,7 @@ use crate::application::ports::inbound::FolderUseCase;
 use crate::application::services::folder_service::FolderService;
 use crate::common::di::AppState as GlobalAppState;
 use crate::common::errors::ErrorKind;
-use crate::infrastructure::services::zip_service::ZipService;
+use crate::infrastructure::services::zip_service::{ZipService, ZipStream};
 use crate::interfaces::middleware::auth::AuthUser;
 
 type AppState = Arc<FolderService>;
@@ -259,46 +260,11 @@ impl FolderHandler {
                 // Create ZIP service with the required services
                 let zip_service = ZipService::new(file_service.clone(), folder_service.clone());
 
-                // Create the ZIP file
-                match zip_service.create_folder_zip(&id, &folder.name).await {
-                    Ok(zip_data) => {
-                        tracing::info!(
-                            "ZIP file created successfully, size: {} bytes",
-                            zip_data.len()
-                        );
-
-                        // Setup headers for download
+                // Start streaming the ZIP file
+                match zip_service.stream_folder_zip(&id, &folder.name).await {
+                    Ok(zip_stream) => {
                         let filename = format!("{}.zip", folder.name);
-                        let content_disposition = format!("attachment; filename=\"{}\"", filename);
-
-                        // Build response with the ZIP data
-                        let mut headers = HashMap::new();
-                        headers.insert(
-                            header::CONTENT_TYPE.to_string(),
-                            "application/zip".to_string(),
-                        );
-                        headers
-                            .insert(header::CONTENT_DISPOSITION.to_string(), content_disposition);
-                        headers.insert(
-                            header::CONTENT_LENGTH.to_string(),
-                            zip_data.len().to_string(),
-                        );
-
-                        // Build the response
-                        let mut response = Response::builder()
-                            .status(StatusCode::OK)
-                            .body(axum::body::Body::from(zip_data))
-                            .unwrap();
-
-                        // Add headers to response
-                        for (name, value) in headers {
-                            response.headers_mut().insert(
-                                HeaderName::from_bytes(name.as_bytes()).unwrap(),
-                                HeaderValue::from_str(&value).unwrap(),
-                            );
-                        }
-
-                        response
+                        zip_response(zip_stream, &filename)
                     }
                     Err(err) => {
                         tracing::error!("Error creating ZIP file: {}", err);
@@ -330,3 +296,18 @@ impl FolderHandler {
         }
     }
 }
+
+/// Builds a download response that streams a ZIP archive as it is generated.
+///
+/// The length is unknown up front, so the body is sent chunked.
+pub fn zip_response(zip_stream: ZipStream, filename: &str) -> axum::response::Response {
+    let content_disposition = format!("attachment; filename=\"{}\"", filename.replace('"', "_"));
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/zip")
+        .header(header::CONTENT_DISPOSITION, content_disposition)
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from_stream(zip_stream))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
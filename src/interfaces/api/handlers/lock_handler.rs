@@ -0,0 +1,32 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::application::dtos::lock_dto::LockDto;
+use crate::application::ports::webdav_lock_ports::WebDavLockUseCase;
+use crate::common::errors::AppError;
+
+#[derive(Debug, Deserialize)]
+pub struct LockQuery {
+    /// Limita el listado a los bloqueos de esta ruta y de lo que contiene
+    pub path: Option<String>,
+}
+
+/// Lists the active WebDAV locks, optionally under a path
+pub async fn list_locks(
+    State(lock_service): State<Arc<dyn WebDavLockUseCase>>,
+    Query(query): Query<LockQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let locks: Vec<LockDto> = lock_service
+        .active_locks(query.path.as_deref())
+        .await
+        .into_iter()
+        .map(LockDto::from)
+        .collect();
+    Ok((StatusCode::OK, Json(locks)))
+}
@@ -1,14 +1,23 @@
 pub mod auth_handler;
 pub mod batch_handler;
 pub mod caldav_handler;
+pub mod encryption_handler;
 pub mod favorites_handler;
 pub mod file_handler;
 pub mod folder_handler;
 pub mod i18n_handler;
+pub mod lock_handler;
+pub mod nextcloud_handler;
+pub mod principal_handler;
 pub mod recent_handler;
+pub mod reminder_handler;
 pub mod search_handler;
 pub mod share_handler;
+pub mod task_handler;
+pub mod thumbnail_handler;
 pub mod trash_handler;
+pub mod tus_handler;
+pub mod version_handler;
 pub mod webdav_handler;
 
 /// Tipo de resultado para controladores de API
@@ -0,0 +1,1150 @@
+/**
+ * Nextcloud Compatibility Handler Module
+ *
+ * This module lets the Nextcloud desktop and mobile apps sync with OxiCloud.
+ * It serves the discovery endpoints those clients probe (`status.php` and
+ * the OCS capabilities and user endpoints), Login Flow v2 to obtain app
+ * passwords and chunked uploads v2, and maps `/remote.php/webdav` and
+ * `/remote.php/dav/files/{user}` onto the regular WebDAV handler and
+ * `/remote.php/dav/principals` onto the principal handler.
+ */
+use axum::{
+    body::Body,
+    extract::{Form, Path, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, Uri},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Json, Response},
+    routing::{any, delete, get, post},
+    Router,
+};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::application::adapters::webdav_adapter::{WebDavAdapter, OWNCLOUD_NS};
+use crate::application::dtos::user_dto::UserDto;
+use crate::application::ports::upload_ports::ChunkedUploadUseCase;
+use crate::application::services::login_flow_service::LoginFlowService;
+use crate::common::di::AppState;
+use crate::common::errors::{AppError, ErrorKind};
+use crate::domain::entities::webdav_property::{DeadProperty, PropertyUpdate};
+use crate::interfaces::api::handlers::principal_handler;
+use crate::interfaces::api::handlers::webdav_handler;
+use crate::interfaces::api::http_range::file_etag;
+use crate::interfaces::middleware::auth::CurrentUser;
+
+/// Nextcloud release the clients are told they talk to
+const NEXTCLOUD_VERSION: [u32; 3] = [28, 0, 0];
+
+/// Seconds a login flow waits for the user, as in Nextcloud
+pub const LOGIN_FLOW_TTL_SECS: u64 = 20 * 60;
+
+const HEADER_IF: HeaderName = HeaderName::from_static("if");
+const HEADER_DESTINATION: HeaderName = HeaderName::from_static("destination");
+const HEADER_DEPTH: HeaderName = HeaderName::from_static("depth");
+const HEADER_OC_CHECKSUM: HeaderName = HeaderName::from_static("oc-checksum");
+const HEADER_OC_TOTAL_LENGTH: HeaderName = HeaderName::from_static("oc-total-length");
+const HEADER_OC_ETAG: HeaderName = HeaderName::from_static("oc-etag");
+const HEADER_OC_FILEID: HeaderName = HeaderName::from_static("oc-fileid");
+
+/**
+ * Services behind the Nextcloud endpoints.
+ */
+#[derive(Clone)]
+pub struct NextcloudState {
+    pub app: Arc<AppState>,
+    pub login_flows: Arc<LoginFlowService>,
+    pub uploads: Arc<dyn ChunkedUploadUseCase>,
+}
+
+/**
+ * Creates the router with the endpoints Nextcloud clients expect at the
+ * root of the server.
+ *
+ * @param state The services behind the endpoints
+ * @return Router with the compatibility endpoints
+ */
+pub fn nextcloud_routes<S>(state: NextcloudState) -> Router<S> {
+    let authenticated = Router::new()
+        .route("/remote.php/webdav", any(legacy_dav))
+        .route("/remote.php/webdav/", any(legacy_dav))
+        .route("/remote.php/webdav/{*path}", any(legacy_dav))
+        .route("/remote.php/dav/files/{user}", any(files_dav))
+        .route("/remote.php/dav/files/{user}/", any(files_dav))
+        .route("/remote.php/dav/files/{user}/{*path}", any(files_dav))
+        .route("/remote.php/dav/principals/{*path}", any(principals_dav))
+        .route(
+            "/remote.php/dav/uploads/{user}/{transfer}",
+            any(upload_transfer),
+        )
+        .route(
+            "/remote.php/dav/uploads/{user}/{transfer}/",
+            any(upload_transfer),
+        )
+        .route(
+            "/remote.php/dav/uploads/{user}/{transfer}/{chunk}",
+            any(upload_chunk),
+        )
+        .route("/ocs/v1.php/cloud/user", get(current_user))
+        .route("/ocs/v2.php/cloud/user", get(current_user))
+        .route("/ocs/v1.php/cloud/users/{id}", get(user_info))
+        .route("/ocs/v2.php/cloud/users/{id}", get(user_info))
+        .route("/ocs/v2.php/core/apppassword", delete(revoke_app_password))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth));
+
+    Router::new()
+        .route("/status.php", get(status))
+        .route("/ocs/v1.php/cloud/capabilities", get(capabilities))
+        .route("/ocs/v2.php/cloud/capabilities", get(capabilities))
+        .route("/index.php/login/v2", post(start_login_flow))
+        .route(
+            "/index.php/login/v2/flow/{token}",
+            get(login_page).post(grant_login),
+        )
+        .route("/index.php/login/v2/poll", post(poll_login_flow))
+        .merge(authenticated)
+        .with_state(state)
+}
+
+/**
+ * Authenticates requests with HTTP Basic credentials, an app password or the
+ * account password, or with a bearer access token.
+ *
+ * Clients only send credentials after a challenge, so missing or wrong ones
+ * get a `WWW-Authenticate: Basic` challenge.
+ */
+async fn require_auth(
+    State(state): State<NextcloudState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    match authenticate(&state, req.headers()).await {
+        Some(user) => {
+            req.extensions_mut().insert(current_user_of(user));
+            next.run(req).await
+        }
+        None => Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(
+                header::WWW_AUTHENTICATE,
+                "Basic realm=\"OxiCloud\", charset=\"UTF-8\"",
+            )
+            .body(Body::empty())
+            .unwrap(),
+    }
+}
+
+/**
+ * Username and password of HTTP Basic credentials.
+ */
+fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let encoded = value.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+async fn authenticate(state: &NextcloudState, headers: &HeaderMap) -> Option<UserDto> {
+    let auth = state.app.auth_service.as_ref()?;
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+
+    if value.starts_with("Basic ") {
+        let (username, password) = basic_credentials(headers)?;
+        auth.auth_application_service
+            .authenticate_basic(&username, &password)
+            .await
+            .ok()
+    } else if let Some(token) = value.strip_prefix("Bearer ") {
+        auth.auth_application_service
+            .user_from_access_token(token.trim())
+            .await
+            .ok()
+    } else {
+        None
+    }
+}
+
+fn current_user_of(user: UserDto) -> CurrentUser {
+    CurrentUser {
+        id: user.id,
+        username: user.username,
+        email: user.email,
+        role: user.role,
+    }
+}
+
+fn request_user(req: &Request<Body>) -> Result<CurrentUser, AppError> {
+    req.extensions()
+        .get::<CurrentUser>()
+        .cloned()
+        .ok_or_else(|| AppError::unauthorized("Authentication required"))
+}
+
+/**
+ * Where a request entered the WebDAV tree of a user. Maps URLs under either
+ * Nextcloud mount onto `/webdav/` and the hrefs of responses back.
+ */
+#[derive(Clone)]
+struct DavMount {
+    /// Prefix of the request, which response hrefs are given
+    base: String,
+    /// Both prefixes under which the user's files are reachable
+    prefixes: [String; 2],
+}
+
+impl DavMount {
+    /// `raw_user` is the user segment exactly as clients encode it in URLs
+    fn new(base: String, raw_user: &str) -> Self {
+        Self {
+            base,
+            prefixes: [
+                "/remote.php/webdav/".to_string(),
+                format!("/remote.php/dav/files/{}/", raw_user),
+            ],
+        }
+    }
+
+    fn legacy(raw_user: &str) -> Self {
+        Self::new("/remote.php/webdav/".to_string(), raw_user)
+    }
+
+    fn files(raw_user: &str) -> Self {
+        Self::new(format!("/remote.php/dav/files/{}/", raw_user), raw_user)
+    }
+
+    /// Path of a URL or href in the user's files, `None` if it lies elsewhere
+    fn path_of(&self, href: &str) -> Option<String> {
+        self.prefixes.iter().find_map(|prefix| {
+            let idx = href.find(prefix.trim_end_matches('/'))?;
+            let rest = &href[idx + prefix.len() - 1..];
+            (rest.is_empty() || rest.starts_with('/'))
+                .then(|| rest.trim_start_matches('/').to_string())
+        })
+    }
+
+    /// Rewrites the URLs in a `Destination` or `If` header to the `/webdav/` form
+    fn to_webdav(&self, value: &str) -> String {
+        self.prefixes
+            .iter()
+            .fold(value.to_string(), |value, prefix| {
+                value.replace(prefix.as_str(), "/webdav/")
+            })
+    }
+
+    /// Gives the hrefs of a WebDAV response body the prefix of the request,
+    /// and principal hrefs the prefix of the principals under `/remote.php/dav`
+    fn to_client(&self, body: &str) -> String {
+        body.replace("<D:href>/webdav/", &format!("<D:href>{}", self.base))
+            .replace(
+                "<D:href>/principals/",
+                "<D:href>/remote.php/dav/principals/",
+            )
+    }
+}
+
+/**
+ * Handles `/remote.php/webdav`, the WebDAV endpoint of older clients.
+ */
+async fn legacy_dav(State(state): State<NextcloudState>, req: Request<Body>) -> Response {
+    let user = match request_user(&req) {
+        Ok(user) => user,
+        Err(e) => return e.into_response(),
+    };
+    let path = req
+        .uri()
+        .path()
+        .splitn(4, '/')
+        .nth(3)
+        .unwrap_or("")
+        .to_string();
+    forward_to_webdav(&state, &DavMount::legacy(&user.username), &path, req).await
+}
+
+/**
+ * Handles `/remote.php/dav/files/{user}`, the WebDAV endpoint of current
+ * clients, which only gives access to the authenticated user's files.
+ */
+async fn files_dav(
+    State(state): State<NextcloudState>,
+    Path(params): Path<Vec<(String, String)>>,
+    req: Request<Body>,
+) -> Response {
+    let user = match request_user(&req) {
+        Ok(user) => user,
+        Err(e) => return e.into_response(),
+    };
+    let owner = params
+        .iter()
+        .find(|(name, _)| name == "user")
+        .map(|(_, value)| value.as_str());
+    if owner != Some(user.username.as_str()) {
+        return AppError::forbidden("Cannot access the files of another user").into_response();
+    }
+
+    // The WebDAV handler works on the path as sent, like for /webdav
+    let mut segments = req.uri().path().splitn(6, '/').skip(4);
+    let raw_user = segments.next().unwrap_or("").to_string();
+    let path = segments.next().unwrap_or("").to_string();
+    forward_to_webdav(&state, &DavMount::files(&raw_user), &path, req).await
+}
+
+/**
+ * Handles `/remote.php/dav/principals`, where clients look up the current
+ * user's principal, by running the request through the principal handler.
+ */
+async fn principals_dav(State(state): State<NextcloudState>, mut req: Request<Body>) -> Response {
+    let user = match request_user(&req) {
+        Ok(user) => user,
+        Err(e) => return e.into_response(),
+    };
+    let path = req
+        .uri()
+        .path()
+        .strip_prefix("/remote.php/dav")
+        .unwrap_or("/principals/")
+        .to_string();
+    match path.parse::<Uri>() {
+        Ok(uri) => *req.uri_mut() = uri,
+        Err(_) => return AppError::bad_request("Invalid resource path").into_response(),
+    }
+
+    let response = match principal_handler::dispatch(state.app.clone(), req).await {
+        Ok(response) => response,
+        Err(e) => e.into_response(),
+    };
+    rewrite_hrefs(&DavMount::files(&user.username), response)
+}
+
+/**
+ * Rewrites a request to the `/webdav/` form, runs it through the WebDAV
+ * handler and maps the hrefs of the response back to the client's mount.
+ */
+async fn forward_to_webdav(
+    state: &NextcloudState,
+    mount: &DavMount,
+    path: &str,
+    mut req: Request<Body>,
+) -> Response {
+    let query = req
+        .uri()
+        .query()
+        .map(|query| format!("?{}", query))
+        .unwrap_or_default();
+    match format!("/webdav/{}{}", path, query).parse::<Uri>() {
+        Ok(uri) => *req.uri_mut() = uri,
+        Err(_) => return AppError::bad_request("Invalid resource path").into_response(),
+    }
+
+    if let Some(destination) = header_str(req.headers(), &HEADER_DESTINATION) {
+        if mount.path_of(&destination).is_none() {
+            return AppError::forbidden("Destination outside the user's files").into_response();
+        }
+        set_header(
+            req.headers_mut(),
+            HEADER_DESTINATION,
+            &mount.to_webdav(&destination),
+        );
+    }
+    if let Some(condition) = header_str(req.headers(), &HEADER_IF) {
+        set_header(req.headers_mut(), HEADER_IF, &mount.to_webdav(&condition));
+    }
+
+    let method = req.method().clone();
+    let checksum = header_str(req.headers(), &HEADER_OC_CHECKSUM);
+    let response = run_webdav(state, req).await;
+
+    if method == Method::PUT && response.status().is_success() {
+        return finish_upload(state, path, checksum, response).await;
+    }
+    if matches!(
+        method.as_str(),
+        "PROPFIND" | "PROPPATCH" | "REPORT" | "LOCK"
+    ) {
+        return rewrite_hrefs(mount, response);
+    }
+    response
+}
+
+async fn run_webdav(state: &NextcloudState, req: Request<Body>) -> Response {
+    match webdav_handler::dispatch(state.app.clone(), req).await {
+        Ok(response) => response,
+        Err(e) => e.into_response(),
+    }
+}
+
+/**
+ * Rewrites the hrefs of an XML response to the mount the client used.
+ *
+ * The body is rewritten chunk by chunk as it streams, which holds because
+ * the WebDAV handler only ever splits a multistatus between elements.
+ */
+fn rewrite_hrefs(mount: &DavMount, response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let mount = mount.clone();
+    let body = body
+        .into_data_stream()
+        .map(move |chunk| chunk.map(|bytes| mount.to_client(&String::from_utf8_lossy(&bytes))));
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+/**
+ * Adds the headers Nextcloud clients expect after an upload and keeps the
+ * checksum they computed, which they read back as `oc:checksums`.
+ */
+async fn finish_upload(
+    state: &NextcloudState,
+    path: &str,
+    checksum: Option<String>,
+    mut response: Response,
+) -> Response {
+    let Ok(file) = state
+        .app
+        .applications
+        .file_service
+        .get_file_by_path(path)
+        .await
+    else {
+        return response;
+    };
+
+    let headers = response.headers_mut();
+    set_header(headers, HEADER_OC_ETAG, &file_etag(&file.etag));
+    set_header(headers, HEADER_OC_FILEID, &file.id);
+
+    if let (Some(checksum), Some(service)) = (checksum, &state.app.property_service) {
+        let update =
+            PropertyUpdate::Set(DeadProperty::new(OWNCLOUD_NS, "checksums", Some(checksum)));
+        if let Err(e) = service.patch_properties(&file.id, vec![update]).await {
+            tracing::warn!("Could not store the checksum of {}: {}", path, e);
+        }
+    }
+    response
+}
+
+fn header_str(headers: &HeaderMap, name: &HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+fn set_header(headers: &mut HeaderMap, name: HeaderName, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
+
+/**
+ * Checks that an upload URL belongs to the authenticated user.
+ */
+fn upload_owner(req: &Request<Body>, user: &str) -> Result<CurrentUser, AppError> {
+    let current = request_user(req)?;
+    if current.username != user {
+        return Err(AppError::forbidden(
+            "Cannot access the uploads of another user",
+        ));
+    }
+    Ok(current)
+}
+
+/**
+ * Handles the collection of a chunked upload: MKCOL starts the transfer,
+ * PROPFIND lists the chunks received so far and DELETE aborts it.
+ */
+async fn upload_transfer(
+    State(state): State<NextcloudState>,
+    Path((user, transfer)): Path<(String, String)>,
+    req: Request<Body>,
+) -> Result<Response, AppError> {
+    let owner = upload_owner(&req, &user)?;
+
+    match req.method().as_str() {
+        "MKCOL" => match state.uploads.begin(&owner.id, &transfer).await {
+            Ok(()) => Ok(StatusCode::CREATED.into_response()),
+            Err(e) if e.kind == ErrorKind::AlreadyExists => Err(AppError::method_not_allowed(
+                format!("Upload {} already exists", transfer),
+            )),
+            Err(e) => Err(e.into()),
+        },
+        "PROPFIND" => {
+            let chunks = state.uploads.chunks(&owner.id, &transfer).await?;
+            let depth = header_str(req.headers(), &HEADER_DEPTH).unwrap_or_else(|| "1".into());
+            let base_href = format!("/remote.php/dav/uploads/{}/{}/", user, transfer);
+            let mut body = Vec::new();
+            WebDavAdapter::generate_upload_chunks_response(&mut body, &base_href, &chunks, &depth)
+                .map_err(|e| AppError::internal_error(format!("Failed to list upload: {}", e)))?;
+            Ok(Response::builder()
+                .status(StatusCode::MULTI_STATUS)
+                .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+                .body(Body::from(body))
+                .unwrap())
+        }
+        "DELETE" => {
+            state.uploads.abort(&owner.id, &transfer).await?;
+            Ok(StatusCode::NO_CONTENT.into_response())
+        }
+        method => Err(AppError::method_not_allowed(format!(
+            "Method not allowed on an upload: {}",
+            method
+        ))),
+    }
+}
+
+/**
+ * Handles the members of a chunked upload: PUT stores a chunk and MOVE of
+ * `.file` assembles the chunks onto the `Destination`.
+ */
+async fn upload_chunk(
+    State(state): State<NextcloudState>,
+    Path((user, transfer, chunk)): Path<(String, String, String)>,
+    req: Request<Body>,
+) -> Result<Response, AppError> {
+    let owner = upload_owner(&req, &user)?;
+
+    match (req.method().as_str(), chunk.as_str()) {
+        ("PUT", _) => {
+            // A chunk that cannot fit in the quota will not fit once assembled
+            let allowance = webdav_handler::quota_allowance(&state.app, &owner).await;
+            let content = webdav_handler::read_body_within(req.into_body(), allowance).await?;
+            state
+                .uploads
+                .store_chunk(&owner.id, &transfer, &chunk, &content)
+                .await?;
+            Ok(StatusCode::CREATED.into_response())
+        }
+        ("MOVE", ".file") => assemble_upload(&state, owner, &transfer, req).await,
+        (method, _) => Err(AppError::method_not_allowed(format!(
+            "Method not allowed on an upload chunk: {}",
+            method
+        ))),
+    }
+}
+
+/**
+ * Assembles a chunked upload and writes it to its destination as a WebDAV
+ * PUT would, so quotas, locks and preconditions apply the same way.
+ */
+async fn assemble_upload(
+    state: &NextcloudState,
+    owner: CurrentUser,
+    transfer: &str,
+    req: Request<Body>,
+) -> Result<Response, AppError> {
+    let destination = header_str(req.headers(), &HEADER_DESTINATION)
+        .ok_or_else(|| AppError::bad_request("Missing Destination header"))?;
+    let mount = DavMount::files(&owner.username);
+    let path = mount
+        .path_of(&destination)
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| AppError::forbidden("Destination outside the user's files"))?;
+    let expected_length = header_str(req.headers(), &HEADER_OC_TOTAL_LENGTH)
+        .map(|value| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| AppError::bad_request("Invalid OC-Total-Length header"))
+        })
+        .transpose()?;
+
+    let assembled = state
+        .uploads
+        .assemble(&owner.id, transfer, expected_length)
+        .await?;
+
+    let mut put = Request::builder()
+        .method(Method::PUT)
+        .uri(
+            format!("/webdav/{}", path)
+                .parse::<Uri>()
+                .map_err(|_| AppError::bad_request("Invalid destination path"))?,
+        )
+        .header(header::CONTENT_LENGTH, assembled.size)
+        .header(
+            header::CONTENT_TYPE,
+            mime_guess::from_path(&path)
+                .first_or_octet_stream()
+                .essence_str(),
+        );
+    for name in [header::IF_MATCH, header::IF_NONE_MATCH] {
+        if let Some(value) = req.headers().get(&name) {
+            put = put.header(name, value);
+        }
+    }
+    if let Some(condition) = header_str(req.headers(), &HEADER_IF) {
+        put = put.header(HEADER_IF, mount.to_webdav(&condition));
+    }
+    let mut put = put
+        .body(Body::from_stream(assembled.content))
+        .map_err(|e| AppError::internal_error(format!("Failed to build upload: {}", e)))?;
+    put.extensions_mut().insert(owner.clone());
+
+    let checksum = header_str(req.headers(), &HEADER_OC_CHECKSUM);
+    let response = run_webdav(state, put).await;
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+
+    if let Err(e) = state.uploads.abort(&owner.id, transfer).await {
+        tracing::warn!("Could not remove the chunks of upload {}: {}", transfer, e);
+    }
+    Ok(finish_upload(state, &path, checksum, response).await)
+}
+
+/**
+ * Wraps data in the OCS envelope. v1 reports success as status code 100 and
+ * v2 as 200, the HTTP status.
+ */
+fn ocs_response(uri: &Uri, data: Value) -> Response {
+    let statuscode = if uri.path().starts_with("/ocs/v2.php/") {
+        200
+    } else {
+        100
+    };
+    Json(json!({
+        "ocs": {
+            "meta": {
+                "status": "ok",
+                "statuscode": statuscode,
+                "message": "OK",
+                "totalitems": "",
+                "itemsperpage": "",
+            },
+            "data": data,
+        }
+    }))
+    .into_response()
+}
+
+fn version_string() -> String {
+    let [major, minor, micro] = NEXTCLOUD_VERSION;
+    format!("{}.{}.{}", major, minor, micro)
+}
+
+/**
+ * Handles `status.php`, which clients query to recognize the server.
+ */
+async fn status() -> Json<Value> {
+    Json(json!({
+        "installed": true,
+        "maintenance": false,
+        "needsDbUpgrade": false,
+        "version": format!("{}.0", version_string()),
+        "versionstring": version_string(),
+        "edition": "",
+        "productname": "OxiCloud",
+        "extendedSupport": false,
+    }))
+}
+
+/**
+ * Handles the OCS capabilities, which tell clients the features they can use.
+ */
+async fn capabilities(State(state): State<NextcloudState>, uri: Uri) -> Response {
+    let [major, minor, micro] = NEXTCLOUD_VERSION;
+    let config = &state.app.core.config;
+    ocs_response(
+        &uri,
+        json!({
+            "version": {
+                "major": major,
+                "minor": minor,
+                "micro": micro,
+                "string": version_string(),
+                "edition": "",
+                "extendedSupport": false,
+            },
+            "capabilities": {
+                "core": {
+                    "pollinterval": 60,
+                    "webdav-root": "remote.php/webdav",
+                },
+                "dav": {
+                    "chunking": "1.0",
+                },
+                "files": {
+                    "bigfilechunking": true,
+                    "undelete": config.features.enable_trash,
+                    "versioning": config.storage.versioning_enabled,
+                },
+            },
+        }),
+    )
+}
+
+/**
+ * Describes a user as OCS does, quota included.
+ */
+fn ocs_user(user: &UserDto) -> Value {
+    let used = user.storage_used_bytes.max(0);
+    // Nextcloud reports unlimited quotas as -3
+    let quota = if user.storage_quota_bytes > 0 {
+        json!({
+            "free": (user.storage_quota_bytes - used).max(0),
+            "used": used,
+            "total": user.storage_quota_bytes,
+            "relative": (used as f64 * 10000.0 / user.storage_quota_bytes as f64).round() / 100.0,
+            "quota": user.storage_quota_bytes,
+        })
+    } else {
+        json!({ "free": -3, "used": used, "total": -3, "relative": 0, "quota": -3 })
+    };
+    json!({
+        "id": user.username,
+        "enabled": user.active,
+        "email": user.email,
+        "display-name": user.username,
+        "displayname": user.username,
+        "quota": quota,
+    })
+}
+
+/**
+ * Handles the OCS user endpoint, which clients use to show who is signed in.
+ */
+async fn current_user(
+    State(state): State<NextcloudState>,
+    uri: Uri,
+    req: Request<Body>,
+) -> Result<Response, AppError> {
+    let user = request_user(&req)?;
+    let auth = state
+        .app
+        .auth_service
+        .as_ref()
+        .ok_or_else(|| AppError::internal_error("Authentication is not configured"))?;
+    let user = auth
+        .auth_application_service
+        .get_user_by_id(&user.id)
+        .await?;
+    Ok(ocs_response(&uri, ocs_user(&user)))
+}
+
+/**
+ * Handles the OCS user details endpoint. Users see their own details and
+ * administrators those of anyone.
+ */
+async fn user_info(
+    State(state): State<NextcloudState>,
+    Path(id): Path<String>,
+    uri: Uri,
+    req: Request<Body>,
+) -> Result<Response, AppError> {
+    let current = request_user(&req)?;
+    if current.username != id && current.role != "admin" {
+        return Err(AppError::forbidden(
+            "Cannot read the details of another user",
+        ));
+    }
+    let auth = state
+        .app
+        .auth_service
+        .as_ref()
+        .ok_or_else(|| AppError::internal_error("Authentication is not configured"))?;
+    let user = auth
+        .auth_application_service
+        .get_user_by_username(&id)
+        .await?;
+    Ok(ocs_response(&uri, ocs_user(&user)))
+}
+
+/**
+ * Revokes the app password the client signs in with. Clients call it when
+ * the account is removed from them.
+ */
+async fn revoke_app_password(
+    State(state): State<NextcloudState>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let (username, password) = basic_credentials(&headers)
+        .ok_or_else(|| AppError::forbidden("Not signed in with an app password"))?;
+    let auth = state
+        .app
+        .auth_service
+        .as_ref()
+        .ok_or_else(|| AppError::internal_error("Authentication is not configured"))?;
+    auth.auth_application_service
+        .revoke_app_password(&username, &password)
+        .await
+        .map_err(|_| AppError::forbidden("Not signed in with an app password"))?;
+    Ok(ocs_response(&uri, json!([])))
+}
+
+/**
+ * Base URL of the server as the client reached it, honouring a reverse proxy.
+ */
+fn server_url(headers: &HeaderMap) -> String {
+    let scheme = header_str(headers, &HeaderName::from_static("x-forwarded-proto"))
+        .unwrap_or_else(|| "http".to_string());
+    let host = header_str(headers, &HeaderName::from_static("x-forwarded-host"))
+        .or_else(|| header_str(headers, &header::HOST))
+        .unwrap_or_else(|| "localhost".to_string());
+    format!("{}://{}", scheme, host)
+}
+
+/**
+ * Starts Login Flow v2: the client gets a URL to open in the browser and a
+ * token to poll with until the user grants access.
+ */
+async fn start_login_flow(State(state): State<NextcloudState>, headers: HeaderMap) -> Json<Value> {
+    let server = server_url(&headers);
+    let client_name =
+        header_str(&headers, &header::USER_AGENT).unwrap_or_else(|| "Nextcloud client".to_string());
+    let flow = state.login_flows.start(&server, &client_name).await;
+    Json(json!({
+        "poll": {
+            "token": flow.poll_token,
+            "endpoint": format!("{}/index.php/login/v2/poll", server),
+        },
+        "login": format!("{}/index.php/login/v2/flow/{}", server, flow.login_token),
+    }))
+}
+
+#[derive(Deserialize)]
+struct PollForm {
+    token: String,
+}
+
+/**
+ * Polled by the client until the user grants access. Answers 404 until then
+ * and hands out the app password only once.
+ */
+async fn poll_login_flow(
+    State(state): State<NextcloudState>,
+    Form(form): Form<PollForm>,
+) -> Response {
+    match state.login_flows.poll(&form.token).await {
+        Some(credentials) => Json(json!({
+            "server": credentials.server,
+            "loginName": credentials.login_name,
+            "appPassword": credentials.app_password,
+        }))
+        .into_response(),
+        None => (StatusCode::NOT_FOUND, Json(json!([]))).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    user: String,
+    password: String,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn login_form(client_name: &str, error: Option<&str>) -> String {
+    let error = error
+        .map(|error| format!("<p class=\"error\">{}</p>", escape_html(error)))
+        .unwrap_or_default();
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>OxiCloud</title></head>\
+         <body><h1>Connect to your account</h1>\
+         <p>Sign in to grant <strong>{}</strong> access to your OxiCloud account.</p>{}\
+         <form method=\"post\"><p><input name=\"user\" placeholder=\"Username\" required \
+         autocomplete=\"username\"></p><p><input name=\"password\" type=\"password\" \
+         placeholder=\"Password\" required autocomplete=\"current-password\"></p>\
+         <p><button type=\"submit\">Grant access</button></p></form></body></html>",
+        escape_html(client_name),
+        error
+    )
+}
+
+fn login_message(status: StatusCode, message: &str) -> Response {
+    let page = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>OxiCloud</title></head>\
+         <body><p>{}</p></body></html>",
+        escape_html(message)
+    );
+    (status, Html(page)).into_response()
+}
+
+/**
+ * Shows the page where the user signs in to grant a client access.
+ */
+async fn login_page(State(state): State<NextcloudState>, Path(token): Path<String>) -> Response {
+    match state.login_flows.pending(&token).await {
+        Some(flow) => Html(login_form(&flow.client_name, None)).into_response(),
+        None => login_message(
+            StatusCode::NOT_FOUND,
+            "This login link has expired. Please try again from your client.",
+        ),
+    }
+}
+
+/**
+ * Signs the user in and grants the client an app password.
+ */
+async fn grant_login(
+    State(state): State<NextcloudState>,
+    Path(token): Path<String>,
+    Form(form): Form<LoginForm>,
+) -> Response {
+    let Some(flow) = state.login_flows.pending(&token).await else {
+        return login_message(
+            StatusCode::NOT_FOUND,
+            "This login link has expired. Please try again from your client.",
+        );
+    };
+    let Some(auth) = state.app.auth_service.as_ref() else {
+        return AppError::internal_error("Authentication is not configured").into_response();
+    };
+
+    let user = match auth
+        .auth_application_service
+        .check_credentials(&form.user, &form.password)
+        .await
+    {
+        Ok(user) => user,
+        Err(_) => {
+            let page = login_form(&flow.client_name, Some("Wrong username or password."));
+            return (StatusCode::UNAUTHORIZED, Html(page)).into_response();
+        }
+    };
+
+    let granted = match auth
+        .auth_application_service
+        .create_app_password(&user.id, &flow.client_name)
+        .await
+    {
+        Ok(app_password) => {
+            state
+                .login_flows
+                .grant(&token, &user.username, &app_password)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    match granted {
+        Ok(()) => login_message(
+            StatusCode::OK,
+            "Account connected. You can close this window and return to your client.",
+        ),
+        Err(e) => AppError::from(e).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::adapters::webdav_adapter::PropFindContext;
+    use crate::application::dtos::file_dto::FileDto;
+    use crate::application::dtos::folder_dto::FolderDto;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_mounts_map_onto_webdav() {
+        let files = DavMount::files("alice");
+        assert_eq!(
+            files.path_of("https://cloud.example.com/remote.php/dav/files/alice/Docs/a.txt"),
+            Some("Docs/a.txt".to_string())
+        );
+        assert_eq!(
+            files.path_of("/remote.php/webdav/b.txt"),
+            Some("b.txt".to_string())
+        );
+        assert_eq!(
+            files.path_of("/remote.php/dav/files/alice"),
+            Some(String::new())
+        );
+        assert_eq!(files.path_of("/remote.php/dav/files/bob/a.txt"), None);
+        assert_eq!(files.path_of("/remote.php/dav/files/alice2/a.txt"), None);
+
+        assert_eq!(
+            files.to_webdav(
+                "<https://host/remote.php/dav/files/alice/a.txt> (<opaquelocktoken:1>) \
+                 </remote.php/webdav/b.txt> ([\"e\"])"
+            ),
+            "<https://host/webdav/a.txt> (<opaquelocktoken:1>) </webdav/b.txt> ([\"e\"])"
+        );
+        assert_eq!(
+            DavMount::legacy("alice").to_client("<D:href>/webdav/Docs/</D:href>"),
+            "<D:href>/remote.php/webdav/Docs/</D:href>"
+        );
+        assert_eq!(
+            files.to_client("<D:href>/principals/users/alice/</D:href>"),
+            "<D:href>/remote.php/dav/principals/users/alice/</D:href>"
+        );
+    }
+
+    /// Replays the PROPFIND a desktop client sends after connecting and
+    /// checks what it reads back
+    #[test]
+    fn test_client_propfind_replay() {
+        let body = r#"<?xml version="1.0"?>
+            <d:propfind xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns"
+                        xmlns:nc="http://nextcloud.org/ns">
+              <d:prop>
+                <d:resourcetype/><d:getlastmodified/><d:getcontentlength/><d:getetag/>
+                <oc:size/><oc:id/><oc:fileid/><oc:permissions/><oc:checksums/>
+                <nc:has-preview/>
+              </d:prop>
+            </d:propfind>"#;
+        let request = WebDavAdapter::parse_propfind(body.as_bytes()).unwrap();
+        // Live ownCloud properties are not stored, the checksum is
+        assert!(request.includes_dead_properties());
+
+        let folder = FolderDto {
+            id: "folder-1".to_string(),
+            name: "Docs".to_string(),
+            path: "Docs".to_string(),
+            ..Default::default()
+        };
+        let file = FileDto {
+            id: "file-1".to_string(),
+            name: "a.txt".to_string(),
+            size: 42,
+            etag: "v1".to_string(),
+            ..Default::default()
+        };
+        let mut dead_properties = HashMap::new();
+        dead_properties.insert(
+            "file-1".to_string(),
+            vec![DeadProperty::new(
+                OWNCLOUD_NS,
+                "checksums",
+                Some("SHA1:da39a3ee".to_string()),
+            )],
+        );
+        let context = PropFindContext {
+            dead_properties,
+            ..Default::default()
+        };
+
+        let mut xml = Vec::new();
+        WebDavAdapter::generate_propfind_response(
+            &mut xml,
+            Some(&folder),
+            &[file],
+            &[],
+            &request,
+            "/webdav/Docs/",
+            &context,
+        )
+        .unwrap();
+        let xml = DavMount::files("alice").to_client(&String::from_utf8(xml).unwrap());
+
+        assert!(xml.contains("<D:href>/remote.php/dav/files/alice/Docs/</D:href>"));
+        assert!(xml.contains("<D:href>/remote.php/dav/files/alice/Docs/a.txt</D:href>"));
+        assert!(!xml.contains("/webdav/"));
+        let fileid = WebDavAdapter::numeric_file_id("file-1").to_string();
+        assert!(xml.contains(&format!("{}</x:fileid>", fileid)));
+        assert!(xml.contains(">file-1</x:id>"));
+        assert!(xml.contains(">42</x:size>"));
+        assert!(xml.contains(">RGDNVW</x:permissions>"));
+        assert!(xml.contains(">RGDNVCK</x:permissions>"));
+        assert!(xml.contains("<x:checksum>SHA1:da39a3ee</x:checksum>"));
+        assert!(xml.contains(">false</x:has-preview>"));
+        assert!(xml.contains("<D:getetag>&quot;v1&quot;</D:getetag>"));
+    }
+
+    #[test]
+    fn test_numeric_file_ids_are_stable_and_safe_for_javascript() {
+        let id = WebDavAdapter::numeric_file_id("2b7c3f9e-0d0b-4f45-9a0c-1f5d6a7b8c9d");
+        assert_eq!(
+            id,
+            WebDavAdapter::numeric_file_id("2b7c3f9e-0d0b-4f45-9a0c-1f5d6a7b8c9d")
+        );
+        assert_ne!(id, WebDavAdapter::numeric_file_id("other"));
+        assert!(id < 1 << 53);
+        assert!(WebDavAdapter::is_protected_property(
+            &crate::application::adapters::webdav_adapter::QualifiedName::new(
+                OWNCLOUD_NS,
+                "fileid"
+            )
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_streamed_hrefs_are_rewritten() {
+        let body = Body::from_stream(futures::stream::iter(vec![
+            Ok::<_, std::io::Error>(bytes::Bytes::from_static(
+                b"<D:multistatus><D:response><D:href>/webdav/</D:href></D:response>",
+            )),
+            Ok(bytes::Bytes::from_static(
+                b"<D:response><D:href>/webdav/a.txt</D:href></D:response></D:multistatus>",
+            )),
+        ]));
+        let response = Response::builder()
+            .header(header::CONTENT_LENGTH, "10")
+            .body(body)
+            .unwrap();
+
+        let response = rewrite_hrefs(&DavMount::files("alice"), response);
+        assert!(response.headers().get(header::CONTENT_LENGTH).is_none());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&body),
+            "<D:multistatus><D:response><D:href>/remote.php/dav/files/alice/</D:href>\
+             </D:response><D:response><D:href>/remote.php/dav/files/alice/a.txt</D:href>\
+             </D:response></D:multistatus>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ocs_envelope_and_user() {
+        let user = UserDto {
+            id: "user-1".to_string(),
+            username: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            role: "user".to_string(),
+            storage_quota_bytes: 1000,
+            storage_used_bytes: 250,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            last_login_at: None,
+            active: true,
+        };
+
+        for (path, statuscode) in [
+            ("/ocs/v1.php/cloud/user", 100),
+            ("/ocs/v2.php/cloud/user", 200),
+        ] {
+            let response = ocs_response(&path.parse().unwrap(), ocs_user(&user));
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let value: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(value["ocs"]["meta"]["statuscode"], statuscode);
+            let data = &value["ocs"]["data"];
+            assert_eq!(data["id"], "alice");
+            assert_eq!(data["quota"]["free"], 750);
+            assert_eq!(data["quota"]["relative"], 25.0);
+        }
+
+        let unlimited = UserDto {
+            storage_quota_bytes: 0,
+            ..user
+        };
+        assert_eq!(ocs_user(&unlimited)["quota"]["quota"], -3);
+    }
+
+    #[test]
+    fn test_login_flow_urls_and_page() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_static("internal:8086"));
+        assert_eq!(server_url(&headers), "http://internal:8086");
+        headers.insert("x-forwarded-proto", HeaderValue::from_static("https"));
+        headers.insert(
+            "x-forwarded-host",
+            HeaderValue::from_static("cloud.example.com"),
+        );
+        assert_eq!(server_url(&headers), "https://cloud.example.com");
+
+        let page = login_form("<script>alert(1)</script>", Some("Wrong"));
+        assert!(!page.contains("<script>"));
+        assert!(page.contains("&lt;script&gt;"));
+    }
+}
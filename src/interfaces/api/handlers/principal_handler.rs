@@ -0,0 +1,294 @@
+/**
+ * Principal Handler Module
+ *
+ * This module serves the principal resources of WebDAV Access Control
+ * (RFC 3744): `/principals/` and `/principals/users/{name}/`, one principal
+ * per user. Clients discover them through `current-user-principal` and find
+ * other users with the `principal-property-search` report. CalDAV and
+ * CardDAV discovery start from these same principals.
+ */
+use axum::{
+    body::{self, Body},
+    extract::State,
+    http::{header, HeaderName, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use bytes::Buf;
+use std::sync::Arc;
+
+use crate::application::adapters::webdav_adapter::{
+    AclReport, PrincipalResource, PropFindRequest, PropFindType, WebDavAdapter,
+};
+use crate::application::ports::webdav_acl_ports::WebDavAclUseCase;
+use crate::common::di::AppState;
+use crate::common::errors::AppError;
+use crate::domain::entities::webdav_acl::{
+    AclPrincipal, DavPrivilege, Principal, PrincipalMatch, USER_PRINCIPALS_PATH,
+};
+use crate::interfaces::middleware::auth::CurrentUser;
+
+const HEADER_DAV: HeaderName = HeaderName::from_static("dav");
+
+/// Root of the principal tree
+const PRINCIPALS_PATH: &str = "/principals/";
+
+/**
+ * Creates the router for the principal tree.
+ *
+ * @return Router configured with the principal endpoints
+ */
+pub fn principal_routes() -> Router<AppState> {
+    Router::new()
+        .route("/principals", axum::routing::any(handle_principal_methods))
+        .route("/principals/", axum::routing::any(handle_principal_methods))
+        .route(
+            "/principals/{*path}",
+            axum::routing::any(handle_principal_methods),
+        )
+}
+
+async fn handle_principal_methods(
+    State(state): State<AppState>,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    dispatch(Arc::new(state), req).await
+}
+
+/**
+ * Runs a request whose URI path starts with `/principals`.
+ *
+ * @param state The application state containing service dependencies
+ * @param req The request, carrying the authenticated user as an extension
+ * @return The WebDAV response
+ */
+pub(crate) async fn dispatch(
+    state: Arc<AppState>,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let user = req
+        .extensions()
+        .get::<CurrentUser>()
+        .cloned()
+        .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
+    let path = req
+        .uri()
+        .path()
+        .strip_prefix("/principals")
+        .unwrap_or("")
+        .trim_matches('/')
+        .to_string();
+
+    match req.method().as_str() {
+        "OPTIONS" => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(HEADER_DAV, "1, 2, access-control")
+            .header(header::ALLOW, "OPTIONS, PROPFIND, REPORT")
+            .body(Body::empty())
+            .unwrap()),
+        "PROPFIND" => handle_propfind(&state, &user, &path, req).await,
+        "REPORT" => handle_report(&state, &user, req).await,
+        method => Err(AppError::method_not_allowed(format!(
+            "Method not allowed: {}",
+            method
+        ))),
+    }
+}
+
+fn acl_service(state: &AppState) -> Result<&Arc<dyn WebDavAclUseCase>, AppError> {
+    state
+        .acl_service
+        .as_ref()
+        .ok_or_else(|| AppError::forbidden("Access control is not enabled"))
+}
+
+/**
+ * Handles PROPFIND on the principal tree.
+ *
+ * The root and the user collection list their members at Depth 1; a user
+ * principal has no members.
+ *
+ * @param state The application state containing service dependencies
+ * @param user The authenticated user information
+ * @param path The path below `/principals/`
+ * @param req The HTTP request containing the PROPFIND XML body
+ * @return XML response with the properties of the principal resources
+ */
+async fn handle_propfind(
+    state: &AppState,
+    user: &CurrentUser,
+    path: &str,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let service = acl_service(state)?;
+    let members = req
+        .headers()
+        .get("Depth")
+        .and_then(|v| v.to_str().ok())
+        .is_none_or(|depth| depth != "0");
+    let request = parse_propfind_body(req.into_body()).await?;
+
+    let users = PrincipalResource::Collection {
+        href: USER_PRINCIPALS_PATH.to_string(),
+        name: "users".to_string(),
+    };
+    let mut resources = Vec::new();
+    match path.split('/').collect::<Vec<_>>().as_slice() {
+        [""] => {
+            resources.push(PrincipalResource::Collection {
+                href: PRINCIPALS_PATH.to_string(),
+                name: "principals".to_string(),
+            });
+            if members {
+                resources.push(users);
+            }
+        }
+        ["users"] => {
+            resources.push(users);
+            if members {
+                resources.extend(
+                    service
+                        .principals()
+                        .await?
+                        .into_iter()
+                        .map(PrincipalResource::User),
+                );
+            }
+        }
+        ["users", name] => resources.push(PrincipalResource::User(service.principal(name).await?)),
+        _ => {
+            return Err(AppError::not_found(format!(
+                "Principal not found: {}",
+                path
+            )))
+        }
+    }
+
+    principal_response(&resources, &request, user)
+}
+
+/**
+ * Handles REPORT on the principal tree; only principal-property-search
+ * applies here, as principals carry no ACL of their own.
+ */
+async fn handle_report(
+    state: &AppState,
+    user: &CurrentUser,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let body_bytes = body::to_bytes(req.into_body(), usize::MAX)
+        .await
+        .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))?;
+    match WebDavAdapter::parse_acl_report(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse REPORT request: {}", e)))?
+    {
+        Some(AclReport::PrincipalPropertySearch {
+            criteria,
+            all_of,
+            properties,
+        }) => search_principals(state, user, &criteria, all_of, &properties).await,
+        _ => Err(AppError::forbidden("Unsupported REPORT type")),
+    }
+}
+
+/**
+ * Answers the principal reports of RFC 3744 on a WebDAV resource.
+ *
+ * `acl-principal-prop-set` returns the principals named in the ACL of the
+ * resource, which takes the read-acl privilege; `principal-property-search`
+ * searches all user principals.
+ *
+ * @param state The application state containing service dependencies
+ * @param user The authenticated user information
+ * @param path The WebDAV path the report was sent to
+ * @param report The parsed report
+ * @return XML response with the properties of the principals
+ */
+pub(crate) async fn acl_report(
+    state: &AppState,
+    user: &CurrentUser,
+    path: &str,
+    report: AclReport,
+) -> Result<Response<Body>, AppError> {
+    let properties = match report {
+        AclReport::PrincipalPropSet(properties) => properties,
+        AclReport::PrincipalPropertySearch {
+            criteria,
+            all_of,
+            properties,
+        } => return search_principals(state, user, &criteria, all_of, &properties).await,
+    };
+
+    let service = acl_service(state)?;
+    let privileges = service
+        .privileges(path, &user.username, user.role == "admin")
+        .await;
+    if !privileges.contains(DavPrivilege::ReadAcl) {
+        return Err(AppError::forbidden(
+            "The read-acl privilege is required on the resource",
+        ));
+    }
+
+    let mut resources = Vec::new();
+    for entry in service.acl(path).await {
+        if let AclPrincipal::User(name) = entry.principal {
+            // Users removed since the ACL was derived simply drop out
+            if let Ok(principal) = service.principal(&name).await {
+                resources.push(PrincipalResource::User(principal));
+            }
+        }
+    }
+
+    principal_response(&resources, &properties, user)
+}
+
+async fn search_principals(
+    state: &AppState,
+    user: &CurrentUser,
+    criteria: &[PrincipalMatch],
+    all_of: bool,
+    properties: &PropFindRequest,
+) -> Result<Response<Body>, AppError> {
+    let resources: Vec<_> = acl_service(state)?
+        .search_principals(criteria, all_of)
+        .await?
+        .into_iter()
+        .map(PrincipalResource::User)
+        .collect();
+    principal_response(&resources, properties, user)
+}
+
+async fn parse_propfind_body(body: Body) -> Result<PropFindRequest, AppError> {
+    let body_bytes = body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))?;
+    if body_bytes.is_empty() {
+        // Empty body means get all properties
+        return Ok(PropFindRequest {
+            prop_find_type: PropFindType::AllProp,
+        });
+    }
+    WebDavAdapter::parse_propfind(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse PROPFIND request: {}", e)))
+}
+
+fn principal_response(
+    resources: &[PrincipalResource],
+    request: &PropFindRequest,
+    user: &CurrentUser,
+) -> Result<Response<Body>, AppError> {
+    let mut response_body = Vec::new();
+    WebDavAdapter::generate_principal_response(
+        &mut response_body,
+        resources,
+        request,
+        &Principal::href_for(&user.username),
+    )
+    .map_err(|e| AppError::internal_error(format!("Failed to generate response: {}", e)))?;
+
+    Ok(Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(response_body))
+        .unwrap())
+}
@@ -0,0 +1,87 @@
+/**
+ * Reminder Handler Module
+ *
+ * This module exposes the reminders of calendar alarms delivered in-app:
+ * `/reminders` lists the pending notifications of the current user, and
+ * each one can be acknowledged or snoozed. Both changes are written back
+ * to the alarm of the event or task, so CalDAV clients stop showing it too.
+ */
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use std::sync::Arc;
+
+use crate::application::dtos::calendar_dto::{ReminderNotificationDto, SnoozeReminderDto};
+use crate::application::ports::reminder_ports::ReminderUseCase;
+use crate::common::di::AppState;
+use crate::common::errors::AppError;
+use crate::interfaces::middleware::auth::CurrentUser;
+
+/**
+ * Creates the router for the reminder endpoints.
+ *
+ * @return Router configured with the reminder endpoints
+ */
+pub fn reminder_routes() -> Router<AppState> {
+    Router::new()
+        .route("/reminders", get(list_reminders))
+        .route("/reminders/{id}/acknowledge", post(acknowledge_reminder))
+        .route("/reminders/{id}/snooze", post(snooze_reminder))
+}
+
+fn reminder_service(state: &AppState) -> Result<Arc<dyn ReminderUseCase>, AppError> {
+    state
+        .reminder_service
+        .clone()
+        .ok_or_else(|| AppError::method_not_allowed("Reminder service is not available"))
+}
+
+/**
+ * Lists the reminders delivered to the current user and not dismissed yet,
+ * oldest first.
+ */
+async fn list_reminders(
+    State(state): State<AppState>,
+    Extension(user): Extension<CurrentUser>,
+) -> Result<Json<Vec<ReminderNotificationDto>>, AppError> {
+    Ok(Json(
+        reminder_service(&state)?
+            .list_notifications(&user.id)
+            .await?,
+    ))
+}
+
+/**
+ * Dismisses a reminder and marks its alarm as acknowledged.
+ *
+ * @return 204 No Content
+ */
+async fn acknowledge_reminder(
+    State(state): State<AppState>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    reminder_service(&state)?.acknowledge(&id, &user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/**
+ * Dismisses a reminder and adds a snooze alarm firing again after the
+ * given minutes.
+ *
+ * @return 204 No Content
+ */
+async fn snooze_reminder(
+    State(state): State<AppState>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<String>,
+    Json(snooze): Json<SnoozeReminderDto>,
+) -> Result<StatusCode, AppError> {
+    reminder_service(&state)?
+        .snooze(&id, snooze.minutes, &user.id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
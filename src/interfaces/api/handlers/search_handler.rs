@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Json, Query, State},
+    extract::{Extension, Json, Query, State},
     http::StatusCode,
     response::IntoResponse,
 };
@@ -8,6 +8,8 @@ use tracing::{error, info};
 
 use crate::application::dtos::search_dto::SearchCriteriaDto;
 use crate::common::di::AppState;
+use crate::common::errors::{DomainError, ErrorKind};
+use crate::interfaces::middleware::auth::CurrentUser;
 
 /**
  * Manejador para las operaciones de búsqueda a través de la API.
@@ -24,11 +26,13 @@ impl SearchHandler {
      * Este endpoint permite búsquedas simples directamente con parámetros URL.
      *
      * @param state Estado de la aplicación con servicios
+     * @param current_user Usuario autenticado, que limita la búsqueda por contenido
      * @param query_params Parámetros de búsqueda como query string
      * @return Respuesta HTTP con los resultados de la búsqueda
      */
     pub async fn search_files_get(
         State(state): State<AppState>,
+        current_user: Option<Extension<CurrentUser>>,
         Query(params): Query<SearchParams>,
     ) -> impl IntoResponse {
         info!("API: Búsqueda de archivos con parámetros: {:?}", params);
@@ -51,9 +55,11 @@ impl SearchHandler {
         // Convertir parámetros de búsqueda a DTO
         let search_criteria = SearchCriteriaDto {
             name_contains: params.query,
+            content_contains: params.content,
             file_types: params
                 .type_filter
                 .map(|t| t.split(',').map(|s| s.trim().to_string()).collect()),
+            content_type: None,
             created_after: params.created_after,
             created_before: params.created_before,
             modified_after: params.modified_after,
@@ -67,7 +73,8 @@ impl SearchHandler {
         };
 
         // Realizar la búsqueda
-        match search_service.search(search_criteria).await {
+        let username = current_user.as_ref().map(|user| user.username.as_str());
+        match search_service.search(search_criteria, username).await {
             Ok(results) => {
                 info!(
                     "Búsqueda completada, {} archivos y {} carpetas encontrados",
@@ -79,7 +86,7 @@ impl SearchHandler {
             Err(err) => {
                 error!("Error en búsqueda: {}", err);
                 (
-                    StatusCode::INTERNAL_SERVER_ERROR,
+                    search_error_status(&err),
                     Json(json!({
                         "error": format!("Search error: {}", err)
                     })),
@@ -96,11 +103,13 @@ impl SearchHandler {
      * proporcionados en el cuerpo de la solicitud.
      *
      * @param state Estado de la aplicación con servicios
+     * @param current_user Usuario autenticado, que limita la búsqueda por contenido
      * @param criteria Criterios de búsqueda completos
      * @return Respuesta HTTP con los resultados de la búsqueda
      */
     pub async fn search_files_post(
         State(state): State<AppState>,
+        current_user: Option<Extension<CurrentUser>>,
         Json(criteria): Json<SearchCriteriaDto>,
     ) -> impl IntoResponse {
         info!("API: Búsqueda avanzada de archivos");
@@ -121,7 +130,8 @@ impl SearchHandler {
         };
 
         // Realizar la búsqueda
-        match search_service.search(criteria).await {
+        let username = current_user.as_ref().map(|user| user.username.as_str());
+        match search_service.search(criteria, username).await {
             Ok(results) => {
                 info!(
                     "Búsqueda completada, {} archivos y {} carpetas encontrados",
@@ -133,7 +143,7 @@ impl SearchHandler {
             Err(err) => {
                 error!("Error en búsqueda: {}", err);
                 (
-                    StatusCode::INTERNAL_SERVER_ERROR,
+                    search_error_status(&err),
                     Json(json!({
                         "error": format!("Search error: {}", err)
                     })),
@@ -196,12 +206,25 @@ impl SearchHandler {
     }
 }
 
+/// Código HTTP para un error de búsqueda
+fn search_error_status(err: &DomainError) -> StatusCode {
+    match err.kind {
+        ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+        ErrorKind::NotImplemented => StatusCode::NOT_IMPLEMENTED,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 /// Parámetros de búsqueda para el endpoint GET
 #[derive(Debug, serde::Deserialize)]
 pub struct SearchParams {
     /// Texto a buscar en nombres de archivos y carpetas
     pub query: Option<String>,
 
+    /// Palabras a buscar en el contenido de los documentos
+    pub content: Option<String>,
+
     /// Filtro por tipos de archivo (extensiones separadas por comas)
     #[serde(rename = "type")]
     pub type_filter: Option<String>,
@@ -0,0 +1,134 @@
+/**
+ * Task Handler Module
+ *
+ * This module exposes the VTODO tasks of calendars over REST, for the web
+ * UI and scripts: `/calendars/{calendar_id}/tasks` lists and creates the
+ * tasks of a calendar and `/tasks/{id}` reads, updates and deletes one.
+ * Task apps reach the same tasks through CalDAV.
+ */
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::application::dtos::calendar_dto::{CalendarTaskDto, CreateTaskDto, UpdateTaskDto};
+use crate::application::ports::calendar_ports::CalendarUseCase;
+use crate::common::di::AppState;
+use crate::common::errors::AppError;
+use crate::interfaces::middleware::auth::CurrentUser;
+
+/// Filters of a task listing
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskListQuery {
+    /// Only tasks with this status, e.g. `NEEDS-ACTION`
+    pub status: Option<String>,
+    /// Only subtasks of the task with this UID
+    pub parent: Option<String>,
+}
+
+/**
+ * Creates the router for the task endpoints.
+ *
+ * @return Router configured with the task endpoints
+ */
+pub fn task_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/calendars/{calendar_id}/tasks",
+            get(list_tasks).post(create_task),
+        )
+        .route(
+            "/tasks/{id}",
+            get(get_task).put(update_task).delete(delete_task),
+        )
+}
+
+fn calendar_service(state: &AppState) -> Result<Arc<dyn CalendarUseCase>, AppError> {
+    state
+        .calendar_service
+        .clone()
+        .ok_or_else(|| AppError::method_not_allowed("Calendar service is not available"))
+}
+
+/**
+ * Lists the tasks of a calendar, optionally only those with a status or
+ * the subtasks of a task.
+ */
+async fn list_tasks(
+    State(state): State<AppState>,
+    Extension(user): Extension<CurrentUser>,
+    Path(calendar_id): Path<String>,
+    Query(query): Query<TaskListQuery>,
+) -> Result<Json<Vec<CalendarTaskDto>>, AppError> {
+    let mut tasks = calendar_service(&state)?
+        .list_tasks(&calendar_id, &user.id)
+        .await?;
+    tasks.retain(|task| {
+        query
+            .status
+            .as_deref()
+            .is_none_or(|status| task.status.eq_ignore_ascii_case(status))
+            && query
+                .parent
+                .as_deref()
+                .is_none_or(|parent| task.parent_uid.as_deref() == Some(parent))
+    });
+    Ok(Json(tasks))
+}
+
+/**
+ * Creates a task in a calendar.
+ *
+ * @return 201 Created with the stored task
+ */
+async fn create_task(
+    State(state): State<AppState>,
+    Extension(user): Extension<CurrentUser>,
+    Path(calendar_id): Path<String>,
+    Json(task): Json<CreateTaskDto>,
+) -> Result<(StatusCode, Json<CalendarTaskDto>), AppError> {
+    let task = calendar_service(&state)?
+        .create_task(&calendar_id, task, &user.id)
+        .await?;
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+async fn get_task(
+    State(state): State<AppState>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<String>,
+) -> Result<Json<CalendarTaskDto>, AppError> {
+    Ok(Json(
+        calendar_service(&state)?.get_task(&id, &user.id).await?,
+    ))
+}
+
+/**
+ * Updates the fields of a task given in the request; empty strings clear
+ * the description, parent and recurrence.
+ */
+async fn update_task(
+    State(state): State<AppState>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<String>,
+    Json(update): Json<UpdateTaskDto>,
+) -> Result<Json<CalendarTaskDto>, AppError> {
+    Ok(Json(
+        calendar_service(&state)?
+            .update_task(&id, update, &user.id)
+            .await?,
+    ))
+}
+
+async fn delete_task(
+    State(state): State<AppState>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    calendar_service(&state)?.delete_task(&id, &user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
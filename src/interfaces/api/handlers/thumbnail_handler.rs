@@ -0,0 +1,115 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::Response,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::application::ports::thumbnail_ports::ThumbnailUseCase;
+use crate::common::errors::AppError;
+use crate::domain::entities::thumbnail::{ThumbnailFormat, ThumbnailSize};
+use crate::interfaces::api::http_range::etag_matches;
+
+/// Parámetros de `GET /api/files/{id}/thumbnail`
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailQuery {
+    pub size: Option<String>,
+    pub format: Option<String>,
+}
+
+/// Formato pedido explícitamente o, si no, WebP cuando el cliente lo acepta
+fn negotiate_format(requested: Option<&str>, headers: &HeaderMap) -> Option<ThumbnailFormat> {
+    if let Some(requested) = requested {
+        return ThumbnailFormat::parse(requested);
+    }
+    let accepts_webp = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.to_ascii_lowercase().contains("image/webp"));
+    Some(if accepts_webp {
+        ThumbnailFormat::Webp
+    } else {
+        ThumbnailFormat::Jpeg
+    })
+}
+
+/// Serves a thumbnail of an image file, generating it on first request
+pub async fn get_thumbnail(
+    State(thumbnail_service): State<Arc<dyn ThumbnailUseCase>>,
+    Path(file_id): Path<String>,
+    Query(query): Query<ThumbnailQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let size = match query.size.as_deref() {
+        Some(value) => ThumbnailSize::parse(value)
+            .ok_or_else(|| AppError::bad_request(format!("Unknown thumbnail size: {}", value)))?,
+        None => ThumbnailSize::Medium,
+    };
+    let format = negotiate_format(query.format.as_deref(), &headers).ok_or_else(|| {
+        AppError::bad_request(format!(
+            "Unknown thumbnail format: {}",
+            query.format.as_deref().unwrap_or_default()
+        ))
+    })?;
+
+    let thumbnail = thumbnail_service
+        .get_thumbnail(&file_id, size, format)
+        .await?;
+    let etag = format!(
+        "\"{}-{}-{}\"",
+        thumbnail.fingerprint,
+        size.name(),
+        format.extension()
+    );
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|list| etag_matches(list, &etag, true));
+
+    let builder = Response::builder()
+        .header(header::ETAG, &etag)
+        .header(header::CACHE_CONTROL, "private, max-age=86400")
+        .header(header::VARY, "Accept");
+    let response = if not_modified {
+        builder.status(StatusCode::NOT_MODIFIED).body(Body::empty())
+    } else {
+        builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, format.content_type())
+            .header(header::CONTENT_LENGTH, thumbnail.content.len())
+            .body(Body::from(thumbnail.content))
+    };
+    response.map_err(|e| AppError::internal_error(format!("Failed to build response: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_format_negotiation() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            negotiate_format(None, &headers),
+            Some(ThumbnailFormat::Jpeg)
+        );
+
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("image/avif,image/webp,*/*"),
+        );
+        assert_eq!(
+            negotiate_format(None, &headers),
+            Some(ThumbnailFormat::Webp)
+        );
+        assert_eq!(
+            negotiate_format(Some("jpg"), &headers),
+            Some(ThumbnailFormat::Jpeg)
+        );
+        assert_eq!(negotiate_format(Some("gif"), &headers), None);
+    }
+}
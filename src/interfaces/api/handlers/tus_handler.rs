@@ -0,0 +1,385 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::post,
+    Extension, Router,
+};
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::application::dtos::upload_dto::{CreateUploadDto, UploadSessionDto};
+use crate::application::ports::upload_ports::{
+    ResumableUploadUseCase, UploadChecksum, UploadChunkStream, UploadError,
+};
+use crate::common::di::AuthServices;
+use crate::domain::entities::upload_session::ChecksumAlgorithm;
+use crate::interfaces::api::http_range::http_date;
+use crate::interfaces::middleware::auth::{require_access_token, CurrentUser};
+
+/// Versión del protocolo tus implementada
+const TUS_VERSION: &str = "1.0.0";
+/// Extensiones del protocolo tus admitidas
+const TUS_EXTENSIONS: &str = "creation,termination,checksum,expiration";
+/// Tipo de contenido obligatorio de los fragmentos
+const OFFSET_OCTET_STREAM: &str = "application/offset+octet-stream";
+/// Estado HTTP definido por la extensión checksum para sumas que no coinciden
+const CHECKSUM_MISMATCH: u16 = 460;
+
+const TUS_RESUMABLE: HeaderName = HeaderName::from_static("tus-resumable");
+const TUS_VERSION_HEADER: HeaderName = HeaderName::from_static("tus-version");
+const TUS_EXTENSION: HeaderName = HeaderName::from_static("tus-extension");
+const TUS_MAX_SIZE: HeaderName = HeaderName::from_static("tus-max-size");
+const TUS_CHECKSUM_ALGORITHM: HeaderName = HeaderName::from_static("tus-checksum-algorithm");
+const UPLOAD_OFFSET: HeaderName = HeaderName::from_static("upload-offset");
+const UPLOAD_LENGTH: HeaderName = HeaderName::from_static("upload-length");
+const UPLOAD_DEFER_LENGTH: HeaderName = HeaderName::from_static("upload-defer-length");
+const UPLOAD_METADATA: HeaderName = HeaderName::from_static("upload-metadata");
+const UPLOAD_EXPIRES: HeaderName = HeaderName::from_static("upload-expires");
+const UPLOAD_CHECKSUM: HeaderName = HeaderName::from_static("upload-checksum");
+const FILE_ID: HeaderName = HeaderName::from_static("x-file-id");
+
+/// Rutas de subidas reanudables compatibles con tus 1.0.
+///
+/// Todo salvo `OPTIONS` exige un token de acceso, y cada subida solo la ve
+/// el usuario que la creó.
+pub fn tus_routes<S>(service: Arc<dyn ResumableUploadUseCase>, auth: AuthServices) -> Router<S> {
+    let authenticated = middleware::from_fn_with_state(auth, require_access_token);
+    Router::new()
+        .route(
+            "/",
+            post(create_upload)
+                .route_layer(authenticated.clone())
+                .options(tus_options),
+        )
+        .route(
+            "/{id}",
+            post(not_allowed)
+                .head(get_upload_offset)
+                .patch(append_chunk)
+                .delete(terminate_upload)
+                .route_layer(authenticated)
+                .options(tus_options),
+        )
+        .with_state(service)
+}
+
+/// Errores del protocolo tus convertidos en respuestas
+pub struct TusError(StatusCode, String);
+
+impl From<UploadError> for TusError {
+    fn from(err: UploadError) -> Self {
+        let status = match &err {
+            UploadError::NotFound(_) => StatusCode::NOT_FOUND,
+            UploadError::OffsetMismatch { .. } => StatusCode::CONFLICT,
+            UploadError::ChecksumMismatch(_) => {
+                StatusCode::from_u16(CHECKSUM_MISMATCH).unwrap_or(StatusCode::BAD_REQUEST)
+            }
+            UploadError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            UploadError::QuotaExceeded(_) => StatusCode::INSUFFICIENT_STORAGE,
+            UploadError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            UploadError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        if status.is_server_error() {
+            tracing::error!("Resumable upload failed: {}", err);
+        }
+        TusError(status, err.to_string())
+    }
+}
+
+impl IntoResponse for TusError {
+    fn into_response(self) -> Response {
+        let mut response = (self.0, self.1).into_response();
+        response
+            .headers_mut()
+            .insert(TUS_RESUMABLE, HeaderValue::from_static(TUS_VERSION));
+        response
+    }
+}
+
+fn bad_request(message: impl Into<String>) -> TusError {
+    TusError(StatusCode::BAD_REQUEST, message.into())
+}
+
+/// Comprueba que el cliente habla la versión del protocolo que implementamos
+fn check_tus_version(headers: &HeaderMap) -> Result<(), TusError> {
+    match headers.get(&TUS_RESUMABLE).and_then(|v| v.to_str().ok()) {
+        Some(TUS_VERSION) => Ok(()),
+        _ => Err(TusError(
+            StatusCode::PRECONDITION_FAILED,
+            format!("Unsupported tus version, expected {}", TUS_VERSION),
+        )),
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &HeaderName) -> Result<Option<u64>, TusError> {
+    headers
+        .get(name)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .ok_or_else(|| bad_request(format!("Invalid {} header", name)))
+        })
+        .transpose()
+}
+
+/// Respuesta vacía con las cabeceras comunes del protocolo
+fn tus_response(status: StatusCode) -> axum::http::response::Builder {
+    Response::builder()
+        .status(status)
+        .header(TUS_RESUMABLE, TUS_VERSION)
+        .header(header::CACHE_CONTROL, "no-store")
+}
+
+fn finish(builder: axum::http::response::Builder) -> Response {
+    builder
+        .body(Body::empty())
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+fn with_upload_state(
+    builder: axum::http::response::Builder,
+    upload: &UploadSessionDto,
+) -> axum::http::response::Builder {
+    let builder = builder
+        .header(UPLOAD_OFFSET, upload.offset)
+        .header(UPLOAD_EXPIRES, http_date(upload.expires_at));
+    match &upload.file {
+        Some(file) => builder.header(FILE_ID, file.id.as_str()),
+        None => builder,
+    }
+}
+
+/// Describe las capacidades del servidor
+pub async fn tus_options(State(service): State<Arc<dyn ResumableUploadUseCase>>) -> Response {
+    let algorithms = ChecksumAlgorithm::ALL
+        .iter()
+        .map(|a| a.name())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let mut builder = tus_response(StatusCode::NO_CONTENT)
+        .header(TUS_VERSION_HEADER, TUS_VERSION)
+        .header(TUS_EXTENSION, TUS_EXTENSIONS)
+        .header(TUS_CHECKSUM_ALGORITHM, algorithms);
+    if let Some(max_size) = service.max_size() {
+        builder = builder.header(TUS_MAX_SIZE, max_size);
+    }
+    finish(builder)
+}
+
+/// Crea una subida a partir de `Upload-Length` y `Upload-Metadata`
+pub async fn create_upload(
+    State(service): State<Arc<dyn ResumableUploadUseCase>>,
+    Extension(user): Extension<CurrentUser>,
+    headers: HeaderMap,
+) -> Result<Response, TusError> {
+    check_tus_version(&headers)?;
+
+    if headers.contains_key(&UPLOAD_DEFER_LENGTH) {
+        return Err(bad_request("Deferred upload length is not supported"));
+    }
+    let length = header_u64(&headers, &UPLOAD_LENGTH)?
+        .ok_or_else(|| bad_request("Missing Upload-Length header"))?;
+
+    let mut metadata = match headers.get(&UPLOAD_METADATA) {
+        Some(value) => parse_metadata(
+            value
+                .to_str()
+                .map_err(|_| bad_request("Invalid Upload-Metadata header"))?,
+        )
+        .ok_or_else(|| bad_request("Invalid Upload-Metadata header"))?,
+        None => HashMap::new(),
+    };
+
+    // Claves usadas por los clientes tus más comunes
+    let take = |metadata: &HashMap<String, String>, keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| metadata.get(*key))
+            .filter(|value| !value.is_empty())
+            .cloned()
+    };
+    let name = take(&metadata, &["filename", "name"])
+        .ok_or_else(|| bad_request("Upload-Metadata must include a filename"))?;
+    let content_type = take(&metadata, &["filetype", "content_type", "type"]);
+    let folder_id = take(&metadata, &["folder_id", "folderId"]);
+    metadata.retain(|_, value| !value.is_empty());
+
+    let upload = service
+        .create_upload(
+            &user.id,
+            CreateUploadDto {
+                length,
+                name,
+                content_type,
+                folder_id,
+                metadata,
+            },
+        )
+        .await?;
+
+    let builder = tus_response(StatusCode::CREATED)
+        .header(header::LOCATION, format!("/api/uploads/{}", upload.id));
+    Ok(finish(with_upload_state(builder, &upload)))
+}
+
+/// Devuelve el desplazamiento actual de una subida
+pub async fn get_upload_offset(
+    State(service): State<Arc<dyn ResumableUploadUseCase>>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, TusError> {
+    check_tus_version(&headers)?;
+    let upload = service.get_upload(&user.id, &id).await?;
+
+    let builder = tus_response(StatusCode::OK).header(UPLOAD_LENGTH, upload.length);
+    Ok(finish(with_upload_state(builder, &upload)))
+}
+
+/// Anexa el cuerpo de la petición a la subida en `Upload-Offset`
+pub async fn append_chunk(
+    State(service): State<Arc<dyn ResumableUploadUseCase>>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response, TusError> {
+    check_tus_version(&headers)?;
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if !content_type.eq_ignore_ascii_case(OFFSET_OCTET_STREAM) {
+        return Err(TusError(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Content-Type must be {}", OFFSET_OCTET_STREAM),
+        ));
+    }
+
+    let offset = header_u64(&headers, &UPLOAD_OFFSET)?
+        .ok_or_else(|| bad_request("Missing Upload-Offset header"))?;
+
+    let checksum = headers
+        .get(&UPLOAD_CHECKSUM)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(parse_checksum)
+                .ok_or_else(|| bad_request("Invalid or unsupported Upload-Checksum header"))
+        })
+        .transpose()?;
+
+    let chunk: UploadChunkStream = Box::pin(
+        body.into_data_stream()
+            .map(|result| result.map_err(std::io::Error::other)),
+    );
+
+    let upload = service
+        .append_chunk(&user.id, &id, offset, chunk, checksum)
+        .await?;
+    Ok(finish(with_upload_state(
+        tus_response(StatusCode::NO_CONTENT),
+        &upload,
+    )))
+}
+
+/// Cancela una subida y descarta su contenido
+pub async fn terminate_upload(
+    State(service): State<Arc<dyn ResumableUploadUseCase>>,
+    Extension(user): Extension<CurrentUser>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, TusError> {
+    check_tus_version(&headers)?;
+    service.terminate_upload(&user.id, &id).await?;
+    Ok(finish(tus_response(StatusCode::NO_CONTENT)))
+}
+
+async fn not_allowed() -> TusError {
+    TusError(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "Uploads are appended with PATCH".to_string(),
+    )
+}
+
+/// Interpreta `Upload-Metadata`: pares `clave valor-base64` separados por comas
+fn parse_metadata(value: &str) -> Option<HashMap<String, String>> {
+    let mut metadata = HashMap::new();
+    for pair in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let mut parts = pair.split_whitespace();
+        let key = parts.next()?;
+        let value = match parts.next() {
+            Some(encoded) => String::from_utf8(decode_base64(encoded)?).ok()?,
+            None => String::new(),
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        metadata.insert(key.to_string(), value);
+    }
+    Some(metadata)
+}
+
+/// Interpreta `Upload-Checksum`: `algoritmo resumen-base64`
+fn parse_checksum(value: &str) -> Option<UploadChecksum> {
+    let (algorithm, digest) = value.trim().split_once(' ')?;
+    Some(UploadChecksum {
+        algorithm: ChecksumAlgorithm::parse(algorithm)?,
+        digest: decode_base64(digest.trim())?,
+    })
+}
+
+/// Base64 estándar que acepta valores con o sin relleno
+const BASE64_ANY_PADDING: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    BASE64_ANY_PADDING.decode(input).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(decode_base64("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(decode_base64("aGVsbG8").unwrap(), b"hello");
+        assert_eq!(decode_base64("YWJj").unwrap(), b"abc");
+        assert_eq!(decode_base64("").unwrap(), b"");
+        assert!(decode_base64("a").is_none());
+        assert!(decode_base64("a*==").is_none());
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let metadata =
+            parse_metadata("filename d29ybGRfZG9taW5hdGlvbi5wZGY=,is_confidential, folder_id YWJj")
+                .unwrap();
+        assert_eq!(metadata["filename"], "world_domination.pdf");
+        assert_eq!(metadata["is_confidential"], "");
+        assert_eq!(metadata["folder_id"], "abc");
+        assert!(parse_metadata("filename a b").is_none());
+    }
+
+    #[test]
+    fn test_parse_checksum() {
+        let checksum = parse_checksum("sha1 Kq5sNclPz7QV2+lfQIuc6R7oRu0=").unwrap();
+        assert_eq!(checksum.algorithm, ChecksumAlgorithm::Sha1);
+        assert_eq!(checksum.digest.len(), 20);
+        assert!(parse_checksum("crc32 AAAA").is_none());
+    }
+}
@@ -0,0 +1,54 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::application::ports::version_ports::FileVersionUseCase;
+use crate::common::errors::AppError;
+
+/// Lists the stored versions of a file, newest first
+pub async fn list_versions(
+    State(version_service): State<Arc<dyn FileVersionUseCase>>,
+    Path(file_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let versions = version_service.list_versions(&file_id).await?;
+    Ok((StatusCode::OK, Json(versions)))
+}
+
+/// Downloads the content of a specific version
+pub async fn download_version(
+    State(version_service): State<Arc<dyn FileVersionUseCase>>,
+    Path((file_id, version_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    let (version, stream) = version_service
+        .get_version_stream(&file_id, &version_id)
+        .await?;
+
+    let disposition = format!("attachment; filename=\"{}\"", version.name.replace('"', ""));
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, version.mime_type)
+        .header(header::CONTENT_LENGTH, version.size)
+        .header(header::CONTENT_DISPOSITION, disposition)
+        .body(Body::from_stream(stream))
+        .map_err(|e| AppError::internal_error(format!("Failed to build response: {}", e)))
+}
+
+/// Restores a version as the current content of the file
+pub async fn restore_version(
+    State(version_service): State<Arc<dyn FileVersionUseCase>>,
+    Path((file_id, version_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    version_service
+        .restore_version(&file_id, &version_id)
+        .await?;
+
+    info!("Restored version {} of file {}", version_id, file_id);
+    Ok(StatusCode::NO_CONTENT)
+}
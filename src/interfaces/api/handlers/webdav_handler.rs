@@ -8,27 +8,53 @@
  */
 use axum::{
     body::{self, Body},
-    http::{header, HeaderName, Request, StatusCode},
+    extract::State,
+    http::{header, HeaderMap, HeaderName, Request, StatusCode},
     response::Response,
     Router,
 };
-use bytes::Buf;
+use bytes::{Buf, Bytes, BytesMut};
 use chrono::Utc;
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use uuid::Uuid;
 
 use crate::application::adapters::webdav_adapter::{
-    LockInfo, LockScope, LockType, PropFindRequest, WebDavAdapter,
+    IfCondition, PropFindContext, PropFindRequest, PropPatchStatus, SearchHit,
+    SyncCollectionRequest, SyncCollectionResult, WebDavAdapter, OWNCLOUD_NS,
 };
+use crate::application::dtos::file_dto::FileDto;
 use crate::application::dtos::folder_dto::FolderDto;
+use crate::application::dtos::pagination::PaginationRequestDto;
+use crate::application::ports::storage_ports::StorageQuota;
+use crate::application::ports::webdav_lock_ports::LockRequest;
 use crate::common::di::AppState;
-use crate::common::errors::AppError;
+use crate::common::errors::{AppError, DomainError, ErrorKind};
+use crate::domain::entities::sync_change::{
+    format_sync_token, is_direct_member, is_inside, parse_sync_token, ChangeKind, SyncChange,
+};
+use crate::domain::entities::webdav_acl::{
+    AccessControlEntry, DavPrivilege, Principal, PrivilegeSet,
+};
+use crate::domain::entities::webdav_lock::{LockDepth, LockedOperation, WebDavLock};
+use crate::domain::entities::webdav_property::{DeadProperty, PropertyUpdate};
+use crate::domain::entities::webdav_search::compare_resources;
+use crate::interfaces::api::handlers::principal_handler;
+use crate::interfaces::api::http_range::{
+    check_write_preconditions, etag_matches, file_etag, serve_file, FileResource,
+};
 use crate::interfaces::middleware::auth::CurrentUser;
 
 // Create a custom DAV header since it's not in the standard headers
 const HEADER_DAV: HeaderName = HeaderName::from_static("dav");
 const HEADER_LOCK_TOKEN: HeaderName = HeaderName::from_static("lock-token");
-// const HEADER_IF: HeaderName = HeaderName::from_static("if");
+const HEADER_IF: HeaderName = HeaderName::from_static("if");
+const HEADER_DASL: HeaderName = HeaderName::from_static("dasl");
+const HEADER_PAGE_SIZE: HeaderName = HeaderName::from_static("x-page-size");
+const HEADER_PAGE: HeaderName = HeaderName::from_static("x-page");
+const HEADER_NEXT_PAGE: HeaderName = HeaderName::from_static("x-next-page");
 
 /**
  * Creates and returns the WebDAV router with all required endpoints.
@@ -44,7 +70,29 @@ pub fn webdav_routes() -> Router<AppState> {
     Router::new().route("/webdav/{*path}", axum::routing::any(handle_webdav_methods))
 }
 
-async fn handle_webdav_methods(req: Request<Body>) -> Result<Response<Body>, AppError> {
+async fn handle_webdav_methods(
+    State(state): State<AppState>,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    dispatch(Arc::new(state), req).await
+}
+
+/**
+ * Runs a WebDAV request whose URI path starts with `/webdav/`.
+ *
+ * Other mounts of the WebDAV tree, such as the Nextcloud endpoints, rewrite
+ * their requests to that form and hand them over here.
+ *
+ * @param state The application state containing service dependencies
+ * @param req The request, carrying the authenticated user as an extension
+ * @return The WebDAV response
+ */
+pub(crate) async fn dispatch(
+    state: Arc<AppState>,
+    mut req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    // The method handlers read the application state from the request extensions
+    req.extensions_mut().insert(state);
     let method = req.method().clone();
 
     match method.as_str() {
@@ -58,6 +106,8 @@ async fn handle_webdav_methods(req: Request<Body>) -> Result<Response<Body>, App
         "PROPFIND" => handle_propfind(req).await,
         "PROPPATCH" => handle_proppatch(req).await,
         "LOCK" => handle_lock(req).await,
+        "REPORT" => handle_report(req).await,
+        "SEARCH" => handle_search(req).await,
         "UNLOCK" => handle_unlock(req).await,
         _ => Err(AppError::method_not_allowed(format!(
             "Method not allowed: {}",
@@ -66,6 +116,510 @@ async fn handle_webdav_methods(req: Request<Body>) -> Result<Response<Body>, App
     }
 }
 
+/**
+ * Maps a tagged `If` header resource or a `Destination` URL to a WebDAV path.
+ */
+fn href_to_path(href: &str) -> String {
+    match href.find("/webdav") {
+        Some(idx) => href[idx + "/webdav".len()..].trim_matches('/').to_string(),
+        None => href.trim_matches('/').to_string(),
+    }
+}
+
+/**
+ * Returns the entity tag clients see for the resource at `path`, if it exists.
+ */
+async fn resource_etag(state: &AppState, path: &str) -> Option<String> {
+    if path.trim_matches('/').is_empty() {
+        return Some("\"root\"".to_string());
+    }
+    if let Ok(file) = state.applications.file_service.get_file_by_path(path).await {
+        return Some(file_etag(&file.etag));
+    }
+    state
+        .applications
+        .folder_service
+        .get_folder_by_path(path)
+        .await
+        .ok()
+        .map(|folder| format!("\"{}\"", folder.id))
+}
+
+/**
+ * Applies `If-Match` and `If-None-Match` to a write on `path`, so clients can
+ * detect that someone else changed the resource since they last read it.
+ *
+ * @param state The application state containing service dependencies
+ * @param headers The request headers
+ * @param path The resource being written
+ * @return 412 Precondition Failed when a condition does not hold
+ */
+async fn check_conditional_write(
+    state: &AppState,
+    headers: &HeaderMap,
+    path: &str,
+) -> Result<(), AppError> {
+    if !headers.contains_key(header::IF_MATCH) && !headers.contains_key(header::IF_NONE_MATCH) {
+        return Ok(());
+    }
+    check_write_preconditions(headers, resource_etag(state, path).await.as_deref())
+}
+
+/**
+ * Evaluates the `If` header of a request (RFC 4918, section 10.4).
+ *
+ * Lock token conditions hold when the token belongs to an active lock on the
+ * resource and entity tag conditions are compared with the current ETag.
+ * The header holds when any of its lists holds; otherwise the request fails
+ * with 412 Precondition Failed.
+ *
+ * @param state The application state containing service dependencies
+ * @param headers The request headers
+ * @param path The requested resource path
+ * @return The lock tokens submitted by the client
+ */
+async fn evaluate_if_header(
+    state: &AppState,
+    headers: &HeaderMap,
+    path: &str,
+) -> Result<Vec<String>, AppError> {
+    let value = match headers.get(HEADER_IF) {
+        Some(value) => value
+            .to_str()
+            .map_err(|_| AppError::bad_request("Invalid If header"))?,
+        None => return Ok(Vec::new()),
+    };
+    let if_header = WebDavAdapter::parse_if_header(value)
+        .map_err(|e| AppError::bad_request(format!("Failed to parse If header: {}", e)))?;
+
+    for list in &if_header.lists {
+        let list_path = match &list.resource {
+            Some(href) => href_to_path(href),
+            None => path.to_string(),
+        };
+
+        let mut holds = true;
+        for (negated, condition) in &list.conditions {
+            let matched = match condition {
+                IfCondition::Token(token) => match &state.lock_service {
+                    Some(locks) => locks
+                        .locks_covering(&list_path)
+                        .await
+                        .iter()
+                        .any(|lock| &lock.token == token),
+                    None => false,
+                },
+                IfCondition::ETag(tag) => resource_etag(state, &list_path)
+                    .await
+                    .is_some_and(|etag| etag_matches(tag, &etag, false)),
+            };
+            if matched == *negated {
+                holds = false;
+                break;
+            }
+        }
+
+        if holds {
+            return Ok(if_header.submitted_tokens());
+        }
+    }
+
+    Err(AppError::precondition_failed(
+        "None of the conditions in the If header holds",
+    ))
+}
+
+/**
+ * Checks that a write on `path` does not break a lock held by someone else.
+ *
+ * @param state The application state containing service dependencies
+ * @param path The resource being written
+ * @param operation The kind of write
+ * @param tokens The lock tokens submitted in the If header
+ * @param user The authenticated user information
+ * @return 423 Locked when a lock token is missing
+ */
+async fn check_locks(
+    state: &AppState,
+    path: &str,
+    operation: LockedOperation,
+    tokens: &[String],
+    user: &CurrentUser,
+) -> Result<(), AppError> {
+    match &state.lock_service {
+        Some(locks) => locks
+            .check_write(path, operation, tokens, Some(&user.username))
+            .await
+            .map_err(AppError::from),
+        None => Ok(()),
+    }
+}
+
+/**
+ * Checks that the user holds a privilege on the resource at `path`
+ * (RFC 3744, section 3).
+ *
+ * @return 403 Forbidden when the privilege is missing
+ */
+async fn check_privilege(
+    state: &AppState,
+    user: &CurrentUser,
+    path: &str,
+    privilege: DavPrivilege,
+) -> Result<(), AppError> {
+    let Some(acl) = &state.acl_service else {
+        return Ok(());
+    };
+    let privileges = acl
+        .privileges(path, &user.username, user.role == "admin")
+        .await;
+    if privileges.contains(privilege) {
+        Ok(())
+    } else {
+        Err(AppError::forbidden(format!(
+            "The {} privilege is required on /{}",
+            privilege.name(),
+            path.trim_matches('/')
+        )))
+    }
+}
+
+/**
+ * Checks the privileges a write needs (RFC 3744, appendix B): changing a
+ * resource takes write access to it, while adding or removing one takes
+ * bind or unbind on its collection.
+ */
+async fn check_write_access(
+    state: &AppState,
+    user: &CurrentUser,
+    path: &str,
+    operation: LockedOperation,
+) -> Result<(), AppError> {
+    let parent = parent_path(path);
+    match operation {
+        LockedOperation::Modify => {
+            check_privilege(state, user, path, DavPrivilege::WriteContent).await
+        }
+        LockedOperation::Create => check_privilege(state, user, parent, DavPrivilege::Bind).await,
+        LockedOperation::Remove => {
+            check_privilege(state, user, path, DavPrivilege::Write).await?;
+            check_privilege(state, user, parent, DavPrivilege::Unbind).await
+        }
+    }
+}
+
+/**
+ * Returns the collection containing `path`; the root for top-level members.
+ */
+fn parent_path(path: &str) -> &str {
+    let path = path.trim_matches('/');
+    path.rfind('/').map(|idx| &path[..idx]).unwrap_or("")
+}
+
+/**
+ * Collects the locks to report in a PROPFIND on `path`: those that apply to
+ * the resource itself and those rooted at its members.
+ */
+async fn locks_for_propfind(state: &AppState, path: &str) -> Vec<WebDavLock> {
+    let Some(service) = &state.lock_service else {
+        return Vec::new();
+    };
+    let mut locks = service.locks_covering(path).await;
+    for lock in service.active_locks(Some(path)).await {
+        if !locks.iter().any(|existing| existing.token == lock.token) {
+            locks.push(lock);
+        }
+    }
+    locks
+}
+
+/**
+ * Returns the ID of the resource at `path`; the root folder uses `root`.
+ */
+async fn resource_id(state: &AppState, path: &str) -> Option<String> {
+    if path.trim_matches('/').is_empty() {
+        return Some("root".to_string());
+    }
+    if let Ok(file) = state.applications.file_service.get_file_by_path(path).await {
+        return Some(file.id);
+    }
+    state
+        .applications
+        .folder_service
+        .get_folder_by_path(path)
+        .await
+        .ok()
+        .map(|folder| folder.id)
+}
+
+/**
+ * Collects the IDs of a folder and of everything below it.
+ */
+async fn subtree_ids(state: &AppState, folder_id: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut pending = vec![folder_id.to_string()];
+    while let Some(id) = pending.pop() {
+        if let Ok(files) = state.applications.file_service.list_files(Some(&id)).await {
+            ids.extend(files.into_iter().map(|file| file.id));
+        }
+        if let Ok(folders) = state
+            .applications
+            .folder_service
+            .list_folders(Some(&id))
+            .await
+        {
+            pending.extend(folders.into_iter().map(|folder| folder.id));
+        }
+        ids.push(id);
+    }
+    ids
+}
+
+/**
+ * Loads the dead properties of the resources in a PROPFIND response, only
+ * when the request asks for something other than live properties.
+ */
+async fn dead_properties_for(
+    state: &AppState,
+    request: &PropFindRequest,
+    resource_ids: Vec<String>,
+) -> HashMap<String, Vec<DeadProperty>> {
+    match &state.property_service {
+        Some(service) if request.includes_dead_properties() => {
+            service.get_properties_for(&resource_ids).await
+        }
+        _ => HashMap::new(),
+    }
+}
+
+/**
+ * Computes the user's privileges on the resources in a PROPFIND response
+ * and, when the ACL itself is asked for, the ACLs the user may read.
+ */
+async fn access_control_for(
+    state: &AppState,
+    user: &CurrentUser,
+    request: &PropFindRequest,
+    resources: &[(String, String)],
+) -> (
+    HashMap<String, PrivilegeSet>,
+    HashMap<String, Vec<AccessControlEntry>>,
+) {
+    let mut privileges = HashMap::new();
+    let mut acls = HashMap::new();
+    let Some(service) = &state.acl_service else {
+        return (privileges, acls);
+    };
+    let wants_acl = request.requests("DAV:", "acl");
+    if !wants_acl
+        && !request.requests("DAV:", "current-user-privilege-set")
+        && !request.requests(OWNCLOUD_NS, "permissions")
+    {
+        return (privileges, acls);
+    }
+
+    let is_admin = user.role == "admin";
+    for (id, path) in resources {
+        let granted = service.privileges(path, &user.username, is_admin).await;
+        if wants_acl && granted.contains(DavPrivilege::ReadAcl) {
+            acls.insert(id.clone(), service.acl(path).await);
+        }
+        privileges.insert(id.clone(), granted);
+    }
+    (privileges, acls)
+}
+
+/**
+ * Pairs the ID of each folder and file in a response with its path.
+ */
+fn resources_of<'a>(
+    folders: impl IntoIterator<Item = &'a FolderDto>,
+    files: impl IntoIterator<Item = &'a FileDto>,
+) -> Vec<(String, String)> {
+    folders
+        .into_iter()
+        .map(|folder| (folder.id.clone(), folder.path.clone()))
+        .chain(
+            files
+                .into_iter()
+                .map(|file| (file.id.clone(), file.path.clone())),
+        )
+        .collect()
+}
+
+/**
+ * Gathers what a PROPFIND response reports besides resource metadata: the
+ * locks, the dead properties, the access control properties and, only when
+ * asked for, the sync tokens of the collections in the response and the
+ * user's storage quota.
+ */
+async fn propfind_context(
+    state: &AppState,
+    user: &CurrentUser,
+    request: &PropFindRequest,
+    path: &str,
+    resources: Vec<(String, String)>,
+    collections: &[&FolderDto],
+) -> PropFindContext {
+    let mut sync_tokens = HashMap::new();
+    if let Some(change_log) = &state.change_log {
+        if request.requests("DAV:", "sync-token") {
+            for folder in collections {
+                let seq = change_log.current_seq(&folder.path).await;
+                sync_tokens.insert(folder.id.clone(), format_sync_token(seq));
+            }
+        }
+    }
+
+    let quota = if request.requests("DAV:", "quota-available-bytes")
+        || request.requests("DAV:", "quota-used-bytes")
+    {
+        user_quota(state, user).await
+    } else {
+        None
+    };
+
+    let (privileges, acls) = access_control_for(state, user, request, &resources).await;
+    let resource_ids = resources.into_iter().map(|(id, _)| id).collect();
+
+    PropFindContext {
+        locks: locks_for_propfind(state, path).await,
+        dead_properties: dead_properties_for(state, request, resource_ids).await,
+        sync_tokens,
+        quota,
+        privileges,
+        acls,
+        principal: Some(Principal::href_for(&user.username)),
+    }
+}
+
+/**
+ * Returns the recorded storage usage and quota of the user, when usage is
+ * tracked.
+ */
+pub(crate) async fn user_quota(state: &AppState, user: &CurrentUser) -> Option<StorageQuota> {
+    let service = state.storage_usage_service.as_ref()?;
+    match service.get_user_quota(&user.id).await {
+        Ok(quota) => Some(quota),
+        Err(e) => {
+            tracing::warn!(
+                "Could not read the storage quota of {}: {}",
+                user.username,
+                e
+            );
+            None
+        }
+    }
+}
+
+/**
+ * Returns how many more bytes a write by the user may store, or `None` when
+ * quotas are not enforced or the user has no limit.
+ */
+pub(crate) async fn quota_allowance(state: &AppState, user: &CurrentUser) -> Option<u64> {
+    if !state.core.config.features.enable_user_storage_quotas {
+        return None;
+    }
+    user_quota(state, user).await?.available_bytes()
+}
+
+/**
+ * Rejects a write of `requested` bytes that does not fit in the allowance.
+ */
+fn check_quota(requested: u64, allowance: Option<u64>) -> Result<(), AppError> {
+    match allowance {
+        Some(available) if requested > available => {
+            Err(AppError::from(DomainError::quota_exceeded(
+                "User",
+                format!(
+                    "Storage quota exceeded: {} bytes requested, {} available",
+                    requested, available
+                ),
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/**
+ * Recalculates the user's storage usage in the background after a write.
+ */
+fn refresh_storage_usage(state: &AppState, user: &CurrentUser) {
+    if let Some(service) = state.storage_usage_service.clone() {
+        let user_id = user.id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.update_user_storage_usage(&user_id).await {
+                tracing::warn!("Failed to update storage usage for {}: {}", user_id, e);
+            }
+        });
+    }
+}
+
+/**
+ * Reads a request body, giving up as soon as it grows past `limit` bytes.
+ *
+ * Chunked uploads declare no length, so this is where they are stopped when
+ * they overflow the quota. Nothing has been written to storage at that point,
+ * so dropping what was buffered is all the cleanup needed.
+ */
+pub(crate) async fn read_body_within(body: Body, limit: Option<u64>) -> Result<Bytes, AppError> {
+    let mut stream = body.into_data_stream();
+    let mut buffer = BytesMut::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk
+            .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))?;
+        check_quota((buffer.len() + chunk.len()) as u64, limit)?;
+        buffer.extend_from_slice(&chunk);
+    }
+    Ok(buffer.freeze())
+}
+
+/**
+ * Streams a request body that fails once it goes past `limit` bytes.
+ * `received` counts the bytes read, so the caller can tell a write that
+ * failed for lack of quota from other failures.
+ */
+fn body_stream_within(
+    body: Body,
+    limit: Option<u64>,
+    received: Arc<AtomicU64>,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>> {
+    Box::pin(body.into_data_stream().map(move |chunk| {
+        let chunk = chunk.map_err(std::io::Error::other)?;
+        let total = received.fetch_add(chunk.len() as u64, Ordering::Relaxed) + chunk.len() as u64;
+        if limit.is_some_and(|limit| total > limit) {
+            return Err(std::io::Error::other("Storage quota exceeded"));
+        }
+        Ok(chunk)
+    }))
+}
+
+/**
+ * Gives a copied resource the dead properties of its source.
+ */
+async fn copy_dead_properties(state: &AppState, source_id: &str, target_id: &str) {
+    if let Some(service) = &state.property_service {
+        if let Err(e) = service.copy_properties(source_id, target_id).await {
+            tracing::warn!("Could not copy WebDAV properties of {}: {}", source_id, e);
+        }
+    }
+}
+
+/**
+ * Parses a `Timeout` header such as `Infinite, Second-4100000000`, keeping
+ * the first value the server understands.
+ */
+fn parse_timeout(value: &str) -> Option<u64> {
+    value.split(',').map(str::trim).find_map(|part| {
+        if part.eq_ignore_ascii_case("infinite") {
+            return Some(u64::MAX);
+        }
+        part.get(..7)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("second-"))
+            .and_then(|_| part[7..].parse().ok())
+    })
+}
+
 /**
  * Handles OPTIONS requests to advertise WebDAV capabilities.
  *
@@ -87,10 +641,11 @@ async fn handle_options(req: Request<Body>) -> Result<Response<Body>, AppError>
 
     Ok(Response::builder()
         .status(StatusCode::OK)
-        .header(HEADER_DAV, "1, 2") // Class 1 and 2 WebDAV support
+        .header(HEADER_DAV, "1, 2, access-control") // Class 1 and 2 WebDAV support, RFC 3744
+        .header(HEADER_DASL, "<DAV:basicsearch>") // RFC 5323, section 3.2
         .header(
             header::ALLOW,
-            "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, PROPPATCH, MKCOL, COPY, MOVE, LOCK, UNLOCK",
+            "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, PROPPATCH, MKCOL, COPY, MOVE, LOCK, UNLOCK, REPORT, SEARCH",
         )
         .body(Body::empty())
         .unwrap())
@@ -103,6 +658,13 @@ async fn handle_options(req: Request<Body>) -> Result<Response<Body>, AppError>
  * retrieving properties of files and folders in the specified path.
  * It supports the Depth header to control recursion depth.
  *
+ * Collections are listed page by page and the multistatus is streamed as
+ * each page is written, so memory use does not grow with the collection.
+ * Depth infinity is answered up to a configured number of resources and
+ * refused with `propfind-finite-depth` beyond it. A client that sends
+ * `X-Page-Size` (and optionally `X-Page`) gets one page of the members of
+ * a Depth 1 listing, with `X-Next-Page` set while more remain.
+ *
  * @param state The application state containing service dependencies
  * @param user The authenticated user information
  * @param path The requested resource path
@@ -129,6 +691,10 @@ async fn handle_propfind(req: Request<Body>) -> Result<Response<Body>, AppError>
         .unwrap_or("infinity")
         .to_string();
 
+    // Optional paging of the members, for clients that ask for it
+    let page_size = header_number(req.headers(), &HEADER_PAGE_SIZE);
+    let page = header_number(req.headers(), &HEADER_PAGE).unwrap_or(0);
+
     // Get the state and user in a way that doesn't keep req borrowed
     let state = {
         let state_ref = req
@@ -138,7 +704,7 @@ async fn handle_propfind(req: Request<Body>) -> Result<Response<Body>, AppError>
         state_ref.clone()
     };
 
-    let _user = {
+    let user = {
         let user_ref = req
             .extensions()
             .get::<CurrentUser>()
@@ -170,6 +736,8 @@ async fn handle_propfind(req: Request<Body>) -> Result<Response<Body>, AppError>
         })?
     };
 
+    check_privilege(&state, &user, &path, DavPrivilege::Read).await?;
+
     // Get folder service from state
     let folder_service = &state.applications.folder_service;
     let file_service = &state.applications.file_service;
@@ -177,20 +745,8 @@ async fn handle_propfind(req: Request<Body>) -> Result<Response<Body>, AppError>
     // Determine base HREF
     let base_href = format!("/webdav/{}/", path);
 
-    // Check if path exists as a file or folder
-    if path.is_empty() || path == "/" {
-        // Root folder
-        let subfolders = folder_service
-            .list_folders(None)
-            .await
-            .map_err(|e| AppError::internal_error(format!("Failed to get subfolders: {}", e)))?;
-
-        let files = file_service
-            .list_files(None)
-            .await
-            .map_err(|e| AppError::internal_error(format!("Failed to get files: {}", e)))?;
-
-        // Create root folder DTO for response
+    // The root is not stored as a folder
+    let (collection, folder_id) = if path.is_empty() || path == "/" {
         let root_folder = FolderDto {
             id: "root".to_string(),
             name: "".to_string(),
@@ -200,169 +756,936 @@ async fn handle_propfind(req: Request<Body>) -> Result<Response<Body>, AppError>
             modified_at: Utc::now().timestamp() as u64,
             is_root: true,
         };
-
-        // Generate response
+        (root_folder, None)
+    } else if let Ok(folder) = folder_service.get_folder_by_path(&path).await {
+        let folder_id = Some(folder.id.clone());
+        (folder, folder_id)
+    } else if let Ok(file) = file_service.get_file_by_path(&path).await {
+        // Path is a file
+        let context = propfind_context(
+            &state,
+            &user,
+            &propfind_request,
+            &path,
+            resources_of([], [&file]),
+            &[],
+        )
+        .await;
         let mut response_body = Vec::new();
-        WebDavAdapter::generate_propfind_response(
+        WebDavAdapter::generate_propfind_response_for_file(
             &mut response_body,
-            Some(&root_folder),
-            &files,
-            &subfolders,
+            &file,
             &propfind_request,
             &depth,
             &base_href,
+            &context,
         )
         .map_err(|e| {
             AppError::internal_error(format!("Failed to generate PROPFIND response: {}", e))
         })?;
 
-        Ok(Response::builder()
+        return Ok(Response::builder()
             .status(StatusCode::MULTI_STATUS)
             .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
             .body(Body::from(response_body))
-            .unwrap())
+            .unwrap());
     } else {
-        // Check if path is a folder
-        let folder_result = folder_service.get_folder_by_path(&path).await;
-
-        if let Ok(folder) = folder_result {
-            // Path is a folder
-            let files = if depth != "0" {
-                file_service
-                    .list_files(Some(&folder.id))
-                    .await
-                    .map_err(|e| AppError::internal_error(format!("Failed to get files: {}", e)))?
+        // Path does not exist
+        return Err(AppError::not_found(format!("Resource not found: {}", path)));
+    };
+
+    let listing = PropFindListing {
+        state: state.clone(),
+        user,
+        request: propfind_request,
+        path: path.clone(),
+    };
+    let href = WebDavAdapter::member_href(&path, true);
+
+    if let Some(page_size) = page_size {
+        if depth != "1" {
+            return Err(AppError::bad_request(
+                "Paged PROPFIND requires Depth: 1".to_string(),
+            ));
+        }
+        return propfind_page_response(&listing, collection, folder_id, href, page, page_size)
+            .await;
+    }
+
+    let infinite = depth != "0" && depth != "1";
+    if infinite {
+        let limit = state.core.config.webdav.propfind_infinity_limit;
+        if limit == 0 || subtree_exceeds(&state, folder_id.as_deref(), limit).await? {
+            return error_response(StatusCode::FORBIDDEN, "propfind-finite-depth");
+        }
+    }
+
+    let members = (depth != "0").then_some(folder_id);
+    Ok(Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from_stream(propfind_stream(
+            listing, collection, href, members, infinite,
+        )))
+        .unwrap())
+}
+
+/// Resources listed per page when walking a collection for PROPFIND
+const PROPFIND_PAGE_SIZE: usize = 500;
+
+/**
+ * What every page of a PROPFIND response is rendered with.
+ */
+struct PropFindListing {
+    state: Arc<AppState>,
+    user: CurrentUser,
+    request: PropFindRequest,
+    /// The requested path, whose locks are reported
+    path: String,
+}
+
+impl PropFindListing {
+    /**
+     * Renders the responses of a page of resources, with the locks, dead
+     * properties and privileges of only those resources.
+     */
+    async fn render(
+        &self,
+        folders: &[(FolderDto, String)],
+        files: &[(FileDto, String)],
+    ) -> Result<Vec<u8>, AppError> {
+        let resources = resources_of(
+            folders.iter().map(|(folder, _)| folder),
+            files.iter().map(|(file, _)| file),
+        );
+        let collections: Vec<&FolderDto> = folders.iter().map(|(folder, _)| folder).collect();
+        let context = propfind_context(
+            &self.state,
+            &self.user,
+            &self.request,
+            &self.path,
+            resources,
+            &collections,
+        )
+        .await;
+
+        let mut page = Vec::new();
+        WebDavAdapter::generate_propfind_page(&mut page, folders, files, &self.request, &context)
+            .map_err(|e| {
+            AppError::internal_error(format!("Failed to generate PROPFIND response: {}", e))
+        })?;
+        Ok(page)
+    }
+}
+
+/**
+ * Reads a numeric request header, ignoring values that are not numbers.
+ */
+fn header_number(headers: &HeaderMap, name: &HeaderName) -> Option<usize> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/**
+ * Lists a page of the subfolders of a collection; `None` stands for the root
+ * folder. Also returns how many subfolders there are in total.
+ */
+async fn folder_page(
+    state: &AppState,
+    folder_id: Option<&str>,
+    page: usize,
+    page_size: usize,
+) -> Result<(Vec<FolderDto>, usize), AppError> {
+    let pagination = PaginationRequestDto { page, page_size };
+    let folders = state
+        .applications
+        .folder_service
+        .list_folders_paginated(folder_id, &pagination)
+        .await?;
+    Ok((folders.items, folders.pagination.total_items))
+}
+
+/**
+ * Tells whether the subtree below a collection holds more than `limit`
+ * resources, walking it page by page and stopping as soon as it does.
+ */
+async fn subtree_exceeds(
+    state: &AppState,
+    folder_id: Option<&str>,
+    limit: usize,
+) -> Result<bool, AppError> {
+    let mut count = 0;
+    let mut pending = vec![folder_id.map(str::to_string)];
+    while let Some(folder_id) = pending.pop() {
+        let mut page = 0;
+        loop {
+            let (folders, total) =
+                folder_page(state, folder_id.as_deref(), page, PROPFIND_PAGE_SIZE).await?;
+            count += folders.len();
+            pending.extend(folders.into_iter().map(|folder| Some(folder.id)));
+            if count > limit {
+                return Ok(true);
+            }
+            page += 1;
+            if page * PROPFIND_PAGE_SIZE >= total {
+                break;
+            }
+        }
+
+        let mut files = state
+            .applications
+            .file_service
+            .list_files_stream(folder_id.as_deref())
+            .await?;
+        while let Some(file) = files.next().await {
+            file?;
+            count += 1;
+            if count > limit {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/**
+ * Turns an error met while streaming a response into a body error.
+ */
+fn stream_error(e: impl Into<AppError>) -> std::io::Error {
+    std::io::Error::other(e.into().message)
+}
+
+/**
+ * Streams a PROPFIND multistatus: the collection, then its members page by
+ * page and, when `infinite`, the members of every subfolder below it.
+ *
+ * `members` is the ID of the folder whose members are listed (`Some(None)`
+ * for the root), or `None` for Depth 0. Only one page of resources is in
+ * memory at a time, plus the IDs of the subfolders still to visit. An error
+ * after the response has started aborts the body, so clients never take a
+ * truncated listing for a complete one.
+ */
+fn propfind_stream(
+    listing: PropFindListing,
+    collection: FolderDto,
+    href: String,
+    members: Option<Option<String>>,
+    infinite: bool,
+) -> impl futures::Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static {
+    async_stream::try_stream! {
+        let mut start = Vec::new();
+        WebDavAdapter::generate_multistatus_start(&mut start)
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        start.extend(
+            listing
+                .render(&[(collection, href.clone())], &[])
+                .await
+                .map_err(stream_error)?,
+        );
+        yield Bytes::from(start);
+
+        let mut pending: std::collections::VecDeque<_> =
+            members.map(|folder_id| (folder_id, href)).into_iter().collect();
+        while let Some((folder_id, href)) = pending.pop_front() {
+            let mut page = 0;
+            loop {
+                let (folders, total) =
+                    folder_page(&listing.state, folder_id.as_deref(), page, PROPFIND_PAGE_SIZE)
+                        .await
+                        .map_err(stream_error)?;
+                let folders: Vec<_> = folders
+                    .into_iter()
+                    .map(|folder| {
+                        let folder_href = format!("{}{}/", href, folder.name);
+                        (folder, folder_href)
+                    })
+                    .collect();
+                if infinite {
+                    pending.extend(
+                        folders
+                            .iter()
+                            .map(|(folder, href)| (Some(folder.id.clone()), href.clone())),
+                    );
+                }
+                yield Bytes::from(
+                    listing.render(&folders, &[]).await.map_err(stream_error)?,
+                );
+                page += 1;
+                if page * PROPFIND_PAGE_SIZE >= total {
+                    break;
+                }
+            }
+
+            // One directory read serves every page of the folder's files
+            let mut pages = listing
+                .state
+                .applications
+                .file_service
+                .list_files_stream(folder_id.as_deref())
+                .await
+                .map_err(stream_error)?
+                .chunks(PROPFIND_PAGE_SIZE);
+            while let Some(files) = pages.next().await {
+                let files = files
+                    .into_iter()
+                    .map(|file| {
+                        file.map(|file| {
+                            let file_href = format!("{}{}", href, file.name);
+                            (file, file_href)
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(stream_error)?;
+                yield Bytes::from(
+                    listing.render(&[], &files).await.map_err(stream_error)?,
+                );
+            }
+        }
+
+        let mut end = Vec::new();
+        WebDavAdapter::generate_multistatus_end(&mut end)
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        yield Bytes::from(end);
+    }
+}
+
+/**
+ * Answers a paged Depth 1 PROPFIND with one page of the members of a
+ * collection: subfolders first, then files. The collection itself is only
+ * part of the first page.
+ *
+ * The page size is kept within the bounds of the other paginated listings
+ * and echoed in `X-Page-Size`; `X-Next-Page` names the next page while
+ * more members remain.
+ */
+async fn propfind_page_response(
+    listing: &PropFindListing,
+    collection: FolderDto,
+    folder_id: Option<String>,
+    href: String,
+    page: usize,
+    page_size: usize,
+) -> Result<Response<Body>, AppError> {
+    let page_size = PaginationRequestDto { page, page_size }
+        .validate_and_adjust()
+        .page_size;
+    let first = page * page_size;
+
+    let (folders, folder_count) =
+        folder_page(&listing.state, folder_id.as_deref(), page, page_size).await?;
+    let mut folders: Vec<_> = folders
+        .into_iter()
+        .map(|folder| {
+            let folder_href = format!("{}{}/", href, folder.name);
+            (folder, folder_href)
+        })
+        .collect();
+
+    // Files follow the subfolders; one more than fits tells if more remain
+    let room = page_size - folders.len();
+    let mut files = listing
+        .state
+        .applications
+        .file_service
+        .list_files_paginated(
+            folder_id.as_deref(),
+            first.saturating_sub(folder_count),
+            room + 1,
+        )
+        .await?;
+    let has_more = files.len() > room || first + page_size < folder_count;
+    files.truncate(room);
+    let files: Vec<_> = files
+        .into_iter()
+        .map(|file| {
+            let file_href = format!("{}{}", href, file.name);
+            (file, file_href)
+        })
+        .collect();
+
+    if page == 0 {
+        folders.insert(0, (collection, href.clone()));
+    }
+
+    let mut response_body = Vec::new();
+    WebDavAdapter::generate_multistatus_start(&mut response_body)
+        .map_err(|e| AppError::internal_error(format!("Failed to generate response: {}", e)))?;
+    response_body.extend(listing.render(&folders, &files).await?);
+    WebDavAdapter::generate_multistatus_end(&mut response_body)
+        .map_err(|e| AppError::internal_error(format!("Failed to generate response: {}", e)))?;
+
+    let mut response = Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .header(HEADER_PAGE_SIZE, page_size.to_string());
+    if has_more {
+        response = response.header(HEADER_NEXT_PAGE, (page + 1).to_string());
+    }
+    Ok(response.body(Body::from(response_body)).unwrap())
+}
+
+/**
+ * Handles PROPPATCH requests to set or remove resource properties.
+ *
+ * This handler processes WebDAV PROPPATCH requests according to RFC 4918,
+ * modifying properties of files and folders in the specified path.
+ *
+ * @param state The application state containing service dependencies
+ * @param user The authenticated user information
+ * @param path The requested resource path
+ * @param req The HTTP request containing the PROPPATCH XML body
+ * @return XML response with property modification results
+ */
+async fn handle_proppatch(req: Request<Body>) -> Result<Response<Body>, AppError> {
+    // Extract State, Extension, and Path from request
+    let uri = req.uri().clone();
+    let parts = uri.path().split('/').collect::<Vec<&str>>();
+    let path = if parts.len() > 2 {
+        parts[2..].join("/")
+    } else {
+        "".to_string()
+    };
+
+    let state = req
+        .extensions()
+        .get::<Arc<AppState>>()
+        .ok_or_else(|| AppError::internal_error("Missing AppState extension"))?
+        .clone();
+    let user = req
+        .extensions()
+        .get::<CurrentUser>()
+        .ok_or_else(|| AppError::unauthorized("Authentication required"))?
+        .clone();
+
+    let property_service = state
+        .property_service
+        .clone()
+        .ok_or_else(|| AppError::method_not_allowed("Property storage is not available"))?;
+    let resource_id = resource_id(&state, &path)
+        .await
+        .ok_or_else(|| AppError::not_found(format!("Resource not found: {}", path)))?;
+
+    // Changing properties requires the token of any lock on the resource
+    check_privilege(&state, &user, &path, DavPrivilege::WriteProperties).await?;
+    let tokens = evaluate_if_header(&state, req.headers(), &path).await?;
+    check_locks(&state, &path, LockedOperation::Modify, &tokens, &user).await?;
+
+    // Read request body
+    let body_bytes = body::to_bytes(req.into_body(), usize::MAX)
+        .await
+        .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))?;
+
+    // Parse PROPPATCH request
+    let (props_to_set, props_to_remove) = WebDavAdapter::parse_proppatch(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse PROPPATCH request: {}", e)))?;
+
+    // PROPPATCH is atomic: a single protected property fails the whole request
+    let rejected = props_to_set
+        .iter()
+        .map(|prop| &prop.name)
+        .chain(&props_to_remove)
+        .any(WebDavAdapter::is_protected_property);
+
+    if !rejected {
+        let updates = props_to_remove
+            .iter()
+            .map(|prop| PropertyUpdate::Remove {
+                namespace: prop.namespace.clone(),
+                name: prop.name.clone(),
+            })
+            .chain(props_to_set.iter().map(|prop| {
+                PropertyUpdate::Set(DeadProperty::new(
+                    prop.name.namespace.clone(),
+                    prop.name.name.clone(),
+                    prop.value.clone(),
+                ))
+            }))
+            .collect();
+        property_service
+            .patch_properties(&resource_id, updates)
+            .await
+            .map_err(|e| AppError::internal_error(format!("Failed to save properties: {}", e)))?;
+    }
+
+    let results: Vec<_> = props_to_set
+        .iter()
+        .map(|prop| &prop.name)
+        .chain(&props_to_remove)
+        .map(|prop| {
+            let status = if !rejected {
+                PropPatchStatus::Ok
+            } else if WebDavAdapter::is_protected_property(prop) {
+                PropPatchStatus::Forbidden
             } else {
-                vec![]
+                PropPatchStatus::FailedDependency
             };
+            (prop, status)
+        })
+        .collect();
+
+    // Generate response
+    let href = format!("/webdav/{}", path);
+    let mut response_body = Vec::new();
+    WebDavAdapter::generate_proppatch_response(&mut response_body, &href, &results).map_err(
+        |e| AppError::internal_error(format!("Failed to generate PROPPATCH response: {}", e)),
+    )?;
+
+    Ok(Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(response_body))
+        .unwrap())
+}
+
+/**
+ * Handles REPORT requests: sync-collection and the principal reports of
+ * RFC 3744.
+ *
+ * This handler processes WebDAV sync-collection reports according to
+ * RFC 6578. Without a token it lists every member of the collection; with a
+ * token from an earlier report it returns only the members created, changed
+ * or moved since then and a 404 entry for each one that is gone. Either way
+ * the response ends with the token for the next report. The
+ * acl-principal-prop-set and principal-property-search reports are answered
+ * by the principal handler.
+ *
+ * @param state The application state containing service dependencies
+ * @param user The authenticated user information
+ * @param path The requested collection path
+ * @param req The HTTP request containing the REPORT XML body
+ * @return XML response with the changed members and the new sync token
+ */
+async fn handle_report(req: Request<Body>) -> Result<Response<Body>, AppError> {
+    // Clone all necessary data first to avoid borrow issues
+    let uri = req.uri().clone();
+    let path = {
+        let parts = uri.path().split('/').collect::<Vec<&str>>();
+        if parts.len() > 2 {
+            parts[2..].join("/")
+        } else {
+            "".to_string()
+        }
+    };
+
+    // Get the state and user in a way that doesn't keep req borrowed
+    let state = {
+        let state_ref = req
+            .extensions()
+            .get::<Arc<AppState>>()
+            .ok_or_else(|| AppError::internal_error("Missing AppState extension"))?;
+        state_ref.clone()
+    };
+
+    let user = {
+        let user_ref = req
+            .extensions()
+            .get::<CurrentUser>()
+            .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
+        user_ref.clone()
+    };
+
+    let body_bytes = body::to_bytes(req.into_body(), usize::MAX)
+        .await
+        .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))?;
+
+    let acl_report = WebDavAdapter::parse_acl_report(body_bytes.clone().reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse REPORT request: {}", e)))?;
+    if let Some(report) = acl_report {
+        check_privilege(&state, &user, &path, DavPrivilege::Read).await?;
+        return principal_handler::acl_report(&state, &user, &path, report).await;
+    }
+
+    let request = WebDavAdapter::parse_sync_collection(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse REPORT request: {}", e)))?
+        .ok_or_else(|| AppError::forbidden("Unsupported REPORT type"))?;
+    check_privilege(&state, &user, &path, DavPrivilege::Read).await?;
+    let change_log = state
+        .change_log
+        .clone()
+        .ok_or_else(|| AppError::forbidden("Collection synchronization is not enabled"))?;
+
+    // The report only applies to collections
+    let collection = path.trim_matches('/').to_string();
+    let folder_id = if collection.is_empty() {
+        None
+    } else {
+        match state
+            .applications
+            .folder_service
+            .get_folder_by_path(&collection)
+            .await
+        {
+            Ok(folder) => Some(folder.id),
+            Err(_) => {
+                return Err(
+                    match state
+                        .applications
+                        .file_service
+                        .get_file_by_path(&collection)
+                        .await
+                    {
+                        Ok(_) => AppError::forbidden("sync-collection requires a collection"),
+                        Err(_) => AppError::not_found(format!("Resource not found: {}", path)),
+                    },
+                );
+            }
+        }
+    };
+
+    let result = match &request.sync_token {
+        None => {
+            // Take the token first so changes made while listing show up next time
+            let seq = change_log.current_seq(&collection).await;
+            let (folders, files) =
+                collection_members(&state, folder_id.as_deref(), request.infinite).await?;
+            if request
+                .limit
+                .is_some_and(|limit| folders.len() + files.len() > limit)
+            {
+                return error_response(
+                    StatusCode::INSUFFICIENT_STORAGE,
+                    "number-of-matches-within-limits",
+                );
+            }
+            SyncCollectionResult {
+                folders,
+                files,
+                sync_token: format_sync_token(seq),
+                ..Default::default()
+            }
+        }
+        Some(token) => {
+            let changes = match parse_sync_token(token) {
+                Some(since) => change_log
+                    .changes_since(&collection, since)
+                    .await
+                    .ok()
+                    .map(|changes| (since, changes)),
+                None => None,
+            };
+            let Some((since, changes)) = changes else {
+                return error_response(StatusCode::FORBIDDEN, "valid-sync-token");
+            };
+            sync_changes_result(&state, &collection, since, changes, &request).await?
+        }
+    };
+
+    let resources = resources_of(&result.folders, &result.files);
+    let collections: Vec<&FolderDto> = result.folders.iter().collect();
+    let context = propfind_context(
+        &state,
+        &user,
+        &request.properties,
+        &collection,
+        resources,
+        &collections,
+    )
+    .await;
+
+    let mut response_body = Vec::new();
+    WebDavAdapter::generate_sync_collection_response(
+        &mut response_body,
+        &result,
+        &request.properties,
+        &context,
+    )
+    .map_err(|e| AppError::internal_error(format!("Failed to generate REPORT response: {}", e)))?;
+
+    Ok(Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(response_body))
+        .unwrap())
+}
+
+/**
+ * Handles SEARCH requests (RFC 5323) with a `DAV:basicsearch` query.
+ *
+ * The query is turned into search criteria for the search service, which
+ * returns candidates from the scope collection; the full `where` condition,
+ * the user's read privilege, the ordering and the limit are then applied
+ * to those candidates.
+ *
+ * @param state The application state containing service dependencies
+ * @param user The authenticated user information
+ * @param path The requested resource path, against which relative scopes resolve
+ * @param req The HTTP request containing the searchrequest XML body
+ * @return XML response with the properties of the matching resources
+ */
+async fn handle_search(req: Request<Body>) -> Result<Response<Body>, AppError> {
+    // Clone all necessary data first to avoid borrow issues
+    let uri = req.uri().clone();
+    let path = {
+        let parts = uri.path().split('/').collect::<Vec<&str>>();
+        if parts.len() > 2 {
+            parts[2..].join("/")
+        } else {
+            "".to_string()
+        }
+    };
+
+    // Get the state and user in a way that doesn't keep req borrowed
+    let state = {
+        let state_ref = req
+            .extensions()
+            .get::<Arc<AppState>>()
+            .ok_or_else(|| AppError::internal_error("Missing AppState extension"))?;
+        state_ref.clone()
+    };
+
+    let user = {
+        let user_ref = req
+            .extensions()
+            .get::<CurrentUser>()
+            .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
+        user_ref.clone()
+    };
+
+    let body_bytes = body::to_bytes(req.into_body(), usize::MAX)
+        .await
+        .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))?;
+    let search = WebDavAdapter::parse_search(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse SEARCH request: {}", e)))?;
+    let search_service = state
+        .applications
+        .search_service
+        .clone()
+        .ok_or_else(|| AppError::forbidden("Search is not enabled"))?;
+
+    // Absolute scopes name a WebDAV URL; relative ones start at the request URI
+    let scope = if search.scope.starts_with('/') || search.scope.contains("://") {
+        href_to_path(&search.scope)
+    } else {
+        format!("{}/{}", path.trim_matches('/'), search.scope)
+            .trim_matches('/')
+            .to_string()
+    };
+    check_privilege(&state, &user, &scope, DavPrivilege::Read).await?;
+    let folder_id = if scope.is_empty() {
+        None
+    } else {
+        let folder = state
+            .applications
+            .folder_service
+            .get_folder_by_path(&scope)
+            .await
+            .map_err(|_| AppError::not_found(format!("Search scope not found: {}", scope)))?;
+        Some(folder.id)
+    };
+
+    let mut hits = Vec::new();
+    if search.depth != "0" {
+        let results = search_service
+            .search(search.search_criteria(folder_id), Some(&user.username))
+            .await?;
+        hits.extend(results.folders.into_iter().map(SearchHit::Folder));
+        hits.extend(results.files.into_iter().map(SearchHit::File));
+    }
+    hits.retain(|hit| {
+        search
+            .condition
+            .as_ref()
+            .is_none_or(|condition| condition.matches(&hit.resource()))
+    });
+    if let Some(acl) = &state.acl_service {
+        let is_admin = user.role == "admin";
+        let mut readable = Vec::with_capacity(hits.len());
+        for hit in hits {
+            if acl
+                .privileges(hit.path(), &user.username, is_admin)
+                .await
+                .contains(DavPrivilege::Read)
+            {
+                readable.push(hit);
+            }
+        }
+        hits = readable;
+    }
+    hits.sort_by(|a, b| compare_resources(&a.resource(), &b.resource(), &search.order));
+    if let Some(limit) = search.limit {
+        hits.truncate(limit);
+    }
 
-            let subfolders = if depth != "0" {
-                folder_service
-                    .list_folders(Some(&folder.id))
-                    .await
-                    .map_err(|e| {
-                        AppError::internal_error(format!("Failed to get subfolders: {}", e))
-                    })?
-            } else {
-                vec![]
-            };
+    let resources = hits
+        .iter()
+        .map(|hit| (hit.id().to_string(), hit.path().to_string()))
+        .collect();
+    let collections: Vec<&FolderDto> = hits
+        .iter()
+        .filter_map(|hit| match hit {
+            SearchHit::Folder(folder) => Some(folder),
+            SearchHit::File(_) => None,
+        })
+        .collect();
+    let context = propfind_context(
+        &state,
+        &user,
+        &search.select,
+        &scope,
+        resources,
+        &collections,
+    )
+    .await;
 
-            // Generate response
-            let mut response_body = Vec::new();
-            WebDavAdapter::generate_propfind_response(
-                &mut response_body,
-                Some(&folder),
-                &files,
-                &subfolders,
-                &propfind_request,
-                &depth,
-                &base_href,
-            )
-            .map_err(|e| {
-                AppError::internal_error(format!("Failed to generate PROPFIND response: {}", e))
-            })?;
+    let mut response_body = Vec::new();
+    WebDavAdapter::generate_search_response(&mut response_body, &hits, &search.select, &context)
+        .map_err(|e| {
+            AppError::internal_error(format!("Failed to generate SEARCH response: {}", e))
+        })?;
 
-            Ok(Response::builder()
-                .status(StatusCode::MULTI_STATUS)
-                .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
-                .body(Body::from(response_body))
-                .unwrap())
-        } else {
-            // Check if path is a file
-            let file_result = file_service.get_file_by_path(&path).await;
-
-            if let Ok(file) = file_result {
-                // Path is a file
-                let mut response_body = Vec::new();
-                WebDavAdapter::generate_propfind_response_for_file(
-                    &mut response_body,
-                    &file,
-                    &propfind_request,
-                    &depth,
-                    &base_href,
-                )
-                .map_err(|e| {
-                    AppError::internal_error(format!("Failed to generate PROPFIND response: {}", e))
-                })?;
-
-                Ok(Response::builder()
-                    .status(StatusCode::MULTI_STATUS)
-                    .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
-                    .body(Body::from(response_body))
-                    .unwrap())
-            } else {
-                // Path does not exist
-                Err(AppError::not_found(format!("Resource not found: {}", path)))
-            }
+    Ok(Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(response_body))
+        .unwrap())
+}
+
+/**
+ * Lists the members of a collection, or of the whole subtree when
+ * `infinite` is set; `None` stands for the root folder.
+ */
+async fn collection_members(
+    state: &AppState,
+    folder_id: Option<&str>,
+    infinite: bool,
+) -> Result<(Vec<FolderDto>, Vec<FileDto>), AppError> {
+    let mut folders = Vec::new();
+    let mut files = Vec::new();
+    let mut pending = vec![folder_id.map(str::to_string)];
+    while let Some(id) = pending.pop() {
+        files.extend(
+            state
+                .applications
+                .file_service
+                .list_files(id.as_deref())
+                .await
+                .map_err(|e| AppError::internal_error(format!("Failed to get files: {}", e)))?,
+        );
+        let children = state
+            .applications
+            .folder_service
+            .list_folders(id.as_deref())
+            .await
+            .map_err(|e| AppError::internal_error(format!("Failed to get subfolders: {}", e)))?;
+        if infinite {
+            pending.extend(children.iter().map(|folder| Some(folder.id.clone())));
         }
+        folders.extend(children);
     }
+    Ok((folders, files))
 }
 
 /**
- * Handles PROPPATCH requests to set or remove resource properties.
- *
- * This handler processes WebDAV PROPPATCH requests according to RFC 4918,
- * modifying properties of files and folders in the specified path.
+ * Turns the changes logged since a client's token into the members to report.
  *
- * @param state The application state containing service dependencies
- * @param user The authenticated user information
- * @param path The requested resource path
- * @param req The HTTP request containing the PROPPATCH XML body
- * @return XML response with property modification results
+ * Changes are collapsed per resource: a member that still exists in the
+ * collection is reported with its current properties, and every location it
+ * left or was deleted from gets a 404 entry. For an infinite sync, a folder
+ * that moved brings its whole subtree along under the new hrefs. When the
+ * client set a limit, only that many resources are reported and the token
+ * points just past the last change included.
  */
-async fn handle_proppatch(req: Request<Body>) -> Result<Response<Body>, AppError> {
-    // Extract State, Extension, and Path from request
-    let uri = req.uri().clone();
-    let parts = uri.path().split('/').collect::<Vec<&str>>();
-    let path = if parts.len() > 2 {
-        parts[2..].join("/")
-    } else {
-        "".to_string()
+async fn sync_changes_result(
+    state: &AppState,
+    collection: &str,
+    since: u64,
+    changes: Vec<SyncChange>,
+    request: &SyncCollectionRequest,
+) -> Result<SyncCollectionResult, AppError> {
+    let in_scope = |path: &str| {
+        if request.infinite {
+            is_inside(path, collection)
+        } else {
+            is_direct_member(path, collection)
+        }
     };
 
-    let _state = req
-        .extensions()
-        .get::<Arc<AppState>>()
-        .ok_or_else(|| AppError::internal_error("Missing AppState extension"))?;
-    let _user = req
-        .extensions()
-        .get::<CurrentUser>()
-        .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
-
-    // Read request body
-    let body_bytes = body::to_bytes(req.into_body(), usize::MAX)
-        .await
-        .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))?;
+    // Latest change of each resource and every path it had, in log order
+    let mut order: Vec<String> = Vec::new();
+    let mut latest: HashMap<String, (SyncChange, Vec<String>)> = HashMap::new();
+    let mut last_seq = since;
+    let mut truncated = false;
+    for change in changes {
+        if !latest.contains_key(&change.resource_id) {
+            if request.limit.is_some_and(|limit| order.len() >= limit) {
+                truncated = true;
+                break;
+            }
+            order.push(change.resource_id.clone());
+        }
+        last_seq = change.seq;
+        let (latest_change, paths) = latest
+            .entry(change.resource_id.clone())
+            .or_insert_with(|| (change.clone(), Vec::new()));
+        paths.extend(change.previous_path.iter().cloned());
+        paths.push(change.path.clone());
+        *latest_change = change;
+    }
 
-    // Parse PROPPATCH request
-    let (props_to_set, props_to_remove) = WebDavAdapter::parse_proppatch(body_bytes.reader())
-        .map_err(|e| AppError::bad_request(format!("Failed to parse PROPPATCH request: {}", e)))?;
+    let mut result = SyncCollectionResult {
+        sync_token: format_sync_token(last_seq),
+        truncated: truncated.then(|| WebDavAdapter::member_href(collection, true)),
+        ..Default::default()
+    };
 
-    // For now, we don't actually persist custom properties, but we respond as if we did
-    // In a full implementation, we would store these properties in a database
+    for id in order {
+        let Some((change, paths)) = latest.remove(&id) else {
+            continue;
+        };
 
-    // Generate response - we'll pretend all operations succeeded
-    let mut results = Vec::new();
+        let mut current_path = None;
+        if change.kind != ChangeKind::Deleted {
+            if change.is_folder {
+                if let Ok(folder) = state.applications.folder_service.get_folder(&id).await {
+                    let path = folder.path.trim_matches('/').to_string();
+                    if in_scope(&path) {
+                        // Members of a moved folder are reported under their new hrefs
+                        let moved = paths.iter().any(|previous| previous != &path);
+                        if request.infinite && moved {
+                            let (folders, files) =
+                                collection_members(state, Some(&folder.id), true).await?;
+                            result.folders.extend(folders);
+                            result.files.extend(files);
+                        }
+                        result.folders.push(folder);
+                    }
+                    current_path = Some(path);
+                }
+            } else if let Ok(file) = state.applications.file_service.get_file(&id).await {
+                current_path = Some(file.path.trim_matches('/').to_string());
+                if in_scope(&file.path) {
+                    result.files.push(file);
+                }
+            }
+        }
 
-    // For each property to set, indicate success
-    for prop in &props_to_set {
-        results.push((&prop.name, true));
+        for path in paths {
+            if current_path.as_deref() == Some(path.as_str()) || !in_scope(&path) {
+                continue;
+            }
+            let href = WebDavAdapter::member_href(&path, change.is_folder);
+            if !result.removed.contains(&href) {
+                result.removed.push(href);
+            }
+        }
     }
 
-    // For each property to remove, indicate success
-    for prop in &props_to_remove {
-        results.push((prop, true));
-    }
+    Ok(result)
+}
 
-    // Generate response
-    let href = format!("/webdav/{}", path);
+/**
+ * Builds an error response whose `DAV:error` body names the failed
+ * precondition, as sync-collection clients expect.
+ */
+fn error_response(status: StatusCode, condition: &str) -> Result<Response<Body>, AppError> {
     let mut response_body = Vec::new();
-    WebDavAdapter::generate_proppatch_response(&mut response_body, &href, &results).map_err(
-        |e| AppError::internal_error(format!("Failed to generate PROPPATCH response: {}", e)),
-    )?;
+    WebDavAdapter::generate_error_response(&mut response_body, condition)
+        .map_err(|e| AppError::internal_error(format!("Failed to generate error: {}", e)))?;
 
     Ok(Response::builder()
-        .status(StatusCode::MULTI_STATUS)
+        .status(status)
         .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
         .body(Body::from(response_body))
         .unwrap())
@@ -371,7 +1694,8 @@ async fn handle_proppatch(req: Request<Body>) -> Result<Response<Body>, AppError
 /**
  * Handles GET requests to retrieve file contents.
  *
- * This handler retrieves the contents of a file at the specified path.
+ * This handler retrieves the contents of a file at the specified path, supporting
+ * byte ranges and conditional requests so clients can resume and seek.
  *
  * @param state The application state containing service dependencies
  * @param user The authenticated user information
@@ -392,7 +1716,7 @@ async fn handle_get(req: Request<Body>) -> Result<Response<Body>, AppError> {
         .extensions()
         .get::<Arc<AppState>>()
         .ok_or_else(|| AppError::internal_error("Missing AppState extension"))?;
-    let _user = req
+    let user = req
         .extensions()
         .get::<CurrentUser>()
         .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
@@ -405,6 +1729,7 @@ async fn handle_get(req: Request<Body>) -> Result<Response<Body>, AppError> {
     if path.is_empty() || path == "/" {
         return Err(AppError::bad_request("Cannot GET a directory"));
     }
+    check_privilege(state, user, &path, DavPrivilege::Read).await?;
 
     // Get file metadata
     let file = file_service
@@ -412,26 +1737,22 @@ async fn handle_get(req: Request<Body>) -> Result<Response<Body>, AppError> {
         .await
         .map_err(|_e| AppError::not_found(format!("File not found: {}", path)))?;
 
-    // Get file content
-    let content = file_retrieval_service
-        .get_file_content(&file.id)
-        .await
-        .map_err(|e| AppError::internal_error(format!("Failed to get file content: {}", e)))?;
-
-    // Build response
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, file.mime_type)
-        .header(header::CONTENT_LENGTH, content.len())
-        .header(header::ETAG, format!("\"{}\"", file.id))
-        .header(
-            header::LAST_MODIFIED,
-            chrono::DateTime::<Utc>::from_timestamp(file.created_at as i64, 0)
-                .unwrap_or_else(|| Utc::now())
-                .to_rfc2822(),
-        )
-        .body(Body::from(content))
-        .unwrap())
+    // Stream the content honouring Range and conditional headers
+    let resource = FileResource {
+        size: file.size,
+        content_type: file.mime_type.clone(),
+        etag: file_etag(&file.etag),
+        last_modified: file.modified_at,
+        content_disposition: None,
+    };
+    let file_retrieval_service = file_retrieval_service.clone();
+    let file_id = file.id.clone();
+    serve_file(req.headers(), resource, move |start, end| {
+        let service = file_retrieval_service.clone();
+        let file_id = file_id.clone();
+        async move { service.get_file_range_stream(&file_id, start, end).await }
+    })
+    .await
 }
 
 /**
@@ -466,7 +1787,7 @@ async fn handle_put(req: Request<Body>) -> Result<Response<Body>, AppError> {
         state_ref.clone()
     };
 
-    let _user = {
+    let user = {
         let user_ref = req
             .extensions()
             .get::<CurrentUser>()
@@ -482,6 +1803,21 @@ async fn handle_put(req: Request<Body>) -> Result<Response<Body>, AppError> {
         return Err(AppError::bad_request("Cannot PUT to root folder"));
     }
 
+    // Check if file exists
+    let existing_file = file_service.get_file_by_path(&path).await.ok();
+    let file_exists = existing_file.is_some();
+
+    // Reject the upload before reading the body if the resource changed or is locked
+    check_conditional_write(&state, req.headers(), &path).await?;
+    let tokens = evaluate_if_header(&state, req.headers(), &path).await?;
+    let operation = if file_exists {
+        LockedOperation::Modify
+    } else {
+        LockedOperation::Create
+    };
+    check_write_access(&state, &user, &path, operation).await?;
+    check_locks(&state, &path, operation, &tokens, &user).await?;
+
     // Extract content type before consuming the request
     let content_type = req
         .headers()
@@ -490,31 +1826,39 @@ async fn handle_put(req: Request<Body>) -> Result<Response<Body>, AppError> {
         .unwrap_or("application/octet-stream")
         .to_string();
 
-    // Read request body
-    let body_bytes = {
-        // Convert the request into a body
-        let body = req.into_body();
-
-        // Read request body
-        body::to_bytes(body, usize::MAX)
-            .await
-            .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))?
-    };
+    // Reject uploads that cannot fit before reading them
+    let mut allowance = quota_allowance(&state, &user).await;
+    if let (Some(available), Some(file)) = (allowance.as_mut(), &existing_file) {
+        // Without version history the overwritten content stops counting
+        if !state.core.config.storage.versioning_enabled {
+            *available += file.size;
+        }
+    }
+    let content_length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if let Some(length) = content_length {
+        check_quota(length, allowance)?;
+    }
 
-    // Check if file exists
-    let file_exists = file_service.get_file_by_path(&path).await.is_ok();
+    // Stream the body into storage, stopping once it no longer fits
+    let received = Arc::new(AtomicU64::new(0));
+    let body = body_stream_within(req.into_body(), allowance, received.clone());
 
     if file_exists {
         // Update existing file
-        file_service
-            .update_file(&path, &body_bytes)
-            .await
-            .map_err(|e| AppError::internal_error(format!("Failed to update file: {}", e)))?;
+        if let Err(e) = file_service.update_file_stream(&path, body).await {
+            check_quota(received.load(Ordering::Relaxed), allowance)?;
+            return Err(AppError::internal_error(format!(
+                "Failed to update file: {}",
+                e
+            )));
+        }
+        refresh_storage_usage(&state, &user);
 
-        Ok(Response::builder()
-            .status(StatusCode::NO_CONTENT)
-            .body(Body::empty())
-            .unwrap())
+        Ok(written_response(&state, &path, StatusCode::NO_CONTENT).await)
     } else {
         // Create new file
         // Extract filename from path
@@ -527,16 +1871,32 @@ async fn handle_put(req: Request<Body>) -> Result<Response<Body>, AppError> {
             ""
         };
 
-        file_service
-            .create_file(parent_path, filename, &body_bytes, &content_type)
+        if let Err(e) = file_service
+            .create_file_stream(parent_path, filename, body, &content_type)
             .await
-            .map_err(|e| AppError::internal_error(format!("Failed to create file: {}", e)))?;
+        {
+            check_quota(received.load(Ordering::Relaxed), allowance)?;
+            return Err(AppError::internal_error(format!(
+                "Failed to create file: {}",
+                e
+            )));
+        }
+        refresh_storage_usage(&state, &user);
 
-        Ok(Response::builder()
-            .status(StatusCode::CREATED)
-            .body(Body::empty())
-            .unwrap())
+        Ok(written_response(&state, &path, StatusCode::CREATED).await)
+    }
+}
+
+/**
+ * Builds the response to a successful PUT, carrying the new ETag so clients
+ * can make their next write conditional without another request.
+ */
+async fn written_response(state: &AppState, path: &str, status: StatusCode) -> Response<Body> {
+    let mut builder = Response::builder().status(status);
+    if let Ok(file) = state.applications.file_service.get_file_by_path(path).await {
+        builder = builder.header(header::ETAG, file_etag(&file.etag));
     }
+    builder.body(Body::empty()).unwrap()
 }
 
 /**
@@ -570,7 +1930,7 @@ async fn handle_mkcol(req: Request<Body>) -> Result<Response<Body>, AppError> {
         state_ref.clone()
     };
 
-    let _user = {
+    let user = {
         let user_ref = req
             .extensions()
             .get::<CurrentUser>()
@@ -586,6 +1946,11 @@ async fn handle_mkcol(req: Request<Body>) -> Result<Response<Body>, AppError> {
         return Err(AppError::conflict("Root folder already exists"));
     }
 
+    // Adding a member to a locked collection requires its lock token
+    check_write_access(&state, &user, &path, LockedOperation::Create).await?;
+    let tokens = evaluate_if_header(&state, req.headers(), &path).await?;
+    check_locks(&state, &path, LockedOperation::Create, &tokens, &user).await?;
+
     // Read request body - must be empty for MKCOL
     let body_bytes = {
         // Convert the request into a body
@@ -662,7 +2027,7 @@ async fn handle_delete(req: Request<Body>) -> Result<Response<Body>, AppError> {
         .extensions()
         .get::<Arc<AppState>>()
         .ok_or_else(|| AppError::internal_error("Missing AppState extension"))?;
-    let _user = req
+    let user = req
         .extensions()
         .get::<CurrentUser>()
         .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
@@ -676,15 +2041,29 @@ async fn handle_delete(req: Request<Body>) -> Result<Response<Body>, AppError> {
         return Err(AppError::forbidden("Cannot delete root folder"));
     }
 
+    // Deleting requires the tokens of every lock in the removed subtree
+    check_write_access(state, user, &path, LockedOperation::Remove).await?;
+    check_conditional_write(state, req.headers(), &path).await?;
+    let tokens = evaluate_if_header(state, req.headers(), &path).await?;
+    check_locks(state, &path, LockedOperation::Remove, &tokens, user).await?;
+
     // Check if path is a folder
     let folder_result = folder_service.get_folder_by_path(&path).await;
 
-    if let Ok(folder) = folder_result {
+    let removed_ids = if let Ok(folder) = folder_result {
+        // The folder's contents cannot be listed once it is gone
+        let removed_ids = if state.property_service.is_some() {
+            subtree_ids(state, &folder.id).await
+        } else {
+            Vec::new()
+        };
+
         // Delete folder
         folder_service
             .delete_folder(&folder.id)
             .await
             .map_err(|e| AppError::internal_error(format!("Failed to delete folder: {}", e)))?;
+        removed_ids
     } else {
         // Try to delete file
         let file = file_service
@@ -696,6 +2075,17 @@ async fn handle_delete(req: Request<Body>) -> Result<Response<Body>, AppError> {
             .delete_file(&file.id)
             .await
             .map_err(|e| AppError::internal_error(format!("Failed to delete file: {}", e)))?;
+        vec![file.id]
+    };
+
+    // Locks and dead properties do not outlive the resources they belong to
+    if let Some(locks) = &state.lock_service {
+        locks.release_path(&path).await;
+    }
+    if let Some(properties) = &state.property_service {
+        if let Err(e) = properties.delete_properties(&removed_ids).await {
+            tracing::warn!("Could not delete WebDAV properties under {}: {}", path, e);
+        }
     }
 
     Ok(Response::builder()
@@ -729,7 +2119,7 @@ async fn handle_move(req: Request<Body>) -> Result<Response<Body>, AppError> {
         .extensions()
         .get::<Arc<AppState>>()
         .ok_or_else(|| AppError::internal_error("Missing AppState extension"))?;
-    let _user = req
+    let user = req
         .extensions()
         .get::<CurrentUser>()
         .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
@@ -753,6 +2143,34 @@ async fn handle_move(req: Request<Body>) -> Result<Response<Body>, AppError> {
     let file_service = &state.applications.file_service;
     let folder_service = &state.applications.folder_service;
 
+    // Moving removes the source and adds or replaces the destination
+    check_write_access(state, user, &source_path, LockedOperation::Remove).await?;
+    check_conditional_write(state, req.headers(), &source_path).await?;
+    let tokens = evaluate_if_header(state, req.headers(), &source_path).await?;
+    check_locks(state, &source_path, LockedOperation::Remove, &tokens, user).await?;
+    let destination_exists = file_service
+        .get_file_by_path(destination_path)
+        .await
+        .is_ok()
+        || folder_service
+            .get_folder_by_path(destination_path)
+            .await
+            .is_ok();
+    let destination_operation = if destination_exists {
+        LockedOperation::Remove
+    } else {
+        LockedOperation::Create
+    };
+    check_write_access(state, user, destination_path, destination_operation).await?;
+    check_locks(
+        state,
+        destination_path,
+        destination_operation,
+        &tokens,
+        user,
+    )
+    .await?;
+
     // Check if source is a folder
     let folder_result = folder_service.get_folder_by_path(&source_path).await;
 
@@ -814,6 +2232,11 @@ async fn handle_move(req: Request<Body>) -> Result<Response<Body>, AppError> {
             .map_err(|e| AppError::internal_error(format!("Failed to move file: {}", e)))?;
     }
 
+    // A moved resource does not keep the locks of its old location
+    if let Some(locks) = &state.lock_service {
+        locks.release_path(&source_path).await;
+    }
+
     Ok(Response::builder()
         .status(StatusCode::NO_CONTENT)
         .body(Body::empty())
@@ -845,7 +2268,7 @@ async fn handle_copy(req: Request<Body>) -> Result<Response<Body>, AppError> {
         .extensions()
         .get::<Arc<AppState>>()
         .ok_or_else(|| AppError::internal_error("Missing AppState extension"))?;
-    let _user = req
+    let user = req
         .extensions()
         .get::<CurrentUser>()
         .ok_or_else(|| AppError::unauthorized("Authentication required"))?;
@@ -877,9 +2300,48 @@ async fn handle_copy(req: Request<Body>) -> Result<Response<Body>, AppError> {
     let folder_service = &state.applications.folder_service;
     let file_retrieval_service = &state.applications.file_retrieval_service;
 
+    check_privilege(state, user, &source_path, DavPrivilege::Read).await?;
+
+    // Copying only writes to the destination, so the source may stay locked
+    let tokens = evaluate_if_header(state, req.headers(), &source_path).await?;
+    let destination_operation = if file_service
+        .get_file_by_path(destination_path)
+        .await
+        .is_ok()
+        || folder_service
+            .get_folder_by_path(destination_path)
+            .await
+            .is_ok()
+    {
+        LockedOperation::Remove
+    } else {
+        LockedOperation::Create
+    };
+    check_write_access(state, user, destination_path, destination_operation).await?;
+    check_locks(
+        state,
+        destination_path,
+        destination_operation,
+        &tokens,
+        user,
+    )
+    .await?;
+
     // Check if source is a folder
     let folder_result = folder_service.get_folder_by_path(&source_path).await;
 
+    // A file copy must fit in the quota before anything is written; a folder
+    // copy is checked file by file as it runs, whatever its depth
+    let allowance = quota_allowance(state, user).await;
+    if allowance.is_some() && folder_result.is_err() {
+        let required = file_service
+            .get_file_by_path(&source_path)
+            .await
+            .map(|file| file.size)
+            .unwrap_or(0);
+        check_quota(required, allowance)?;
+    }
+
     if let Ok(folder) = folder_result {
         // Copy folder
         let recursive = depth != "0";
@@ -909,12 +2371,13 @@ async fn handle_copy(req: Request<Body>) -> Result<Response<Body>, AppError> {
             },
         };
 
-        let _new_folder = folder_service
+        let new_folder = folder_service
             .create_folder(create_dto)
             .await
             .map_err(|e| {
                 AppError::internal_error(format!("Failed to create destination folder: {}", e))
             })?;
+        copy_dead_properties(state, &folder.id, &new_folder.id).await;
 
         if recursive {
             // Copy subfolders and files (simplified implementation)
@@ -923,7 +2386,11 @@ async fn handle_copy(req: Request<Body>) -> Result<Response<Body>, AppError> {
                 .await
                 .map_err(|e| AppError::internal_error(format!("Failed to list files: {}", e)))?;
 
+            let mut copied_bytes = 0;
             for file in files {
+                copied_bytes += file.size;
+                check_quota(copied_bytes, allowance)?;
+
                 // Get file content
                 if let Ok(file_source) = file_service
                     .get_file_by_path(&format!("{}/{}", source_path, file.name))
@@ -934,7 +2401,7 @@ async fn handle_copy(req: Request<Body>) -> Result<Response<Body>, AppError> {
                         .await
                     {
                         // Create new file in destination
-                        file_service
+                        let copied = file_service
                             .create_file(&destination_path, &file.name, &content, &file.mime_type)
                             .await
                             .map_err(|e| {
@@ -943,6 +2410,7 @@ async fn handle_copy(req: Request<Body>) -> Result<Response<Body>, AppError> {
                                     file.name, e
                                 ))
                             })?;
+                        copy_dead_properties(state, &file_source.id, &copied.id).await;
                     }
                 }
             }
@@ -972,11 +2440,13 @@ async fn handle_copy(req: Request<Body>) -> Result<Response<Body>, AppError> {
         };
 
         // Create new file in destination
-        file_service
+        let copied = file_service
             .create_file(dest_parent_path, dest_filename, &content, &file.mime_type)
             .await
             .map_err(|e| AppError::internal_error(format!("Failed to copy file: {}", e)))?;
+        copy_dead_properties(state, &file.id, &copied.id).await;
     }
+    refresh_storage_usage(state, user);
 
     Ok(Response::builder()
         .status(StatusCode::NO_CONTENT)
@@ -987,8 +2457,9 @@ async fn handle_copy(req: Request<Body>) -> Result<Response<Body>, AppError> {
 /**
  * Handles LOCK requests to lock resources.
  *
- * This handler processes WebDAV LOCK requests according to RFC 4918,
- * creating a lock on a file or folder.
+ * This handler processes WebDAV LOCK requests according to RFC 4918. A request
+ * with a `lockinfo` body creates a new lock, locking an unmapped URL creates an
+ * empty file, and an empty body refreshes the lock named in the If header.
  *
  * @param state The application state containing service dependencies
  * @param user The authenticated user information
@@ -1009,7 +2480,7 @@ async fn handle_lock(req: Request<Body>) -> Result<Response<Body>, AppError> {
     };
 
     // Get the state and user in a way that doesn't keep req borrowed
-    let _state = {
+    let state = {
         let state_ref = req
             .extensions()
             .get::<Arc<AppState>>()
@@ -1025,25 +2496,28 @@ async fn handle_lock(req: Request<Body>) -> Result<Response<Body>, AppError> {
         user_ref.clone()
     };
 
+    let lock_service = state
+        .lock_service
+        .clone()
+        .ok_or_else(|| AppError::method_not_allowed("Locking is not available"))?;
+
     // Get the headers that we need
-    let depth = req
-        .headers()
-        .get("Depth")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("infinity")
-        .to_string();
+    let depth = match req.headers().get("Depth") {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(LockDepth::parse)
+            .ok_or_else(|| AppError::bad_request("Depth must be 0 or infinity for LOCK"))?,
+        None => LockDepth::Infinity,
+    };
 
     let timeout = req
         .headers()
         .get("Timeout")
         .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
+        .and_then(parse_timeout);
 
-    let if_header_value = req
-        .headers()
-        .get("If")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
+    let tokens = evaluate_if_header(&state, req.headers(), &path).await?;
 
     // Extract the body separately to avoid borrow issues
     let body_bytes = {
@@ -1056,81 +2530,117 @@ async fn handle_lock(req: Request<Body>) -> Result<Response<Body>, AppError> {
             .map_err(|e| AppError::bad_request(format!("Failed to read request body: {}", e)))?
     };
 
-    // Check if this is a lock refresh (If header with a lock token)
-    if let Some(if_header) = if_header_value {
-        // This is a lock refresh request
-        // Extract lock token from If header
-        let token = if_header
-            .trim()
-            .trim_start_matches("(<")
-            .trim_end_matches(">)")
-            .to_string();
-
-        // In a full implementation, we would look up the lock in a database
-        // and refresh its timeout. For now, just respond as if we did.
-
-        // Generate lock token and owner (for a real implementation, we'd store these)
-        let lock_info = LockInfo {
-            token,
-            owner: Some(user.id.clone()),
-            depth: depth.to_string(),
-            timeout,
-            scope: LockScope::Exclusive, // Default to exclusive
-            type_: LockType::Write,      // Default to write
-        };
+    if body_bytes.is_empty() {
+        // An empty body refreshes a lock submitted in the If header
+        let mut refreshed = Err(AppError::precondition_failed(
+            "No lock token for this resource was submitted",
+        ));
+        for token in &tokens {
+            match lock_service
+                .refresh(&path, token, Some(&user.username), timeout)
+                .await
+            {
+                Ok(lock) => {
+                    refreshed = Ok(lock);
+                    break;
+                }
+                Err(e) if e.kind == ErrorKind::AccessDenied => refreshed = Err(e.into()),
+                Err(_) => {}
+            }
+        }
 
-        // Generate response
-        let href = format!("/webdav/{}", path);
-        let mut response_body = Vec::new();
-        WebDavAdapter::generate_lock_response(&mut response_body, &lock_info, &href).map_err(
-            |e| AppError::internal_error(format!("Failed to generate LOCK response: {}", e)),
-        )?;
+        return lock_response(StatusCode::OK, &refreshed?, false);
+    }
 
-        Ok(Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
-            .header(HEADER_LOCK_TOKEN, format!("<{}>", lock_info.token))
-            .body(Body::from(response_body))
-            .unwrap())
-    } else if !body_bytes.is_empty() {
-        // Parse lock request
-        let (scope, type_, owner) = WebDavAdapter::parse_lockinfo(body_bytes.reader())
-            .map_err(|e| AppError::bad_request(format!("Failed to parse LOCK request: {}", e)))?;
-
-        // Generate lock token and owner (for a real implementation, we'd store these)
-        let token = format!("opaquelocktoken:{}", Uuid::new_v4());
-        let lock_info = LockInfo {
-            token,
-            owner: owner.or(Some(user.id.clone())),
-            depth: depth.to_string(),
-            timeout,
-            scope,
-            type_,
-        };
+    // Parse lock request
+    let (scope, _type, owner) = WebDavAdapter::parse_lockinfo(body_bytes.reader())
+        .map_err(|e| AppError::bad_request(format!("Failed to parse LOCK request: {}", e)))?;
 
-        // Generate response
-        let href = format!("/webdav/{}", path);
-        let mut response_body = Vec::new();
-        WebDavAdapter::generate_lock_response(&mut response_body, &lock_info, &href).map_err(
-            |e| AppError::internal_error(format!("Failed to generate LOCK response: {}", e)),
-        )?;
+    let file_service = &state.applications.file_service;
+    let exists = path.trim_matches('/').is_empty()
+        || file_service.get_file_by_path(&path).await.is_ok()
+        || state
+            .applications
+            .folder_service
+            .get_folder_by_path(&path)
+            .await
+            .is_ok();
 
-        Ok(Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
-            .header(HEADER_LOCK_TOKEN, format!("<{}>", lock_info.token))
-            .body(Body::from(response_body))
-            .unwrap())
+    // Locking an unmapped URL adds a member to the parent collection
+    if exists {
+        check_write_access(&state, &user, &path, LockedOperation::Modify).await?;
     } else {
-        Err(AppError::bad_request("Invalid LOCK request"))
+        check_write_access(&state, &user, &path, LockedOperation::Create).await?;
+        check_locks(&state, &path, LockedOperation::Create, &tokens, &user).await?;
+    }
+
+    let lock = lock_service
+        .lock(LockRequest {
+            path: path.clone(),
+            scope,
+            depth,
+            owner,
+            principal: Some(user.username.clone()),
+            timeout_secs: timeout,
+        })
+        .await?;
+
+    if exists {
+        return lock_response(StatusCode::OK, &lock, true);
+    }
+
+    // Create an empty resource for the lock (RFC 4918, section 9.10.4)
+    let trimmed = path.trim_matches('/');
+    let (parent_path, filename) = trimmed.rsplit_once('/').unwrap_or(("", trimmed));
+    if let Err(e) = file_service
+        .create_file(parent_path, filename, &[], "application/octet-stream")
+        .await
+    {
+        let _ = lock_service
+            .unlock(&path, &lock.token, Some(&user.username))
+            .await;
+        return Err(AppError::internal_error(format!(
+            "Failed to create locked resource: {}",
+            e
+        )));
+    }
+
+    lock_response(StatusCode::CREATED, &lock, true)
+}
+
+/**
+ * Builds the response to a LOCK request with the lock discovery XML.
+ *
+ * @param status The response status
+ * @param lock The created or refreshed lock
+ * @param with_token Whether to return the token in the Lock-Token header
+ * @return XML response with lock information
+ */
+fn lock_response(
+    status: StatusCode,
+    lock: &WebDavLock,
+    with_token: bool,
+) -> Result<Response<Body>, AppError> {
+    let mut response_body = Vec::new();
+    WebDavAdapter::generate_lock_response(&mut response_body, lock).map_err(|e| {
+        AppError::internal_error(format!("Failed to generate LOCK response: {}", e))
+    })?;
+
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8");
+    if with_token {
+        response = response.header(HEADER_LOCK_TOKEN, format!("<{}>", lock.token));
     }
+    Ok(response.body(Body::from(response_body)).unwrap())
 }
 
 /**
  * Handles UNLOCK requests to remove locks from resources.
  *
  * This handler processes WebDAV UNLOCK requests according to RFC 4918,
- * removing a lock from a file or folder.
+ * removing a lock from a file or folder. Only the user who created the
+ * lock may remove it.
  *
  * @param state The application state containing service dependencies
  * @param user The authenticated user information
@@ -1141,7 +2651,7 @@ async fn handle_lock(req: Request<Body>) -> Result<Response<Body>, AppError> {
 async fn handle_unlock(req: Request<Body>) -> Result<Response<Body>, AppError> {
     // Clone all necessary data first to avoid borrow issues
     let uri = req.uri().clone();
-    let _path = {
+    let path = {
         let parts = uri.path().split('/').collect::<Vec<&str>>();
         if parts.len() > 2 {
             parts[2..].join("/")
@@ -1151,7 +2661,7 @@ async fn handle_unlock(req: Request<Body>) -> Result<Response<Body>, AppError> {
     };
 
     // Get the state and user in a way that doesn't keep req borrowed
-    let _state = {
+    let state = {
         let state_ref = req
             .extensions()
             .get::<Arc<AppState>>()
@@ -1159,7 +2669,7 @@ async fn handle_unlock(req: Request<Body>) -> Result<Response<Body>, AppError> {
         state_ref.clone()
     };
 
-    let _user = {
+    let user = {
         let user_ref = req
             .extensions()
             .get::<CurrentUser>()
@@ -1167,6 +2677,11 @@ async fn handle_unlock(req: Request<Body>) -> Result<Response<Body>, AppError> {
         user_ref.clone()
     };
 
+    let lock_service = state
+        .lock_service
+        .clone()
+        .ok_or_else(|| AppError::method_not_allowed("Locking is not available"))?;
+
     // Get lock token from Lock-Token header
     let lock_token = req
         .headers()
@@ -1175,17 +2690,430 @@ async fn handle_unlock(req: Request<Body>) -> Result<Response<Body>, AppError> {
         .ok_or_else(|| AppError::bad_request("Lock-Token header required"))?;
 
     // Extract token from header value (format: <token>)
-    let _token = lock_token
+    let token = lock_token
         .trim()
         .trim_start_matches('<')
         .trim_end_matches('>')
         .to_string();
 
-    // In a full implementation, we would look up the lock in a database
-    // and remove it. For now, just respond as if we did.
+    match lock_service
+        .unlock(&path, &token, Some(&user.username))
+        .await
+    {
+        Ok(()) => Ok(Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+            .unwrap()),
+        Err(e) if e.kind == ErrorKind::NotFound => Err(AppError::conflict(
+            "Lock token does not match a lock on this resource",
+        )),
+        Err(e) => Err(e.into()),
+    }
+}
 
-    Ok(Response::builder()
-        .status(StatusCode::NO_CONTENT)
-        .body(Body::empty())
-        .unwrap())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::adapters::webdav_adapter::{
+        AclReport, PrincipalResource, PropFindType, QualifiedName,
+    };
+    use crate::domain::entities::webdav_acl::{resource_acl, PrincipalMatch, PrincipalProperty};
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("Second-600"), Some(600));
+        assert_eq!(parse_timeout("Infinite, Second-4100000000"), Some(u64::MAX));
+        assert_eq!(parse_timeout("Bogus, second-30"), Some(30));
+        assert_eq!(parse_timeout("Second-abc"), None);
+    }
+
+    #[test]
+    fn test_parse_if_header() {
+        let header = WebDavAdapter::parse_if_header(
+            "<http://host/webdav/docs/a.txt> (<opaquelocktoken:1> [\"etag\"]) (Not <DAV:no-lock>)",
+        )
+        .unwrap();
+        assert_eq!(header.lists.len(), 2);
+        assert_eq!(
+            header.lists[0].conditions,
+            vec![
+                (false, IfCondition::Token("opaquelocktoken:1".to_string())),
+                (false, IfCondition::ETag("\"etag\"".to_string())),
+            ]
+        );
+        assert!(header.lists[1].conditions[0].0);
+        assert_eq!(
+            header.submitted_tokens(),
+            vec!["opaquelocktoken:1".to_string()]
+        );
+        assert_eq!(
+            href_to_path(header.lists[1].resource.as_deref().unwrap()),
+            "docs/a.txt"
+        );
+
+        assert!(WebDavAdapter::parse_if_header("(Not)").is_err());
+        assert!(WebDavAdapter::parse_if_header("<a> ").is_err());
+    }
+
+    #[test]
+    fn test_parse_proppatch_resolves_namespaces() {
+        let body = r#"<?xml version="1.0"?>
+            <D:propertyupdate xmlns:D="DAV:" xmlns:Z="urn:schemas-microsoft-com:">
+              <D:set><D:prop>
+                <Z:Win32FileAttributes>00000020</Z:Win32FileAttributes>
+                <D:getetag>"forged"</D:getetag>
+              </D:prop></D:set>
+              <D:remove><D:prop><tag xmlns="http://example.com/ns"/></D:prop></D:remove>
+            </D:propertyupdate>"#;
+        let (set, remove) = WebDavAdapter::parse_proppatch(body.as_bytes()).unwrap();
+
+        assert_eq!(set[0].name.namespace, "urn:schemas-microsoft-com:");
+        assert_eq!(set[0].name.name, "Win32FileAttributes");
+        assert_eq!(set[0].value.as_deref(), Some("00000020"));
+        assert!(!WebDavAdapter::is_protected_property(&set[0].name));
+        assert!(WebDavAdapter::is_protected_property(&set[1].name));
+        assert_eq!(remove[0].namespace, "http://example.com/ns");
+    }
+
+    #[test]
+    fn test_parse_sync_collection() {
+        let body = r#"<?xml version="1.0"?>
+            <sync-collection xmlns="DAV:" xmlns:x="urn:example">
+              <sync-token>urn:oxicloud:sync:12</sync-token>
+              <sync-level>infinite</sync-level>
+              <limit><nresults>50</nresults></limit>
+              <prop><getetag/><x:color/></prop>
+            </sync-collection>"#;
+        let request = WebDavAdapter::parse_sync_collection(body.as_bytes())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(request.sync_token.as_deref(), Some("urn:oxicloud:sync:12"));
+        assert_eq!(
+            parse_sync_token(request.sync_token.as_deref().unwrap()),
+            Some(12)
+        );
+        assert!(request.infinite);
+        assert_eq!(request.limit, Some(50));
+        assert!(request.properties.requests("DAV:", "getetag"));
+        assert!(request.properties.requests("urn:example", "color"));
+
+        // An empty token asks for an initial sync
+        let initial = r#"<D:sync-collection xmlns:D="DAV:"><D:sync-token/>
+            <D:sync-level>1</D:sync-level><D:prop><D:getetag/></D:prop></D:sync-collection>"#;
+        let request = WebDavAdapter::parse_sync_collection(initial.as_bytes())
+            .unwrap()
+            .unwrap();
+        assert!(request.sync_token.is_none());
+        assert!(!request.infinite);
+
+        let other = r#"<C:calendar-query xmlns:C="urn:ietf:params:xml:ns:caldav"/>"#;
+        assert!(WebDavAdapter::parse_sync_collection(other.as_bytes())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_search() {
+        let body = r#"<?xml version="1.0"?>
+            <d:searchrequest xmlns:d="DAV:">
+              <d:basicsearch>
+                <d:select><d:prop><d:displayname/><d:getcontentlength/></d:prop></d:select>
+                <d:from><d:scope><d:href>/webdav/Docs/</d:href><d:depth>infinity</d:depth></d:scope></d:from>
+                <d:where>
+                  <d:and>
+                    <d:like><d:prop><d:displayname/></d:prop><d:literal>%report%.pdf</d:literal></d:like>
+                    <d:gte><d:prop><d:getlastmodified/></d:prop><d:literal>Tue, 14 Nov 2023 22:13:20 GMT</d:literal></d:gte>
+                    <d:not><d:is-collection/></d:not>
+                    <d:or>
+                      <d:lt><d:prop><d:getcontentlength/></d:prop><d:literal>2048</d:literal></d:lt>
+                      <d:eq><d:prop><d:getcontenttype/></d:prop><d:literal>text/plain</d:literal></d:eq>
+                    </d:or>
+                  </d:and>
+                </d:where>
+                <d:orderby>
+                  <d:order><d:prop><d:getcontentlength/></d:prop><d:descending/></d:order>
+                </d:orderby>
+                <d:limit><d:nresults>10</d:nresults></d:limit>
+              </d:basicsearch>
+            </d:searchrequest>"#;
+        let search = WebDavAdapter::parse_search(body.as_bytes()).unwrap();
+
+        assert_eq!(href_to_path(&search.scope), "Docs");
+        assert_eq!(search.depth, "infinity");
+        assert_eq!(search.limit, Some(10));
+        assert!(search.select.requests("DAV:", "getcontentlength"));
+        assert_eq!(search.order.len(), 1);
+        assert!(search.order[0].descending);
+        assert!(WebDavAdapter::is_protected_property(&QualifiedName::new(
+            "DAV:",
+            "supported-query-grammar-set"
+        )));
+
+        let condition = search.condition.as_ref().unwrap();
+        assert_eq!(condition.conjuncts().len(), 4);
+        let file = |name: &str, size| {
+            SearchHit::File(FileDto {
+                name: name.to_string(),
+                mime_type: "application/pdf".to_string(),
+                size,
+                modified_at: 1_700_000_000,
+                ..FileDto::default()
+            })
+        };
+        assert!(condition.matches(&file("Q3 Report.pdf", 1024).resource()));
+        assert!(!condition.matches(&file("Q3 Report.pdf", 4096).resource()));
+        assert!(!condition.matches(&file("notes.pdf", 1024).resource()));
+
+        // Only the conjuncts narrow what is asked of the search service
+        let criteria = search.search_criteria(Some("docs-id".to_string()));
+        assert_eq!(criteria.name_contains.as_deref(), Some("report"));
+        assert_eq!(criteria.modified_after, Some(1_700_000_000));
+        assert_eq!(criteria.max_size, None);
+        assert!(criteria.recursive);
+
+        let unsupported = r#"<d:searchrequest xmlns:d="DAV:"><d:basicsearch>
+            <d:select><d:allprop/></d:select>
+            <d:from><d:scope><d:href>/webdav/</d:href><d:depth>1</d:depth></d:scope></d:from>
+            <d:where><d:eq><d:prop><d:owner/></d:prop><d:literal>x</d:literal></d:eq></d:where>
+            </d:basicsearch></d:searchrequest>"#;
+        assert!(WebDavAdapter::parse_search(unsupported.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_access_control_properties() {
+        let body = r#"<?xml version="1.0"?>
+            <d:propfind xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns">
+              <d:prop>
+                <d:current-user-privilege-set/><d:owner/><d:current-user-principal/>
+                <d:principal-collection-set/><d:acl/><oc:permissions/>
+              </d:prop>
+            </d:propfind>"#;
+        let request = WebDavAdapter::parse_propfind(body.as_bytes()).unwrap();
+        assert!(!request.includes_dead_properties());
+
+        let file = FileDto {
+            id: "file-1".to_string(),
+            name: "plan.md".to_string(),
+            path: "Mi Carpeta - alice/plan.md".to_string(),
+            ..Default::default()
+        };
+        let shared = PrivilegeSet::from_share(true, false);
+        let context = PropFindContext {
+            privileges: HashMap::from([("file-1".to_string(), shared.clone())]),
+            acls: HashMap::from([("file-1".to_string(), resource_acl(Some("alice"), &shared))]),
+            principal: Some(Principal::href_for("bob")),
+            ..Default::default()
+        };
+
+        let mut xml = Vec::new();
+        WebDavAdapter::generate_propfind_response_for_file(
+            &mut xml,
+            &file,
+            &request,
+            "0",
+            "/webdav/Mi%20Carpeta%20-%20alice/plan.md",
+            &context,
+        )
+        .unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+
+        assert!(xml.contains(
+            "<D:current-user-privilege-set><D:privilege><D:read/></D:privilege>\
+             <D:privilege><D:read-current-user-privilege-set/></D:privilege>\
+             </D:current-user-privilege-set>"
+        ));
+        assert!(xml.contains("<D:owner><D:href>/principals/users/alice/</D:href></D:owner>"));
+        assert!(xml.contains("<D:current-user-principal><D:href>/principals/users/bob/</D:href>"));
+        assert!(xml.contains("<D:principal-collection-set><D:href>/principals/users/</D:href>"));
+        assert!(xml.contains("<D:principal><D:authenticated/></D:principal>"));
+        assert!(xml.contains("<D:protected/>"));
+        // Read-only items cannot be changed, renamed or removed by the client
+        assert!(xml.contains(">G</x:permissions>"));
+        assert!(WebDavAdapter::is_protected_property(&QualifiedName::new(
+            "DAV:", "acl"
+        )));
+
+        assert_eq!(
+            parent_path("Mi Carpeta - alice/docs/plan.md"),
+            "Mi Carpeta - alice/docs"
+        );
+        assert_eq!(parent_path("plan.md/"), "");
+    }
+
+    #[test]
+    fn test_parse_acl_reports() {
+        let search = r#"<?xml version="1.0"?>
+            <D:principal-property-search xmlns:D="DAV:" test="allof"
+                xmlns:C="urn:ietf:params:xml:ns:caldav">
+              <D:property-search>
+                <D:prop><D:displayname/></D:prop><D:match>Ali</D:match>
+              </D:property-search>
+              <D:property-search>
+                <D:prop><C:calendar-user-address-set/></D:prop>
+                <D:match>mailto:alice@example.com</D:match>
+              </D:property-search>
+              <D:property-search>
+                <D:prop><D:getetag/></D:prop><D:match>x</D:match>
+              </D:property-search>
+              <D:prop><D:displayname/></D:prop>
+            </D:principal-property-search>"#;
+        let Some(AclReport::PrincipalPropertySearch {
+            criteria,
+            all_of,
+            properties,
+        }) = WebDavAdapter::parse_acl_report(search.as_bytes()).unwrap()
+        else {
+            panic!("expected a principal-property-search");
+        };
+        assert!(all_of);
+        assert_eq!(
+            criteria,
+            vec![
+                PrincipalMatch {
+                    property: PrincipalProperty::DisplayName,
+                    text: "Ali".to_string(),
+                },
+                PrincipalMatch {
+                    property: PrincipalProperty::Email,
+                    text: "alice@example.com".to_string(),
+                },
+            ]
+        );
+        assert!(properties.requests("DAV:", "displayname"));
+
+        let prop_set = r#"<D:acl-principal-prop-set xmlns:D="DAV:">
+            <D:prop><D:displayname/></D:prop></D:acl-principal-prop-set>"#;
+        assert!(matches!(
+            WebDavAdapter::parse_acl_report(prop_set.as_bytes()).unwrap(),
+            Some(AclReport::PrincipalPropSet(_))
+        ));
+
+        let sync = r#"<D:sync-collection xmlns:D="DAV:"><D:sync-token/></D:sync-collection>"#;
+        assert!(WebDavAdapter::parse_acl_report(sync.as_bytes())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_principal_response() {
+        let body = r#"<D:propfind xmlns:D="DAV:"><D:prop>
+            <D:resourcetype/><D:displayname/><D:alternate-URI-set/><D:getetag/>
+            </D:prop></D:propfind>"#;
+        let request = WebDavAdapter::parse_propfind(body.as_bytes()).unwrap();
+        let resources = [
+            PrincipalResource::Collection {
+                href: "/principals/users/".to_string(),
+                name: "users".to_string(),
+            },
+            PrincipalResource::User(Principal {
+                name: "alice".to_string(),
+                display_name: "Alice Liddell".to_string(),
+                email: Some("alice@example.com".to_string()),
+            }),
+        ];
+
+        let mut xml = Vec::new();
+        WebDavAdapter::generate_principal_response(
+            &mut xml,
+            &resources,
+            &request,
+            "/principals/users/alice/",
+        )
+        .unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+
+        assert!(xml.contains("<D:resourcetype><D:principal/></D:resourcetype>"));
+        assert!(xml.contains("<D:displayname>Alice Liddell</D:displayname>"));
+        assert!(xml.contains(
+            "<D:alternate-URI-set><D:href>mailto:alice@example.com</D:href></D:alternate-URI-set>"
+        ));
+        // Collections have no alternate URIs; nothing has an entity tag
+        assert!(xml.contains(
+            "<D:prop><D:alternate-URI-set/><D:getetag/></D:prop>\
+             <D:status>HTTP/1.1 404 Not Found</D:status>"
+        ));
+        assert!(xml
+            .contains("<D:prop><D:getetag/></D:prop><D:status>HTTP/1.1 404 Not Found</D:status>"));
+    }
+
+    #[tokio::test]
+    async fn test_read_body_within_aborts_chunked_overflow() {
+        let chunked = || {
+            Body::from_stream(futures::stream::iter(vec![
+                Ok::<_, std::io::Error>(Bytes::from_static(b"hello ")),
+                Ok(Bytes::from_static(b"world")),
+            ]))
+        };
+
+        let error = read_body_within(chunked(), Some(8)).await.unwrap_err();
+        assert_eq!(error.status_code, StatusCode::INSUFFICIENT_STORAGE);
+
+        let body = read_body_within(chunked(), Some(11)).await.unwrap();
+        assert_eq!(&body[..], b"hello world");
+        assert!(read_body_within(chunked(), None).await.is_ok());
+        assert!(check_quota(12, Some(11)).is_err());
+
+        // Streamed writes stop at the first chunk past the allowance
+        let received = Arc::new(AtomicU64::new(0));
+        let mut stream = body_stream_within(chunked(), Some(8), received.clone());
+        assert!(stream.next().await.unwrap().is_ok());
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(check_quota(received.load(Ordering::Relaxed), Some(8)).is_err());
+    }
+
+    #[test]
+    fn test_propfind_pages_form_one_multistatus() {
+        let request = PropFindRequest {
+            prop_find_type: PropFindType::PropName,
+        };
+        let folder = FolderDto {
+            id: "folder-1".to_string(),
+            name: "Docs".to_string(),
+            path: "Docs".to_string(),
+            ..Default::default()
+        };
+        let file = FileDto {
+            id: "file-1".to_string(),
+            name: "a b.txt".to_string(),
+            path: "Docs/a b.txt".to_string(),
+            ..Default::default()
+        };
+
+        let mut xml = Vec::new();
+        WebDavAdapter::generate_multistatus_start(&mut xml).unwrap();
+        let href = WebDavAdapter::member_href("", true);
+        WebDavAdapter::generate_propfind_page(
+            &mut xml,
+            &[(folder, format!("{}Docs/", href))],
+            &[],
+            &request,
+            &PropFindContext::default(),
+        )
+        .unwrap();
+        WebDavAdapter::generate_propfind_page(
+            &mut xml,
+            &[],
+            &[(file, format!("{}Docs/a%20b.txt", href))],
+            &request,
+            &PropFindContext::default(),
+        )
+        .unwrap();
+        WebDavAdapter::generate_multistatus_end(&mut xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+
+        assert_eq!(href, "/webdav/");
+        assert_eq!(xml.matches("<D:multistatus").count(), 1);
+        assert_eq!(xml.matches("<D:response>").count(), 2);
+        assert!(xml.contains("<D:href>/webdav/Docs/</D:href>"));
+        assert!(xml.contains("<D:href>/webdav/Docs/a%20b.txt</D:href>"));
+        assert!(xml.trim_end().ends_with("</D:multistatus>"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(HEADER_PAGE_SIZE, HeaderValue::from_static(" 50"));
+        headers.insert(HEADER_PAGE, HeaderValue::from_static("next"));
+        assert_eq!(header_number(&headers, &HEADER_PAGE_SIZE), Some(50));
+        assert_eq!(header_number(&headers, &HEADER_PAGE), None);
+    }
 }
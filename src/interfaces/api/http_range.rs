@@ -0,0 +1,526 @@
+//! Peticiones parciales (RFC 7233) y condicionales (RFC 7232) para descargas de archivos.
+//!
+//! Los manejadores describen el recurso con [`FileResource`] y entregan una función
+//! que abre el rango de bytes pedido; [`serve_file`] decide entre 200, 206, 304 y 416.
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, Response, StatusCode},
+};
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures::{Stream, StreamExt};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::common::errors::{AppError, DomainError};
+
+/// Stream de bytes de un rango de archivo
+pub type RangeStream = Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>;
+
+/// Número máximo de rangos atendidos en una sola petición
+const MAX_RANGES: usize = 32;
+
+/// Rango de bytes con ambos extremos incluidos, como en `Content-Range`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn content_range(&self, size: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, size)
+    }
+}
+
+/// Interpretación de la cabecera `Range` frente al tamaño del recurso
+#[derive(Debug, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// Sin rango válido: se sirve el contenido completo
+    Full,
+    /// Rangos satisfacibles, ordenados y sin solapes
+    Partial(Vec<ByteRange>),
+    /// Ningún rango es satisfacible (416)
+    Unsatisfiable,
+}
+
+/// Analiza una cabecera `Range`. Una cabecera mal formada se ignora, como
+/// permite el RFC, y los rangos solapados o contiguos se fusionan.
+pub fn parse_range(value: &str, size: u64) -> RangeRequest {
+    let Some((unit, specs)) = value.split_once('=') else {
+        return RangeRequest::Full;
+    };
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return RangeRequest::Full;
+    }
+
+    let mut ranges = Vec::new();
+    for spec in specs.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let Some((first, last)) = spec.split_once('-') else {
+            return RangeRequest::Full;
+        };
+        let (first, last) = (first.trim(), last.trim());
+
+        let range = if first.is_empty() {
+            // Sufijo: los últimos N bytes
+            let Ok(suffix) = last.parse::<u64>() else {
+                return RangeRequest::Full;
+            };
+            (suffix > 0 && size > 0).then(|| ByteRange {
+                start: size.saturating_sub(suffix),
+                end: size - 1,
+            })
+        } else {
+            let Ok(start) = first.parse::<u64>() else {
+                return RangeRequest::Full;
+            };
+            let end = if last.is_empty() {
+                u64::MAX
+            } else {
+                match last.parse::<u64>() {
+                    Ok(end) if end >= start => end,
+                    _ => return RangeRequest::Full,
+                }
+            };
+            (start < size).then(|| ByteRange {
+                start,
+                end: end.min(size - 1),
+            })
+        };
+
+        if let Some(range) = range {
+            ranges.push(range);
+        }
+    }
+
+    if ranges.is_empty() {
+        return if specs.trim().is_empty() {
+            RangeRequest::Full
+        } else {
+            RangeRequest::Unsatisfiable
+        };
+    }
+
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(previous) if range.start <= previous.end.saturating_add(1) => {
+                previous.end = previous.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+
+    if merged.len() > MAX_RANGES {
+        return RangeRequest::Full;
+    }
+    RangeRequest::Partial(merged)
+}
+
+/// Formatea una marca de tiempo Unix como HTTP-date (IMF-fixdate)
+pub fn http_date(timestamp: u64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+/// Interpreta una HTTP-date en cualquiera de los tres formatos del RFC 7231
+pub fn parse_http_date(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let parsed = DateTime::parse_from_rfc2822(value)
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, "%A, %d-%b-%y %H:%M:%S GMT")
+                .or_else(|_| NaiveDateTime::parse_from_str(value, "%a %b %e %H:%M:%S %Y"))
+                .ok()
+                .map(|date| date.and_utc())
+        })?;
+    u64::try_from(parsed.timestamp()).ok()
+}
+
+/// ETag fuerte de un archivo a partir de la versión de su contenido
+pub fn file_etag(version: &str) -> String {
+    format!("\"{}\"", version)
+}
+
+fn opaque_tag(tag: &str) -> (bool, &str) {
+    match tag.strip_prefix("W/") {
+        Some(tag) => (true, tag),
+        None => (false, tag),
+    }
+}
+
+/// Compara una lista de ETags de una cabecera condicional con el ETag actual.
+/// La comparación débil ignora el prefijo `W/`; la fuerte no admite ETags débiles.
+pub fn etag_matches(list: &str, etag: &str, weak: bool) -> bool {
+    let (current_weak, current) = opaque_tag(etag);
+    list.split(',').map(str::trim).any(|candidate| {
+        if candidate == "*" {
+            return true;
+        }
+        let (candidate_weak, candidate) = opaque_tag(candidate);
+        candidate == current && (weak || (!candidate_weak && !current_weak))
+    })
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Indica si una petición GET condicional puede responderse con 304
+pub fn is_not_modified(headers: &HeaderMap, etag: &str, last_modified: u64) -> bool {
+    // If-None-Match tiene prioridad sobre If-Modified-Since
+    if let Some(list) = header_str(headers, header::IF_NONE_MATCH) {
+        return etag_matches(list, etag, true);
+    }
+    header_str(headers, header::IF_MODIFIED_SINCE)
+        .and_then(parse_http_date)
+        .is_some_and(|since| last_modified <= since)
+}
+
+/// Evalúa `If-Match` e `If-None-Match` antes de modificar, mover o borrar un
+/// recurso (RFC 7232, sección 6). `etag` es el ETag actual, o `None` si el
+/// recurso no existe. Falla con 412 si alguna condición no se cumple.
+pub fn check_write_preconditions(headers: &HeaderMap, etag: Option<&str>) -> Result<(), AppError> {
+    if let Some(list) = header_str(headers, header::IF_MATCH) {
+        if !etag.is_some_and(|etag| etag_matches(list, etag, false)) {
+            return Err(AppError::precondition_failed(
+                "The resource does not match If-Match",
+            ));
+        }
+    }
+    if let Some(list) = header_str(headers, header::IF_NONE_MATCH) {
+        if etag.is_some_and(|etag| etag_matches(list, etag, true)) {
+            return Err(AppError::precondition_failed(
+                "The resource matches If-None-Match",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Indica si la cabecera `If-Range` (si existe) permite servir rangos
+pub fn if_range_allows(headers: &HeaderMap, etag: &str, last_modified: u64) -> bool {
+    let Some(value) = header_str(headers, header::IF_RANGE) else {
+        return true;
+    };
+    let value = value.trim();
+    if value.starts_with('"') || value.starts_with("W/") {
+        etag_matches(value, etag, false)
+    } else {
+        parse_http_date(value) == Some(last_modified)
+    }
+}
+
+/// Descripción de un archivo servido con soporte de rangos
+#[derive(Debug, Clone)]
+pub struct FileResource {
+    pub size: u64,
+    pub content_type: String,
+    pub etag: String,
+    pub last_modified: u64,
+    pub content_disposition: Option<String>,
+}
+
+impl FileResource {
+    fn response_builder(&self, status: StatusCode) -> axum::http::response::Builder {
+        let mut builder = Response::builder()
+            .status(status)
+            .header(header::ACCEPT_RANGES, "bytes")
+            .header(header::ETAG, &self.etag)
+            .header(header::LAST_MODIFIED, http_date(self.last_modified));
+        if let Some(disposition) = &self.content_disposition {
+            if let Ok(value) = HeaderValue::from_str(disposition) {
+                builder = builder.header(header::CONTENT_DISPOSITION, value);
+            }
+        }
+        builder
+    }
+}
+
+fn build(builder: axum::http::response::Builder, body: Body) -> Result<Response<Body>, AppError> {
+    builder
+        .body(body)
+        .map_err(|e| AppError::internal_error(format!("Failed to build response: {}", e)))
+}
+
+/// Responde a un GET sobre un archivo aplicando las cabeceras condicionales y de rango.
+///
+/// `open_range(start, end)` debe devolver el contenido del rango `[start, end)`.
+pub async fn serve_file<F, Fut>(
+    headers: &HeaderMap,
+    resource: FileResource,
+    open_range: F,
+) -> Result<Response<Body>, AppError>
+where
+    F: Fn(u64, u64) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<RangeStream, DomainError>> + Send + 'static,
+{
+    if is_not_modified(headers, &resource.etag, resource.last_modified) {
+        return build(
+            resource.response_builder(StatusCode::NOT_MODIFIED),
+            Body::empty(),
+        );
+    }
+
+    let ranges = match header_str(headers, header::RANGE) {
+        Some(value) if if_range_allows(headers, &resource.etag, resource.last_modified) => {
+            parse_range(value, resource.size)
+        }
+        _ => RangeRequest::Full,
+    };
+
+    match ranges {
+        RangeRequest::Full => {
+            let stream = open_range(0, resource.size).await?;
+            build(
+                resource
+                    .response_builder(StatusCode::OK)
+                    .header(header::CONTENT_TYPE, &resource.content_type)
+                    .header(header::CONTENT_LENGTH, resource.size),
+                Body::from_stream(Box::into_pin(stream)),
+            )
+        }
+        RangeRequest::Unsatisfiable => build(
+            resource
+                .response_builder(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", resource.size)),
+            Body::empty(),
+        ),
+        RangeRequest::Partial(ranges) if ranges.len() == 1 => {
+            let range = ranges[0];
+            let stream = open_range(range.start, range.end + 1).await?;
+            build(
+                resource
+                    .response_builder(StatusCode::PARTIAL_CONTENT)
+                    .header(header::CONTENT_TYPE, &resource.content_type)
+                    .header(header::CONTENT_LENGTH, range.len())
+                    .header(header::CONTENT_RANGE, range.content_range(resource.size)),
+                Body::from_stream(Box::into_pin(stream)),
+            )
+        }
+        RangeRequest::Partial(ranges) => {
+            let boundary = uuid::Uuid::new_v4().simple().to_string();
+            let body = MultipartBody::new(&boundary, &resource, ranges);
+            let content_length = body.content_length();
+            build(
+                resource
+                    .response_builder(StatusCode::PARTIAL_CONTENT)
+                    .header(
+                        header::CONTENT_TYPE,
+                        format!("multipart/byteranges; boundary={}", boundary),
+                    )
+                    .header(header::CONTENT_LENGTH, content_length),
+                Body::from_stream(body.into_stream(Arc::new(open_range))),
+            )
+        }
+    }
+}
+
+/// Cuerpo `multipart/byteranges` con las cabeceras de cada parte ya calculadas
+struct MultipartBody {
+    parts: Vec<(String, ByteRange)>,
+    closing: String,
+}
+
+impl MultipartBody {
+    fn new(boundary: &str, resource: &FileResource, ranges: Vec<ByteRange>) -> Self {
+        let parts = ranges
+            .into_iter()
+            .map(|range| {
+                let head = format!(
+                    "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+                    boundary,
+                    resource.content_type,
+                    range.content_range(resource.size)
+                );
+                (head, range)
+            })
+            .collect();
+        Self {
+            parts,
+            closing: format!("\r\n--{}--\r\n", boundary),
+        }
+    }
+
+    fn content_length(&self) -> u64 {
+        self.parts
+            .iter()
+            .map(|(head, range)| head.len() as u64 + range.len())
+            .sum::<u64>()
+            + self.closing.len() as u64
+    }
+
+    fn into_stream<F, Fut>(
+        self,
+        open_range: Arc<F>,
+    ) -> Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>
+    where
+        F: Fn(u64, u64) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<RangeStream, DomainError>> + Send + 'static,
+    {
+        Box::pin(async_stream::try_stream! {
+            for (head, range) in self.parts {
+                yield Bytes::from(head);
+                let mut stream = Box::into_pin(
+                    open_range(range.start, range.end + 1)
+                        .await
+                        .map_err(std::io::Error::other)?,
+                );
+                while let Some(chunk) = stream.next().await {
+                    yield chunk?;
+                }
+            }
+            yield Bytes::from(self.closing);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u64, end: u64) -> ByteRange {
+        ByteRange { start, end }
+    }
+
+    #[test]
+    fn test_parse_range_forms() {
+        assert_eq!(
+            parse_range("bytes=0-99", 1000),
+            RangeRequest::Partial(vec![range(0, 99)])
+        );
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            RangeRequest::Partial(vec![range(900, 999)])
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            RangeRequest::Partial(vec![range(900, 999)])
+        );
+        assert_eq!(
+            parse_range("bytes=500-2000", 1000),
+            RangeRequest::Partial(vec![range(500, 999)])
+        );
+        // Overlapping and adjacent ranges are coalesced
+        assert_eq!(
+            parse_range("bytes=200-299, 0-99, 100-150, 250-400", 1000),
+            RangeRequest::Partial(vec![range(0, 150), range(200, 400)])
+        );
+    }
+
+    #[test]
+    fn test_parse_range_invalid_and_unsatisfiable() {
+        assert_eq!(parse_range("items=0-1", 1000), RangeRequest::Full);
+        assert_eq!(parse_range("bytes=5-1", 1000), RangeRequest::Full);
+        assert_eq!(parse_range("bytes=abc", 1000), RangeRequest::Full);
+        assert_eq!(
+            parse_range("bytes=1000-", 1000),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(parse_range("bytes=-0", 1000), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), RangeRequest::Unsatisfiable);
+    }
+
+    #[test]
+    fn test_conditional_headers() {
+        let etag = file_etag("6553f1000-2a");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", W/{}", etag)).unwrap(),
+        );
+        assert!(is_not_modified(&headers, &etag, 1_700_000_000));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_str(&http_date(1_700_000_000)).unwrap(),
+        );
+        assert!(is_not_modified(&headers, &etag, 1_700_000_000));
+        assert!(!is_not_modified(&headers, &etag, 1_700_000_001));
+
+        // If-Range needs a strong match or the exact modification date
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_RANGE,
+            HeaderValue::from_str(&format!("W/{}", etag)).unwrap(),
+        );
+        assert!(!if_range_allows(&headers, &etag, 1_700_000_000));
+        headers.insert(header::IF_RANGE, HeaderValue::from_str(&etag).unwrap());
+        assert!(if_range_allows(&headers, &etag, 1_700_000_000));
+        headers.insert(
+            header::IF_RANGE,
+            HeaderValue::from_str(&http_date(1_600_000_000)).unwrap(),
+        );
+        assert!(!if_range_allows(&headers, &etag, 1_700_000_000));
+    }
+
+    #[test]
+    fn test_write_preconditions() {
+        let etag = file_etag("6553f1000-2a");
+        let check = |name, value: &str, current: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, HeaderValue::from_str(value).unwrap());
+            check_write_preconditions(&headers, current).map_err(|e| e.status_code)
+        };
+
+        assert!(check_write_preconditions(&HeaderMap::new(), None).is_ok());
+        assert!(check(header::IF_MATCH, &etag, Some(&etag)).is_ok());
+        assert!(check(header::IF_MATCH, "*", Some(&etag)).is_ok());
+        assert_eq!(
+            check(header::IF_MATCH, "\"stale\"", Some(&etag)),
+            Err(StatusCode::PRECONDITION_FAILED)
+        );
+        // If-Match uses the strong comparison and needs an existing resource
+        assert!(check(header::IF_MATCH, &format!("W/{}", etag), Some(&etag)).is_err());
+        assert!(check(header::IF_MATCH, "*", None).is_err());
+
+        // If-None-Match: * only lets writes create new resources
+        assert!(check(header::IF_NONE_MATCH, "*", None).is_ok());
+        assert_eq!(
+            check(header::IF_NONE_MATCH, "*", Some(&etag)),
+            Err(StatusCode::PRECONDITION_FAILED)
+        );
+        assert!(check(header::IF_NONE_MATCH, "\"stale\"", Some(&etag)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_body_matches_content_length() {
+        let resource = FileResource {
+            size: 26,
+            content_type: "text/plain".to_string(),
+            etag: file_etag("1-1a"),
+            last_modified: 1,
+            content_disposition: None,
+        };
+        let body = MultipartBody::new("sep", &resource, vec![range(0, 2), range(10, 12)]);
+        let expected = body.content_length();
+
+        let data = Bytes::from_static(b"abcdefghijklmnopqrstuvwxyz");
+        let mut stream = body.into_stream(Arc::new(move |start: u64, end: u64| {
+            let chunk = data.slice(start as usize..end as usize);
+            async move { Ok(Box::new(futures::stream::iter(vec![Ok(chunk)])) as RangeStream) }
+        }));
+
+        let mut output = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            output.extend_from_slice(&chunk.unwrap());
+        }
+        let text = String::from_utf8(output.clone()).unwrap();
+
+        assert_eq!(output.len() as u64, expected);
+        assert!(text.contains("Content-Range: bytes 0-2/26\r\n\r\nabc"));
+        assert!(text.contains("Content-Range: bytes 10-12/26\r\n\r\nklm"));
+        assert!(text.ends_with("\r\n--sep--\r\n"));
+    }
+}
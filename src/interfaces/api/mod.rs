@@ -1,4 +1,5 @@
 pub mod handlers;
+pub mod http_range;
 pub mod routes;
 
-pub use routes::create_api_routes;
+pub use routes::{create_api_routes, ApiServices};
@@ -2,7 +2,7 @@ use crate::common::config::AppConfig;
 use crate::common::di::AppState;
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{delete, get, post, put},
     Json, Router,
@@ -18,7 +18,10 @@ use crate::application::ports::favorites_ports::FavoritesUseCase;
 use crate::application::ports::inbound::SearchUseCase;
 use crate::application::ports::recent_ports::RecentItemsUseCase;
 use crate::application::ports::share_ports::ShareUseCase;
+use crate::application::ports::thumbnail_ports::ThumbnailUseCase;
 use crate::application::ports::trash_ports::TrashUseCase;
+use crate::application::ports::version_ports::FileVersionUseCase;
+use crate::application::ports::webdav_lock_ports::WebDavLockUseCase;
 use crate::application::services::batch_operations::BatchOperationService;
 use crate::application::services::file_service::FileService;
 use crate::application::services::folder_service::FolderService;
@@ -31,17 +34,38 @@ use crate::interfaces::api::handlers::i18n_handler::I18nHandler;
 use crate::application::dtos::pagination::PaginationRequestDto;
 use crate::interfaces::api::handlers::batch_handler::{self, BatchHandlerState};
 
+/// Services the API routes are built from. Routes backed by an optional
+/// service are only added when it is configured
+pub struct ApiServices {
+    pub folder_service: Arc<FolderService>,
+    pub file_service: Arc<FileService>,
+    pub i18n_service: Option<Arc<I18nApplicationService>>,
+    pub trash_service: Option<Arc<dyn TrashUseCase>>,
+    pub search_service: Option<Arc<dyn SearchUseCase>>,
+    pub share_service: Option<Arc<dyn ShareUseCase>>,
+    pub favorites_service: Option<Arc<dyn FavoritesUseCase>>,
+    pub recent_service: Option<Arc<dyn RecentItemsUseCase>>,
+    pub version_service: Option<Arc<dyn FileVersionUseCase>>,
+    pub thumbnail_service: Option<Arc<dyn ThumbnailUseCase>>,
+    pub lock_service: Option<Arc<dyn WebDavLockUseCase>>,
+}
+
 /// Creates API routes for the application
-pub fn create_api_routes(
-    folder_service: Arc<FolderService>,
-    file_service: Arc<FileService>,
-    i18n_service: Option<Arc<I18nApplicationService>>,
-    trash_service: Option<Arc<dyn TrashUseCase>>,
-    search_service: Option<Arc<dyn SearchUseCase>>,
-    share_service: Option<Arc<dyn ShareUseCase>>,
-    favorites_service: Option<Arc<dyn FavoritesUseCase>>,
-    recent_service: Option<Arc<dyn RecentItemsUseCase>>,
-) -> Router<crate::common::di::AppState> {
+pub fn create_api_routes(services: ApiServices) -> Router<crate::common::di::AppState> {
+    let ApiServices {
+        folder_service,
+        file_service,
+        i18n_service,
+        trash_service,
+        search_service,
+        share_service,
+        favorites_service,
+        recent_service,
+        version_service,
+        thumbnail_service,
+        lock_service,
+    } = services;
+
     // Create a simplified AppState for the trash view
     // Setup required components for repository construction
     let path_service = Arc::new(crate::domain::services::path_service::PathService::new(
@@ -128,7 +152,13 @@ pub fn create_api_routes(
         favorites_service: favorites_service.clone(), // Include the favorites service for routes
         recent_service: recent_service.clone(), // Include the recent service for routes
         calendar_service: None, // Adding missing field
-        contact_service: None   // Adding missing field
+        scheduling_service: None,
+        reminder_service: None,
+        contact_service: None,  // Adding missing field
+        lock_service: lock_service.clone(),
+        property_service: None,
+        change_log: None,
+        acl_service: None,
     };
     // Inicializar el servicio de operaciones por lotes
     let batch_service = Arc::new(BatchOperationService::default(
@@ -272,7 +302,10 @@ pub fn create_api_routes(
             ),
         )
         .route("/upload", post(FileHandler::upload_file))
-        .route("/{id}", get(FileHandler::download_file))
+        .route(
+            "/{id}",
+            get(FileHandler::download_file).put(FileHandler::update_file),
+        )
         .with_state(file_service.clone());
 
     // Let's create a router for file operations with trash support
@@ -293,6 +326,7 @@ pub fn create_api_routes(
             put(
                 |State(state): State<AppState>,
                  Path(id): Path<String>,
+                 headers: HeaderMap,
                  Json(payload): Json<serde_json::Value>| async move {
                     // Simplified move implementation just to get it working
                     let folder_id = payload
@@ -301,6 +335,11 @@ pub fn create_api_routes(
                         .map(|s| s.to_string());
 
                     let file_service = &state.applications.file_service;
+                    if let Err(err) =
+                        FileHandler::check_preconditions(file_service.as_ref(), &id, &headers).await
+                    {
+                        return err.into_response();
+                    }
                     match file_service.move_file(&id, folder_id).await {
                         Ok(file_dto) => (StatusCode::OK, Json(file_dto)).into_response(),
                         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
@@ -309,8 +348,41 @@ pub fn create_api_routes(
             ),
         );
 
+    // Create routes for file version history if the service is available
+    let versions_router = if let Some(version_service) = version_service.clone() {
+        use crate::interfaces::api::handlers::version_handler;
+
+        Router::new()
+            .route("/{id}/versions", get(version_handler::list_versions))
+            .route(
+                "/{id}/versions/{version_id}",
+                get(version_handler::download_version),
+            )
+            .route(
+                "/{id}/versions/{version_id}/restore",
+                post(version_handler::restore_version),
+            )
+            .with_state(version_service)
+    } else {
+        Router::new()
+    };
+
+    // Create the thumbnail route if thumbnails are enabled
+    let thumbnails_router = if let Some(thumbnail_service) = thumbnail_service {
+        use crate::interfaces::api::handlers::thumbnail_handler;
+
+        Router::new()
+            .route("/{id}/thumbnail", get(thumbnail_handler::get_thumbnail))
+            .with_state(thumbnail_service)
+    } else {
+        Router::new()
+    };
+
     // Merge the routers
-    let files_router = basic_file_router.merge(file_operations_router);
+    let files_router = basic_file_router
+        .merge(file_operations_router)
+        .merge(versions_router)
+        .merge(thumbnails_router);
 
     // Crear rutas para operaciones por lotes
     let batch_router = Router::new()
@@ -323,7 +395,13 @@ pub fn create_api_routes(
         .route("/folders/delete", post(batch_handler::delete_folders_batch))
         .route("/folders/create", post(batch_handler::create_folders_batch))
         .route("/folders/get", post(batch_handler::get_folders_batch))
-        .with_state(batch_handler_state);
+        .with_state(batch_handler_state)
+        // Descarga de una selección como ZIP, que necesita el AppState global
+        .merge(
+            Router::new()
+                .route("/download", post(batch_handler::download_selection_zip))
+                .with_state(app_state.clone()),
+        );
 
     // Create search routes if the service is available
     let search_router = if search_service.is_some() {
@@ -413,6 +491,17 @@ pub fn create_api_routes(
         Router::new()
     };
 
+    // Create the route listing WebDAV locks so the web UI can show "locked by"
+    let locks_router = if let Some(lock_service) = lock_service.clone() {
+        use crate::interfaces::api::handlers::lock_handler;
+
+        Router::new()
+            .route("/", get(lock_handler::list_locks))
+            .with_state(lock_service)
+    } else {
+        Router::new()
+    };
+
     let mut router = Router::new()
         .nest("/folders", folders_router)
         .nest("/files", files_router)
@@ -421,7 +510,8 @@ pub fn create_api_routes(
         .nest("/shares", share_router)
         .nest("/s", public_share_router)
         .nest("/favorites", favorites_router)
-        .nest("/recent", recent_router);
+        .nest("/recent", recent_router)
+        .nest("/locks", locks_router);
 
     // Store the share service in app_state for future use
     if let Some(share_service) = share_service.clone() {
@@ -770,8 +860,11 @@ pub fn create_api_routes(
     // Add WebDAV routes if needed
     let webdav_enabled = true; // In production, you'd read this from a config
     let router = if webdav_enabled {
+        use crate::interfaces::api::handlers::principal_handler;
         use crate::interfaces::api::handlers::webdav_handler;
-        router.merge(webdav_handler::webdav_routes())
+        router
+            .merge(webdav_handler::webdav_routes())
+            .merge(principal_handler::principal_routes())
     } else {
         router
     };
@@ -779,8 +872,11 @@ pub fn create_api_routes(
     // Add CalDAV routes if needed
     let caldav_enabled = true; // In production, you'd read this from a config
     let router = if caldav_enabled {
-        use crate::interfaces::api::handlers::caldav_handler;
-        router.nest("/caldav", caldav_handler::caldav_routes())
+        use crate::interfaces::api::handlers::{caldav_handler, reminder_handler, task_handler};
+        router
+            .merge(caldav_handler::caldav_routes())
+            .merge(task_handler::task_routes())
+            .merge(reminder_handler::reminder_routes())
     } else {
         router
     };
@@ -7,7 +7,7 @@ use axum::{
 };
 use std::sync::Arc;
 
-use crate::common::di::AppState;
+use crate::common::di::{AppState, AuthServices};
 
 // Extensión para almacenar datos del usuario autenticado
 #[derive(Clone, Debug)]
@@ -158,26 +158,41 @@ pub async fn auth_middleware(
     Err(AuthError::TokenNotProvided)
 }
 
-// Middleware simplificado para verificar roles de administrador
-pub async fn require_admin(headers: HeaderMap, mut request: Request, next: Next) -> Response {
-    // Implementación simplificada que verifica si hay un token de admin
-    if let Some(auth_value) = headers.get(header::AUTHORIZATION) {
-        if let Ok(auth_str) = auth_value.to_str() {
-            if auth_str.contains("admin") {
-                // Autorizado como admin
-                let current_user = CurrentUser {
-                    id: "admin-user-id".to_string(),
-                    username: "admin".to_string(),
-                    email: "admin@example.com".to_string(),
-                    role: "admin".to_string(),
-                };
-                request.extensions_mut().insert(current_user);
-                return next.run(request).await;
-            }
+// Middleware que valida el token de acceso JWT y añade el usuario a la request
+pub async fn require_access_token(
+    State(auth): State<AuthServices>,
+    headers: HeaderMap,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(AuthError::TokenNotProvided)?;
+
+    let user = auth
+        .auth_application_service
+        .user_from_access_token(token.trim())
+        .await
+        .map_err(|e| AuthError::InvalidToken(e.to_string()))?;
+
+    request.extensions_mut().insert(CurrentUser {
+        id: user.id,
+        username: user.username,
+        email: user.email,
+        role: user.role,
+    });
+    Ok(next.run(request).await)
+}
+
+// Middleware que exige rol de administrador; va detrás de `require_access_token`
+pub async fn require_admin(request: Request, next: Next) -> Response {
+    match request.extensions().get::<CurrentUser>() {
+        Some(user) if user.role == "admin" => next.run(request).await,
+        Some(_) => {
+            AuthError::AccessDenied("Se requiere rol de administrador".to_string()).into_response()
         }
+        None => AuthError::UserNotFound.into_response(),
     }
-
-    // Acceso denegado
-    let error = AuthError::AccessDenied("Se requiere rol de administrador".to_string());
-    error.into_response()
 }
@@ -1,6 +1,6 @@
 use axum::{
     body::Body,
-    http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode},
+    http::{header, HeaderMap, HeaderValue, Method, Request, Response, StatusCode},
     middleware::Next,
 };
 use bytes::Bytes;
@@ -17,6 +17,8 @@ use std::time::{Duration, SystemTime};
 use tower::{Layer, Service};
 use tracing::{debug, info};
 
+use crate::interfaces::api::http_range::etag_matches;
+
 const MAX_CACHE_ENTRIES: usize = 1000; // Máximo número de entradas en caché
 const DEFAULT_MAX_AGE: u64 = 60; // Tiempo de vida por defecto en segundos
 
@@ -195,6 +197,16 @@ impl HttpCache {
 
         format!("\"{}\"", hash)
     }
+
+    /// ETag de una respuesta: se respeta el que haya puesto el manejador (p. ej.
+    /// la versión del contenido de un archivo) y solo se calcula si falta
+    fn response_etag(&self, headers: &HeaderMap, bytes: &[u8]) -> EntityTag {
+        headers
+            .get(header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .unwrap_or_else(|| self.calculate_etag_for_bytes(bytes))
+    }
 }
 
 /// Middleware de caché HTTP
@@ -224,7 +236,7 @@ where
     if let Some(cache_entry) = cache.get(cache_key) {
         // Comprobar si el cliente ya tiene la versión actualizada
         if let Some(client_etag) = if_none_match {
-            if client_etag == cache_entry.etag {
+            if etag_matches(client_etag, &cache_entry.etag, true) {
                 // El cliente tiene la versión más reciente, enviar 304 Not Modified
                 debug!("Cache hit (304) for key: {}", cache_key);
                 return Ok(create_not_modified_response(&cache_entry));
@@ -272,7 +284,7 @@ where
         .unwrap_or_default();
 
     // Calcular ETag
-    let etag = cache.calculate_etag_for_bytes(&bytes);
+    let etag = cache.response_etag(&parts.headers, &bytes);
 
     // Guardar en caché
     cache.set(
@@ -429,7 +441,10 @@ where
         let entry = cache_clone.get(&cache_key);
 
         match entry {
-            Some(cache_entry) if if_none_match == Some(&cache_entry.etag) => {
+            Some(cache_entry)
+                if if_none_match
+                    .is_some_and(|list| etag_matches(list, &cache_entry.etag, true)) =>
+            {
                 // El cliente tiene la versión correcta, enviar 304
                 debug!("Cache HIT (304): {}", cache_key);
                 let response = create_not_modified_response(&cache_entry);
@@ -478,7 +493,7 @@ where
                     let bytes = axum::body::to_bytes(body, 1024 * 1024 * 10).await?;
 
                     // Calcular ETag
-                    let etag = cache_clone.calculate_etag_for_bytes(&bytes);
+                    let etag = cache_clone.response_etag(&parts.headers, &bytes);
 
                     // Guardar en caché
                     cache_clone.set(
@@ -540,12 +555,9 @@ pub fn start_cache_cleanup_task(cache: HttpCache) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::routing::get;
-    use axum::{Extension, Json, Router};
-    use http::StatusCode;
-    use hyper::{Body, Request, Response};
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Response, StatusCode};
     use serde::{Deserialize, Serialize};
-    use tower::ServiceExt;
 
     #[derive(Debug, Serialize, Deserialize)]
     struct TestData {
@@ -592,7 +604,7 @@ mod tests {
             .unwrap();
 
         let (parts1, body1) = response1.into_parts();
-        let bytes1 = hyper::body::to_bytes(body1).await.unwrap();
+        let bytes1 = to_bytes(body1, usize::MAX).await.unwrap();
 
         let etag1 = cache.calculate_etag_for_bytes(&bytes1);
         cache.set(
@@ -2,4 +2,4 @@ pub mod api;
 pub mod middleware;
 pub mod web;
 
-pub use api::create_api_routes;
+pub use api::{create_api_routes, ApiServices};
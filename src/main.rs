@@ -41,30 +41,74 @@ mod infrastructure;
 /// External interfaces like API endpoints and web controllers
 mod interfaces;
 
+use application::ports::calendar_ports::CalendarUseCase;
+use application::ports::content_index_ports::ContentIndexUseCase;
+use application::ports::outbound::IdMappingPort;
+use application::ports::reminder_ports::ReminderUseCase;
+use application::ports::scheduling_ports::SchedulingUseCase;
+use application::ports::sync_ports::ChangeLogUseCase;
+use application::ports::thumbnail_ports::ThumbnailUseCase;
+use application::ports::webdav_acl_ports::WebDavAclUseCase;
+use application::ports::webdav_lock_ports::WebDavLockUseCase;
+use application::ports::webdav_property_ports::DeadPropertyUseCase;
+use application::services::calendar_service::CalendarService;
+use application::services::chunked_upload_service::ChunkedUploadService;
+use application::services::content_index_service::ContentIndexService;
 use application::services::favorites_service::FavoritesService;
 use application::services::file_service::FileService;
+use application::services::file_upload_service::FileUploadService;
+use application::services::file_version_service::FileVersionService;
 use application::services::folder_service::FolderService;
 use application::services::i18n_application_service::I18nApplicationService;
+use application::services::login_flow_service::LoginFlowService;
+use application::services::reminder_service::{InAppReminderChannel, ReminderService};
+use application::services::resumable_upload_service::ResumableUploadService;
+use application::services::scheduling_service::SchedulingService;
 use application::services::share_service::ShareService;
 use application::services::storage_mediator::FileSystemStorageMediator;
+use application::services::sync_service::ChangeLogService;
+use application::services::thumbnail_service::ThumbnailService;
 use application::services::trash_service::TrashService;
+use application::services::webdav_acl_service::WebDavAclService;
+use application::services::webdav_lock_service::WebDavLockService;
+use application::services::webdav_property_service::DeadPropertyService;
 use common::auth_factory::create_auth_services;
+use common::config::{IdMappingBackend, StorageBackend};
 use common::db::create_database_pool;
 use common::di::AppState;
 use domain::services::path_service::PathService;
+use infrastructure::repositories::calendar_storage_adapter::CalendarStorageAdapter;
+use infrastructure::repositories::chunked_upload_fs_repository::ChunkedUploadFsRepository;
+use infrastructure::repositories::content_index_fs_repository::ContentIndexFsRepository;
 use infrastructure::repositories::file_fs_repository::FileFsRepository;
+use infrastructure::repositories::file_version_fs_repository::FileVersionFsRepository;
 use infrastructure::repositories::folder_fs_repository::FolderFsRepository;
 use infrastructure::repositories::parallel_file_processor::ParallelFileProcessor;
+use infrastructure::repositories::pg::IdMappingPgRepository;
 use infrastructure::repositories::share_fs_repository::ShareFsRepository;
+use infrastructure::repositories::sync_change_fs_repository::SyncChangeFsRepository;
+use infrastructure::repositories::thumbnail_fs_repository::ThumbnailFsRepository;
 use infrastructure::repositories::trash_fs_repository::TrashFsRepository;
+use infrastructure::repositories::upload_session_fs_repository::UploadSessionFsRepository;
+use infrastructure::repositories::webdav_lock_fs_repository::WebDavLockFsRepository;
+use infrastructure::repositories::webdav_property_fs_repository::WebDavPropertyFsRepository;
 use infrastructure::services::buffer_pool::BufferPool;
+use infrastructure::services::chunk_store::ChunkStore;
 use infrastructure::services::compression_service::GzipCompressionService;
+use infrastructure::services::content_reference::ReferenceSigner;
+use infrastructure::services::file_encryption_service::FileEncryptionService;
 use infrastructure::services::file_metadata_cache::FileMetadataCache;
 use infrastructure::services::file_system_i18n_service::FileSystemI18nService;
 use infrastructure::services::id_mapping_optimizer::IdMappingOptimizer;
 use infrastructure::services::id_mapping_service::IdMappingService;
+use infrastructure::services::image_magick_renderer::ImageMagickRenderer;
+use infrastructure::services::object_content_store::ObjectContentStore;
+use infrastructure::services::reminder_channels::{EmailReminderChannel, WebhookReminderChannel};
+use infrastructure::services::s3_object_storage::S3ObjectStorage;
+use infrastructure::services::smtp_mailer::SmtpMailer;
+use infrastructure::services::text_extractor::DocumentTextExtractor;
 use infrastructure::services::trash_cleanup_service::TrashCleanupService;
-use interfaces::{create_api_routes, web::create_web_routes};
+use interfaces::{create_api_routes, web::create_web_routes, ApiServices};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -79,6 +123,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load configuration from environment variables
     let config = common::config::AppConfig::from_env();
 
+    // Deduplicated blocks and S3 objects live outside the encrypted tree, so
+    // enabling encryption with them would leave file contents in clear
+    if config.storage.encryption.enabled && config.storage.backend != StorageBackend::Filesystem {
+        return Err(format!(
+            "At-rest encryption is not supported with the {:?} storage backend; \
+             disable OXICLOUD_ENCRYPTION_ENABLED or set OXICLOUD_STORAGE_BACKEND=filesystem",
+            config.storage.backend
+        )
+        .into());
+    }
+
     // Set up storage directory
     let storage_path = config.storage_path.clone();
     if !storage_path.exists() {
@@ -113,21 +168,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize path service
     let path_service = Arc::new(PathService::new(storage_path.clone()));
 
-    // Initialize ID mapping service for folders
+    // Initialize ID mapping services for folders and files
     let folder_id_mapping_path = storage_path.join("folder_ids.json");
-    let folder_id_mapping_service = Arc::new(
-        IdMappingService::new(folder_id_mapping_path)
-            .await
-            .expect("Failed to initialize folder ID mapping service"),
-    );
-
-    // Initialize ID mapping service for files
     let file_id_mapping_path = storage_path.join("file_ids.json");
-    let file_id_mapping_service = Arc::new(
-        IdMappingService::new(file_id_mapping_path)
-            .await
-            .expect("Failed to initialize file ID mapping service"),
-    );
+    let pg_id_mapping_pool = match (config.storage.id_mapping_backend, db_pool_ref) {
+        (IdMappingBackend::Postgres, Some(pool)) => Some(pool.clone()),
+        (IdMappingBackend::Postgres, None) => {
+            tracing::warn!(
+                "PostgreSQL ID mapping requires a database connection, falling back to JSON files"
+            );
+            None
+        }
+        (IdMappingBackend::Json, _) => None,
+    };
+
+    let (folder_id_mapping_service, file_id_mapping_service): (
+        Arc<dyn IdMappingPort>,
+        Arc<dyn IdMappingPort>,
+    ) = match pg_id_mapping_pool {
+        Some(pool) => {
+            let folder_ids = IdMappingPgRepository::new(pool.clone(), "folder");
+            let file_ids = IdMappingPgRepository::new(pool, "file");
+
+            // One-time import of the JSON maps used before the database backend
+            folder_ids
+                .import_json_map(&folder_id_mapping_path)
+                .await
+                .expect("Failed to import folder ID mappings");
+            file_ids
+                .import_json_map(&file_id_mapping_path)
+                .await
+                .expect("Failed to import file ID mappings");
+
+            tracing::info!("Using PostgreSQL for file and folder ID mappings");
+            (Arc::new(folder_ids), Arc::new(file_ids))
+        }
+        None => (
+            Arc::new(
+                IdMappingService::new(folder_id_mapping_path)
+                    .await
+                    .expect("Failed to initialize folder ID mapping service"),
+            ),
+            Arc::new(
+                IdMappingService::new(file_id_mapping_path)
+                    .await
+                    .expect("Failed to initialize file ID mapping service"),
+            ),
+        ),
+    };
 
     // For backward compatibility, use folder ID service as the base ID mapping service
     let base_id_mapping_service = folder_id_mapping_service.clone();
@@ -135,13 +223,84 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create optimized ID mapping service with batch processing and caching
     let id_mapping_optimizer = Arc::new(IdMappingOptimizer::new(base_id_mapping_service.clone()));
 
+    // Initialize the content-addressed block store when the deduplicated backend is selected
+    let chunk_store = if config.storage.backend == StorageBackend::Deduplicated {
+        let store = Arc::new(
+            ChunkStore::new(
+                storage_path.join(".dedup").join("blocks"),
+                config.storage.dedup_chunk_size,
+            )
+            .await
+            .expect("Failed to initialize deduplicated storage backend"),
+        );
+        tracing::info!(
+            "Deduplicated storage backend enabled ({} byte chunks)",
+            config.storage.dedup_chunk_size
+        );
+
+        // Remove blocks no file refers to every hour, also when trash is disabled
+        let collector = store.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                if let Err(e) = collector.collect_garbage().await {
+                    tracing::error!("Failed to collect orphaned blocks: {}", e);
+                }
+            }
+        });
+        Some(store)
+    } else {
+        None
+    };
+
+    // Keep file contents in an S3-compatible bucket when the object backend is selected
+    let content_store: Option<Arc<dyn application::ports::storage_ports::ContentStoragePort>> =
+        if let Some(ref store) = chunk_store {
+            Some(store.clone())
+        } else if config.storage.backend == StorageBackend::S3 {
+            let s3 = S3ObjectStorage::new(config.storage.s3.clone())
+                .expect("Invalid S3 storage backend configuration");
+            if let Err(e) = s3.check_bucket().await {
+                tracing::error!(
+                    "S3 bucket '{}' at {} is not accessible: {}",
+                    config.storage.s3.bucket,
+                    config.storage.s3.endpoint,
+                    e
+                );
+            }
+            tracing::info!(
+                "S3 storage backend enabled (bucket '{}' at {})",
+                config.storage.s3.bucket,
+                config.storage.s3.endpoint
+            );
+            Some(Arc::new(ObjectContentStore::new(Arc::new(s3))))
+        } else {
+            None
+        };
+
+    // References to stored content are signed with a key kept next to the tree,
+    // so a file uploaded with the reference header is never taken for one
+    let reference_signer = match content_store {
+        Some(_) => Some(Arc::new(
+            ReferenceSigner::load_or_create(&storage_path.join(".content_references.key"))
+                .await
+                .expect("Failed to load the content reference signing key"),
+        )),
+        None => None,
+    };
+
     // Initialize folder repository with all required components
-    let folder_repository = Arc::new(FolderFsRepository::new(
+    let mut folder_repository = FolderFsRepository::new(
         storage_path.clone(),
         Arc::new(FileSystemStorageMediator::new_stub()), // Temporary stub (will be replaced)
         base_id_mapping_service.clone(),
         path_service.clone(),
-    ));
+    );
+    if let (Some(store), Some(signer)) = (&content_store, &reference_signer) {
+        folder_repository = folder_repository.with_content_store(store.clone(), signer.clone());
+    }
+    let folder_repository = Arc::new(folder_repository);
 
     // Initialize storage mediator
     let storage_mediator = Arc::new(FileSystemStorageMediator::new(
@@ -152,12 +311,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Update folder repository with proper storage mediator
     // This replaces the stub we initialized it with
-    let folder_repository = Arc::new(FolderFsRepository::new(
+    let mut folder_repository = FolderFsRepository::new(
         storage_path.clone(),
         storage_mediator.clone(),
         base_id_mapping_service.clone(),
         path_service.clone(),
-    ));
+    );
+    if let (Some(store), Some(signer)) = (&content_store, &reference_signer) {
+        folder_repository = folder_repository.with_content_store(store.clone(), signer.clone());
+    }
+    let folder_repository = Arc::new(folder_repository);
 
     // Start cleanup task for ID mapping optimizer
     IdMappingOptimizer::start_cleanup_task(id_mapping_optimizer.clone());
@@ -165,7 +328,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("ID mapping optimizer initialized with batch processing and caching");
 
     // Initialize the metadata cache
-    let config = common::config::AppConfig::default();
     let metadata_cache = Arc::new(FileMetadataCache::default_with_config(config.clone()));
 
     // Start the periodic cleanup task for cache maintenance
@@ -194,19 +356,149 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         buffer_pool.clone(),
     ));
 
+    // Initialize at-rest encryption of file contents when enabled
+    let encryption_service = if config.storage.encryption.enabled {
+        Some(Arc::new(
+            FileEncryptionService::new(storage_path.clone(), &config.storage.encryption)
+                .await
+                .expect("Failed to initialize file encryption"),
+        ))
+    } else {
+        None
+    };
+
     // Initialize file repository with mediator, ID mapping service, metadata cache, and parallel processor
-    let file_repository = Arc::new(FileFsRepository::new_with_processor(
+    let mut file_repository = FileFsRepository::new_with_processor(
         storage_path.clone(),
-        storage_mediator,
+        storage_mediator.clone(),
         file_id_mapping_service.clone(), // Use the file-specific ID mapping service
         path_service.clone(),
         metadata_cache.clone(), // Clone to keep a reference for later use
         parallel_processor,
+    );
+    if let Some(ref encryption) = encryption_service {
+        file_repository = file_repository.with_encryption(encryption.clone());
+    }
+    if let (Some(store), Some(signer)) = (&content_store, &reference_signer) {
+        file_repository = file_repository.with_content_store(store.clone(), signer.clone());
+    }
+    let file_repository = Arc::new(file_repository);
+
+    // Encrypt existing plaintext content in the background
+    if let Some(ref encryption) = encryption_service {
+        if config.storage.encryption.migrate_existing {
+            let encryption = encryption.clone();
+            tokio::spawn(async move {
+                use application::ports::storage_ports::EncryptionKeyPort;
+                if let Err(e) = encryption.migrate_plaintext_files().await {
+                    tracing::error!("Encryption migration failed: {}", e);
+                }
+            });
+        }
+    }
+
+    // Initialize the change log behind WebDAV incremental sync
+    let change_log = Arc::new(ChangeLogService::new(
+        Arc::new(SyncChangeFsRepository::new(storage_path.as_path())),
+        &config.webdav,
     ));
+    if let Err(e) = change_log.load_persisted_changes().await {
+        tracing::warn!("Could not restore the WebDAV change log: {}", e);
+    }
+    let change_log: Arc<dyn ChangeLogUseCase> = change_log;
 
     // Initialize application services
-    let folder_service = Arc::new(FolderService::new(folder_repository.clone()));
-    let file_service = Arc::new(FileService::new(file_repository.clone()));
+    let folder_service =
+        Arc::new(FolderService::new(folder_repository.clone()).with_change_log(change_log.clone()));
+
+    // Initialize file version history when enabled
+    let version_repository = if config.storage.versioning_enabled {
+        let mut repository = FileVersionFsRepository::new(storage_path.as_path());
+        if let Some(ref encryption) = encryption_service {
+            repository = repository.with_encryption(encryption.clone());
+        }
+        Some(Arc::new(repository))
+    } else {
+        None
+    };
+    let version_service: Option<Arc<dyn application::ports::version_ports::FileVersionUseCase>> =
+        version_repository.as_ref().map(|repo| {
+            Arc::new(FileVersionService::new(
+                file_repository.clone(),
+                repo.clone(),
+                config.storage.version_retention.clone(),
+            )) as Arc<dyn application::ports::version_ports::FileVersionUseCase>
+        });
+
+    // Initialize image thumbnails when enabled and ImageMagick is installed. The
+    // cached thumbnails would be stored in clear, so they stay off when
+    // encryption is enabled
+    let thumbnail_service: Option<Arc<dyn ThumbnailUseCase>> = if !config.storage.thumbnails.enabled
+    {
+        None
+    } else if config.storage.encryption.enabled {
+        tracing::warn!("Thumbnails disabled: not supported with encryption at rest");
+        None
+    } else {
+        let renderer = ImageMagickRenderer::new(
+            config.storage.thumbnails.converter.clone(),
+            std::time::Duration::from_secs(config.storage.thumbnails.timeout_secs),
+        );
+        if renderer.is_available().await {
+            tracing::info!(
+                "Thumbnail generation enabled with {} workers",
+                config.storage.thumbnails.workers
+            );
+            Some(Arc::new(ThumbnailService::new(
+                file_repository.clone(),
+                Arc::new(renderer),
+                Arc::new(ThumbnailFsRepository::new(storage_path.as_path())),
+                &config.storage.thumbnails,
+            )))
+        } else {
+            tracing::warn!(
+                "Thumbnails disabled: ImageMagick converter '{}' not found",
+                config.storage.thumbnails.converter
+            );
+            None
+        }
+    };
+
+    // Initialize the full-text content index. Its terms and extracted text
+    // would be stored in clear, so it stays off when encryption is enabled
+    let content_index: Option<Arc<dyn ContentIndexUseCase>> =
+        if !config.features.enable_search || !config.storage.content_index.enabled {
+            None
+        } else if config.storage.encryption.enabled {
+            tracing::warn!("Content search disabled: not supported with encryption at rest");
+            None
+        } else {
+            let service = ContentIndexService::new(
+                file_repository.clone(),
+                folder_repository.clone(),
+                Arc::new(DocumentTextExtractor::new()),
+                Arc::new(ContentIndexFsRepository::new(storage_path.as_path())),
+                &config.storage.content_index,
+            );
+            if let Err(e) = service.index_existing_files().await {
+                tracing::warn!("Could not check content index: {}", e);
+            }
+            tracing::info!("Content search enabled");
+            Some(Arc::new(service))
+        };
+
+    let mut file_service =
+        FileService::new(file_repository.clone()).with_change_log(change_log.clone());
+    if let Some(ref versions) = version_service {
+        file_service = file_service.with_version_service(versions.clone());
+    }
+    if let Some(ref thumbnails) = thumbnail_service {
+        file_service = file_service.with_thumbnail_service(thumbnails.clone());
+    }
+    if let Some(ref index) = content_index {
+        file_service = file_service.with_content_index(index.clone());
+    }
+    let file_service = Arc::new(file_service);
 
     // Initialize trash service if enabled
     let trash_repository = if config.features.enable_trash {
@@ -693,12 +985,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Create the trash service with properly typed adapters
     let trash_service = if let Some(ref trash_repo) = trash_repository {
-        let service = Arc::new(TrashService::new(
+        let mut service = TrashService::new(
             trash_repo.clone(),
             file_repo_adapter,
             folder_repo_adapter,
             config.storage.trash_retention_days,
-        ));
+        );
+
+        // Release orphaned blocks whenever items are permanently deleted
+        if let Some(ref store) = chunk_store {
+            service = service.with_garbage_collector(store.clone());
+        }
+
+        // Drop version history together with permanently deleted files
+        if let Some(ref versions) = version_repository {
+            service = service.with_version_storage(versions.clone());
+        }
+
+        // Keep trashed files out of content search
+        if let Some(ref index) = content_index {
+            service = service.with_content_index(index.clone());
+        }
+        let service = Arc::new(service);
 
         // Initialize trash cleanup service
         let cleanup_service = TrashCleanupService::new(
@@ -772,11 +1080,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         config: config.clone(),
     };
 
-    // Crear stubs para los repositorios
-    let file_read_stub =
-        Arc::new(infrastructure::repositories::FileFsReadRepository::default_stub());
-    let file_write_stub =
-        Arc::new(infrastructure::repositories::FileFsWriteRepository::default_stub());
+    // Crear stubs para los repositorios (o el backend deduplicado o S3 si está activo)
+    let (file_read_stub, file_write_stub): (
+        Arc<dyn application::ports::storage_ports::FileReadPort>,
+        Arc<dyn application::ports::storage_ports::FileWritePort>,
+    ) = match &content_store {
+        Some(_) => {
+            let adapter = Arc::new(FileStorageAdapter {
+                files: file_repository.clone(),
+                folders: folder_repository.clone(),
+            });
+            (adapter.clone(), adapter)
+        }
+        None => {
+            let mut read_stub = infrastructure::repositories::FileFsReadRepository::default_stub();
+            let mut write_stub =
+                infrastructure::repositories::FileFsWriteRepository::default_stub();
+            if let Some(ref encryption) = encryption_service {
+                read_stub = read_stub.with_encryption(encryption.clone());
+                write_stub = write_stub.with_encryption(encryption.clone());
+            }
+            (Arc::new(read_stub), Arc::new(write_stub))
+        }
+    };
     let storage_mediator_stub =
         Arc::new(application::services::storage_mediator::FileSystemStorageMediator::new_stub());
     let metadata_manager = Arc::new(infrastructure::repositories::FileMetadataManager::default());
@@ -790,15 +1116,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             folder_id_mapping_service.clone(),
             path_service.clone(),
         )),
-        file_repository: Arc::new(FileFsRepository::new(
-            storage_path.clone(),
-            storage_mediator_stub.clone(),
-            file_id_mapping_service.clone(),
-            path_service.clone(),
-            metadata_cache.clone(),
-        )),
-        file_read_repository: file_read_stub,
-        file_write_repository: file_write_stub,
+        file_repository: {
+            let repository = FileFsRepository::new(
+                storage_path.clone(),
+                storage_mediator_stub.clone(),
+                file_id_mapping_service.clone(),
+                path_service.clone(),
+                metadata_cache.clone(),
+            );
+            Arc::new(match encryption_service {
+                Some(ref encryption) => repository.with_encryption(encryption.clone()),
+                None => repository,
+            })
+        },
+        file_read_repository: file_read_stub.clone(),
+        file_write_repository: file_write_stub.clone(),
         i18n_repository: i18n_repository.clone(),
         storage_mediator: storage_mediator_stub,
         metadata_manager,
@@ -814,12 +1146,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create the search service
     let search_service: Option<Arc<dyn application::ports::inbound::SearchUseCase>> = {
         // Create the search service with caching
-        let search_service = Arc::new(application::services::search_service::SearchService::new(
+        let mut search_service = application::services::search_service::SearchService::new(
             file_repository.clone(),
             folder_repository.clone(),
             300,  // Cache TTL in seconds (5 minutes)
             1000, // Maximum cache entries
-        ));
+        );
+        if let Some(ref index) = content_index {
+            search_service = search_service.with_content_index(index.clone());
+        }
+        let search_service = Arc::new(search_service);
 
         tracing::info!("Search service initialized with caching (TTL: 300s, max entries: 1000)");
         Some(search_service)
@@ -873,6 +1209,100 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             None
         };
 
+    // Initialize calendar service (CalDAV) if database is available
+    let calendar_service: Option<Arc<dyn CalendarUseCase>> = if let Some(pool) = db_pool_ref {
+        let calendar_storage = Arc::new(CalendarStorageAdapter::new(
+            Arc::new(infrastructure::repositories::pg::CalendarPgRepository::new(
+                pool.clone(),
+            )),
+            Arc::new(
+                infrastructure::repositories::pg::CalendarEventPgRepository::new(pool.clone()),
+            ),
+            Arc::new(infrastructure::repositories::pg::CalendarTaskPgRepository::new(pool.clone())),
+        ));
+
+        tracing::info!("Calendar service initialized successfully");
+        Some(Arc::new(CalendarService::new(calendar_storage)))
+    } else {
+        tracing::info!("Calendar service is disabled (requires database connection)");
+        None
+    };
+
+    // Initialize CalDAV scheduling between local users on top of the calendars
+    let scheduling_service: Option<Arc<dyn SchedulingUseCase>> =
+        match (db_pool_ref, &calendar_service) {
+            (Some(pool), Some(calendars)) => Some(Arc::new(SchedulingService::new(
+                calendars.clone(),
+                Arc::new(infrastructure::repositories::pg::UserPgRepository::new(
+                    pool.clone(),
+                )),
+                Arc::new(
+                    infrastructure::repositories::pg::ScheduleInboxPgRepository::new(pool.clone()),
+                ),
+            ))),
+            _ => None,
+        };
+
+    // Initialize reminders for the alarms of events and tasks
+    let reminder_service: Option<Arc<dyn ReminderUseCase>> = match (db_pool_ref, &calendar_service)
+    {
+        (Some(pool), Some(calendars)) if config.reminders.enabled => {
+            let storage = Arc::new(infrastructure::repositories::pg::ReminderPgRepository::new(
+                pool.clone(),
+            ));
+            let mut service = ReminderService::new(
+                calendars.clone(),
+                Arc::new(infrastructure::repositories::pg::UserPgRepository::new(
+                    pool.clone(),
+                )),
+                storage.clone(),
+                &config.reminders,
+            )
+            .with_channel(Arc::new(InAppReminderChannel::new(storage)));
+            if let Some(mailer) = SmtpMailer::new(&config.reminders.smtp) {
+                service = service.with_channel(Arc::new(EmailReminderChannel::new(mailer)));
+            } else {
+                tracing::info!("Email reminders are disabled (no SMTP server configured)");
+            }
+            if let Some(url) = config.reminders.webhook_url.clone() {
+                service = service.with_channel(Arc::new(WebhookReminderChannel::new(
+                    url,
+                    config.reminders.webhook_secret.clone(),
+                )));
+            }
+            let service: Arc<dyn ReminderUseCase> = Arc::new(service);
+
+            // Index the alarms changed since the previous round and deliver
+            // the due reminders; the whole index is rebuilt every hour
+            let scheduler = service.clone();
+            let poll_interval = std::time::Duration::from_secs(config.reminders.poll_interval_secs);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(poll_interval);
+                let mut last_full_sync: Option<std::time::Instant> = None;
+                loop {
+                    interval.tick().await;
+                    let full = last_full_sync
+                        .is_none_or(|at| at.elapsed() >= std::time::Duration::from_secs(3600));
+                    match scheduler.sync(full).await {
+                        Ok(_) if full => last_full_sync = Some(std::time::Instant::now()),
+                        Ok(_) => {}
+                        Err(e) => tracing::error!("Failed to index calendar alarms: {}", e),
+                    }
+                    if let Err(e) = scheduler.deliver_due().await {
+                        tracing::error!("Failed to deliver reminders: {}", e);
+                    }
+                }
+            });
+
+            tracing::info!(
+                "Reminders enabled (checked every {} seconds)",
+                config.reminders.poll_interval_secs
+            );
+            Some(service)
+        }
+        _ => None,
+    };
+
     // For now, we'll use a placeholder for the contact service
     // Instead of using the real PostgreSQL repositories, we'll create a dummy implementation
     // This makes the code compile, and we can replace it with the real implementation later
@@ -882,13 +1312,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         folder_service: folder_service.clone(),
         file_service: file_service.clone(),
         file_upload_service: Arc::new(
-            application::services::file_upload_service::FileUploadService::default_stub(),
+            application::services::file_upload_service::FileUploadService::new(
+                file_write_stub.clone(),
+            ),
         ),
         file_retrieval_service: Arc::new(
-            application::services::file_retrieval_service::FileRetrievalService::default_stub(),
+            application::services::file_retrieval_service::FileRetrievalService::new(
+                file_read_stub.clone(),
+            ),
         ),
         file_management_service: Arc::new(
-            application::services::file_management_service::FileManagementService::default_stub(),
+            application::services::file_management_service::FileManagementService::new(
+                file_write_stub.clone(),
+            ),
         ),
         file_use_case_factory: Arc::new(
             application::services::file_use_case_factory::AppFileUseCaseFactory::default_stub(),
@@ -901,9 +1337,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         recent_service: recent_service.clone(),
     };
 
-    // Create the AppState without Arc first
-    let calendar_service_option = None;
+    // Initialize WebDAV locks, restoring those held before a restart
+    let lock_service = Arc::new(WebDavLockService::new(
+        Arc::new(WebDavLockFsRepository::new(storage_path.as_path())),
+        &config.webdav,
+    ));
+    if let Err(e) = lock_service.load_persisted_locks().await {
+        tracing::warn!("Could not restore WebDAV locks: {}", e);
+    }
+    let lock_service: Arc<dyn WebDavLockUseCase> = lock_service;
+
+    // Initialize WebDAV dead properties, stored per resource ID
+    let property_service: Arc<dyn DeadPropertyUseCase> = Arc::new(DeadPropertyService::new(
+        Arc::new(WebDavPropertyFsRepository::new(storage_path.as_path())),
+    ));
+
+    // Initialize WebDAV access control from home folders and shared links
+    let mut acl_service = WebDavAclService::new(
+        application_services.file_service.clone(),
+        application_services.folder_service.clone(),
+    );
+    if let Some(ref shares) = share_service {
+        acl_service = acl_service.with_shares(shares.clone());
+    }
+    if let Some(pool) = db_pool_ref {
+        acl_service = acl_service.with_users(Arc::new(
+            infrastructure::repositories::pg::UserPgRepository::new(pool.clone()),
+        ));
+    }
+    let acl_service: Arc<dyn WebDavAclUseCase> = Arc::new(acl_service);
 
+    // Create the AppState without Arc first
     let mut app_state = AppState {
         core: core_services,
         repositories: repository_services,
@@ -915,12 +1379,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         favorites_service: favorites_service.clone(),
         recent_service: recent_service.clone(),
         storage_usage_service: None,
-        calendar_service: calendar_service_option,
+        calendar_service: calendar_service.clone(),
+        scheduling_service,
+        reminder_service,
         contact_service: contact_service.clone(),
+        lock_service: Some(lock_service.clone()),
+        property_service: Some(property_service),
+        change_log: Some(change_log),
+        acl_service: Some(acl_service),
     };
 
     // Initialize storage usage service
-    let _storage_usage_service = if let Some(pool) = db_pool_ref {
+    let storage_usage_service = if let Some(pool) = db_pool_ref {
         // Create a user repository that implements UserStoragePort
         let user_repository = Arc::new(infrastructure::repositories::pg::UserPgRepository::new(
             pool.clone(),
@@ -928,13 +1398,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // Create storage usage service that uses database for user information
         // and file repository for storage calculation
-        let service = Arc::new(
-            application::services::storage_usage_service::StorageUsageService::new(
-                file_repository.clone(),
-                user_repository,
-            ),
+        let mut service = application::services::storage_usage_service::StorageUsageService::new(
+            file_repository.clone(),
+            user_repository,
         );
 
+        // Archived file versions count towards the user's quota
+        if let Some(ref versions) = version_repository {
+            service = service.with_version_repository(versions.clone());
+        }
+        let service = Arc::new(service);
+
         tracing::info!("Storage usage service initialized successfully");
 
         // Add the service to the app state
@@ -946,20 +1420,188 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         None
     };
 
+    // Adapter exposing the main file repository as read and write ports, so that
+    // finished resumable uploads and WebDAV transfers land in the same storage as
+    // regular uploads
+    struct FileStorageAdapter {
+        files: Arc<dyn application::ports::outbound::FileStoragePort>,
+        folders: Arc<dyn application::ports::outbound::FolderStoragePort>,
+    }
+
+    #[async_trait::async_trait]
+    impl application::ports::storage_ports::FileReadPort for FileStorageAdapter {
+        async fn get_file(
+            &self,
+            id: &str,
+        ) -> Result<domain::entities::file::File, common::errors::DomainError> {
+            self.files.get_file(id).await
+        }
+
+        async fn list_files(
+            &self,
+            folder_id: Option<&str>,
+        ) -> Result<Vec<domain::entities::file::File>, common::errors::DomainError> {
+            self.files.list_files(folder_id).await
+        }
+
+        async fn get_file_content(&self, id: &str) -> Result<Vec<u8>, common::errors::DomainError> {
+            self.files.get_file_content(id).await
+        }
+
+        async fn get_file_stream(
+            &self,
+            id: &str,
+        ) -> Result<
+            Box<dyn futures::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send>,
+            common::errors::DomainError,
+        > {
+            self.files.get_file_stream(id).await
+        }
+
+        async fn get_file_range_stream(
+            &self,
+            id: &str,
+            start: u64,
+            end: u64,
+        ) -> Result<
+            Box<dyn futures::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send>,
+            common::errors::DomainError,
+        > {
+            self.files.get_file_range_stream(id, start, end).await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl application::ports::storage_ports::FileWritePort for FileStorageAdapter {
+        async fn save_file(
+            &self,
+            name: String,
+            folder_id: Option<String>,
+            content_type: String,
+            content: Vec<u8>,
+        ) -> Result<domain::entities::file::File, common::errors::DomainError> {
+            self.files
+                .save_file(name, folder_id, content_type, content)
+                .await
+        }
+
+        async fn save_file_stream(
+            &self,
+            name: String,
+            folder_id: Option<String>,
+            content_type: String,
+            stream: std::pin::Pin<
+                Box<dyn futures::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send>,
+            >,
+        ) -> Result<domain::entities::file::File, common::errors::DomainError> {
+            self.files
+                .save_file_stream(name, folder_id, content_type, stream)
+                .await
+        }
+
+        async fn move_file(
+            &self,
+            file_id: &str,
+            target_folder_id: Option<String>,
+        ) -> Result<domain::entities::file::File, common::errors::DomainError> {
+            self.files.move_file(file_id, target_folder_id).await
+        }
+
+        async fn delete_file(&self, id: &str) -> Result<(), common::errors::DomainError> {
+            self.files.delete_file(id).await
+        }
+
+        async fn get_folder_details(
+            &self,
+            folder_id: &str,
+        ) -> Result<domain::entities::file::File, common::errors::DomainError> {
+            let folder = self.folders.get_folder(folder_id).await?;
+            domain::entities::file::File::new_folder(
+                folder.id().to_string(),
+                folder.name().to_string(),
+                folder.storage_path().clone(),
+                folder.parent_id().map(|s| s.to_string()),
+                folder.created_at(),
+                folder.modified_at(),
+            )
+            .map_err(|e| common::errors::DomainError::internal_error("Folder", e.to_string()))
+        }
+
+        async fn get_folder_path_str(
+            &self,
+            folder_id: &str,
+        ) -> Result<String, common::errors::DomainError> {
+            Ok(self.folders.get_folder_path(folder_id).await?.to_string())
+        }
+    }
+
+    // Initialize resumable (tus) uploads
+    let resumable_upload_service: Option<
+        Arc<dyn application::ports::upload_ports::ResumableUploadUseCase>,
+    > = if config.storage.uploads.enabled {
+        let mut upload_service = FileUploadService::new(Arc::new(FileStorageAdapter {
+            files: file_repository.clone(),
+            folders: folder_repository.clone(),
+        }));
+        if let Some(ref usage) = storage_usage_service {
+            upload_service = upload_service.with_storage_usage_service(usage.clone());
+        }
+        if let Some(ref index) = content_index {
+            upload_service = upload_service.with_content_index(index.clone());
+        }
+        if config.features.enable_user_storage_quotas {
+            if let Some(pool) = db_pool_ref {
+                upload_service = upload_service.with_quota_enforcement(Arc::new(
+                    infrastructure::repositories::pg::UserPgRepository::new(pool.clone()),
+                ));
+            }
+        }
+
+        let service = Arc::new(ResumableUploadService::new(
+            Arc::new(UploadSessionFsRepository::new(storage_path.as_path())),
+            Arc::new(upload_service),
+            &config.storage.uploads,
+        ));
+
+        // Drop abandoned uploads every hour
+        let cleanup_service = service.clone();
+        tokio::spawn(async move {
+            use application::ports::upload_ports::ResumableUploadUseCase;
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                if let Err(e) = cleanup_service.cleanup_expired().await {
+                    tracing::error!("Failed to clean up expired uploads: {}", e);
+                }
+            }
+        });
+
+        tracing::info!(
+            "Resumable uploads enabled (sessions expire after {} hours)",
+            config.storage.uploads.expiration_hours
+        );
+        Some(service)
+    } else {
+        None
+    };
+
     // Wrap in Arc after all modifications
     let app_state = Arc::new(app_state);
 
     // Build application router
-    let api_routes = create_api_routes(
+    let api_routes = create_api_routes(ApiServices {
         folder_service,
         file_service,
-        Some(i18n_service),
+        i18n_service: Some(i18n_service),
         trash_service,
         search_service,
         share_service,
         favorites_service,
         recent_service,
-    );
+        version_service,
+        thumbnail_service,
+        lock_service: Some(lock_service),
+    });
     let web_routes = create_web_routes();
 
     // Build the app router
@@ -981,6 +1623,69 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         app = app.nest("/api/auth", auth_router);
     }
 
+    // CalDAV clients look for the calendar server at the root of the host
+    if calendar_service.is_some() {
+        use interfaces::api::handlers::caldav_handler::well_known_routes;
+        app = app.merge(well_known_routes());
+    }
+
+    // Add encryption key management routes for administrators
+    if let Some(ref encryption) = encryption_service {
+        use interfaces::api::handlers::encryption_handler::encryption_routes;
+        match auth_services.clone() {
+            Some(auth) => {
+                app = app.nest(
+                    "/api/admin/encryption",
+                    encryption_routes(encryption.clone(), auth),
+                );
+            }
+            None => tracing::warn!(
+                "Encryption key management API disabled because authentication is disabled"
+            ),
+        }
+    }
+
+    // Add resumable upload routes (tus 1.0)
+    if let Some(ref uploads) = resumable_upload_service {
+        use interfaces::api::handlers::tus_handler::tus_routes;
+        match auth_services.clone() {
+            Some(auth) => app = app.nest("/api/uploads", tus_routes(uploads.clone(), auth)),
+            None => tracing::warn!("Resumable uploads disabled because authentication is disabled"),
+        }
+    }
+
+    // Add the endpoints Nextcloud desktop and mobile clients expect
+    if config.features.enable_nextcloud_compat && auth_services.is_some() {
+        use application::ports::upload_ports::ChunkedUploadUseCase;
+        use interfaces::api::handlers::nextcloud_handler::{
+            nextcloud_routes, NextcloudState, LOGIN_FLOW_TTL_SECS,
+        };
+
+        let chunked_uploads = Arc::new(ChunkedUploadService::new(
+            Arc::new(ChunkedUploadFsRepository::new(storage_path.as_path())),
+            &config.storage.uploads,
+        ));
+
+        // Drop abandoned chunked uploads every hour
+        let cleanup_service = chunked_uploads.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                if let Err(e) = cleanup_service.cleanup_expired().await {
+                    tracing::error!("Failed to clean up expired chunked uploads: {}", e);
+                }
+            }
+        });
+
+        app = app.merge(nextcloud_routes(NextcloudState {
+            app: app_state.clone(),
+            login_flows: Arc::new(LoginFlowService::new(LOGIN_FLOW_TTL_SECS)),
+            uploads: chunked_uploads,
+        }));
+        tracing::info!("Nextcloud client compatibility enabled");
+    }
+
     // Preload common directories to warm the cache
     tracing::info!("Preloading common directories to warm up cache...");
     if let Ok(count) = metadata_cache